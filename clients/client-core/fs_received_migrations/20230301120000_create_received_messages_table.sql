@@ -0,0 +1,6 @@
+CREATE TABLE received_message
+(
+    id         INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    sender_tag BLOB,
+    content    BLOB    NOT NULL
+);
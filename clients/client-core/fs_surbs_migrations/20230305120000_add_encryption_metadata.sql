@@ -0,0 +1,5 @@
+CREATE TABLE encryption_metadata
+(
+    salt         BLOB NOT NULL,
+    verification BLOB NOT NULL
+);
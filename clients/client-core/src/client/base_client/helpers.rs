@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 use crate::{client::replies::reply_storage, config::DebugConfig};
+#[cfg(all(not(target_arch = "wasm32"), feature = "mobile-surb-storage"))]
+use std::path::Path;
 
 pub fn setup_empty_reply_surb_backend(debug_config: &DebugConfig) -> reply_storage::Empty {
     reply_storage::Empty {
@@ -9,3 +11,16 @@ pub fn setup_empty_reply_surb_backend(debug_config: &DebugConfig) -> reply_stora
         max_surb_threshold: debug_config.maximum_reply_surb_storage_threshold,
     }
 }
+
+/// Sets up the reply storage backend that keeps its data blobs in the specified directory.
+#[cfg(all(not(target_arch = "wasm32"), feature = "mobile-surb-storage"))]
+pub fn setup_mobile_reply_surb_backend<P: AsRef<Path>>(
+    blob_directory: P,
+    debug_config: &DebugConfig,
+) -> reply_storage::mobile_backend::Backend {
+    reply_storage::mobile_backend::Backend::new_with_storage(
+        Box::new(mobile_storage::FsBlobStorage::new(blob_directory.as_ref())),
+        debug_config.minimum_reply_surb_storage_threshold,
+        debug_config.maximum_reply_surb_storage_threshold,
+    )
+}
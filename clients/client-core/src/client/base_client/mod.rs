@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::client::cover_traffic_stream::LoopCoverTrafficStream;
+use crate::client::gateway_failover::{FailoverStore, GatewayFailover, SelfAddress};
 use crate::client::inbound_messages::{InputMessage, InputMessageReceiver, InputMessageSender};
 use crate::client::key_manager::KeyManager;
 use crate::client::mix_traffic::{BatchMixMessageSender, MixTrafficController};
+use crate::client::peer_compression::PeerCompressionSupport;
 use crate::client::real_messages_control;
-use crate::client::real_messages_control::RealMessagesController;
+use crate::client::real_messages_control::{PacketPreparationMetrics, RealMessagesController};
+use crate::client::received_buffer::storage::ReceivedMessagesStore;
 use crate::client::received_buffer::{
     ReceivedBufferRequestReceiver, ReceivedBufferRequestSender, ReceivedMessagesBufferController,
 };
@@ -15,8 +18,10 @@ use crate::client::replies::reply_controller::{ReplyControllerReceiver, ReplyCon
 use crate::client::replies::reply_storage::{
     CombinedReplyStorage, PersistentReplyStorage, ReplyStorageBackend, SentReplyKeys,
 };
+use crate::client::statistics::ClientStatistics;
 use crate::client::topology_control::{
-    TopologyAccessor, TopologyRefresher, TopologyRefresherConfig,
+    NymApiTopologyProvider, TopologyAccessor, TopologyProvider, TopologyRefresher,
+    TopologyRefresherConfig,
 };
 use crate::config::{Config, DebugConfig, GatewayEndpointConfig};
 use crate::error::ClientCoreError;
@@ -85,11 +90,22 @@ impl ClientOutput {
 
         Ok(reconstructed_receiver)
     }
+
+    /// Notifies the client that the messages with the provided delivery ids have been processed
+    /// and thus are not going to be redelivered.
+    pub fn acknowledge_messages(&self, delivery_ids: Vec<u64>) -> Result<(), ClientCoreError> {
+        self.received_buffer_request_sender
+            .unbounded_send(ReceivedBufferMessage::Acknowledge(delivery_ids))
+            .map_err(|_| ClientCoreError::FailedToAcknowledgeMessages)
+    }
 }
 
 pub struct ClientState {
     pub shared_lane_queue_lengths: LaneQueueLengths,
     pub reply_controller_sender: ReplyControllerSender,
+    pub packet_preparation_metrics: PacketPreparationMetrics,
+    pub statistics: ClientStatistics,
+    pub self_address: SelfAddress,
 }
 
 pub enum ClientInputStatus {
@@ -149,6 +165,7 @@ impl From<bool> for CredentialsToggle {
 pub struct BaseClientBuilder<'a, B, C: Clone> {
     // due to wasm limitations I had to split it like this : (
     gateway_config: &'a GatewayEndpointConfig,
+    standby_gateways: Vec<GatewayEndpointConfig>,
     debug_config: &'a DebugConfig,
     disabled_credentials: bool,
     nym_api_endpoints: Vec<Url>,
@@ -156,6 +173,9 @@ pub struct BaseClientBuilder<'a, B, C: Clone> {
 
     bandwidth_controller: Option<BandwidthController<C>>,
     key_manager: KeyManager,
+    custom_topology_provider: Option<Box<dyn TopologyProvider>>,
+    received_messages_store: Option<Box<dyn ReceivedMessagesStore>>,
+    failover_store: Option<Box<dyn FailoverStore>>,
 }
 
 impl<'a, B, C> BaseClientBuilder<'a, B, C>
@@ -171,12 +191,16 @@ where
     ) -> BaseClientBuilder<'a, B, C> {
         BaseClientBuilder {
             gateway_config: base_config.get_gateway_endpoint_config(),
+            standby_gateways: base_config.get_standby_gateways().to_vec(),
             debug_config: base_config.get_debug_config(),
             disabled_credentials: base_config.get_disabled_credentials_mode(),
             nym_api_endpoints: base_config.get_nym_api_endpoints(),
             bandwidth_controller,
             reply_storage_backend,
             key_manager,
+            custom_topology_provider: None,
+            received_messages_store: None,
+            failover_store: None,
         }
     }
 
@@ -191,15 +215,54 @@ where
     ) -> BaseClientBuilder<'a, B, C> {
         BaseClientBuilder {
             gateway_config,
+            standby_gateways: Vec::new(),
             debug_config,
             disabled_credentials: credentials_toggle.is_disabled(),
             nym_api_endpoints,
             reply_storage_backend,
             bandwidth_controller,
             key_manager,
+            custom_topology_provider: None,
+            received_messages_store: None,
+            failover_store: None,
         }
     }
 
+    /// Use the provided [`TopologyProvider`] instead of retrieving the network topology
+    /// from the nym-api.
+    #[must_use]
+    pub fn with_topology_provider(mut self, topology_provider: Box<dyn TopologyProvider>) -> Self {
+        self.custom_topology_provider = Some(topology_provider);
+        self
+    }
+
+    /// Gateways the client should fail over to if its current gateway becomes unreachable.
+    #[must_use]
+    pub fn with_standby_gateways(mut self, standby_gateways: Vec<GatewayEndpointConfig>) -> Self {
+        self.standby_gateways = standby_gateways;
+        self
+    }
+
+    /// Persist received messages in the provided store until the application acknowledges them,
+    /// so that they would get redelivered after a restart.
+    #[must_use]
+    pub fn with_received_messages_store(
+        mut self,
+        received_messages_store: Box<dyn ReceivedMessagesStore>,
+    ) -> Self {
+        self.received_messages_store = Some(received_messages_store);
+        self
+    }
+
+    /// Persist the new gateway (and the key shared with it) in the provided store whenever
+    /// the client fails over to one of its standby gateways. Without it, the client is going to
+    /// use its original gateway again after a restart.
+    #[must_use]
+    pub fn with_failover_store(mut self, failover_store: Box<dyn FailoverStore>) -> Self {
+        self.failover_store = Some(failover_store);
+        self
+    }
+
     pub fn as_mix_recipient(&self) -> Recipient {
         Recipient::new(
             *self.key_manager.identity_keypair().public_key(),
@@ -215,7 +278,7 @@ where
     fn start_cover_traffic_stream(
         debug_config: &DebugConfig,
         ack_key: Arc<AckKey>,
-        self_address: Recipient,
+        self_address: SelfAddress,
         topology_accessor: TopologyAccessor,
         mix_tx: BatchMixMessageSender,
         shutdown: TaskClient,
@@ -232,9 +295,9 @@ where
             topology_accessor,
         );
 
-        if let Some(size) = debug_config.use_extended_packet_size {
-            log::debug!("Setting extended packet size: {:?}", size);
-            stream.set_custom_packet_size(size.into());
+        if let Some(size) = debug_config.custom_packet_size() {
+            log::debug!("Setting custom packet size: {:?}", size);
+            stream.set_custom_packet_size(size);
         }
 
         stream.start_with_shutdown(shutdown);
@@ -252,6 +315,9 @@ where
         reply_controller_receiver: ReplyControllerReceiver,
         lane_queue_lengths: LaneQueueLengths,
         client_connection_rx: ConnectionCommandReceiver,
+        packet_preparation_metrics: PacketPreparationMetrics,
+        peer_compression: PeerCompressionSupport,
+        statistics: ClientStatistics,
         shutdown: TaskClient,
     ) {
         info!("Starting real traffic stream...");
@@ -267,18 +333,25 @@ where
             reply_controller_receiver,
             lane_queue_lengths,
             client_connection_rx,
+            packet_preparation_metrics,
+            peer_compression,
+            statistics,
         )
         .start_with_shutdown(shutdown);
     }
 
     // buffer controlling all messages fetched from provider
     // required so that other components would be able to use them (say the websocket)
+    #[allow(clippy::too_many_arguments)]
     fn start_received_messages_buffer_controller(
         local_encryption_keypair: Arc<encryption::KeyPair>,
         query_receiver: ReceivedBufferRequestReceiver,
         mixnet_receiver: MixnetMessageReceiver,
         reply_key_storage: SentReplyKeys,
         reply_controller_sender: ReplyControllerSender,
+        received_messages_store: Option<Box<dyn ReceivedMessagesStore>>,
+        peer_compression: PeerCompressionSupport,
+        statistics: ClientStatistics,
         shutdown: TaskClient,
     ) {
         info!("Starting received messages buffer controller...");
@@ -288,6 +361,9 @@ where
             mixnet_receiver,
             reply_key_storage,
             reply_controller_sender,
+            received_messages_store,
+            peer_compression,
+            statistics,
         )
         .start_with_shutdown(shutdown)
     }
@@ -341,21 +417,33 @@ where
         Ok(gateway_client)
     }
 
+    fn setup_topology_provider(
+        custom_provider: Option<Box<dyn TopologyProvider>>,
+        nym_api_urls: Vec<Url>,
+    ) -> Box<dyn TopologyProvider> {
+        // if no custom provider was specified, fall back to the nym-api
+        custom_provider.unwrap_or_else(|| {
+            Box::new(NymApiTopologyProvider::new(
+                nym_api_urls,
+                env!("CARGO_PKG_VERSION").to_string(),
+            ))
+        })
+    }
+
     // future responsible for periodically polling directory server and updating
     // the current global view of topology
     async fn start_topology_refresher(
-        nym_api_urls: Vec<Url>,
+        topology_provider: Box<dyn TopologyProvider>,
         refresh_rate: Duration,
         topology_accessor: TopologyAccessor,
         shutdown: TaskClient,
     ) -> Result<(), ClientCoreError> {
-        let topology_refresher_config = TopologyRefresherConfig::new(
-            nym_api_urls,
-            refresh_rate,
-            env!("CARGO_PKG_VERSION").to_string(),
+        let topology_refresher_config = TopologyRefresherConfig::new(refresh_rate);
+        let mut topology_refresher = TopologyRefresher::new(
+            topology_refresher_config,
+            topology_accessor,
+            topology_provider,
         );
-        let mut topology_refresher =
-            TopologyRefresher::new(topology_refresher_config, topology_accessor);
         // before returning, block entire runtime to refresh the current network view so that any
         // components depending on topology would see a non-empty view
         info!("Obtaining initial network topology");
@@ -380,14 +468,47 @@ where
     // requests?
     fn start_mix_traffic_controller(
         gateway_client: GatewayClient<C>,
+        gateway_failover: Option<GatewayFailover>,
         shutdown: TaskClient,
     ) -> BatchMixMessageSender {
         info!("Starting mix traffic controller...");
-        let (mix_traffic_controller, mix_tx) = MixTrafficController::new(gateway_client);
+        let (mut mix_traffic_controller, mix_tx) = MixTrafficController::new(gateway_client);
+        if let Some(gateway_failover) = gateway_failover {
+            mix_traffic_controller = mix_traffic_controller.with_gateway_failover(gateway_failover);
+        }
         mix_traffic_controller.start_with_shutdown(shutdown);
         mix_tx
     }
 
+    fn setup_gateway_failover(
+        gateway_config: &GatewayEndpointConfig,
+        standby_gateways: Vec<GatewayEndpointConfig>,
+        identity_keys: Arc<identity::KeyPair>,
+        self_address: SelfAddress,
+        reply_controller_sender: ReplyControllerSender,
+        failover_store: Option<Box<dyn FailoverStore>>,
+    ) -> Option<GatewayFailover> {
+        if standby_gateways.is_empty() {
+            return None;
+        }
+
+        info!(
+            "{} standby gateways are available for failover",
+            standby_gateways.len()
+        );
+        let gateway_failover = GatewayFailover::new(
+            gateway_config.clone(),
+            standby_gateways,
+            identity_keys,
+            self_address,
+            reply_controller_sender,
+        );
+        match failover_store {
+            Some(store) => Some(gateway_failover.with_store(store)),
+            None => Some(gateway_failover),
+        }
+    }
+
     async fn setup_persistent_reply_storage(
         backend: B,
         shutdown: TaskClient,
@@ -454,7 +575,15 @@ where
         let (reply_controller_sender, reply_controller_receiver) =
             reply_controller::requests::new_control_channels();
 
-        let self_address = self.as_mix_recipient();
+        let self_address = SelfAddress::new(self.as_mix_recipient());
+
+        // Shared per-destination traffic statistics. Published by the real traffic controller
+        // and the received messages buffer, so that upstream could diagnose delivery problems.
+        let statistics = ClientStatistics::new();
+
+        // Compression support advertised by the remote clients. Recorded by the received messages
+        // buffer and used by the real traffic controller to decide whether to compress the messages.
+        let peer_compression = PeerCompressionSupport::default();
 
         // the components are started in very specific order. Unless you know what you are doing,
         // do not change that.
@@ -468,8 +597,13 @@ where
         )
         .await?;
 
-        Self::start_topology_refresher(
+        let topology_provider = Self::setup_topology_provider(
+            self.custom_topology_provider.take(),
             self.nym_api_endpoints.clone(),
+        );
+
+        Self::start_topology_refresher(
+            topology_provider,
             self.debug_config.topology_refresh_rate,
             shared_topology_accessor.clone(),
             task_manager.subscribe(),
@@ -482,6 +616,9 @@ where
             mixnet_messages_receiver,
             reply_storage.key_storage(),
             reply_controller_sender.clone(),
+            self.received_messages_store.take(),
+            peer_compression.clone(),
+            statistics.clone(),
             task_manager.subscribe(),
         );
 
@@ -489,8 +626,19 @@ where
         // that are to be sent to the mixnet. They are used by cover traffic stream and real
         // traffic stream.
         // The MixTrafficController then sends the actual traffic
-        let sphinx_message_sender =
-            Self::start_mix_traffic_controller(gateway_client, task_manager.subscribe());
+        let gateway_failover = Self::setup_gateway_failover(
+            self.gateway_config,
+            self.standby_gateways.clone(),
+            self.key_manager.identity_keypair(),
+            self_address.clone(),
+            reply_controller_sender.clone(),
+            self.failover_store.take(),
+        );
+        let sphinx_message_sender = Self::start_mix_traffic_controller(
+            gateway_client,
+            gateway_failover,
+            task_manager.subscribe(),
+        );
 
         // Channels that the websocket listener can use to signal downstream to the real traffic
         // controller that connections are closed.
@@ -500,15 +648,20 @@ where
         // primarily to throttle incoming connections (e.g socks5 for attached network-requesters)
         let shared_lane_queue_lengths = LaneQueueLengths::new();
 
+        // Shared state of the packet preparation pipeline. Published by the preparation workers
+        // and the `OutQueueController`, so that upstream could tell whether the client keeps up
+        // with its sending rate.
+        let packet_preparation_metrics = PacketPreparationMetrics::new();
+
         let mut controller_config = real_messages_control::Config::new(
             self.debug_config,
             self.key_manager.ack_key(),
-            self_address,
+            self_address.clone(),
         );
 
-        if let Some(size) = self.debug_config.use_extended_packet_size {
-            log::debug!("Setting extended packet size: {:?}", size);
-            controller_config.set_custom_packet_size(size.into());
+        if let Some(size) = self.debug_config.custom_packet_size() {
+            log::debug!("Setting custom packet size: {:?}", size);
+            controller_config.set_custom_packet_size(size);
         }
 
         Self::start_real_traffic_controller(
@@ -522,6 +675,9 @@ where
             reply_controller_receiver,
             shared_lane_queue_lengths.clone(),
             client_connection_rx,
+            packet_preparation_metrics.clone(),
+            peer_compression,
+            statistics.clone(),
             task_manager.subscribe(),
         );
 
@@ -529,7 +685,7 @@ where
             Self::start_cover_traffic_stream(
                 self.debug_config,
                 self.key_manager.ack_key(),
-                self_address,
+                self_address.clone(),
                 shared_topology_accessor,
                 sphinx_message_sender,
                 task_manager.subscribe(),
@@ -537,7 +693,7 @@ where
         }
 
         debug!("Core client startup finished!");
-        debug!("The address of this client is: {self_address}");
+        debug!("The address of this client is: {}", self_address.current());
 
         Ok(BaseClient {
             client_input: ClientInputStatus::AwaitingProducer {
@@ -554,6 +710,9 @@ where
             client_state: ClientState {
                 shared_lane_queue_lengths,
                 reply_controller_sender,
+                packet_preparation_metrics,
+                statistics,
+                self_address,
             },
             task_manager,
         })
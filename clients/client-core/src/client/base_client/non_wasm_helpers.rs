@@ -1,9 +1,11 @@
 // Copyright 2022 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::replies::reply_storage::fs_backend::StorageError;
 use crate::client::replies::reply_storage::{
     fs_backend, CombinedReplyStorage, ReplyStorageBackend,
 };
+use crate::config::persistence::encryption::StoragePassphrase;
 use crate::config::DebugConfig;
 use crate::error::ClientCoreError;
 use log::{error, info};
@@ -14,9 +16,10 @@ use time::OffsetDateTime;
 async fn setup_fresh_backend<P: AsRef<Path>>(
     db_path: P,
     debug_config: &DebugConfig,
+    passphrase: Option<&StoragePassphrase>,
 ) -> Result<fs_backend::Backend, ClientCoreError> {
     info!("creating fresh surb database");
-    let mut storage_backend = match fs_backend::Backend::init(db_path).await {
+    let mut storage_backend = match fs_backend::Backend::init(db_path, passphrase).await {
         Ok(backend) => backend,
         Err(err) => {
             error!("failed to setup persistent storage backend for our reply needs: {err}");
@@ -72,9 +75,11 @@ fn archive_corrupted_database<P: AsRef<Path>>(db_path: P) -> io::Result<()> {
     fs::rename(db_path, renamed)
 }
 
+/// Sets up the reply surb storage. If the passphrase is provided, the stored data is encrypted with it.
 pub async fn setup_fs_reply_surb_backend<P: AsRef<Path>>(
     db_path: Option<P>,
     debug_config: &DebugConfig,
+    passphrase: Option<&StoragePassphrase>,
 ) -> Result<fs_backend::Backend, ClientCoreError> {
     if let Some(db_path) = db_path {
         // if the database file doesnt exist, initialise fresh storage, otherwise attempt to load
@@ -82,17 +87,24 @@ pub async fn setup_fs_reply_surb_backend<P: AsRef<Path>>(
         let db_path = db_path.as_ref();
         if db_path.exists() {
             info!("loading existing surb database");
-            match fs_backend::Backend::try_load(db_path).await {
+            match fs_backend::Backend::try_load(db_path, passphrase).await {
                 Ok(backend) => Ok(backend),
+                // don't throw away perfectly valid data just because the user got the passphrase wrong
+                Err(err @ (StorageError::PassphraseRequired | StorageError::InvalidPassphrase)) => {
+                    error!("failed to load the encrypted surb database: {err}");
+                    Err(ClientCoreError::SurbStorageError {
+                        source: Box::new(err),
+                    })
+                }
                 Err(err) => {
                     error!("failed to setup persistent storage backend for our reply needs: {err}. We're going to create a fresh database instead. This behaviour might change in the future");
 
                     archive_corrupted_database(db_path)?;
-                    setup_fresh_backend(db_path, debug_config).await
+                    setup_fresh_backend(db_path, debug_config, passphrase).await
                 }
             }
         } else {
-            setup_fresh_backend(db_path, debug_config).await
+            setup_fresh_backend(db_path, debug_config, passphrase).await
         }
     } else {
         Ok(setup_inactive_backend(debug_config))
@@ -1,6 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::gateway_failover::SelfAddress;
 use crate::client::mix_traffic::BatchMixMessageSender;
 use crate::client::topology_control::TopologyAccessor;
 use crate::spawn_future;
@@ -8,7 +9,6 @@ use futures::task::{Context, Poll};
 use futures::{Future, Stream, StreamExt};
 use log::*;
 use nym_sphinx::acknowledgements::AckKey;
-use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::cover::generate_loop_cover_packet;
 use nym_sphinx::params::PacketSize;
 use nym_sphinx::utils::sample_poisson_duration;
@@ -53,7 +53,7 @@ where
     mix_tx: BatchMixMessageSender,
 
     /// Represents full address of this client.
-    our_full_destination: Recipient,
+    our_full_destination: SelfAddress,
 
     /// Instance of a cryptographically secure random number generator.
     rng: R,
@@ -114,7 +114,7 @@ impl LoopCoverTrafficStream<OsRng> {
         average_packet_delay: Duration,
         average_cover_message_sending_delay: Duration,
         mix_tx: BatchMixMessageSender,
-        our_full_destination: Recipient,
+        our_full_destination: SelfAddress,
         topology_access: TopologyAccessor,
     ) -> Self {
         let rng = OsRng;
@@ -160,11 +160,11 @@ impl LoopCoverTrafficStream<OsRng> {
         // to wait a really tiny bit before actually obtaining the permit hence messing with our
         // poisson delay, but is it really a problem?
         let topology_permit = self.topology_access.get_read_permit().await;
+        let our_full_destination = self.our_full_destination.current();
         // the ack is sent back to ourselves (and then ignored)
-        let topology_ref = match topology_permit.try_get_valid_topology_ref(
-            &self.our_full_destination,
-            Some(&self.our_full_destination),
-        ) {
+        let topology_ref = match topology_permit
+            .try_get_valid_topology_ref(&our_full_destination, Some(&our_full_destination))
+        {
             Ok(topology) => topology,
             Err(err) => {
                 warn!("We're not going to send any loop cover message this time, as the current topology seem to be invalid - {err}");
@@ -176,7 +176,7 @@ impl LoopCoverTrafficStream<OsRng> {
             &mut self.rng,
             topology_ref,
             &self.ack_key,
-            &self.our_full_destination,
+            &our_full_destination,
             self.average_ack_delay,
             self.average_packet_delay,
             self.packet_size,
@@ -0,0 +1,632 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::client::key_manager::KeyManager;
+use crate::client::replies::reply_controller::ReplyControllerSender;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::persistence::{
+    encryption::StoragePassphrase, key_pathfinder::ClientKeyPathfinder,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::ClientCoreConfigTrait;
+use crate::config::GatewayEndpointConfig;
+use crate::error::ClientCoreError;
+#[cfg(not(target_arch = "wasm32"))]
+use config::NymConfig;
+use dashmap::DashMap;
+#[cfg(target_arch = "wasm32")]
+use gateway_client::wasm_mockups::CosmWasmClient;
+use gateway_client::GatewayClient;
+use gateway_requests::registration::handshake::SharedKeys;
+use log::*;
+use nym_crypto::asymmetric::identity;
+use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::anonymous_replies::requests::{GatewayChangeNotice, GATEWAY_CHANGE_NONCE_SIZE};
+use rand::rngs::OsRng;
+use std::collections::{HashSet, VecDeque};
+use std::io;
+#[cfg(not(target_arch = "wasm32"))]
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+use time::OffsetDateTime;
+#[cfg(not(target_arch = "wasm32"))]
+use validator_client::nyxd::CosmWasmClient;
+
+/// Address of this client shared between all the components that embed it in the packets they
+/// create. It changes whenever the client fails over to a different gateway.
+#[derive(Clone)]
+pub struct SelfAddress {
+    inner: Arc<RwLock<Recipient>>,
+}
+
+impl SelfAddress {
+    pub fn new(address: Recipient) -> Self {
+        SelfAddress {
+            inner: Arc::new(RwLock::new(address)),
+        }
+    }
+
+    pub fn current(&self) -> Recipient {
+        *self.inner.read().expect("self address lock got poisoned")
+    }
+
+    fn update(&self, address: Recipient) {
+        *self.inner.write().expect("self address lock got poisoned") = address;
+    }
+}
+
+/// Latest known addresses of remote clients that have announced switching to a different gateway.
+#[derive(Clone, Default)]
+pub(crate) struct PeerRedirects {
+    inner: Arc<DashMap<[u8; identity::PUBLIC_KEY_LENGTH], Recipient>>,
+}
+
+impl PeerRedirects {
+    pub(crate) fn insert(&self, new_address: Recipient) {
+        self.inner
+            .insert(new_address.identity().to_bytes(), new_address);
+    }
+
+    /// Returns the most recent address of the specified recipient.
+    pub(crate) fn resolve(&self, recipient: Recipient) -> Recipient {
+        match self.inner.get(&recipient.identity().to_bytes()) {
+            Some(redirect) if redirect.encryption_key() == recipient.encryption_key() => {
+                *redirect.value()
+            }
+            _ => recipient,
+        }
+    }
+}
+
+/// Maximum age, in seconds, of a gateway change notice that is still going to be accepted.
+/// It has to be generous enough to cover notices that have been waiting in our gateway's inbox
+/// whilst we were offline.
+const MAX_GATEWAY_CHANGE_NOTICE_AGE: u64 = 24 * 60 * 60;
+
+/// Maximum allowed difference, in seconds, between our clock and the clock of the remote
+/// announcing its gateway change.
+const MAX_GATEWAY_CHANGE_NOTICE_CLOCK_SKEW: u64 = 5 * 60;
+
+fn current_unix_timestamp() -> u64 {
+    OffsetDateTime::now_utc()
+        .unix_timestamp()
+        .try_into()
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum GatewayChangeNoticeError {
+    #[error("the notice has an invalid signature - {0}")]
+    InvalidSignature(#[from] identity::SignatureError),
+
+    #[error("the notice created at {timestamp} has expired")]
+    Expired { timestamp: u64 },
+
+    #[error("the notice has been created in the future ({timestamp})")]
+    FromTheFuture { timestamp: u64 },
+
+    #[error("the notice created at {timestamp} has already been superseded or received before")]
+    Replayed { timestamp: u64 },
+}
+
+/// Keeps track of the most recent gateway change notices received from each remote party so that
+/// the same (or an older) notice could not be replayed to move it back to a gateway it has left.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GatewayChangeNoticeGuard {
+    // for each remote identity: timestamp of its most recent notice alongside all nonces seen with it
+    latest: Arc<
+        DashMap<[u8; identity::PUBLIC_KEY_LENGTH], (u64, HashSet<[u8; GATEWAY_CHANGE_NONCE_SIZE]>)>,
+    >,
+}
+
+impl GatewayChangeNoticeGuard {
+    pub(crate) fn check(
+        &self,
+        notice: &GatewayChangeNotice,
+    ) -> Result<(), GatewayChangeNoticeError> {
+        self.check_at(notice, current_unix_timestamp())
+    }
+
+    fn check_at(
+        &self,
+        notice: &GatewayChangeNotice,
+        now: u64,
+    ) -> Result<(), GatewayChangeNoticeError> {
+        notice.verify()?;
+
+        let timestamp = notice.timestamp();
+        if timestamp.saturating_add(MAX_GATEWAY_CHANGE_NOTICE_AGE) < now {
+            return Err(GatewayChangeNoticeError::Expired { timestamp });
+        }
+        if timestamp > now.saturating_add(MAX_GATEWAY_CHANGE_NOTICE_CLOCK_SKEW) {
+            return Err(GatewayChangeNoticeError::FromTheFuture { timestamp });
+        }
+
+        let mut latest = self
+            .latest
+            .entry(notice.recipient().identity().to_bytes())
+            .or_insert_with(|| (timestamp, HashSet::new()));
+        let (latest_timestamp, seen_nonces) = latest.value_mut();
+
+        if timestamp < *latest_timestamp {
+            return Err(GatewayChangeNoticeError::Replayed { timestamp });
+        }
+        if timestamp > *latest_timestamp {
+            *latest_timestamp = timestamp;
+            seen_nonces.clear();
+        }
+        if !seen_nonces.insert(*notice.nonce()) {
+            return Err(GatewayChangeNoticeError::Replayed { timestamp });
+        }
+        Ok(())
+    }
+}
+
+/// Persists the outcome of a failover. Our peers are told to use the new address straight away,
+/// so unless it's stored, the client would go back to the gateway it has abandoned (with the key
+/// it has derived with it) after a restart and never receive the messages sent to it since.
+pub trait FailoverStore: Send {
+    fn store_failover(
+        &mut self,
+        current_gateway: &GatewayEndpointConfig,
+        standby_gateways: &[GatewayEndpointConfig],
+        shared_key: Arc<SharedKeys>,
+    ) -> io::Result<()>;
+}
+
+/// [`FailoverStore`] that stores the new shared key alongside the other keys of the client
+/// and updates the gateways in its config file.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ConfigFailoverStore<T> {
+    id: String,
+    key_manager: KeyManager,
+    key_pathfinder: ClientKeyPathfinder,
+    passphrase: Option<StoragePassphrase>,
+    _config: PhantomData<fn() -> T>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> ConfigFailoverStore<T>
+where
+    T: NymConfig + ClientCoreConfigTrait,
+{
+    pub fn new<S: Into<String>>(
+        id: S,
+        key_manager: KeyManager,
+        key_pathfinder: ClientKeyPathfinder,
+        passphrase: Option<StoragePassphrase>,
+    ) -> Self {
+        ConfigFailoverStore {
+            id: id.into(),
+            key_manager,
+            key_pathfinder,
+            passphrase,
+            _config: PhantomData,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> FailoverStore for ConfigFailoverStore<T>
+where
+    T: NymConfig + ClientCoreConfigTrait,
+{
+    fn store_failover(
+        &mut self,
+        current_gateway: &GatewayEndpointConfig,
+        standby_gateways: &[GatewayEndpointConfig],
+        shared_key: Arc<SharedKeys>,
+    ) -> io::Result<()> {
+        self.key_manager.insert_gateway_shared_key(shared_key);
+        self.key_manager
+            .store_gateway_key(&self.key_pathfinder, self.passphrase.as_ref())?;
+
+        // reload the config so that we wouldn't overwrite anything that has been changed since startup
+        let mut config = T::load_from_file(&self.id)?;
+        config.set_gateway_endpoints(current_gateway.clone(), standby_gateways.to_vec());
+        config.save_to_file(None)
+    }
+}
+
+/// Moves the client to one of its standby gateways once the connection to the current one
+/// could not be re-established, and announces the new address to the remote parties
+/// that have provided us with reply SURBs.
+pub struct GatewayFailover {
+    current_gateway: GatewayEndpointConfig,
+    standby_gateways: VecDeque<GatewayEndpointConfig>,
+    identity_keys: Arc<identity::KeyPair>,
+    self_address: SelfAddress,
+    reply_controller_sender: ReplyControllerSender,
+    store: Option<Box<dyn FailoverStore>>,
+}
+
+impl GatewayFailover {
+    pub fn new(
+        current_gateway: GatewayEndpointConfig,
+        standby_gateways: Vec<GatewayEndpointConfig>,
+        identity_keys: Arc<identity::KeyPair>,
+        self_address: SelfAddress,
+        reply_controller_sender: ReplyControllerSender,
+    ) -> Self {
+        GatewayFailover {
+            current_gateway,
+            standby_gateways: standby_gateways.into(),
+            identity_keys,
+            self_address,
+            reply_controller_sender,
+            store: None,
+        }
+    }
+
+    /// Persist the new gateway in the provided store after every failover.
+    #[must_use]
+    pub fn with_store(mut self, store: Box<dyn FailoverStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    async fn switch_gateway<C>(
+        &self,
+        gateway_client: &mut GatewayClient<C>,
+        gateway: &GatewayEndpointConfig,
+    ) -> Result<(Recipient, Arc<SharedKeys>), ClientCoreError>
+    where
+        C: CosmWasmClient + Sync + Send + Clone,
+    {
+        let gateway_identity = identity::PublicKey::from_base58_string(&gateway.gateway_id)
+            .map_err(ClientCoreError::UnableToCreatePublicKeyFromGatewayId)?;
+
+        let shared_key = gateway_client
+            .switch_gateway(gateway.gateway_listener.clone(), gateway_identity)
+            .await?;
+
+        let previous_address = self.self_address.current();
+        let new_address = Recipient::new(
+            *previous_address.identity(),
+            *previous_address.encryption_key(),
+            gateway_identity,
+        );
+        Ok((new_address, shared_key))
+    }
+
+    /// Makes the provided gateway the current one, persists it alongside the new shared key,
+    /// updates our address and announces it to the remote parties.
+    fn complete_failover(
+        &mut self,
+        gateway: GatewayEndpointConfig,
+        new_address: Recipient,
+        shared_key: Arc<SharedKeys>,
+    ) {
+        let previous = std::mem::replace(&mut self.current_gateway, gateway);
+        self.standby_gateways.push_back(previous);
+
+        if let Some(store) = self.store.as_mut() {
+            let standby_gateways: Vec<_> = self.standby_gateways.iter().cloned().collect();
+            if let Err(err) =
+                store.store_failover(&self.current_gateway, &standby_gateways, shared_key)
+            {
+                // we're still connected to the new gateway, it's just that we'll go back
+                // to the old one after a restart
+                error!(
+                    "failed to persist the failover to gateway {} - {err}",
+                    self.current_gateway.gateway_id
+                );
+            }
+        } else {
+            warn!("the failover to gateway {} is not going to be persisted - the client will use its previous gateway after a restart", self.current_gateway.gateway_id);
+        }
+
+        self.self_address.update(new_address);
+        let notice = GatewayChangeNotice::new_signed(
+            &mut OsRng,
+            new_address,
+            current_unix_timestamp(),
+            self.identity_keys.private_key(),
+        );
+        self.reply_controller_sender
+            .send_gateway_change_announcement(notice);
+    }
+
+    /// Attempts to register with each of the standby gateways in turn. The gateway we're moving
+    /// away from becomes the last standby so that we could go back to it if it ever recovers.
+    /// Returns whether the client has successfully moved to a different gateway.
+    pub(crate) async fn fail_over<C>(&mut self, gateway_client: &mut GatewayClient<C>) -> bool
+    where
+        C: CosmWasmClient + Sync + Send + Clone,
+    {
+        if self.standby_gateways.is_empty() {
+            warn!("there are no standby gateways available to fail over to");
+            return false;
+        }
+
+        for _ in 0..self.standby_gateways.len() {
+            let Some(candidate) = self.standby_gateways.pop_front() else {
+                break;
+            };
+
+            match self.switch_gateway(gateway_client, &candidate).await {
+                Ok((new_address, shared_key)) => {
+                    info!(
+                        "failed over to gateway {} - the address of this client is now: {new_address}",
+                        candidate.gateway_id
+                    );
+                    self.complete_failover(candidate, new_address, shared_key);
+                    return true;
+                }
+                Err(err) => {
+                    warn!(
+                        "could not switch to the standby gateway {} - {err}",
+                        candidate.gateway_id
+                    );
+                    self.standby_gateways.push_back(candidate);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::replies::reply_controller::requests::{
+        new_control_channels, ReplyControllerMessage,
+    };
+    use crate::client::replies::reply_storage::UsedSenderTags;
+    use nym_crypto::asymmetric::encryption;
+    use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+
+    const NOW: u64 = 1_680_000_000;
+
+    #[derive(Clone, Default)]
+    struct InMemoryFailoverStore {
+        stored: Arc<std::sync::Mutex<Vec<(GatewayEndpointConfig, Vec<GatewayEndpointConfig>)>>>,
+    }
+
+    impl FailoverStore for InMemoryFailoverStore {
+        fn store_failover(
+            &mut self,
+            current_gateway: &GatewayEndpointConfig,
+            standby_gateways: &[GatewayEndpointConfig],
+            _shared_key: Arc<SharedKeys>,
+        ) -> io::Result<()> {
+            self.stored
+                .lock()
+                .unwrap()
+                .push((current_gateway.clone(), standby_gateways.to_vec()));
+            Ok(())
+        }
+    }
+
+    fn shared_key() -> Arc<SharedKeys> {
+        Arc::new(SharedKeys::try_from_bytes(&[42; 32]).unwrap())
+    }
+
+    fn random_identity() -> identity::PublicKey {
+        *identity::KeyPair::new(&mut OsRng).public_key()
+    }
+
+    fn client_keys() -> (Arc<identity::KeyPair>, encryption::PublicKey) {
+        (
+            Arc::new(identity::KeyPair::new(&mut OsRng)),
+            *encryption::KeyPair::new(&mut OsRng).public_key(),
+        )
+    }
+
+    fn address(
+        identity_keys: &identity::KeyPair,
+        encryption_key: encryption::PublicKey,
+        gateway: identity::PublicKey,
+    ) -> Recipient {
+        Recipient::new(*identity_keys.public_key(), encryption_key, gateway)
+    }
+
+    fn gateway_config(identity: identity::PublicKey) -> GatewayEndpointConfig {
+        GatewayEndpointConfig {
+            gateway_id: identity.to_base58_string(),
+            gateway_owner: "n1foomp".to_string(),
+            gateway_listener: "ws://1.2.3.4:9000".to_string(),
+        }
+    }
+
+    fn notice(
+        identity_keys: &identity::KeyPair,
+        encryption_key: encryption::PublicKey,
+        timestamp: u64,
+    ) -> GatewayChangeNotice {
+        GatewayChangeNotice::new_signed(
+            &mut OsRng,
+            address(identity_keys, encryption_key, random_identity()),
+            timestamp,
+            identity_keys.private_key(),
+        )
+    }
+
+    #[test]
+    fn failover_updates_self_address_and_announces_it() {
+        let (identity_keys, encryption_key) = client_keys();
+        let (initial, standby, other_standby) =
+            (random_identity(), random_identity(), random_identity());
+        let self_address = SelfAddress::new(address(&identity_keys, encryption_key, initial));
+        let (sender, mut receiver) = new_control_channels();
+
+        let mut failover = GatewayFailover::new(
+            gateway_config(initial),
+            vec![gateway_config(standby), gateway_config(other_standby)],
+            Arc::clone(&identity_keys),
+            self_address.clone(),
+            sender,
+        );
+
+        let candidate = failover.standby_gateways.pop_front().unwrap();
+        let new_address = address(&identity_keys, encryption_key, standby);
+        failover.complete_failover(candidate, new_address, shared_key());
+
+        // every clone of the address observes the change
+        assert_eq!(self_address.current(), new_address);
+        assert_eq!(failover.current_gateway, gateway_config(standby));
+        // the gateway we have left is now the last resort
+        assert_eq!(
+            failover.standby_gateways,
+            vec![gateway_config(other_standby), gateway_config(initial)]
+        );
+
+        match receiver.try_next().unwrap().unwrap() {
+            ReplyControllerMessage::AnnounceGatewayChange { notice } => {
+                assert_eq!(notice.recipient(), &new_address);
+                assert_eq!(notice.new_gateway(), &standby);
+                assert!(notice.verify().is_ok());
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn failover_is_persisted_in_the_store() {
+        let (identity_keys, encryption_key) = client_keys();
+        let (initial, standby, other_standby) =
+            (random_identity(), random_identity(), random_identity());
+        let self_address = SelfAddress::new(address(&identity_keys, encryption_key, initial));
+        let (sender, _receiver) = new_control_channels();
+        let store = InMemoryFailoverStore::default();
+
+        let mut failover = GatewayFailover::new(
+            gateway_config(initial),
+            vec![gateway_config(standby), gateway_config(other_standby)],
+            Arc::clone(&identity_keys),
+            self_address,
+            sender,
+        )
+        .with_store(Box::new(store.clone()));
+
+        let candidate = failover.standby_gateways.pop_front().unwrap();
+        let new_address = address(&identity_keys, encryption_key, standby);
+        failover.complete_failover(candidate, new_address, shared_key());
+
+        assert_eq!(
+            *store.stored.lock().unwrap(),
+            vec![(
+                gateway_config(standby),
+                vec![gateway_config(other_standby), gateway_config(initial)]
+            )]
+        );
+    }
+
+    #[test]
+    fn peer_redirects_only_apply_to_the_same_keys() {
+        let redirects = PeerRedirects::default();
+        let (identity_keys, encryption_key) = client_keys();
+        let old_address = address(&identity_keys, encryption_key, random_identity());
+        let new_address = address(&identity_keys, encryption_key, random_identity());
+        let unrelated = address(&client_keys().0, encryption_key, random_identity());
+
+        assert_eq!(redirects.resolve(old_address), old_address);
+        redirects.insert(new_address);
+        assert_eq!(redirects.resolve(old_address), new_address);
+        assert_eq!(redirects.resolve(unrelated), unrelated);
+
+        let (_, other_encryption_key) = client_keys();
+        let rekeyed = address(&identity_keys, other_encryption_key, random_identity());
+        assert_eq!(redirects.resolve(rekeyed), rekeyed);
+    }
+
+    #[test]
+    fn sender_tag_is_inherited_by_the_new_address() {
+        let tags = UsedSenderTags::new();
+        let (identity_keys, encryption_key) = client_keys();
+        let old_address = address(&identity_keys, encryption_key, random_identity());
+        let new_address = address(&identity_keys, encryption_key, random_identity());
+        let tag = AnonymousSenderTag::new_random(&mut OsRng);
+
+        tags.insert_new(&old_address, tag);
+        tags.inherit_for_new_address(&new_address);
+        assert_eq!(tags.try_get_existing(&new_address), Some(tag));
+    }
+
+    #[test]
+    fn fresh_gateway_change_notice_is_accepted_once() {
+        let guard = GatewayChangeNoticeGuard::default();
+        let (identity_keys, encryption_key) = client_keys();
+        let notice = notice(&identity_keys, encryption_key, NOW);
+
+        assert!(guard.check_at(&notice, NOW).is_ok());
+        assert!(matches!(
+            guard.check_at(&notice, NOW),
+            Err(GatewayChangeNoticeError::Replayed { .. })
+        ));
+    }
+
+    #[test]
+    fn superseded_gateway_change_notice_is_rejected() {
+        let guard = GatewayChangeNoticeGuard::default();
+        let (identity_keys, encryption_key) = client_keys();
+        let older = notice(&identity_keys, encryption_key, NOW - 10);
+        let same_time = notice(&identity_keys, encryption_key, NOW - 10);
+        let newer = notice(&identity_keys, encryption_key, NOW);
+
+        assert!(guard.check_at(&older, NOW).is_ok());
+        // different nonce, so it's a distinct notice
+        assert!(guard.check_at(&same_time, NOW).is_ok());
+        assert!(guard.check_at(&newer, NOW).is_ok());
+        assert!(matches!(
+            guard.check_at(&older, NOW),
+            Err(GatewayChangeNoticeError::Replayed { .. })
+        ));
+
+        // notices of other clients are tracked independently
+        let (other_keys, other_encryption_key) = client_keys();
+        let other = notice(&other_keys, other_encryption_key, NOW - 10);
+        assert!(guard.check_at(&other, NOW).is_ok());
+    }
+
+    #[test]
+    fn gateway_change_notice_outside_the_time_window_is_rejected() {
+        let guard = GatewayChangeNoticeGuard::default();
+        let (identity_keys, encryption_key) = client_keys();
+
+        let expired = notice(
+            &identity_keys,
+            encryption_key,
+            NOW - MAX_GATEWAY_CHANGE_NOTICE_AGE - 1,
+        );
+        assert!(matches!(
+            guard.check_at(&expired, NOW),
+            Err(GatewayChangeNoticeError::Expired { .. })
+        ));
+
+        let from_the_future = notice(
+            &identity_keys,
+            encryption_key,
+            NOW + MAX_GATEWAY_CHANGE_NOTICE_CLOCK_SKEW + 1,
+        );
+        assert!(matches!(
+            guard.check_at(&from_the_future, NOW),
+            Err(GatewayChangeNoticeError::FromTheFuture { .. })
+        ));
+    }
+
+    #[test]
+    fn forged_gateway_change_notice_is_rejected() {
+        let guard = GatewayChangeNoticeGuard::default();
+        let (identity_keys, encryption_key) = client_keys();
+        let (attacker_keys, _) = client_keys();
+
+        let forged = GatewayChangeNotice::new_signed(
+            &mut OsRng,
+            address(&identity_keys, encryption_key, random_identity()),
+            NOW,
+            attacker_keys.private_key(),
+        );
+        assert!(matches!(
+            guard.check_at(&forged, NOW),
+            Err(GatewayChangeNoticeError::InvalidSignature(_))
+        ));
+
+        // and it did not affect the state for the legitimate notices
+        let legit = notice(&identity_keys, encryption_key, NOW);
+        assert!(guard.check_at(&legit, NOW).is_ok());
+    }
+}
@@ -1,15 +1,62 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::config::persistence::encryption::StoragePassphrase;
 use crate::config::persistence::key_pathfinder::ClientKeyPathfinder;
 use gateway_requests::registration::handshake::SharedKeys;
 use log::*;
 use nym_crypto::asymmetric::{encryption, identity};
+use nym_pemstore::traits::{PemStorableKey, PemStorableKeyPair};
+use nym_pemstore::KeyPairPath;
 use nym_sphinx::acknowledgements::AckKey;
 use rand::{CryptoRng, RngCore};
 use std::io;
+use std::path::Path;
 use std::sync::Arc;
 
+// if the passphrase is provided, the private keys are expected to be stored encrypted
+fn load_keypair<T: PemStorableKeyPair>(
+    paths: &KeyPairPath,
+    passphrase: Option<&StoragePassphrase>,
+) -> io::Result<T> {
+    match passphrase {
+        Some(passphrase) => nym_pemstore::load_keypair_encrypted(paths, passphrase),
+        None => nym_pemstore::load_keypair(paths),
+    }
+}
+
+fn store_keypair<T: PemStorableKeyPair>(
+    keypair: &T,
+    paths: &KeyPairPath,
+    passphrase: Option<&StoragePassphrase>,
+) -> io::Result<()> {
+    match passphrase {
+        Some(passphrase) => nym_pemstore::store_keypair_encrypted(keypair, paths, passphrase),
+        None => nym_pemstore::store_keypair(keypair, paths),
+    }
+}
+
+fn load_key<T: PemStorableKey>(
+    path: &Path,
+    passphrase: Option<&StoragePassphrase>,
+) -> io::Result<T> {
+    match passphrase {
+        Some(passphrase) => nym_pemstore::load_encrypted_key(path, passphrase),
+        None => nym_pemstore::load_key(path),
+    }
+}
+
+fn store_key<T: PemStorableKey>(
+    key: &T,
+    path: &Path,
+    passphrase: Option<&StoragePassphrase>,
+) -> io::Result<()> {
+    match passphrase {
+        Some(passphrase) => nym_pemstore::store_encrypted_key(key, path, passphrase),
+        None => nym_pemstore::store_key(key, path),
+    }
+}
+
 // Note: to support key rotation in the future, all keys will require adding an extra smart pointer,
 // most likely an AtomicCell, or if it doesn't work as I think it does, a Mutex. Although I think
 // AtomicCell includes a Mutex implicitly if the underlying type does not work atomically.
@@ -70,19 +117,26 @@ impl KeyManager {
     }
 
     /// Loads previously stored client keys from the disk.
-    fn load_client_keys(client_pathfinder: &ClientKeyPathfinder) -> io::Result<Self> {
-        let identity_keypair: identity::KeyPair =
-            nym_pemstore::load_keypair(&nym_pemstore::KeyPairPath::new(
+    fn load_client_keys(
+        client_pathfinder: &ClientKeyPathfinder,
+        passphrase: Option<&StoragePassphrase>,
+    ) -> io::Result<Self> {
+        let identity_keypair: identity::KeyPair = load_keypair(
+            &KeyPairPath::new(
                 client_pathfinder.private_identity_key().to_owned(),
                 client_pathfinder.public_identity_key().to_owned(),
-            ))?;
-        let encryption_keypair: encryption::KeyPair =
-            nym_pemstore::load_keypair(&nym_pemstore::KeyPairPath::new(
+            ),
+            passphrase,
+        )?;
+        let encryption_keypair: encryption::KeyPair = load_keypair(
+            &KeyPairPath::new(
                 client_pathfinder.private_encryption_key().to_owned(),
                 client_pathfinder.public_encryption_key().to_owned(),
-            ))?;
+            ),
+            passphrase,
+        )?;
 
-        let ack_key: AckKey = nym_pemstore::load_key(client_pathfinder.ack_key())?;
+        let ack_key: AckKey = load_key(client_pathfinder.ack_key(), passphrase)?;
 
         Ok(KeyManager {
             identity_keypair: Arc::new(identity_keypair),
@@ -93,12 +147,15 @@ impl KeyManager {
     }
 
     /// Loads previously stored keys from the disk. Fails if not all, including the shared gateway
-    /// key, is available.
-    pub fn load_keys(client_pathfinder: &ClientKeyPathfinder) -> io::Result<Self> {
-        let mut key_manager = Self::load_client_keys(client_pathfinder)?;
+    /// key, is available. If the passphrase is provided, the private keys are decrypted with it.
+    pub fn load_keys(
+        client_pathfinder: &ClientKeyPathfinder,
+        passphrase: Option<&StoragePassphrase>,
+    ) -> io::Result<Self> {
+        let mut key_manager = Self::load_client_keys(client_pathfinder, passphrase)?;
 
         let gateway_shared_key: SharedKeys =
-            nym_pemstore::load_key(client_pathfinder.gateway_shared_key())?;
+            load_key(client_pathfinder.gateway_shared_key(), passphrase)?;
 
         key_manager.gateway_shared_key = Some(Arc::new(gateway_shared_key));
 
@@ -109,11 +166,12 @@ impl KeyManager {
     /// shared gateway key is optional.
     pub fn load_keys_but_gateway_is_optional(
         client_pathfinder: &ClientKeyPathfinder,
+        passphrase: Option<&StoragePassphrase>,
     ) -> io::Result<Self> {
-        let mut key_manager = Self::load_client_keys(client_pathfinder)?;
+        let mut key_manager = Self::load_client_keys(client_pathfinder, passphrase)?;
 
         let gateway_shared_key: Result<SharedKeys, io::Error> =
-            nym_pemstore::load_key(client_pathfinder.gateway_shared_key());
+            load_key(client_pathfinder.gateway_shared_key(), passphrase);
 
         // It's ok if the gateway key was not found
         let gateway_shared_key = match gateway_shared_key {
@@ -127,39 +185,56 @@ impl KeyManager {
         Ok(key_manager)
     }
 
-    /// Stores all available keys on the disk.
+    /// Stores all available keys on the disk. If the passphrase is provided, the private keys
+    /// are encrypted with it.
     // While perhaps there is no much point in storing the `AckKey` on the disk,
     // it is done so for the consistency sake so that you wouldn't require an rng instance
     // during `load_keys` to generate the said key.
-    pub fn store_keys(&self, client_pathfinder: &ClientKeyPathfinder) -> io::Result<()> {
-        nym_pemstore::store_keypair(
+    pub fn store_keys(
+        &self,
+        client_pathfinder: &ClientKeyPathfinder,
+        passphrase: Option<&StoragePassphrase>,
+    ) -> io::Result<()> {
+        store_keypair(
             self.identity_keypair.as_ref(),
-            &nym_pemstore::KeyPairPath::new(
+            &KeyPairPath::new(
                 client_pathfinder.private_identity_key().to_owned(),
                 client_pathfinder.public_identity_key().to_owned(),
             ),
+            passphrase,
         )?;
-        nym_pemstore::store_keypair(
+        store_keypair(
             self.encryption_keypair.as_ref(),
-            &nym_pemstore::KeyPairPath::new(
+            &KeyPairPath::new(
                 client_pathfinder.private_encryption_key().to_owned(),
                 client_pathfinder.public_encryption_key().to_owned(),
             ),
+            passphrase,
         )?;
 
-        nym_pemstore::store_key(self.ack_key.as_ref(), client_pathfinder.ack_key())?;
+        store_key(
+            self.ack_key.as_ref(),
+            client_pathfinder.ack_key(),
+            passphrase,
+        )?;
 
         match self.gateway_shared_key.as_ref() {
             None => debug!("No gateway shared key available to store!"),
-            Some(gate_key) => {
-                nym_pemstore::store_key(gate_key.as_ref(), client_pathfinder.gateway_shared_key())?
-            }
+            Some(gate_key) => store_key(
+                gate_key.as_ref(),
+                client_pathfinder.gateway_shared_key(),
+                passphrase,
+            )?,
         }
 
         Ok(())
     }
 
-    pub fn store_gateway_key(&self, client_pathfinder: &ClientKeyPathfinder) -> io::Result<()> {
+    pub fn store_gateway_key(
+        &self,
+        client_pathfinder: &ClientKeyPathfinder,
+        passphrase: Option<&StoragePassphrase>,
+    ) -> io::Result<()> {
         match self.gateway_shared_key.as_ref() {
             None => {
                 return Err(io::Error::new(
@@ -167,9 +242,11 @@ impl KeyManager {
                     "trying to store a non-existing key",
                 ))
             }
-            Some(gate_key) => {
-                nym_pemstore::store_key(gate_key.as_ref(), client_pathfinder.gateway_shared_key())?
-            }
+            Some(gate_key) => store_key(
+                gate_key.as_ref(),
+                client_pathfinder.gateway_shared_key(),
+                passphrase,
+            )?,
         }
 
         Ok(())
@@ -1,6 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::gateway_failover::GatewayFailover;
 use crate::spawn_future;
 #[cfg(target_arch = "wasm32")]
 use gateway_client::wasm_mockups::CosmWasmClient;
@@ -23,6 +24,9 @@ pub struct MixTrafficController<C: Clone> {
     gateway_client: GatewayClient<C>,
     mix_rx: BatchMixMessageReceiver,
 
+    /// If configured, allows moving to a standby gateway once the current one becomes unreachable.
+    gateway_failover: Option<GatewayFailover>,
+
     // TODO: this is temporary work-around.
     // in long run `gateway_client` will be moved away from `MixTrafficController` anyway.
     consecutive_gateway_failure_count: usize,
@@ -41,12 +45,26 @@ where
             MixTrafficController {
                 gateway_client,
                 mix_rx: sphinx_message_receiver,
+                gateway_failover: None,
                 consecutive_gateway_failure_count: 0,
             },
             sphinx_message_sender,
         )
     }
 
+    #[must_use]
+    pub fn with_gateway_failover(mut self, gateway_failover: GatewayFailover) -> Self {
+        self.gateway_failover = Some(gateway_failover);
+        self
+    }
+
+    async fn try_fail_over(&mut self) -> bool {
+        match self.gateway_failover.as_mut() {
+            Some(failover) => failover.fail_over(&mut self.gateway_client).await,
+            None => false,
+        }
+    }
+
     async fn on_messages(&mut self, mut mix_packets: Vec<MixPacket>) {
         debug_assert!(!mix_packets.is_empty());
 
@@ -62,6 +80,12 @@ where
         match result {
             Err(err) => {
                 error!("Failed to send sphinx packet(s) to the gateway! - {err}");
+                if err.is_reconnection_exhausted() && self.try_fail_over().await {
+                    // note: the packets that failed to get sent will be retransmitted
+                    // once their acks time out
+                    self.consecutive_gateway_failure_count = 0;
+                    return;
+                }
                 self.consecutive_gateway_failure_count += 1;
                 if self.consecutive_gateway_failure_count == MAX_FAILURE_COUNT {
                     // todo: in the future this should initiate a 'graceful' shutdown or try
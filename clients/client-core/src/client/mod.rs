@@ -3,12 +3,15 @@
 
 pub mod base_client;
 pub mod cover_traffic_stream;
+pub mod gateway_failover;
 pub(crate) mod helpers;
 pub mod inbound_messages;
 pub mod key_manager;
 pub mod mix_traffic;
+pub(crate) mod peer_compression;
 pub mod real_messages_control;
 pub mod received_buffer;
 pub mod replies;
+pub mod statistics;
 pub mod topology_control;
 pub(crate) mod transmission_buffer;
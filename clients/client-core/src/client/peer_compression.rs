@@ -0,0 +1,121 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use dashmap::DashMap;
+use nym_crypto::asymmetric::identity;
+use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::anonymous_replies::requests::{AnonymousSenderTag, ReplyMessageContent};
+use nym_sphinx::compression::SupportedCompression;
+use nym_sphinx::message::NymMessage;
+use std::sync::Arc;
+
+/// Compression support advertised by the remote clients in the messages they have sent us.
+/// Only the messages that could be attributed to a particular peer are taken into account, i.e.
+/// the repliable messages (attributed to their sender tags) and the requests for additional
+/// reply SURBs (attributed to the address of the requester). Messages sent to peers that haven't
+/// advertised anything are never compressed.
+#[derive(Clone, Default)]
+pub(crate) struct PeerCompressionSupport {
+    recipients: Arc<DashMap<[u8; identity::PUBLIC_KEY_LENGTH], SupportedCompression>>,
+    sender_tags: Arc<DashMap<AnonymousSenderTag, SupportedCompression>>,
+}
+
+impl PeerCompressionSupport {
+    /// Records the compression support advertised by the sender of the received message.
+    /// Note that it overwrites the previous advertisement, as the peer might have downgraded.
+    pub(crate) fn record(&self, message: &NymMessage, supported: SupportedCompression) {
+        match message {
+            NymMessage::Repliable(repliable) => {
+                self.sender_tags.insert(repliable.sender_tag, supported);
+            }
+            NymMessage::Reply(reply) => {
+                if let ReplyMessageContent::SurbRequest { recipient, .. } = &reply.content {
+                    self.recipients
+                        .insert(recipient.identity().to_bytes(), supported);
+                }
+            }
+            NymMessage::Plain(_) => (),
+        }
+    }
+
+    pub(crate) fn for_recipient(&self, recipient: &Recipient) -> SupportedCompression {
+        self.recipients
+            .get(&recipient.identity().to_bytes())
+            .map(|supported| *supported)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn for_sender_tag(&self, sender_tag: &AnonymousSenderTag) -> SupportedCompression {
+        self.sender_tags
+            .get(sender_tag)
+            .map(|supported| *supported)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::encryption;
+    use nym_sphinx::anonymous_replies::requests::RepliableMessage;
+    use nym_sphinx::compression::MessageCompression;
+    use rand::rngs::OsRng;
+
+    fn random_recipient() -> Recipient {
+        Recipient::new(
+            *identity::KeyPair::new(&mut OsRng).public_key(),
+            *encryption::KeyPair::new(&mut OsRng).public_key(),
+            *identity::KeyPair::new(&mut OsRng).public_key(),
+        )
+    }
+
+    fn deflate() -> SupportedCompression {
+        SupportedCompression::default().with(MessageCompression::Deflate)
+    }
+
+    #[test]
+    fn unknown_peers_have_no_compression_support() {
+        let support = PeerCompressionSupport::default();
+        assert!(support.for_recipient(&random_recipient()).is_empty());
+        assert!(support
+            .for_sender_tag(&AnonymousSenderTag::new_random(&mut OsRng))
+            .is_empty());
+    }
+
+    #[test]
+    fn repliable_messages_advertise_support_of_their_sender_tag() {
+        let support = PeerCompressionSupport::default();
+        let tag = AnonymousSenderTag::new_random(&mut OsRng);
+
+        let message =
+            NymMessage::new_repliable(RepliableMessage::new_additional_surbs(tag, vec![]));
+        support.record(&message, deflate());
+        assert_eq!(support.for_sender_tag(&tag), deflate());
+
+        // the peer might have gone back to an older version
+        support.record(&message, SupportedCompression::default());
+        assert!(support.for_sender_tag(&tag).is_empty());
+    }
+
+    #[test]
+    fn surb_requests_advertise_support_of_the_requester() {
+        let support = PeerCompressionSupport::default();
+        let recipient = random_recipient();
+
+        support.record(
+            &NymMessage::new_additional_surbs_request(recipient, 10),
+            deflate(),
+        );
+        assert_eq!(support.for_recipient(&recipient), deflate());
+        assert!(support.for_recipient(&random_recipient()).is_empty());
+    }
+
+    #[test]
+    fn plain_messages_are_not_attributed_to_anyone() {
+        let support = PeerCompressionSupport::default();
+        support.record(&NymMessage::new_plain(vec![1, 2, 3]), deflate());
+
+        assert!(support.recipients.is_empty());
+        assert!(support.sender_tags.is_empty());
+    }
+}
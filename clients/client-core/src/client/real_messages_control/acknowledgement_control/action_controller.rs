@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::PendingAcknowledgement;
+use crate::client::helpers::{get_time_now, Instant};
 use crate::client::real_messages_control::acknowledgement_control::RetransmissionRequestSender;
+use crate::client::statistics::ClientStatistics;
 use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
@@ -16,8 +18,13 @@ use std::time::Duration;
 pub(crate) type AckActionSender = mpsc::UnboundedSender<Action>;
 pub(crate) type AckActionReceiver = mpsc::UnboundedReceiver<Action>;
 
-// The actual data being sent off as well as potential key to the delay queue
-type PendingAckEntry = (Arc<PendingAcknowledgement>, Option<QueueKey>);
+// The actual data being sent off, potential key to the delay queue
+// and the time when the packet was last sent to the network
+type PendingAckEntry = (
+    Arc<PendingAcknowledgement>,
+    Option<QueueKey>,
+    Option<Instant>,
+);
 
 // we can either:
 // - have a completely new set of packets we just sent and need to create entries for
@@ -100,6 +107,9 @@ pub(super) struct ActionController {
 
     /// Channel for notifying `RetransmissionRequestListener` about expired acknowledgements.
     retransmission_sender: RetransmissionRequestSender,
+
+    /// Per-destination statistics of sent, retransmitted and acknowledged packets.
+    statistics: ClientStatistics,
 }
 
 impl ActionController {
@@ -107,6 +117,7 @@ impl ActionController {
         config: Config,
         retransmission_sender: RetransmissionRequestSender,
         incoming_actions: AckActionReceiver,
+        statistics: ClientStatistics,
     ) -> Self {
         ActionController {
             config,
@@ -114,6 +125,7 @@ impl ActionController {
             pending_acks_timers: NonExhaustiveDelayQueue::new(),
             incoming_actions,
             retransmission_sender,
+            statistics,
         }
     }
 
@@ -122,9 +134,14 @@ impl ActionController {
             let frag_id = pending_ack.message_chunk.fragment_identifier();
             trace!("{} is inserted", frag_id);
 
+            self.statistics.record_sent(
+                (&pending_ack.destination).into(),
+                pending_ack.message_chunk.payload_size(),
+            );
+
             if self
                 .pending_acks_data
-                .insert(frag_id, (Arc::new(pending_ack), None))
+                .insert(frag_id, (Arc::new(pending_ack), None, None))
                 .is_some()
             {
                 panic!("Tried to insert duplicate pending ack")
@@ -135,7 +152,9 @@ impl ActionController {
     fn handle_start_timer(&mut self, frag_id: FragmentIdentifier) {
         trace!("{} is starting its timer", frag_id);
 
-        if let Some((pending_ack_data, queue_key)) = self.pending_acks_data.get_mut(&frag_id) {
+        if let Some((pending_ack_data, queue_key, sent_at)) =
+            self.pending_acks_data.get_mut(&frag_id)
+        {
             // the fact that this branch is now POSSIBLE is a sign of a need to refactor this whole
             // retransmission procedure
             //
@@ -151,7 +170,8 @@ impl ActionController {
                 + self.config.ack_wait_addition;
 
             let new_queue_key = self.pending_acks_timers.insert(frag_id, timeout);
-            *queue_key = Some(new_queue_key)
+            *queue_key = Some(new_queue_key);
+            *sent_at = Some(get_time_now());
         } else {
             debug!(
                 "Tried to START TIMER on pending ack that is already gone! - {}",
@@ -170,7 +190,12 @@ impl ActionController {
                     frag_id
                 );
             }
-            Some((_, queue_key)) => {
+            Some((pending_ack_data, queue_key, sent_at)) => {
+                self.statistics.record_acknowledgement(
+                    (&pending_ack_data.destination).into(),
+                    sent_at.map(|sent_at| get_time_now().duration_since(sent_at)),
+                );
+
                 if let Some(queue_key) = queue_key {
                     // there are no possible checks here, we must GUARANTEE that we NEVER try
                     // to remove an entry that doesn't exist (and we MUST GUARANTEE that
@@ -194,7 +219,9 @@ impl ActionController {
     fn handle_update_delay(&mut self, frag_id: FragmentIdentifier, delay: SphinxDelay) {
         trace!("{} is updating its delay", frag_id);
         // TODO: is it possible to solve this without either locking or temporarily removing the value?
-        if let Some((pending_ack_data, queue_key)) = self.pending_acks_data.remove(&frag_id) {
+        if let Some((pending_ack_data, queue_key, sent_at)) =
+            self.pending_acks_data.remove(&frag_id)
+        {
             // this Action is triggered by `RetransmissionRequestListener` (for 'normal' packets)
             // or `ReplyController` (for 'reply' packets) which held the other potential
             // reference to this Arc. HOWEVER, before the Action was pushed onto the queue, the reference
@@ -203,7 +230,7 @@ impl ActionController {
             inner_data.update_delay(delay);
 
             self.pending_acks_data
-                .insert(frag_id, (Arc::new(inner_data), queue_key));
+                .insert(frag_id, (Arc::new(inner_data), queue_key, sent_at));
         } else {
             debug!(
                 "Tried to UPDATE TIMER on pending ack that is already gone! - {}",
@@ -225,13 +252,15 @@ impl ActionController {
 
         trace!("{} has expired", frag_id);
 
-        if let Some((pending_ack_data, queue_key)) = self.pending_acks_data.get_mut(&frag_id) {
+        if let Some((pending_ack_data, queue_key, _)) = self.pending_acks_data.get_mut(&frag_id) {
             if queue_key.is_none() {
                 // this branch should be IMPOSSIBLE under ANY condition. It would imply the timeout
                 // happened before it even started.
                 panic!("Ack expired before it was even scheduled!")
             }
             *queue_key = None;
+            self.statistics
+                .record_retransmission((&pending_ack_data.destination).into());
             // downgrading an arc and then upgrading vs cloning is difference of 30ns vs 15ns
             // so it's literally a NO difference while it might prevent us from unnecessarily
             // resending data (in maybe 1 in 1 million cases, but it's something)
@@ -10,6 +10,7 @@ use self::{
 use crate::client::inbound_messages::InputMessageReceiver;
 use crate::client::real_messages_control::message_handler::MessageHandler;
 use crate::client::replies::reply_controller::ReplyControllerSender;
+use crate::client::statistics::{ClientStatistics, StatisticsDestination};
 use crate::spawn_future;
 use action_controller::AckActionReceiver;
 use futures::channel::mpsc;
@@ -63,6 +64,15 @@ pub(crate) enum PacketDestination {
     KnownRecipient(Box<Recipient>),
 }
 
+impl From<&PacketDestination> for StatisticsDestination {
+    fn from(destination: &PacketDestination) -> Self {
+        match destination {
+            PacketDestination::Anonymous { recipient_tag, .. } => (*recipient_tag).into(),
+            PacketDestination::KnownRecipient(recipient) => recipient.as_ref().into(),
+        }
+    }
+}
+
 /// Structure representing a data `Fragment` that is on-route to the specified `Recipient`
 #[derive(Debug)]
 pub(crate) struct PendingAcknowledgement {
@@ -202,6 +212,7 @@ where
         connectors: AcknowledgementControllerConnectors,
         message_handler: MessageHandler<R>,
         reply_controller_sender: ReplyControllerSender,
+        statistics: ClientStatistics,
     ) -> Self {
         let (retransmission_tx, retransmission_rx) = mpsc::unbounded();
 
@@ -211,6 +222,7 @@ where
             action_config,
             retransmission_tx,
             connectors.ack_action_receiver,
+            statistics,
         );
 
         // will listen for any acks coming from the network
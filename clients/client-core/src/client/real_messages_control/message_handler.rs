@@ -1,12 +1,17 @@
 // Copyright 2022 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::gateway_failover::{PeerRedirects, SelfAddress};
+use crate::client::peer_compression::PeerCompressionSupport;
 use crate::client::real_messages_control::acknowledgement_control::PendingAcknowledgement;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::client::real_messages_control::preparation_pool::PreparationPool;
 use crate::client::real_messages_control::real_traffic_stream::{
     BatchRealMessageSender, RealMessage,
 };
 use crate::client::real_messages_control::{AckActionSender, Action};
 use crate::client::replies::reply_storage::{ReceivedReplySurbsMap, SentReplyKeys, UsedSenderTags};
+use crate::client::statistics::ClientStatistics;
 use crate::client::topology_control::{TopologyAccessor, TopologyReadPermit};
 use log::{debug, error, info, trace, warn};
 use nym_sphinx::acknowledgements::AckKey;
@@ -14,18 +19,26 @@ use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::anonymous_replies::requests::{AnonymousSenderTag, RepliableMessage, ReplyMessage};
 use nym_sphinx::anonymous_replies::{ReplySurb, SurbEncryptionKey};
 use nym_sphinx::chunking::fragment::{Fragment, FragmentIdentifier};
+use nym_sphinx::compression::{MessageCompression, SupportedCompression};
 use nym_sphinx::message::NymMessage;
 use nym_sphinx::params::{PacketSize, DEFAULT_NUM_MIX_HOPS};
 use nym_sphinx::preparer::{MessagePreparer, PreparedFragment};
 use nym_sphinx::Delay;
 use nym_task::connections::TransmissionLane;
 use nym_topology::{NymTopology, NymTopologyError};
+#[cfg(not(target_arch = "wasm32"))]
+use rand::rngs::OsRng;
 use rand::{CryptoRng, Rng};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 
+/// Minimum number of packets a message has to be split into before they get constructed by the
+/// preparation pool rather than inline.
+#[cfg(not(target_arch = "wasm32"))]
+const PARALLEL_PREPARATION_THRESHOLD: usize = 2;
+
 // TODO: move that error elsewhere since it seems to be contaminating different files
 #[derive(Debug, Clone, Error)]
 pub enum PreparationError {
@@ -37,6 +50,9 @@ pub enum PreparationError {
 
     #[error("Not enough reply SURBs to send the message. We have {available} available and require at least {required}.")]
     NotEnoughSurbs { available: usize, required: usize },
+
+    #[error("The packet preparation job has failed to complete")]
+    PreparationJobFailed,
 }
 
 impl PreparationError {
@@ -89,7 +105,7 @@ pub(crate) struct Config {
 
     /// Address of this client which also represent an address to which all acknowledgements
     /// and surb-based are going to be sent.
-    sender_address: Recipient,
+    sender_address: SelfAddress,
 
     /// Average delay a data packet is going to get delay at a single mixnode.
     average_packet_delay: Duration,
@@ -103,12 +119,19 @@ pub(crate) struct Config {
 
     /// Predefined packet size used for the encapsulated messages.
     packet_size: PacketSize,
+
+    /// Ratio of parity to data fragments attached to the sent messages. Disabled if 0.
+    fec_redundancy: f64,
+
+    /// Preferred compression algorithm applied to the content of the messages sent to the peers
+    /// that have advertised support for it.
+    compression: MessageCompression,
 }
 
 impl Config {
     pub fn new(
         ack_key: Arc<AckKey>,
-        sender_address: Recipient,
+        sender_address: SelfAddress,
         average_packet_delay: Duration,
         average_ack_delay: Duration,
     ) -> Self {
@@ -119,6 +142,8 @@ impl Config {
             average_ack_delay,
             num_mix_hops: DEFAULT_NUM_MIX_HOPS,
             packet_size: PacketSize::default(),
+            fec_redundancy: 0.0,
+            compression: MessageCompression::None,
         }
     }
 
@@ -134,6 +159,35 @@ impl Config {
         self.packet_size = packet_size;
         self
     }
+
+    /// Allows attaching parity fragments to the sent messages so that they could be reconstructed
+    /// despite losing some of the packets.
+    pub fn with_forward_error_correction(mut self, redundancy: f64) -> Self {
+        self.fec_redundancy = redundancy;
+        self
+    }
+
+    /// Allows compressing the content of the messages sent to the peers that support it.
+    pub fn with_compression(mut self, compression: MessageCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    fn message_preparer<R>(&self, rng: R) -> MessagePreparer<R>
+    where
+        R: CryptoRng + Rng,
+    {
+        MessagePreparer::new(
+            rng,
+            self.sender_address.current(),
+            self.average_packet_delay,
+            self.average_ack_delay,
+        )
+        .with_custom_real_message_packet_size(self.packet_size)
+        .with_mix_hops(self.num_mix_hops)
+        .with_forward_error_correction(self.fec_redundancy)
+        .with_supported_compression(SupportedCompression::local())
+    }
 }
 
 #[derive(Clone)]
@@ -146,12 +200,18 @@ pub(crate) struct MessageHandler<R> {
     topology_access: TopologyAccessor,
     reply_key_storage: SentReplyKeys,
     tag_storage: UsedSenderTags,
+    peer_redirects: PeerRedirects,
+    peer_compression: PeerCompressionSupport,
+    statistics: ClientStatistics,
+    #[cfg(not(target_arch = "wasm32"))]
+    preparation_pool: Option<PreparationPool>,
 }
 
 impl<R> MessageHandler<R>
 where
     R: CryptoRng + Rng,
 {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         config: Config,
         rng: R,
@@ -160,18 +220,13 @@ where
         topology_access: TopologyAccessor,
         reply_key_storage: SentReplyKeys,
         tag_storage: UsedSenderTags,
+        peer_compression: PeerCompressionSupport,
+        statistics: ClientStatistics,
     ) -> Self
     where
         R: Copy,
     {
-        let message_preparer = MessagePreparer::new(
-            rng,
-            config.sender_address,
-            config.average_packet_delay,
-            config.average_ack_delay,
-        )
-        .with_custom_real_message_packet_size(config.packet_size)
-        .with_mix_hops(config.num_mix_hops);
+        let message_preparer = config.message_preparer(rng);
 
         MessageHandler {
             config,
@@ -182,9 +237,33 @@ where
             topology_access,
             reply_key_storage,
             tag_storage,
+            peer_redirects: PeerRedirects::default(),
+            peer_compression,
+            statistics,
+            #[cfg(not(target_arch = "wasm32"))]
+            preparation_pool: None,
         }
     }
 
+    /// Makes the packets of multi-packet messages get constructed in parallel by the provided pool.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn with_preparation_pool(mut self, preparation_pool: PreparationPool) -> Self {
+        self.preparation_pool = Some(preparation_pool);
+        self
+    }
+
+    // our address might have changed if we failed over to a different gateway
+    fn refresh_sender_address(&mut self) {
+        self.message_preparer
+            .set_sender_address(self.config.sender_address.current())
+    }
+
+    /// Makes all subsequent messages addressed to any of the previous addresses of the remote
+    /// client be sent to its new address instead.
+    pub(crate) fn redirect_recipient(&self, new_address: Recipient) {
+        self.peer_redirects.insert(new_address)
+    }
+
     fn get_or_create_sender_tag(&mut self, recipient: &Recipient) -> AnonymousSenderTag {
         if let Some(existing) = self.tag_storage.try_get_existing(recipient) {
             trace!("we already had sender tag for {recipient}");
@@ -202,7 +281,7 @@ where
         &self,
         permit: &'a TopologyReadPermit<'a>,
     ) -> Result<&'a NymTopology, PreparationError> {
-        match permit.try_get_valid_topology_ref(&self.config.sender_address, None) {
+        match permit.try_get_valid_topology_ref(&self.config.sender_address.current(), None) {
             Ok(topology_ref) => Ok(topology_ref),
             Err(err) => {
                 warn!("Could not process the packet - the network topology is invalid - {err}");
@@ -211,10 +290,113 @@ where
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    fn parallel_preparation_pool(&self, packets: usize) -> Option<PreparationPool> {
+        if packets < PARALLEL_PREPARATION_THRESHOLD {
+            return None;
+        }
+        self.preparation_pool.clone()
+    }
+
+    /// Splits the items into (at most) one batch per worker and constructs their packets in parallel.
+    /// Every batch gets its own `MessagePreparer` alongside a shared snapshot of the current topology.
+    /// The prepared packets are returned in the order of the provided items.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn prepare_in_pool<T, F>(
+        &self,
+        pool: PreparationPool,
+        items: Vec<T>,
+        topology: &NymTopology,
+        prepare: F,
+    ) -> Result<Vec<PreparedFragment>, PreparationError>
+    where
+        T: Send + 'static,
+        F: Fn(
+                &mut MessagePreparer<OsRng>,
+                T,
+                &NymTopology,
+                &AckKey,
+            ) -> Result<PreparedFragment, NymTopologyError>
+            + Copy
+            + Send
+            + 'static,
+    {
+        let topology = Arc::new(topology.clone());
+        let jobs = pool.spawn_batches(
+            items,
+            || {
+                (
+                    self.config.message_preparer(OsRng),
+                    Arc::clone(&topology),
+                    Arc::clone(&self.config.ack_key),
+                )
+            },
+            move |(message_preparer, topology, ack_key), item| {
+                prepare(message_preparer, item, topology, ack_key)
+            },
+        );
+
+        let prepared = jobs
+            .join()
+            .await
+            .map_err(|_| PreparationError::PreparationJobFailed)?
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        pool.metrics().record_prepared_packets(prepared.len());
+        Ok(prepared)
+    }
+
+    async fn prepare_chunks_for_sending(
+        &mut self,
+        fragments: &[Fragment],
+        topology: &NymTopology,
+        recipient: Recipient,
+    ) -> Result<Vec<PreparedFragment>, PreparationError> {
+        // we need to clone the fragments because we need to keep them in memory in case we had to
+        // retransmit them. And then we'd need to recreate entire ACK again.
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(pool) = self.parallel_preparation_pool(fragments.len()) {
+            match self
+                .prepare_in_pool(
+                    pool,
+                    fragments.to_vec(),
+                    topology,
+                    move |message_preparer, fragment, topology, ack_key| {
+                        message_preparer
+                            .prepare_chunk_for_sending(fragment, topology, ack_key, &recipient)
+                    },
+                )
+                .await
+            {
+                // we still have all the fragments, so we can just prepare them here instead
+                Err(PreparationError::PreparationJobFailed) => {
+                    warn!(
+                        "failed to prepare the packets in the pool - preparing them inline instead"
+                    )
+                }
+                prepared => return prepared,
+            }
+        }
+
+        Ok(fragments
+            .iter()
+            .map(|fragment| {
+                self.message_preparer.prepare_chunk_for_sending(
+                    fragment.clone(),
+                    topology,
+                    &self.config.ack_key,
+                    &recipient,
+                )
+            })
+            .collect::<Result<_, _>>()?)
+    }
+
     async fn generate_reply_surbs_with_keys(
         &mut self,
         amount: usize,
     ) -> Result<(Vec<ReplySurb>, Vec<SurbEncryptionKey>), PreparationError> {
+        self.refresh_sender_address();
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = self.get_topology(&topology_permit)?;
 
@@ -282,17 +464,26 @@ where
         debug!("requesting {amount} reply SURBs from {from}");
 
         let surbs_request =
-            ReplyMessage::new_surb_request_message(self.config.sender_address, amount);
+            ReplyMessage::new_surb_request_message(self.config.sender_address.current(), amount);
         self.try_send_single_surb_message(from, surbs_request, reply_surb, true)
             .await
     }
 
     // // TODO: this will require additional argument to make it use different variant of `ReplyMessage`
-    pub(crate) fn split_reply_message(&mut self, message: Vec<u8>) -> Vec<Fragment> {
-        self.message_preparer
-            .pad_and_split_message(NymMessage::new_reply(ReplyMessage::new_data_message(
-                message,
-            )))
+    pub(crate) fn split_reply_message(
+        &mut self,
+        target: &AnonymousSenderTag,
+        message: Vec<u8>,
+    ) -> Vec<Fragment> {
+        let compression = self
+            .peer_compression
+            .for_sender_tag(target)
+            .negotiate(self.config.compression);
+
+        self.message_preparer.compress_pad_and_split_message(
+            NymMessage::new_reply(ReplyMessage::new_data_message(message)),
+            compression,
+        )
     }
 
     pub(crate) async fn send_retransmission_reply_chunks(
@@ -348,11 +539,14 @@ where
             let fragment = raw.1;
 
             let real_message = RealMessage::new(prepared.mix_packet, prepared.fragment_identifier);
+            to_forward.entry(lane).or_default().push(real_message);
+
+            // parity fragments are never retransmitted, they only exist to make up for lost data
+            if fragment.is_parity() {
+                continue;
+            }
             let delay = prepared.total_delay;
             let pending_ack = PendingAcknowledgement::new_anonymous(fragment, delay, target, false);
-
-            let entry = to_forward.entry(lane).or_default();
-            entry.push(real_message);
             pending_acks.push(pending_ack);
         }
 
@@ -384,31 +578,38 @@ where
         // TODO: I really dislike existence of this assertion, it implies code has to be re-organised
         debug_assert!(!matches!(message, NymMessage::Reply(_)));
 
+        self.refresh_sender_address();
+        let recipient = self.peer_redirects.resolve(recipient);
+
         // TODO2: it's really annoying we have to get topology permit again here due to borrow-checker
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = self.get_topology(&topology_permit)?;
 
-        let fragments = self.message_preparer.pad_and_split_message(message);
+        let compression = self
+            .peer_compression
+            .for_recipient(&recipient)
+            .negotiate(self.config.compression);
+        let fragments = self
+            .message_preparer
+            .compress_pad_and_split_message(message, compression);
+        let prepared_fragments = self
+            .prepare_chunks_for_sending(&fragments, topology, recipient)
+            .await?;
+        drop(topology_permit);
 
         let mut pending_acks = Vec::with_capacity(fragments.len());
         let mut real_messages = Vec::with_capacity(fragments.len());
-        for fragment in fragments {
-            // we need to clone it because we need to keep it in memory in case we had to retransmit
-            // it. And then we'd need to recreate entire ACK again.
-            let chunk_clone = fragment.clone();
-            let prepared_fragment = self.message_preparer.prepare_chunk_for_sending(
-                chunk_clone,
-                topology,
-                &self.config.ack_key,
-                &recipient,
-            )?;
-
+        for (fragment, prepared_fragment) in fragments.into_iter().zip(prepared_fragments) {
             let real_message =
                 RealMessage::new(prepared_fragment.mix_packet, fragment.fragment_identifier());
+            real_messages.push(real_message);
+
+            // parity fragments are never retransmitted, they only exist to make up for lost data
+            if fragment.is_parity() {
+                continue;
+            }
             let delay = prepared_fragment.total_delay;
             let pending_ack = PendingAcknowledgement::new_known(fragment, delay, recipient);
-
-            real_messages.push(real_message);
             pending_acks.push(pending_ack);
         }
 
@@ -426,6 +627,7 @@ where
         let sender_tag = self.get_or_create_sender_tag(&recipient);
         let (reply_surbs, reply_keys) =
             self.generate_reply_surbs_with_keys(amount as usize).await?;
+        let reply_surbs_count = reply_surbs.len();
 
         let message = NymMessage::new_repliable(RepliableMessage::new_additional_surbs(
             sender_tag,
@@ -438,6 +640,8 @@ where
             TransmissionLane::AdditionalReplySurbs,
         )
         .await?;
+        self.statistics
+            .record_reply_surbs_sent(&recipient, reply_surbs_count);
 
         log::trace!("storing {} reply keys", reply_keys.len());
         self.reply_key_storage.insert_multiple(reply_keys);
@@ -456,12 +660,15 @@ where
         let (reply_surbs, reply_keys) = self
             .generate_reply_surbs_with_keys(num_reply_surbs as usize)
             .await?;
+        let reply_surbs_count = reply_surbs.len();
 
         let message =
             NymMessage::new_repliable(RepliableMessage::new_data(message, sender_tag, reply_surbs));
 
         self.try_split_and_send_non_reply_message(message, recipient, lane)
             .await?;
+        self.statistics
+            .record_reply_surbs_sent(&recipient, reply_surbs_count);
 
         log::trace!("storing {} reply keys", reply_keys.len());
         self.reply_key_storage.insert_multiple(reply_keys);
@@ -474,6 +681,8 @@ where
         recipient: Recipient,
         chunk: Fragment,
     ) -> Result<PreparedFragment, PreparationError> {
+        self.refresh_sender_address();
+        let recipient = self.peer_redirects.resolve(recipient);
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = self.get_topology(&topology_permit)?;
 
@@ -498,12 +707,31 @@ where
             reply_surbs.len()
         );
 
+        self.refresh_sender_address();
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = match self.get_topology(&topology_permit) {
             Ok(topology) => topology,
             Err(err) => return Err(err.return_surbs(reply_surbs)),
         };
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(pool) = self.parallel_preparation_pool(fragments.len()) {
+            let prepared = self
+                .prepare_in_pool(
+                    pool,
+                    fragments.into_iter().zip(reply_surbs).collect(),
+                    topology,
+                    |message_preparer, (fragment, reply_surb), topology, ack_key| {
+                        message_preparer.prepare_reply_chunk_for_sending(
+                            fragment, topology, ack_key, reply_surb,
+                        )
+                    },
+                )
+                // the reply SURBs have been consumed by the failed job, so there's nothing to return
+                .await?;
+            return Ok(prepared);
+        }
+
         Ok(fragments
             .into_iter()
             .zip(reply_surbs.into_iter())
@@ -526,6 +754,7 @@ where
         reply_surb: ReplySurb,
         chunk: Fragment,
     ) -> Result<PreparedFragment, SurbWrappedPreparationError> {
+        self.refresh_sender_address();
         let topology_permit = self.topology_access.get_read_permit().await;
         let topology = match self.get_topology(&topology_permit) {
             Ok(topology) => topology,
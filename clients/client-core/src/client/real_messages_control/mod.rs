@@ -8,11 +8,14 @@
 use self::{
     acknowledgement_control::AcknowledgementController, real_traffic_stream::OutQueueControl,
 };
+use crate::client::gateway_failover::SelfAddress;
+use crate::client::peer_compression::PeerCompressionSupport;
 use crate::client::real_messages_control::message_handler::MessageHandler;
 use crate::client::replies::reply_controller::{
     ReplyController, ReplyControllerReceiver, ReplyControllerSender,
 };
 use crate::client::replies::reply_storage::CombinedReplyStorage;
+use crate::client::statistics::ClientStatistics;
 use crate::{
     client::{
         inbound_messages::InputMessageReceiver, mix_traffic::BatchMixMessageSender,
@@ -25,7 +28,7 @@ use futures::channel::mpsc;
 use gateway_client::AcknowledgementReceiver;
 use log::*;
 use nym_sphinx::acknowledgements::AckKey;
-use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::compression::MessageCompression;
 use nym_sphinx::params::PacketSize;
 use nym_task::connections::{ConnectionCommandReceiver, LaneQueueLengths};
 use rand::{rngs::OsRng, CryptoRng, Rng};
@@ -35,9 +38,14 @@ use std::time::Duration;
 use crate::client::replies::reply_controller;
 use crate::config;
 pub(crate) use acknowledgement_control::{AckActionSender, Action};
+pub use preparation_pool::PacketPreparationMetrics;
+
+#[cfg(not(target_arch = "wasm32"))]
+use self::preparation_pool::PreparationPool;
 
 pub(crate) mod acknowledgement_control;
 pub(crate) mod message_handler;
+pub(crate) mod preparation_pool;
 pub(crate) mod real_traffic_stream;
 
 // TODO: ack_key and self_recipient shouldn't really be part of this config
@@ -52,7 +60,7 @@ pub struct Config {
     ack_wait_multiplier: f64,
 
     /// Address of `this` client.
-    self_recipient: Recipient,
+    self_recipient: SelfAddress,
 
     /// Average delay between sending subsequent packets from this client.
     average_message_sending_delay: Duration,
@@ -94,6 +102,18 @@ pub struct Config {
     /// Defines maximum amount of time given reply key is going to be valid for.
     /// This is going to be superseded by key rotation once implemented.
     maximum_reply_key_age: Duration,
+
+    /// Number of dedicated threads used for constructing sphinx packets outside of the async runtime.
+    packet_preparation_workers: usize,
+
+    /// Number of loop cover packets kept constructed ahead of time.
+    precomputed_cover_packets: usize,
+
+    /// Ratio of parity to data fragments attached to the sent messages.
+    forward_error_correction_redundancy: f64,
+
+    /// Compression algorithm applied to the content of the sent messages.
+    message_compression: MessageCompression,
 }
 
 impl<'a> From<&'a Config> for acknowledgement_control::Config {
@@ -107,7 +127,7 @@ impl<'a> From<&'a Config> for real_traffic_stream::Config {
     fn from(cfg: &'a Config) -> Self {
         real_traffic_stream::Config::new(
             Arc::clone(&cfg.ack_key),
-            cfg.self_recipient,
+            cfg.self_recipient.clone(),
             cfg.average_ack_delay_duration,
             cfg.average_packet_delay_duration,
             cfg.average_message_sending_delay,
@@ -135,11 +155,13 @@ impl<'a> From<&'a Config> for message_handler::Config {
     fn from(cfg: &'a Config) -> Self {
         message_handler::Config::new(
             Arc::clone(&cfg.ack_key),
-            cfg.self_recipient,
+            cfg.self_recipient.clone(),
             cfg.average_packet_delay_duration,
             cfg.average_ack_delay_duration,
         )
         .with_custom_packet_size(cfg.packet_size)
+        .with_forward_error_correction(cfg.forward_error_correction_redundancy)
+        .with_compression(cfg.message_compression)
     }
 }
 
@@ -147,7 +169,7 @@ impl Config {
     pub fn new(
         base_client_debug_config: &config::DebugConfig,
         ack_key: Arc<AckKey>,
-        self_recipient: Recipient,
+        self_recipient: SelfAddress,
     ) -> Self {
         Config {
             ack_key,
@@ -172,6 +194,14 @@ impl Config {
                 .maximum_reply_surb_drop_waiting_period,
             maximum_reply_surb_age: base_client_debug_config.maximum_reply_surb_age,
             maximum_reply_key_age: base_client_debug_config.maximum_reply_key_age,
+            packet_preparation_workers: base_client_debug_config.packet_preparation_workers,
+            precomputed_cover_packets: base_client_debug_config.precomputed_cover_packets,
+            forward_error_correction_redundancy: base_client_debug_config
+                .forward_error_correction_redundancy,
+            message_compression: base_client_debug_config
+                .message_compression
+                .map(Into::into)
+                .unwrap_or_default(),
         }
     }
 
@@ -205,9 +235,20 @@ impl RealMessagesController<OsRng> {
         reply_controller_receiver: ReplyControllerReceiver,
         lane_queue_lengths: LaneQueueLengths,
         client_connection_rx: ConnectionCommandReceiver,
+        preparation_metrics: PacketPreparationMetrics,
+        peer_compression: PeerCompressionSupport,
+        statistics: ClientStatistics,
     ) -> Self {
         let rng = OsRng;
 
+        #[cfg(not(target_arch = "wasm32"))]
+        let preparation_pool = (config.packet_preparation_workers > 0)
+            .then(|| PreparationPool::new(config.packet_preparation_workers, preparation_metrics));
+
+        // the metrics are never going to get updated as all packets are constructed inline
+        #[cfg(target_arch = "wasm32")]
+        let _ = preparation_metrics;
+
         // create channels for inter-task communication
         let (real_message_sender, real_message_receiver) = tokio::sync::mpsc::channel(1);
         let (sent_notifier_tx, sent_notifier_rx) = mpsc::unbounded();
@@ -235,14 +276,23 @@ impl RealMessagesController<OsRng> {
             topology_access.clone(),
             reply_storage.key_storage(),
             reply_storage.tags_storage(),
+            peer_compression,
+            statistics.clone(),
         );
 
+        #[cfg(not(target_arch = "wasm32"))]
+        let message_handler = match &preparation_pool {
+            Some(pool) => message_handler.with_preparation_pool(pool.clone()),
+            None => message_handler,
+        };
+
         let ack_control = AcknowledgementController::new(
             ack_control_config,
             Arc::clone(&config.ack_key),
             ack_controller_connectors,
             message_handler.clone(),
             reply_controller_sender,
+            statistics,
         );
 
         let reply_control = ReplyController::new(
@@ -263,6 +313,14 @@ impl RealMessagesController<OsRng> {
             client_connection_rx,
         );
 
+        #[cfg(not(target_arch = "wasm32"))]
+        let out_queue_control = match preparation_pool {
+            Some(pool) => {
+                out_queue_control.with_preparation_pool(pool, config.precomputed_cover_packets)
+            }
+            None => out_queue_control,
+        };
+
         RealMessagesController {
             out_queue_control,
             ack_control,
@@ -0,0 +1,434 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use futures::channel::oneshot;
+#[cfg(not(target_arch = "wasm32"))]
+use log::warn;
+#[cfg(not(target_arch = "wasm32"))]
+use nym_sphinx::{
+    acknowledgements::AckKey, addressing::clients::Recipient, cover::generate_loop_cover_packet,
+    forwarding::packet::MixPacket, params::PacketSize,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use nym_topology::NymTopology;
+#[cfg(not(target_arch = "wasm32"))]
+use rand::rngs::OsRng;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::VecDeque;
+#[cfg(not(target_arch = "wasm32"))]
+use std::panic::{catch_unwind, AssertUnwindSafe};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+/// Counters describing the state of the packet preparation pipeline. They are updated by the
+/// preparation workers and the real traffic stream and are cheap to clone and read at any point.
+#[derive(Clone, Debug, Default)]
+pub struct PacketPreparationMetrics {
+    inner: Arc<PacketPreparationMetricsInner>,
+}
+
+#[derive(Debug, Default)]
+struct PacketPreparationMetricsInner {
+    queued_jobs: AtomicUsize,
+    active_jobs: AtomicUsize,
+    prepared_packets: AtomicUsize,
+    precomputed_cover_packets: AtomicUsize,
+}
+
+impl PacketPreparationMetrics {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Number of preparation jobs waiting for a free worker.
+    pub fn queue_depth(&self) -> usize {
+        self.inner.queued_jobs.load(Ordering::Relaxed)
+    }
+
+    /// Number of preparation jobs currently being executed by the workers.
+    pub fn active_jobs(&self) -> usize {
+        self.inner.active_jobs.load(Ordering::Relaxed)
+    }
+
+    /// Total number of packets (both real and cover) constructed by the workers.
+    pub fn prepared_packets(&self) -> usize {
+        self.inner.prepared_packets.load(Ordering::Relaxed)
+    }
+
+    /// Number of loop cover packets that are constructed and ready to be sent.
+    pub fn precomputed_cover_packets(&self) -> usize {
+        self.inner.precomputed_cover_packets.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_prepared_packets(&self, count: usize) {
+        self.inner
+            .prepared_packets
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_precomputed_cover_packets(&self, count: usize) {
+        self.inner
+            .precomputed_cover_packets
+            .store(count, Ordering::Relaxed);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Fixed set of threads constructing sphinx packets. Header construction is CPU-heavy, so doing it
+/// directly on the async runtime stalls every other task (including the poisson sending stream)
+/// whenever a large message is being split into packets.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub(crate) struct PreparationPool {
+    job_sender: crossbeam_channel::Sender<Job>,
+    workers: usize,
+    metrics: PacketPreparationMetrics,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PreparationPool {
+    pub(crate) fn new(workers: usize, metrics: PacketPreparationMetrics) -> Self {
+        let (job_sender, job_receiver) = crossbeam_channel::unbounded::<Job>();
+
+        for i in 0..workers {
+            let job_receiver = job_receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("packet-preparer-{i}"))
+                .spawn(move || {
+                    // the loop terminates once all the senders (i.e. all the pool handles) are dropped
+                    while let Ok(job) = job_receiver.recv() {
+                        job()
+                    }
+                })
+                .expect("failed to spawn packet preparation worker");
+        }
+
+        PreparationPool {
+            job_sender,
+            workers,
+            metrics,
+        }
+    }
+
+    pub(crate) fn workers(&self) -> usize {
+        self.workers
+    }
+
+    pub(crate) fn metrics(&self) -> &PacketPreparationMetrics {
+        &self.metrics
+    }
+
+    /// Schedules the job on one of the workers and returns a receiver for its result.
+    /// If the job panics, the worker survives it and the receiver resolves to `Canceled`.
+    pub(crate) fn spawn<F, T>(&self, job: F) -> oneshot::Receiver<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (result_sender, result_receiver) = oneshot::channel();
+        let metrics = Arc::clone(&self.metrics.inner);
+        metrics.queued_jobs.fetch_add(1, Ordering::Relaxed);
+
+        let job: Job = Box::new(move || {
+            metrics.queued_jobs.fetch_sub(1, Ordering::Relaxed);
+            metrics.active_jobs.fetch_add(1, Ordering::Relaxed);
+            // don't let a single faulty job take the whole worker down with it
+            let result = catch_unwind(AssertUnwindSafe(job));
+            metrics.active_jobs.fetch_sub(1, Ordering::Relaxed);
+            match result {
+                // the requester might have given up on the result in the meantime, which is fine
+                Ok(result) => {
+                    let _ = result_sender.send(result);
+                }
+                Err(_) => warn!("packet preparation job has panicked"),
+            }
+        });
+
+        // this could only fail if all the workers have exited, in which case the job (alongside
+        // its result sender) gets dropped and the receiver resolves to `Canceled`
+        if self.job_sender.send(job).is_err() {
+            warn!("there are no packet preparation workers to execute the job");
+            self.metrics
+                .inner
+                .queued_jobs
+                .fetch_sub(1, Ordering::Relaxed);
+        }
+        result_receiver
+    }
+
+    /// Splits the items into (at most) one batch per worker and processes the batches in parallel.
+    /// Every batch gets its own state created with `batch_state`.
+    pub(crate) fn spawn_batches<T, S, R, F>(
+        &self,
+        items: Vec<T>,
+        mut batch_state: impl FnMut() -> S,
+        process: F,
+    ) -> BatchedJobs<R>
+    where
+        T: Send + 'static,
+        S: Send + 'static,
+        R: Send + 'static,
+        F: Fn(&mut S, T) -> R + Copy + Send + 'static,
+    {
+        let total = items.len();
+        let batch_size = total.saturating_sub(1) / self.workers + 1;
+
+        let mut items = items.into_iter().peekable();
+        let mut jobs = Vec::with_capacity(self.workers);
+        while items.peek().is_some() {
+            let batch = items.by_ref().take(batch_size).collect::<Vec<_>>();
+            let mut state = batch_state();
+            jobs.push(self.spawn(move || {
+                batch
+                    .into_iter()
+                    .map(|item| process(&mut state, item))
+                    .collect()
+            }))
+        }
+
+        BatchedJobs { total, jobs }
+    }
+}
+
+/// Results of the batches scheduled with [`PreparationPool::spawn_batches`].
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct BatchedJobs<R> {
+    total: usize,
+    jobs: Vec<oneshot::Receiver<Vec<R>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<R> BatchedJobs<R> {
+    /// Waits for all the batches to finish and returns their results in the order of the original items.
+    pub(crate) async fn join(self) -> Result<Vec<R>, oneshot::Canceled> {
+        let mut results = Vec::with_capacity(self.total);
+        for job in self.jobs {
+            results.extend(job.await?)
+        }
+        Ok(results)
+    }
+}
+
+/// Buffer of loop cover packets constructed by the preparation pool ahead of the moment they are
+/// needed, so that the real traffic stream could send them without any delay.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct PrecomputedCoverPackets {
+    pool: PreparationPool,
+
+    /// Number of packets the buffer is being refilled up to.
+    target_size: usize,
+
+    ack_key: Arc<AckKey>,
+    average_ack_delay: Duration,
+    average_packet_delay: Duration,
+    packet_size: PacketSize,
+
+    /// What the buffered packets were constructed for.
+    context: Option<CoverPacketsContext>,
+    packets: VecDeque<MixPacket>,
+    pending_refill: Option<(CoverPacketsContext, oneshot::Receiver<Vec<MixPacket>>)>,
+}
+
+/// If any of it changes (i.e. we failed over to a different gateway or the topology, including
+/// the sphinx keys of the nodes, got updated), the precomputed packets are discarded.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CoverPacketsContext {
+    address: Recipient,
+    topology_generation: u64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PrecomputedCoverPackets {
+    pub(crate) fn new(
+        pool: PreparationPool,
+        target_size: usize,
+        ack_key: Arc<AckKey>,
+        average_ack_delay: Duration,
+        average_packet_delay: Duration,
+        packet_size: PacketSize,
+    ) -> Self {
+        PrecomputedCoverPackets {
+            pool,
+            target_size,
+            ack_key,
+            average_ack_delay,
+            average_packet_delay,
+            packet_size,
+            context: None,
+            packets: VecDeque::with_capacity(target_size),
+            pending_refill: None,
+        }
+    }
+
+    fn collect_refill(&mut self) {
+        let Some((context, receiver)) = self.pending_refill.as_mut() else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(None) => return,
+            Ok(Some(packets)) => {
+                if self.context == Some(*context) {
+                    self.packets.extend(packets)
+                }
+            }
+            // the job panicked - a new one is going to get scheduled
+            Err(_) => warn!("failed to precompute loop cover packets"),
+        }
+        self.pending_refill = None;
+    }
+
+    fn schedule_refill(&mut self, topology: &NymTopology, context: CoverPacketsContext) {
+        let address = context.address;
+        let amount = self.target_size - self.packets.len();
+        let topology = topology.clone();
+        let ack_key = Arc::clone(&self.ack_key);
+        let average_ack_delay = self.average_ack_delay;
+        let average_packet_delay = self.average_packet_delay;
+        let packet_size = self.packet_size;
+        let metrics = self.pool.metrics().clone();
+
+        let receiver = self.pool.spawn(move || {
+            let mut rng = OsRng;
+            let mut packets = Vec::with_capacity(amount);
+            for _ in 0..amount {
+                match generate_loop_cover_packet(
+                    &mut rng,
+                    &topology,
+                    &ack_key,
+                    &address,
+                    average_ack_delay,
+                    average_packet_delay,
+                    packet_size,
+                ) {
+                    Ok(packet) => packets.push(packet),
+                    Err(err) => {
+                        warn!("failed to precompute loop cover packet - {err}");
+                        break;
+                    }
+                }
+            }
+            metrics.record_prepared_packets(packets.len());
+            packets
+        });
+        self.pending_refill = Some((context, receiver));
+    }
+
+    /// Takes the next precomputed packet constructed for the provided address and topology generation
+    /// (if any is available) and schedules construction of more of them once the buffer runs low.
+    pub(crate) fn next_packet(
+        &mut self,
+        topology: &NymTopology,
+        topology_generation: u64,
+        address: &Recipient,
+    ) -> Option<MixPacket> {
+        let context = CoverPacketsContext {
+            address: *address,
+            topology_generation,
+        };
+
+        self.collect_refill();
+        if self.context != Some(context) {
+            self.context = Some(context);
+            self.packets.clear();
+        }
+
+        let packet = self.packets.pop_front();
+        let refill_is_stale =
+            matches!(&self.pending_refill, Some((pending, _)) if *pending != context);
+        if refill_is_stale
+            || self.pending_refill.is_none() && self.packets.len() <= self.target_size / 2
+        {
+            // if the pending refill is for an outdated context, its packets are going to be discarded anyway
+            self.schedule_refill(topology, context);
+        }
+
+        self.pool
+            .metrics()
+            .set_precomputed_cover_packets(self.packets.len());
+        packet
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn jobs_return_their_results() {
+        let pool = PreparationPool::new(2, PacketPreparationMetrics::new());
+        let jobs = (0..10u32)
+            .map(|i| pool.spawn(move || i * i))
+            .collect::<Vec<_>>();
+
+        for (i, job) in jobs.into_iter().enumerate() {
+            assert_eq!(job.await.unwrap(), (i * i) as u32);
+        }
+        assert_eq!(pool.metrics().queue_depth(), 0);
+        assert_eq!(pool.metrics().active_jobs(), 0);
+    }
+
+    #[tokio::test]
+    async fn workers_survive_panicking_jobs() {
+        let pool = PreparationPool::new(1, PacketPreparationMetrics::new());
+
+        let failed = pool.spawn(|| -> u32 { panic!("faulty job") });
+        assert!(failed.await.is_err());
+
+        // the only worker is still there to execute the following job
+        assert_eq!(pool.spawn(|| 42).await.unwrap(), 42);
+        assert_eq!(pool.metrics().queue_depth(), 0);
+        assert_eq!(pool.metrics().active_jobs(), 0);
+    }
+
+    #[tokio::test]
+    async fn batched_results_preserve_item_order() {
+        let workers = 4;
+        let pool = PreparationPool::new(workers, PacketPreparationMetrics::new());
+
+        for total in [1, 3, workers, workers + 1, 1000] {
+            let mut batches = 0;
+            let jobs = pool.spawn_batches(
+                (0..total).collect(),
+                || {
+                    batches += 1;
+                    // make the batches finish in a different order than they were scheduled in
+                    Duration::from_millis(10 * (workers - batches) as u64)
+                },
+                |delay, item: usize| {
+                    std::thread::sleep(*delay);
+                    *delay = Duration::ZERO;
+                    item * 2
+                },
+            );
+            assert!(batches > 0 && batches <= workers);
+
+            let results = jobs.join().await.unwrap();
+            assert_eq!(results, (0..total).map(|i| i * 2).collect::<Vec<_>>());
+        }
+    }
+
+    #[tokio::test]
+    async fn batched_jobs_fail_if_any_batch_panics() {
+        let pool = PreparationPool::new(2, PacketPreparationMetrics::new());
+        let jobs = pool.spawn_batches(
+            (0..10).collect(),
+            || (),
+            |_, item: usize| {
+                if item == 7 {
+                    panic!("faulty item")
+                }
+                item
+            },
+        );
+
+        assert!(jobs.join().await.is_err());
+    }
+}
@@ -2,15 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use self::sending_delay_controller::SendingDelayController;
+use crate::client::gateway_failover::SelfAddress;
 use crate::client::mix_traffic::BatchMixMessageSender;
 use crate::client::real_messages_control::acknowledgement_control::SentPacketNotificationSender;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::client::real_messages_control::preparation_pool::{
+    PrecomputedCoverPackets, PreparationPool,
+};
 use crate::client::topology_control::TopologyAccessor;
 use crate::client::transmission_buffer::TransmissionBuffer;
 use futures::task::{Context, Poll};
 use futures::{Future, Stream, StreamExt};
 use log::*;
 use nym_sphinx::acknowledgements::AckKey;
-use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::chunking::fragment::FragmentIdentifier;
 use nym_sphinx::cover::generate_loop_cover_packet;
 use nym_sphinx::forwarding::packet::MixPacket;
@@ -39,7 +43,7 @@ pub(crate) struct Config {
     ack_key: Arc<AckKey>,
 
     /// Represents full address of this client.
-    our_full_destination: Recipient,
+    our_full_destination: SelfAddress,
 
     /// Average delay an acknowledgement packet is going to get delay at a single mixnode.
     average_ack_delay: Duration,
@@ -61,7 +65,7 @@ pub(crate) struct Config {
 impl Config {
     pub(crate) fn new(
         ack_key: Arc<AckKey>,
-        our_full_destination: Recipient,
+        our_full_destination: SelfAddress,
         average_ack_delay: Duration,
         average_packet_delay: Duration,
         average_message_sending_delay: Duration,
@@ -130,6 +134,14 @@ where
 
     /// Report queue lengths so that upstream can backoff sending data, and keep connections open.
     lane_queue_lengths: LaneQueueLengths,
+
+    /// Pool of workers used for constructing the packets outside of the async runtime.
+    #[cfg(not(target_arch = "wasm32"))]
+    preparation_pool: Option<PreparationPool>,
+
+    /// Loop cover packets constructed ahead of time, used whenever there are no real packets to send.
+    #[cfg(not(target_arch = "wasm32"))]
+    precomputed_cover: Option<PrecomputedCoverPackets>,
 }
 
 #[derive(Debug)]
@@ -150,7 +162,7 @@ impl From<PreparedFragment> for RealMessage {
 
 impl RealMessage {
     pub(crate) fn packet_size(&self) -> usize {
-        self.mix_packet.sphinx_packet().len()
+        self.mix_packet.packet().len()
     }
 
     pub(crate) fn new(mix_packet: MixPacket, fragment_id: FragmentIdentifier) -> Self {
@@ -201,7 +213,33 @@ where
             transmission_buffer: TransmissionBuffer::new(),
             client_connection_rx,
             lane_queue_lengths,
+            #[cfg(not(target_arch = "wasm32"))]
+            preparation_pool: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            precomputed_cover: None,
+        }
+    }
+
+    /// Makes the loop cover packets get constructed by the provided pool ahead of the poisson
+    /// schedule, keeping up to `precomputed_cover_packets` of them ready to be sent.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn with_preparation_pool(
+        mut self,
+        preparation_pool: PreparationPool,
+        precomputed_cover_packets: usize,
+    ) -> Self {
+        if precomputed_cover_packets > 0 {
+            self.precomputed_cover = Some(PrecomputedCoverPackets::new(
+                preparation_pool.clone(),
+                precomputed_cover_packets,
+                Arc::clone(&self.config.ack_key),
+                self.config.average_ack_delay,
+                self.config.average_packet_delay,
+                self.config.cover_packet_size,
+            ));
         }
+        self.preparation_pool = Some(preparation_pool);
+        self
     }
 
     fn sent_notify(&self, frag_id: FragmentIdentifier) {
@@ -221,11 +259,11 @@ where
                 // to wait a really tiny bit before actually obtaining the permit hence messing with our
                 // poisson delay, but is it really a problem?
                 let topology_permit = self.topology_access.get_read_permit().await;
+                let our_full_destination = self.config.our_full_destination.current();
                 // the ack is sent back to ourselves (and then ignored)
-                let topology_ref = match topology_permit.try_get_valid_topology_ref(
-                    &self.config.our_full_destination,
-                    Some(&self.config.our_full_destination),
-                ) {
+                let topology_ref = match topology_permit
+                    .try_get_valid_topology_ref(&our_full_destination, Some(&our_full_destination))
+                {
                     Ok(topology) => topology,
                     Err(err) => {
                         warn!("We're not going to send any loop cover message this time, as the current topology seem to be invalid - {err}");
@@ -233,12 +271,26 @@ where
                     }
                 };
 
-                (
-                    generate_loop_cover_packet(
+                #[cfg(not(target_arch = "wasm32"))]
+                let precomputed = self.precomputed_cover.as_mut().and_then(|cover| {
+                    cover.next_packet(
+                        topology_ref,
+                        topology_permit.generation(),
+                        &our_full_destination,
+                    )
+                });
+
+                #[cfg(target_arch = "wasm32")]
+                let precomputed = None;
+
+                // if we ran out of the precomputed packets, we have no choice but to construct one now
+                let cover_packet = match precomputed {
+                    Some(cover_packet) => cover_packet,
+                    None => generate_loop_cover_packet(
                         &mut self.rng,
                         topology_ref,
                         &self.config.ack_key,
-                        &self.config.our_full_destination,
+                        &our_full_destination,
                         self.config.average_ack_delay,
                         self.config.average_packet_delay,
                         self.config.cover_packet_size,
@@ -246,8 +298,9 @@ where
                     .expect(
                         "Somehow failed to generate a loop cover message with a valid topology",
                     ),
-                    None,
-                )
+                };
+
+                (cover_packet, None)
             }
             StreamMessage::Real(real_message) => {
                 (real_message.mix_packet, Some(real_message.fragment_id))
@@ -483,6 +536,23 @@ where
             log::debug!("{status_str}");
         }
 
+        if let Some(preparation_pool) = &self.preparation_pool {
+            let metrics = preparation_pool.metrics();
+            let queued = metrics.queue_depth();
+            let preparation_status = format!(
+                "Packet preparation: {queued} queued jobs, {} in progress ({} workers), {} precomputed cover packets, {} packets prepared in total",
+                metrics.active_jobs(),
+                preparation_pool.workers(),
+                metrics.precomputed_cover_packets(),
+                metrics.prepared_packets(),
+            );
+            if queued > preparation_pool.workers() {
+                log::info!("{preparation_status}");
+            } else {
+                log::debug!("{preparation_status}");
+            }
+        }
+
         // Send status message to whoever is listening (possibly UI)
         if mult == self.sending_delay_controller.max_multiplier() {
             shutdown.send_status_msg(Box::new(ClientCoreStatusMessage::GatewayIsVerySlow));
@@ -1,8 +1,12 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::gateway_failover::GatewayChangeNoticeGuard;
+use crate::client::peer_compression::PeerCompressionSupport;
+use crate::client::received_buffer::storage::ReceivedMessagesStore;
 use crate::client::replies::reply_controller::ReplyControllerSender;
 use crate::client::replies::reply_storage::SentReplyKeys;
+use crate::client::statistics::ClientStatistics;
 use crate::spawn_future;
 use futures::channel::mpsc;
 use futures::lock::Mutex;
@@ -21,6 +25,8 @@ use nym_sphinx::receiver::{MessageReceiver, MessageRecoveryError, ReconstructedM
 use std::collections::HashSet;
 use std::sync::Arc;
 
+pub mod storage;
+
 // Buffer Requests to say "hey, send any reconstructed messages to this channel"
 // or to say "hey, I'm going offline, don't send anything more to me. Just buffer them instead"
 pub type ReceivedBufferRequestSender = mpsc::UnboundedSender<ReceivedBufferMessage>;
@@ -43,6 +49,14 @@ struct ReceivedMessagesBufferInner {
     // but perhaps it should be changed to include timestamps of when the message was reconstructed
     // and every now and then remove ids older than X
     recently_reconstructed: HashSet<i32>,
+
+    // if set, reconstructed messages are persisted until the application acknowledges them
+    received_store: Option<Box<dyn ReceivedMessagesStore>>,
+
+    // compression support advertised by the senders of the reconstructed messages
+    peer_compression: PeerCompressionSupport,
+
+    statistics: ClientStatistics,
 }
 
 impl ReceivedMessagesBufferInner {
@@ -51,6 +65,7 @@ impl ReceivedMessagesBufferInner {
             trace!("The message was a loop cover message! Skipping it");
             return None;
         }
+        self.statistics.record_received_packet();
 
         let fragment = match self.message_receiver.recover_fragment(fragment_data) {
             Err(err) => {
@@ -60,8 +75,10 @@ impl ReceivedMessagesBufferInner {
             Ok(frag) => frag,
         };
 
-        if self.recently_reconstructed.contains(&fragment.id()) {
-            debug!("Received a chunk of already re-assembled message ({:?})! It probably got here because the ack got lost", fragment.id());
+        // parity fragments are checked against the set they are protecting
+        let set_id = fragment.data_set_id();
+        if self.recently_reconstructed.contains(&set_id) {
+            debug!("Received a chunk of already re-assembled message ({set_id:?})! It probably got here because the ack got lost or it was no longer needed parity data");
             return None;
         }
 
@@ -84,13 +101,15 @@ impl ReceivedMessagesBufferInner {
                 ),
             },
             Ok(reconstruction_result) => match reconstruction_result {
-                Some((reconstructed_message, used_sets)) => {
+                Some((reconstructed_message, supported_compression, used_sets)) => {
                     for set_id in used_sets {
                         if !self.recently_reconstructed.insert(set_id) {
                             // or perhaps we should even panic at this point?
                             error!("Reconstructed another message containing already used set id!")
                         }
                     }
+                    self.peer_compression
+                        .record(&reconstructed_message, supported_compression);
                     Some(reconstructed_message)
                 }
                 None => None,
@@ -134,6 +153,8 @@ struct ReceivedMessagesBuffer {
     inner: Arc<Mutex<ReceivedMessagesBufferInner>>,
     reply_key_storage: SentReplyKeys,
     reply_controller_sender: ReplyControllerSender,
+    gateway_change_guard: GatewayChangeNoticeGuard,
+    statistics: ClientStatistics,
 }
 
 impl ReceivedMessagesBuffer {
@@ -141,6 +162,9 @@ impl ReceivedMessagesBuffer {
         local_encryption_keypair: Arc<encryption::KeyPair>,
         reply_key_storage: SentReplyKeys,
         reply_controller_sender: ReplyControllerSender,
+        received_store: Option<Box<dyn ReceivedMessagesStore>>,
+        peer_compression: PeerCompressionSupport,
+        statistics: ClientStatistics,
     ) -> Self {
         ReceivedMessagesBuffer {
             inner: Arc::new(Mutex::new(ReceivedMessagesBufferInner {
@@ -149,9 +173,14 @@ impl ReceivedMessagesBuffer {
                 message_receiver: MessageReceiver::new(),
                 message_sender: None,
                 recently_reconstructed: HashSet::new(),
+                received_store,
+                peer_compression,
+                statistics: statistics.clone(),
             })),
             reply_key_storage,
             reply_controller_sender,
+            gateway_change_guard: GatewayChangeNoticeGuard::default(),
+            statistics,
         }
     }
 
@@ -174,8 +203,19 @@ impl ReceivedMessagesBuffer {
         }
 
         // while we're at it, also empty the buffer if we happened to receive anything while
-        // no sender was connected
-        let stored_messages = std::mem::take(&mut guard.messages);
+        // no sender was connected. If we're using persistent storage, this also includes
+        // all messages that the previous receivers haven't acknowledged
+        let mut stored_messages = match &guard.received_store {
+            Some(store) => match store.unacknowledged_messages().await {
+                Ok(unacknowledged) => unacknowledged,
+                Err(err) => {
+                    error!("failed to retrieve unacknowledged messages - {err}");
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        stored_messages.append(&mut guard.messages);
         if !stored_messages.is_empty() {
             if let Err(err) = sender.unbounded_send(stored_messages) {
                 error!(
@@ -186,13 +226,36 @@ impl ReceivedMessagesBuffer {
                 // the returned error has two fields: err: SendError and val: T,
                 // where val is the value that was failed to get sent;
                 // it's returned by the `into_inner` call
-                guard.messages = err.into_inner();
+                // (the persisted messages are going to be retrieved again regardless)
+                guard.messages = err
+                    .into_inner()
+                    .into_iter()
+                    .filter(|msg| msg.delivery_id.is_none())
+                    .collect();
                 return;
             }
         }
         guard.message_sender = Some(sender);
     }
 
+    async fn acknowledge(&mut self, ids: Vec<u64>) {
+        let mut guard = self.inner.lock().await;
+        match guard.received_store.as_mut() {
+            Some(store) => {
+                if let Err(err) = store.remove_messages(&ids).await {
+                    error!(
+                        "failed to remove {} acknowledged messages - {err}",
+                        ids.len()
+                    )
+                }
+            }
+            None => debug!(
+                "ignoring acknowledgement of {} messages as received messages are not persisted",
+                ids.len()
+            ),
+        }
+    }
+
     fn handle_reconstructed_plain_messages(
         &mut self,
         msgs: Vec<PlainMessage>,
@@ -237,6 +300,8 @@ impl ReceivedMessagesBuffer {
                 }
             };
 
+            self.statistics
+                .record_reply_surbs_received(msg.sender_tag, reply_surbs.len());
             self.reply_controller_sender.send_additional_surbs(
                 msg.sender_tag,
                 reply_surbs,
@@ -259,6 +324,16 @@ impl ReceivedMessagesBuffer {
                     self.reply_controller_sender
                         .send_additional_surbs_request(*recipient, amount);
                 }
+                ReplyMessageContent::GatewayChange { notice } => {
+                    let recipient = *notice.recipient();
+                    if let Err(err) = self.gateway_change_guard.check(&notice) {
+                        warn!("rejected gateway change notice from {recipient} - {err}");
+                        continue;
+                    }
+                    debug!("received gateway change notice from {recipient}");
+                    self.reply_controller_sender
+                        .send_peer_gateway_change(recipient);
+                }
             }
         }
         reconstructed
@@ -287,22 +362,53 @@ impl ReceivedMessagesBuffer {
         reconstructed_messages
             .append(&mut self.handle_reconstructed_reply_messages(reply_messages));
 
+        for msg in &reconstructed_messages {
+            self.statistics
+                .record_received_message(msg.sender_tag, msg.message.len());
+        }
+
         let mut inner_guard = self.inner.lock().await;
         debug!(
             "Adding {:?} new messages to the buffer!",
             reconstructed_messages.len()
         );
 
+        if let Some(store) = inner_guard
+            .received_store
+            .as_mut()
+            .filter(|_| !reconstructed_messages.is_empty())
+        {
+            match store.store_messages(&reconstructed_messages).await {
+                Ok(ids) => {
+                    for (msg, id) in reconstructed_messages.iter_mut().zip(ids) {
+                        msg.delivery_id = Some(id)
+                    }
+                }
+                Err(err) => {
+                    error!("failed to persist received messages - {err}. They will only be kept in memory")
+                }
+            }
+        }
+
         if let Some(sender) = &inner_guard.message_sender {
             trace!("Sending reconstructed messages to announced sender");
             if let Err(err) = sender.unbounded_send(reconstructed_messages) {
                 warn!("The reconstructed message receiver went offline without explicit notification (relevant error: - {err})");
                 inner_guard.message_sender = None;
-                inner_guard.messages.extend(err.into_inner());
+                inner_guard.messages.extend(
+                    err.into_inner()
+                        .into_iter()
+                        .filter(|msg| msg.delivery_id.is_none()),
+                );
             }
         } else {
             trace!("No sender available - buffering reconstructed messages");
-            inner_guard.messages.extend(reconstructed_messages)
+            // persisted messages will be retrieved from the storage once a receiver connects
+            inner_guard.messages.extend(
+                reconstructed_messages
+                    .into_iter()
+                    .filter(|msg| msg.delivery_id.is_none()),
+            )
         }
     }
 
@@ -370,6 +476,10 @@ pub enum ReceivedBufferMessage {
 
     // Explicit signal that Receiver connection will no longer accept messages
     ReceiverDisconnect,
+
+    // Signals the messages with the provided delivery ids have been processed by the application
+    // and do not need to be persisted anymore
+    Acknowledge(Vec<u64>),
 }
 
 struct RequestReceiver {
@@ -396,6 +506,7 @@ impl RequestReceiver {
             ReceivedBufferMessage::ReceiverDisconnect => {
                 self.received_buffer.disconnect_sender().await
             }
+            ReceivedBufferMessage::Acknowledge(ids) => self.received_buffer.acknowledge(ids).await,
         }
     }
 
@@ -472,11 +583,17 @@ impl ReceivedMessagesBufferController {
         mixnet_packet_receiver: MixnetMessageReceiver,
         reply_key_storage: SentReplyKeys,
         reply_controller_sender: ReplyControllerSender,
+        received_store: Option<Box<dyn ReceivedMessagesStore>>,
+        peer_compression: PeerCompressionSupport,
+        statistics: ClientStatistics,
     ) -> Self {
         let received_buffer = ReceivedMessagesBuffer::new(
             local_encryption_keypair,
             reply_key_storage,
             reply_controller_sender,
+            received_store,
+            peer_compression,
+            statistics,
         );
 
         ReceivedMessagesBufferController {
@@ -503,3 +620,201 @@ impl ReceivedMessagesBufferController {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::received_buffer::storage::ReceivedStorageError;
+    use crate::client::replies::reply_controller::requests::new_control_channels;
+    use async_trait::async_trait;
+    use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+    use rand::rngs::OsRng;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    type StoredMessages = BTreeMap<u64, (Vec<u8>, Option<AnonymousSenderTag>)>;
+
+    // simple in-memory stand-in for the persistent storage that is shared between "restarts"
+    #[derive(Clone, Default)]
+    struct MockStore {
+        messages: Arc<std::sync::Mutex<StoredMessages>>,
+        next_id: Arc<AtomicU64>,
+    }
+
+    impl MockStore {
+        fn stored(&self) -> Vec<Vec<u8>> {
+            let messages = self.messages.lock().unwrap();
+            messages.values().map(|(msg, _)| msg.clone()).collect()
+        }
+    }
+
+    #[async_trait]
+    impl ReceivedMessagesStore for MockStore {
+        async fn store_messages(
+            &mut self,
+            messages: &[ReconstructedMessage],
+        ) -> Result<Vec<u64>, ReceivedStorageError> {
+            let mut stored = self.messages.lock().unwrap();
+            Ok(messages
+                .iter()
+                .map(|msg| {
+                    let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+                    stored.insert(id, (msg.message.clone(), msg.sender_tag));
+                    id
+                })
+                .collect())
+        }
+
+        async fn remove_messages(&mut self, ids: &[u64]) -> Result<(), ReceivedStorageError> {
+            let mut stored = self.messages.lock().unwrap();
+            for id in ids {
+                stored.remove(id);
+            }
+            Ok(())
+        }
+
+        async fn unacknowledged_messages(
+            &self,
+        ) -> Result<Vec<ReconstructedMessage>, ReceivedStorageError> {
+            let stored = self.messages.lock().unwrap();
+            Ok(stored
+                .iter()
+                .map(|(id, (message, sender_tag))| ReconstructedMessage {
+                    message: message.clone(),
+                    sender_tag: *sender_tag,
+                    delivery_id: Some(*id),
+                })
+                .collect())
+        }
+    }
+
+    fn new_buffer(store: Option<MockStore>) -> ReceivedMessagesBuffer {
+        let (reply_controller_sender, _) = new_control_channels();
+        ReceivedMessagesBuffer::new(
+            Arc::new(encryption::KeyPair::new(&mut OsRng)),
+            SentReplyKeys::new(),
+            reply_controller_sender,
+            store.map(|store| Box::new(store) as Box<dyn ReceivedMessagesStore>),
+            PeerCompressionSupport::default(),
+            ClientStatistics::new(),
+        )
+    }
+
+    async fn receive(buffer: &mut ReceivedMessagesBuffer, contents: &[&[u8]]) {
+        let messages = contents
+            .iter()
+            .map(|content| NymMessage::new_plain(content.to_vec()))
+            .collect();
+        buffer.handle_reconstructed_messages(messages).await
+    }
+
+    async fn connect(
+        buffer: &mut ReceivedMessagesBuffer,
+    ) -> mpsc::UnboundedReceiver<Vec<ReconstructedMessage>> {
+        let (sender, receiver) = mpsc::unbounded();
+        buffer.connect_sender(sender).await;
+        receiver
+    }
+
+    fn delivered(
+        receiver: &mut mpsc::UnboundedReceiver<Vec<ReconstructedMessage>>,
+    ) -> Vec<ReconstructedMessage> {
+        let mut delivered = Vec::new();
+        while let Ok(Some(mut batch)) = receiver.try_next() {
+            delivered.append(&mut batch)
+        }
+        delivered
+    }
+
+    fn contents(messages: &[ReconstructedMessage]) -> Vec<&[u8]> {
+        messages.iter().map(|msg| msg.message.as_slice()).collect()
+    }
+
+    #[tokio::test]
+    async fn messages_are_buffered_in_memory_without_persistent_storage() {
+        let mut buffer = new_buffer(None);
+        receive(&mut buffer, &[b"first", b"second"]).await;
+
+        let mut receiver = connect(&mut buffer).await;
+        let messages = delivered(&mut receiver);
+        assert_eq!(contents(&messages), vec![&b"first"[..], &b"second"[..]]);
+        assert!(messages.iter().all(|msg| msg.delivery_id.is_none()));
+
+        // with no storage, messages are delivered exactly once
+        buffer.disconnect_sender().await;
+        let mut receiver = connect(&mut buffer).await;
+        assert!(delivered(&mut receiver).is_empty());
+    }
+
+    #[tokio::test]
+    async fn persisted_messages_are_delivered_with_delivery_ids() {
+        let store = MockStore::default();
+        let mut buffer = new_buffer(Some(store.clone()));
+
+        let mut receiver = connect(&mut buffer).await;
+        receive(&mut buffer, &[b"first", b"second"]).await;
+
+        let messages = delivered(&mut receiver);
+        assert_eq!(contents(&messages), vec![&b"first"[..], &b"second"[..]]);
+        assert!(messages.iter().all(|msg| msg.delivery_id.is_some()));
+        assert_eq!(store.stored().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_messages_are_redelivered_to_the_next_receiver() {
+        let store = MockStore::default();
+        let mut buffer = new_buffer(Some(store.clone()));
+
+        let mut receiver = connect(&mut buffer).await;
+        receive(&mut buffer, &[b"first", b"second"]).await;
+        assert_eq!(delivered(&mut receiver).len(), 2);
+        buffer.disconnect_sender().await;
+
+        // received while nobody was listening
+        receive(&mut buffer, &[b"third"]).await;
+
+        let mut receiver = connect(&mut buffer).await;
+        let messages = delivered(&mut receiver);
+        // everything is redelivered exactly once, in the order it was received in
+        assert_eq!(
+            contents(&messages),
+            vec![&b"first"[..], &b"second"[..], &b"third"[..]]
+        );
+    }
+
+    #[tokio::test]
+    async fn acknowledged_messages_are_not_redelivered() {
+        let store = MockStore::default();
+        let mut buffer = new_buffer(Some(store.clone()));
+
+        let mut receiver = connect(&mut buffer).await;
+        receive(&mut buffer, &[b"first", b"second", b"third"]).await;
+        let messages = delivered(&mut receiver);
+
+        let acked = vec![
+            messages[0].delivery_id.unwrap(),
+            messages[2].delivery_id.unwrap(),
+        ];
+        buffer.acknowledge(acked).await;
+        assert_eq!(store.stored(), vec![b"second".to_vec()]);
+
+        // after a "restart", only the unacknowledged message comes back
+        drop(receiver);
+        let mut restarted = new_buffer(Some(store.clone()));
+        let mut receiver = connect(&mut restarted).await;
+        let messages = delivered(&mut receiver);
+        assert_eq!(contents(&messages), vec![&b"second"[..]]);
+    }
+
+    #[tokio::test]
+    async fn messages_sent_to_a_dropped_receiver_are_not_lost() {
+        let mut buffer = new_buffer(None);
+
+        let receiver = connect(&mut buffer).await;
+        drop(receiver);
+        receive(&mut buffer, &[b"first"]).await;
+
+        let mut receiver = connect(&mut buffer).await;
+        assert_eq!(contents(&delivered(&mut receiver)), vec![&b"first"[..]]);
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::client::received_buffer::storage::{ReceivedMessagesStore, ReceivedStorageError};
+use async_trait::async_trait;
+use nym_sphinx::anonymous_replies::requests::{AnonymousSenderTag, SENDER_TAG_SIZE};
+use nym_sphinx::receiver::ReconstructedMessage;
+use wasm_bindgen::JsValue;
+use wasm_utils::indexed_db::Database;
+
+const DB_VERSION: u32 = 1;
+const MESSAGES_STORE: &str = "received_messages";
+const METADATA_STORE: &str = "metadata";
+const NEXT_ID_KEY: &str = "next_id";
+
+fn js_error(err: JsValue) -> ReceivedStorageError {
+    ReceivedStorageError::new(format!("IndexedDB failure: {err:?}"))
+}
+
+// 1 | 0 indicating sender_tag || Option<sender_tag> || message
+fn encode_message(message: &ReconstructedMessage) -> Vec<u8> {
+    match message.sender_tag {
+        Some(sender_tag) => std::iter::once(true as u8)
+            .chain(sender_tag.to_bytes())
+            .chain(message.message.iter().copied())
+            .collect(),
+        None => std::iter::once(false as u8)
+            .chain(message.message.iter().copied())
+            .collect(),
+    }
+}
+
+fn decode_message(id: u64, raw: &[u8]) -> Result<ReconstructedMessage, ReceivedStorageError> {
+    let (sender_tag, message) = match raw.first() {
+        Some(0) => (None, &raw[1..]),
+        Some(1) if raw.len() > SENDER_TAG_SIZE => {
+            let mut tag = [0u8; SENDER_TAG_SIZE];
+            tag.copy_from_slice(&raw[1..1 + SENDER_TAG_SIZE]);
+            (
+                Some(AnonymousSenderTag::from_bytes(tag)),
+                &raw[1 + SENDER_TAG_SIZE..],
+            )
+        }
+        _ => {
+            return Err(ReceivedStorageError::new(format!(
+                "stored message {id} is malformed"
+            )))
+        }
+    };
+
+    Ok(ReconstructedMessage {
+        message: message.to_vec(),
+        sender_tag,
+        delivery_id: Some(id),
+    })
+}
+
+/// Keeps unacknowledged messages in the IndexedDB of the browser.
+pub struct Backend {
+    db: Database,
+    next_id: u64,
+}
+
+impl Backend {
+    pub async fn init(database_name: &str) -> Result<Self, ReceivedStorageError> {
+        let db = Database::open(database_name, DB_VERSION, &[MESSAGES_STORE, METADATA_STORE])
+            .await
+            .map_err(js_error)?;
+
+        // ids are never reused, even if all messages got acknowledged in the meantime
+        let next_id = match db
+            .get(METADATA_STORE, &JsValue::from_str(NEXT_ID_KEY))
+            .await
+            .map_err(js_error)?
+        {
+            Some(raw) => {
+                let bytes = raw.try_into().map_err(|_| {
+                    ReceivedStorageError::new("the stored next message id is malformed")
+                })?;
+                u64::from_be_bytes(bytes)
+            }
+            None => 1,
+        };
+
+        Ok(Backend { db, next_id })
+    }
+}
+
+#[async_trait(?Send)]
+impl ReceivedMessagesStore for Backend {
+    async fn store_messages(
+        &mut self,
+        messages: &[ReconstructedMessage],
+    ) -> Result<Vec<u64>, ReceivedStorageError> {
+        let first_id = self.next_id;
+        let next_id = first_id + messages.len() as u64;
+
+        // bump the counter first so that a failure in the middle would not result in id reuse
+        self.db
+            .put(
+                METADATA_STORE,
+                &JsValue::from_str(NEXT_ID_KEY),
+                &next_id.to_be_bytes(),
+            )
+            .await
+            .map_err(js_error)?;
+        self.next_id = next_id;
+
+        let entries = messages
+            .iter()
+            .zip(first_id..)
+            .map(|(message, id)| (JsValue::from(id as f64), encode_message(message)))
+            .collect::<Vec<_>>();
+        self.db
+            .put_all(MESSAGES_STORE, &entries)
+            .await
+            .map_err(js_error)?;
+
+        Ok((first_id..next_id).collect())
+    }
+
+    async fn remove_messages(&mut self, ids: &[u64]) -> Result<(), ReceivedStorageError> {
+        let keys = ids
+            .iter()
+            .map(|id| JsValue::from(*id as f64))
+            .collect::<Vec<_>>();
+        self.db
+            .delete_all(MESSAGES_STORE, &keys)
+            .await
+            .map_err(js_error)
+    }
+
+    async fn unacknowledged_messages(
+        &self,
+    ) -> Result<Vec<ReconstructedMessage>, ReceivedStorageError> {
+        self.db
+            .get_all(MESSAGES_STORE)
+            .await
+            .map_err(js_error)?
+            .into_iter()
+            .map(|(key, raw)| {
+                let id = key.as_f64().ok_or_else(|| {
+                    ReceivedStorageError::new(format!("invalid stored message key: {key:?}"))
+                })?;
+                decode_message(id as u64, &raw)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::client::received_buffer::storage::{ReceivedMessagesStore, ReceivedStorageError};
+use async_trait::async_trait;
+use log::{error, info};
+use nym_sphinx::anonymous_replies::requests::{AnonymousSenderTag, SENDER_TAG_SIZE};
+use nym_sphinx::receiver::ReconstructedMessage;
+use sqlx::ConnectOptions;
+use std::path::Path;
+
+impl From<sqlx::Error> for ReceivedStorageError {
+    fn from(err: sqlx::Error) -> Self {
+        ReceivedStorageError::new(format!("failed to run the SQL query: {err}"))
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredReceivedMessage {
+    id: i64,
+    sender_tag: Option<Vec<u8>>,
+    content: Vec<u8>,
+}
+
+impl TryFrom<StoredReceivedMessage> for ReconstructedMessage {
+    type Error = ReceivedStorageError;
+
+    fn try_from(value: StoredReceivedMessage) -> Result<Self, Self::Error> {
+        let sender_tag = match value.sender_tag {
+            None => None,
+            Some(raw) => {
+                let tag_len = raw.len();
+                let Ok(tag) = <[u8; SENDER_TAG_SIZE]>::try_from(raw) else {
+                    return Err(ReceivedStorageError::new(format!(
+                        "the sender tag of message {} has length of {tag_len} while {SENDER_TAG_SIZE} was expected",
+                        value.id
+                    )));
+                };
+                Some(AnonymousSenderTag::from_bytes(tag))
+            }
+        };
+
+        Ok(ReconstructedMessage {
+            message: value.content,
+            sender_tag,
+            delivery_id: Some(value.id as u64),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Backend {
+    connection_pool: sqlx::SqlitePool,
+}
+
+impl Backend {
+    pub async fn init<P: AsRef<Path>>(database_path: P) -> Result<Self, ReceivedStorageError> {
+        // ensure the whole directory structure exists
+        if let Some(parent_dir) = database_path.as_ref().parent() {
+            std::fs::create_dir_all(parent_dir).map_err(|err| {
+                ReceivedStorageError::new(format!(
+                    "unable to create the directory for the database: {err}"
+                ))
+            })?;
+        }
+
+        let mut opts = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(database_path)
+            .create_if_missing(true);
+
+        opts.disable_statement_logging();
+
+        let connection_pool = match sqlx::SqlitePool::connect_with(opts).await {
+            Ok(pool) => pool,
+            Err(err) => {
+                error!("Failed to connect to SQLx database: {err}");
+                return Err(err.into());
+            }
+        };
+
+        if let Err(err) = sqlx::migrate!("./fs_received_migrations")
+            .run(&connection_pool)
+            .await
+        {
+            error!("Failed to initialize SQLx database: {err}");
+            return Err(ReceivedStorageError::new(format!(
+                "failed to perform sqlx migration: {err}"
+            )));
+        }
+
+        info!("Database migration finished!");
+        Ok(Backend { connection_pool })
+    }
+}
+
+#[async_trait]
+impl ReceivedMessagesStore for Backend {
+    async fn store_messages(
+        &mut self,
+        messages: &[ReconstructedMessage],
+    ) -> Result<Vec<u64>, ReceivedStorageError> {
+        let mut tx = self.connection_pool.begin().await?;
+        let mut ids = Vec::with_capacity(messages.len());
+        for message in messages {
+            let id = sqlx::query("INSERT INTO received_message(sender_tag, content) VALUES (?, ?)")
+                .bind(message.sender_tag.map(|tag| tag.to_bytes().to_vec()))
+                .bind(&message.message)
+                .execute(&mut tx)
+                .await?
+                .last_insert_rowid();
+            ids.push(id as u64);
+        }
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    async fn remove_messages(&mut self, ids: &[u64]) -> Result<(), ReceivedStorageError> {
+        let mut tx = self.connection_pool.begin().await?;
+        for id in ids {
+            sqlx::query("DELETE FROM received_message WHERE id = ?")
+                .bind(*id as i64)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn unacknowledged_messages(
+        &self,
+    ) -> Result<Vec<ReconstructedMessage>, ReceivedStorageError> {
+        sqlx::query_as::<_, StoredReceivedMessage>(
+            "SELECT id, sender_tag, content FROM received_message ORDER BY id",
+        )
+        .fetch_all(&self.connection_pool)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: &[u8], sender_tag: Option<AnonymousSenderTag>) -> ReconstructedMessage {
+        ReconstructedMessage {
+            message: content.to_vec(),
+            sender_tag,
+            delivery_id: None,
+        }
+    }
+
+    fn contents(messages: &[ReconstructedMessage]) -> Vec<&[u8]> {
+        messages.iter().map(|msg| msg.message.as_slice()).collect()
+    }
+
+    #[tokio::test]
+    async fn stored_messages_are_retrieved_in_order_with_their_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut backend = Backend::init(dir.path().join("received.sqlite"))
+            .await
+            .unwrap();
+
+        let tag = AnonymousSenderTag::from_bytes([42u8; SENDER_TAG_SIZE]);
+        let ids = backend
+            .store_messages(&[message(b"first", None), message(b"second", Some(tag))])
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 2);
+
+        let stored = backend.unacknowledged_messages().await.unwrap();
+        assert_eq!(contents(&stored), vec![&b"first"[..], &b"second"[..]]);
+        assert_eq!(
+            stored.iter().map(|msg| msg.delivery_id).collect::<Vec<_>>(),
+            ids.iter().copied().map(Some).collect::<Vec<_>>()
+        );
+        assert_eq!(stored[0].sender_tag, None);
+        assert_eq!(stored[1].sender_tag, Some(tag));
+    }
+
+    #[tokio::test]
+    async fn acknowledged_messages_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut backend = Backend::init(dir.path().join("received.sqlite"))
+            .await
+            .unwrap();
+
+        let ids = backend
+            .store_messages(&[
+                message(b"first", None),
+                message(b"second", None),
+                message(b"third", None),
+            ])
+            .await
+            .unwrap();
+
+        backend.remove_messages(&[ids[0], ids[2]]).await.unwrap();
+        let stored = backend.unacknowledged_messages().await.unwrap();
+        assert_eq!(contents(&stored), vec![&b"second"[..]]);
+
+        // acknowledging unknown (or already acknowledged) messages is not an error
+        backend.remove_messages(&[ids[0], 12345]).await.unwrap();
+        assert_eq!(backend.unacknowledged_messages().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_messages_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("nested").join("received.sqlite");
+
+        let mut backend = Backend::init(&database_path).await.unwrap();
+        let ids = backend
+            .store_messages(&[message(b"first", None), message(b"second", None)])
+            .await
+            .unwrap();
+        backend.remove_messages(&ids[..1]).await.unwrap();
+        drop(backend);
+
+        let mut backend = Backend::init(&database_path).await.unwrap();
+        let stored = backend.unacknowledged_messages().await.unwrap();
+        assert_eq!(contents(&stored), vec![&b"second"[..]]);
+        assert_eq!(stored[0].delivery_id, Some(ids[1]));
+
+        // ids are never reused, so a late acknowledgement can't remove a newer message
+        let new_ids = backend
+            .store_messages(&[message(b"third", None)])
+            .await
+            .unwrap();
+        assert!(new_ids[0] > ids[1]);
+    }
+
+    #[tokio::test]
+    async fn malformed_sender_tag_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Backend::init(dir.path().join("received.sqlite"))
+            .await
+            .unwrap();
+
+        sqlx::query("INSERT INTO received_message(sender_tag, content) VALUES (?, ?)")
+            .bind(vec![1u8, 2, 3])
+            .bind(b"content".to_vec())
+            .execute(&backend.connection_pool)
+            .await
+            .unwrap();
+
+        assert!(backend.unacknowledged_messages().await.is_err());
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use nym_sphinx::receiver::ReconstructedMessage;
+use thiserror::Error;
+
+#[cfg(target_arch = "wasm32")]
+pub mod browser_backend;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "fs-received-storage"))]
+pub mod fs_backend;
+
+#[derive(Debug, Error)]
+#[error("received messages storage failure: {details}")]
+pub struct ReceivedStorageError {
+    details: String,
+}
+
+impl ReceivedStorageError {
+    pub fn new<S: Into<String>>(details: S) -> Self {
+        ReceivedStorageError {
+            details: details.into(),
+        }
+    }
+}
+
+/// Persistent storage of reconstructed messages that have not yet been acknowledged
+/// by the application, so that they could survive client restarts.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait ReceivedMessagesStore: Send {
+    /// Persists the provided messages and returns ids assigned to each of them (in the same order).
+    async fn store_messages(
+        &mut self,
+        messages: &[ReconstructedMessage],
+    ) -> Result<Vec<u64>, ReceivedStorageError>;
+
+    /// Removes the messages that have been acknowledged by the application.
+    async fn remove_messages(&mut self, ids: &[u64]) -> Result<(), ReceivedStorageError>;
+
+    /// Retrieves all messages that are yet to be acknowledged, in the order they were received in.
+    async fn unacknowledged_messages(
+        &self,
+    ) -> Result<Vec<ReconstructedMessage>, ReceivedStorageError>;
+}
@@ -8,7 +8,9 @@ use futures::channel::oneshot;
 use futures::StreamExt;
 use log::{debug, error, info, trace, warn};
 use nym_sphinx::addressing::clients::Recipient;
-use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+use nym_sphinx::anonymous_replies::requests::{
+    AnonymousSenderTag, GatewayChangeNotice, ReplyMessage,
+};
 use nym_sphinx::anonymous_replies::ReplySurb;
 use nym_sphinx::chunking::fragment::{Fragment, FragmentIdentifier};
 use nym_task::connections::{ConnectionId, TransmissionLane};
@@ -213,9 +215,16 @@ where
         }
 
         trace!("handling reply to {:?}", recipient_tag);
-        let mut fragments = self.message_handler.split_reply_message(data);
-        let total_size = fragments.len();
-        trace!("This reply requires {:?} SURBs", total_size);
+        let mut fragments = self
+            .message_handler
+            .split_reply_message(&recipient_tag, data);
+        // every parity fragment needs its own reply SURB, exactly like the data ones, so they
+        // are all counted towards the queue we're going to request more SURBs for
+        let parity = fragments.iter().filter(|f| f.is_parity()).count();
+        trace!(
+            "This reply requires {} SURBs ({parity} of them for the parity fragments)",
+            fragments.len()
+        );
 
         let available_surbs = self
             .full_reply_storage
@@ -533,6 +542,55 @@ where
         }
     }
 
+    async fn handle_gateway_change_announcement(&mut self, notice: GatewayChangeNotice) {
+        let targets = self
+            .full_reply_storage
+            .surbs_storage_ref()
+            .as_raw_iter()
+            .map(|entry| *entry.key())
+            .collect::<Vec<_>>();
+
+        info!(
+            "announcing our new address to {} remote parties",
+            targets.len()
+        );
+        for target in targets {
+            let reply_surb = match self
+                .full_reply_storage
+                .surbs_storage_ref()
+                .get_reply_surb_ignoring_threshold(&target)
+                .and_then(|(reply_surb, _)| reply_surb)
+            {
+                Some(reply_surb) => reply_surb,
+                None => {
+                    warn!("we don't have any reply surbs left to announce our new address to {target}");
+                    continue;
+                }
+            };
+
+            let message = ReplyMessage::new_gateway_change_message(notice.clone());
+            if let Err(err) = self
+                .message_handler
+                .try_send_single_surb_message(target, message, reply_surb, false)
+                .await
+            {
+                let err =
+                    err.return_unused_surbs(self.full_reply_storage.surbs_storage_ref(), &target);
+                warn!("failed to announce our new address to {target} - {err}");
+            }
+        }
+    }
+
+    fn handle_peer_gateway_change(&mut self, new_address: Recipient) {
+        info!("{new_address} has moved to a different gateway");
+
+        // keep using the same sender tag so that the remote wouldn't treat us as a new party
+        self.full_reply_storage
+            .tags_storage_ref()
+            .inherit_for_new_address(&new_address);
+        self.message_handler.redirect_recipient(new_address);
+    }
+
     fn buffer_pending_ack(
         &mut self,
         recipient: AnonymousSenderTag,
@@ -678,6 +736,12 @@ where
             ReplyControllerMessage::AdditionalSurbsRequest { recipient, amount } => {
                 self.handle_surb_request(*recipient, amount).await
             }
+            ReplyControllerMessage::AnnounceGatewayChange { notice } => {
+                self.handle_gateway_change_announcement(*notice).await
+            }
+            ReplyControllerMessage::PeerGatewayChange { new_address } => {
+                self.handle_peer_gateway_change(*new_address)
+            }
         }
     }
 
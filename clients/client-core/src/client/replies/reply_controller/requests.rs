@@ -5,7 +5,7 @@ use crate::client::real_messages_control::acknowledgement_control::PendingAcknow
 use futures::channel::{mpsc, oneshot};
 use log::error;
 use nym_sphinx::addressing::clients::Recipient;
-use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+use nym_sphinx::anonymous_replies::requests::{AnonymousSenderTag, GatewayChangeNotice};
 use nym_sphinx::anonymous_replies::ReplySurb;
 use nym_task::connections::{ConnectionId, TransmissionLane};
 use std::sync::Weak;
@@ -79,6 +79,22 @@ impl ReplyControllerSender {
             .expect("ReplyControllerReceiver has died!")
     }
 
+    pub(crate) fn send_gateway_change_announcement(&self, notice: GatewayChangeNotice) {
+        self.0
+            .unbounded_send(ReplyControllerMessage::AnnounceGatewayChange {
+                notice: Box::new(notice),
+            })
+            .expect("ReplyControllerReceiver has died!")
+    }
+
+    pub(crate) fn send_peer_gateway_change(&self, new_address: Recipient) {
+        self.0
+            .unbounded_send(ReplyControllerMessage::PeerGatewayChange {
+                new_address: Box::new(new_address),
+            })
+            .expect("ReplyControllerReceiver has died!")
+    }
+
     pub async fn get_lane_queue_length(&self, connection_id: ConnectionId) -> usize {
         let (response_tx, response_rx) = oneshot::channel();
         self.0
@@ -151,4 +167,14 @@ pub(crate) enum ReplyControllerMessage {
         recipient: Box<Recipient>,
         amount: u32,
     },
+
+    // we have moved to a different gateway and have to let everyone holding our reply surbs know
+    AnnounceGatewayChange {
+        notice: Box<GatewayChangeNotice>,
+    },
+
+    // a remote party we have sent anonymous messages to has moved to a different gateway
+    PeerGatewayChange {
+        new_address: Box<Recipient>,
+    },
 }
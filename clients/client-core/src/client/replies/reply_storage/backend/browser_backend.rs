@@ -1,62 +1,183 @@
 // Copyright 2022 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::client::replies::reply_storage::backend::Empty;
+use crate::client::replies::reply_storage::backend::snapshot::{
+    restore_storage, ReplyDataSnapshot, StorageStatus, REPLY_DATA_KEY, STATUS_KEY,
+};
 use crate::client::replies::reply_storage::{CombinedReplyStorage, ReplyStorageBackend};
 use async_trait::async_trait;
-
 use std::path::PathBuf;
+use thiserror::Error;
+use time::OffsetDateTime;
+use wasm_bindgen::JsValue;
+use wasm_utils::indexed_db::Database;
+
+const DB_VERSION: u32 = 1;
+const REPLY_STORE: &str = "reply_storage";
 
-// well, right now we don't have the browser storage : (
-// so we keep everything in memory
+#[derive(Debug, Error)]
+#[error("IndexedDB failure: {details}")]
+pub struct StorageError {
+    details: String,
+}
+
+impl From<JsValue> for StorageError {
+    fn from(value: JsValue) -> Self {
+        StorageError {
+            details: format!("{value:?}"),
+        }
+    }
+}
+
+/// Keeps the reply-related data in the IndexedDB of the browser so that it would survive
+/// page reloads. If no database is used, everything is only kept in memory.
 #[derive(Debug)]
 pub struct Backend {
-    empty: Empty,
+    db: Option<Database>,
+    min_surb_threshold: usize,
+    max_surb_threshold: usize,
 }
 
 impl Backend {
     pub fn new(min_surb_threshold: usize, max_surb_threshold: usize) -> Self {
         Backend {
-            empty: Empty {
-                min_surb_threshold,
-                max_surb_threshold,
-            },
+            db: None,
+            min_surb_threshold,
+            max_surb_threshold,
         }
     }
+
+    pub async fn init(
+        database_name: &str,
+        min_surb_threshold: usize,
+        max_surb_threshold: usize,
+    ) -> Result<Self, StorageError> {
+        let db = Database::open(database_name, DB_VERSION, &[REPLY_STORE]).await?;
+
+        Ok(Backend {
+            db: Some(db),
+            min_surb_threshold,
+            max_surb_threshold,
+        })
+    }
+
+    fn db(&self) -> &Database {
+        self.db
+            .as_ref()
+            .expect("tried to get the database of an inactive backend")
+    }
+
+    async fn get_status(&self) -> Result<StorageStatus, StorageError> {
+        let raw = self
+            .db()
+            .get(REPLY_STORE, &JsValue::from_str(STATUS_KEY))
+            .await?;
+
+        Ok(raw
+            .and_then(|raw| StorageStatus::try_from_bytes(&raw).ok())
+            .unwrap_or_default())
+    }
+
+    async fn set_status(&self, status: StorageStatus) -> Result<(), StorageError> {
+        Ok(self
+            .db()
+            .put(
+                REPLY_STORE,
+                &JsValue::from_str(STATUS_KEY),
+                &status.to_bytes(),
+            )
+            .await?)
+    }
 }
 
-#[async_trait]
+#[async_trait(?Send)]
 impl ReplyStorageBackend for Backend {
-    type StorageError = <Empty as ReplyStorageBackend>::StorageError;
+    type StorageError = StorageError;
 
     async fn new(
         debug_config: &crate::config::DebugConfig,
-        _db_path: Option<PathBuf>,
+        db_path: Option<PathBuf>,
     ) -> Result<Self, Self::StorageError> {
-        Ok(Backend {
-            empty: Empty {
-                min_surb_threshold: debug_config.minimum_reply_surb_storage_threshold,
-                max_surb_threshold: debug_config.maximum_reply_surb_storage_threshold,
-            },
-        })
+        let min_surb_threshold = debug_config.minimum_reply_surb_storage_threshold;
+        let max_surb_threshold = debug_config.maximum_reply_surb_storage_threshold;
+
+        // there's no filesystem in the browser, so the 'path' is just the name of the database
+        match db_path {
+            Some(db_path) => {
+                Backend::init(
+                    &db_path.to_string_lossy(),
+                    min_surb_threshold,
+                    max_surb_threshold,
+                )
+                .await
+            }
+            None => Ok(Backend::new(min_surb_threshold, max_surb_threshold)),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.db.is_some()
+    }
+
+    async fn start_storage_session(&self) -> Result<(), Self::StorageError> {
+        let mut status = self.get_status().await?;
+        status.client_in_use = true;
+        self.set_status(status).await
     }
 
     async fn flush_surb_storage(
         &mut self,
         storage: &CombinedReplyStorage,
     ) -> Result<(), Self::StorageError> {
-        self.empty.flush_surb_storage(storage).await
+        // the entire data is written with a single request, so it's never going to be partially flushed
+        self.db()
+            .put(
+                REPLY_STORE,
+                &JsValue::from_str(REPLY_DATA_KEY),
+                &ReplyDataSnapshot::encode(storage),
+            )
+            .await?;
+
+        self.set_status(StorageStatus {
+            client_in_use: true,
+            previous_flush_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+        })
+        .await
     }
 
-    async fn init_fresh(&mut self, fresh: &CombinedReplyStorage) -> Result<(), Self::StorageError> {
-        self.empty.init_fresh(fresh).await
+    async fn init_fresh(
+        &mut self,
+        _fresh: &CombinedReplyStorage,
+    ) -> Result<(), Self::StorageError> {
+        // the metadata is stored alongside the rest of the data during the flush
+        Ok(())
     }
 
     async fn load_surb_storage(&self) -> Result<CombinedReplyStorage, Self::StorageError> {
-        self.empty.load_surb_storage().await
+        let db = self.db();
+        let status = db.get(REPLY_STORE, &JsValue::from_str(STATUS_KEY)).await?;
+        let reply_data = db
+            .get(REPLY_STORE, &JsValue::from_str(REPLY_DATA_KEY))
+            .await?;
+
+        Ok(restore_storage(
+            status.as_deref(),
+            reply_data.as_deref(),
+            self.min_surb_threshold,
+            self.max_surb_threshold,
+        ))
     }
 
     fn get_inactive_storage(&self) -> Result<CombinedReplyStorage, Self::StorageError> {
-        self.empty.get_inactive_storage()
+        Ok(CombinedReplyStorage::new(
+            self.min_surb_threshold,
+            self.max_surb_threshold,
+        ))
+    }
+
+    async fn stop_storage_session(self) -> Result<(), Self::StorageError> {
+        let mut status = self.get_status().await?;
+        status.client_in_use = false;
+        self.set_status(status).await
     }
 }
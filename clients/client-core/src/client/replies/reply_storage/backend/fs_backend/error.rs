@@ -1,6 +1,7 @@
 // Copyright 2022 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::config::persistence::encryption::EncryptionError;
 use std::io;
 use std::path::PathBuf;
 use thiserror::Error;
@@ -57,6 +58,22 @@ pub enum StorageError {
         // err: Option<Box<dyn std::error::Error>>
     },
 
+    #[error("the storage is encrypted - a passphrase is required to load it")]
+    PassphraseRequired,
+
+    #[error("the provided passphrase is invalid for the existing storage")]
+    InvalidPassphrase,
+
+    #[error("the existing storage is not encrypted while a passphrase has been provided")]
+    NotEncrypted,
+
+    #[error("failed to encrypt or decrypt the stored data: {source}")]
+    Encryption {
+        #[source]
+        #[from]
+        source: EncryptionError,
+    },
+
     #[error("failed to create storage")]
     FailedToCreateStorage {
         source: Box<dyn std::error::Error + Send + Sync>,
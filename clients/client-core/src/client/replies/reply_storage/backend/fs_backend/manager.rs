@@ -3,7 +3,8 @@
 
 use crate::client::replies::reply_storage::backend::fs_backend::error::StorageError;
 use crate::client::replies::reply_storage::backend::fs_backend::models::{
-    ReplySurbStorageMetadata, StoredReplyKey, StoredReplySurb, StoredSenderTag, StoredSurbSender,
+    ReplySurbStorageMetadata, StoredEncryptionMetadata, StoredReplyKey, StoredReplySurb,
+    StoredSenderTag, StoredSurbSender,
 };
 use log::{error, info};
 use sqlx::ConnectOptions;
@@ -264,4 +265,31 @@ impl StorageManager {
         ).execute(&self.connection_pool).await?;
         Ok(())
     }
+
+    pub(crate) async fn get_encryption_metadata(
+        &self,
+    ) -> Result<Option<StoredEncryptionMetadata>, sqlx::Error> {
+        sqlx::query_as!(
+            StoredEncryptionMetadata,
+            "SELECT * FROM encryption_metadata;",
+        )
+        .fetch_optional(&self.connection_pool)
+        .await
+    }
+
+    pub(crate) async fn insert_encryption_metadata(
+        &self,
+        metadata: StoredEncryptionMetadata,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+                INSERT INTO encryption_metadata(salt, verification) VALUES (?, ?);
+            "#,
+            metadata.salt,
+            metadata.verification
+        )
+        .execute(&self.connection_pool)
+        .await?;
+        Ok(())
+    }
 }
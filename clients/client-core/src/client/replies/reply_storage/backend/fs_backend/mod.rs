@@ -4,12 +4,14 @@
 use crate::client::base_client::non_wasm_helpers;
 use crate::client::replies::reply_storage::backend::fs_backend::manager::StorageManager;
 use crate::client::replies::reply_storage::backend::fs_backend::models::{
-    ReplySurbStorageMetadata, StoredReplyKey, StoredReplySurb, StoredSenderTag, StoredSurbSender,
+    EncryptableFields, ReplySurbStorageMetadata, StoredEncryptionMetadata, StoredReplyKey,
+    StoredReplySurb, StoredSenderTag, StoredSurbSender,
 };
 use crate::client::replies::reply_storage::surb_storage::ReceivedReplySurbs;
 use crate::client::replies::reply_storage::{
     CombinedReplyStorage, ReceivedReplySurbsMap, ReplyStorageBackend, SentReplyKeys, UsedSenderTags,
 };
+use crate::config::persistence::encryption::{StorageCipher, StoragePassphrase, SALT_LEN};
 use async_trait::async_trait;
 use log::{error, info, warn};
 use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
@@ -23,6 +25,9 @@ mod error;
 mod manager;
 mod models;
 
+// known plaintext used for checking whether the provided passphrase matches the stored data
+const PASSPHRASE_VERIFICATION: &[u8] = b"nym-reply-storage";
+
 #[derive(Debug)]
 enum StorageManagerState {
     Storage(StorageManager),
@@ -66,12 +71,19 @@ pub struct Backend {
     temporary_old_path: Option<PathBuf>,
     database_path: PathBuf,
     manager: StorageManagerState,
+
+    /// If the storage is protected with a passphrase, all of the stored surbs, keys and tags
+    /// are encrypted with this cipher.
+    cipher: Option<StorageCipher>,
 }
 
 impl Backend {
     const OLD_EXTENSION: &'static str = "old";
 
-    pub async fn init<P: AsRef<Path>>(database_path: P) -> Result<Self, StorageError> {
+    pub async fn init<P: AsRef<Path>>(
+        database_path: P,
+        passphrase: Option<&StoragePassphrase>,
+    ) -> Result<Self, StorageError> {
         let owned_path: PathBuf = database_path.as_ref().into();
         if owned_path.file_name().is_none() {
             return Err(StorageError::DatabasePathWithoutFilename {
@@ -82,10 +94,18 @@ impl Backend {
         let manager = StorageManager::init(database_path, true).await?;
         manager.create_status_table().await?;
 
+        let cipher = passphrase
+            .map(StorageCipher::new_with_random_salt)
+            .transpose()?;
+        if let Some(cipher) = &cipher {
+            Self::store_encryption_metadata(&manager, cipher).await?;
+        }
+
         let backend = Backend {
             temporary_old_path: None,
             database_path: owned_path,
             manager: StorageManagerState::Storage(manager),
+            cipher,
         };
 
         Ok(backend)
@@ -102,10 +122,14 @@ impl Backend {
                 minimum_reply_surb_storage_threshold,
                 maximum_reply_surb_storage_threshold,
             }),
+            cipher: None,
         }
     }
 
-    pub async fn try_load<P: AsRef<Path>>(database_path: P) -> Result<Self, StorageError> {
+    pub async fn try_load<P: AsRef<Path>>(
+        database_path: P,
+        passphrase: Option<&StoragePassphrase>,
+    ) -> Result<Self, StorageError> {
         let owned_path: PathBuf = database_path.as_ref().into();
         if owned_path.file_name().is_none() {
             return Err(StorageError::DatabasePathWithoutFilename {
@@ -115,6 +139,9 @@ impl Backend {
 
         let manager = StorageManager::init(database_path, false).await?;
 
+        // verify the passphrase before touching any of the data
+        let cipher = Self::load_cipher(&manager, passphrase).await?;
+
         // the database flush wasn't fully finished and thus the data is in inconsistent state
         // (we don't really know what's properly saved or what's not)
         if manager.get_flush_status().await? {
@@ -174,9 +201,71 @@ impl Backend {
             temporary_old_path: None,
             database_path: owned_path,
             manager: StorageManagerState::Storage(manager),
+            cipher,
         })
     }
 
+    async fn store_encryption_metadata(
+        manager: &StorageManager,
+        cipher: &StorageCipher,
+    ) -> Result<(), StorageError> {
+        let metadata = StoredEncryptionMetadata {
+            salt: cipher.salt().to_vec(),
+            verification: cipher.encrypt(PASSPHRASE_VERIFICATION)?,
+        };
+        Ok(manager.insert_encryption_metadata(metadata).await?)
+    }
+
+    async fn load_cipher(
+        manager: &StorageManager,
+        passphrase: Option<&StoragePassphrase>,
+    ) -> Result<Option<StorageCipher>, StorageError> {
+        let metadata = manager.get_encryption_metadata().await?;
+
+        match (metadata, passphrase) {
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(StorageError::NotEncrypted),
+            (Some(_), None) => Err(StorageError::PassphraseRequired),
+            (Some(metadata), Some(passphrase)) => {
+                let salt_len = metadata.salt.len();
+                let salt: [u8; SALT_LEN] = match metadata.salt.try_into() {
+                    Ok(salt) => salt,
+                    Err(_) => {
+                        return Err(StorageError::CorruptedData {
+                            details: format!(
+                                "the stored salt has length of {salt_len} while {SALT_LEN} was expected"
+                            ),
+                        })
+                    }
+                };
+
+                let cipher = StorageCipher::new(passphrase, salt)?;
+                match cipher.decrypt(&metadata.verification) {
+                    Ok(plaintext) if *plaintext == PASSPHRASE_VERIFICATION => Ok(Some(cipher)),
+                    _ => Err(StorageError::InvalidPassphrase),
+                }
+            }
+        }
+    }
+
+    fn seal<T: EncryptableFields>(&self, entry: T) -> Result<T, StorageError> {
+        match &self.cipher {
+            Some(cipher) => entry.map_fields(|field| Ok(cipher.encrypt(field)?)),
+            None => Ok(entry),
+        }
+    }
+
+    fn unseal<T: EncryptableFields>(&self, entry: T) -> Result<T, StorageError> {
+        match &self.cipher {
+            Some(cipher) => entry.map_fields(|field| {
+                // move the decrypted buffer into the entry so that no extra copy is left behind
+                let mut plaintext = cipher.decrypt(field)?;
+                Ok(std::mem::take(&mut *plaintext))
+            }),
+            None => Ok(entry),
+        }
+    }
+
     async fn close_pool(&mut self) {
         self.manager.get_mut().connection_pool.close().await;
     }
@@ -200,6 +289,9 @@ impl Backend {
         self.manager =
             StorageManagerState::Storage(StorageManager::init(&self.database_path, true).await?);
         self.manager.get_mut().create_status_table().await?;
+        if let Some(cipher) = &self.cipher {
+            Self::store_encryption_metadata(self.manager.get(), cipher).await?;
+        }
 
         self.temporary_old_path = Some(temp_old);
         Ok(())
@@ -242,7 +334,7 @@ impl Backend {
         // something weird has happened and we can't trust the rest of the data
         let raw = stored
             .into_iter()
-            .map(TryInto::try_into)
+            .map(|entry| self.unseal(entry).and_then(TryInto::try_into))
             .collect::<Result<_, _>>()?;
 
         Ok(UsedSenderTags::from_raw(raw))
@@ -253,7 +345,7 @@ impl Backend {
             let (recipient, tag) = map_ref.pair();
             self.manager
                 .get()
-                .insert_tag(StoredSenderTag::new(*recipient, *tag))
+                .insert_tag(self.seal(StoredSenderTag::new(*recipient, *tag))?)
                 .await?;
         }
         Ok(())
@@ -266,7 +358,7 @@ impl Backend {
         // something weird has happened and we can't trust the rest of the data
         let raw = stored
             .into_iter()
-            .map(TryInto::try_into)
+            .map(|entry| self.unseal(entry).and_then(TryInto::try_into))
             .collect::<Result<_, _>>()?;
 
         Ok(SentReplyKeys::from_raw(raw))
@@ -277,7 +369,7 @@ impl Backend {
             let (digest, key) = map_ref.pair();
             self.manager
                 .get()
-                .insert_reply_key(StoredReplyKey::new(*digest, *key))
+                .insert_reply_key(self.seal(StoredReplyKey::new(*digest, *key))?)
                 .await?;
         }
         Ok(())
@@ -291,14 +383,14 @@ impl Backend {
         for sender in surb_senders {
             let sender_id = sender.id;
             let (sender_tag, surbs_last_received_at_timestamp): (AnonymousSenderTag, i64) =
-                sender.try_into()?;
+                self.unseal(sender)?.try_into()?;
             let stored_surbs = self
                 .manager
                 .get()
                 .get_reply_surbs(sender_id)
                 .await?
                 .into_iter()
-                .map(|raw| raw.try_into())
+                .map(|raw| self.unseal(raw).and_then(TryInto::try_into))
                 .collect::<Result<_, _>>()?;
 
             received_surbs.push((
@@ -323,16 +415,16 @@ impl Backend {
             let sender_id = self
                 .manager
                 .get()
-                .insert_surb_sender(StoredSurbSender::new(
+                .insert_surb_sender(self.seal(StoredSurbSender::new(
                     *tag,
                     received_surbs.surbs_last_received_at(),
-                ))
+                ))?)
                 .await?;
 
             for reply_surb in received_surbs.surbs_ref() {
                 self.manager
                     .get()
-                    .insert_reply_surb(StoredReplySurb::new(sender_id, reply_surb))
+                    .insert_reply_surb(self.seal(StoredReplySurb::new(sender_id, reply_surb))?)
                     .await?
             }
         }
@@ -372,7 +464,7 @@ impl ReplyStorageBackend for Backend {
         debug_config: &crate::config::DebugConfig,
         db_path: Option<PathBuf>,
     ) -> Result<Self, Self::StorageError> {
-        non_wasm_helpers::setup_fs_reply_surb_backend(db_path, debug_config)
+        non_wasm_helpers::setup_fs_reply_surb_backend(db_path, debug_config, None)
             .await
             .map_err(|err| {
                 log::error!("Failed to create storage: {err}");
@@ -382,6 +474,22 @@ impl ReplyStorageBackend for Backend {
             })
     }
 
+    async fn new_encrypted(
+        debug_config: &crate::config::DebugConfig,
+        db_path: Option<PathBuf>,
+        passphrase: &StoragePassphrase,
+    ) -> Result<Option<Self>, Self::StorageError> {
+        non_wasm_helpers::setup_fs_reply_surb_backend(db_path, debug_config, Some(passphrase))
+            .await
+            .map(Some)
+            .map_err(|err| {
+                log::error!("Failed to create encrypted storage: {err}");
+                Self::StorageError::FailedToCreateStorage {
+                    source: Box::new(err),
+                }
+            })
+    }
+
     fn is_active(&self) -> bool {
         self.manager.is_active()
     }
@@ -439,3 +547,152 @@ impl ReplyStorageBackend for Backend {
         self.stop_client_use().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::replies::reply_storage::key_storage::UsedReplyKey;
+    use nym_sphinx::addressing::clients::Recipient;
+    use nym_sphinx::anonymous_replies::SurbEncryptionKey;
+    use rand::rngs::OsRng;
+
+    const RECIPIENT: [u8; Recipient::LEN] = [1u8; Recipient::LEN];
+
+    fn passphrase() -> StoragePassphrase {
+        StoragePassphrase::new("reply storage passphrase")
+    }
+
+    fn reply_storage(tag: AnonymousSenderTag, key: UsedReplyKey) -> CombinedReplyStorage {
+        CombinedReplyStorage::load(
+            SentReplyKeys::from_raw(vec![(key.compute_digest(), key)]),
+            ReceivedReplySurbsMap::from_raw(10, 100, Vec::new()),
+            UsedSenderTags::from_raw(vec![(RECIPIENT, tag)]),
+        )
+    }
+
+    #[tokio::test]
+    async fn encrypted_storage_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reply_store.sqlite");
+
+        let tag = AnonymousSenderTag::new_random(&mut OsRng);
+        let key = UsedReplyKey::new(SurbEncryptionKey::new(&mut OsRng), 1234);
+
+        let mut backend = Backend::init(&path, Some(&passphrase())).await.unwrap();
+        backend.start_storage_session().await.unwrap();
+        backend
+            .flush_surb_storage(&reply_storage(tag, key))
+            .await
+            .unwrap();
+        backend.stop_storage_session().await.unwrap();
+
+        let backend = Backend::try_load(&path, Some(&passphrase())).await.unwrap();
+
+        // nothing is stored in plaintext
+        let stored_tags = backend.manager.get().get_tags().await.unwrap();
+        assert_eq!(stored_tags.len(), 1);
+        assert_ne!(stored_tags[0].recipient, RECIPIENT.to_vec());
+        assert_ne!(stored_tags[0].tag, tag.to_bytes().to_vec());
+        let stored_keys = backend.manager.get().get_reply_keys().await.unwrap();
+        assert_eq!(stored_keys.len(), 1);
+        assert_ne!(stored_keys[0].reply_key, key.to_bytes());
+
+        let loaded = backend.load_surb_storage().await.unwrap();
+        assert_eq!(
+            loaded
+                .tags_storage_ref()
+                .as_raw_iter()
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect::<Vec<_>>(),
+            vec![(RECIPIENT, tag)]
+        );
+        let loaded_key = loaded
+            .key_storage_ref()
+            .try_pop(key.compute_digest())
+            .unwrap();
+        assert_eq!(loaded_key.to_bytes(), key.to_bytes());
+        assert_eq!(loaded_key.sent_at_timestamp, 1234);
+    }
+
+    #[tokio::test]
+    async fn wrong_passphrase_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reply_store.sqlite");
+
+        Backend::init(&path, Some(&passphrase())).await.unwrap();
+
+        let wrong = StoragePassphrase::new("not the reply storage passphrase");
+        assert!(matches!(
+            Backend::try_load(&path, Some(&wrong)).await,
+            Err(StorageError::InvalidPassphrase)
+        ));
+    }
+
+    #[tokio::test]
+    async fn passphrase_presence_must_match_the_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let encrypted = dir.path().join("encrypted.sqlite");
+        let plaintext = dir.path().join("plaintext.sqlite");
+
+        Backend::init(&encrypted, Some(&passphrase()))
+            .await
+            .unwrap();
+        Backend::init(&plaintext, None).await.unwrap();
+
+        assert!(matches!(
+            Backend::try_load(&encrypted, None).await,
+            Err(StorageError::PassphraseRequired)
+        ));
+        assert!(matches!(
+            Backend::try_load(&plaintext, Some(&passphrase())).await,
+            Err(StorageError::NotEncrypted)
+        ));
+    }
+
+    #[tokio::test]
+    async fn sealed_entries_are_authenticated() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Backend::init(dir.path().join("reply_store.sqlite"), Some(&passphrase()))
+            .await
+            .unwrap();
+
+        let key = UsedReplyKey::new(SurbEncryptionKey::new(&mut OsRng), 1234);
+        let entry = StoredReplyKey::new(key.compute_digest(), key);
+        let sealed = backend.seal(entry.clone()).unwrap();
+        assert_ne!(sealed.key_digest, entry.key_digest);
+        assert_ne!(sealed.reply_key, entry.reply_key);
+        assert_eq!(sealed.sent_at_timestamp, entry.sent_at_timestamp);
+
+        let unsealed = backend.unseal(sealed.clone()).unwrap();
+        assert_eq!(unsealed.key_digest, entry.key_digest);
+        assert_eq!(unsealed.reply_key, entry.reply_key);
+
+        let mut tampered = sealed.clone();
+        *tampered.reply_key.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            backend.unseal(tampered),
+            Err(StorageError::Encryption { .. })
+        ));
+
+        let mut truncated = sealed;
+        truncated.key_digest.truncate(5);
+        assert!(matches!(
+            backend.unseal(truncated),
+            Err(StorageError::Encryption { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn entries_are_left_untouched_without_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Backend::init(dir.path().join("reply_store.sqlite"), None)
+            .await
+            .unwrap();
+
+        let key = UsedReplyKey::new(SurbEncryptionKey::new(&mut OsRng), 1234);
+        let entry = StoredReplyKey::new(key.compute_digest(), key);
+        let sealed = backend.seal(entry.clone()).unwrap();
+        assert_eq!(sealed.reply_key, entry.reply_key);
+        assert_eq!(backend.unseal(sealed).unwrap().reply_key, entry.reply_key);
+    }
+}
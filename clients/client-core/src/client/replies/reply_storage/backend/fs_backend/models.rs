@@ -167,6 +167,70 @@ impl TryFrom<StoredReplySurb> for ReplySurb {
     }
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct StoredEncryptionMetadata {
+    pub(crate) salt: Vec<u8>,
+    // known plaintext encrypted with the derived key, used for verifying the passphrase
+    pub(crate) verification: Vec<u8>,
+}
+
+/// Stored entries whose blob fields get encrypted when the storage is protected with a passphrase.
+pub(crate) trait EncryptableFields: Sized {
+    fn map_fields<F>(self, f: F) -> Result<Self, StorageError>
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, StorageError>;
+}
+
+impl EncryptableFields for StoredSenderTag {
+    fn map_fields<F>(self, f: F) -> Result<Self, StorageError>
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, StorageError>,
+    {
+        Ok(StoredSenderTag {
+            recipient: f(&self.recipient)?,
+            tag: f(&self.tag)?,
+        })
+    }
+}
+
+impl EncryptableFields for StoredReplyKey {
+    fn map_fields<F>(self, f: F) -> Result<Self, StorageError>
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, StorageError>,
+    {
+        Ok(StoredReplyKey {
+            key_digest: f(&self.key_digest)?,
+            reply_key: f(&self.reply_key)?,
+            sent_at_timestamp: self.sent_at_timestamp,
+        })
+    }
+}
+
+impl EncryptableFields for StoredSurbSender {
+    fn map_fields<F>(self, f: F) -> Result<Self, StorageError>
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, StorageError>,
+    {
+        Ok(StoredSurbSender {
+            id: self.id,
+            tag: f(&self.tag)?,
+            last_sent_timestamp: self.last_sent_timestamp,
+        })
+    }
+}
+
+impl EncryptableFields for StoredReplySurb {
+    fn map_fields<F>(self, f: F) -> Result<Self, StorageError>
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, StorageError>,
+    {
+        Ok(StoredReplySurb {
+            reply_surb_sender_id: self.reply_surb_sender_id,
+            reply_surb: f(&self.reply_surb)?,
+        })
+    }
+}
+
 #[derive(Copy, Clone)]
 pub(crate) struct ReplySurbStorageMetadata {
     pub(crate) min_reply_surb_threshold: u32,
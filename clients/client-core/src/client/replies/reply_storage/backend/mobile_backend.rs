@@ -0,0 +1,144 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::client::replies::reply_storage::backend::snapshot::{
+    restore_storage, ReplyDataSnapshot, StorageStatus, REPLY_DATA_KEY, STATUS_KEY,
+};
+use crate::client::replies::reply_storage::{CombinedReplyStorage, ReplyStorageBackend};
+use async_trait::async_trait;
+use mobile_storage::{BlobStorage, FsBlobStorage};
+use std::path::PathBuf;
+use time::OffsetDateTime;
+
+pub use mobile_storage::StorageError;
+
+/// Keeps the reply-related data in the storage provided by the mobile application
+/// (or in its data directory), for the targets where the sqlite backend is unavailable.
+/// If no storage is used, everything is only kept in memory.
+pub struct Backend {
+    storage: Option<Box<dyn BlobStorage>>,
+    min_surb_threshold: usize,
+    max_surb_threshold: usize,
+}
+
+impl Backend {
+    pub fn new_inactive(min_surb_threshold: usize, max_surb_threshold: usize) -> Self {
+        Backend {
+            storage: None,
+            min_surb_threshold,
+            max_surb_threshold,
+        }
+    }
+
+    pub fn new_with_storage(
+        storage: Box<dyn BlobStorage>,
+        min_surb_threshold: usize,
+        max_surb_threshold: usize,
+    ) -> Self {
+        Backend {
+            storage: Some(storage),
+            min_surb_threshold,
+            max_surb_threshold,
+        }
+    }
+
+    fn storage(&self) -> &dyn BlobStorage {
+        self.storage
+            .as_deref()
+            .expect("tried to get the storage of an inactive backend")
+    }
+
+    async fn get_status(&self) -> Result<StorageStatus, StorageError> {
+        let raw = self.storage().get_blob(STATUS_KEY).await?;
+        Ok(raw
+            .and_then(|raw| StorageStatus::try_from_bytes(&raw).ok())
+            .unwrap_or_default())
+    }
+
+    async fn set_status(&self, status: StorageStatus) -> Result<(), StorageError> {
+        self.storage()
+            .set_blob(STATUS_KEY, &status.to_bytes())
+            .await
+    }
+}
+
+#[async_trait]
+impl ReplyStorageBackend for Backend {
+    type StorageError = StorageError;
+
+    async fn new(
+        debug_config: &crate::config::DebugConfig,
+        db_path: Option<PathBuf>,
+    ) -> Result<Self, Self::StorageError> {
+        let min_surb_threshold = debug_config.minimum_reply_surb_storage_threshold;
+        let max_surb_threshold = debug_config.maximum_reply_surb_storage_threshold;
+
+        // the path is treated as the directory for the blobs holding the data
+        Ok(match db_path {
+            Some(directory) => Backend::new_with_storage(
+                Box::new(FsBlobStorage::new(directory)),
+                min_surb_threshold,
+                max_surb_threshold,
+            ),
+            None => Backend::new_inactive(min_surb_threshold, max_surb_threshold),
+        })
+    }
+
+    fn is_active(&self) -> bool {
+        self.storage.is_some()
+    }
+
+    async fn start_storage_session(&self) -> Result<(), Self::StorageError> {
+        let mut status = self.get_status().await?;
+        status.client_in_use = true;
+        self.set_status(status).await
+    }
+
+    async fn flush_surb_storage(
+        &mut self,
+        storage: &CombinedReplyStorage,
+    ) -> Result<(), Self::StorageError> {
+        self.storage()
+            .set_blob(REPLY_DATA_KEY, &ReplyDataSnapshot::encode(storage))
+            .await?;
+
+        self.set_status(StorageStatus {
+            client_in_use: true,
+            previous_flush_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+        })
+        .await
+    }
+
+    async fn init_fresh(
+        &mut self,
+        _fresh: &CombinedReplyStorage,
+    ) -> Result<(), Self::StorageError> {
+        // the metadata is stored alongside the rest of the data during the flush
+        Ok(())
+    }
+
+    async fn load_surb_storage(&self) -> Result<CombinedReplyStorage, Self::StorageError> {
+        let status = self.storage().get_blob(STATUS_KEY).await?;
+        let reply_data = self.storage().get_blob(REPLY_DATA_KEY).await?;
+
+        Ok(restore_storage(
+            status.as_deref(),
+            reply_data.as_deref(),
+            self.min_surb_threshold,
+            self.max_surb_threshold,
+        ))
+    }
+
+    fn get_inactive_storage(&self) -> Result<CombinedReplyStorage, Self::StorageError> {
+        Ok(CombinedReplyStorage::new(
+            self.min_surb_threshold,
+            self.max_surb_threshold,
+        ))
+    }
+
+    async fn stop_storage_session(self) -> Result<(), Self::StorageError> {
+        let mut status = self.get_status().await?;
+        status.client_in_use = false;
+        self.set_status(status).await
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::client::replies::reply_storage::CombinedReplyStorage;
+use crate::config::persistence::encryption::StoragePassphrase;
 use async_trait::async_trait;
 use std::{error::Error, path::PathBuf};
 use thiserror::Error;
@@ -9,6 +10,12 @@ use thiserror::Error;
 #[cfg(target_arch = "wasm32")]
 pub mod browser_backend;
 
+#[cfg(all(not(target_arch = "wasm32"), feature = "mobile-surb-storage"))]
+pub mod mobile_backend;
+
+#[cfg(any(target_arch = "wasm32", feature = "mobile-surb-storage"))]
+mod snapshot;
+
 #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
 pub mod fs_backend;
 
@@ -26,7 +33,8 @@ pub struct Empty {
     pub max_surb_threshold: usize,
 }
 
-#[async_trait]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl ReplyStorageBackend for Empty {
     type StorageError = UndefinedError;
 
@@ -69,7 +77,8 @@ impl ReplyStorageBackend for Empty {
     }
 }
 
-#[async_trait]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 pub trait ReplyStorageBackend: Sized {
     type StorageError: Error + 'static;
 
@@ -78,6 +87,16 @@ pub trait ReplyStorageBackend: Sized {
         db_path: Option<PathBuf>,
     ) -> Result<Self, Self::StorageError>;
 
+    /// Creates the backend with all of its persisted data encrypted with the provided passphrase.
+    /// Returns `None` if the backend does not support encryption.
+    async fn new_encrypted(
+        _debug_config: &crate::config::DebugConfig,
+        _db_path: Option<PathBuf>,
+        _passphrase: &StoragePassphrase,
+    ) -> Result<Option<Self>, Self::StorageError> {
+        Ok(None)
+    }
+
     fn is_active(&self) -> bool {
         true
     }
@@ -0,0 +1,666 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serialization of the entire reply storage into a single blob, for the backends that are
+//! only capable of persisting opaque key-value data (such as the IndexedDB or app-provided storage).
+
+use crate::client::replies::reply_storage::key_storage::UsedReplyKey;
+use crate::client::replies::reply_storage::surb_storage::ReceivedReplySurbs;
+use crate::client::replies::reply_storage::{
+    CombinedReplyStorage, ReceivedReplySurbsMap, SentReplyKeys, UsedSenderTags,
+};
+use log::{error, info};
+use nym_crypto::generic_array::typenum::Unsigned;
+use nym_crypto::Digest;
+use nym_sphinx::addressing::clients::{Recipient, RecipientBytes};
+use nym_sphinx::anonymous_replies::encryption_key::EncryptionKeyDigest;
+use nym_sphinx::anonymous_replies::requests::{AnonymousSenderTag, SENDER_TAG_SIZE};
+use nym_sphinx::anonymous_replies::{ReplySurb, SurbEncryptionKey, SurbEncryptionKeySize};
+use nym_sphinx::params::ReplySurbKeyDigestAlgorithm;
+use thiserror::Error;
+use time::OffsetDateTime;
+
+pub(crate) const STATUS_KEY: &str = "status";
+pub(crate) const REPLY_DATA_KEY: &str = "reply_data";
+
+// bump it whenever the encoding of the reply data changes
+const SNAPSHOT_VERSION: u8 = 1;
+
+#[derive(Debug, Error)]
+pub(crate) enum CorruptedSnapshot {
+    #[error("the stored reply data has version {received} while {expected} was expected")]
+    UnsupportedVersion { received: u8, expected: u8 },
+
+    #[error("the stored reply data is corrupted: {details}")]
+    Malformed { details: String },
+}
+
+impl CorruptedSnapshot {
+    fn new<S: Into<String>>(details: S) -> Self {
+        CorruptedSnapshot::Malformed {
+            details: details.into(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct StorageStatus {
+    pub(crate) client_in_use: bool,
+    pub(crate) previous_flush_timestamp: i64,
+}
+
+impl StorageStatus {
+    const LEN: usize = 1 + 8;
+
+    // client_in_use || previous_flush_timestamp
+    pub(crate) fn to_bytes(self) -> Vec<u8> {
+        std::iter::once(self.client_in_use as u8)
+            .chain(self.previous_flush_timestamp.to_be_bytes())
+            .collect()
+    }
+
+    pub(crate) fn try_from_bytes(bytes: &[u8]) -> Result<Self, CorruptedSnapshot> {
+        if bytes.len() != Self::LEN {
+            return Err(CorruptedSnapshot::new(format!(
+                "the storage status has length of {} while {} was expected",
+                bytes.len(),
+                Self::LEN
+            )));
+        }
+
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(&bytes[1..]);
+
+        Ok(StorageStatus {
+            client_in_use: bytes[0] != 0,
+            previous_flush_timestamp: i64::from_be_bytes(timestamp),
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize, what: &str) -> Result<&'a [u8], CorruptedSnapshot> {
+        if self.bytes.len() < n {
+            return Err(CorruptedSnapshot::new(format!(
+                "not enough bytes left to read {what}"
+            )));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self, what: &str) -> Result<u8, CorruptedSnapshot> {
+        Ok(self.take(1, what)?[0])
+    }
+
+    fn u32(&mut self, what: &str) -> Result<u32, CorruptedSnapshot> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.take(4, what)?);
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    fn i64(&mut self, what: &str) -> Result<i64, CorruptedSnapshot> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8, what)?);
+        Ok(i64::from_be_bytes(bytes))
+    }
+
+    fn sender_tag(&mut self) -> Result<AnonymousSenderTag, CorruptedSnapshot> {
+        let mut bytes = [0u8; SENDER_TAG_SIZE];
+        bytes.copy_from_slice(self.take(SENDER_TAG_SIZE, "sender tag")?);
+        Ok(AnonymousSenderTag::from_bytes(bytes))
+    }
+}
+
+// writes the number of entries followed by all of them (encoded with the provided closure).
+// the count is filled in afterwards so that we'd iterate over the maps only once
+fn encode_entries<I, F>(out: &mut Vec<u8>, entries: I, mut encode: F)
+where
+    I: Iterator,
+    F: FnMut(&mut Vec<u8>, I::Item),
+{
+    let count_position = out.len();
+    out.extend_from_slice(&[0u8; 4]);
+
+    let mut count = 0u32;
+    for entry in entries {
+        encode(out, entry);
+        count += 1;
+    }
+    out[count_position..count_position + 4].copy_from_slice(&count.to_be_bytes());
+}
+
+/// Decoded content of the reply storage that has not yet been loaded into the memory.
+pub(crate) struct ReplyDataSnapshot {
+    min_surb_threshold: usize,
+    max_surb_threshold: usize,
+    tags: Vec<(RecipientBytes, AnonymousSenderTag)>,
+    reply_keys: Vec<(EncryptionKeyDigest, UsedReplyKey)>,
+    reply_surbs: Vec<(AnonymousSenderTag, i64, Vec<ReplySurb>)>,
+}
+
+impl ReplyDataSnapshot {
+    // version || min_threshold || max_threshold ||
+    // num_tags || (recipient || tag)* ||
+    // num_keys || (digest || key || sent_at)* ||
+    // num_senders || (tag || last_received_at || num_surbs || (surb_len || surb)*)*
+    pub(crate) fn encode(storage: &CombinedReplyStorage) -> Vec<u8> {
+        let mut out = vec![SNAPSHOT_VERSION];
+
+        let surbs = storage.surbs_storage_ref();
+        out.extend_from_slice(&(surbs.min_surb_threshold() as u32).to_be_bytes());
+        out.extend_from_slice(&(surbs.max_surb_threshold() as u32).to_be_bytes());
+
+        encode_entries(
+            &mut out,
+            storage.tags_storage_ref().as_raw_iter(),
+            |out, entry| {
+                let (recipient, tag) = entry.pair();
+                out.extend_from_slice(recipient);
+                out.extend_from_slice(&tag.to_bytes());
+            },
+        );
+
+        encode_entries(
+            &mut out,
+            storage.key_storage_ref().as_raw_iter(),
+            |out, entry| {
+                let (digest, key) = entry.pair();
+                out.extend_from_slice(digest);
+                out.extend_from_slice(&(**key).to_bytes());
+                out.extend_from_slice(&key.sent_at_timestamp.to_be_bytes());
+            },
+        );
+
+        encode_entries(&mut out, surbs.as_raw_iter(), |out, entry| {
+            let (tag, received) = entry.pair();
+            out.extend_from_slice(&tag.to_bytes());
+            out.extend_from_slice(&received.surbs_last_received_at().to_be_bytes());
+            encode_entries(out, received.surbs_ref().iter(), |out, surb| {
+                let surb_bytes = surb.to_bytes();
+                out.extend_from_slice(&(surb_bytes.len() as u32).to_be_bytes());
+                out.extend_from_slice(&surb_bytes);
+            });
+        });
+
+        out
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self, CorruptedSnapshot> {
+        let mut reader = Reader { bytes };
+        let digest_size = ReplySurbKeyDigestAlgorithm::output_size();
+
+        let version = reader.u8("snapshot version")?;
+        if version != SNAPSHOT_VERSION {
+            return Err(CorruptedSnapshot::UnsupportedVersion {
+                received: version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+
+        let min_surb_threshold = reader.u32("minimum surb threshold")? as usize;
+        let max_surb_threshold = reader.u32("maximum surb threshold")? as usize;
+
+        let num_tags = reader.u32("number of sender tags")?;
+        let mut tags = Vec::new();
+        for _ in 0..num_tags {
+            let mut recipient = [0u8; Recipient::LEN];
+            recipient.copy_from_slice(reader.take(Recipient::LEN, "recipient")?);
+            tags.push((recipient, reader.sender_tag()?));
+        }
+
+        let num_keys = reader.u32("number of reply keys")?;
+        let mut reply_keys = Vec::new();
+        for _ in 0..num_keys {
+            let digest = EncryptionKeyDigest::from_exact_iter(
+                reader
+                    .take(digest_size, "reply key digest")?
+                    .iter()
+                    .copied(),
+            )
+            .ok_or_else(|| CorruptedSnapshot::new("malformed reply key digest"))?;
+            let key = SurbEncryptionKey::try_from_bytes(
+                reader.take(SurbEncryptionKeySize::USIZE, "reply key")?,
+            )
+            .map_err(|err| CorruptedSnapshot::new(format!("malformed reply key: {err}")))?;
+            let sent_at = reader.i64("reply key timestamp")?;
+            reply_keys.push((digest, UsedReplyKey::new(key, sent_at)));
+        }
+
+        let num_senders = reader.u32("number of surb senders")?;
+        let mut reply_surbs = Vec::new();
+        for _ in 0..num_senders {
+            let tag = reader.sender_tag()?;
+            let last_received_at = reader.i64("surbs reception timestamp")?;
+            let num_surbs = reader.u32("number of reply surbs")?;
+            let mut surbs = Vec::new();
+            for _ in 0..num_surbs {
+                let surb_len = reader.u32("reply surb length")? as usize;
+                // `ReplySurb::from_bytes` doesn't perform any bound checks on its own,
+                // so make sure there's at least enough data for a surb going directly to the gateway
+                if surb_len < ReplySurb::serialized_len(0) {
+                    return Err(CorruptedSnapshot::new(format!(
+                        "reply surb has length of {surb_len} which is too short"
+                    )));
+                }
+                let surb =
+                    ReplySurb::from_bytes(reader.take(surb_len, "reply surb")?).map_err(|err| {
+                        CorruptedSnapshot::new(format!("failed to recover the reply surb: {err}"))
+                    })?;
+                surbs.push(surb);
+            }
+            reply_surbs.push((tag, last_received_at, surbs));
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(CorruptedSnapshot::new(
+                "trailing bytes after the reply data",
+            ));
+        }
+
+        Ok(ReplyDataSnapshot {
+            min_surb_threshold,
+            max_surb_threshold,
+            tags,
+            reply_keys,
+            reply_surbs,
+        })
+    }
+
+    /// Applies the same invalidation rules as the sqlite backend: the surbs and keys are not
+    /// trusted if the client hasn't been shutdown gracefully, and all data eventually goes stale.
+    pub(crate) fn purge_outdated(&mut self, status: StorageStatus) {
+        if status.client_in_use {
+            error!("the client hasn't undergone through graceful shutdown the last time it's gone down - we can't trust its reply surbs or stored encryption keys. They shall get purged");
+            self.reply_surbs.clear();
+            self.reply_keys.clear();
+        }
+
+        let last_flush = match OffsetDateTime::from_unix_timestamp(status.previous_flush_timestamp)
+        {
+            Ok(last_flush) => last_flush,
+            Err(err) => {
+                error!("failed to parse stored flush timestamp - {err}. All reply data shall get purged");
+                self.reply_surbs.clear();
+                self.reply_keys.clear();
+                self.tags.clear();
+                return;
+            }
+        };
+
+        let since_last_flush = OffsetDateTime::now_utc() - last_flush;
+        if since_last_flush.whole_days() > 0 {
+            info!("it's been over {} days since we last used our data store. our reply surbs are already outdated - we're going to purge them now.", since_last_flush.whole_days());
+            self.reply_surbs.clear();
+        }
+
+        if since_last_flush.whole_days() > 1 {
+            info!("it's been over {} days since we last used our data store. our reply keys are already outdated - we're going to purge them now.", since_last_flush.whole_days());
+            self.reply_keys.clear();
+        }
+
+        if since_last_flush.whole_days() > 2 {
+            info!("it's been over {} days since we last used our data store. our used sender tags are already outdated - we're going to purge them now.", since_last_flush.whole_days());
+            self.tags.clear();
+        }
+    }
+
+    pub(crate) fn into_storage(self) -> CombinedReplyStorage {
+        let reply_surbs = self
+            .reply_surbs
+            .into_iter()
+            .map(|(tag, last_received_at, surbs)| {
+                (
+                    tag,
+                    ReceivedReplySurbs::new_retrieved(surbs, last_received_at),
+                )
+            })
+            .collect();
+
+        CombinedReplyStorage::load(
+            SentReplyKeys::from_raw(self.reply_keys),
+            ReceivedReplySurbsMap::from_raw(
+                self.min_surb_threshold,
+                self.max_surb_threshold,
+                reply_surbs,
+            ),
+            UsedSenderTags::from_raw(self.tags),
+        )
+    }
+}
+
+/// Recreates the in-memory reply storage from the persisted data (if any). Corrupted data is
+/// discarded in favour of a fresh storage as it's going to be overwritten during the next flush anyway.
+pub(crate) fn restore_storage(
+    status: Option<&[u8]>,
+    reply_data: Option<&[u8]>,
+    min_surb_threshold: usize,
+    max_surb_threshold: usize,
+) -> CombinedReplyStorage {
+    let Some(reply_data) = reply_data else {
+        info!("there is no stored reply data - starting with a fresh storage");
+        return CombinedReplyStorage::new(min_surb_threshold, max_surb_threshold);
+    };
+
+    // if the status is missing, the data couldn't have been fully flushed and thus can't be trusted
+    let status = match status.map(StorageStatus::try_from_bytes).transpose() {
+        Ok(status) => status.unwrap_or_default(),
+        Err(err) => {
+            error!("{err}. We're going to start with a fresh storage instead");
+            return CombinedReplyStorage::new(min_surb_threshold, max_surb_threshold);
+        }
+    };
+
+    match ReplyDataSnapshot::decode(reply_data) {
+        Ok(mut snapshot) => {
+            snapshot.purge_outdated(status);
+            snapshot.into_storage()
+        }
+        Err(err) => {
+            error!("{err}. We're going to start with a fresh storage instead");
+            CombinedReplyStorage::new(min_surb_threshold, max_surb_threshold)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use nym_mixnet_contract_common::Layer;
+    use nym_topology::{gateway, mix, NymTopology};
+    use rand::rngs::OsRng;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn mix_node(mix_id: u32, layer: Layer) -> mix::Node {
+        mix::Node {
+            mix_id,
+            owner: format!("owner{mix_id}"),
+            host: "10.0.0.1".parse().unwrap(),
+            mix_host: "10.0.0.1:1789".parse().unwrap(),
+            identity_key: *identity::KeyPair::new(&mut OsRng).public_key(),
+            sphinx_key: *encryption::KeyPair::new(&mut OsRng).public_key(),
+            upcoming_sphinx_key: None,
+            layer,
+            version: "1.1.9".to_string(),
+        }
+    }
+
+    fn topology_with_gateway(gateway_identity: identity::PublicKey) -> NymTopology {
+        let mut mixes = HashMap::new();
+        mixes.insert(1, vec![mix_node(1, Layer::One)]);
+        mixes.insert(2, vec![mix_node(2, Layer::Two)]);
+        mixes.insert(3, vec![mix_node(3, Layer::Three)]);
+
+        NymTopology::new(
+            mixes,
+            vec![gateway::Node {
+                owner: "gateway-owner".to_string(),
+                stake: 123,
+                location: "unknown".to_string(),
+                host: "1.2.3.4".parse().unwrap(),
+                mix_host: "1.2.3.4:1789".parse().unwrap(),
+                clients_port: 9000,
+                identity_key: gateway_identity,
+                sphinx_key: *encryption::KeyPair::new(&mut OsRng).public_key(),
+                upcoming_sphinx_key: None,
+                version: "1.1.9".to_string(),
+            }],
+        )
+    }
+
+    fn random_recipient() -> Recipient {
+        Recipient::new(
+            *identity::KeyPair::new(&mut OsRng).public_key(),
+            *encryption::KeyPair::new(&mut OsRng).public_key(),
+            *identity::KeyPair::new(&mut OsRng).public_key(),
+        )
+    }
+
+    fn reply_surbs(amount: usize) -> Vec<ReplySurb> {
+        let recipient = random_recipient();
+        let topology = topology_with_gateway(*recipient.gateway());
+        (0..amount)
+            .map(|_| {
+                ReplySurb::construct(&mut OsRng, &recipient, Duration::from_millis(50), &topology)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    struct TestData {
+        storage: CombinedReplyStorage,
+        recipient: Recipient,
+        tag: AnonymousSenderTag,
+        key: UsedReplyKey,
+        surbs: Vec<Vec<u8>>,
+    }
+
+    fn populated_storage() -> TestData {
+        let storage = CombinedReplyStorage::new(10, 200);
+
+        let recipient = random_recipient();
+        let tag = AnonymousSenderTag::new_random(&mut OsRng);
+        storage.tags_storage_ref().insert_new(&recipient, tag);
+
+        let key = UsedReplyKey::new(SurbEncryptionKey::new(&mut OsRng), 1234);
+        storage.key_storage_ref().insert(key);
+
+        let surbs = reply_surbs(3);
+        let surbs_bytes = surbs.iter().map(|surb| surb.to_bytes()).collect();
+        storage.surbs_storage_ref().insert_surbs(&tag, surbs);
+
+        TestData {
+            storage,
+            recipient,
+            tag,
+            key,
+            surbs: surbs_bytes,
+        }
+    }
+
+    fn fresh_status() -> StorageStatus {
+        StorageStatus {
+            client_in_use: false,
+            previous_flush_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+        }
+    }
+
+    fn assert_contains_data(storage: &CombinedReplyStorage, data: &TestData) {
+        assert_eq!(storage.surbs_storage_ref().min_surb_threshold(), 10);
+        assert_eq!(storage.surbs_storage_ref().max_surb_threshold(), 200);
+
+        assert_eq!(
+            storage.tags_storage_ref().try_get_existing(&data.recipient),
+            Some(data.tag)
+        );
+
+        let key = storage
+            .key_storage_ref()
+            .try_pop(data.key.compute_digest())
+            .unwrap();
+        assert_eq!(key.to_bytes(), data.key.to_bytes());
+        assert_eq!(key.sent_at_timestamp, data.key.sent_at_timestamp);
+
+        let surbs = storage.surbs_storage_ref();
+        let entry = surbs.as_raw_iter().find(|e| e.key() == &data.tag).unwrap();
+        let restored = entry
+            .surbs_ref()
+            .iter()
+            .map(|surb| surb.to_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(restored, data.surbs);
+    }
+
+    #[test]
+    fn storage_status_roundtrip() {
+        let status = StorageStatus {
+            client_in_use: true,
+            previous_flush_timestamp: 1676457426,
+        };
+        let recovered = StorageStatus::try_from_bytes(&status.to_bytes()).unwrap();
+        assert!(recovered.client_in_use);
+        assert_eq!(recovered.previous_flush_timestamp, 1676457426);
+
+        let mut bytes = status.to_bytes();
+        assert!(StorageStatus::try_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        bytes.push(0);
+        assert!(StorageStatus::try_from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn reply_data_roundtrip() {
+        let data = populated_storage();
+        let encoded = ReplyDataSnapshot::encode(&data.storage);
+
+        let restored = ReplyDataSnapshot::decode(&encoded).unwrap().into_storage();
+        assert_contains_data(&restored, &data);
+
+        let status = fresh_status().to_bytes();
+        let restored = restore_storage(Some(&status), Some(&encoded), 1, 2);
+        assert_contains_data(&restored, &data);
+    }
+
+    #[test]
+    fn empty_reply_data_roundtrip() {
+        let storage = CombinedReplyStorage::new(10, 200);
+        let encoded = ReplyDataSnapshot::encode(&storage);
+        let snapshot = ReplyDataSnapshot::decode(&encoded).unwrap();
+
+        assert_eq!(snapshot.min_surb_threshold, 10);
+        assert_eq!(snapshot.max_surb_threshold, 200);
+        assert!(snapshot.tags.is_empty());
+        assert!(snapshot.reply_keys.is_empty());
+        assert!(snapshot.reply_surbs.is_empty());
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let data = populated_storage();
+        let mut encoded = ReplyDataSnapshot::encode(&data.storage);
+        encoded[0] = SNAPSHOT_VERSION + 1;
+
+        match ReplyDataSnapshot::decode(&encoded) {
+            Err(CorruptedSnapshot::UnsupportedVersion { received, expected }) => {
+                assert_eq!(received, SNAPSHOT_VERSION + 1);
+                assert_eq!(expected, SNAPSHOT_VERSION);
+            }
+            _ => panic!("expected the version mismatch to be detected"),
+        }
+
+        // and we start from scratch instead
+        let status = fresh_status().to_bytes();
+        let restored = restore_storage(Some(&status), Some(&encoded), 1, 2);
+        assert!(!restored.tags_storage_ref().exists(&data.recipient));
+        assert_eq!(restored.surbs_storage_ref().min_surb_threshold(), 1);
+        assert_eq!(restored.surbs_storage_ref().max_surb_threshold(), 2);
+    }
+
+    #[test]
+    fn truncated_reply_data_is_rejected() {
+        let data = populated_storage();
+        let encoded = ReplyDataSnapshot::encode(&data.storage);
+
+        for len in 0..encoded.len() {
+            assert!(
+                ReplyDataSnapshot::decode(&encoded[..len]).is_err(),
+                "snapshot truncated to {len} bytes was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        let data = populated_storage();
+        let mut encoded = ReplyDataSnapshot::encode(&data.storage);
+        encoded.push(42);
+
+        assert!(matches!(
+            ReplyDataSnapshot::decode(&encoded),
+            Err(CorruptedSnapshot::Malformed { .. })
+        ));
+    }
+
+    #[test]
+    fn too_short_surbs_are_rejected() {
+        let storage = CombinedReplyStorage::new(10, 200);
+        let mut encoded = ReplyDataSnapshot::encode(&storage);
+
+        // replace the empty surb section with a single sender holding a single, tiny surb
+        encoded.truncate(encoded.len() - 4);
+        encoded.extend_from_slice(&1u32.to_be_bytes());
+        encoded.extend_from_slice(&AnonymousSenderTag::new_random(&mut OsRng).to_bytes());
+        encoded.extend_from_slice(&0i64.to_be_bytes());
+        encoded.extend_from_slice(&1u32.to_be_bytes());
+        encoded.extend_from_slice(&4u32.to_be_bytes());
+        encoded.extend_from_slice(&[1, 2, 3, 4]);
+
+        assert!(ReplyDataSnapshot::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn corrupted_status_results_in_fresh_storage() {
+        let data = populated_storage();
+        let encoded = ReplyDataSnapshot::encode(&data.storage);
+
+        let restored = restore_storage(Some(&[1, 2, 3]), Some(&encoded), 1, 2);
+        assert!(!restored.tags_storage_ref().exists(&data.recipient));
+        assert_eq!(restored.surbs_storage_ref().min_surb_threshold(), 1);
+    }
+
+    #[test]
+    fn unclean_shutdown_purges_surbs_and_keys() {
+        let data = populated_storage();
+        let encoded = ReplyDataSnapshot::encode(&data.storage);
+
+        let mut snapshot = ReplyDataSnapshot::decode(&encoded).unwrap();
+        snapshot.purge_outdated(StorageStatus {
+            client_in_use: true,
+            ..fresh_status()
+        });
+        assert!(snapshot.reply_surbs.is_empty());
+        assert!(snapshot.reply_keys.is_empty());
+        assert_eq!(snapshot.tags.len(), 1);
+    }
+
+    #[test]
+    fn stale_data_gets_purged() {
+        let data = populated_storage();
+        let encoded = ReplyDataSnapshot::encode(&data.storage);
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let day = 24 * 60 * 60;
+
+        let mut snapshot = ReplyDataSnapshot::decode(&encoded).unwrap();
+        snapshot.purge_outdated(StorageStatus {
+            client_in_use: false,
+            previous_flush_timestamp: now - day - 60,
+        });
+        assert!(snapshot.reply_surbs.is_empty());
+        assert_eq!(snapshot.reply_keys.len(), 1);
+        assert_eq!(snapshot.tags.len(), 1);
+
+        let mut snapshot = ReplyDataSnapshot::decode(&encoded).unwrap();
+        snapshot.purge_outdated(StorageStatus {
+            client_in_use: false,
+            previous_flush_timestamp: now - 2 * day - 60,
+        });
+        assert!(snapshot.reply_surbs.is_empty());
+        assert!(snapshot.reply_keys.is_empty());
+        assert_eq!(snapshot.tags.len(), 1);
+
+        let mut snapshot = ReplyDataSnapshot::decode(&encoded).unwrap();
+        snapshot.purge_outdated(StorageStatus {
+            client_in_use: false,
+            previous_flush_timestamp: now - 3 * day - 60,
+        });
+        assert!(snapshot.reply_surbs.is_empty());
+        assert!(snapshot.reply_keys.is_empty());
+        assert!(snapshot.tags.is_empty());
+    }
+}
@@ -41,7 +41,11 @@ impl ReceivedReplySurbsMap {
         }
     }
 
-    #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
+    #[cfg(any(
+        target_arch = "wasm32",
+        feature = "fs-surb-storage",
+        feature = "mobile-surb-storage"
+    ))]
     pub(crate) fn from_raw(
         min_surb_threshold: usize,
         max_surb_threshold: usize,
@@ -207,7 +211,11 @@ impl ReceivedReplySurbs {
         }
     }
 
-    #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
+    #[cfg(any(
+        target_arch = "wasm32",
+        feature = "fs-surb-storage",
+        feature = "mobile-surb-storage"
+    ))]
     pub(crate) fn new_retrieved(
         surbs: Vec<ReplySurb>,
         surbs_last_received_at_timestamp: i64,
@@ -219,7 +227,11 @@ impl ReceivedReplySurbs {
         }
     }
 
-    #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
+    #[cfg(any(
+        target_arch = "wasm32",
+        feature = "fs-surb-storage",
+        feature = "mobile-surb-storage"
+    ))]
     pub(crate) fn surbs_ref(&self) -> &VecDeque<ReplySurb> {
         &self.data
     }
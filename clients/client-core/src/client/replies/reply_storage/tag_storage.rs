@@ -6,7 +6,11 @@ use nym_sphinx::addressing::clients::{Recipient, RecipientBytes};
 use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
 use std::sync::Arc;
 
-#[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
+#[cfg(any(
+    target_arch = "wasm32",
+    feature = "fs-surb-storage",
+    feature = "mobile-surb-storage"
+))]
 use dashmap::iter::Iter;
 
 #[derive(Debug, Clone)]
@@ -28,7 +32,11 @@ impl UsedSenderTags {
         }
     }
 
-    #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
+    #[cfg(any(
+        target_arch = "wasm32",
+        feature = "fs-surb-storage",
+        feature = "mobile-surb-storage"
+    ))]
     pub(crate) fn from_raw(raw: Vec<(RecipientBytes, AnonymousSenderTag)>) -> UsedSenderTags {
         UsedSenderTags {
             inner: Arc::new(UsedSenderTagsInner {
@@ -37,7 +45,11 @@ impl UsedSenderTags {
         }
     }
 
-    #[cfg(all(not(target_arch = "wasm32"), feature = "fs-surb-storage"))]
+    #[cfg(any(
+        target_arch = "wasm32",
+        feature = "fs-surb-storage",
+        feature = "mobile-surb-storage"
+    ))]
     pub(crate) fn as_raw_iter(&self) -> Iter<'_, RecipientBytes, AnonymousSenderTag> {
         self.inner.data.iter()
     }
@@ -56,4 +68,21 @@ impl UsedSenderTags {
     pub(crate) fn exists(&self, recipient: &Recipient) -> bool {
         self.inner.data.contains_key(&recipient.to_bytes())
     }
+
+    /// Assigns the tag we used for any previous address of the client (i.e. with the same keys,
+    /// but a different gateway) to its new address.
+    pub(crate) fn inherit_for_new_address(&self, new_address: &Recipient) {
+        // note: the iterator has to be dropped before we attempt to insert anything,
+        // otherwise we might deadlock on the shard lock
+        let existing = self.inner.data.iter().find_map(|entry| {
+            let previous = Recipient::try_from_bytes(*entry.key()).ok()?;
+            (previous.identity() == new_address.identity()
+                && previous.encryption_key() == new_address.encryption_key())
+            .then(|| *entry.value())
+        });
+
+        if let Some(tag) = existing {
+            self.insert_new(new_address, tag)
+        }
+    }
 }
@@ -0,0 +1,689 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use dashmap::DashMap;
+use nym_crypto::blake3;
+use nym_sphinx::addressing::clients::{Recipient, RecipientBytes};
+use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+use serde::{Serialize, Serializer};
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use mixnode_common::metrics::{MetricType, PrometheusEncoder};
+
+/// Maximum number of destinations that are tracked individually. Once it's reached, the
+/// destination that has been inactive for the longest is evicted to make room for the new one,
+/// so that a client talking to a lot of (possibly short-lived) anonymous senders wouldn't keep
+/// growing its memory usage. The traffic of evicted destinations remains accounted for in the totals.
+pub const MAX_TRACKED_DESTINATIONS: usize = 1000;
+
+/// Number of bytes of the destination digest used for labelling its metrics.
+const METRICS_LABEL_DIGEST_LENGTH: usize = 8;
+
+/// The other side of the communication the statistics are collected for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatisticsDestination {
+    /// Client whose address is known to us, i.e. the recipient of our messages.
+    Recipient(RecipientBytes),
+
+    /// Client that has only revealed its sender tag to us, i.e. the sender of repliable messages.
+    AnonymousSender(AnonymousSenderTag),
+}
+
+impl From<&Recipient> for StatisticsDestination {
+    fn from(recipient: &Recipient) -> Self {
+        StatisticsDestination::Recipient(recipient.to_bytes())
+    }
+}
+
+impl From<AnonymousSenderTag> for StatisticsDestination {
+    fn from(sender_tag: AnonymousSenderTag) -> Self {
+        StatisticsDestination::AnonymousSender(sender_tag)
+    }
+}
+
+impl Display for StatisticsDestination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StatisticsDestination::Recipient(bytes) => match Recipient::try_from_bytes(*bytes) {
+                Ok(recipient) => recipient.fmt(f),
+                Err(_) => write!(f, "<malformed recipient>"),
+            },
+            StatisticsDestination::AnonymousSender(sender_tag) => sender_tag.fmt(f),
+        }
+    }
+}
+
+impl StatisticsDestination {
+    /// Kind of the destination, i.e. whether its address is known.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StatisticsDestination::Recipient(_) => "recipient",
+            StatisticsDestination::AnonymousSender(_) => "anonymous_sender",
+        }
+    }
+
+    /// Stable, shortened digest of the destination used for labelling its metrics, so that the
+    /// addresses of our peers wouldn't end up in whatever system is scraping them.
+    pub fn metrics_label(&self) -> String {
+        let digest = match self {
+            StatisticsDestination::Recipient(bytes) => blake3::hash(bytes),
+            StatisticsDestination::AnonymousSender(sender_tag) => {
+                blake3::hash(&sender_tag.to_bytes())
+            }
+        };
+
+        digest.as_bytes()[..METRICS_LABEL_DIGEST_LENGTH]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+impl Serialize for StatisticsDestination {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn serialize_optional_millis<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&(duration.as_secs_f64() * 1000.0)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Default)]
+struct DestinationCounters {
+    /// Value of the activity clock at the last update of the counters, used for picking the
+    /// destination to evict once the tracking limit is reached.
+    last_activity: u64,
+
+    sent_packets: u64,
+    sent_bytes: u64,
+    retransmitted_packets: u64,
+    acknowledged_packets: u64,
+    received_messages: u64,
+    received_bytes: u64,
+    reply_surbs_sent: u64,
+    reply_surbs_received: u64,
+    reply_surbs_used: u64,
+
+    ack_rtt_samples: u64,
+    ack_rtt_total: Duration,
+    last_ack_rtt: Option<Duration>,
+    min_ack_rtt: Option<Duration>,
+    max_ack_rtt: Option<Duration>,
+}
+
+impl DestinationCounters {
+    fn record_ack_rtt(&mut self, rtt: Duration) {
+        self.ack_rtt_samples += 1;
+        self.ack_rtt_total += rtt;
+        self.last_ack_rtt = Some(rtt);
+        self.min_ack_rtt = Some(self.min_ack_rtt.map_or(rtt, |min| min.min(rtt)));
+        self.max_ack_rtt = Some(self.max_ack_rtt.map_or(rtt, |max| max.max(rtt)));
+    }
+
+    fn snapshot(&self, destination: StatisticsDestination) -> DestinationStatistics {
+        let average_ack_rtt = (self.ack_rtt_samples > 0).then(|| {
+            Duration::from_secs_f64(self.ack_rtt_total.as_secs_f64() / self.ack_rtt_samples as f64)
+        });
+
+        DestinationStatistics {
+            destination,
+            sent_packets: self.sent_packets,
+            sent_bytes: self.sent_bytes,
+            retransmitted_packets: self.retransmitted_packets,
+            acknowledged_packets: self.acknowledged_packets,
+            received_messages: self.received_messages,
+            received_bytes: self.received_bytes,
+            reply_surbs_sent: self.reply_surbs_sent,
+            reply_surbs_received: self.reply_surbs_received,
+            reply_surbs_used: self.reply_surbs_used,
+            average_ack_rtt,
+            last_ack_rtt: self.last_ack_rtt,
+            min_ack_rtt: self.min_ack_rtt,
+            max_ack_rtt: self.max_ack_rtt,
+        }
+    }
+}
+
+/// Statistics of the traffic exchanged with a particular destination.
+/// Round trip times are measured from the moment a packet is sent to the gateway until its
+/// acknowledgement arrives back at the client.
+#[derive(Clone, Debug, Serialize)]
+pub struct DestinationStatistics {
+    pub destination: StatisticsDestination,
+
+    /// Number of real packets sent for the first time (i.e. excluding retransmissions).
+    pub sent_packets: u64,
+
+    /// Number of bytes of message data sent in the real packets.
+    pub sent_bytes: u64,
+
+    /// Number of packets that had to be retransmitted due to their acknowledgement timing out.
+    pub retransmitted_packets: u64,
+
+    /// Number of packets whose acknowledgements were received.
+    pub acknowledged_packets: u64,
+
+    /// Number of messages reconstructed from the received packets.
+    /// Note that it's only known for anonymous senders as otherwise the sender is not revealed.
+    pub received_messages: u64,
+
+    /// Number of bytes of message data reconstructed from the received packets.
+    pub received_bytes: u64,
+
+    /// Number of reply SURBs we sent so that the destination could reply to us.
+    pub reply_surbs_sent: u64,
+
+    /// Number of reply SURBs we received from the destination.
+    pub reply_surbs_received: u64,
+
+    /// Number of reply SURBs of the destination used up for sending (or resending) packets to it.
+    pub reply_surbs_used: u64,
+
+    #[serde(
+        rename = "average_ack_rtt_ms",
+        serialize_with = "serialize_optional_millis"
+    )]
+    pub average_ack_rtt: Option<Duration>,
+
+    #[serde(
+        rename = "last_ack_rtt_ms",
+        serialize_with = "serialize_optional_millis"
+    )]
+    pub last_ack_rtt: Option<Duration>,
+
+    #[serde(
+        rename = "min_ack_rtt_ms",
+        serialize_with = "serialize_optional_millis"
+    )]
+    pub min_ack_rtt: Option<Duration>,
+
+    #[serde(
+        rename = "max_ack_rtt_ms",
+        serialize_with = "serialize_optional_millis"
+    )]
+    pub max_ack_rtt: Option<Duration>,
+}
+
+/// Point in time view of all the traffic statistics of the client.
+#[derive(Clone, Debug, Serialize)]
+pub struct ClientStatisticsSnapshot {
+    pub total_sent_packets: u64,
+    pub total_sent_bytes: u64,
+    pub total_retransmitted_packets: u64,
+    pub total_acknowledged_packets: u64,
+
+    /// Number of real (i.e. non-cover) packets received from the mix network.
+    pub total_received_packets: u64,
+    pub total_received_messages: u64,
+    pub total_received_bytes: u64,
+
+    /// Statistics of the individually tracked destinations, the most active ones first.
+    pub destinations: Vec<DestinationStatistics>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type CounterAccessor = fn(&DestinationStatistics) -> u64;
+
+impl ClientStatisticsSnapshot {
+    /// Renders the statistics in the Prometheus text exposition format.
+    /// The destinations are labelled with their kind and the digest of their address
+    /// (see [`StatisticsDestination::metrics_label`]) rather than the address itself.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_prometheus_text(&self) -> String {
+        let mut encoder = PrometheusEncoder::new();
+
+        encoder.counter(
+            "nym_client_sent_packets_total",
+            "Real packets sent, excluding retransmissions",
+            self.total_sent_packets,
+        );
+        encoder.counter(
+            "nym_client_sent_bytes_total",
+            "Bytes of message data sent",
+            self.total_sent_bytes,
+        );
+        encoder.counter(
+            "nym_client_retransmitted_packets_total",
+            "Packets retransmitted due to their acknowledgement timing out",
+            self.total_retransmitted_packets,
+        );
+        encoder.counter(
+            "nym_client_acknowledged_packets_total",
+            "Packets whose acknowledgements were received",
+            self.total_acknowledged_packets,
+        );
+        encoder.counter(
+            "nym_client_received_packets_total",
+            "Real packets received from the mix network",
+            self.total_received_packets,
+        );
+        encoder.counter(
+            "nym_client_received_messages_total",
+            "Messages reconstructed from the received packets",
+            self.total_received_messages,
+        );
+        encoder.counter(
+            "nym_client_received_bytes_total",
+            "Bytes of message data received",
+            self.total_received_bytes,
+        );
+
+        let labels: Vec<_> = self
+            .destinations
+            .iter()
+            .map(|destination| {
+                (
+                    destination.destination.kind(),
+                    destination.destination.metrics_label(),
+                )
+            })
+            .collect();
+
+        let per_destination: [(&str, &str, CounterAccessor); 9] = [
+            (
+                "nym_client_destination_sent_packets_total",
+                "Real packets sent to the destination, excluding retransmissions",
+                |s| s.sent_packets,
+            ),
+            (
+                "nym_client_destination_sent_bytes_total",
+                "Bytes of message data sent to the destination",
+                |s| s.sent_bytes,
+            ),
+            (
+                "nym_client_destination_retransmitted_packets_total",
+                "Packets retransmitted to the destination",
+                |s| s.retransmitted_packets,
+            ),
+            (
+                "nym_client_destination_acknowledged_packets_total",
+                "Packets sent to the destination whose acknowledgements were received",
+                |s| s.acknowledged_packets,
+            ),
+            (
+                "nym_client_destination_received_messages_total",
+                "Messages received from the destination",
+                |s| s.received_messages,
+            ),
+            (
+                "nym_client_destination_received_bytes_total",
+                "Bytes of message data received from the destination",
+                |s| s.received_bytes,
+            ),
+            (
+                "nym_client_destination_reply_surbs_sent_total",
+                "Reply SURBs sent to the destination",
+                |s| s.reply_surbs_sent,
+            ),
+            (
+                "nym_client_destination_reply_surbs_received_total",
+                "Reply SURBs received from the destination",
+                |s| s.reply_surbs_received,
+            ),
+            (
+                "nym_client_destination_reply_surbs_used_total",
+                "Reply SURBs of the destination used for sending packets to it",
+                |s| s.reply_surbs_used,
+            ),
+        ];
+
+        for (name, help, value) in per_destination {
+            encoder.describe(name, help, MetricType::Counter);
+            for (destination, (kind, label)) in self.destinations.iter().zip(&labels) {
+                encoder.sample(
+                    name,
+                    &[("kind", kind), ("destination", label)],
+                    value(destination),
+                );
+            }
+        }
+
+        let name = "nym_client_destination_average_ack_rtt_seconds";
+        encoder.describe(
+            name,
+            "Average time between sending a packet to the destination and receiving its acknowledgement",
+            MetricType::Gauge,
+        );
+        for (destination, (kind, label)) in self.destinations.iter().zip(&labels) {
+            if let Some(rtt) = destination.average_ack_rtt {
+                encoder.sample(
+                    name,
+                    &[("kind", kind), ("destination", label)],
+                    rtt.as_secs_f64(),
+                );
+            }
+        }
+
+        encoder.finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct ClientStatisticsInner {
+    destinations: DashMap<StatisticsDestination, DestinationCounters>,
+
+    /// Logical clock ticking on every update of the per-destination counters.
+    activity_clock: AtomicU64,
+
+    total_sent_packets: AtomicU64,
+    total_sent_bytes: AtomicU64,
+    total_retransmitted_packets: AtomicU64,
+    total_acknowledged_packets: AtomicU64,
+    total_received_packets: AtomicU64,
+    total_received_messages: AtomicU64,
+    total_received_bytes: AtomicU64,
+}
+
+/// Per-destination traffic statistics of the client. They are updated by the components
+/// sending and receiving the packets and are cheap to clone and read at any point.
+#[derive(Clone, Debug, Default)]
+pub struct ClientStatistics {
+    inner: Arc<ClientStatisticsInner>,
+}
+
+impl ClientStatistics {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the current view of all the statistics.
+    pub fn snapshot(&self) -> ClientStatisticsSnapshot {
+        let mut destinations: Vec<_> = self
+            .inner
+            .destinations
+            .iter()
+            .map(|entry| entry.value().snapshot(*entry.key()))
+            .collect();
+        destinations.sort_by(|a, b| {
+            (b.sent_packets + b.received_messages).cmp(&(a.sent_packets + a.received_messages))
+        });
+
+        ClientStatisticsSnapshot {
+            total_sent_packets: self.inner.total_sent_packets.load(Ordering::Relaxed),
+            total_sent_bytes: self.inner.total_sent_bytes.load(Ordering::Relaxed),
+            total_retransmitted_packets: self
+                .inner
+                .total_retransmitted_packets
+                .load(Ordering::Relaxed),
+            total_acknowledged_packets: self
+                .inner
+                .total_acknowledged_packets
+                .load(Ordering::Relaxed),
+            total_received_packets: self.inner.total_received_packets.load(Ordering::Relaxed),
+            total_received_messages: self.inner.total_received_messages.load(Ordering::Relaxed),
+            total_received_bytes: self.inner.total_received_bytes.load(Ordering::Relaxed),
+            destinations,
+        }
+    }
+
+    /// Returns statistics of the particular destination, if it's being tracked.
+    pub fn destination(&self, destination: StatisticsDestination) -> Option<DestinationStatistics> {
+        self.inner
+            .destinations
+            .get(&destination)
+            .map(|counters| counters.snapshot(destination))
+    }
+
+    fn update_destination<F>(&self, destination: StatisticsDestination, f: F)
+    where
+        F: FnOnce(&mut DestinationCounters),
+    {
+        let now = self.inner.activity_clock.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(mut counters) = self.inner.destinations.get_mut(&destination) {
+            counters.last_activity = now;
+            f(&mut counters);
+            return;
+        }
+
+        // note: no guard into the map is held at this point, so the eviction can't deadlock
+        if self.inner.destinations.len() >= MAX_TRACKED_DESTINATIONS {
+            self.evict_least_recently_active();
+        }
+
+        let mut counters = self.inner.destinations.entry(destination).or_default();
+        counters.last_activity = now;
+        f(&mut counters)
+    }
+
+    fn evict_least_recently_active(&self) {
+        let least_recently_active = self
+            .inner
+            .destinations
+            .iter()
+            .min_by_key(|entry| entry.value().last_activity)
+            .map(|entry| *entry.key());
+
+        if let Some(destination) = least_recently_active {
+            self.inner.destinations.remove(&destination);
+        }
+    }
+
+    pub(crate) fn record_sent(&self, destination: StatisticsDestination, bytes: usize) {
+        self.inner
+            .total_sent_packets
+            .fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .total_sent_bytes
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+
+        self.update_destination(destination, |counters| {
+            counters.sent_packets += 1;
+            counters.sent_bytes += bytes as u64;
+            if matches!(destination, StatisticsDestination::AnonymousSender(_)) {
+                counters.reply_surbs_used += 1;
+            }
+        })
+    }
+
+    pub(crate) fn record_retransmission(&self, destination: StatisticsDestination) {
+        self.inner
+            .total_retransmitted_packets
+            .fetch_add(1, Ordering::Relaxed);
+
+        self.update_destination(destination, |counters| {
+            counters.retransmitted_packets += 1;
+            if matches!(destination, StatisticsDestination::AnonymousSender(_)) {
+                counters.reply_surbs_used += 1;
+            }
+        })
+    }
+
+    pub(crate) fn record_acknowledgement(
+        &self,
+        destination: StatisticsDestination,
+        rtt: Option<Duration>,
+    ) {
+        self.inner
+            .total_acknowledged_packets
+            .fetch_add(1, Ordering::Relaxed);
+
+        self.update_destination(destination, |counters| {
+            counters.acknowledged_packets += 1;
+            if let Some(rtt) = rtt {
+                counters.record_ack_rtt(rtt)
+            }
+        })
+    }
+
+    pub(crate) fn record_received_packet(&self) {
+        self.inner
+            .total_received_packets
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_received_message(
+        &self,
+        sender_tag: Option<AnonymousSenderTag>,
+        bytes: usize,
+    ) {
+        self.inner
+            .total_received_messages
+            .fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .total_received_bytes
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+
+        if let Some(sender_tag) = sender_tag {
+            self.update_destination(sender_tag.into(), |counters| {
+                counters.received_messages += 1;
+                counters.received_bytes += bytes as u64;
+            })
+        }
+    }
+
+    pub(crate) fn record_reply_surbs_sent(&self, recipient: &Recipient, amount: usize) {
+        self.update_destination(recipient.into(), |counters| {
+            counters.reply_surbs_sent += amount as u64
+        })
+    }
+
+    pub(crate) fn record_reply_surbs_received(
+        &self,
+        sender_tag: AnonymousSenderTag,
+        amount: usize,
+    ) {
+        self.update_destination(sender_tag.into(), |counters| {
+            counters.reply_surbs_received += amount as u64
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use rand::rngs::OsRng;
+
+    fn random_recipient() -> Recipient {
+        Recipient::new(
+            *identity::KeyPair::new(&mut OsRng).public_key(),
+            *encryption::KeyPair::new(&mut OsRng).public_key(),
+            *identity::KeyPair::new(&mut OsRng).public_key(),
+        )
+    }
+
+    fn random_sender_tag() -> AnonymousSenderTag {
+        AnonymousSenderTag::new_random(&mut OsRng)
+    }
+
+    #[test]
+    fn traffic_is_accounted_for_per_destination_and_in_totals() {
+        let statistics = ClientStatistics::new();
+        let recipient = random_recipient();
+        let sender_tag = random_sender_tag();
+
+        statistics.record_sent((&recipient).into(), 100);
+        statistics.record_sent((&recipient).into(), 50);
+        statistics.record_retransmission((&recipient).into());
+        statistics.record_acknowledgement((&recipient).into(), Some(Duration::from_millis(10)));
+        statistics.record_acknowledgement((&recipient).into(), Some(Duration::from_millis(30)));
+        statistics.record_reply_surbs_sent(&recipient, 20);
+
+        statistics.record_received_packet();
+        statistics.record_received_message(Some(sender_tag), 42);
+        statistics.record_received_message(None, 8);
+        statistics.record_reply_surbs_received(sender_tag, 5);
+        statistics.record_sent(sender_tag.into(), 10);
+
+        let snapshot = statistics.snapshot();
+        assert_eq!(snapshot.total_sent_packets, 3);
+        assert_eq!(snapshot.total_sent_bytes, 160);
+        assert_eq!(snapshot.total_retransmitted_packets, 1);
+        assert_eq!(snapshot.total_acknowledged_packets, 2);
+        assert_eq!(snapshot.total_received_packets, 1);
+        assert_eq!(snapshot.total_received_messages, 2);
+        assert_eq!(snapshot.total_received_bytes, 50);
+        assert_eq!(snapshot.destinations.len(), 2);
+
+        let recipient_stats = statistics.destination((&recipient).into()).unwrap();
+        assert_eq!(recipient_stats.sent_packets, 2);
+        assert_eq!(recipient_stats.sent_bytes, 150);
+        assert_eq!(recipient_stats.retransmitted_packets, 1);
+        assert_eq!(recipient_stats.acknowledged_packets, 2);
+        assert_eq!(recipient_stats.reply_surbs_sent, 20);
+        assert_eq!(recipient_stats.reply_surbs_used, 0);
+        assert_eq!(
+            recipient_stats.average_ack_rtt,
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            recipient_stats.last_ack_rtt,
+            Some(Duration::from_millis(30))
+        );
+        assert_eq!(recipient_stats.min_ack_rtt, Some(Duration::from_millis(10)));
+        assert_eq!(recipient_stats.max_ack_rtt, Some(Duration::from_millis(30)));
+
+        let sender_stats = statistics.destination(sender_tag.into()).unwrap();
+        assert_eq!(sender_stats.received_messages, 1);
+        assert_eq!(sender_stats.received_bytes, 42);
+        assert_eq!(sender_stats.reply_surbs_received, 5);
+        assert_eq!(sender_stats.reply_surbs_used, 1);
+    }
+
+    #[test]
+    fn least_recently_active_destination_is_evicted_once_the_limit_is_reached() {
+        let statistics = ClientStatistics::new();
+        let sender_tags: Vec<_> = (0..MAX_TRACKED_DESTINATIONS)
+            .map(|_| random_sender_tag())
+            .collect();
+        for sender_tag in &sender_tags {
+            statistics.record_reply_surbs_received(*sender_tag, 1);
+        }
+
+        // the oldest destination becomes active again, so the second one is the stalest now
+        statistics.record_reply_surbs_received(sender_tags[0], 1);
+
+        let new_sender_tag = random_sender_tag();
+        statistics.record_reply_surbs_received(new_sender_tag, 1);
+
+        assert_eq!(
+            statistics.inner.destinations.len(),
+            MAX_TRACKED_DESTINATIONS
+        );
+        assert!(statistics.destination(sender_tags[1].into()).is_none());
+        assert_eq!(
+            statistics
+                .destination(sender_tags[0].into())
+                .unwrap()
+                .reply_surbs_received,
+            2
+        );
+        assert!(statistics.destination(new_sender_tag.into()).is_some());
+    }
+
+    #[test]
+    fn metrics_labels_dont_reveal_the_destination() {
+        let recipient = random_recipient();
+        let destination = StatisticsDestination::from(&recipient);
+
+        let label = destination.metrics_label();
+        assert_eq!(label.len(), METRICS_LABEL_DIGEST_LENGTH * 2);
+        assert_eq!(label, destination.metrics_label());
+        assert_ne!(
+            label,
+            StatisticsDestination::from(&random_recipient()).metrics_label()
+        );
+
+        let statistics = ClientStatistics::new();
+        statistics.record_sent(destination, 100);
+        let metrics = statistics.snapshot().to_prometheus_text();
+
+        assert!(!metrics.contains(&recipient.to_string()));
+        assert!(metrics.contains(&format!(
+            "nym_client_destination_sent_packets_total{{kind=\"recipient\",destination=\"{label}\"}} 1\n"
+        )));
+        assert!(metrics.contains("nym_client_sent_bytes_total 100\n"));
+    }
+}
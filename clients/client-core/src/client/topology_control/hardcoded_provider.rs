@@ -0,0 +1,138 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::client::topology_control::TopologyProvider;
+use async_trait::async_trait;
+use nym_topology::NymTopology;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::ClientCoreError;
+#[cfg(not(target_arch = "wasm32"))]
+use nym_mixnet_contract_common::{mixnode::MixNodeDetails, GatewayBond};
+#[cfg(not(target_arch = "wasm32"))]
+use nym_topology::nym_topology_from_detailed;
+#[cfg(not(target_arch = "wasm32"))]
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+/// On-disk representation of a network topology, i.e. the same data as returned by
+/// the nym-api `mixnodes/active` and `gateways` endpoints.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TopologyFile {
+    pub mixnodes: Vec<MixNodeDetails>,
+    pub gateways: Vec<GatewayBond>,
+}
+
+/// [`TopologyProvider`] that always returns the same, pre-defined, network topology.
+/// Useful for local testnets, for pinning particular routes while debugging
+/// or for operating without access to the nym-api.
+#[derive(Debug, Clone)]
+pub struct HardcodedTopologyProvider {
+    topology: NymTopology,
+}
+
+impl HardcodedTopologyProvider {
+    pub fn new(topology: NymTopology) -> Self {
+        HardcodedTopologyProvider { topology }
+    }
+
+    /// Attempts to load the topology from a json file with the structure of [`TopologyFile`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ClientCoreError> {
+        let content = std::fs::read_to_string(path)?;
+        let file: TopologyFile = serde_json::from_str(&content)
+            .map_err(|source| ClientCoreError::MalformedTopologyFile { source })?;
+
+        Ok(Self::new(nym_topology_from_detailed(
+            file.mixnodes,
+            file.gateways,
+        )))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TopologyProvider for HardcodedTopologyProvider {
+    async fn get_new_topology(&mut self) -> Option<NymTopology> {
+        Some(self.topology.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use nym_topology::gateway;
+    use std::collections::HashMap;
+    use std::io::Write;
+
+    fn gateway_node() -> gateway::Node {
+        gateway::Node {
+            owner: "foomp".to_string(),
+            stake: 123,
+            location: "unknown".to_string(),
+            host: "1.2.3.4".parse().unwrap(),
+            mix_host: "1.2.3.4:1789".parse().unwrap(),
+            clients_port: 9000,
+            identity_key: identity::PublicKey::from_base58_string(
+                "3ebjp1Fb9hdcS1AR6AZihgeJiMHkB5jjJUsvqNnfQwU7",
+            )
+            .unwrap(),
+            sphinx_key: encryption::PublicKey::from_base58_string(
+                "C7cown6dYCLZpLiMFC1PaBmhvLvmJmLDJGeRTbPD45bX",
+            )
+            .unwrap(),
+            upcoming_sphinx_key: None,
+            version: "1.1.0".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn always_returns_the_provided_topology() {
+        let topology = NymTopology::new(HashMap::new(), vec![gateway_node()]);
+        let mut provider = HardcodedTopologyProvider::new(topology);
+
+        for _ in 0..3 {
+            let returned = provider.get_new_topology().await.unwrap();
+            assert_eq!(returned.gateways().len(), 1);
+            assert_eq!(
+                returned.gateways()[0].identity_key,
+                gateway_node().identity_key
+            );
+            assert!(returned.mixes().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn can_be_loaded_from_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let content = serde_json::to_string(&TopologyFile::default()).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+
+        let mut provider = HardcodedTopologyProvider::new_from_file(file.path()).unwrap();
+        let topology = provider.get_new_topology().await.unwrap();
+        assert!(topology.gateways().is_empty());
+        assert!(topology.mixes().is_empty());
+    }
+
+    #[test]
+    fn malformed_file_is_rejected() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"{\"mixnodes\": 42}").unwrap();
+
+        assert!(matches!(
+            HardcodedTopologyProvider::new_from_file(file.path()),
+            Err(ClientCoreError::MalformedTopologyFile { .. })
+        ));
+    }
+
+    #[test]
+    fn missing_file_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(
+            HardcodedTopologyProvider::new_from_file(dir.path().join("topology.json")).is_err()
+        );
+    }
+}
@@ -2,37 +2,114 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::spawn_future;
+use async_trait::async_trait;
 use futures::StreamExt;
 use log::*;
 use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::params::DEFAULT_NUM_MIX_HOPS;
-use nym_topology::{nym_topology_from_detailed, NymTopology, NymTopologyError};
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use nym_topology::{NymTopology, NymTopologyError};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{RwLock, RwLockReadGuard};
-use url::Url;
+
+pub use hardcoded_provider::HardcodedTopologyProvider;
+#[cfg(not(target_arch = "wasm32"))]
+pub use hardcoded_provider::TopologyFile;
+pub use nym_api_provider::NymApiTopologyProvider;
+
+mod hardcoded_provider;
+mod nym_api_provider;
+
+/// Source of the network topology used by the [`TopologyRefresher`].
+///
+/// By default, the topology is obtained from the nym-api (see [`NymApiTopologyProvider`]),
+/// but users can supply their own implementation, for example to run against
+/// a hand-crafted local testnet or to pin particular routes while debugging.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait TopologyProvider: Send {
+    /// Retrieves the most up to date network topology.
+    /// Returning `None` indicates a (possibly temporary) failure, in which case the refresher
+    /// will attempt to keep on using the previously obtained topology.
+    async fn get_new_topology(&mut self) -> Option<NymTopology>;
+}
 
 // I'm extremely curious why compiler NEVER complained about lack of Debug here before
 #[derive(Debug)]
-pub struct TopologyAccessorInner(Option<NymTopology>);
+pub struct TopologyAccessorInner {
+    topology: Option<NymTopology>,
+
+    // summary of the routing information of the current topology alongside the number of times
+    // it has changed, so that anything constructed with an older topology could be told apart
+    routing_fingerprint: Option<u64>,
+    generation: u64,
+}
 
 impl AsRef<Option<NymTopology>> for TopologyAccessorInner {
     fn as_ref(&self) -> &Option<NymTopology> {
-        &self.0
+        &self.topology
     }
 }
 
 impl TopologyAccessorInner {
     fn new() -> Self {
-        TopologyAccessorInner(None)
+        TopologyAccessorInner {
+            topology: None,
+            routing_fingerprint: None,
+            generation: 0,
+        }
     }
 
     fn update(&mut self, new: Option<NymTopology>) {
-        self.0 = new;
+        let routing_fingerprint = new.as_ref().map(routing_fingerprint);
+        if routing_fingerprint != self.routing_fingerprint {
+            self.routing_fingerprint = routing_fingerprint;
+            self.generation += 1;
+        }
+        self.topology = new;
     }
+
+    /// Number of times the routing information (i.e. the nodes, their addresses or their sphinx keys)
+    /// of the topology has changed. Packets constructed with an older generation might no longer
+    /// be routable.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+// hash of everything that ends up in the constructed sphinx packets
+fn routing_fingerprint(topology: &NymTopology) -> u64 {
+    let mut nodes = topology
+        .mixes()
+        .iter()
+        .flat_map(|(layer, mixes)| {
+            mixes.iter().map(move |mix| {
+                (
+                    *layer,
+                    mix.identity_key.to_bytes(),
+                    mix.sphinx_key.to_bytes(),
+                    mix.mix_host,
+                )
+            })
+        })
+        .chain(topology.gateways().iter().map(|gateway| {
+            (
+                0,
+                gateway.identity_key.to_bytes(),
+                gateway.sphinx_key.to_bytes(),
+                gateway.mix_host,
+            )
+        }))
+        .collect::<Vec<_>>();
+    // the mixnodes are stored in a `HashMap`, so make sure the order is deterministic
+    nodes.sort_unstable();
+
+    let mut hasher = DefaultHasher::new();
+    nodes.hash(&mut hasher);
+    hasher.finish()
 }
 
 pub struct TopologyReadPermit<'a> {
@@ -122,7 +199,7 @@ impl TopologyAccessor {
     // only used by the client at startup to get a slightly more reasonable error message
     // (currently displays as unused because health checker is disabled due to required changes)
     pub async fn ensure_is_routable(&self) -> Result<(), NymTopologyError> {
-        match &self.inner.read().await.0 {
+        match &self.inner.read().await.topology {
             None => Err(NymTopologyError::EmptyNetworkTopology),
             Some(ref topology) => topology.ensure_can_construct_path_through(DEFAULT_NUM_MIX_HOPS),
         }
@@ -136,156 +213,40 @@ impl Default for TopologyAccessor {
 }
 
 pub struct TopologyRefresherConfig {
-    nym_api_urls: Vec<Url>,
     refresh_rate: Duration,
-    client_version: String,
 }
 
 impl TopologyRefresherConfig {
-    pub fn new(nym_api_urls: Vec<Url>, refresh_rate: Duration, client_version: String) -> Self {
-        TopologyRefresherConfig {
-            nym_api_urls,
-            refresh_rate,
-            client_version,
-        }
+    pub fn new(refresh_rate: Duration) -> Self {
+        TopologyRefresherConfig { refresh_rate }
     }
 }
 
 pub struct TopologyRefresher {
-    validator_client: validator_client::client::NymApiClient,
-    client_version: String,
-
-    nym_api_urls: Vec<Url>,
+    topology_provider: Box<dyn TopologyProvider>,
     topology_accessor: TopologyAccessor,
     refresh_rate: Duration,
 
-    currently_used_api: usize,
     was_latest_valid: bool,
 }
 
 impl TopologyRefresher {
-    pub fn new(mut cfg: TopologyRefresherConfig, topology_accessor: TopologyAccessor) -> Self {
-        cfg.nym_api_urls.shuffle(&mut thread_rng());
-
+    pub fn new(
+        cfg: TopologyRefresherConfig,
+        topology_accessor: TopologyAccessor,
+        topology_provider: Box<dyn TopologyProvider>,
+    ) -> Self {
         TopologyRefresher {
-            validator_client: validator_client::client::NymApiClient::new(
-                cfg.nym_api_urls[0].clone(),
-            ),
-            client_version: cfg.client_version,
-            nym_api_urls: cfg.nym_api_urls,
+            topology_provider,
             topology_accessor,
             refresh_rate: cfg.refresh_rate,
-            currently_used_api: 0,
             was_latest_valid: true,
         }
     }
 
-    fn use_next_nym_api(&mut self) {
-        if self.nym_api_urls.len() == 1 {
-            warn!("There's only a single nym API available - it won't be possible to use a different one");
-            return;
-        }
-
-        self.currently_used_api = (self.currently_used_api + 1) % self.nym_api_urls.len();
-        self.validator_client
-            .change_nym_api(self.nym_api_urls[self.currently_used_api].clone())
-    }
-
-    /// Verifies whether nodes a reasonably distributed among all mix layers.
-    ///
-    /// In ideal world we would have 33% nodes on layer 1, 33% on layer 2 and 33% on layer 3.
-    /// However, this is a rather unrealistic expectation, instead we check whether there exists
-    /// a layer with more than 66% of nodes or with fewer than 15% and if so, we trigger a failure.
-    ///
-    /// # Arguments
-    ///
-    /// * `topology`: active topology constructed from validator api data
-    fn check_layer_distribution(&self, active_topology: &NymTopology) -> bool {
-        let mixes = active_topology.mixes();
-        let mixnodes_count = active_topology.num_mixnodes();
-
-        if active_topology.gateways().is_empty() {
-            return false;
-        }
-
-        // trivial check to see if have at least a single node on each layer (regardless of active set size)
-        if mixes.get(&1).is_none() || mixes.get(&2).is_none() || mixes.get(&3).is_none() {
-            return false;
-        }
-
-        let upper_bound = (mixnodes_count as f32 * 0.66) as usize;
-        let lower_bound = (mixnodes_count as f32 * 0.15) as usize;
-
-        let layer1 = mixes.get(&1).unwrap().len();
-        let layer2 = mixes.get(&2).unwrap().len();
-        let layer3 = mixes.get(&3).unwrap().len();
-
-        if layer1 < lower_bound || layer1 > upper_bound {
-            warn!(
-                "nodes: {}, layer1: {}, layer2: {}, layer3: {}",
-                mixnodes_count, layer1, layer2, layer3
-            );
-            return false;
-        }
-
-        if layer2 < lower_bound || layer2 > upper_bound {
-            warn!(
-                "nodes: {}, layer1: {}, layer2: {}, layer3: {}",
-                mixnodes_count, layer1, layer2, layer3
-            );
-            return false;
-        }
-
-        if layer3 < lower_bound || layer3 > upper_bound {
-            warn!(
-                "nodes: {}, layer1: {}, layer2: {}, layer3: {}",
-                mixnodes_count, layer1, layer2, layer3
-            );
-            return false;
-        }
-
-        true
-    }
-
-    async fn get_current_compatible_topology(&self) -> Option<NymTopology> {
-        // TODO: optimization for the future:
-        // only refresh mixnodes on timer and refresh gateways only when
-        // we have to send to a new, unknown, gateway
-
-        let mixnodes = match self.validator_client.get_cached_active_mixnodes().await {
-            Err(err) => {
-                error!("failed to get network mixnodes - {err}");
-                return None;
-            }
-            Ok(mixes) => mixes,
-        };
-
-        let gateways = match self.validator_client.get_cached_gateways().await {
-            Err(err) => {
-                error!("failed to get network gateways - {err}");
-                return None;
-            }
-            Ok(gateways) => gateways,
-        };
-
-        let topology = nym_topology_from_detailed(mixnodes, gateways)
-            .filter_system_version(&self.client_version);
-
-        if !self.check_layer_distribution(&topology) {
-            warn!("The current filtered active topology has extremely skewed layer distribution. It cannot be used.");
-            None
-        } else {
-            Some(topology)
-        }
-    }
-
     pub async fn refresh(&mut self) {
         trace!("Refreshing the topology");
-        let new_topology = self.get_current_compatible_topology().await;
-
-        if new_topology.is_none() {
-            self.use_next_nym_api();
-        }
+        let new_topology = self.topology_provider.get_new_topology().await;
 
         if new_topology.is_none() && self.was_latest_valid {
             // if we failed to grab this topology, but the one before it was alright, let's assume
@@ -334,3 +295,56 @@ impl TopologyRefresher {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::{encryption, identity};
+    use nym_mixnet_contract_common::Layer;
+    use nym_topology::{mix, UpcomingSphinxKey};
+    use rand::rngs::OsRng;
+    use std::collections::HashMap;
+
+    fn mix_node(mix_id: u32, layer: Layer) -> mix::Node {
+        mix::Node {
+            mix_id,
+            owner: format!("owner{mix_id}"),
+            host: "10.0.0.1".parse().unwrap(),
+            mix_host: "10.0.0.1:1789".parse().unwrap(),
+            identity_key: *identity::KeyPair::new(&mut OsRng).public_key(),
+            sphinx_key: *encryption::KeyPair::new(&mut OsRng).public_key(),
+            upcoming_sphinx_key: None,
+            layer,
+            version: "1.1.9".to_string(),
+        }
+    }
+
+    #[test]
+    fn activating_announced_sphinx_key_starts_new_generation() {
+        let mut announcing = mix_node(1, Layer::One);
+        announcing.upcoming_sphinx_key = Some(UpcomingSphinxKey {
+            sphinx_key: *encryption::KeyPair::new(&mut OsRng).public_key(),
+            valid_from_epoch: 10,
+        });
+
+        let mut mixes = HashMap::new();
+        mixes.insert(1, vec![announcing]);
+        mixes.insert(2, vec![mix_node(2, Layer::Two)]);
+        mixes.insert(3, vec![mix_node(3, Layer::Three)]);
+        let mut topology = NymTopology::new(mixes, vec![]);
+
+        let mut inner = TopologyAccessorInner::new();
+        inner.update(Some(topology.clone()));
+        assert_eq!(inner.generation(), 1);
+
+        // refreshing the topology before the announced key becomes active changes nothing
+        topology.use_sphinx_keys_for_epoch(9);
+        inner.update(Some(topology.clone()));
+        assert_eq!(inner.generation(), 1);
+
+        // but once it's active, anything constructed with the old key is outdated
+        topology.use_sphinx_keys_for_epoch(10);
+        inner.update(Some(topology));
+        assert_eq!(inner.generation(), 2);
+    }
+}
@@ -3,6 +3,7 @@
 
 use config::defaults::NymNetworkDetails;
 use config::{NymConfig, OptionalSet, DB_FILE_NAME};
+use nym_sphinx::compression::MessageCompression;
 use nym_sphinx::params::PacketSize;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
@@ -54,12 +55,22 @@ const DEFAULT_MAXIMUM_REPLY_SURB_AGE: Duration = Duration::from_secs(12 * 60 * 6
 // 24 hours
 const DEFAULT_MAXIMUM_REPLY_KEY_AGE: Duration = Duration::from_secs(24 * 60 * 60);
 
+// packet preparation related:
+const DEFAULT_PACKET_PREPARATION_WORKERS: usize = 4;
+const DEFAULT_PRECOMPUTED_COVER_PACKETS: usize = 16;
+
 pub fn missing_string_value() -> String {
     MISSING_VALUE.to_string()
 }
 
 pub trait ClientCoreConfigTrait {
     fn get_gateway_endpoint(&self) -> &GatewayEndpointConfig;
+
+    fn set_gateway_endpoints(
+        &mut self,
+        gateway_endpoint: GatewayEndpointConfig,
+        standby_gateways: Vec<GatewayEndpointConfig>,
+    );
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
@@ -77,6 +88,15 @@ impl<T> ClientCoreConfigTrait for Config<T> {
     fn get_gateway_endpoint(&self) -> &GatewayEndpointConfig {
         &self.client.gateway_endpoint
     }
+
+    fn set_gateway_endpoints(
+        &mut self,
+        gateway_endpoint: GatewayEndpointConfig,
+        standby_gateways: Vec<GatewayEndpointConfig>,
+    ) {
+        self.set_gateway_endpoint(gateway_endpoint);
+        self.set_standby_gateways(standby_gateways);
+    }
 }
 
 impl<T> OptionalSet for Config<T> where T: NymConfig {}
@@ -159,6 +179,28 @@ impl<T> Config<T> {
                 self::Client::<T>::default_reply_surb_database_path(id);
         }
 
+        if self
+            .client
+            .reply_surb_blob_storage_directory
+            .as_os_str()
+            .is_empty()
+        {
+            changes_made = true;
+            self.client.reply_surb_blob_storage_directory =
+                self::Client::<T>::default_reply_surb_blob_storage_directory(id);
+        }
+
+        if self
+            .client
+            .received_messages_database_path
+            .as_os_str()
+            .is_empty()
+        {
+            changes_made = true;
+            self.client.received_messages_database_path =
+                self::Client::<T>::default_received_messages_database_path(id);
+        }
+
         if self.client.database_path.as_os_str().is_empty() {
             changes_made = true;
             self.client.database_path = self::Client::<T>::default_database_path(id);
@@ -180,6 +222,23 @@ impl<T> Config<T> {
         self
     }
 
+    pub fn set_standby_gateways(&mut self, standby_gateways: Vec<GatewayEndpointConfig>) {
+        self.client.standby_gateways = standby_gateways;
+    }
+
+    pub fn with_standby_gateways(mut self, standby_gateways: Vec<GatewayEndpointConfig>) -> Self {
+        self.client.standby_gateways = standby_gateways;
+        self
+    }
+
+    pub fn set_persist_received_messages(&mut self, persist_received_messages: bool) {
+        self.client.persist_received_messages = persist_received_messages;
+    }
+
+    pub fn set_encrypted_storage(&mut self, encrypted_storage: bool) {
+        self.client.encrypted_storage = encrypted_storage;
+    }
+
     pub fn with_gateway_id<S: Into<String>>(&mut self, id: S) {
         self.client.gateway_endpoint.gateway_id = id.into();
     }
@@ -293,6 +352,10 @@ impl<T> Config<T> {
         &self.client.gateway_endpoint
     }
 
+    pub fn get_standby_gateways(&self) -> &[GatewayEndpointConfig] {
+        &self.client.standby_gateways
+    }
+
     pub fn get_database_path(&self) -> PathBuf {
         self.client.database_path.clone()
     }
@@ -301,6 +364,22 @@ impl<T> Config<T> {
         self.client.reply_surb_database_path.clone()
     }
 
+    pub fn get_reply_surb_blob_storage_directory(&self) -> PathBuf {
+        self.client.reply_surb_blob_storage_directory.clone()
+    }
+
+    pub fn get_persist_received_messages(&self) -> bool {
+        self.client.persist_received_messages
+    }
+
+    pub fn get_received_messages_database_path(&self) -> PathBuf {
+        self.client.received_messages_database_path.clone()
+    }
+
+    pub fn get_encrypted_storage(&self) -> bool {
+        self.client.encrypted_storage
+    }
+
     pub fn get_version(&self) -> &str {
         &self.client.version
     }
@@ -358,6 +437,10 @@ impl<T> Config<T> {
         self.debug.use_extended_packet_size
     }
 
+    pub fn get_use_outfox(&self) -> bool {
+        self.debug.use_outfox
+    }
+
     pub fn get_minimum_reply_surb_storage_threshold(&self) -> usize {
         self.debug.minimum_reply_surb_storage_threshold
     }
@@ -491,6 +574,12 @@ pub struct Client<T> {
     /// Information regarding how the client should send data to gateway.
     gateway_endpoint: GatewayEndpointConfig,
 
+    /// Gateways the client is going to register with, in order, if it loses the connection to
+    /// its current gateway and fails to re-establish it.
+    /// Note that the shared keys derived with the standby gateways are not persisted.
+    #[serde(default)]
+    standby_gateways: Vec<GatewayEndpointConfig>,
+
     /// Path to the database containing bandwidth credentials of this client.
     database_path: PathBuf,
 
@@ -501,6 +590,25 @@ pub struct Client<T> {
     #[serde(default)]
     reply_surb_database_path: PathBuf,
 
+    /// Path to the directory holding the reply data blobs of the clients that can't use
+    /// the sqlite store (i.e. the ones running on mobile).
+    #[serde(default)]
+    reply_surb_blob_storage_directory: PathBuf,
+
+    /// Specifies whether received messages should be persisted until the application explicitly
+    /// acknowledges them, so that they would get redelivered after the client restarts.
+    #[serde(default)]
+    persist_received_messages: bool,
+
+    /// Path to the persistent store for received messages that have not yet been acknowledged.
+    #[serde(default)]
+    received_messages_database_path: PathBuf,
+
+    /// Specifies whether the private keys and the reply surb storage are encrypted with
+    /// a passphrase that has to be provided on every run.
+    #[serde(default)]
+    encrypted_storage: bool,
+
     /// nym_home_directory specifies absolute path to the home nym Clients directory.
     /// It is expected to use default value and hence .toml file should not redefine this field.
     nym_root_directory: PathBuf,
@@ -541,8 +649,13 @@ impl<T: NymConfig> Default for Client<T> {
             gateway_shared_key_file: Default::default(),
             ack_key_file: Default::default(),
             gateway_endpoint: Default::default(),
+            standby_gateways: Vec::new(),
             database_path: Default::default(),
             reply_surb_database_path: Default::default(),
+            reply_surb_blob_storage_directory: Default::default(),
+            persist_received_messages: false,
+            received_messages_database_path: Default::default(),
+            encrypted_storage: false,
             nym_root_directory: T::default_root_directory(),
             super_struct: Default::default(),
         }
@@ -578,6 +691,14 @@ impl<T: NymConfig> Client<T> {
         T::default_data_directory(id).join("persistent_reply_store.sqlite")
     }
 
+    fn default_reply_surb_blob_storage_directory(id: &str) -> PathBuf {
+        T::default_data_directory(id).join("reply_surb_blobs")
+    }
+
+    fn default_received_messages_database_path(id: &str) -> PathBuf {
+        T::default_data_directory(id).join("received_messages_store.sqlite")
+    }
+
     fn default_database_path(id: &str) -> PathBuf {
         T::default_data_directory(id).join(DB_FILE_NAME)
     }
@@ -654,6 +775,11 @@ pub struct DebugConfig {
     /// Controls whether the sent sphinx packet use a NON-DEFAULT bigger size.
     pub use_extended_packet_size: Option<ExtendedPacketSize>,
 
+    /// Controls whether the sent packets should use the outfox format rather than sphinx.
+    /// Note that replies sent with reply SURBs and acknowledgements are always going to use sphinx
+    /// and that if set, it takes precedence over `use_extended_packet_size`.
+    pub use_outfox: bool,
+
     /// Defines the minimum number of reply surbs the client wants to keep in its storage at all times.
     /// It can only allow to go below that value if its to request additional reply surbs.
     pub minimum_reply_surb_storage_threshold: usize,
@@ -689,6 +815,31 @@ pub struct DebugConfig {
     /// This is going to be superseded by key rotation once implemented.
     #[serde(with = "humantime_serde")]
     pub maximum_reply_key_age: Duration,
+
+    /// Number of dedicated threads used for constructing sphinx packets outside of the async runtime,
+    /// so that large messages wouldn't stall the sending of other packets.
+    /// If set to 0, all packets are constructed inline. It has no effect in wasm.
+    pub packet_preparation_workers: usize,
+
+    /// Number of loop cover packets the real traffic stream keeps constructed ahead of time,
+    /// so that it wouldn't have to create them whenever it runs out of real packets to send.
+    /// It has no effect if [Self::packet_preparation_workers] is set to 0.
+    pub precomputed_cover_packets: usize,
+
+    /// Ratio of parity to data fragments attached to each sent message, so that the recipient could
+    /// reconstruct it despite losing some of the packets, without waiting for the retransmissions.
+    /// For example 0.1 means one parity packet for every (started) 10 data packets.
+    /// Note that a single set (up to 255 packets) can have at most 256 data and parity packets combined.
+    /// Every parity packet of a reply uses up another reply SURB. Parity is never attached to control
+    /// replies (such as requests for more reply SURBs) or outfox packets. If set to 0, it is disabled.
+    pub forward_error_correction_redundancy: f64,
+
+    /// Preferred compression algorithm applied to the content of the sent messages before they get chunked.
+    /// Messages are only compressed for the peers that have advertised support for it, i.e. replies
+    /// to clients whose repliable messages advertised it and messages to clients that advertised it
+    /// when requesting additional reply SURBs. Deflate is used instead of zstd for the peers
+    /// that only support the former, and on clients built without the `zstd-compression` feature (such as wasm).
+    pub message_compression: Option<CompressionAlgorithm>,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -699,6 +850,13 @@ pub enum ExtendedPacketSize {
     Extended32,
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    Deflate,
+    Zstd,
+}
+
 impl Default for DebugConfig {
     fn default() -> Self {
         DebugConfig {
@@ -714,6 +872,7 @@ impl Default for DebugConfig {
             disable_loop_cover_traffic_stream: false,
             disable_main_poisson_packet_distribution: false,
             use_extended_packet_size: None,
+            use_outfox: false,
             minimum_reply_surb_storage_threshold: DEFAULT_MINIMUM_REPLY_SURB_STORAGE_THRESHOLD,
             maximum_reply_surb_storage_threshold: DEFAULT_MAXIMUM_REPLY_SURB_STORAGE_THRESHOLD,
             minimum_reply_surb_request_size: DEFAULT_MINIMUM_REPLY_SURB_REQUEST_SIZE,
@@ -724,6 +883,21 @@ impl Default for DebugConfig {
             maximum_reply_surb_drop_waiting_period: DEFAULT_MAXIMUM_REPLY_SURB_DROP_WAITING_PERIOD,
             maximum_reply_surb_age: DEFAULT_MAXIMUM_REPLY_SURB_AGE,
             maximum_reply_key_age: DEFAULT_MAXIMUM_REPLY_KEY_AGE,
+            packet_preparation_workers: DEFAULT_PACKET_PREPARATION_WORKERS,
+            precomputed_cover_packets: DEFAULT_PRECOMPUTED_COVER_PACKETS,
+            forward_error_correction_redundancy: 0.0,
+            message_compression: None,
+        }
+    }
+}
+
+impl DebugConfig {
+    /// Returns the non-default packet size that should be used for real and cover traffic, if any.
+    pub fn custom_packet_size(&self) -> Option<PacketSize> {
+        if self.use_outfox {
+            Some(PacketSize::OutfoxRegularPacket)
+        } else {
+            self.use_extended_packet_size.map(Into::into)
         }
     }
 }
@@ -737,3 +911,12 @@ impl From<ExtendedPacketSize> for PacketSize {
         }
     }
 }
+
+impl From<CompressionAlgorithm> for MessageCompression {
+    fn from(algorithm: CompressionAlgorithm) -> MessageCompression {
+        match algorithm {
+            CompressionAlgorithm::Deflate => MessageCompression::Deflate,
+            CompressionAlgorithm::Zstd => MessageCompression::Zstd,
+        }
+    }
+}
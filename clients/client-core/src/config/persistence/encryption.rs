@@ -0,0 +1,276 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Passphrase-based encryption of the client keys and the reply storage.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use nym_pemstore::traits::KeyCipher;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+const MEMORY_COST: u32 = 16 * 1024;
+const ITERATIONS: u32 = 3;
+const PARALLELISM: u32 = 1;
+const KEY_LEN: usize = 32;
+
+// as per Argon2 recommendation
+pub const SALT_LEN: usize = 16;
+
+// AES256GCM Nonce is 96 bit long.
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("failed to derive the encryption key from the passphrase: {details}")]
+    KeyDerivationFailure { details: String },
+
+    #[error("failed to encrypt the data")]
+    EncryptionFailure,
+
+    #[error(
+        "failed to decrypt the data - either the passphrase is invalid or the data got corrupted"
+    )]
+    DecryptionFailure,
+
+    #[error("the encrypted data is too short to be valid")]
+    MalformedCiphertext,
+}
+
+impl From<EncryptionError> for io::Error {
+    fn from(err: EncryptionError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Passphrase protecting the client keys and the reply storage. It gets zeroized on drop.
+#[derive(Clone)]
+pub struct StoragePassphrase(Zeroizing<String>);
+
+impl StoragePassphrase {
+    pub fn new<S: Into<String>>(passphrase: S) -> Self {
+        StoragePassphrase(Zeroizing::new(passphrase.into()))
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+/// AES-256-GCM cipher with the key derived from a passphrase and a salt using Argon2id.
+/// The derivation is deliberately expensive, so the same cipher should be reused
+/// for encrypting multiple values.
+pub struct StorageCipher {
+    cipher: Aes256Gcm,
+    salt: [u8; SALT_LEN],
+}
+
+// make sure the key material never ends up in the logs
+impl Debug for StorageCipher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StorageCipher").finish_non_exhaustive()
+    }
+}
+
+impl StorageCipher {
+    pub fn new(
+        passphrase: &StoragePassphrase,
+        salt: [u8; SALT_LEN],
+    ) -> Result<Self, EncryptionError> {
+        // this can only fail if output length is either smaller than 4 or larger than 2^32 - 1 which is not the case here
+        let params = Params::new(MEMORY_COST, ITERATIONS, PARALLELISM, Some(KEY_LEN)).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        argon2
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|err| EncryptionError::KeyDerivationFailure {
+                details: err.to_string(),
+            })?;
+
+        Ok(StorageCipher {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref())),
+            salt,
+        })
+    }
+
+    pub fn new_with_random_salt(passphrase: &StoragePassphrase) -> Result<Self, EncryptionError> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::new(passphrase, salt)
+    }
+
+    pub fn salt(&self) -> [u8; SALT_LEN] {
+        self.salt
+    }
+
+    /// Encrypts the data under a fresh random nonce and returns `nonce || ciphertext`.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| EncryptionError::EncryptionFailure)?;
+
+        Ok(nonce.into_iter().chain(ciphertext).collect())
+    }
+
+    /// Decrypts `nonce || ciphertext`. The recovered plaintext gets zeroized on drop.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, EncryptionError> {
+        if data.len() < NONCE_LEN {
+            return Err(EncryptionError::MalformedCiphertext);
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map(Zeroizing::new)
+            .map_err(|_| EncryptionError::DecryptionFailure)
+    }
+}
+
+// every key file gets its own salt, so the stored data is `salt || nonce || ciphertext`
+impl KeyCipher for StoragePassphrase {
+    fn encrypt(&self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let cipher = StorageCipher::new_with_random_salt(self)?;
+        let ciphertext = cipher.encrypt(plaintext)?;
+
+        Ok(cipher.salt().into_iter().chain(ciphertext).collect())
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> io::Result<Zeroizing<Vec<u8>>> {
+        if ciphertext.len() < SALT_LEN {
+            return Err(EncryptionError::MalformedCiphertext.into());
+        }
+        let (salt, ciphertext) = ciphertext.split_at(SALT_LEN);
+
+        // the length has just been checked
+        let salt = salt.try_into().unwrap();
+        Ok(StorageCipher::new(self, salt)?.decrypt(ciphertext)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase() -> StoragePassphrase {
+        StoragePassphrase::new("correct horse battery staple")
+    }
+
+    #[test]
+    fn storage_cipher_roundtrip() {
+        let cipher = StorageCipher::new_with_random_salt(&passphrase()).unwrap();
+        let ciphertext = cipher.encrypt(b"my secret reply key").unwrap();
+        assert_ne!(&ciphertext[NONCE_LEN..], b"my secret reply key");
+
+        let plaintext = cipher.decrypt(&ciphertext).unwrap();
+        assert_eq!(plaintext.as_slice(), b"my secret reply key");
+
+        // the same passphrase and salt result in the same key
+        let recreated = StorageCipher::new(&passphrase(), cipher.salt()).unwrap();
+        let plaintext = recreated.decrypt(&ciphertext).unwrap();
+        assert_eq!(plaintext.as_slice(), b"my secret reply key");
+    }
+
+    #[test]
+    fn every_encryption_uses_fresh_nonce() {
+        let cipher = StorageCipher::new_with_random_salt(&passphrase()).unwrap();
+        let first = cipher.encrypt(b"data").unwrap();
+        let second = cipher.encrypt(b"data").unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn storage_cipher_rejects_wrong_passphrase_and_salt() {
+        let cipher = StorageCipher::new_with_random_salt(&passphrase()).unwrap();
+        let ciphertext = cipher.encrypt(b"data").unwrap();
+
+        let wrong_passphrase =
+            StorageCipher::new(&StoragePassphrase::new("wrong"), cipher.salt()).unwrap();
+        assert!(matches!(
+            wrong_passphrase.decrypt(&ciphertext),
+            Err(EncryptionError::DecryptionFailure)
+        ));
+
+        let mut salt = cipher.salt();
+        salt[0] ^= 1;
+        let wrong_salt = StorageCipher::new(&passphrase(), salt).unwrap();
+        assert!(matches!(
+            wrong_salt.decrypt(&ciphertext),
+            Err(EncryptionError::DecryptionFailure)
+        ));
+    }
+
+    #[test]
+    fn storage_cipher_rejects_tampered_and_truncated_data() {
+        let cipher = StorageCipher::new_with_random_salt(&passphrase()).unwrap();
+        let ciphertext = cipher.encrypt(b"data").unwrap();
+
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert!(
+                cipher.decrypt(&tampered).is_err(),
+                "byte {i} was not authenticated"
+            );
+        }
+
+        for len in 0..NONCE_LEN {
+            assert!(matches!(
+                cipher.decrypt(&ciphertext[..len]),
+                Err(EncryptionError::MalformedCiphertext)
+            ));
+        }
+        for len in NONCE_LEN..ciphertext.len() {
+            assert!(matches!(
+                cipher.decrypt(&ciphertext[..len]),
+                Err(EncryptionError::DecryptionFailure)
+            ));
+        }
+    }
+
+    #[test]
+    fn key_cipher_roundtrip() {
+        let ciphertext = KeyCipher::encrypt(&passphrase(), b"private key bytes").unwrap();
+        assert_eq!(ciphertext.len(), SALT_LEN + NONCE_LEN + 17 + 16);
+
+        let plaintext = KeyCipher::decrypt(&passphrase(), &ciphertext).unwrap();
+        assert_eq!(plaintext.as_slice(), b"private key bytes");
+
+        // every key gets its own salt
+        let another = KeyCipher::encrypt(&passphrase(), b"private key bytes").unwrap();
+        assert_ne!(ciphertext[..SALT_LEN], another[..SALT_LEN]);
+    }
+
+    #[test]
+    fn key_cipher_rejects_wrong_passphrase() {
+        let ciphertext = KeyCipher::encrypt(&passphrase(), b"private key bytes").unwrap();
+        let err = KeyCipher::decrypt(&StoragePassphrase::new("wrong"), &ciphertext).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn key_cipher_rejects_tampered_and_truncated_data() {
+        let ciphertext = KeyCipher::encrypt(&passphrase(), b"private key bytes").unwrap();
+
+        let mut tampered_salt = ciphertext.clone();
+        tampered_salt[0] ^= 1;
+        assert!(KeyCipher::decrypt(&passphrase(), &tampered_salt).is_err());
+
+        let mut tampered_ciphertext = ciphertext.clone();
+        *tampered_ciphertext.last_mut().unwrap() ^= 1;
+        assert!(KeyCipher::decrypt(&passphrase(), &tampered_ciphertext).is_err());
+
+        assert!(KeyCipher::decrypt(&passphrase(), &ciphertext[..SALT_LEN - 1]).is_err());
+        assert!(KeyCipher::decrypt(&passphrase(), &ciphertext[..SALT_LEN + NONCE_LEN]).is_err());
+        assert!(KeyCipher::decrypt(&passphrase(), &ciphertext[..ciphertext.len() - 1]).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2022 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::client::received_buffer::storage::ReceivedStorageError;
 use gateway_client::error::GatewayClientError;
 use nym_crypto::asymmetric::identity::Ed25519RecoveryError;
 use nym_topology::NymTopologyError;
@@ -38,6 +39,12 @@ pub enum ClientCoreError {
     #[error("The current network topology seem to be insufficient to route any packets through")]
     InsufficientNetworkTopology(#[from] NymTopologyError),
 
+    #[error("The provided topology file is malformed: {source}")]
+    MalformedTopologyFile {
+        #[source]
+        source: serde_json::Error,
+    },
+
     #[error("experienced a failure with our reply surb persistent storage: {source}")]
     SurbStorageError {
         source: Box<dyn std::error::Error + Send + Sync>,
@@ -58,6 +65,12 @@ pub enum ClientCoreError {
     #[error("failed to register receiver for reconstructed mixnet messages")]
     FailedToRegisterReceiver,
 
+    #[error("failed to acknowledge the received mixnet messages")]
+    FailedToAcknowledgeMessages,
+
+    #[error("experienced a failure with our received messages persistent storage: {0}")]
+    ReceivedStorageError(#[from] ReceivedStorageError),
+
     #[error("Unexpected exit")]
     UnexpectedExit,
 }
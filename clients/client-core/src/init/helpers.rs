@@ -3,7 +3,10 @@
 
 use crate::{
     client::key_manager::KeyManager,
-    config::{persistence::key_pathfinder::ClientKeyPathfinder, Config},
+    config::{
+        persistence::{encryption::StoragePassphrase, key_pathfinder::ClientKeyPathfinder},
+        Config,
+    },
     error::ClientCoreError,
 };
 use config::NymConfig;
@@ -38,17 +41,24 @@ pub(super) async fn query_gateway_details(
 
     let filtered_gateways = valid_gateways.filter_by_version(env!("CARGO_PKG_VERSION"));
 
-    // if we have chosen particular gateway - use it, otherwise choose a random one.
     // (remember that in active topology all gateways have at least 100 reputation so should
     // be working correctly)
+    choose_gateway(&filtered_gateways, chosen_gateway_id)
+}
+
+/// If we have chosen particular gateway - use it, otherwise choose a random one.
+pub(super) fn choose_gateway(
+    gateways: &[gateway::Node],
+    chosen_gateway_id: Option<identity::PublicKey>,
+) -> Result<gateway::Node, ClientCoreError> {
     if let Some(gateway_id) = chosen_gateway_id {
-        filtered_gateways
+        gateways
             .iter()
             .find(|gateway| gateway.identity_key == gateway_id)
             .ok_or_else(|| ClientCoreError::NoGatewayWithId(gateway_id.to_string()))
             .cloned()
     } else {
-        filtered_gateways
+        gateways
             .choose(&mut rand::thread_rng())
             .ok_or(ClientCoreError::NoGatewaysOnNetwork)
             .cloned()
@@ -80,12 +90,74 @@ pub(super) async fn register_with_gateway(
 pub(super) fn store_keys<T>(
     key_manager: &KeyManager,
     config: &Config<T>,
+    passphrase: Option<&StoragePassphrase>,
 ) -> Result<(), ClientCoreError>
 where
     T: NymConfig,
 {
     let pathfinder = ClientKeyPathfinder::new_from_config(config);
     Ok(key_manager
-        .store_keys(&pathfinder)
+        .store_keys(&pathfinder, passphrase)
         .tap_err(|err| log::error!("Failed to generate keys: {err}"))?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::encryption;
+
+    fn gateway_node(identity_key: identity::PublicKey) -> gateway::Node {
+        gateway::Node {
+            owner: "foomp".to_string(),
+            stake: 123,
+            location: "unknown".to_string(),
+            host: "1.2.3.4".parse().unwrap(),
+            mix_host: "1.2.3.4:1789".parse().unwrap(),
+            clients_port: 9000,
+            identity_key,
+            sphinx_key: encryption::PublicKey::from_base58_string(
+                "C7cown6dYCLZpLiMFC1PaBmhvLvmJmLDJGeRTbPD45bX",
+            )
+            .unwrap(),
+            upcoming_sphinx_key: None,
+            version: "1.1.0".to_string(),
+        }
+    }
+
+    fn random_identity() -> identity::PublicKey {
+        *identity::KeyPair::new(&mut rand::rngs::OsRng).public_key()
+    }
+
+    #[test]
+    fn chosen_gateway_is_picked_out_of_the_list() {
+        let chosen = random_identity();
+        let gateways = vec![gateway_node(random_identity()), gateway_node(chosen)];
+
+        let gateway = choose_gateway(&gateways, Some(chosen)).unwrap();
+        assert_eq!(gateway.identity_key, chosen);
+    }
+
+    #[test]
+    fn unknown_chosen_gateway_is_rejected() {
+        let gateways = vec![gateway_node(random_identity())];
+        let chosen = random_identity();
+
+        assert!(matches!(
+            choose_gateway(&gateways, Some(chosen)),
+            Err(ClientCoreError::NoGatewayWithId(_))
+        ));
+    }
+
+    #[test]
+    fn random_gateway_is_picked_if_none_was_chosen() {
+        let gateways = vec![gateway_node(random_identity())];
+        assert_eq!(
+            choose_gateway(&gateways, None).unwrap().identity_key,
+            gateways[0].identity_key
+        );
+        assert!(matches!(
+            choose_gateway(&[], None),
+            Err(ClientCoreError::NoGatewaysOnNetwork)
+        ));
+    }
+}
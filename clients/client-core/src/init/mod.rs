@@ -12,13 +12,14 @@ use tap::TapFallible;
 
 use config::NymConfig;
 use nym_crypto::asymmetric::{encryption, identity};
+use nym_topology::NymTopology;
 use url::Url;
 
 use crate::client::key_manager::KeyManager;
 use crate::{
     config::{
-        persistence::key_pathfinder::ClientKeyPathfinder, ClientCoreConfigTrait, Config,
-        GatewayEndpointConfig,
+        persistence::{encryption::StoragePassphrase, key_pathfinder::ClientKeyPathfinder},
+        ClientCoreConfigTrait, Config, GatewayEndpointConfig,
     },
     error::ClientCoreError,
 };
@@ -91,6 +92,27 @@ pub async fn register_with_gateway(
     Ok(gateway.into())
 }
 
+/// Authenticate and register with a gateway present in the provided network topology, rather than
+/// the one obtained from the nym-api.
+/// Either pick one at random or use the chosen one if it's among the available ones.
+/// The shared key is added to the supplied `KeyManager` and the endpoint details are returned.
+pub async fn register_with_gateway_from_topology(
+    key_manager: &mut KeyManager,
+    topology: &NymTopology,
+    chosen_gateway_id: Option<identity::PublicKey>,
+) -> Result<GatewayEndpointConfig, ClientCoreError> {
+    let gateway = helpers::choose_gateway(topology.gateways(), chosen_gateway_id)?;
+    log::debug!("Chosen gateway from the provided topology: {}", gateway);
+
+    let our_identity = key_manager.identity_keypair();
+
+    // Establish connection, authenticate and generate keys for talking with the gateway
+    let shared_keys = helpers::register_with_gateway(&gateway, our_identity).await?;
+    key_manager.insert_gateway_shared_key(shared_keys);
+
+    Ok(gateway.into())
+}
+
 /// Convenience function for setting up the gateway for a client given a `Config`. Depending on the
 /// arguments given it will do the sensible thing. Either it will
 ///
@@ -98,10 +120,13 @@ pub async fn register_with_gateway(
 /// b. Create a new gateway configuration but keep existing keys. This assumes that the caller
 ///    knows what they are doing and that the keys match the requested gateway.
 /// c. Create a new gateway configuration with a newly registered gateway and keys.
+///
+/// If the passphrase is provided, the newly created private keys are stored encrypted.
 pub async fn setup_gateway_from_config<C, T>(
     register_gateway: bool,
     user_chosen_gateway_id: Option<identity::PublicKey>,
     config: &Config<T>,
+    passphrase: Option<&StoragePassphrase>,
 ) -> Result<GatewayEndpointConfig, ClientCoreError>
 where
     C: NymConfig + ClientCoreConfigTrait,
@@ -140,7 +165,7 @@ where
 
     // Write all keys to storage and just return the gateway endpoint config. It is assumed that we
     // will load keys from storage when actually connecting.
-    helpers::store_keys(&key_manager, config)?;
+    helpers::store_keys(&key_manager, config, passphrase)?;
     Ok(gateway.into())
 }
 
@@ -178,43 +203,38 @@ pub fn get_client_address(
 }
 
 /// Get the client address by loading the keys from stored files.
+// only the public keys are needed here, so this works even if the private keys are encrypted
 pub fn get_client_address_from_stored_keys<T>(
     config: &Config<T>,
 ) -> Result<Recipient, ClientCoreError>
 where
     T: config::NymConfig,
 {
-    fn load_identity_keys(
+    fn load_identity_key(
         pathfinder: &ClientKeyPathfinder,
-    ) -> Result<identity::KeyPair, ClientCoreError> {
-        let identity_keypair: identity::KeyPair =
-            nym_pemstore::load_keypair(&nym_pemstore::KeyPairPath::new(
-                pathfinder.private_identity_key().to_owned(),
-                pathfinder.public_identity_key().to_owned(),
-            ))
-            .tap_err(|_| log::error!("Failed to read stored identity key files"))?;
-        Ok(identity_keypair)
+    ) -> Result<identity::PublicKey, ClientCoreError> {
+        let identity_key: identity::PublicKey =
+            nym_pemstore::load_key(pathfinder.public_identity_key())
+                .tap_err(|_| log::error!("Failed to read stored identity key file"))?;
+        Ok(identity_key)
     }
 
-    fn load_sphinx_keys(
+    fn load_sphinx_key(
         pathfinder: &ClientKeyPathfinder,
-    ) -> Result<encryption::KeyPair, ClientCoreError> {
-        let sphinx_keypair: encryption::KeyPair =
-            nym_pemstore::load_keypair(&nym_pemstore::KeyPairPath::new(
-                pathfinder.private_encryption_key().to_owned(),
-                pathfinder.public_encryption_key().to_owned(),
-            ))
-            .tap_err(|_| log::error!("Failed to read stored sphinx key files"))?;
-        Ok(sphinx_keypair)
+    ) -> Result<encryption::PublicKey, ClientCoreError> {
+        let sphinx_key: encryption::PublicKey =
+            nym_pemstore::load_key(pathfinder.public_encryption_key())
+                .tap_err(|_| log::error!("Failed to read stored sphinx key file"))?;
+        Ok(sphinx_key)
     }
 
     let pathfinder = ClientKeyPathfinder::new_from_config(config);
-    let identity_keypair = load_identity_keys(&pathfinder)?;
-    let sphinx_keypair = load_sphinx_keys(&pathfinder)?;
+    let identity_key = load_identity_key(&pathfinder)?;
+    let sphinx_key = load_sphinx_key(&pathfinder)?;
 
     let client_recipient = Recipient::new(
-        *identity_keypair.public_key(),
-        *sphinx_keypair.public_key(),
+        identity_key,
+        sphinx_key,
         // TODO: below only works under assumption that gateway address == gateway id
         // (which currently is true)
         NodeIdentity::from_base58_string(config.get_gateway_id())?,
@@ -83,6 +83,15 @@ impl ClientCoreConfigTrait for Config {
     fn get_gateway_endpoint(&self) -> &client_core::config::GatewayEndpointConfig {
         self.base.get_gateway_endpoint()
     }
+
+    fn set_gateway_endpoints(
+        &mut self,
+        gateway_endpoint: client_core::config::GatewayEndpointConfig,
+        standby_gateways: Vec<client_core::config::GatewayEndpointConfig>,
+    ) {
+        self.base
+            .set_gateway_endpoints(gateway_endpoint, standby_gateways)
+    }
 }
 
 impl Config {
@@ -117,6 +126,11 @@ impl Config {
         self
     }
 
+    pub fn with_statistics_port(mut self, port: u16) -> Self {
+        self.socket.statistics_listening_port = port;
+        self
+    }
+
     // getters
     pub fn get_config_file_save_location(&self) -> PathBuf {
         self.config_directory().join(Self::config_file_name())
@@ -146,6 +160,10 @@ impl Config {
         self.socket.listening_port
     }
 
+    pub fn get_statistics_listening_port(&self) -> u16 {
+        self.socket.statistics_listening_port
+    }
+
     // poor man's 'builder' method
     pub fn with_base<F, T>(mut self, f: F, val: T) -> Self
     where
@@ -197,6 +215,10 @@ pub struct Socket {
     socket_type: SocketType,
     host: IpAddr,
     listening_port: u16,
+
+    /// Port on which the local HTTP endpoint exposing traffic statistics is listening.
+    /// The endpoint is disabled if set to 0.
+    statistics_listening_port: u16,
 }
 
 impl Default for Socket {
@@ -205,6 +227,7 @@ impl Default for Socket {
             socket_type: SocketType::WebSocket,
             host: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
             listening_port: DEFAULT_WEBSOCKET_LISTENING_PORT,
+            statistics_listening_port: 0,
         }
     }
 }
@@ -55,6 +55,17 @@ database_path = '{{ client.database_path }}'
 # Path to the persistent store for received reply surbs, unused encryption keys and used sender tags.
 reply_surb_database_path = '{{ client.reply_surb_database_path }}'
 
+# Specifies whether received messages should be persisted until the application explicitly
+# acknowledges them, so that they would get redelivered after the client restarts.
+persist_received_messages = {{ client.persist_received_messages }}
+
+# Path to the persistent store for received messages that have not yet been acknowledged.
+received_messages_database_path = '{{ client.received_messages_database_path }}'
+
+# Specifies whether the private keys and the reply surb storage are encrypted with
+# a passphrase that has to be provided on every run.
+encrypted_storage = {{ client.encrypted_storage }}
+
 ##### additional client config options #####
 
 # A gateway specific, optional, base58 stringified shared key used for
@@ -80,6 +91,15 @@ gateway_owner = '{{ client.gateway_endpoint.gateway_owner }}'
 # Address of the gateway listener to which all client requests should be sent.
 gateway_listener = '{{ client.gateway_endpoint.gateway_listener }}'
 
+# Gateways the client is going to fail over to, in order, if its gateway becomes unreachable.
+# Each entry is a `[[client.standby_gateways]]` table with the same fields as `[client.gateway_endpoint]`.
+{{#each client.standby_gateways }}
+[[client.standby_gateways]]
+gateway_id = '{{ this.gateway_id }}'
+gateway_owner = '{{ this.gateway_owner }}'
+gateway_listener = '{{ this.gateway_listener }}'
+{{/each}}
+
 
 
 ##### socket config options #####
@@ -97,6 +117,10 @@ listening_port = {{ socket.listening_port }}
 # will be listening for incoming requests
 host = '{{ socket.host }}'
 
+# if non-zero, the port on which the client will expose its traffic statistics over HTTP,
+# as JSON under `/statistics` and in the Prometheus text format under `/metrics`
+statistics_listening_port = {{ socket.statistics_listening_port }}
+
 ##### logging configuration options #####
 
 [logging]
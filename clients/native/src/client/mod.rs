@@ -9,11 +9,15 @@ use crate::websocket;
 use client_core::client::base_client::{
     non_wasm_helpers, BaseClientBuilder, ClientInput, ClientOutput, ClientState,
 };
+use client_core::client::gateway_failover::{ConfigFailoverStore, SelfAddress};
 use client_core::client::inbound_messages::InputMessage;
+use client_core::client::received_buffer::storage::{fs_backend, ReceivedMessagesStore};
 use client_core::client::received_buffer::{
     ReceivedBufferMessage, ReceivedBufferRequestSender, ReconstructedMessagesReceiver,
 };
+use client_core::config::persistence::encryption::StoragePassphrase;
 use client_core::config::persistence::key_pathfinder::ClientKeyPathfinder;
+use client_core::error::ClientCoreError;
 use futures::channel::mpsc;
 use gateway_client::bandwidth::BandwidthController;
 use log::*;
@@ -27,6 +31,7 @@ pub use client_core::client::key_manager::KeyManager;
 pub use nym_sphinx::addressing::clients::Recipient;
 pub use nym_sphinx::receiver::ReconstructedMessage;
 pub mod config;
+mod statistics_api;
 
 pub struct SocketClient {
     /// Client configuration options, including, among other things, packet sending rates,
@@ -35,16 +40,27 @@ pub struct SocketClient {
 
     /// KeyManager object containing smart pointers to all relevant keys used by the client.
     key_manager: KeyManager,
+
+    /// Passphrase used for decrypting the reply surb storage, if it's encrypted.
+    passphrase: Option<StoragePassphrase>,
 }
 
 impl SocketClient {
     pub fn new(config: Config) -> Self {
+        Self::new_with_passphrase(config, None)
+    }
+
+    /// Creates the client whose private keys and reply surb storage are encrypted
+    /// with the provided passphrase.
+    pub fn new_with_passphrase(config: Config, passphrase: Option<StoragePassphrase>) -> Self {
         let pathfinder = ClientKeyPathfinder::new_from_config(config.get_base());
-        let key_manager = KeyManager::load_keys(&pathfinder).expect("failed to load stored keys");
+        let key_manager = KeyManager::load_keys(&pathfinder, passphrase.as_ref())
+            .expect("failed to load stored keys");
 
         SocketClient {
             config,
             key_manager,
+            passphrase,
         }
     }
 
@@ -52,6 +68,7 @@ impl SocketClient {
         SocketClient {
             config,
             key_manager,
+            passphrase: None,
         }
     }
 
@@ -79,12 +96,34 @@ impl SocketClient {
         )
     }
 
+    fn failover_store(&self) -> ConfigFailoverStore<Config> {
+        ConfigFailoverStore::new(
+            self.config.get_base().get_id(),
+            self.key_manager.clone(),
+            ClientKeyPathfinder::new_from_config(self.config.get_base()),
+            self.passphrase.clone(),
+        )
+    }
+
+    async fn setup_received_messages_store(
+        config: &Config,
+    ) -> Result<Option<Box<dyn ReceivedMessagesStore>>, ClientError> {
+        if !config.get_base().get_persist_received_messages() {
+            return Ok(None);
+        }
+
+        let store =
+            fs_backend::Backend::init(config.get_base().get_received_messages_database_path())
+                .await
+                .map_err(ClientCoreError::from)?;
+        Ok(Some(Box::new(store)))
+    }
+
     fn start_websocket_listener(
         config: &Config,
         client_input: ClientInput,
         client_output: ClientOutput,
         client_state: ClientState,
-        self_address: &Recipient,
         shutdown: nym_task::TaskClient,
     ) {
         info!("Starting websocket listener...");
@@ -101,8 +140,21 @@ impl SocketClient {
         let ClientState {
             shared_lane_queue_lengths,
             reply_controller_sender,
+            packet_preparation_metrics: _,
+            statistics,
+            self_address,
         } = client_state;
 
+        let statistics_port = config.get_statistics_listening_port();
+        if statistics_port != 0 {
+            statistics_api::start_statistics_api(
+                config.get_listening_ip(),
+                statistics_port,
+                statistics.clone(),
+                shutdown.clone(),
+            );
+        }
+
         let websocket_handler = websocket::HandlerBuilder::new(
             input_sender,
             connection_command_sender,
@@ -110,6 +162,7 @@ impl SocketClient {
             self_address,
             shared_lane_queue_lengths,
             reply_controller_sender,
+            statistics,
         );
 
         websocket::Listener::new(config.get_listening_ip(), config.get_listening_port())
@@ -137,16 +190,24 @@ impl SocketClient {
             Some(Self::create_bandwidth_controller(&self.config).await)
         };
 
-        let base_builder = BaseClientBuilder::new_from_base_config(
+        let received_messages_store = Self::setup_received_messages_store(&self.config).await?;
+        let failover_store = self.failover_store();
+
+        let mut base_builder = BaseClientBuilder::new_from_base_config(
             self.config.get_base(),
             self.key_manager,
             bandwidth_controller,
             non_wasm_helpers::setup_fs_reply_surb_backend(
                 Some(self.config.get_base().get_reply_surb_database_path()),
                 self.config.get_debug_settings(),
+                self.passphrase.as_ref(),
             )
             .await?,
         );
+        if let Some(store) = received_messages_store {
+            base_builder = base_builder.with_received_messages_store(store);
+        }
+        base_builder = base_builder.with_failover_store(Box::new(failover_store));
 
         let self_address = base_builder.as_mix_recipient();
         let mut started_client = base_builder.start_base().await?;
@@ -159,7 +220,6 @@ impl SocketClient {
             client_input,
             client_output,
             client_state,
-            &self_address,
             started_client.task_manager.subscribe(),
         );
 
@@ -181,18 +241,24 @@ impl SocketClient {
             Some(Self::create_bandwidth_controller(&self.config).await)
         };
 
-        let base_client = BaseClientBuilder::new_from_base_config(
+        let received_messages_store = Self::setup_received_messages_store(&self.config).await?;
+        let failover_store = self.failover_store();
+
+        let mut base_client = BaseClientBuilder::new_from_base_config(
             self.config.get_base(),
             self.key_manager,
             bandwidth_controller,
             non_wasm_helpers::setup_fs_reply_surb_backend(
                 Some(self.config.get_base().get_reply_surb_database_path()),
                 self.config.get_debug_settings(),
+                self.passphrase.as_ref(),
             )
             .await?,
         );
-
-        let address = base_client.as_mix_recipient();
+        if let Some(store) = received_messages_store {
+            base_client = base_client.with_received_messages_store(store);
+        }
+        base_client = base_client.with_failover_store(Box::new(failover_store));
 
         let mut started_client = base_client.start_base().await?;
         let client_input = started_client.client_input.register_producer();
@@ -211,9 +277,9 @@ impl SocketClient {
 
         Ok(DirectClient {
             client_input,
-            _received_buffer_request_sender: client_output.received_buffer_request_sender,
+            received_buffer_request_sender: client_output.received_buffer_request_sender,
             reconstructed_receiver,
-            address,
+            address: started_client.client_state.self_address,
             shutdown_notifier: started_client.task_manager,
         })
     }
@@ -222,17 +288,19 @@ impl SocketClient {
 pub struct DirectClient {
     client_input: ClientInput,
     // make sure to not drop the channel
-    _received_buffer_request_sender: ReceivedBufferRequestSender,
+    received_buffer_request_sender: ReceivedBufferRequestSender,
     reconstructed_receiver: ReconstructedMessagesReceiver,
-    address: Recipient,
+    address: SelfAddress,
 
     // we need to keep reference to this guy otherwise things will start dropping
     shutdown_notifier: TaskManager,
 }
 
 impl DirectClient {
-    pub fn address(&self) -> &Recipient {
-        &self.address
+    /// Returns the current address of this client. It changes whenever the client fails over
+    /// to one of its standby gateways.
+    pub fn address(&self) -> Recipient {
+        self.address.current()
     }
 
     pub fn signal_shutdown(&self) -> Result<(), SendError<()>> {
@@ -304,4 +372,15 @@ impl DirectClient {
             .await
             .expect("buffer controller seems to have somehow died!")
     }
+
+    /// EXPERIMENTAL DIRECT RUST API
+    /// It's untested and there are absolutely no guarantees about it (but seems to have worked
+    /// well enough in local tests)
+    /// Note: acknowledgements only have any effect if `persist_received_messages` is enabled,
+    /// in which case any unacknowledged messages are going to be redelivered after a restart.
+    pub fn acknowledge_messages(&self, delivery_ids: Vec<u64>) {
+        self.received_buffer_request_sender
+            .unbounded_send(ReceivedBufferMessage::Acknowledge(delivery_ids))
+            .expect("the buffer request failed!");
+    }
 }
@@ -0,0 +1,60 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use client_core::client::statistics::{ClientStatistics, ClientStatisticsSnapshot};
+use log::*;
+use rocket::http::ContentType;
+use rocket::serde::json::Json;
+use rocket::State;
+use std::net::IpAddr;
+
+#[rocket::get("/statistics")]
+pub(crate) fn get_statistics(
+    statistics: &State<ClientStatistics>,
+) -> Json<ClientStatisticsSnapshot> {
+    Json(statistics.snapshot())
+}
+
+#[rocket::get("/metrics")]
+pub(crate) fn get_metrics(statistics: &State<ClientStatistics>) -> (ContentType, String) {
+    (
+        ContentType::Plain,
+        statistics.snapshot().to_prometheus_text(),
+    )
+}
+
+/// Starts the local HTTP endpoint exposing the traffic statistics of this client,
+/// both as JSON (`/statistics`) and in the Prometheus text format (`/metrics`).
+pub(crate) fn start_statistics_api(
+    address: IpAddr,
+    port: u16,
+    statistics: ClientStatistics,
+    mut shutdown: nym_task::TaskClient,
+) {
+    info!("Starting statistics HTTP API on http://{address}:{port}");
+
+    let mut config = rocket::config::Config::release_default();
+    config.address = address;
+    config.port = port;
+    // SIGINT is already handled by the client itself
+    config.shutdown.ctrlc = false;
+
+    tokio::spawn(async move {
+        let rocket = rocket::build()
+            .configure(config)
+            .mount("/", rocket::routes![get_statistics, get_metrics])
+            .manage(statistics);
+
+        tokio::select! {
+            res = rocket.launch() => {
+                if let Err(err) = res {
+                    error!("The statistics HTTP API has failed: {err}");
+                }
+            }
+            _ = shutdown.recv() => {
+                trace!("StatisticsApi: Received shutdown");
+            }
+        }
+        debug!("StatisticsApi: Exiting");
+    });
+}
@@ -7,7 +7,9 @@ use crate::{
     error::ClientError,
 };
 use clap::Args;
+use client_core::config::persistence::encryption::StoragePassphrase;
 use config::NymConfig;
+use nym_bin_common::passphrase::read_passphrase;
 use nym_crypto::asymmetric::identity;
 use nym_sphinx::addressing::clients::Recipient;
 use serde::Serialize;
@@ -51,6 +53,11 @@ pub(crate) struct Init {
     #[clap(long)]
     host: Option<IpAddr>,
 
+    /// Port for the local HTTP endpoint exposing traffic statistics to listen on in all
+    /// subsequent runs. Setting it to 0 disables the endpoint.
+    #[clap(long)]
+    statistics_port: Option<u16>,
+
     /// Mostly debug-related option to increase default traffic rate so that you would not need to
     /// modify config post init
     #[clap(long, hide = true)]
@@ -65,6 +72,11 @@ pub(crate) struct Init {
     #[clap(long, hide = true)]
     enabled_credentials_mode: Option<bool>,
 
+    /// Encrypt the private keys and the reply surb storage with a passphrase. It is going to be
+    /// required on every subsequent run (or provided via the NYM_STORAGE_PASSPHRASE environment variable).
+    #[clap(long)]
+    encrypt_storage: bool,
+
     /// Save a summary of the initialization to a json file
     #[clap(long)]
     output_json: bool,
@@ -77,6 +89,7 @@ impl From<Init> for OverrideConfig {
             disable_socket: init_config.disable_socket,
             port: init_config.port,
             host: init_config.host,
+            statistics_port: init_config.statistics_port,
             fastmode: init_config.fastmode,
             no_cover: init_config.no_cover,
 
@@ -134,6 +147,24 @@ pub(crate) async fn execute(args: &Init) -> Result<(), ClientError> {
     // Attempt to use a user-provided gateway, if possible
     let user_chosen_gateway_id = args.gateway;
 
+    // Keys are only generated when registering with the gateway, so that's the only time we can
+    // decide whether they should be encrypted. Otherwise keep whatever has been chosen before.
+    let encrypt_storage = if register_gateway {
+        args.encrypt_storage
+    } else {
+        if args.encrypt_storage {
+            eprintln!("Not registering gateway, the existing keys are not going to get encrypted");
+        }
+        Config::load_from_file(id)
+            .map(|existing| existing.get_base().get_encrypted_storage())
+            .unwrap_or_default()
+    };
+    let passphrase = if register_gateway && encrypt_storage {
+        Some(StoragePassphrase::new(read_passphrase(true)?))
+    } else {
+        None
+    };
+
     // Load and potentially override config
     let mut config = override_config(Config::new(id), OverrideConfig::from(args.clone()));
 
@@ -143,11 +174,13 @@ pub(crate) async fn execute(args: &Init) -> Result<(), ClientError> {
         register_gateway,
         user_chosen_gateway_id,
         config.get_base(),
+        passphrase.as_ref(),
     )
     .await
     .tap_err(|err| eprintln!("Failed to setup gateway\nError: {err}"))?;
 
     config.get_base_mut().set_gateway_endpoint(gateway);
+    config.get_base_mut().set_encrypted_storage(encrypt_storage);
 
     config.save_to_file(None).tap_err(|_| {
         log::error!("Failed to save the config file");
@@ -58,6 +58,7 @@ pub(crate) struct OverrideConfig {
     disable_socket: Option<bool>,
     port: Option<u16>,
     host: Option<IpAddr>,
+    statistics_port: Option<u16>,
     fastmode: bool,
     no_cover: bool,
     nyxd_urls: Option<Vec<url::Url>>,
@@ -84,6 +85,7 @@ pub(crate) fn override_config(config: Config, args: OverrideConfig) -> Config {
         .with_base(BaseConfig::with_disabled_cover_traffic, args.no_cover)
         .with_optional(Config::with_port, args.port)
         .with_optional(Config::with_host, args.host)
+        .with_optional(Config::with_statistics_port, args.statistics_port)
         .with_optional_custom_env_ext(
             BaseConfig::with_custom_nym_apis,
             args.nym_apis,
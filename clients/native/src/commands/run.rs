@@ -11,8 +11,10 @@ use crate::{
 };
 
 use clap::Args;
+use client_core::config::persistence::encryption::StoragePassphrase;
 use config::NymConfig;
 use log::*;
+use nym_bin_common::passphrase::read_passphrase;
 use nym_bin_common::version_checker::is_minor_version_compatible;
 use nym_crypto::asymmetric::identity;
 
@@ -48,6 +50,11 @@ pub(crate) struct Run {
     #[clap(long)]
     host: Option<IpAddr>,
 
+    /// Port for the local HTTP endpoint exposing traffic statistics to listen on.
+    /// Setting it to 0 disables the endpoint.
+    #[clap(long)]
+    statistics_port: Option<u16>,
+
     /// Mostly debug-related option to increase default traffic rate so that you would not need to
     /// modify config post init
     #[clap(long, hide = true)]
@@ -70,6 +77,7 @@ impl From<Run> for OverrideConfig {
             disable_socket: run_config.disable_socket,
             port: run_config.port,
             host: run_config.host,
+            statistics_port: run_config.statistics_port,
             fastmode: run_config.fastmode,
             no_cover: run_config.no_cover,
             nyxd_urls: run_config.nyxd_urls,
@@ -120,5 +128,13 @@ pub(crate) async fn execute(args: &Run) -> Result<(), Box<dyn Error + Send + Syn
         return Err(Box::new(ClientError::FailedLocalVersionCheck));
     }
 
-    SocketClient::new(config).run_socket_forever().await
+    let passphrase = if config.get_base().get_encrypted_storage() {
+        Some(StoragePassphrase::new(read_passphrase(false)?))
+    } else {
+        None
+    };
+
+    SocketClient::new_with_passphrase(config, passphrase)
+        .run_socket_forever()
+        .await
 }
@@ -1,7 +1,9 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use client_core::client::gateway_failover::SelfAddress;
 use client_core::client::replies::reply_controller::requests::ReplyControllerSender;
+use client_core::client::statistics::ClientStatistics;
 use client_core::client::{
     inbound_messages::{InputMessage, InputMessageSender},
     received_buffer::{
@@ -38,9 +40,10 @@ pub(crate) struct HandlerBuilder {
     msg_input: InputMessageSender,
     client_connection_tx: ConnectionCommandSender,
     buffer_requester: ReceivedBufferRequestSender,
-    self_full_address: Recipient,
+    self_full_address: SelfAddress,
     lane_queue_lengths: LaneQueueLengths,
     reply_controller_sender: ReplyControllerSender,
+    statistics: ClientStatistics,
 }
 
 impl HandlerBuilder {
@@ -48,17 +51,19 @@ impl HandlerBuilder {
         msg_input: InputMessageSender,
         client_connection_tx: ConnectionCommandSender,
         buffer_requester: ReceivedBufferRequestSender,
-        self_full_address: &Recipient,
+        self_full_address: SelfAddress,
         lane_queue_lengths: LaneQueueLengths,
         reply_controller_sender: ReplyControllerSender,
+        statistics: ClientStatistics,
     ) -> Self {
         Self {
             msg_input,
             client_connection_tx,
             buffer_requester,
-            self_full_address: *self_full_address,
+            self_full_address,
             lane_queue_lengths,
             reply_controller_sender,
+            statistics,
         }
     }
 
@@ -68,11 +73,12 @@ impl HandlerBuilder {
             msg_input: self.msg_input.clone(),
             client_connection_tx: self.client_connection_tx.clone(),
             buffer_requester: self.buffer_requester.clone(),
-            self_full_address: self.self_full_address,
+            self_full_address: self.self_full_address.clone(),
             socket: None,
             received_response_type: Default::default(),
             lane_queue_lengths: self.lane_queue_lengths.clone(),
             reply_controller_sender: self.reply_controller_sender.clone(),
+            statistics: self.statistics.clone(),
         }
     }
 }
@@ -81,11 +87,12 @@ pub(crate) struct Handler {
     msg_input: InputMessageSender,
     client_connection_tx: ConnectionCommandSender,
     buffer_requester: ReceivedBufferRequestSender,
-    self_full_address: Recipient,
+    self_full_address: SelfAddress,
     socket: Option<WebSocketStream<TcpStream>>,
     received_response_type: ReceivedResponseType,
     lane_queue_lengths: LaneQueueLengths,
     reply_controller_sender: ReplyControllerSender,
+    statistics: ClientStatistics,
 }
 
 impl Drop for Handler {
@@ -233,7 +240,7 @@ impl Handler {
     }
 
     fn handle_self_address(&self) -> ServerResponse {
-        ServerResponse::SelfAddress(Box::new(self.self_full_address))
+        ServerResponse::SelfAddress(Box::new(self.self_full_address.current()))
     }
 
     fn handle_closed_connection(&self, connection_id: u64) -> Option<ServerResponse> {
@@ -247,6 +254,28 @@ impl Handler {
         self.get_lane_queue_length(connection_id).await
     }
 
+    fn handle_acknowledge(&self, delivery_ids: Vec<u64>) -> Option<ServerResponse> {
+        if self
+            .buffer_requester
+            .unbounded_send(ReceivedBufferMessage::Acknowledge(delivery_ids))
+            .is_err()
+        {
+            return Some(ServerResponse::new_error(
+                "failed to acknowledge the received messages",
+            ));
+        }
+        None
+    }
+
+    fn handle_get_statistics(&self) -> ServerResponse {
+        match serde_json::to_value(self.statistics.snapshot()) {
+            Ok(statistics) => ServerResponse::Statistics(statistics),
+            Err(err) => {
+                ServerResponse::new_error(format!("failed to serialize statistics - {err}"))
+            }
+        }
+    }
+
     async fn handle_request(&mut self, request: ClientRequest) -> Option<ServerResponse> {
         match request {
             ClientRequest::Send {
@@ -274,6 +303,8 @@ impl Handler {
             ClientRequest::SelfAddress => Some(self.handle_self_address()),
             ClientRequest::ClosedConnection(id) => self.handle_closed_connection(id),
             ClientRequest::GetLaneQueueLength(id) => self.handle_get_lane_queue_length(id).await,
+            ClientRequest::Acknowledge(ids) => self.handle_acknowledge(ids),
+            ClientRequest::GetStatistics => Some(self.handle_get_statistics()),
         }
     }
 
@@ -30,6 +30,12 @@ enum ClientRequestTag {
 
     /// Value tag representing [`GetLaneQueueLength`] variant of the [`ClientRequest`]
     GetLaneQueueLength = 0x05,
+
+    /// Value tag representing [`Acknowledge`] variant of the [`ClientRequest`]
+    Acknowledge = 0x06,
+
+    /// Value tag representing [`GetStatistics`] variant of the [`ClientRequest`]
+    GetStatistics = 0x07,
 }
 
 impl TryFrom<u8> for ClientRequestTag {
@@ -43,6 +49,8 @@ impl TryFrom<u8> for ClientRequestTag {
             _ if value == (Self::SelfAddress as u8) => Ok(Self::SelfAddress),
             _ if value == (Self::ClosedConnection as u8) => Ok(Self::ClosedConnection),
             _ if value == (Self::GetLaneQueueLength as u8) => Ok(Self::GetLaneQueueLength),
+            _ if value == (Self::Acknowledge as u8) => Ok(Self::Acknowledge),
+            _ if value == (Self::GetStatistics as u8) => Ok(Self::GetStatistics),
             n => Err(error::Error::new(
                 ErrorKind::UnknownRequest,
                 format!("{n} does not correspond to any valid request tag"),
@@ -94,6 +102,13 @@ pub enum ClientRequest {
     ClosedConnection(u64),
 
     GetLaneQueueLength(u64),
+
+    /// Confirm the received messages with the specified delivery ids have been processed,
+    /// so that the client would not attempt to redeliver them.
+    Acknowledge(Vec<u64>),
+
+    /// Retrieve the per-destination traffic statistics of the client.
+    GetStatistics,
 }
 
 // we could have been parsing it directly TryFrom<WsMessage>, but we want to retain
@@ -382,6 +397,60 @@ impl ClientRequest {
         Ok(ClientRequest::GetLaneQueueLength(connection_id))
     }
 
+    // ACKNOWLEDGE_REQUEST_TAG || num_ids || delivery_ids
+    fn serialize_acknowledge(delivery_ids: Vec<u64>) -> Vec<u8> {
+        let num_ids_bytes = (delivery_ids.len() as u64).to_be_bytes();
+        std::iter::once(ClientRequestTag::Acknowledge as u8)
+            .chain(num_ids_bytes.into_iter())
+            .chain(delivery_ids.into_iter().flat_map(|id| id.to_be_bytes()))
+            .collect()
+    }
+
+    // ACKNOWLEDGE_REQUEST_TAG || num_ids || delivery_ids
+    fn deserialize_acknowledge(b: &[u8]) -> Result<Self, error::Error> {
+        if b.len() < 1 + size_of::<u64>() {
+            return Err(error::Error::new(
+                ErrorKind::TooShortRequest,
+                "not enough data provided to recover 'acknowledge'".to_string(),
+            ));
+        }
+
+        // this MUST match because it was called by 'deserialize'
+        debug_assert_eq!(b[0], ClientRequestTag::Acknowledge as u8);
+
+        let num_ids = u64::from_be_bytes(b[1..1 + size_of::<u64>()].try_into().unwrap());
+        let ids_bytes = &b[1 + size_of::<u64>()..];
+        if num_ids.checked_mul(size_of::<u64>() as u64) != Some(ids_bytes.len() as u64) {
+            return Err(error::Error::new(
+                ErrorKind::MalformedRequest,
+                format!(
+                    "delivery ids have inconsistent length. specified: {num_ids} got: {} bytes",
+                    ids_bytes.len()
+                ),
+            ));
+        }
+
+        let delivery_ids = ids_bytes
+            .chunks_exact(size_of::<u64>())
+            .map(|id| u64::from_be_bytes(id.try_into().unwrap()))
+            .collect();
+
+        Ok(ClientRequest::Acknowledge(delivery_ids))
+    }
+
+    // GET_STATISTICS_REQUEST_TAG
+    fn serialize_get_statistics() -> Vec<u8> {
+        vec![ClientRequestTag::GetStatistics as u8]
+    }
+
+    // GET_STATISTICS_REQUEST_TAG
+    fn deserialize_get_statistics(b: &[u8]) -> Result<Self, error::Error> {
+        // this MUST match because it was called by 'deserialize'
+        debug_assert_eq!(b[0], ClientRequestTag::GetStatistics as u8);
+
+        Ok(ClientRequest::GetStatistics)
+    }
+
     pub fn serialize(self) -> Vec<u8> {
         match self {
             ClientRequest::Send {
@@ -408,6 +477,10 @@ impl ClientRequest {
             ClientRequest::ClosedConnection(id) => Self::serialize_closed_connection(id),
 
             ClientRequest::GetLaneQueueLength(id) => Self::serialize_get_lane_queue_lengths(id),
+
+            ClientRequest::Acknowledge(ids) => Self::serialize_acknowledge(ids),
+
+            ClientRequest::GetStatistics => Self::serialize_get_statistics(),
         }
     }
 
@@ -431,6 +504,8 @@ impl ClientRequest {
             ClientRequestTag::SelfAddress => Self::deserialize_self_address(b),
             ClientRequestTag::ClosedConnection => Self::deserialize_closed_connection(b),
             ClientRequestTag::GetLaneQueueLength => Self::deserialize_get_lane_queue_length(b),
+            ClientRequestTag::Acknowledge => Self::deserialize_acknowledge(b),
+            ClientRequestTag::GetStatistics => Self::deserialize_get_statistics(b),
         }
     }
 
@@ -565,4 +640,26 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn acknowledge_request_serialization_works() {
+        let acknowledge_request = ClientRequest::Acknowledge(vec![1, 42, 1234]);
+        let bytes = acknowledge_request.serialize();
+        let recovered = ClientRequest::deserialize(&bytes).unwrap();
+        match recovered {
+            ClientRequest::Acknowledge(ids) => assert_eq!(ids, vec![1, 42, 1234]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn get_statistics_request_serialization_works() {
+        let get_statistics_request = ClientRequest::GetStatistics;
+        let bytes = get_statistics_request.serialize();
+        let recovered = ClientRequest::deserialize(&bytes).unwrap();
+        match recovered {
+            ClientRequest::GetStatistics => (),
+            _ => unreachable!(),
+        }
+    }
 }
@@ -25,6 +25,13 @@ enum ServerResponseTag {
 
     /// Value tag representing [`LaneQueueLength`] variant of the [`ServerResponse`]
     LaneQueueLength = 0x03,
+
+    /// Value tag representing [`Received`] variant of the [`ServerResponse`] that has been
+    /// persisted by the client and has to be acknowledged
+    ReceivedWithDeliveryId = 0x04,
+
+    /// Value tag representing [`Statistics`] variant of the [`ServerResponse`]
+    Statistics = 0x05,
 }
 
 impl TryFrom<u8> for ServerResponseTag {
@@ -36,6 +43,8 @@ impl TryFrom<u8> for ServerResponseTag {
             _ if value == (Self::Received as u8) => Ok(Self::Received),
             _ if value == (Self::SelfAddress as u8) => Ok(Self::SelfAddress),
             _ if value == (Self::LaneQueueLength as u8) => Ok(Self::LaneQueueLength),
+            _ if value == (Self::ReceivedWithDeliveryId as u8) => Ok(Self::ReceivedWithDeliveryId),
+            _ if value == (Self::Statistics as u8) => Ok(Self::Statistics),
             n => Err(error::Error::new(
                 ErrorKind::UnknownResponse,
                 format!("{n} does not correspond to any valid response tag"),
@@ -49,6 +58,7 @@ pub enum ServerResponse {
     Received(ReconstructedMessage),
     SelfAddress(Box<Recipient>),
     LaneQueueLength { lane: u64, queue_length: usize },
+    Statistics(serde_json::Value),
     Error(error::Error),
 }
 
@@ -60,40 +70,61 @@ impl ServerResponse {
         })
     }
 
-    // RECEIVED_RESPONSE_TAG || 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
-    fn serialize_received(reconstructed_message: ReconstructedMessage) -> Vec<u8> {
-        let message_len_bytes = (reconstructed_message.message.len() as u64).to_be_bytes();
+    // 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
+    fn serialize_received_content(
+        message: Vec<u8>,
+        sender_tag: Option<AnonymousSenderTag>,
+    ) -> impl Iterator<Item = u8> {
+        let message_len_bytes = (message.len() as u64).to_be_bytes();
 
-        if let Some(sender_tag) = reconstructed_message.sender_tag {
-            std::iter::once(ServerResponseTag::Received as u8)
-                .chain(std::iter::once(true as u8))
+        let sender_tag_bytes = match sender_tag {
+            Some(sender_tag) => std::iter::once(true as u8)
                 .chain(sender_tag.to_bytes().into_iter())
-                .chain(message_len_bytes.iter().cloned())
-                .chain(reconstructed_message.message.into_iter())
+                .collect(),
+            None => vec![false as u8],
+        };
+
+        sender_tag_bytes
+            .into_iter()
+            .chain(message_len_bytes.into_iter())
+            .chain(message.into_iter())
+    }
+
+    // RECEIVED_RESPONSE_TAG || 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
+    // or, if the message has been persisted:
+    // RECEIVED_WITH_DELIVERY_ID_RESPONSE_TAG || delivery_id || 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
+    fn serialize_received(reconstructed_message: ReconstructedMessage) -> Vec<u8> {
+        let content = Self::serialize_received_content(
+            reconstructed_message.message,
+            reconstructed_message.sender_tag,
+        );
+
+        if let Some(delivery_id) = reconstructed_message.delivery_id {
+            std::iter::once(ServerResponseTag::ReceivedWithDeliveryId as u8)
+                .chain(delivery_id.to_be_bytes().into_iter())
+                .chain(content)
                 .collect()
         } else {
             std::iter::once(ServerResponseTag::Received as u8)
-                .chain(std::iter::once(false as u8))
-                .chain(message_len_bytes.iter().cloned())
-                .chain(reconstructed_message.message.into_iter())
+                .chain(content)
                 .collect()
         }
     }
 
-    // RECEIVED_RESPONSE_TAG || 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
-    fn deserialize_received(b: &[u8]) -> Result<Self, error::Error> {
-        // this MUST match because it was called by 'deserialize'
-
+    // 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
+    fn deserialize_received_content(
+        b: &[u8],
+        delivery_id: Option<u64>,
+    ) -> Result<Self, error::Error> {
         // we must be able to read at the very least if it has a reply_surb and length of some field
-        if b.len() < 2 + size_of::<u64>() {
+        if b.len() < 1 + size_of::<u64>() {
             return Err(error::Error::new(
                 ErrorKind::TooShortResponse,
                 "not enough data provided to recover 'received'".to_string(),
             ));
         }
-        debug_assert_eq!(b[0], ServerResponseTag::Received as u8);
 
-        let has_sender_tag = match b[1] {
+        let has_sender_tag = match b[0] {
             0 => false,
             1 => true,
             n => {
@@ -104,9 +135,9 @@ impl ServerResponse {
             }
         };
 
-        let mut i = 2;
+        let mut i = 1;
         let sender_tag = if has_sender_tag {
-            if b[2..].len() < SENDER_TAG_SIZE {
+            if b[1..].len() < SENDER_TAG_SIZE {
                 return Err(error::Error::new(
                     ErrorKind::TooShortResponse,
                     "not enough data provided to recover 'received'".to_string(),
@@ -114,7 +145,7 @@ impl ServerResponse {
             }
             i += SENDER_TAG_SIZE;
             Some(AnonymousSenderTag::from_bytes(
-                b[2..2 + SENDER_TAG_SIZE].try_into().unwrap(),
+                b[1..1 + SENDER_TAG_SIZE].try_into().unwrap(),
             ))
         } else {
             None
@@ -143,9 +174,34 @@ impl ServerResponse {
         Ok(ServerResponse::Received(ReconstructedMessage {
             message: message.to_vec(),
             sender_tag,
+            delivery_id,
         }))
     }
 
+    // RECEIVED_RESPONSE_TAG || 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
+    fn deserialize_received(b: &[u8]) -> Result<Self, error::Error> {
+        // this MUST match because it was called by 'deserialize'
+        debug_assert_eq!(b[0], ServerResponseTag::Received as u8);
+
+        Self::deserialize_received_content(&b[1..], None)
+    }
+
+    // RECEIVED_WITH_DELIVERY_ID_RESPONSE_TAG || delivery_id || 1 | 0 indicating sender_tag || Option<sender_tag> || msg_len || msg
+    fn deserialize_received_with_delivery_id(b: &[u8]) -> Result<Self, error::Error> {
+        if b.len() < 1 + size_of::<u64>() {
+            return Err(error::Error::new(
+                ErrorKind::TooShortResponse,
+                "not enough data provided to recover 'received'".to_string(),
+            ));
+        }
+
+        // this MUST match because it was called by 'deserialize'
+        debug_assert_eq!(b[0], ServerResponseTag::ReceivedWithDeliveryId as u8);
+
+        let delivery_id = u64::from_be_bytes(b[1..1 + size_of::<u64>()].try_into().unwrap());
+        Self::deserialize_received_content(&b[1 + size_of::<u64>()..], Some(delivery_id))
+    }
+
     // SELF_ADDRESS_RESPONSE_TAG || self_address
     fn serialize_self_address(address: Recipient) -> Vec<u8> {
         std::iter::once(ServerResponseTag::SelfAddress as u8)
@@ -206,6 +262,52 @@ impl ServerResponse {
         Ok(ServerResponse::LaneQueueLength { lane, queue_length })
     }
 
+    // STATISTICS_RESPONSE_TAG || json_len || json
+    fn serialize_statistics(statistics: serde_json::Value) -> Vec<u8> {
+        // serializing a `Value` can't fail as all of its map keys are strings
+        let json = serde_json::to_vec(&statistics).unwrap();
+        let json_len_bytes = (json.len() as u64).to_be_bytes();
+        std::iter::once(ServerResponseTag::Statistics as u8)
+            .chain(json_len_bytes.into_iter())
+            .chain(json.into_iter())
+            .collect()
+    }
+
+    // STATISTICS_RESPONSE_TAG || json_len || json
+    fn deserialize_statistics(b: &[u8]) -> Result<Self, error::Error> {
+        if b.len() < 1 + size_of::<u64>() {
+            return Err(error::Error::new(
+                ErrorKind::TooShortResponse,
+                "not enough data provided to recover 'statistics'".to_string(),
+            ));
+        }
+
+        // this MUST match because it was called by 'deserialize'
+        debug_assert_eq!(b[0], ServerResponseTag::Statistics as u8);
+
+        let json_len = u64::from_be_bytes(b[1..1 + size_of::<u64>()].try_into().unwrap());
+        let json = &b[1 + size_of::<u64>()..];
+        if json.len() as u64 != json_len {
+            return Err(error::Error::new(
+                ErrorKind::MalformedResponse,
+                format!(
+                    "statistics have inconsistent length. specified: {} got: {}",
+                    json_len,
+                    json.len()
+                ),
+            ));
+        }
+
+        let statistics = serde_json::from_slice(json).map_err(|err| {
+            error::Error::new(
+                ErrorKind::MalformedResponse,
+                format!("malformed statistics: {err}"),
+            )
+        })?;
+
+        Ok(ServerResponse::Statistics(statistics))
+    }
+
     // ERROR_RESPONSE_TAG || err_code || msg_len || msg
     fn serialize_error(error: error::Error) -> Vec<u8> {
         let message_len_bytes = (error.message.len() as u64).to_be_bytes();
@@ -269,6 +371,7 @@ impl ServerResponse {
             ServerResponse::LaneQueueLength { lane, queue_length } => {
                 Self::serialize_lane_queue_length(lane, queue_length)
             }
+            ServerResponse::Statistics(statistics) => Self::serialize_statistics(statistics),
             ServerResponse::Error(err) => Self::serialize_error(err),
         }
     }
@@ -298,8 +401,12 @@ impl ServerResponse {
         // determine what kind of response that is and try to deserialize it
         match response_tag {
             ServerResponseTag::Received => Self::deserialize_received(b),
+            ServerResponseTag::ReceivedWithDeliveryId => {
+                Self::deserialize_received_with_delivery_id(b)
+            }
             ServerResponseTag::SelfAddress => Self::deserialize_self_address(b),
             ServerResponseTag::LaneQueueLength => Self::deserialize_lane_queue_length(b),
+            ServerResponseTag::Statistics => Self::deserialize_statistics(b),
             ServerResponseTag::Error => Self::deserialize_error(b),
         }
     }
@@ -325,6 +432,7 @@ mod tests {
         let received_with_sender_tag = ServerResponse::Received(ReconstructedMessage {
             message: b"foomp".to_vec(),
             sender_tag: Some([42u8; SENDER_TAG_SIZE].into()),
+            delivery_id: None,
         });
         let bytes = received_with_sender_tag.serialize();
         let recovered = ServerResponse::deserialize(&bytes).unwrap();
@@ -342,6 +450,7 @@ mod tests {
         let received_without_sender_tag = ServerResponse::Received(ReconstructedMessage {
             message: b"foomp".to_vec(),
             sender_tag: None,
+            delivery_id: None,
         });
         let bytes = received_without_sender_tag.serialize();
         let recovered = ServerResponse::deserialize(&bytes).unwrap();
@@ -354,6 +463,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn received_with_delivery_id_response_serialization_works() {
+        let received_with_sender_tag = ServerResponse::Received(ReconstructedMessage {
+            message: b"foomp".to_vec(),
+            sender_tag: Some([42u8; SENDER_TAG_SIZE].into()),
+            delivery_id: Some(1234),
+        });
+        let bytes = received_with_sender_tag.serialize();
+        let recovered = ServerResponse::deserialize(&bytes).unwrap();
+        match recovered {
+            ServerResponse::Received(reconstructed) => {
+                assert_eq!(reconstructed.message, b"foomp".to_vec());
+                assert_eq!(
+                    reconstructed.sender_tag,
+                    Some([42u8; SENDER_TAG_SIZE].into())
+                );
+                assert_eq!(reconstructed.delivery_id, Some(1234))
+            }
+            _ => unreachable!(),
+        }
+
+        let received_without_sender_tag = ServerResponse::Received(ReconstructedMessage {
+            message: b"foomp".to_vec(),
+            sender_tag: None,
+            delivery_id: Some(1234),
+        });
+        let bytes = received_without_sender_tag.serialize();
+        let recovered = ServerResponse::deserialize(&bytes).unwrap();
+        match recovered {
+            ServerResponse::Received(reconstructed) => {
+                assert_eq!(reconstructed.message, b"foomp".to_vec());
+                assert!(reconstructed.sender_tag.is_none());
+                assert_eq!(reconstructed.delivery_id, Some(1234))
+            }
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn self_address_response_serialization_works() {
         let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
@@ -387,6 +534,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn statistics_response_serialization_works() {
+        let statistics = serde_json::json!({
+            "total_sent_packets": 42,
+            "destinations": [{"destination": "foomp", "average_ack_rtt_ms": null}],
+        });
+        let statistics_response = ServerResponse::Statistics(statistics.clone());
+        let bytes = statistics_response.serialize();
+        let recovered = ServerResponse::deserialize(&bytes).unwrap();
+        match recovered {
+            ServerResponse::Statistics(recovered) => assert_eq!(recovered, statistics),
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn error_response_serialization_works() {
         let dummy_error = error::Error::new(ErrorKind::UnknownRequest, "foomp message".to_string());
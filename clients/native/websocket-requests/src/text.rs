@@ -35,6 +35,11 @@ pub(super) enum ClientRequestText {
         connection_id: Option<u64>,
     },
     SelfAddress,
+    #[serde(rename_all = "camelCase")]
+    Acknowledge {
+        delivery_ids: Vec<u64>,
+    },
+    GetStatistics,
 }
 
 impl TryFrom<String> for ClientRequestText {
@@ -84,6 +89,10 @@ impl TryInto<ClientRequest> for ClientRequestText {
                 })
             }
             ClientRequestText::SelfAddress => Ok(ClientRequest::SelfAddress),
+            ClientRequestText::Acknowledge { delivery_ids } => {
+                Ok(ClientRequest::Acknowledge(delivery_ids))
+            }
+            ClientRequestText::GetStatistics => Ok(ClientRequest::GetStatistics),
             ClientRequestText::Reply {
                 sender_tag,
                 message,
@@ -115,6 +124,8 @@ pub(super) enum ServerResponseText {
     Received {
         message: String,
         sender_tag: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delivery_id: Option<u64>,
     },
     SelfAddress {
         address: String,
@@ -123,6 +134,9 @@ pub(super) enum ServerResponseText {
         lane: u64,
         queue_length: usize,
     },
+    Statistics {
+        statistics: serde_json::Value,
+    },
     Error {
         message: String,
     },
@@ -157,6 +171,7 @@ impl From<ServerResponse> for ServerResponseText {
                     // pure binary later
                     message: String::from_utf8_lossy(&reconstructed.message).into_owned(),
                     sender_tag: reconstructed.sender_tag.map(|tag| tag.to_base58_string()),
+                    delivery_id: reconstructed.delivery_id,
                 }
             }
             ServerResponse::SelfAddress(recipient) => ServerResponseText::SelfAddress {
@@ -165,6 +180,7 @@ impl From<ServerResponse> for ServerResponseText {
             ServerResponse::LaneQueueLength { lane, queue_length } => {
                 ServerResponseText::LaneQueueLength { lane, queue_length }
             }
+            ServerResponse::Statistics(statistics) => ServerResponseText::Statistics { statistics },
             ServerResponse::Error(err) => ServerResponseText::Error {
                 message: err.to_string(),
             },
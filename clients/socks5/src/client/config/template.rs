@@ -55,6 +55,14 @@ database_path = '{{ client.database_path }}'
 # Path to the persistent store for received reply surbs, unused encryption keys and used sender tags.
 reply_surb_database_path = '{{ client.reply_surb_database_path }}'
 
+# Path to the directory holding the reply data blobs of the clients that can't use
+# the sqlite store (i.e. the ones running on mobile).
+reply_surb_blob_storage_directory = '{{ client.reply_surb_blob_storage_directory }}'
+
+# Specifies whether the private keys and the reply surb storage are encrypted with
+# a passphrase that has to be provided on every run.
+encrypted_storage = {{ client.encrypted_storage }}
+
 ##### additional client config options #####
 
 # A gateway specific, optional, base58 stringified shared key used for
@@ -80,6 +88,15 @@ gateway_owner = '{{ client.gateway_endpoint.gateway_owner }}'
 # Address of the gateway listener to which all client requests should be sent.
 gateway_listener = '{{ client.gateway_endpoint.gateway_listener }}'
 
+# Gateways the client is going to fail over to, in order, if its gateway becomes unreachable.
+# Each entry is a `[[client.standby_gateways]]` table with the same fields as `[client.gateway_endpoint]`.
+{{#each client.standby_gateways }}
+[[client.standby_gateways]]
+gateway_id = '{{ this.gateway_id }}'
+gateway_owner = '{{ this.gateway_owner }}'
+gateway_listener = '{{ this.gateway_listener }}'
+{{/each}}
+
 
 ##### socket config options #####
 
@@ -10,18 +10,19 @@ use crate::socks::{
 };
 
 #[cfg(feature = "mobile")]
-use client_core::client::base_client::helpers::setup_empty_reply_surb_backend;
+use client_core::client::base_client::helpers::setup_mobile_reply_surb_backend;
 #[cfg(not(feature = "mobile"))]
 use client_core::client::base_client::non_wasm_helpers;
 use client_core::client::base_client::{BaseClientBuilder, ClientInput, ClientOutput, ClientState};
+use client_core::client::gateway_failover::ConfigFailoverStore;
 use client_core::client::key_manager::KeyManager;
+use client_core::config::persistence::encryption::StoragePassphrase;
 use client_core::config::persistence::key_pathfinder::ClientKeyPathfinder;
 use futures::channel::mpsc;
 use futures::StreamExt;
 #[cfg(not(feature = "mobile"))]
 use gateway_client::bandwidth::BandwidthController;
 use log::*;
-use nym_sphinx::addressing::clients::Recipient;
 use nym_task::{TaskClient, TaskManager};
 use std::error::Error;
 use validator_client::nyxd::QueryNyxdClient;
@@ -45,28 +46,40 @@ pub struct NymClient {
 
     /// KeyManager object containing smart pointers to all relevant keys used by the client.
     key_manager: KeyManager,
+
+    /// Passphrase used for decrypting the reply surb storage, if it's encrypted.
+    passphrase: Option<StoragePassphrase>,
 }
 
 impl NymClient {
     pub fn new(config: Config) -> Self {
+        Self::new_with_passphrase(config, None)
+    }
+
+    /// Creates the client whose private keys and reply surb storage are encrypted
+    /// with the provided passphrase.
+    pub fn new_with_passphrase(config: Config, passphrase: Option<StoragePassphrase>) -> Self {
         let pathfinder = ClientKeyPathfinder::new_from_config(config.get_base());
-        let key_manager = KeyManager::load_keys(&pathfinder).expect("failed to load stored keys");
+        let key_manager = KeyManager::load_keys(&pathfinder, passphrase.as_ref())
+            .expect("failed to load stored keys");
 
         NymClient {
             config,
             key_manager,
+            passphrase,
         }
     }
 
     pub fn new_with_keys(config: Config, key_manager: Option<KeyManager>) -> Self {
         let key_manager = key_manager.unwrap_or_else(|| {
             let pathfinder = ClientKeyPathfinder::new_from_config(config.get_base());
-            KeyManager::load_keys(&pathfinder).expect("failed to load stored keys")
+            KeyManager::load_keys(&pathfinder, None).expect("failed to load stored keys")
         });
 
         NymClient {
             config,
             key_manager,
+            passphrase: None,
         }
     }
 
@@ -105,7 +118,6 @@ impl NymClient {
         client_input: ClientInput,
         client_output: ClientOutput,
         client_status: ClientState,
-        self_address: Recipient,
         shutdown: TaskClient,
     ) {
         info!("Starting socks5 listener...");
@@ -124,6 +136,9 @@ impl NymClient {
         let ClientState {
             shared_lane_queue_lengths,
             reply_controller_sender: _,
+            packet_preparation_metrics: _,
+            statistics: _,
+            self_address,
         } = client_status;
 
         let authenticator = Authenticator::new(auth_methods, allowed_users);
@@ -211,7 +226,18 @@ impl NymClient {
         res
     }
 
+    fn failover_store(&self) -> ConfigFailoverStore<Config> {
+        ConfigFailoverStore::new(
+            self.config.get_base().get_id(),
+            self.key_manager.clone(),
+            ClientKeyPathfinder::new_from_config(self.config.get_base()),
+            self.passphrase.clone(),
+        )
+    }
+
     pub async fn start(self) -> Result<TaskManager, Socks5ClientError> {
+        let failover_store = self.failover_store();
+
         #[cfg(not(feature = "mobile"))]
         let base_builder = BaseClientBuilder::new_from_base_config(
             self.config.get_base(),
@@ -220,18 +246,31 @@ impl NymClient {
             non_wasm_helpers::setup_fs_reply_surb_backend(
                 Some(self.config.get_base().get_reply_surb_database_path()),
                 self.config.get_debug_settings(),
+                self.passphrase.as_ref(),
             )
             .await?,
         );
 
+        #[cfg(feature = "mobile")]
+        if self.passphrase.is_some() {
+            warn!("the reply surb storage encryption is not supported on mobile - the data is going to be stored in plaintext");
+        }
+
         #[cfg(feature = "mobile")]
         let base_builder = BaseClientBuilder::<_, QueryNyxdClient>::new_from_base_config(
             self.config.get_base(),
             self.key_manager,
             None,
-            setup_empty_reply_surb_backend(self.config.get_debug_settings()),
+            setup_mobile_reply_surb_backend(
+                self.config
+                    .get_base()
+                    .get_reply_surb_blob_storage_directory(),
+                self.config.get_debug_settings(),
+            ),
         );
 
+        let base_builder = base_builder.with_failover_store(Box::new(failover_store));
+
         let self_address = base_builder.as_mix_recipient();
         let mut started_client = base_builder.start_base().await?;
         let client_input = started_client.client_input.register_producer();
@@ -243,7 +282,6 @@ impl NymClient {
             client_input,
             client_output,
             client_state,
-            self_address,
             started_client.task_manager.subscribe(),
         );
 
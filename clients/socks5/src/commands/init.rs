@@ -7,7 +7,9 @@ use crate::{
     error::Socks5ClientError,
 };
 use clap::Args;
+use client_core::config::persistence::encryption::StoragePassphrase;
 use config::NymConfig;
+use nym_bin_common::passphrase::read_passphrase;
 use nym_crypto::asymmetric::identity;
 use nym_sphinx::addressing::clients::Recipient;
 use serde::Serialize;
@@ -69,6 +71,11 @@ pub(crate) struct Init {
     #[clap(long, hide = true)]
     enabled_credentials_mode: Option<bool>,
 
+    /// Encrypt the private keys and the reply surb storage with a passphrase. It is going to be
+    /// required on every subsequent run (or provided via the NYM_STORAGE_PASSPHRASE environment variable).
+    #[clap(long)]
+    encrypt_storage: bool,
+
     /// Save a summary of the initialization to a json file
     #[clap(long)]
     output_json: bool,
@@ -137,6 +144,24 @@ pub(crate) async fn execute(args: &Init) -> Result<(), Socks5ClientError> {
     // Attempt to use a user-provided gateway, if possible
     let user_chosen_gateway_id = args.gateway;
 
+    // Keys are only generated when registering with the gateway, so that's the only time we can
+    // decide whether they should be encrypted. Otherwise keep whatever has been chosen before.
+    let encrypt_storage = if register_gateway {
+        args.encrypt_storage
+    } else {
+        if args.encrypt_storage {
+            eprintln!("Not registering gateway, the existing keys are not going to get encrypted");
+        }
+        Config::load_from_file(id)
+            .map(|existing| existing.get_base().get_encrypted_storage())
+            .unwrap_or_default()
+    };
+    let passphrase = if register_gateway && encrypt_storage {
+        Some(StoragePassphrase::new(read_passphrase(true)?))
+    } else {
+        None
+    };
+
     // Load and potentially override config
     let mut config = override_config(
         Config::new(id, &provider_address.to_string()),
@@ -149,11 +174,13 @@ pub(crate) async fn execute(args: &Init) -> Result<(), Socks5ClientError> {
         register_gateway,
         user_chosen_gateway_id,
         config.get_base(),
+        passphrase.as_ref(),
     )
     .await
     .tap_err(|err| eprintln!("Failed to setup gateway\nError: {err}"))?;
 
     config.get_base_mut().set_gateway_endpoint(gateway);
+    config.get_base_mut().set_encrypted_storage(encrypt_storage);
 
     // TODO: ask the service provider we specified for its interface version and set it in the config
 
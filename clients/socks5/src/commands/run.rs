@@ -8,8 +8,10 @@ use crate::{
 };
 
 use clap::Args;
+use client_core::config::persistence::encryption::StoragePassphrase;
 use config::NymConfig;
 use log::*;
+use nym_bin_common::passphrase::read_passphrase;
 use nym_bin_common::version_checker::is_minor_version_compatible;
 use nym_crypto::asymmetric::identity;
 use nym_sphinx::addressing::clients::Recipient;
@@ -130,5 +132,13 @@ pub(crate) async fn execute(args: &Run) -> Result<(), Box<dyn std::error::Error
         return Err(Box::new(Socks5ClientError::FailedLocalVersionCheck));
     }
 
-    NymClient::new(config).run_forever().await
+    let passphrase = if config.get_base().get_encrypted_storage() {
+        Some(StoragePassphrase::new(read_passphrase(false)?))
+    } else {
+        None
+    };
+
+    NymClient::new_with_passphrase(config, passphrase)
+        .run_forever()
+        .await
 }
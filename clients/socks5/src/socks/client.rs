@@ -2,31 +2,49 @@
 
 use super::authentication::{AuthenticationMethods, Authenticator, User};
 use super::request::{SocksCommand, SocksRequest};
-use super::types::{ResponseCodeV4, ResponseCodeV5, SocksProxyError};
+use super::types::{AddrType, ResponseCodeV4, ResponseCodeV5, SocksProxyError};
+use super::udp::UdpDatagram;
 use super::{SocksVersion, RESERVED, SOCKS4_VERSION, SOCKS5_VERSION};
+use client_core::client::gateway_failover::SelfAddress;
 use client_core::client::inbound_messages::{InputMessage, InputMessageSender};
 use futures::channel::mpsc;
 use futures::task::{Context, Poll};
+use futures::StreamExt;
 use log::*;
 use nym_sphinx::addressing::clients::Recipient;
 use nym_task::connections::{LaneQueueLengths, TransmissionLane};
 use nym_task::TaskClient;
 use pin_project::pin_project;
 use proxy_helpers::connection_controller::{
-    ConnectionReceiver, ControllerCommand, ControllerSender,
+    ConnectionMessage, ConnectionReceiver, ControllerCommand, ControllerSender, DatagramReceiver,
 };
 use proxy_helpers::proxy_runner::ProxyRunner;
 use rand::RngCore;
 use service_providers_common::interface::{ProviderInterfaceVersion, RequestVersion};
 use socks5_requests::{
-    ConnectionId, RemoteAddress, Socks5ProtocolVersion, Socks5ProviderRequest, Socks5Request,
+    ConnectionErrorKind, ConnectionId, RemoteAddress, Socks5ProtocolVersion, Socks5ProviderRequest,
+    Socks5Request,
 };
 use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::UdpSocket;
+use tokio::sync::Semaphore;
 use tokio::{self, net::TcpStream};
 
+// maximum size of the UDP payload
+const MAX_UDP_DATAGRAM_SIZE: usize = 65535;
+
+/// Maximum number of UDP associations that can be running at the same time.
+pub(crate) const MAX_UDP_ASSOCIATIONS: usize = 64;
+
+// how long we're willing to wait for the service provider to tell us whether it managed
+// to connect to the remote
+const REMOTE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
+
 #[pin_project(project = StateProject)]
 enum StreamState {
     Available(TcpStream),
@@ -66,6 +84,17 @@ impl StreamState {
         }
     }
 
+    /// Returns the local address that this stream is connected to.
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            StreamState::RunningProxy => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "stream is being used to run the proxy",
+            )),
+            StreamState::Available(ref stream) => stream.local_addr(),
+        }
+    }
+
     async fn shutdown(&mut self) -> io::Result<()> {
         // shutdown should only be called if proxy is not being run. If it is, there's some bug
         // somewhere
@@ -161,6 +190,18 @@ impl Config {
             provider_protocol: self.socks5_protocol_version,
         }
     }
+
+    fn supports_connection_status(&self) -> bool {
+        self.socks5_protocol_version.supports_connection_status()
+    }
+
+    fn supports_flow_control(&self) -> bool {
+        self.socks5_protocol_version.supports_flow_control()
+    }
+
+    fn supports_datagrams(&self) -> bool {
+        self.socks5_protocol_version.supports_datagrams()
+    }
 }
 
 /// A client connecting to the Socks proxy server, because
@@ -177,9 +218,10 @@ pub(crate) struct SocksClient {
     input_sender: InputMessageSender,
     connection_id: ConnectionId,
     service_provider: Recipient,
-    self_address: Recipient,
+    self_address: SelfAddress,
     started_proxy: bool,
     lane_queue_lengths: LaneQueueLengths,
+    udp_associations: Arc<Semaphore>,
     shutdown_listener: TaskClient,
 }
 
@@ -206,8 +248,9 @@ impl SocksClient {
         input_sender: InputMessageSender,
         service_provider: &Recipient,
         controller_sender: ControllerSender,
-        self_address: &Recipient,
+        self_address: SelfAddress,
         lane_queue_lengths: LaneQueueLengths,
+        udp_associations: Arc<Semaphore>,
         mut shutdown_listener: TaskClient,
     ) -> Self {
         // If this task fails and exits, we don't want to send shutdown signal
@@ -225,9 +268,10 @@ impl SocksClient {
             authenticator,
             input_sender,
             service_provider: *service_provider,
-            self_address: *self_address,
+            self_address,
             started_proxy: false,
             lane_queue_lengths,
+            udp_associations,
             shutdown_listener,
         }
     }
@@ -238,7 +282,6 @@ impl SocksClient {
     }
 
     pub async fn send_error(&mut self, err: SocksProxyError) -> Result<(), SocksProxyError> {
-        let error_text = format!("{err}");
         let Some(ref version) = self.socks_version else {
             log::error!("Trying to send error without knowing the version");
             return Ok(());
@@ -250,15 +293,7 @@ impl SocksClient {
                 self.send_error_v4(response).await
             }
             SocksVersion::V5 => {
-                let response = if error_text.contains("Host") {
-                    ResponseCodeV5::HostUnreachable
-                } else if error_text.contains("Network") {
-                    ResponseCodeV5::NetworkUnreachable
-                } else if error_text.contains("ttl") {
-                    ResponseCodeV5::TtlExpired
-                } else {
-                    ResponseCodeV5::Failure
-                };
+                let response = err.reply_code_v5();
                 self.send_error_v5(response).await
             }
         }
@@ -266,15 +301,17 @@ impl SocksClient {
 
     // Send an error back to the client
     pub async fn send_error_v4(&mut self, r: ResponseCodeV4) -> Result<(), SocksProxyError> {
+        // the reply always has to be 8 bytes long, even if the port and address are ignored
         self.stream
-            .write_all(&[SOCKS4_VERSION, r as u8])
+            .write_all(&[0, r as u8, 0, 0, 0, 0, 0, 0])
             .await
             .map_err(|source| SocksProxyError::SocketWriteError { source })
     }
 
     pub async fn send_error_v5(&mut self, r: ResponseCodeV5) -> Result<(), SocksProxyError> {
+        // the reply always has to include the bound address, even if it's ignored on failure
         self.stream
-            .write_all(&[SOCKS5_VERSION, r as u8])
+            .write_all(&[SOCKS5_VERSION, r as u8, RESERVED, 1, 0, 0, 0, 0, 0, 0])
             .await
             .map_err(|source| SocksProxyError::SocketWriteError { source })
     }
@@ -358,7 +395,7 @@ impl SocksClient {
             self.config.socks5_protocol_version,
             self.connection_id,
             remote_address,
-            Some(self.self_address),
+            Some(self.self_address.current()),
         );
         let msg =
             Socks5ProviderRequest::new_provider_data(self.config.provider_interface_version, req);
@@ -383,10 +420,199 @@ impl SocksClient {
         }
     }
 
-    async fn run_proxy(&mut self, conn_receiver: ConnectionReceiver, remote_proxy_target: String) {
-        self.send_connect_to_mixnet(remote_proxy_target.clone())
-            .await;
+    async fn send_reset_to_mixnet(&mut self) {
+        let req = Socks5Request::new_reset(self.config.socks5_protocol_version, self.connection_id);
+        let msg =
+            Socks5ProviderRequest::new_provider_data(self.config.provider_interface_version, req);
+
+        // the lane of the connection is going to get removed as soon as we're done with it,
+        // so use the general one to make sure the reset actually gets sent
+        let lane = TransmissionLane::General;
+        let input_message = if self.config.use_surbs_for_responses {
+            InputMessage::new_anonymous(self.service_provider, msg.into_bytes(), 0, lane)
+        } else {
+            InputMessage::new_regular(self.service_provider, msg.into_bytes(), lane)
+        };
+        self.input_sender
+            .send(input_message)
+            .await
+            .expect("InputMessageReceiver has stopped receiving!");
+    }
 
+    /// Waits for the service provider to tell us whether it managed to connect to the remote.
+    /// Since the mixnet doesn't preserve ordering, the first chunk of data might overtake
+    /// the confirmation, in which case it's returned alongside the indication of whether
+    /// the remote has already closed the connection.
+    async fn wait_for_remote_connection(
+        &mut self,
+        conn_receiver: &mut ConnectionReceiver,
+    ) -> Result<Option<(Vec<u8>, bool)>, SocksProxyError> {
+        let deadline = tokio::time::Instant::now() + REMOTE_CONNECTION_TIMEOUT;
+        loop {
+            let status = tokio::select! {
+                biased;
+                _ = self.shutdown_listener.recv() => {
+                    log::trace!("SocksClient: Received shutdown while waiting for the remote connection");
+                    return Err(ResponseCodeV5::Failure.into());
+                }
+                status = tokio::time::timeout_at(deadline, conn_receiver.next()) => status,
+            };
+
+            return match status {
+                Err(_timeout) => {
+                    // make sure the service provider won't keep the connection open
+                    // if it eventually manages to establish it
+                    self.send_reset_to_mixnet().await;
+                    Err(SocksProxyError::RemoteConnectionTimeout)
+                }
+                Ok(Some(ConnectionMessage::Established)) => Ok(None),
+                Ok(Some(ConnectionMessage::Data {
+                    payload,
+                    socket_closed,
+                })) => Ok(Some((payload, socket_closed))),
+                Ok(Some(ConnectionMessage::Failed(kind))) => {
+                    Err(SocksProxyError::RemoteConnectionFailure { kind })
+                }
+                Ok(Some(ConnectionMessage::Reset)) => {
+                    Err(SocksProxyError::RemoteConnectionFailure {
+                        kind: ConnectionErrorKind::Other,
+                    })
+                }
+                // we haven't sent anything yet, so the credit is not meaningful at this point
+                Ok(Some(ConnectionMessage::Credit(_))) => continue,
+                // the controller is gone, so we must be shutting down
+                Ok(None) => Err(ResponseCodeV5::Failure.into()),
+            };
+        }
+    }
+
+    async fn send_datagram_to_mixnet(
+        &mut self,
+        remote_address: RemoteAddress,
+        data: Vec<u8>,
+        surbs: u32,
+    ) {
+        // without surbs, the return address has to be attached to every datagram as we don't know
+        // which of them is going to reach the service provider first
+        let return_address = if self.config.use_surbs_for_responses {
+            None
+        } else {
+            Some(self.self_address.current())
+        };
+
+        let req = Socks5Request::new_datagram(
+            self.config.socks5_protocol_version,
+            self.connection_id,
+            remote_address,
+            data,
+            return_address,
+        );
+        let msg =
+            Socks5ProviderRequest::new_provider_data(self.config.provider_interface_version, req);
+
+        let lane = TransmissionLane::ConnectionId(self.connection_id);
+        let input_message = if self.config.use_surbs_for_responses {
+            InputMessage::new_anonymous(self.service_provider, msg.into_bytes(), surbs, lane)
+        } else {
+            InputMessage::new_regular(self.service_provider, msg.into_bytes(), lane)
+        };
+        self.input_sender
+            .send(input_message)
+            .await
+            .expect("InputMessageReceiver has stopped receiving!");
+    }
+
+    /// Relays datagrams between the application and the service provider for as long as
+    /// the TCP connection the UDP ASSOCIATE request arrived on stays open.
+    async fn run_udp_association(
+        &mut self,
+        socket: UdpSocket,
+        mut datagram_receiver: DatagramReceiver,
+    ) -> Result<(), SocksProxyError> {
+        let connection_id = self.connection_id;
+        let client_ip = self
+            .stream
+            .peer_addr()
+            .map_err(|source| SocksProxyError::PeerAddrExtractionFailure { source })?
+            .ip();
+
+        // the address the application is sending its datagrams from
+        let mut client_udp_address = None;
+        let mut sent_any = false;
+        let mut buf = vec![0u8; MAX_UDP_DATAGRAM_SIZE];
+        let mut control_buf = [0u8; 1];
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = self.shutdown_listener.recv() => {
+                    log::trace!("UDP association: Received shutdown");
+                    break;
+                }
+                read = self.stream.read(&mut control_buf) => match read {
+                    // nothing is supposed to be sent on the control connection
+                    Ok(n) if n > 0 => continue,
+                    _ => {
+                        debug!("the control connection of UDP association {} got closed", connection_id);
+                        break;
+                    }
+                },
+                received = socket.recv_from(&mut buf) => {
+                    let (n, source) = match received {
+                        Ok(received) => received,
+                        Err(err) => {
+                            warn!("failed to receive datagram from the application: {err}");
+                            continue;
+                        }
+                    };
+                    if source.ip() != client_ip {
+                        debug!("ignoring datagram from unexpected address {source}");
+                        continue;
+                    }
+                    client_udp_address = Some(source);
+
+                    match UdpDatagram::try_from_bytes(&buf[..n]) {
+                        Ok(datagram) => {
+                            let surbs = if sent_any {
+                                self.config.per_request_surbs
+                            } else {
+                                self.config.connection_start_surbs
+                            };
+                            sent_any = true;
+                            let remote_address = datagram.address_string();
+                            self.send_datagram_to_mixnet(remote_address, datagram.data, surbs).await
+                        }
+                        Err(err) => debug!("received malformed datagram from the application: {err}")
+                    }
+                }
+                datagram = datagram_receiver.next() => {
+                    let Some(datagram) = datagram else {
+                        break;
+                    };
+                    let Some(client_address) = client_udp_address else {
+                        debug!("received a datagram before the application has sent anything");
+                        continue;
+                    };
+                    let source = match datagram.remote_addr.parse() {
+                        Ok(source) => source,
+                        Err(err) => {
+                            warn!("received datagram from malformed address {}: {err}", datagram.remote_addr);
+                            continue;
+                        }
+                    };
+
+                    let reply = UdpDatagram::encode_reply(source, datagram.data);
+                    if let Err(err) = socket.send_to(&reply, client_address).await {
+                        warn!("failed to send datagram to the application: {err}");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn run_proxy(&mut self, conn_receiver: ConnectionReceiver, remote_proxy_target: String) {
         let stream = self.stream.run_proxy();
         let peer_addr = match stream.peer_addr() {
             Ok(peer_addr) => peer_addr,
@@ -404,6 +630,29 @@ impl SocksClient {
         let request_version = self.config.request_version();
 
         let recipient = self.service_provider;
+        let credit_adapter_fn = self.config.supports_flow_control().then(|| {
+            let request_version = request_version.clone();
+            move |conn_id, credit| {
+                let provider_request =
+                    Socks5Request::new_credit(request_version.provider_protocol, conn_id, credit);
+                let provider_message = Socks5ProviderRequest::new_provider_data(
+                    request_version.provider_interface,
+                    provider_request,
+                );
+                let lane = TransmissionLane::ConnectionId(conn_id);
+                if anonymous {
+                    InputMessage::new_anonymous(
+                        recipient,
+                        provider_message.into_bytes(),
+                        per_request_surbs,
+                        lane,
+                    )
+                } else {
+                    InputMessage::new_regular(recipient, provider_message.into_bytes(), lane)
+                }
+            }
+        });
+
         let (stream, _) = ProxyRunner::new(
             stream,
             local_stream_remote,
@@ -414,29 +663,32 @@ impl SocksClient {
             Some(self.lane_queue_lengths.clone()),
             self.shutdown_listener.clone(),
         )
-        .run(move |conn_id, read_data, socket_closed| {
-            let provider_request = Socks5Request::new_send(
-                request_version.provider_protocol,
-                conn_id,
-                read_data,
-                socket_closed,
-            );
-            let provider_message = Socks5ProviderRequest::new_provider_data(
-                request_version.provider_interface,
-                provider_request,
-            );
-            let lane = TransmissionLane::ConnectionId(conn_id);
-            if anonymous {
-                InputMessage::new_anonymous(
-                    recipient,
-                    provider_message.into_bytes(),
-                    per_request_surbs,
-                    lane,
-                )
-            } else {
-                InputMessage::new_regular(recipient, provider_message.into_bytes(), lane)
-            }
-        })
+        .run(
+            move |conn_id, read_data, socket_closed| {
+                let provider_request = Socks5Request::new_send(
+                    request_version.provider_protocol,
+                    conn_id,
+                    read_data,
+                    socket_closed,
+                );
+                let provider_message = Socks5ProviderRequest::new_provider_data(
+                    request_version.provider_interface,
+                    provider_request,
+                );
+                let lane = TransmissionLane::ConnectionId(conn_id);
+                if anonymous {
+                    InputMessage::new_anonymous(
+                        recipient,
+                        provider_message.into_bytes(),
+                        per_request_surbs,
+                        lane,
+                    )
+                } else {
+                    InputMessage::new_regular(recipient, provider_message.into_bytes(), lane)
+                }
+            },
+            credit_adapter_fn,
+        )
         .await
         .into_inner();
         // recover stream from the proxy
@@ -445,8 +697,6 @@ impl SocksClient {
 
     /// Handles a client request.
     async fn handle_request(&mut self) -> Result<(), SocksProxyError> {
-        debug!("Handling CONNECT Command");
-
         let version = self
             .socks_version
             .as_ref()
@@ -460,17 +710,13 @@ impl SocksClient {
         let remote_address = request.address_string();
 
         // setup for receiving from the mixnet
-        let (mix_sender, mix_receiver) = mpsc::unbounded();
+        let (mix_sender, mut mix_receiver) = mpsc::unbounded();
 
         match request.command {
             // Use the Proxy to connect to the specified addr/port
             SocksCommand::Connect => {
+                debug!("Handling CONNECT Command");
                 trace!("Connecting to: {:?}", remote_address.clone());
-                match version {
-                    SocksVersion::V4 => self.acknowledge_socks4().await,
-                    SocksVersion::V5 => self.acknowledge_socks5().await,
-                }
-
                 self.started_proxy = true;
                 self.controller_sender
                     .unbounded_send(ControllerCommand::Insert {
@@ -478,6 +724,34 @@ impl SocksClient {
                         connection_sender: mix_sender,
                     })
                     .unwrap();
+                self.send_connect_to_mixnet(remote_address.clone()).await;
+
+                // older service providers don't tell us whether they managed to connect,
+                // so we can only hope for the best
+                let early_data = if self.config.supports_connection_status() {
+                    self.wait_for_remote_connection(&mut mix_receiver).await?
+                } else {
+                    None
+                };
+
+                match version {
+                    SocksVersion::V4 => self.acknowledge_socks4().await,
+                    SocksVersion::V5 => self.acknowledge_socks5().await,
+                }
+
+                if let Some((payload, socket_closed)) = early_data {
+                    self.stream
+                        .write_all(&payload)
+                        .await
+                        .map_err(|source| SocksProxyError::SocketWriteError { source })?;
+                    if socket_closed {
+                        // the remote is done sending, but the application might still want to write
+                        self.stream
+                            .shutdown()
+                            .await
+                            .map_err(|source| SocksProxyError::SocketShutdownFailure { source })?;
+                    }
+                }
 
                 info!(
                     "Starting proxy for {} (id: {})",
@@ -491,8 +765,64 @@ impl SocksClient {
                 );
             }
 
+            SocksCommand::UdpAssociate => {
+                debug!("Handling UDP ASSOCIATE Command");
+                // UDP is not a part of the SOCKS4 protocol
+                if *version != SocksVersion::V5 {
+                    return Err(ResponseCodeV5::CommandNotSupported.into());
+                }
+                if !self.config.supports_datagrams() {
+                    warn!("the service provider does not support relaying UDP datagrams");
+                    return Err(ResponseCodeV5::CommandNotSupported.into());
+                }
+
+                // the slot is released once the association finishes
+                let Ok(_association_permit) =
+                    Arc::clone(&self.udp_associations).try_acquire_owned()
+                else {
+                    warn!("the maximum number of UDP associations ({MAX_UDP_ASSOCIATIONS}) is already running");
+                    return Err(ResponseCodeV5::Failure.into());
+                };
+
+                // the application is going to send its datagrams to this socket. It's bound
+                // to the same address as the proxy itself, since that's the one the application
+                // could reach us on
+                let local_address = self
+                    .stream
+                    .local_addr()
+                    .map_err(|source| SocksProxyError::UdpSocketBindFailure { source })?;
+                let socket = UdpSocket::bind((local_address.ip(), 0))
+                    .await
+                    .map_err(|source| SocksProxyError::UdpSocketBindFailure { source })?;
+                let relay_address = socket
+                    .local_addr()
+                    .map_err(|source| SocksProxyError::UdpSocketBindFailure { source })?;
+
+                let (datagram_sender, datagram_receiver) = mpsc::unbounded();
+                self.started_proxy = true;
+                self.controller_sender
+                    .unbounded_send(ControllerCommand::InsertDatagram {
+                        connection_id: self.connection_id,
+                        datagram_sender,
+                    })
+                    .unwrap();
+
+                self.acknowledge_socks5_udp_associate(relay_address).await?;
+
+                info!(
+                    "Starting UDP association on {} (id: {})",
+                    relay_address, self.connection_id
+                );
+                let result = self.run_udp_association(socket, datagram_receiver).await;
+                // the service provider would otherwise keep the association around until it times out
+                if self.config.supports_connection_status() {
+                    self.send_reset_to_mixnet().await;
+                }
+                result?;
+                info!("UDP association is finished (id: {})", self.connection_id);
+            }
+
             SocksCommand::Bind => unimplemented!(), // not handled
-            SocksCommand::UdpAssociate => unimplemented!(), // not handled
         };
 
         Ok(())
@@ -518,6 +848,34 @@ impl SocksClient {
             .unwrap();
     }
 
+    /// Writes a Socks5 header back to the requesting client's TCP stream, telling it the address
+    /// of the relay it should send its datagrams to.
+    async fn acknowledge_socks5_udp_associate(
+        &mut self,
+        relay_address: SocketAddr,
+    ) -> Result<(), SocksProxyError> {
+        let (addr_type, addr) = match relay_address {
+            SocketAddr::V4(addr) => (AddrType::V4, addr.ip().octets().to_vec()),
+            SocketAddr::V6(addr) => (AddrType::V6, addr.ip().octets().to_vec()),
+        };
+
+        let response: Vec<_> = [
+            SOCKS5_VERSION,
+            ResponseCodeV5::Success as u8,
+            RESERVED,
+            addr_type as u8,
+        ]
+        .into_iter()
+        .chain(addr)
+        .chain(relay_address.port().to_be_bytes())
+        .collect();
+
+        self.stream
+            .write_all(&response)
+            .await
+            .map_err(|source| SocksProxyError::SocketWriteError { source })
+    }
+
     /// Writes a Socks4 header back to the requesting client's TCP stream,
     async fn acknowledge_socks4(&mut self) {
         self.stream
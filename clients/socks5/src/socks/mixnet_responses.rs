@@ -6,7 +6,7 @@ use client_core::client::received_buffer::ReconstructedMessagesReceiver;
 use client_core::client::received_buffer::{ReceivedBufferMessage, ReceivedBufferRequestSender};
 use nym_sphinx::receiver::ReconstructedMessage;
 use nym_task::TaskClient;
-use proxy_helpers::connection_controller::ControllerSender;
+use proxy_helpers::connection_controller::{ControllerCommand, ControllerSender};
 use service_providers_common::interface::{ControlResponse, ResponseContent};
 use socks5_requests::{Socks5ProviderResponse, Socks5Response, Socks5ResponseContent};
 
@@ -83,6 +83,36 @@ impl MixnetResponseListener {
                     .unwrap();
                 Ok(())
             }
+            Socks5ResponseContent::Datagram(response) => {
+                self.controller_sender
+                    .unbounded_send(response.into())
+                    .unwrap();
+                Ok(())
+            }
+            Socks5ResponseContent::Credit(credit) => {
+                self.controller_sender
+                    .unbounded_send(credit.into())
+                    .unwrap();
+                Ok(())
+            }
+            Socks5ResponseContent::Connected(connection_id) => {
+                debug!("Network requester has established connection {connection_id}");
+                self.controller_sender
+                    .unbounded_send(ControllerCommand::Established { connection_id })
+                    .unwrap();
+                Ok(())
+            }
+            Socks5ResponseContent::ConnectionFailure(failure) => {
+                // this is reported back to the application, so it's not really our error
+                info!(
+                    "Network requester failed on connection id {} ({:?}): {}",
+                    failure.connection_id, failure.kind, failure.message
+                );
+                self.controller_sender
+                    .unbounded_send(failure.into())
+                    .unwrap();
+                Ok(())
+            }
         }
     }
 
@@ -10,6 +10,7 @@ pub(crate) mod mixnet_responses;
 mod request;
 pub mod server;
 pub mod types;
+mod udp;
 pub mod utils;
 
 /// Version of socks
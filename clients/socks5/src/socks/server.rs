@@ -5,7 +5,8 @@ use super::{
 };
 use crate::socks::client;
 use client_core::client::{
-    inbound_messages::InputMessageSender, received_buffer::ReceivedBufferRequestSender,
+    gateway_failover::SelfAddress, inbound_messages::InputMessageSender,
+    received_buffer::ReceivedBufferRequestSender,
 };
 use log::*;
 use nym_sphinx::addressing::clients::Recipient;
@@ -13,17 +14,20 @@ use nym_task::connections::{ConnectionCommandSender, LaneQueueLengths};
 use nym_task::TaskClient;
 use proxy_helpers::connection_controller::Controller;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tap::TapFallible;
 use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
 
 /// A Socks5 server that listens for connections.
 pub struct SphinxSocksServer {
     authenticator: Authenticator,
     listening_address: SocketAddr,
     service_provider: Recipient,
-    self_address: Recipient,
+    self_address: SelfAddress,
     client_config: client::Config,
     lane_queue_lengths: LaneQueueLengths,
+    udp_associations: Arc<Semaphore>,
     shutdown: TaskClient,
 }
 
@@ -33,7 +37,7 @@ impl SphinxSocksServer {
         port: u16,
         authenticator: Authenticator,
         service_provider: Recipient,
-        self_address: Recipient,
+        self_address: SelfAddress,
         lane_queue_lengths: LaneQueueLengths,
         client_config: client::Config,
         shutdown: TaskClient,
@@ -49,6 +53,7 @@ impl SphinxSocksServer {
             self_address,
             client_config,
             lane_queue_lengths,
+            udp_associations: Arc::new(Semaphore::new(client::MAX_UDP_ASSOCIATIONS)),
             shutdown,
         }
     }
@@ -101,8 +106,9 @@ impl SphinxSocksServer {
                         input_sender.clone(),
                         &self.service_provider,
                         controller_sender.clone(),
-                        &self.self_address,
+                        self.self_address.clone(),
                         self.lane_queue_lengths.clone(),
+                        Arc::clone(&self.udp_associations),
                         self.shutdown.clone(),
                     );
 
@@ -1,4 +1,4 @@
-use socks5_requests::Socks5RequestError;
+use socks5_requests::{ConnectionErrorKind, Socks5RequestError};
 use std::string::FromUtf8Error;
 use thiserror::Error;
 
@@ -12,7 +12,7 @@ pub(crate) enum ResponseCodeV4 {
 }
 
 /// Possible SOCKS5 Response Codes
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Copy, Error)]
 pub enum ResponseCodeV5 {
     #[error("SOCKS5 Server Success")]
     Success = 0x00,
@@ -34,6 +34,19 @@ pub enum ResponseCodeV5 {
     AddrTypeNotSupported = 0x08,
 }
 
+impl From<ConnectionErrorKind> for ResponseCodeV5 {
+    fn from(kind: ConnectionErrorKind) -> Self {
+        match kind {
+            ConnectionErrorKind::NotAllowed => ResponseCodeV5::RuleFailure,
+            ConnectionErrorKind::NetworkUnreachable => ResponseCodeV5::NetworkUnreachable,
+            ConnectionErrorKind::HostUnreachable => ResponseCodeV5::HostUnreachable,
+            ConnectionErrorKind::ConnectionRefused => ResponseCodeV5::ConnectionRefused,
+            ConnectionErrorKind::TimedOut => ResponseCodeV5::TtlExpired,
+            ConnectionErrorKind::Other => ResponseCodeV5::Failure,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum SocksProxyError {
     #[error("{version} of the socks protocol is not supported by this client")]
@@ -57,6 +70,12 @@ pub enum SocksProxyError {
         source: std::io::Error,
     },
 
+    #[error("failed to bind the UDP relay socket: {source}")]
+    UdpSocketBindFailure {
+        #[source]
+        source: std::io::Error,
+    },
+
     #[error("failed to extract ip address of the connected peer: {source}")]
     PeerAddrExtractionFailure {
         #[source]
@@ -78,6 +97,12 @@ pub enum SocksProxyError {
     #[error(transparent)]
     Socks5ResponseFailure(#[from] ResponseCodeV5),
 
+    #[error("the service provider failed to connect to the remote: {kind:?}")]
+    RemoteConnectionFailure { kind: ConnectionErrorKind },
+
+    #[error("the service provider did not respond to the connection request in time")]
+    RemoteConnectionTimeout,
+
     #[error("could not complete the provider request: {source}")]
     ProviderRequestFailure {
         #[from]
@@ -85,6 +110,18 @@ pub enum SocksProxyError {
     },
 }
 
+impl SocksProxyError {
+    /// The reply code that should be sent back to the SOCKS5 application because of this error.
+    pub(crate) fn reply_code_v5(&self) -> ResponseCodeV5 {
+        match self {
+            SocksProxyError::Socks5ResponseFailure(code) => *code,
+            SocksProxyError::RemoteConnectionFailure { kind, .. } => (*kind).into(),
+            SocksProxyError::RemoteConnectionTimeout => ResponseCodeV5::TtlExpired,
+            _ => ResponseCodeV5::Failure,
+        }
+    }
+}
+
 /// DST.addr variant types
 #[derive(Debug, PartialEq)]
 pub(crate) enum AddrType {
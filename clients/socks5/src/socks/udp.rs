@@ -0,0 +1,100 @@
+use super::types::AddrType;
+use super::utils as socks_utils;
+use std::net::SocketAddr;
+use thiserror::Error;
+
+// RSV (2 bytes) || FRAG (1 byte) || ATYP (1 byte)
+const UDP_HEADER_PREFIX_LEN: usize = 4;
+
+#[derive(Debug, Error)]
+pub(crate) enum UdpDatagramError {
+    #[error("the datagram is too short to contain a valid header")]
+    TooShort,
+
+    #[error("fragmented datagrams are not supported (received fragment {0})")]
+    Fragmented(u8),
+
+    #[error("{0} is not a valid address type")]
+    UnknownAddressType(u8),
+}
+
+/// A datagram sent by the application to the UDP relay, as defined in the section 7
+/// of https://www.rfc-editor.org/rfc/rfc1928:
+///
+/// +----+------+------+----------+----------+----------+
+/// |RSV | FRAG | ATYP | DST.ADDR | DST.PORT |   DATA   |
+/// +----+------+------+----------+----------+----------+
+/// | 2  |  1   |  1   | Variable |    2     | Variable |
+/// +----+------+------+----------+----------+----------+
+pub(crate) struct UdpDatagram {
+    pub addr_type: AddrType,
+    pub addr: Vec<u8>,
+    pub port: u16,
+    pub data: Vec<u8>,
+}
+
+impl UdpDatagram {
+    pub(crate) fn try_from_bytes(b: &[u8]) -> Result<Self, UdpDatagramError> {
+        if b.len() < UDP_HEADER_PREFIX_LEN {
+            return Err(UdpDatagramError::TooShort);
+        }
+
+        // we don't do any reassembly, so only standalone datagrams are accepted
+        if b[2] != 0 {
+            return Err(UdpDatagramError::Fragmented(b[2]));
+        }
+
+        let addr_type =
+            AddrType::from(b[3] as usize).ok_or(UdpDatagramError::UnknownAddressType(b[3]))?;
+
+        let remaining = &b[UDP_HEADER_PREFIX_LEN..];
+        let (addr, remaining) = match addr_type {
+            AddrType::V4 => split_checked(remaining, 4)?,
+            AddrType::V6 => split_checked(remaining, 16)?,
+            AddrType::Domain => {
+                let (domain_length, remaining) = split_checked(remaining, 1)?;
+                split_checked(remaining, domain_length[0] as usize)?
+            }
+        };
+        let (port, data) = split_checked(remaining, 2)?;
+
+        Ok(UdpDatagram {
+            addr_type,
+            addr: addr.to_vec(),
+            port: u16::from_be_bytes([port[0], port[1]]),
+            data: data.to_vec(),
+        })
+    }
+
+    /// Print out the destination address and port to a String.
+    pub(crate) fn address_string(&self) -> String {
+        let address = socks_utils::pretty_print_addr(&self.addr_type, &self.addr);
+        if self.addr_type == AddrType::V6 {
+            format!("[{}]:{}", address, self.port)
+        } else {
+            format!("{}:{}", address, self.port)
+        }
+    }
+
+    /// Wraps data received from the `source` address in the header expected by the application.
+    pub(crate) fn encode_reply(source: SocketAddr, data: Vec<u8>) -> Vec<u8> {
+        let (addr_type, addr) = match source {
+            SocketAddr::V4(addr) => (AddrType::V4, addr.ip().octets().to_vec()),
+            SocketAddr::V6(addr) => (AddrType::V6, addr.ip().octets().to_vec()),
+        };
+
+        [0, 0, 0, addr_type as u8]
+            .into_iter()
+            .chain(addr.into_iter())
+            .chain(source.port().to_be_bytes().into_iter())
+            .chain(data.into_iter())
+            .collect()
+    }
+}
+
+fn split_checked(b: &[u8], mid: usize) -> Result<(&[u8], &[u8]), UdpDatagramError> {
+    if b.len() < mid {
+        return Err(UdpDatagramError::TooShort);
+    }
+    Ok(b.split_at(mid))
+}
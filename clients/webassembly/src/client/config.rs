@@ -4,7 +4,9 @@
 // due to expansion of #[wasm_bindgen] macro on `Debug` Config struct
 #![allow(clippy::drop_non_drop)]
 
-use client_core::config::{DebugConfig as ConfigDebug, ExtendedPacketSize, GatewayEndpointConfig};
+use client_core::config::{
+    CompressionAlgorithm, DebugConfig as ConfigDebug, ExtendedPacketSize, GatewayEndpointConfig,
+};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use url::Url;
@@ -107,6 +109,9 @@ pub struct Debug {
     /// Controls whether the sent sphinx packet use the NON-DEFAULT bigger size.
     pub use_extended_packet_size: bool,
 
+    /// Controls whether the sent packets should use the outfox format rather than sphinx.
+    pub use_outfox: bool,
+
     /// Defines the minimum number of reply surbs the client wants to keep in its storage at all times.
     /// It can only allow to go below that value if its to request additional reply surbs.
     pub minimum_reply_surb_storage_threshold: usize,
@@ -138,6 +143,14 @@ pub struct Debug {
     /// Defines maximum amount of time given reply key is going to be valid for.
     /// This is going to be superseded by key rotation once implemented.
     pub maximum_reply_key_age_ms: u64,
+
+    /// Ratio of parity to data packets attached to each sent message, so that the recipient could
+    /// reconstruct it despite losing some of the packets. If set to 0, it is disabled.
+    pub forward_error_correction_redundancy: f64,
+
+    /// Controls whether the content of the sent messages should be compressed (with deflate).
+    /// Messages are only compressed for the peers that have advertised support for it.
+    pub use_message_compression: bool,
 }
 
 impl From<Debug> for ConfigDebug {
@@ -147,6 +160,11 @@ impl From<Debug> for ConfigDebug {
             .use_extended_packet_size
             .then(|| ExtendedPacketSize::Extended32);
 
+        // zstd is not available in wasm
+        let message_compression = debug
+            .use_message_compression
+            .then_some(CompressionAlgorithm::Deflate);
+
         ConfigDebug {
             average_packet_delay: Duration::from_millis(debug.average_packet_delay_ms),
             average_ack_delay: Duration::from_millis(debug.average_ack_delay_ms),
@@ -167,6 +185,7 @@ impl From<Debug> for ConfigDebug {
             disable_main_poisson_packet_distribution: debug
                 .disable_main_poisson_packet_distribution,
             use_extended_packet_size,
+            use_outfox: debug.use_outfox,
             minimum_reply_surb_storage_threshold: debug.minimum_reply_surb_storage_threshold,
             maximum_reply_surb_storage_threshold: debug.maximum_reply_surb_storage_threshold,
             minimum_reply_surb_request_size: debug.minimum_reply_surb_request_size,
@@ -180,6 +199,11 @@ impl From<Debug> for ConfigDebug {
             ),
             maximum_reply_surb_age: Duration::from_millis(debug.maximum_reply_surb_age_ms),
             maximum_reply_key_age: Duration::from_millis(debug.maximum_reply_key_age_ms),
+            // there are no worker threads in wasm, all packets are always constructed inline
+            packet_preparation_workers: 0,
+            precomputed_cover_packets: 0,
+            forward_error_correction_redundancy: debug.forward_error_correction_redundancy,
+            message_compression,
         }
     }
 }
@@ -202,6 +226,7 @@ impl From<ConfigDebug> for Debug {
             disable_main_poisson_packet_distribution: debug
                 .disable_main_poisson_packet_distribution,
             use_extended_packet_size: debug.use_extended_packet_size.is_some(),
+            use_outfox: debug.use_outfox,
             minimum_reply_surb_storage_threshold: debug.minimum_reply_surb_storage_threshold,
             maximum_reply_surb_storage_threshold: debug.maximum_reply_surb_storage_threshold,
             minimum_reply_surb_request_size: debug.minimum_reply_surb_request_size,
@@ -215,6 +240,8 @@ impl From<ConfigDebug> for Debug {
                 .as_millis() as u64,
             maximum_reply_surb_age_ms: debug.maximum_reply_surb_age.as_millis() as u64,
             maximum_reply_key_age_ms: debug.maximum_reply_key_age.as_millis() as u64,
+            forward_error_correction_redundancy: debug.forward_error_correction_redundancy,
+            use_message_compression: debug.message_compression.is_some(),
         }
     }
 }
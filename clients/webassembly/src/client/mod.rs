@@ -7,6 +7,7 @@ use crate::client::response_pusher::ResponsePusher;
 use client_core::client::base_client::{
     BaseClientBuilder, ClientInput, ClientOutput, CredentialsToggle,
 };
+use client_core::client::received_buffer::storage::browser_backend as received_browser_backend;
 use client_core::client::replies::reply_storage::browser_backend;
 use client_core::client::{inbound_messages::InputMessage, key_manager::KeyManager};
 use gateway_client::bandwidth::BandwidthController;
@@ -30,6 +31,7 @@ mod response_pusher;
 pub struct NymClient {
     self_address: String,
     client_input: Arc<ClientInput>,
+    client_output: ClientOutput,
 
     // even though we don't use graceful shutdowns, other components rely on existence of this struct
     // and if it's dropped, everything will start going offline
@@ -47,6 +49,10 @@ pub struct NymClientBuilder {
 
     on_message: js_sys::Function,
 
+    persist_received_messages: bool,
+
+    persist_reply_storage: bool,
+
     // unimplemented:
     bandwidth_controller: Option<BandwidthController<SigningNyxdClient>>,
     disabled_credentials: bool,
@@ -62,6 +68,8 @@ impl NymClientBuilder {
             config,
             key_manager: Self::setup_key_manager(),
             on_message,
+            persist_received_messages: false,
+            persist_reply_storage: false,
             bandwidth_controller: None,
             disabled_credentials: true,
         }
@@ -78,8 +86,7 @@ impl NymClientBuilder {
         KeyManager::new(&mut rng)
     }
 
-    // don't get too excited about the name, under the hood it's just a big fat placeholder
-    // with no persistence
+    // unless `persist_reply_storage` is called, the data is only kept in memory
     fn setup_reply_surb_storage_backend(config: &Config) -> browser_backend::Backend {
         browser_backend::Backend::new(
             config.debug.minimum_reply_surb_storage_threshold,
@@ -87,6 +94,20 @@ impl NymClientBuilder {
         )
     }
 
+    /// Persist received messages in the IndexedDB of the browser until they are acknowledged
+    /// with `acknowledge_messages`, so that they would be delivered again after the client restarts.
+    pub fn persist_received_messages(mut self) -> Self {
+        self.persist_received_messages = true;
+        self
+    }
+
+    /// Persist reply SURBs, reply keys and used sender tags in the IndexedDB of the browser,
+    /// so that they would survive page reloads.
+    pub fn persist_reply_storage(mut self) -> Self {
+        self.persist_reply_storage = true;
+        self
+    }
+
     fn start_reconstructed_pusher(client_output: ClientOutput, on_message: js_sys::Function) {
         ResponsePusher::new(client_output, on_message).start()
     }
@@ -101,16 +122,53 @@ impl NymClientBuilder {
                 CredentialsToggle::Enabled
             };
 
-            let base_builder = BaseClientBuilder::new(
+            let reply_surb_storage_backend = if self.persist_reply_storage {
+                let database_name = format!("nym-reply-storage-{}", self.config.id);
+                match browser_backend::Backend::init(
+                    &database_name,
+                    self.config.debug.minimum_reply_surb_storage_threshold,
+                    self.config.debug.maximum_reply_surb_storage_threshold,
+                )
+                .await
+                {
+                    Ok(backend) => backend,
+                    Err(err) => {
+                        let error_msg = format!("failed to setup reply storage - {err}");
+                        console_error!("{}", error_msg);
+                        let js_error = js_sys::Error::new(&error_msg);
+                        return Err(JsValue::from(js_error));
+                    }
+                }
+            } else {
+                self.reply_surb_storage_backend
+            };
+
+            let mut base_builder = BaseClientBuilder::new(
                 &self.config.gateway_endpoint,
                 &self.config.debug,
                 self.key_manager,
                 self.bandwidth_controller,
-                self.reply_surb_storage_backend,
+                reply_surb_storage_backend,
                 disabled_credentials,
                 vec![self.config.nym_api_url.clone()],
             );
 
+            if self.persist_received_messages {
+                let database_name = format!("nym-received-messages-{}", self.config.id);
+                match received_browser_backend::Backend::init(&database_name).await {
+                    Ok(store) => {
+                        base_builder = base_builder.with_received_messages_store(Box::new(store))
+                    }
+                    Err(err) => {
+                        let error_msg =
+                            format!("failed to setup received messages storage - {err}");
+                        console_error!("{}", error_msg);
+                        let js_error = js_sys::Error::new(&error_msg);
+                        return Err(JsValue::from(js_error));
+                    }
+                }
+            }
+
             let self_address = base_builder.as_mix_recipient().to_string();
             let mut started_client = match base_builder.start_base().await {
                 Ok(base_client) => base_client,
@@ -125,11 +183,12 @@ impl NymClientBuilder {
             let client_input = started_client.client_input.register_producer();
             let client_output = started_client.client_output.register_consumer();
 
-            Self::start_reconstructed_pusher(client_output, self.on_message);
+            Self::start_reconstructed_pusher(client_output.clone(), self.on_message);
 
             Ok(JsValue::from(NymClient {
                 self_address,
                 client_input: Arc::new(client_input),
+                client_output,
                 _task_manager: started_client.task_manager,
             }))
         })
@@ -142,6 +201,18 @@ impl NymClient {
         self.self_address.clone()
     }
 
+    /// Confirms the messages with the provided delivery ids have been processed, so that they
+    /// are not going to be delivered again. Only relevant if received messages are persisted.
+    pub fn acknowledge_messages(&self, delivery_ids: Vec<u64>) -> Result<(), JsValue> {
+        self.client_output
+            .acknowledge_messages(delivery_ids)
+            .map_err(|err| {
+                let error_msg = format!("failed to acknowledge the messages - {err}");
+                console_error!("{}", error_msg);
+                JsValue::from(js_sys::Error::new(&error_msg))
+            })
+    }
+
     fn parse_recipient(recipient: &str) -> Result<Recipient, JsValue> {
         match Recipient::try_from_base58_string(recipient) {
             Ok(recipient) => Ok(recipient),
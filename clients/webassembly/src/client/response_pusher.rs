@@ -40,14 +40,16 @@ impl ResponsePusher {
 
             while let Some(reconstructed) = self.reconstructed_receiver.next().await {
                 for reconstructed_msg in reconstructed {
+                    let delivery_id = reconstructed_msg.delivery_id;
                     let (msg, tag) = reconstructed_msg.into_inner();
 
                     let msg_slice: &[u8] = &msg;
                     let array = Uint8Array::from(msg_slice);
                     let arg1 = JsValue::from(array);
                     let arg2 = JsValue::from(tag);
+                    let arg3 = JsValue::from(delivery_id);
                     self.on_message
-                        .call2(&this, &arg1, &arg2)
+                        .call3(&this, &arg1, &arg2, &arg3)
                         .expect("on binary message failed!");
                 }
             }
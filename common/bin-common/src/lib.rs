@@ -4,4 +4,6 @@
 pub mod build_information;
 pub mod completions;
 pub mod logging;
+#[cfg(feature = "passphrase")]
+pub mod passphrase;
 pub mod version_checker;
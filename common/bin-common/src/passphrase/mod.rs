@@ -0,0 +1,43 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io;
+
+/// Environment variable that, if set, is used instead of prompting for the passphrase,
+/// so that binaries with encrypted storage could still run unattended.
+pub const PASSPHRASE_ENV_VAR: &str = "NYM_STORAGE_PASSPHRASE";
+
+/// Reads the storage passphrase, either from the `NYM_STORAGE_PASSPHRASE` environment variable
+/// or by prompting the user for it on the terminal.
+/// If `confirm` is set, the user has to type the passphrase twice.
+pub fn read_passphrase(confirm: bool) -> io::Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+        return ensure_not_empty(passphrase);
+    }
+
+    let passphrase = ensure_not_empty(rpassword::prompt_password(
+        "Enter the storage passphrase: ",
+    )?)?;
+
+    if confirm {
+        let confirmation = rpassword::prompt_password("Confirm the storage passphrase: ")?;
+        if passphrase != confirmation {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the provided passphrases do not match",
+            ));
+        }
+    }
+
+    Ok(passphrase)
+}
+
+fn ensure_not_empty(passphrase: String) -> io::Result<String> {
+    if passphrase.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the passphrase cannot be empty",
+        ));
+    }
+    Ok(passphrase)
+}
@@ -172,6 +172,30 @@ where
         self.bandwidth_remaining
     }
 
+    /// Abandons the current gateway and registers with the specified one instead.
+    /// Note that upon success a new shared key is derived, which the caller might wish to persist.
+    pub async fn switch_gateway(
+        &mut self,
+        gateway_address: String,
+        gateway_identity: identity::PublicKey,
+    ) -> Result<Arc<SharedKeys>, GatewayClientError> {
+        info!("Switching to gateway {gateway_identity} at {gateway_address}");
+
+        // the old gateway is most likely dead so we don't really care whether we closed it cleanly
+        if let Err(err) = self.close_connection().await {
+            debug!("failed to cleanly close the connection to the previous gateway - {err}");
+        }
+        self.connection = SocketState::NotConnected;
+
+        self.gateway_address = gateway_address;
+        self.gateway_identity = gateway_identity;
+        self.shared_key = None;
+        self.authenticated = false;
+        self.bandwidth_remaining = 0;
+
+        self.authenticate_and_start().await
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     async fn _close_connection(&mut self) -> Result<(), GatewayClientError> {
         match std::mem::replace(&mut self.connection, SocketState::NotConnected) {
@@ -265,7 +289,10 @@ where
                     "failed to reconnect after {} attempts",
                     self.reconnection_attempts
                 );
-                Err(err)
+                Err(GatewayClientError::ReconnectionExhausted {
+                    attempts: self.reconnection_attempts,
+                    source: Box::new(err),
+                })
             }
         }
     }
@@ -462,7 +489,11 @@ where
             ServerResponse::Register {
                 protocol_version,
                 status,
-            } => (status, protocol_version),
+                dropped_messages,
+            } => {
+                Self::report_dropped_messages(dropped_messages);
+                (status, protocol_version)
+            }
             ServerResponse::Error { message } => {
                 return Err(GatewayClientError::GatewayError(message))
             }
@@ -478,6 +509,15 @@ where
         Ok(())
     }
 
+    fn report_dropped_messages(dropped_messages: i64) {
+        if dropped_messages > 0 {
+            log::warn!(
+                "The gateway had to drop {dropped_messages} messages sent to us while we were offline, \
+                because our inbox was full or the messages have expired"
+            );
+        }
+    }
+
     async fn authenticate(
         &mut self,
         shared_key: Option<SharedKeys>,
@@ -513,8 +553,10 @@ where
                 protocol_version,
                 status,
                 bandwidth_remaining,
+                dropped_messages,
             } => {
                 self.check_gateway_protocol(protocol_version)?;
+                Self::report_dropped_messages(dropped_messages);
                 self.authenticated = status;
                 self.bandwidth_remaining = bandwidth_remaining;
                 Ok(())
@@ -610,7 +652,7 @@ where
     fn estimate_required_bandwidth(&self, packets: &[MixPacket]) -> i64 {
         packets
             .iter()
-            .map(|packet| packet.sphinx_packet().len())
+            .map(|packet| packet.packet().len())
             .sum::<usize>() as i64
     }
 
@@ -691,9 +733,9 @@ where
         if !self.authenticated {
             return Err(GatewayClientError::NotAuthenticated);
         }
-        if (mix_packet.sphinx_packet().len() as i64) > self.bandwidth_remaining {
+        if (mix_packet.packet().len() as i64) > self.bandwidth_remaining {
             return Err(GatewayClientError::NotEnoughBandwidth(
-                mix_packet.sphinx_packet().len() as i64,
+                mix_packet.packet().len() as i64,
                 self.bandwidth_remaining,
             ));
         }
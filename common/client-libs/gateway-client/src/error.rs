@@ -91,6 +91,12 @@ pub enum GatewayClientError {
 
     #[error("Attempted to negotiate connection with gateway using incompatible protocol version. Ours is {current} and the gateway reports {gateway:?}")]
     IncompatibleProtocol { gateway: Option<u8>, current: u8 },
+
+    #[error("Failed to reconnect to the gateway after {attempts} attempts - {source}")]
+    ReconnectionExhausted {
+        attempts: usize,
+        source: Box<GatewayClientError>,
+    },
 }
 
 impl GatewayClientError {
@@ -109,4 +115,8 @@ impl GatewayClientError {
             _ => false,
         }
     }
+
+    pub fn is_reconnection_exhausted(&self) -> bool {
+        matches!(self, GatewayClientError::ReconnectionExhausted { .. })
+    }
 }
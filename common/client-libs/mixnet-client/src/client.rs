@@ -4,14 +4,14 @@
 use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
-use nym_sphinx::framing::codec::SphinxCodec;
-use nym_sphinx::framing::packet::FramedSphinxPacket;
+use nym_sphinx::framing::codec::NymCodec;
+use nym_sphinx::framing::packet::FramedNymPacket;
 use nym_sphinx::params::PacketMode;
-use nym_sphinx::{addressing::nodes::NymNodeRoutingAddress, SphinxPacket};
+use nym_sphinx::{addressing::nodes::NymNodeRoutingAddress, NymPacket};
 use std::collections::HashMap;
 use std::io;
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpStream;
@@ -50,23 +50,92 @@ pub trait SendWithoutResponse {
     fn send_without_response(
         &mut self,
         address: NymNodeRoutingAddress,
-        packet: SphinxPacket,
+        packet: NymPacket,
         packet_mode: PacketMode,
     ) -> io::Result<()>;
 }
 
+pub type DroppedPacketsSender = mpsc::UnboundedSender<DroppedPackets>;
+pub type DroppedPacketsReceiver = mpsc::UnboundedReceiver<DroppedPackets>;
+
+/// Reason for which the connection to the next hop has failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionFailure {
+    /// The remote has actively refused the connection.
+    Refused,
+
+    /// The connection could not be established within the configured timeout.
+    Timeout,
+
+    /// The connection could not be established for any other reason.
+    Unreachable,
+
+    /// The connection was established but it broke while packets were being sent through it.
+    Broken,
+}
+
+impl From<&io::Error> for ConnectionFailure {
+    fn from(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::ConnectionRefused => ConnectionFailure::Refused,
+            io::ErrorKind::TimedOut => ConnectionFailure::Timeout,
+            _ => ConnectionFailure::Unreachable,
+        }
+    }
+}
+
+/// Packets that were waiting to be sent to the next hop, but got dropped since the connection to it has failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DroppedPackets {
+    pub address: NymNodeRoutingAddress,
+    pub failure: ConnectionFailure,
+    pub count: usize,
+}
+
+/// Counts of the connections to other nodes maintained by the [`Client`].
+#[derive(Clone, Default)]
+pub struct ConnectionStats {
+    inner: Arc<ConnectionStatsInner>,
+}
+
+#[derive(Default)]
+struct ConnectionStatsInner {
+    established: AtomicUsize,
+    pending: AtomicUsize,
+    failed_attempts: AtomicU64,
+}
+
+impl ConnectionStats {
+    /// Number of currently open connections.
+    pub fn established(&self) -> usize {
+        self.inner.established.load(Ordering::Relaxed)
+    }
+
+    /// Number of connections that are currently being established.
+    pub fn pending(&self) -> usize {
+        self.inner.pending.load(Ordering::Relaxed)
+    }
+
+    /// Total number of connection attempts that have failed since startup.
+    pub fn failed_attempts(&self) -> u64 {
+        self.inner.failed_attempts.load(Ordering::Relaxed)
+    }
+}
+
 pub struct Client {
     conn_new: HashMap<NymNodeRoutingAddress, ConnectionSender>,
     config: Config,
+    connection_stats: ConnectionStats,
+    dropped_packets_sender: Option<DroppedPacketsSender>,
 }
 
 struct ConnectionSender {
-    channel: mpsc::Sender<FramedSphinxPacket>,
+    channel: mpsc::Sender<FramedNymPacket>,
     current_reconnection_attempt: Arc<AtomicU32>,
 }
 
 impl ConnectionSender {
-    fn new(channel: mpsc::Sender<FramedSphinxPacket>) -> Self {
+    fn new(channel: mpsc::Sender<FramedNymPacket>) -> Self {
         ConnectionSender {
             channel,
             current_reconnection_attempt: Arc::new(AtomicU32::new(0)),
@@ -79,30 +148,87 @@ impl Client {
         Client {
             conn_new: HashMap::new(),
             config,
+            connection_stats: ConnectionStats::default(),
+            dropped_packets_sender: None,
+        }
+    }
+
+    /// Makes the client notify the provided channel about any packets dropped due to failed connections.
+    #[must_use]
+    pub fn with_dropped_packets_sender(mut self, sender: DroppedPacketsSender) -> Self {
+        self.dropped_packets_sender = Some(sender);
+        self
+    }
+
+    pub fn connection_stats(&self) -> ConnectionStats {
+        self.connection_stats.clone()
+    }
+
+    /// Drains the packets that were waiting to be sent through a failed connection and, if requested,
+    /// reports them as dropped.
+    fn report_dropped_packets(
+        address: SocketAddr,
+        mut receiver: mpsc::Receiver<FramedNymPacket>,
+        failure: ConnectionFailure,
+        dropped_packets_sender: Option<&DroppedPacketsSender>,
+    ) {
+        // make sure nothing new is going to get pushed onto the channel
+        receiver.close();
+        let mut count = 0;
+        while let Ok(Some(_)) = receiver.try_next() {
+            count += 1;
+        }
+
+        if count == 0 {
+            return;
+        }
+        debug!("dropped {count} packets destined for {address} due to a failed connection ({failure:?})");
+
+        if let Some(sender) = dropped_packets_sender {
+            // if the receiver has been dropped, nobody is interested in the dropped packets anymore
+            let _ = sender.unbounded_send(DroppedPackets {
+                address: address.into(),
+                failure,
+                count,
+            });
         }
     }
 
     async fn manage_connection(
         address: SocketAddr,
-        receiver: mpsc::Receiver<FramedSphinxPacket>,
+        mut receiver: mpsc::Receiver<FramedNymPacket>,
         connection_timeout: Duration,
         current_reconnection: &AtomicU32,
+        connection_stats: &ConnectionStats,
+        dropped_packets_sender: Option<DroppedPacketsSender>,
     ) {
         let connection_fut = TcpStream::connect(address);
 
-        let conn = match tokio::time::timeout(connection_timeout, connection_fut).await {
+        let stats = &connection_stats.inner;
+        stats.pending.fetch_add(1, Ordering::Relaxed);
+        let connection_res = tokio::time::timeout(connection_timeout, connection_fut).await;
+        stats.pending.fetch_sub(1, Ordering::Relaxed);
+
+        let mut conn = match connection_res {
             Ok(stream_res) => match stream_res {
                 Ok(stream) => {
                     debug!("Managed to establish connection to {}", address);
                     // if we managed to connect, reset the reconnection count (whatever it might have been)
                     current_reconnection.store(0, Ordering::Release);
-                    Framed::new(stream, SphinxCodec)
+                    Framed::new(stream, NymCodec)
                 }
                 Err(err) => {
                     debug!(
                         "failed to establish connection to {} (err: {})",
                         address, err
                     );
+                    stats.failed_attempts.fetch_add(1, Ordering::Relaxed);
+                    Self::report_dropped_packets(
+                        address,
+                        receiver,
+                        ConnectionFailure::from(&err),
+                        dropped_packets_sender.as_ref(),
+                    );
                     return;
                 }
             },
@@ -114,16 +240,31 @@ impl Client {
 
                 // we failed to connect - increase reconnection attempt
                 current_reconnection.fetch_add(1, Ordering::SeqCst);
+                stats.failed_attempts.fetch_add(1, Ordering::Relaxed);
+                Self::report_dropped_packets(
+                    address,
+                    receiver,
+                    ConnectionFailure::Timeout,
+                    dropped_packets_sender.as_ref(),
+                );
                 return;
             }
         };
 
         // Take whatever the receiver channel produces and put it on the connection.
-        // We could have as well used conn.send_all(receiver.map(Ok)), but considering we don't care
-        // about neither receiver nor the connection, it doesn't matter which one gets consumed
-        if let Err(err) = receiver.map(Ok).forward(conn).await {
+        // We only borrow the receiver (and the connection) so that if the connection breaks,
+        // we could still find out how many packets were left waiting to be sent
+        stats.established.fetch_add(1, Ordering::Relaxed);
+        if let Err(err) = (&mut receiver).map(Ok).forward(&mut conn).await {
             warn!("Failed to forward packets to {} - {err}", address);
+            Self::report_dropped_packets(
+                address,
+                receiver,
+                ConnectionFailure::Broken,
+                dropped_packets_sender.as_ref(),
+            );
         }
+        stats.established.fetch_sub(1, Ordering::Relaxed);
 
         debug!(
             "connection manager to {} is finished. Either the connection failed or mixnet client got dropped",
@@ -148,11 +289,7 @@ impl Client {
         }
     }
 
-    fn make_connection(
-        &mut self,
-        address: NymNodeRoutingAddress,
-        pending_packet: FramedSphinxPacket,
-    ) {
+    fn make_connection(&mut self, address: NymNodeRoutingAddress, pending_packet: FramedNymPacket) {
         let (mut sender, receiver) = mpsc::channel(self.config.maximum_connection_buffer_size);
 
         // this CAN'T fail because we just created the channel which has a non-zero capacity
@@ -175,8 +312,10 @@ impl Client {
         let reconnection_attempt = current_reconnection_attempt.load(Ordering::Acquire);
         let backoff = self.determine_backoff(reconnection_attempt);
 
-        // copy the value before moving into another task
+        // copy the values before moving into another task
         let initial_connection_timeout = self.config.initial_connection_timeout;
+        let connection_stats = self.connection_stats.clone();
+        let dropped_packets_sender = self.dropped_packets_sender.clone();
 
         tokio::spawn(async move {
             // before executing the manager, wait for what was specified, if anything
@@ -190,6 +329,8 @@ impl Client {
                 receiver,
                 initial_connection_timeout,
                 &current_reconnection_attempt,
+                &connection_stats,
+                dropped_packets_sender,
             )
             .await
         });
@@ -200,12 +341,12 @@ impl SendWithoutResponse for Client {
     fn send_without_response(
         &mut self,
         address: NymNodeRoutingAddress,
-        packet: SphinxPacket,
+        packet: NymPacket,
         packet_mode: PacketMode,
     ) -> io::Result<()> {
         trace!("Sending packet to {:?}", address);
         let framed_packet =
-            FramedSphinxPacket::new(packet, packet_mode, self.config.use_legacy_version);
+            FramedNymPacket::new(packet, packet_mode, self.config.use_legacy_version);
 
         if let Some(sender) = self.conn_new.get_mut(&address) {
             if let Err(err) = sender.channel.try_send(framed_packet) {
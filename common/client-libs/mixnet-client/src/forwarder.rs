@@ -1,7 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::client::{Client, Config, SendWithoutResponse};
+use crate::client::{Client, Config, ConnectionStats, SendWithoutResponse};
 use futures::channel::mpsc;
 use futures::StreamExt;
 use log::*;
@@ -48,6 +48,10 @@ impl PacketForwarder {
         )
     }
 
+    pub fn connection_stats(&self) -> ConnectionStats {
+        self.mixnet_client.connection_stats()
+    }
+
     pub async fn run(&mut self) {
         while !self.shutdown.is_shutdown() {
             tokio::select! {
@@ -60,13 +64,13 @@ impl PacketForwarder {
 
                     let next_hop = mix_packet.next_hop();
                     let packet_mode = mix_packet.packet_mode();
-                    let sphinx_packet = mix_packet.into_sphinx_packet();
+                    let packet = mix_packet.into_packet();
                     // we don't care about responses, we just want to fire packets
                     // as quickly as possible
 
                     if let Err(err) =
                         self.mixnet_client
-                            .send_without_response(next_hop, sphinx_packet, packet_mode)
+                            .send_without_response(next_hop, packet, packet_mode)
                     {
                         debug!("failed to forward the packet - {err}")
                     }
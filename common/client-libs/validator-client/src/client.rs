@@ -13,7 +13,7 @@ use nym_api_requests::models::{
 };
 use nym_mixnet_contract_common::mixnode::MixNodeDetails;
 use nym_mixnet_contract_common::MixId;
-use nym_mixnet_contract_common::{GatewayBond, IdentityKeyRef};
+use nym_mixnet_contract_common::{GatewayBond, IdentityKeyRef, Interval};
 
 #[cfg(feature = "nyxd-client")]
 use crate::nyxd::traits::{DkgQueryClient, MixnetQueryClient, MultisigQueryClient};
@@ -878,6 +878,12 @@ impl NymApiClient {
         Ok(self.nym_api_client.get_gateways().await?)
     }
 
+    pub async fn get_cached_current_interval(
+        &self,
+    ) -> Result<Option<Interval>, ValidatorClientError> {
+        Ok(self.nym_api_client.get_current_interval().await?)
+    }
+
     pub async fn get_gateway_core_status_count(
         &self,
         identity: IdentityKeyRef<'_>,
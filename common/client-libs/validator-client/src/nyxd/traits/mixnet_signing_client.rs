@@ -12,6 +12,7 @@ use nym_mixnet_contract_common::mixnode::{MixNodeConfigUpdate, MixNodeCostParams
 use nym_mixnet_contract_common::reward_params::{IntervalRewardingParamsUpdate, Performance};
 use nym_mixnet_contract_common::{
     ContractStateParams, ExecuteMsg as MixnetExecuteMsg, Gateway, LayerAssignment, MixId, MixNode,
+    SphinxKey,
 };
 
 #[async_trait]
@@ -430,6 +431,19 @@ pub trait MixnetSigningClient {
         .await
     }
 
+    async fn announce_mixnode_sphinx_key(
+        &self,
+        sphinx_key: SphinxKey,
+        fee: Option<Fee>,
+    ) -> Result<ExecuteResult, NyxdError> {
+        self.execute_mixnet_contract(
+            fee,
+            MixnetExecuteMsg::AnnounceMixnodeSphinxKey { sphinx_key },
+            vec![],
+        )
+        .await
+    }
+
     // gateway-related:
 
     async fn bond_gateway(
@@ -490,6 +504,19 @@ pub trait MixnetSigningClient {
         .await
     }
 
+    async fn announce_gateway_sphinx_key(
+        &self,
+        sphinx_key: SphinxKey,
+        fee: Option<Fee>,
+    ) -> Result<ExecuteResult, NyxdError> {
+        self.execute_mixnet_contract(
+            fee,
+            MixnetExecuteMsg::AnnounceGatewaySphinxKey { sphinx_key },
+            vec![],
+        )
+        .await
+    }
+
     // delegation-related:
 
     async fn delegate_to_mixnode(
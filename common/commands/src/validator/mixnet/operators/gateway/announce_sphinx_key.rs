@@ -0,0 +1,25 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::context::SigningClient;
+use clap::Parser;
+use log::info;
+use validator_client::nyxd::traits::MixnetSigningClient;
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Base58-encoded sphinx key the gateway is going to use from the next epoch onwards
+    #[clap(long)]
+    pub sphinx_key: String,
+}
+
+pub async fn announce_sphinx_key(args: Args, client: SigningClient) {
+    info!("Announcing upcoming gateway sphinx key!");
+
+    let res = client
+        .announce_gateway_sphinx_key(args.sphinx_key, None)
+        .await
+        .expect("failed to announce gateway sphinx key!");
+
+    info!("Announcement result: {:?}", res)
+}
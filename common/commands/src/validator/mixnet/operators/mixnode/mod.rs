@@ -3,6 +3,7 @@
 
 use clap::{Args, Subcommand};
 
+pub mod announce_sphinx_key;
 pub mod bond_mixnode;
 pub mod keys;
 pub mod rewards;
@@ -34,4 +35,6 @@ pub enum MixnetOperatorsMixnodeCommands {
     BondVesting(vesting_bond_mixnode::Args),
     /// Unbound from a mixnode (when originally using locked tokens)
     UnboundVesting(vesting_unbond_mixnode::Args),
+    /// Announce the sphinx key your mixnode is going to use from the next epoch onwards
+    AnnounceSphinxKey(announce_sphinx_key::Args),
 }
@@ -73,6 +73,9 @@ pub enum MixnetContractError {
     #[error("Provided ed25519 signature did not verify correctly")]
     InvalidEd25519Signature,
 
+    #[error("Failed to recover x25519 sphinx key from its base58 representation - {0}")]
+    MalformedX25519SphinxKey(String),
+
     #[error("Can't perform the specified action as the current epoch is still progress. It started at {epoch_start} and finishes at {epoch_end}, while the current block time is {current_block_time}")]
     EpochInProgress {
         current_block_time: u64,
@@ -4,7 +4,7 @@
 // due to code generated by JsonSchema
 #![allow(clippy::field_reassign_with_default)]
 
-use crate::{IdentityKey, SphinxKey};
+use crate::{EpochId, IdentityKey, SphinxKey, SphinxKeyRef, UpcomingSphinxKey};
 use cosmwasm_std::{Addr, Coin};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -30,6 +30,11 @@ pub struct GatewayBond {
     pub block_height: u64,
     pub gateway: Gateway,
     pub proxy: Option<Addr>,
+
+    /// Sphinx key announced by the operator that is going to replace the current one
+    /// at the beginning of the specified epoch.
+    #[serde(default)]
+    pub upcoming_sphinx_key: Option<UpcomingSphinxKey>,
 }
 
 impl GatewayBond {
@@ -46,6 +51,7 @@ impl GatewayBond {
             block_height,
             gateway,
             proxy,
+            upcoming_sphinx_key: None,
         }
     }
 
@@ -64,6 +70,43 @@ impl GatewayBond {
     pub fn gateway(&self) -> &Gateway {
         &self.gateway
     }
+
+    /// Returns the sphinx key that should be used for the packets sent during the specified (absolute) epoch.
+    pub fn sphinx_key_for_epoch(&self, absolute_epoch_id: EpochId) -> SphinxKeyRef<'_> {
+        match &self.upcoming_sphinx_key {
+            Some(upcoming) if upcoming.is_active(absolute_epoch_id) => &upcoming.sphinx_key,
+            _ => &self.gateway.sphinx_key,
+        }
+    }
+
+    /// Makes the announced sphinx key the current key of the gateway if it is in use in the specified (absolute) epoch.
+    /// Returns whether the key has been promoted.
+    pub fn promote_sphinx_key(&mut self, absolute_epoch_id: EpochId) -> bool {
+        match self.upcoming_sphinx_key.take() {
+            Some(upcoming) if upcoming.is_active(absolute_epoch_id) => {
+                self.gateway.sphinx_key = upcoming.sphinx_key;
+                true
+            }
+            not_active => {
+                self.upcoming_sphinx_key = not_active;
+                false
+            }
+        }
+    }
+
+    /// Schedules the provided sphinx key to replace the current one at the beginning of the next epoch.
+    /// If the previously announced key is already in use, it becomes the current key of the gateway.
+    pub fn announce_sphinx_key(
+        &mut self,
+        sphinx_key: SphinxKey,
+        current_absolute_epoch_id: EpochId,
+    ) -> &UpcomingSphinxKey {
+        self.promote_sphinx_key(current_absolute_epoch_id);
+        self.upcoming_sphinx_key.insert(UpcomingSphinxKey::new(
+            sphinx_key,
+            current_absolute_epoch_id.saturating_add(1),
+        ))
+    }
 }
 
 impl PartialOrd for GatewayBond {
@@ -174,6 +217,7 @@ mod tests {
             block_height: 100,
             gateway: gateway_fixture(),
             proxy: None,
+            upcoming_sphinx_key: None,
         };
 
         let gate2 = GatewayBond {
@@ -182,6 +226,7 @@ mod tests {
             block_height: 120,
             gateway: gateway_fixture(),
             proxy: None,
+            upcoming_sphinx_key: None,
         };
 
         let gate3 = GatewayBond {
@@ -190,6 +235,7 @@ mod tests {
             block_height: 120,
             gateway: gateway_fixture(),
             proxy: None,
+            upcoming_sphinx_key: None,
         };
 
         let gate4 = GatewayBond {
@@ -198,6 +244,7 @@ mod tests {
             block_height: 120,
             gateway: gateway_fixture(),
             proxy: None,
+            upcoming_sphinx_key: None,
         };
 
         let gate5 = GatewayBond {
@@ -206,6 +253,7 @@ mod tests {
             block_height: 120,
             gateway: gateway_fixture(),
             proxy: None,
+            upcoming_sphinx_key: None,
         };
 
         // summary:
@@ -10,7 +10,9 @@ use crate::helpers::IntoBaseDecimal;
 use crate::reward_params::{NodeRewardParams, RewardingParams};
 use crate::rewarding::helpers::truncate_reward;
 use crate::rewarding::RewardDistribution;
-use crate::{Delegation, EpochId, IdentityKey, MixId, Percent, SphinxKey};
+use crate::{
+    Delegation, EpochId, IdentityKey, MixId, Percent, SphinxKey, SphinxKeyRef, UpcomingSphinxKey,
+};
 use cosmwasm_std::{Addr, Coin, Decimal, StdResult, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -465,6 +467,11 @@ pub struct MixNodeBond {
     /// Flag to indicate whether this node is in the process of unbonding,
     /// that will conclude upon the epoch finishing.
     pub is_unbonding: bool,
+
+    /// Sphinx key announced by the operator that is going to replace the current one
+    /// at the beginning of the specified epoch.
+    #[serde(default)]
+    pub upcoming_sphinx_key: Option<UpcomingSphinxKey>,
 }
 
 impl MixNodeBond {
@@ -486,6 +493,7 @@ impl MixNodeBond {
             proxy,
             bonding_height,
             is_unbonding: false,
+            upcoming_sphinx_key: None,
         }
     }
 
@@ -504,6 +512,43 @@ impl MixNodeBond {
     pub fn mix_node(&self) -> &MixNode {
         &self.mix_node
     }
+
+    /// Returns the sphinx key that should be used for the packets sent during the specified (absolute) epoch.
+    pub fn sphinx_key_for_epoch(&self, absolute_epoch_id: EpochId) -> SphinxKeyRef<'_> {
+        match &self.upcoming_sphinx_key {
+            Some(upcoming) if upcoming.is_active(absolute_epoch_id) => &upcoming.sphinx_key,
+            _ => &self.mix_node.sphinx_key,
+        }
+    }
+
+    /// Makes the announced sphinx key the current key of the node if it is in use in the specified (absolute) epoch.
+    /// Returns whether the key has been promoted.
+    pub fn promote_sphinx_key(&mut self, absolute_epoch_id: EpochId) -> bool {
+        match self.upcoming_sphinx_key.take() {
+            Some(upcoming) if upcoming.is_active(absolute_epoch_id) => {
+                self.mix_node.sphinx_key = upcoming.sphinx_key;
+                true
+            }
+            not_active => {
+                self.upcoming_sphinx_key = not_active;
+                false
+            }
+        }
+    }
+
+    /// Schedules the provided sphinx key to replace the current one at the beginning of the next epoch.
+    /// If the previously announced key is already in use, it becomes the current key of the node.
+    pub fn announce_sphinx_key(
+        &mut self,
+        sphinx_key: SphinxKey,
+        current_absolute_epoch_id: EpochId,
+    ) -> &UpcomingSphinxKey {
+        self.promote_sphinx_key(current_absolute_epoch_id);
+        self.upcoming_sphinx_key.insert(UpcomingSphinxKey::new(
+            sphinx_key,
+            current_absolute_epoch_id.saturating_add(1),
+        ))
+    }
 }
 
 // information provided by the operator
@@ -9,7 +9,7 @@ use crate::reward_params::{
     IntervalRewardParams, IntervalRewardingParamsUpdate, Performance, RewardingParams,
 };
 use crate::{delegation, ContractStateParams, Layer, LayerAssignment, MixId, Percent};
-use crate::{Gateway, IdentityKey, MixNode};
+use crate::{Gateway, IdentityKey, MixNode, SphinxKey};
 use cosmwasm_std::Decimal;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -181,6 +181,9 @@ pub enum ExecuteMsg {
         new_config: MixNodeConfigUpdate,
         owner: String,
     },
+    AnnounceMixnodeSphinxKey {
+        sphinx_key: SphinxKey,
+    },
 
     // gateway-related:
     BondGateway {
@@ -196,6 +199,9 @@ pub enum ExecuteMsg {
     UnbondGatewayOnBehalf {
         owner: String,
     },
+    AnnounceGatewaySphinxKey {
+        sphinx_key: SphinxKey,
+    },
 
     // delegation-related:
     DelegateToMixnode {
@@ -301,6 +307,7 @@ impl ExecuteMsg {
             ExecuteMsg::UpdateMixnodeConfigOnBehalf { .. } => {
                 "updating mixnode configuration on behalf".into()
             }
+            ExecuteMsg::AnnounceMixnodeSphinxKey { .. } => "announcing mixnode sphinx key".into(),
             ExecuteMsg::BondGateway { gateway, .. } => {
                 format!("bonding gateway {}", gateway.identity_key)
             }
@@ -309,6 +316,7 @@ impl ExecuteMsg {
             }
             ExecuteMsg::UnbondGateway { .. } => "unbonding gateway".into(),
             ExecuteMsg::UnbondGatewayOnBehalf { .. } => "unbonding gateway on behalf".into(),
+            ExecuteMsg::AnnounceGatewaySphinxKey { .. } => "announcing gateway sphinx key".into(),
             ExecuteMsg::DelegateToMixnode { mix_id } => format!("delegating to mixnode {mix_id}"),
             ExecuteMsg::DelegateToMixnodeOnBehalf { mix_id, .. } => {
                 format!("delegating to mixnode {mix_id} on behalf")
@@ -3,7 +3,7 @@
 
 use crate::mixnode::MixNodeCostParams;
 use crate::reward_params::IntervalRewardingParamsUpdate;
-use crate::{BlockHeight, EpochEventId, IntervalEventId, MixId};
+use crate::{BlockHeight, EpochEventId, IdentityKey, IntervalEventId, MixId};
 use cosmwasm_std::{Addr, Coin};
 use serde::{Deserialize, Serialize};
 
@@ -44,6 +44,12 @@ pub enum PendingEpochEventKind {
     UpdateActiveSetSize {
         new_size: u32,
     },
+    PromoteMixnodeSphinxKey {
+        mix_id: MixId,
+    },
+    PromoteGatewaySphinxKey {
+        identity: IdentityKey,
+    },
 }
 
 impl PendingEpochEventKind {
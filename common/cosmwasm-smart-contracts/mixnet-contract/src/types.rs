@@ -119,6 +119,31 @@ impl Index<Layer> for LayerDistribution {
     }
 }
 
+/// Sphinx key announced by the operator of a node that is going to replace its current key
+/// once the specified epoch begins.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct UpcomingSphinxKey {
+    /// Base58-encoded x25519 public key that is going to be used for sphinx key derivation.
+    pub sphinx_key: SphinxKey,
+
+    /// Absolute id of the first epoch during which this key is going to be used.
+    pub valid_from_epoch: EpochId,
+}
+
+impl UpcomingSphinxKey {
+    pub fn new(sphinx_key: SphinxKey, valid_from_epoch: EpochId) -> Self {
+        UpcomingSphinxKey {
+            sphinx_key,
+            valid_from_epoch,
+        }
+    }
+
+    /// Checks whether this key should already be used during the specified (absolute) epoch.
+    pub fn is_active(&self, absolute_epoch_id: EpochId) -> bool {
+        absolute_epoch_id >= self.valid_from_epoch
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ContractState {
     pub owner: Addr, // only the owner account can update state
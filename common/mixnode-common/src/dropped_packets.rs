@@ -0,0 +1,105 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured reasons for which a node might end up dropping mix packets.
+
+use crate::packet_processor::error::MixProcessingError;
+use mixnet_client::ConnectionFailure;
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    /// The next hop has actively refused our connection.
+    ConnectionRefused,
+
+    /// The connection to the next hop could not be established or it broke while sending.
+    ConnectionFailed,
+
+    /// The buffer of packets waiting to be sent to the next hop was full.
+    QueueFull,
+
+    /// The connection to the next hop could not be established within the configured timeout.
+    Timeout,
+
+    /// The received packet could not be processed.
+    Malformed,
+
+    /// The received packet has already been processed before.
+    Replay,
+}
+
+impl DropReason {
+    pub const ALL: [DropReason; 6] = [
+        DropReason::ConnectionRefused,
+        DropReason::ConnectionFailed,
+        DropReason::QueueFull,
+        DropReason::Timeout,
+        DropReason::Malformed,
+        DropReason::Replay,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DropReason::ConnectionRefused => "connection_refused",
+            DropReason::ConnectionFailed => "connection_failed",
+            DropReason::QueueFull => "queue_full",
+            DropReason::Timeout => "timeout",
+            DropReason::Malformed => "malformed",
+            DropReason::Replay => "replay",
+        }
+    }
+
+    /// Whether the packet got dropped while attempting to forward it to the next hop,
+    /// as opposed to being rejected upon getting received.
+    pub fn is_forwarding_failure(&self) -> bool {
+        !matches!(self, DropReason::Malformed | DropReason::Replay)
+    }
+}
+
+impl Display for DropReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<ConnectionFailure> for DropReason {
+    fn from(failure: ConnectionFailure) -> Self {
+        match failure {
+            ConnectionFailure::Refused => DropReason::ConnectionRefused,
+            ConnectionFailure::Timeout => DropReason::Timeout,
+            ConnectionFailure::Unreachable | ConnectionFailure::Broken => {
+                DropReason::ConnectionFailed
+            }
+        }
+    }
+}
+
+impl From<&MixProcessingError> for DropReason {
+    fn from(err: &MixProcessingError) -> Self {
+        match err {
+            MixProcessingError::ReplayedPacket => DropReason::Replay,
+            _ => DropReason::Malformed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn processing_errors_are_classified() {
+        assert_eq!(
+            DropReason::from(&MixProcessingError::ReplayedPacket),
+            DropReason::Replay
+        );
+        assert_eq!(
+            DropReason::from(&MixProcessingError::NoSurbAckInFinalHop),
+            DropReason::Malformed
+        );
+        assert!(!DropReason::Replay.is_forwarding_failure());
+        assert!(DropReason::QueueFull.is_forwarding_failure());
+    }
+}
@@ -1,5 +1,11 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod dropped_packets;
+pub mod hardware;
+pub mod metrics;
+pub mod node_description;
+pub mod packet_delayforwarder;
 pub mod packet_processor;
+pub mod sphinx_key_rotation;
 pub mod verloc;
@@ -0,0 +1,286 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Minimal support for exposing node metrics in the Prometheus text format.
+
+use std::fmt::{Display, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Upper bounds of the default latency histogram buckets, ranging from 10µs to 1s.
+pub const DEFAULT_LATENCY_BUCKETS: [Duration; 11] = [
+    Duration::from_micros(10),
+    Duration::from_micros(25),
+    Duration::from_micros(50),
+    Duration::from_micros(100),
+    Duration::from_micros(250),
+    Duration::from_micros(500),
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(25),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Display for MetricType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricType::Counter => write!(f, "counter"),
+            MetricType::Gauge => write!(f, "gauge"),
+            MetricType::Histogram => write!(f, "histogram"),
+        }
+    }
+}
+
+/// Builds a response in the Prometheus text exposition format.
+#[derive(Debug, Default)]
+pub struct PrometheusEncoder {
+    output: String,
+}
+
+impl PrometheusEncoder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Writes the `HELP` and `TYPE` lines of a metric family. It must be called before writing its samples.
+    pub fn describe(&mut self, name: &str, help: &str, metric_type: MetricType) {
+        // writing into a String can't fail
+        let _ = writeln!(self.output, "# HELP {name} {}", escape_help(help));
+        let _ = writeln!(self.output, "# TYPE {name} {metric_type}");
+    }
+
+    /// Writes a single sample of a metric with the provided labels.
+    pub fn sample<V: Display>(&mut self, name: &str, labels: &[(&str, &str)], value: V) {
+        self.output.push_str(name);
+        if !labels.is_empty() {
+            self.output.push('{');
+            for (i, (label, label_value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.output.push(',');
+                }
+                let _ = write!(
+                    self.output,
+                    "{label}=\"{}\"",
+                    escape_label_value(label_value)
+                );
+            }
+            self.output.push('}');
+        }
+        let _ = writeln!(self.output, " {value}");
+    }
+
+    /// Writes all the samples (buckets, sum and count) of a single histogram with the provided labels.
+    pub fn histogram_samples(
+        &mut self,
+        name: &str,
+        labels: &[(&str, &str)],
+        histogram: &HistogramSnapshot,
+    ) {
+        let bucket_name = format!("{name}_bucket");
+        for (upper_bound, count) in &histogram.buckets {
+            let upper_bound = upper_bound.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &upper_bound));
+            self.sample(&bucket_name, &bucket_labels, count);
+        }
+        let mut inf_labels = labels.to_vec();
+        inf_labels.push(("le", "+Inf"));
+        self.sample(&bucket_name, &inf_labels, histogram.count);
+
+        self.sample(&format!("{name}_sum"), labels, histogram.sum);
+        self.sample(&format!("{name}_count"), labels, histogram.count);
+    }
+
+    /// Convenience method for writing an entire unlabelled counter.
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.describe(name, help, MetricType::Counter);
+        self.sample(name, &[], value)
+    }
+
+    /// Convenience method for writing an entire unlabelled gauge.
+    pub fn gauge<V: Display>(&mut self, name: &str, help: &str, value: V) {
+        self.describe(name, help, MetricType::Gauge);
+        self.sample(name, &[], value)
+    }
+
+    /// Convenience method for writing an entire unlabelled histogram.
+    pub fn histogram(&mut self, name: &str, help: &str, histogram: &HistogramSnapshot) {
+        self.describe(name, help, MetricType::Histogram);
+        self.histogram_samples(name, &[], histogram)
+    }
+
+    pub fn finish(self) -> String {
+        self.output
+    }
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Histogram of observed durations that can be safely updated from multiple threads.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    inner: Arc<LatencyHistogramInner>,
+}
+
+#[derive(Debug)]
+struct LatencyHistogramInner {
+    upper_bounds: Vec<Duration>,
+
+    // note: those are NOT cumulative. The last bucket holds all values above the largest bound.
+    buckets: Vec<AtomicU64>,
+    sum_nanos: AtomicU64,
+}
+
+impl LatencyHistogram {
+    /// Creates new histogram with the provided bucket upper bounds, which must be sorted in increasing order.
+    pub fn new(upper_bounds: &[Duration]) -> Self {
+        assert!(
+            upper_bounds.windows(2).all(|w| w[0] < w[1]),
+            "the histogram bucket bounds must be strictly increasing"
+        );
+
+        LatencyHistogram {
+            inner: Arc::new(LatencyHistogramInner {
+                upper_bounds: upper_bounds.to_vec(),
+                buckets: (0..=upper_bounds.len())
+                    .map(|_| AtomicU64::new(0))
+                    .collect(),
+                sum_nanos: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn observe(&self, value: Duration) {
+        let bucket = self
+            .inner
+            .upper_bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.inner.upper_bounds.len());
+
+        self.inner.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .sum_nanos
+            .fetch_add(value.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let buckets = self
+            .inner
+            .upper_bounds
+            .iter()
+            .zip(&self.inner.buckets)
+            .map(|(bound, count)| {
+                cumulative += count.load(Ordering::Relaxed);
+                (bound.as_secs_f64(), cumulative)
+            })
+            .collect();
+
+        let overflow = self.inner.buckets[self.inner.upper_bounds.len()].load(Ordering::Relaxed);
+
+        HistogramSnapshot {
+            buckets,
+            sum: Duration::from_nanos(self.inner.sum_nanos.load(Ordering::Relaxed)).as_secs_f64(),
+            count: cumulative + overflow,
+        }
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram::new(&DEFAULT_LATENCY_BUCKETS)
+    }
+}
+
+/// Point in time view of a histogram with all values expressed in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    /// Upper bounds of the buckets alongside the cumulative number of observations within them.
+    pub buckets: Vec<(f64, u64)>,
+    pub sum: f64,
+    pub count: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let histogram =
+            LatencyHistogram::new(&[Duration::from_millis(1), Duration::from_millis(10)]);
+        histogram.observe(Duration::from_micros(500));
+        histogram.observe(Duration::from_millis(1));
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_secs(1));
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.buckets, vec![(0.001, 2), (0.01, 3)]);
+        assert_eq!(snapshot.count, 4);
+        assert!((snapshot.sum - 1.0065).abs() < 1e-9);
+    }
+
+    #[test]
+    fn encoding_samples() {
+        let mut encoder = PrometheusEncoder::new();
+        encoder.counter("foo_total", "Total number of foos.", 42);
+        encoder.describe("bar", "Bars per \"destination\".", MetricType::Gauge);
+        encoder.sample("bar", &[("destination", "1.2.3.4:1789")], 1.5);
+        encoder.sample("bar", &[("destination", "weird\"value\\")], 2);
+
+        let expected = r#"# HELP foo_total Total number of foos.
+# TYPE foo_total counter
+foo_total 42
+# HELP bar Bars per "destination".
+# TYPE bar gauge
+bar{destination="1.2.3.4:1789"} 1.5
+bar{destination="weird\"value\\"} 2
+"#;
+        assert_eq!(encoder.finish(), expected)
+    }
+
+    #[test]
+    fn encoding_histogram() {
+        let histogram =
+            LatencyHistogram::new(&[Duration::from_millis(1), Duration::from_millis(10)]);
+        histogram.observe(Duration::from_micros(500));
+        histogram.observe(Duration::from_millis(20));
+
+        let mut encoder = PrometheusEncoder::new();
+        encoder.describe("latency_seconds", "Latency.", MetricType::Histogram);
+        encoder.histogram_samples(
+            "latency_seconds",
+            &[("packet_type", "sphinx")],
+            &histogram.snapshot(),
+        );
+
+        let expected = r#"# HELP latency_seconds Latency.
+# TYPE latency_seconds histogram
+latency_seconds_bucket{packet_type="sphinx",le="0.001"} 1
+latency_seconds_bucket{packet_type="sphinx",le="0.01"} 1
+latency_seconds_bucket{packet_type="sphinx",le="+Inf"} 2
+latency_seconds_sum{packet_type="sphinx"} 0.0205
+latency_seconds_count{packet_type="sphinx"} 2
+"#;
+        assert_eq!(encoder.finish(), expected)
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::{fs, io};
+
+pub const DESCRIPTION_FILE: &str = "description.toml";
+
+/// Operator-provided description of a mixnode or a gateway served by their http apis.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct NodeDescription {
+    pub name: String,
+    pub description: String,
+    pub link: String,
+    pub location: String,
+}
+
+impl Default for NodeDescription {
+    fn default() -> Self {
+        NodeDescription {
+            name: "This node has not yet set a name".to_string(),
+            description: "This node has not yet set a description".to_string(),
+            link: "https://nymtech.net".to_string(),
+            location: "This node has not yet set a location".to_string(),
+        }
+    }
+}
+
+impl NodeDescription {
+    pub fn load_from_file<P: AsRef<Path>>(config_path: P) -> io::Result<NodeDescription> {
+        let toml = fs::read_to_string(config_path.as_ref().join(DESCRIPTION_FILE))?;
+        toml::from_str(&toml).map_err(|toml_err| io::Error::new(io::ErrorKind::Other, toml_err))
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(
+        description: &NodeDescription,
+        config_path: P,
+    ) -> io::Result<()> {
+        let description_toml =
+            toml::to_string(description).expect("could not encode description to toml");
+        fs::write(
+            config_path.as_ref().join(DESCRIPTION_FILE),
+            description_toml,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn description_survives_saving_and_loading() {
+        let directory =
+            std::env::temp_dir().join(format!("node-description-{}", rand::random::<u64>()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let description = NodeDescription {
+            name: "foomp".to_string(),
+            description: "a very good node".to_string(),
+            link: "https://nymtech.net".to_string(),
+            location: "Neuchâtel".to_string(),
+        };
+        NodeDescription::save_to_file(&description, &directory).unwrap();
+        let loaded = NodeDescription::load_from_file(&directory);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(loaded.unwrap(), description);
+    }
+}
@@ -0,0 +1,449 @@
+// Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::dropped_packets::DropReason;
+use crate::metrics::{HistogramSnapshot, LatencyHistogram};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use mixnet_client::{DroppedPackets, DroppedPacketsReceiver};
+use nonexhaustive_delayqueue::{Expired, NonExhaustiveDelayQueue};
+use nym_sphinx_forwarding::packet::MixPacket;
+use nym_task::TaskClient;
+use std::io;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Delay + MixPacket vs Instant + MixPacket
+
+// rather than using Duration directly, we use an Instant, this way we minimise skew due to
+// time packet spent waiting in the queue to get delayed
+pub type PacketDelayForwardSender = mpsc::UnboundedSender<(MixPacket, Option<Instant>)>;
+type PacketDelayForwardReceiver = mpsc::UnboundedReceiver<(MixPacket, Option<Instant>)>;
+
+/// Allows the owner of the [`DelayForwarder`] to keep track of the results of forwarding packets,
+/// for example for the purposes of node statistics.
+pub trait ForwardingReporter {
+    fn report_sent(&self, destination: String);
+
+    fn report_dropped(&self, destination: String, reason: DropReason, count: usize);
+}
+
+/// Number of packets currently being held by the [`DelayForwarder`] until their delay expires.
+#[derive(Clone, Default)]
+pub struct DelayQueueLength(Arc<AtomicUsize>);
+
+impl DelayQueueLength {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn decrement(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Accounting of the delays requested by the delayed packets against the delays they actually experienced.
+/// Both are measured from the moment the packet got handed over to the [`DelayForwarder`].
+#[derive(Clone, Default)]
+pub struct DelayAccounting {
+    inner: Arc<DelayAccountingInner>,
+}
+
+#[derive(Default)]
+struct DelayAccountingInner {
+    delayed_packets: AtomicU64,
+    requested_delay_nanos: AtomicU64,
+    actual_delay_nanos: AtomicU64,
+
+    // how much later than requested the packets got forwarded
+    lateness: LatencyHistogram,
+}
+
+impl DelayAccounting {
+    fn record(&self, requested: Duration, actual: Duration) {
+        self.inner.delayed_packets.fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .requested_delay_nanos
+            .fetch_add(requested.as_nanos() as u64, Ordering::Relaxed);
+        self.inner
+            .actual_delay_nanos
+            .fetch_add(actual.as_nanos() as u64, Ordering::Relaxed);
+        self.inner
+            .lateness
+            .observe(actual.saturating_sub(requested));
+    }
+
+    /// Number of packets that had a delay attached to them.
+    pub fn delayed_packets(&self) -> u64 {
+        self.inner.delayed_packets.load(Ordering::Relaxed)
+    }
+
+    /// Sum of the delays requested by all the delayed packets.
+    pub fn total_requested_delay(&self) -> Duration {
+        Duration::from_nanos(self.inner.requested_delay_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Sum of the delays actually experienced by all the delayed packets.
+    pub fn total_actual_delay(&self) -> Duration {
+        Duration::from_nanos(self.inner.actual_delay_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Distribution of how much later than requested the delayed packets got forwarded.
+    pub fn lateness(&self) -> HistogramSnapshot {
+        self.inner.lateness.snapshot()
+    }
+}
+
+/// Entity responsible for delaying received sphinx packet and forwarding it to next node.
+pub struct DelayForwarder<C, R>
+where
+    C: mixnet_client::SendWithoutResponse,
+    R: ForwardingReporter,
+{
+    // alongside the packet we keep the time at which it has been received for the delay accounting
+    delay_queue: NonExhaustiveDelayQueue<(MixPacket, Instant)>,
+    delay_queue_length: DelayQueueLength,
+    delay_accounting: DelayAccounting,
+    mixnet_client: C,
+    packet_sender: PacketDelayForwardSender,
+    packet_receiver: PacketDelayForwardReceiver,
+    dropped_packets_receiver: Option<DroppedPacketsReceiver>,
+    forwarding_reporter: R,
+    shutdown: TaskClient,
+}
+
+impl<C, R> DelayForwarder<C, R>
+where
+    C: mixnet_client::SendWithoutResponse,
+    R: ForwardingReporter,
+{
+    pub fn new(client: C, forwarding_reporter: R, shutdown: TaskClient) -> DelayForwarder<C, R> {
+        let (packet_sender, packet_receiver) = mpsc::unbounded();
+
+        DelayForwarder::<C, R> {
+            delay_queue: NonExhaustiveDelayQueue::new(),
+            delay_queue_length: DelayQueueLength::default(),
+            delay_accounting: DelayAccounting::default(),
+            mixnet_client: client,
+            packet_sender,
+            packet_receiver,
+            dropped_packets_receiver: None,
+            forwarding_reporter,
+            shutdown,
+        }
+    }
+
+    /// Makes the forwarder report packets dropped by the mixnet client due to failed connections.
+    #[must_use]
+    pub fn with_dropped_packets_receiver(mut self, receiver: DroppedPacketsReceiver) -> Self {
+        self.dropped_packets_receiver = Some(receiver);
+        self
+    }
+
+    pub fn sender(&self) -> PacketDelayForwardSender {
+        self.packet_sender.clone()
+    }
+
+    pub fn delay_queue_length(&self) -> DelayQueueLength {
+        self.delay_queue_length.clone()
+    }
+
+    pub fn delay_accounting(&self) -> DelayAccounting {
+        self.delay_accounting.clone()
+    }
+
+    fn forward_packet(&mut self, packet: MixPacket) {
+        let next_hop = packet.next_hop();
+        let packet_mode = packet.packet_mode();
+        let packet = packet.into_packet();
+
+        if let Err(err) = self
+            .mixnet_client
+            .send_without_response(next_hop, packet, packet_mode)
+        {
+            if err.kind() == io::ErrorKind::WouldBlock {
+                // we only know for sure if we dropped a packet if our sending queue was full
+                // in any other case the connection might still be re-established (or created for the first time)
+                // and the packet might get sent, but we won't know about it
+                self.forwarding_reporter.report_dropped(
+                    next_hop.to_string(),
+                    DropReason::QueueFull,
+                    1,
+                )
+            } else if err.kind() == io::ErrorKind::NotConnected {
+                // let's give the benefit of the doubt and assume we manage to establish connection
+                self.forwarding_reporter.report_sent(next_hop.to_string());
+            }
+        } else {
+            self.forwarding_reporter.report_sent(next_hop.to_string());
+        }
+    }
+
+    /// Upon packet being finished getting delayed, forward it to the mixnet.
+    fn handle_done_delaying(&mut self, packet: Expired<(MixPacket, Instant)>) {
+        self.delay_queue_length.decrement();
+        let deadline = packet.deadline();
+        let (delayed_packet, received_at) = packet.into_inner();
+        self.delay_accounting.record(
+            deadline.saturating_duration_since(received_at),
+            received_at.elapsed(),
+        );
+        self.forward_packet(delayed_packet)
+    }
+
+    fn handle_new_packet(&mut self, new_packet: (MixPacket, Option<Instant>)) {
+        // in case of a zero delay packet, don't bother putting it in the delay queue,
+        // just forward it immediately
+        if let Some(instant) = new_packet.1 {
+            let now = Instant::now();
+            // check if the delay has already expired, if so, don't bother putting it through
+            // the delay queue only to retrieve it immediately. Just forward it.
+            if instant.checked_duration_since(now).is_none() {
+                // the whole requested delay has been spent before the packet even got to us
+                self.delay_accounting
+                    .record(Duration::ZERO, now.saturating_duration_since(instant));
+                self.forward_packet(new_packet.0)
+            } else {
+                self.delay_queue.insert_at((new_packet.0, now), instant);
+                self.delay_queue_length.increment();
+            }
+        } else {
+            self.forward_packet(new_packet.0)
+        }
+    }
+
+    fn handle_dropped_packets(&mut self, dropped: Option<DroppedPackets>) {
+        match dropped {
+            Some(dropped) => self.forwarding_reporter.report_dropped(
+                dropped.address.to_string(),
+                dropped.failure.into(),
+                dropped.count,
+            ),
+            None => {
+                log::trace!("DelayForwarder: the dropped packets channel has been closed");
+                self.dropped_packets_receiver = None;
+            }
+        }
+    }
+
+    pub async fn run(&mut self) {
+        log::trace!("Starting DelayForwarder");
+        loop {
+            tokio::select! {
+                delayed = self.delay_queue.next() => {
+                    self.handle_done_delaying(delayed.unwrap());
+                }
+                new_packet = self.packet_receiver.next() => {
+                    // this one is impossible to ever panic - the object itself contains a sender
+                    // and hence it can't happen that ALL senders are dropped
+                    self.handle_new_packet(new_packet.unwrap())
+                }
+                dropped = next_dropped_packets(&mut self.dropped_packets_receiver) => {
+                    self.handle_dropped_packets(dropped)
+                }
+                _ = self.shutdown.recv() => {
+                    log::trace!("DelayForwarder: Received shutdown");
+                    break;
+                }
+            }
+        }
+        log::trace!("DelayForwarder: Exiting");
+    }
+}
+
+async fn next_dropped_packets(
+    receiver: &mut Option<DroppedPacketsReceiver>,
+) -> Option<DroppedPackets> {
+    match receiver {
+        Some(receiver) => receiver.next().await,
+        None => futures::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use nym_task::TaskManager;
+
+    use nym_sphinx_addressing::nodes::NymNodeRoutingAddress;
+    use nym_sphinx_params::packet_sizes::PacketSize;
+    use nym_sphinx_params::PacketMode;
+    use nym_sphinx_types::{
+        crypto, Delay as SphinxDelay, Destination, DestinationAddressBytes, Node, NodeAddressBytes,
+        NymPacket, DESTINATION_ADDRESS_LENGTH, IDENTIFIER_LENGTH, NODE_ADDRESS_LENGTH,
+    };
+
+    struct NoopReporter;
+
+    impl ForwardingReporter for NoopReporter {
+        fn report_sent(&self, _destination: String) {}
+
+        fn report_dropped(&self, _destination: String, _reason: DropReason, _count: usize) {}
+    }
+
+    #[derive(Default)]
+    struct TestClient {
+        pub packets_sent: Arc<Mutex<Vec<(NymNodeRoutingAddress, NymPacket, PacketMode)>>>,
+    }
+
+    impl mixnet_client::SendWithoutResponse for TestClient {
+        fn send_without_response(
+            &mut self,
+            address: NymNodeRoutingAddress,
+            packet: NymPacket,
+            packet_mode: PacketMode,
+        ) -> io::Result<()> {
+            self.packets_sent
+                .lock()
+                .unwrap()
+                .push((address, packet, packet_mode));
+            Ok(())
+        }
+    }
+
+    fn make_valid_sphinx_packet(size: PacketSize) -> NymPacket {
+        let (_, node1_pk) = crypto::keygen();
+        let node1 = Node::new(
+            NodeAddressBytes::from_bytes([5u8; NODE_ADDRESS_LENGTH]),
+            node1_pk,
+        );
+        let (_, node2_pk) = crypto::keygen();
+        let node2 = Node::new(
+            NodeAddressBytes::from_bytes([4u8; NODE_ADDRESS_LENGTH]),
+            node2_pk,
+        );
+        let (_, node3_pk) = crypto::keygen();
+        let node3 = Node::new(
+            NodeAddressBytes::from_bytes([2u8; NODE_ADDRESS_LENGTH]),
+            node3_pk,
+        );
+
+        let route = [node1, node2, node3];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![
+            SphinxDelay::new_from_nanos(42),
+            SphinxDelay::new_from_nanos(42),
+            SphinxDelay::new_from_nanos(42),
+        ];
+        NymPacket::sphinx_build(size.payload_size(), b"foomp", &route, &destination, &delays)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn packets_received_are_forwarded() {
+        // Wire up the DelayForwarder
+        let client = TestClient::default();
+        let client_packets_sent = client.packets_sent.clone();
+        let shutdown = TaskManager::default();
+        let mut delay_forwarder = DelayForwarder::new(client, NoopReporter, shutdown.subscribe());
+        let packet_sender = delay_forwarder.sender();
+
+        // Spawn the worker, listening on packet_sender channel
+        tokio::spawn(async move { delay_forwarder.run().await });
+
+        // Send a `MixPacket` down the channel without any delay attached.
+        let next_hop =
+            NymNodeRoutingAddress::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 42));
+        let mix_packet = MixPacket::new(
+            next_hop,
+            make_valid_sphinx_packet(PacketSize::default()),
+            PacketMode::default(),
+        );
+        let forward_instant = None;
+        packet_sender
+            .unbounded_send((mix_packet, forward_instant))
+            .unwrap();
+
+        // Give the the worker a chance to act
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // The client should have forwarded the packet straight away
+        assert_eq!(
+            client_packets_sent
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(a, _, _)| *a)
+                .collect::<Vec<_>>(),
+            vec![next_hop]
+        );
+    }
+
+    #[tokio::test]
+    async fn delayed_packets_are_counted_in_the_queue_length() {
+        let client = TestClient::default();
+        let client_packets_sent = client.packets_sent.clone();
+        let shutdown = TaskManager::default();
+        let mut delay_forwarder = DelayForwarder::new(client, NoopReporter, shutdown.subscribe());
+        let packet_sender = delay_forwarder.sender();
+        let queue_length = delay_forwarder.delay_queue_length();
+
+        tokio::spawn(async move { delay_forwarder.run().await });
+
+        let next_hop =
+            NymNodeRoutingAddress::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 42));
+        let mix_packet = MixPacket::new(
+            next_hop,
+            make_valid_sphinx_packet(PacketSize::default()),
+            PacketMode::default(),
+        );
+        let forward_instant = Some(Instant::now() + Duration::from_millis(50));
+        packet_sender
+            .unbounded_send((mix_packet, forward_instant))
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(queue_length.get(), 1);
+        assert!(client_packets_sent.lock().unwrap().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(queue_length.get(), 0);
+        assert_eq!(client_packets_sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn actual_delays_are_accounted_against_requested_ones() {
+        let client = TestClient::default();
+        let shutdown = TaskManager::default();
+        let mut delay_forwarder = DelayForwarder::new(client, NoopReporter, shutdown.subscribe());
+        let packet_sender = delay_forwarder.sender();
+        let delay_accounting = delay_forwarder.delay_accounting();
+
+        tokio::spawn(async move { delay_forwarder.run().await });
+
+        let next_hop =
+            NymNodeRoutingAddress::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 42));
+        for forward_instant in [None, Some(Instant::now() + Duration::from_millis(20))] {
+            let mix_packet = MixPacket::new(
+                next_hop,
+                make_valid_sphinx_packet(PacketSize::default()),
+                PacketMode::default(),
+            );
+            packet_sender
+                .unbounded_send((mix_packet, forward_instant))
+                .unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // packets without any delay are not accounted for
+        assert_eq!(delay_accounting.delayed_packets(), 1);
+        assert!(delay_accounting.total_requested_delay() <= Duration::from_millis(20));
+        assert!(delay_accounting.total_actual_delay() >= delay_accounting.total_requested_delay());
+        assert_eq!(delay_accounting.lateness().count, 1);
+    }
+}
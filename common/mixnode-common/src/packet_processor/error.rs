@@ -3,7 +3,9 @@
 
 use nym_sphinx_acknowledgements::surb_ack::SurbAckRecoveryError;
 use nym_sphinx_addressing::nodes::NymNodeRoutingAddressError;
-use nym_sphinx_types::Error as SphinxError;
+use nym_sphinx_types::{Error as SphinxError, OutFoxError};
+use std::io;
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,6 +13,9 @@ pub enum MixProcessingError {
     #[error("failed to process received packet: {0}")]
     SphinxProcessingError(#[from] SphinxError),
 
+    #[error("failed to process received outfox packet: {0}")]
+    OutfoxProcessingError(#[from] OutFoxError),
+
     #[error("the forward hop address was malformed: {0}")]
     InvalidForwardHopAddress(#[from] NymNodeRoutingAddressError),
 
@@ -22,4 +27,23 @@ pub enum MixProcessingError {
 
     #[error("the received packet was set to use the very old and very much deprecated 'VPN' mode")]
     ReceivedOldTypeVpnPacket,
+
+    #[error("the received packet has already been processed before")]
+    ReplayedPacket,
+}
+
+#[derive(Error, Debug)]
+pub enum ReplayProtectionError {
+    #[error("failed to access the persisted replay protection filter at {}: {source}", .path.display())]
+    PersistenceFailure {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("the persisted replay protection filter is malformed")]
+    MalformedPersistedFilter,
+
+    #[error("the persisted replay protection filter has an unsupported version {0}")]
+    UnsupportedPersistedFilterVersion(u8),
 }
@@ -3,3 +3,5 @@
 
 pub mod error;
 pub mod processor;
+pub mod replay_protection;
+pub mod sphinx_keys;
@@ -2,21 +2,44 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::packet_processor::error::MixProcessingError;
+use crate::packet_processor::replay_protection::ReplayProtectionFilter;
+use crate::packet_processor::sphinx_keys::SphinxKeys;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
 use log::*;
 use nym_sphinx_acknowledgements::surb_ack::SurbAck;
 use nym_sphinx_addressing::nodes::NymNodeRoutingAddress;
 use nym_sphinx_forwarding::packet::MixPacket;
-use nym_sphinx_framing::packet::FramedSphinxPacket;
+use nym_sphinx_framing::packet::FramedNymPacket;
 use nym_sphinx_params::{PacketMode, PacketSize};
 use nym_sphinx_types::{
-    Delay as SphinxDelay, DestinationAddressBytes, NodeAddressBytes, Payload, PrivateKey,
-    ProcessedPacket, SphinxPacket,
+    Delay as SphinxDelay, DestinationAddressBytes, ErrorKind as SphinxErrorKind, NodeAddressBytes,
+    NymPacket, OutFoxError, OutfoxPacket, Payload, PrivateKey, ProcessedPacket, SphinxPacket,
 };
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
 type ForwardAck = MixPacket;
 
+const SPHINX_REPLAY_TAG_CONTEXT: &str = "NYM_SPHINX_REPLAY_TAG";
+
+/// Derives the replay tag of a sphinx packet from the x25519 secret shared between its creator
+/// and this node. The blinded group element carried in the header can't be used directly,
+/// as it could be re-encoded (e.g. by setting the ignored most significant bit, using
+/// a non-canonical encoding or adding a low order component) without changing the derived secret,
+/// and thus without invalidating the packet.
+fn sphinx_replay_tag(node_key: &PrivateKey, blinded_element: &[u8; 32]) -> [u8; 32] {
+    // clamp the key as per x25519, which in particular clears any low order component of the element
+    let mut scalar_bytes = node_key.to_bytes();
+    scalar_bytes[0] &= 248;
+    scalar_bytes[31] &= 127;
+    scalar_bytes[31] |= 64;
+
+    let shared_secret = Scalar::from_bits(scalar_bytes) * MontgomeryPoint(*blinded_element);
+    blake3::derive_key(SPHINX_REPLAY_TAG_CONTEXT, shared_secret.as_bytes())
+}
+
 pub struct ProcessedFinalHop {
     pub destination: DestinationAddressBytes,
     pub forward_ack: Option<ForwardAck>,
@@ -33,49 +56,124 @@ pub enum MixProcessingResult {
 
 #[derive(Clone)]
 pub struct SphinxPacketProcessor {
-    /// Private sphinx key of this node required to unwrap received sphinx packet.
-    sphinx_key: Arc<PrivateKey>,
+    /// Private sphinx keys of this node required to unwrap received sphinx packet.
+    sphinx_keys: SphinxKeys,
+
+    /// Filter of the replay tags of all previously processed packets, if replay protection is enabled.
+    replay_protection: Option<ReplayProtectionFilter>,
 }
 
 impl SphinxPacketProcessor {
     /// Creates new instance of `CachedPacketProcessor`
-    pub fn new(sphinx_key: PrivateKey) -> Self {
+    pub fn new(sphinx_keys: SphinxKeys) -> Self {
         SphinxPacketProcessor {
-            sphinx_key: Arc::new(sphinx_key),
+            sphinx_keys,
+            replay_protection: None,
+        }
+    }
+
+    /// Rejects any packet whose replay tag has already been seen by the provided filter.
+    #[must_use]
+    pub fn with_replay_protection(mut self, replay_protection: ReplayProtectionFilter) -> Self {
+        self.replay_protection = Some(replay_protection);
+        self
+    }
+
+    /// Records the replay tag of a successfully unwrapped packet, failing if it has been seen before.
+    fn check_replay(&self, replay_tag: &[u8]) -> Result<(), MixProcessingError> {
+        match &self.replay_protection {
+            Some(filter) if filter.check_and_record(replay_tag) => {
+                debug!("Received a replayed packet");
+                Err(MixProcessingError::ReplayedPacket)
+            }
+            _ => Ok(()),
         }
     }
 
-    /// Performs a fresh sphinx unwrapping using no cache.
+    /// Performs a fresh sphinx unwrapping using no cache. Returns the unwrapped packet alongside
+    /// the key that was used for unwrapping it.
+    ///
+    /// Other active keys are only attempted if the header failed to get authenticated
+    /// with the current one, as no other failure could have been caused by using a wrong key.
     fn perform_initial_sphinx_packet_processing(
         &self,
         packet: SphinxPacket,
-    ) -> Result<ProcessedPacket, MixProcessingError> {
-        packet.process(&self.sphinx_key).map_err(|err| {
+        packet_bytes: Option<&[u8]>,
+    ) -> Result<(ProcessedPacket, Arc<PrivateKey>), MixProcessingError> {
+        let keys = self.sphinx_keys.active_keys();
+
+        // processing consumes the packet, so if we might have to retry it with other keys,
+        // we have to be able to reconstruct it. Use the bytes it was received as if available
+        // so that we wouldn't have to serialize it again
+        let packet_bytes = keys.has_fallback().then(|| match packet_bytes {
+            Some(bytes) => Cow::Borrowed(bytes),
+            None => Cow::Owned(packet.to_bytes()),
+        });
+
+        let mut processed = packet
+            .process(&keys.current)
+            .map(|processed| (processed, Arc::clone(&keys.current)));
+        if let Some(packet_bytes) = packet_bytes {
+            for key in keys.fallback() {
+                match &processed {
+                    Err(err) if matches!(err.kind(), SphinxErrorKind::InvalidHeader) => (),
+                    _ => break,
+                }
+                processed = SphinxPacket::from_bytes(&packet_bytes)
+                    .and_then(|packet| packet.process(key))
+                    .map(|processed| (processed, Arc::clone(key)));
+            }
+        }
+
+        processed.map_err(|err| {
             debug!("Failed to unwrap Sphinx packet: {err}");
             MixProcessingError::SphinxProcessingError(err)
         })
     }
 
-    /// Takes the received framed packet and tries to unwrap it from the sphinx encryption.
-    fn perform_initial_unwrapping(
+    /// Unwraps a single layer of the received outfox packet.
+    fn perform_initial_outfox_packet_processing(
         &self,
-        received: FramedSphinxPacket,
-    ) -> Result<ProcessedPacket, MixProcessingError> {
-        let packet_mode = received.packet_mode();
-        let sphinx_packet = received.into_inner();
+        mut packet: OutfoxPacket,
+        packet_size: PacketSize,
+        packet_mode: PacketMode,
+    ) -> Result<MixProcessingResult, MixProcessingError> {
+        let keys = self.sphinx_keys.active_keys();
 
-        if packet_mode.is_old_vpn() {
-            return Err(MixProcessingError::ReceivedOldTypeVpnPacket);
+        // the packet is left untouched unless its layer gets successfully authenticated,
+        // so it can be retried with other keys as it is. But only if the authentication has failed,
+        // as no other failure could have been caused by using a wrong key
+        let mut decoded = packet.decode_next_layer_with_replay_tag(&keys.current.to_bytes());
+        for key in keys.fallback() {
+            match &decoded {
+                Err(OutFoxError::ChaCha20Poly1305Error) => (),
+                _ => break,
+            }
+            decoded = packet.decode_next_layer_with_replay_tag(&key.to_bytes());
         }
 
-        self.perform_initial_sphinx_packet_processing(sphinx_packet)
+        let (routing_information, replay_tag) = decoded.map_err(|err| {
+            debug!("Failed to unwrap Outfox packet: {err}");
+            MixProcessingError::OutfoxProcessingError(err)
+        })?;
+        self.check_replay(&replay_tag)?;
+
+        if packet.is_fully_unwrapped() {
+            let destination = DestinationAddressBytes::from_bytes(routing_information.next_address);
+            let packet_message = packet.recover_plaintext()?;
+            self.process_final_hop_message(destination, packet_message, packet_size, packet_mode)
+        } else {
+            let forward_address = NodeAddressBytes::from_bytes(routing_information.next_address);
+            let delay = SphinxDelay::new_from_nanos(routing_information.delay);
+            self.process_forward_hop(packet.into(), forward_address, delay, packet_mode)
+        }
     }
 
     /// Processed received forward hop packet - tries to extract next hop address, sets delay
     /// and packs all the data in a way that can be easily sent to the next hop.
     fn process_forward_hop(
         &self,
-        packet: SphinxPacket,
+        packet: NymPacket,
         forward_address: NodeAddressBytes,
         delay: SphinxDelay,
         packet_mode: PacketMode,
@@ -119,11 +217,18 @@ impl SphinxPacketProcessor {
             PacketSize::RegularPacket
             | PacketSize::ExtendedPacket8
             | PacketSize::ExtendedPacket16
-            | PacketSize::ExtendedPacket32 => {
+            | PacketSize::ExtendedPacket32
+            | PacketSize::OutfoxRegularPacket => {
                 trace!("received a normal packet!");
                 let (ack_data, message) = self.split_hop_data_into_ack_and_message(data)?;
                 let (ack_first_hop, ack_packet) = SurbAck::try_recover_first_hop_packet(&ack_data)?;
-                let forward_ack = MixPacket::new(ack_first_hop, ack_packet, packet_mode);
+                // SURB-Acks are always sphinx packets, even if they were carried inside an outfox packet
+                let ack_mode = if packet_mode.is_outfox() {
+                    PacketMode::Mix
+                } else {
+                    packet_mode
+                };
+                let forward_ack = MixPacket::new(ack_first_hop, ack_packet.into(), ack_mode);
                 Ok((Some(forward_ack), message))
             }
         }
@@ -140,7 +245,18 @@ impl SphinxPacketProcessor {
         packet_mode: PacketMode,
     ) -> Result<MixProcessingResult, MixProcessingError> {
         let packet_message = payload.recover_plaintext()?;
+        self.process_final_hop_message(destination, packet_message, packet_size, packet_mode)
+    }
 
+    /// Splits the recovered final hop plaintext into the SURBAck (if applicable) and the message
+    /// that should get delivered to the destination.
+    fn process_final_hop_message(
+        &self,
+        destination: DestinationAddressBytes,
+        packet_message: Vec<u8>,
+        packet_size: PacketSize,
+        packet_mode: PacketMode,
+    ) -> Result<MixProcessingResult, MixProcessingError> {
         let (forward_ack, message) =
             self.split_into_ack_and_message(packet_message, packet_size, packet_mode)?;
 
@@ -161,7 +277,7 @@ impl SphinxPacketProcessor {
     ) -> Result<MixProcessingResult, MixProcessingError> {
         match packet {
             ProcessedPacket::ForwardHop(packet, address, delay) => {
-                self.process_forward_hop(*packet, address, delay, packet_mode)
+                self.process_forward_hop((*packet).into(), address, delay, packet_mode)
             }
             // right now there's no use for the surb_id included in the header - probably it should get removed from the
             // sphinx all together?
@@ -173,18 +289,45 @@ impl SphinxPacketProcessor {
 
     pub fn process_received(
         &self,
-        received: FramedSphinxPacket,
+        received: FramedNymPacket,
     ) -> Result<MixProcessingResult, MixProcessingError> {
         // explicit packet size will help to correctly parse final hop
         let packet_size = received.packet_size();
         let packet_mode = received.packet_mode();
 
-        // unwrap the sphinx packet and if possible and appropriate, cache keys
-        let processed_packet = self.perform_initial_unwrapping(received)?;
+        if packet_mode.is_old_vpn() {
+            return Err(MixProcessingError::ReceivedOldTypeVpnPacket);
+        }
 
-        // for forward packets, extract next hop and set delay (but do NOT delay here)
-        // for final packets, extract SURBAck
-        self.perform_final_processing(processed_packet, packet_size, packet_mode)
+        let (packet, packet_bytes) = received.into_parts();
+        match packet {
+            NymPacket::Sphinx(sphinx_packet) => {
+                let blinded_element = *sphinx_packet.header.shared_secret.as_bytes();
+
+                // unwrap the sphinx packet and if possible and appropriate, cache keys
+                let (processed_packet, key) = self.perform_initial_sphinx_packet_processing(
+                    sphinx_packet,
+                    packet_bytes.as_deref(),
+                )?;
+
+                // only record the tag once the packet is known to be valid, so that it couldn't be
+                // used for preemptively blocking legitimate packets
+                if self.replay_protection.is_some() {
+                    self.check_replay(&sphinx_replay_tag(&key, &blinded_element))?;
+                }
+
+                // for forward packets, extract next hop and set delay (but do NOT delay here)
+                // for final packets, extract SURBAck
+                self.perform_final_processing(processed_packet, packet_size, packet_mode)
+            }
+            // outfox packets carry the same routing information as sphinx, so after unwrapping
+            // the layer they're processed in exactly the same way
+            NymPacket::Outfox(outfox_packet) => self.perform_initial_outfox_packet_processing(
+                outfox_packet,
+                packet_size,
+                packet_mode,
+            ),
+        }
     }
 }
 
@@ -192,11 +335,18 @@ impl SphinxPacketProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::packet_processor::replay_protection::Config as ReplayProtectionConfig;
+    use bytes::BytesMut;
+    use nym_sphinx_framing::codec::NymCodec;
     use nym_sphinx_types::crypto::keygen;
+    use nym_sphinx_types::{Destination, Node, DESTINATION_ADDRESS_LENGTH, IDENTIFIER_LENGTH};
+    use std::convert::TryInto;
+    use std::net::SocketAddr;
+    use tokio_util::codec::{Decoder, Encoder};
 
     fn fixture() -> SphinxPacketProcessor {
         let local_keys = keygen();
-        SphinxPacketProcessor::new(local_keys.0)
+        SphinxPacketProcessor::new(SphinxKeys::new(local_keys.0))
     }
 
     #[tokio::test]
@@ -234,4 +384,355 @@ mod tests {
         assert!(ack.is_none());
         assert_eq!(data, message)
     }
+
+    #[tokio::test]
+    async fn outfox_forward_hop_can_be_processed() {
+        let (node1_sk, node1_pk) = keygen();
+        let (_, node2_pk) = keygen();
+        let (_, node3_pk) = keygen();
+
+        let node2_address: SocketAddr = "1.2.3.4:1789".parse().unwrap();
+        let node2_routing_address = NymNodeRoutingAddress::from(node2_address);
+
+        let route = [
+            Node::new(
+                NymNodeRoutingAddress::from("4.3.2.1:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                node1_pk,
+            ),
+            Node::new(node2_routing_address.try_into().unwrap(), node2_pk),
+            Node::new(
+                NymNodeRoutingAddress::from("5.6.7.8:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                node3_pk,
+            ),
+        ];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![SphinxDelay::new_from_nanos(42); 3];
+        let payload = vec![42u8; PacketSize::OutfoxRegularPacket.plaintext_size()];
+
+        let packet = NymPacket::outfox_build(payload, &route, &destination, &delays).unwrap();
+        let framed = FramedNymPacket::new(packet, PacketMode::Outfox, false);
+        assert_eq!(framed.packet_size(), PacketSize::OutfoxRegularPacket);
+
+        let processor = SphinxPacketProcessor::new(SphinxKeys::new(node1_sk));
+        match processor.process_received(framed).unwrap() {
+            MixProcessingResult::ForwardHop(mix_packet, delay) => {
+                assert_eq!(mix_packet.next_hop(), node2_routing_address);
+                assert_eq!(mix_packet.packet_mode(), PacketMode::Outfox);
+                assert_eq!(
+                    mix_packet.packet().len(),
+                    PacketSize::OutfoxRegularPacket.size()
+                );
+                assert_eq!(delay, Some(SphinxDelay::new_from_nanos(42)));
+            }
+            MixProcessingResult::FinalHop(..) => panic!("expected a forward hop"),
+        }
+    }
+
+    #[tokio::test]
+    async fn replayed_packets_are_rejected() {
+        let (node_sk, node_pk) = keygen();
+        let route = [Node::new(
+            NymNodeRoutingAddress::from("1.2.3.4:1789".parse::<SocketAddr>().unwrap())
+                .try_into()
+                .unwrap(),
+            node_pk,
+        )];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![SphinxDelay::new_from_nanos(42)];
+        let payload = vec![42u8; PacketSize::OutfoxRegularPacket.plaintext_size()];
+        let packet_bytes = NymPacket::outfox_build(payload, &route, &destination, &delays)
+            .unwrap()
+            .to_bytes();
+
+        let framed = || {
+            let packet = NymPacket::outfox_from_bytes(&packet_bytes).unwrap();
+            FramedNymPacket::new(packet, PacketMode::Outfox, false)
+        };
+
+        let filter = ReplayProtectionFilter::new(
+            ReplayProtectionConfig::build()
+                .expected_packets_per_generation(100)
+                .build(),
+        );
+        let processor = SphinxPacketProcessor::new(SphinxKeys::new(node_sk))
+            .with_replay_protection(filter.clone());
+
+        // the first packet is fine, even if its content turns out to be meaningless
+        assert!(!matches!(
+            processor.process_received(framed()),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+        assert!(matches!(
+            processor.process_received(framed()),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+        assert_eq!(filter.metrics().rejected_replays(), 1);
+    }
+
+    #[tokio::test]
+    async fn reencoded_outfox_packets_are_rejected_as_replays() {
+        let (node_sk, node_pk) = keygen();
+        let route = [Node::new(
+            NymNodeRoutingAddress::from("1.2.3.4:1789".parse::<SocketAddr>().unwrap())
+                .try_into()
+                .unwrap(),
+            node_pk,
+        )];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![SphinxDelay::new_from_nanos(42)];
+        let payload = vec![42u8; PacketSize::OutfoxRegularPacket.plaintext_size()];
+        let packet_bytes = NymPacket::outfox_build(payload, &route, &destination, &delays)
+            .unwrap()
+            .to_bytes();
+
+        // the most significant bit of the public element (following the 4 byte payload length)
+        // does not affect the derived shared key
+        let mut reencoded_bytes = packet_bytes.clone();
+        reencoded_bytes[4 + 31] ^= 0x80;
+
+        let framed = |bytes: &[u8]| {
+            let packet = NymPacket::outfox_from_bytes(bytes).unwrap();
+            FramedNymPacket::new(packet, PacketMode::Outfox, false)
+        };
+
+        let processor = SphinxPacketProcessor::new(SphinxKeys::new(node_sk))
+            .with_replay_protection(ReplayProtectionFilter::new(
+                ReplayProtectionConfig::build()
+                    .expected_packets_per_generation(100)
+                    .build(),
+            ));
+
+        assert!(!matches!(
+            processor.process_received(framed(&packet_bytes)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+        assert!(matches!(
+            processor.process_received(framed(&reencoded_bytes)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+    }
+
+    #[tokio::test]
+    async fn replayed_sphinx_packets_are_rejected() {
+        let (node_sk, node_pk) = keygen();
+        let route = [Node::new(
+            NymNodeRoutingAddress::from("1.2.3.4:1789".parse::<SocketAddr>().unwrap())
+                .try_into()
+                .unwrap(),
+            node_pk,
+        )];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![SphinxDelay::new_from_nanos(42)];
+        let size = PacketSize::RegularPacket;
+        let packet_bytes =
+            NymPacket::sphinx_build(size.payload_size(), b"foomp", &route, &destination, &delays)
+                .unwrap()
+                .to_bytes();
+
+        // the header starts with the blinded group element, whose most significant bit is ignored
+        let mut reencoded_bytes = packet_bytes.clone();
+        reencoded_bytes[31] ^= 0x80;
+
+        let framed = |bytes: &[u8]| {
+            let packet = NymPacket::sphinx_from_bytes(bytes).unwrap();
+            FramedNymPacket::new(packet, PacketMode::Mix, false)
+        };
+
+        let processor = SphinxPacketProcessor::new(SphinxKeys::new(node_sk))
+            .with_replay_protection(ReplayProtectionFilter::new(
+                ReplayProtectionConfig::build()
+                    .expected_packets_per_generation(100)
+                    .build(),
+            ));
+
+        assert!(!matches!(
+            processor.process_received(framed(&packet_bytes)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+        assert!(matches!(
+            processor.process_received(framed(&packet_bytes)),
+            Err(MixProcessingError::ReplayedPacket)
+        ));
+        assert!(processor
+            .process_received(framed(&reencoded_bytes))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn packets_for_previous_key_are_accepted_within_overlap() {
+        let (old_sk, old_pk) = keygen();
+        let (new_sk, _) = keygen();
+        let (_, next_pk) = keygen();
+        let route = [
+            Node::new(
+                NymNodeRoutingAddress::from("1.2.3.4:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                old_pk,
+            ),
+            Node::new(
+                NymNodeRoutingAddress::from("5.6.7.8:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                next_pk,
+            ),
+        ];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![SphinxDelay::new_from_nanos(42); 2];
+        let payload = vec![42u8; PacketSize::OutfoxRegularPacket.plaintext_size()];
+        let packet_bytes = NymPacket::outfox_build(payload, &route, &destination, &delays)
+            .unwrap()
+            .to_bytes();
+
+        let framed = || {
+            let packet = NymPacket::outfox_from_bytes(&packet_bytes).unwrap();
+            FramedNymPacket::new(packet, PacketMode::Outfox, false)
+        };
+
+        let keys = SphinxKeys::new(old_sk);
+        let processor = SphinxPacketProcessor::new(keys.clone());
+
+        keys.rotate(new_sk, std::time::Duration::from_secs(60));
+        assert!(matches!(
+            processor.process_received(framed()),
+            Ok(MixProcessingResult::ForwardHop(..))
+        ));
+
+        // once the overlap is over, the previous key is no longer accepted
+        let (newer_sk, _) = keygen();
+        keys.rotate(newer_sk, std::time::Duration::ZERO);
+        assert!(matches!(
+            processor.process_received(framed()),
+            Err(MixProcessingError::OutfoxProcessingError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn packets_for_announced_upcoming_key_are_accepted() {
+        let (current_sk, _) = keygen();
+        let (upcoming_sk, upcoming_pk) = keygen();
+        let (_, next_pk) = keygen();
+        let route = [
+            Node::new(
+                NymNodeRoutingAddress::from("1.2.3.4:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                upcoming_pk,
+            ),
+            Node::new(
+                NymNodeRoutingAddress::from("5.6.7.8:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                next_pk,
+            ),
+        ];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![SphinxDelay::new_from_nanos(42); 2];
+        let payload = vec![42u8; PacketSize::OutfoxRegularPacket.plaintext_size()];
+        let packet_bytes = NymPacket::outfox_build(payload, &route, &destination, &delays)
+            .unwrap()
+            .to_bytes();
+
+        let framed = || {
+            let packet = NymPacket::outfox_from_bytes(&packet_bytes).unwrap();
+            FramedNymPacket::new(packet, PacketMode::Outfox, false)
+        };
+
+        let keys = SphinxKeys::new(current_sk);
+        let processor = SphinxPacketProcessor::new(keys.clone());
+
+        // the key hasn't been announced yet
+        assert!(matches!(
+            processor.process_received(framed()),
+            Err(MixProcessingError::OutfoxProcessingError(_))
+        ));
+
+        keys.accept_upcoming(upcoming_sk);
+        assert!(matches!(
+            processor.process_received(framed()),
+            Ok(MixProcessingResult::ForwardHop(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn received_sphinx_packets_for_previous_key_are_accepted_within_overlap() {
+        let (old_sk, old_pk) = keygen();
+        let (new_sk, _) = keygen();
+        let (_, next_pk) = keygen();
+        let route = [
+            Node::new(
+                NymNodeRoutingAddress::from("1.2.3.4:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                old_pk,
+            ),
+            Node::new(
+                NymNodeRoutingAddress::from("5.6.7.8:1789".parse::<SocketAddr>().unwrap())
+                    .try_into()
+                    .unwrap(),
+                next_pk,
+            ),
+        ];
+        let destination = Destination::new(
+            DestinationAddressBytes::from_bytes([3u8; DESTINATION_ADDRESS_LENGTH]),
+            [4u8; IDENTIFIER_LENGTH],
+        );
+        let delays = vec![SphinxDelay::new_from_nanos(42); 2];
+        let size = PacketSize::RegularPacket;
+        let packet_bytes =
+            NymPacket::sphinx_build(size.payload_size(), b"foomp", &route, &destination, &delays)
+                .unwrap()
+                .to_bytes();
+
+        // go through the codec so that the packet would carry the bytes it was received as
+        let received = || {
+            let packet = NymPacket::sphinx_from_bytes(&packet_bytes).unwrap();
+            let mut bytes = BytesMut::new();
+            NymCodec
+                .encode(
+                    FramedNymPacket::new(packet, PacketMode::Mix, false),
+                    &mut bytes,
+                )
+                .unwrap();
+            NymCodec.decode(&mut bytes).unwrap().unwrap()
+        };
+
+        let keys = SphinxKeys::new(old_sk);
+        let processor = SphinxPacketProcessor::new(keys.clone());
+
+        keys.rotate(new_sk, std::time::Duration::from_secs(60));
+        assert!(matches!(
+            processor.process_received(received()),
+            Ok(MixProcessingResult::ForwardHop(..))
+        ));
+
+        let (newer_sk, _) = keygen();
+        keys.rotate(newer_sk, std::time::Duration::ZERO);
+        assert!(matches!(
+            processor.process_received(received()),
+            Err(MixProcessingError::SphinxProcessingError(_))
+        ));
+    }
 }
@@ -0,0 +1,758 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detection of replayed packets.
+//!
+//! Every packet successfully unwrapped by the node has its replay tag recorded in a Bloom filter
+//! and any subsequent packet carrying the same tag is rejected. The tag is derived from the secret
+//! shared between the creator of the outermost layer and the node rather than from the public
+//! element carried in the packet, as the same element can be encoded in multiple ways
+//! (and have low order components added to it) without invalidating the layer.
+//!
+//! A packet can only be unwrapped for as long as the sphinx key it has been created for is accepted
+//! by the node, so the filter is split into generations that follow the rotations of the key.
+//! New tags are always inserted into the current generation, while lookups consult all of them.
+//! Once the key gets rotated, a new generation is started and the oldest one, holding the tags
+//! of the packets created for a key that is no longer accepted, gets forgotten.
+//!
+//! In order to avoid contention between the connection handlers, the filter is further split into
+//! independently locked shards, with every tag belonging to exactly one of them.
+
+use crate::packet_processor::error::ReplayProtectionError;
+use log::*;
+use nym_task::TaskClient;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const DEFAULT_EXPECTED_PACKETS_PER_GENERATION: usize = 5_000_000;
+const DEFAULT_FALSE_POSITIVE_RATE: f64 = 1e-6;
+const DEFAULT_PERSISTENCE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Name of the file, within the data directory of the node, the filter is persisted in.
+const PERSISTED_FILTER_FILENAME: &str = "replay_protection_filter";
+
+/// Number of generations whose tags are remembered. Packets created for a particular sphinx key
+/// are accepted while it's announced as the upcoming key, while it's the current key and for
+/// a while after the rotation as the previous key, so their tags can end up in three consecutive generations.
+const RETAINED_GENERATIONS: usize = 3;
+
+/// Number of independently locked shards of the filter.
+const NUM_SHARDS: usize = 16;
+
+const PERSISTED_FILTER_VERSION: u8 = 2;
+const SALT_SIZE: usize = blake3::KEY_LEN;
+
+/// Replay protection section of the configuration files of mixnodes and gateways.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ReplayProtectionSettings {
+    /// Specifies whether the node should reject sphinx packets it has already processed before.
+    #[serde(rename = "replay_protection_enabled")]
+    pub enabled: bool,
+
+    /// Expected number of packets processed during a single generation of the replay protection
+    /// filter, i.e. while a single sphinx key is in use. Together with the false positive rate,
+    /// it determines the memory used by the filter.
+    #[serde(rename = "replay_protection_expected_packets")]
+    pub expected_packets: usize,
+
+    /// Desired probability of a fresh packet being incorrectly rejected as a replay.
+    #[serde(rename = "replay_protection_false_positive_rate")]
+    pub false_positive_rate: f64,
+
+    /// Specifies whether the replay protection filter should be persisted on disk, so that
+    /// restarting the node would not allow replaying previously seen packets.
+    #[serde(rename = "persist_replay_protection_filter")]
+    pub persist_filter: bool,
+
+    /// Delay between each subsequent snapshot of the replay protection filter being persisted.
+    #[serde(
+        rename = "replay_protection_persistence_interval",
+        with = "humantime_serde"
+    )]
+    pub persistence_interval: Duration,
+}
+
+impl ReplayProtectionSettings {
+    /// Derives the configuration of the filter of the node with the provided data directory.
+    pub fn filter_config(&self, data_directory: &Path) -> Config {
+        Config::build()
+            .expected_packets_per_generation(self.expected_packets)
+            .false_positive_rate(self.false_positive_rate)
+            .persistence_path(
+                self.persist_filter
+                    .then(|| data_directory.join(PERSISTED_FILTER_FILENAME)),
+            )
+            .persistence_interval(self.persistence_interval)
+            .build()
+    }
+}
+
+impl Default for ReplayProtectionSettings {
+    fn default() -> Self {
+        ReplayProtectionSettings {
+            enabled: true,
+            expected_packets: DEFAULT_EXPECTED_PACKETS_PER_GENERATION,
+            false_positive_rate: DEFAULT_FALSE_POSITIVE_RATE,
+            persist_filter: false,
+            persistence_interval: DEFAULT_PERSISTENCE_INTERVAL,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Expected number of packets processed during a single generation of the filter.
+    /// If it's exceeded, the oldest generation is forgotten early.
+    expected_packets_per_generation: usize,
+
+    /// Desired probability of a fresh packet being incorrectly rejected as a replay.
+    false_positive_rate: f64,
+
+    /// Path to the file the filter is persisted in between restarts, if any.
+    persistence_path: Option<PathBuf>,
+
+    /// Interval between subsequent snapshots of the filter being written to the disk.
+    persistence_interval: Duration,
+}
+
+impl Config {
+    pub fn build() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    fn expected_packets_per_shard(&self) -> usize {
+        // the tags are spread evenly between the shards, so leave a tiny bit of leeway
+        self.expected_packets_per_generation / NUM_SHARDS + 1
+    }
+
+    fn shard_filter_parameters(&self) -> (u64, u32) {
+        BloomFilter::optimal_parameters(self.expected_packets_per_shard(), self.false_positive_rate)
+    }
+}
+
+#[must_use]
+pub struct ConfigBuilder(Config);
+
+impl ConfigBuilder {
+    pub fn new() -> ConfigBuilder {
+        Self::default()
+    }
+
+    pub fn expected_packets_per_generation(mut self, expected_packets: usize) -> Self {
+        self.0.expected_packets_per_generation = expected_packets;
+        self
+    }
+
+    pub fn false_positive_rate(mut self, false_positive_rate: f64) -> Self {
+        self.0.false_positive_rate = false_positive_rate;
+        self
+    }
+
+    pub fn persistence_path(mut self, persistence_path: Option<PathBuf>) -> Self {
+        self.0.persistence_path = persistence_path;
+        self
+    }
+
+    pub fn persistence_interval(mut self, persistence_interval: Duration) -> Self {
+        self.0.persistence_interval = persistence_interval;
+        self
+    }
+
+    pub fn build(self) -> Config {
+        // panics here are fine as those are only ever constructed at the initial setup
+        assert!(
+            self.0.expected_packets_per_generation > 0,
+            "the replay protection filter must accept at least a single packet"
+        );
+        assert!(
+            self.0.false_positive_rate > 0.0 && self.0.false_positive_rate < 1.0,
+            "the false positive rate of the replay protection filter must be within (0, 1)"
+        );
+        self.0
+    }
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        ConfigBuilder(Config {
+            expected_packets_per_generation: DEFAULT_EXPECTED_PACKETS_PER_GENERATION,
+            false_positive_rate: DEFAULT_FALSE_POSITIVE_RATE,
+            persistence_path: None,
+            persistence_interval: DEFAULT_PERSISTENCE_INTERVAL,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReplayProtectionMetricsInner {
+    checked_packets: AtomicU64,
+    rejected_replays: AtomicU64,
+    rotations: AtomicU64,
+    overflows: AtomicU64,
+}
+
+/// Counters of the replay protection filter that can be cheaply shared with any metrics reporter.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayProtectionMetrics {
+    inner: Arc<ReplayProtectionMetricsInner>,
+}
+
+impl ReplayProtectionMetrics {
+    /// Number of packets checked against the filter.
+    pub fn checked_packets(&self) -> u64 {
+        self.inner.checked_packets.load(Ordering::Relaxed)
+    }
+
+    /// Number of packets rejected as replays.
+    pub fn rejected_replays(&self) -> u64 {
+        self.inner.rejected_replays.load(Ordering::Relaxed)
+    }
+
+    /// Number of times the generations of the filter got rotated.
+    pub fn rotations(&self) -> u64 {
+        self.inner.rotations.load(Ordering::Relaxed)
+    }
+
+    /// Number of times a shard of the filter got full and had to forget its oldest generation early.
+    pub fn overflows(&self) -> u64 {
+        self.inner.overflows.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone)]
+struct BloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+    items: u64,
+}
+
+impl BloomFilter {
+    fn new(num_bits: u64, num_hashes: u32) -> Self {
+        BloomFilter {
+            bits: vec![0; (num_bits / 64) as usize],
+            num_hashes,
+            items: 0,
+        }
+    }
+
+    /// Derives the optimal number of bits (rounded up to a full word) and hash functions
+    /// for the provided capacity and false positive rate.
+    fn optimal_parameters(expected_items: usize, false_positive_rate: f64) -> (u64, u32) {
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = -(expected_items as f64) * false_positive_rate.ln() / (ln2 * ln2);
+        let num_bits = (num_bits / 64.0).ceil().max(1.0) as u64 * 64;
+        let num_hashes = ((num_bits as f64 / expected_items as f64) * ln2).round() as u32;
+        (num_bits, num_hashes.max(1))
+    }
+
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    // uses the standard double hashing technique to derive all bit positions out of two hashes
+    fn positions(&self, tag_hash: &[u8; 32]) -> impl Iterator<Item = u64> {
+        // those unwraps can't fail as we're operating on fixed length array
+        let h1 = u64::from_le_bytes(tag_hash[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(tag_hash[8..16].try_into().unwrap()) | 1;
+        let num_bits = self.num_bits();
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    fn contains(&self, tag_hash: &[u8; 32]) -> bool {
+        self.positions(tag_hash)
+            .all(|pos| self.bits[(pos / 64) as usize] & (1 << (pos % 64)) != 0)
+    }
+
+    fn insert(&mut self, tag_hash: &[u8; 32]) {
+        for pos in self.positions(tag_hash) {
+            self.bits[(pos / 64) as usize] |= 1 << (pos % 64);
+        }
+        self.items += 1;
+    }
+
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.items.to_be_bytes());
+        for word in &self.bits {
+            buf.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    fn decode(
+        bytes: &mut &[u8],
+        num_bits: u64,
+        num_hashes: u32,
+    ) -> Result<Self, ReplayProtectionError> {
+        let items = u64::from_be_bytes(take_bytes(bytes)?);
+        let mut bits = Vec::with_capacity((num_bits / 64) as usize);
+        for _ in 0..num_bits / 64 {
+            bits.push(u64::from_le_bytes(take_bytes(bytes)?));
+        }
+        Ok(BloomFilter {
+            bits,
+            num_hashes,
+            items,
+        })
+    }
+}
+
+fn take_bytes<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N], ReplayProtectionError> {
+    if bytes.len() < N {
+        return Err(ReplayProtectionError::MalformedPersistedFilter);
+    }
+    let (taken, remaining) = bytes.split_at(N);
+    *bytes = remaining;
+    // this can't fail as we have just split off exactly N bytes
+    Ok(taken.try_into().unwrap())
+}
+
+/// Single shard of the filter holding its generations, the current one first.
+#[derive(Clone)]
+struct Shard {
+    generations: VecDeque<BloomFilter>,
+}
+
+impl Shard {
+    fn new(num_bits: u64, num_hashes: u32) -> Self {
+        Shard {
+            generations: VecDeque::from([BloomFilter::new(num_bits, num_hashes)]),
+        }
+    }
+
+    fn current(&mut self) -> &mut BloomFilter {
+        // there's always at least a single generation
+        &mut self.generations[0]
+    }
+
+    fn rotate(&mut self) {
+        let current = &self.generations[0];
+        let fresh = BloomFilter::new(current.num_bits(), current.num_hashes);
+        self.generations.push_front(fresh);
+        self.generations.truncate(RETAINED_GENERATIONS);
+    }
+
+    fn contains(&self, tag_hash: &[u8; 32]) -> bool {
+        self.generations
+            .iter()
+            .any(|generation| generation.contains(tag_hash))
+    }
+
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.push(self.generations.len() as u8);
+        for generation in &self.generations {
+            generation.encode_into(buf)
+        }
+    }
+
+    fn decode(
+        bytes: &mut &[u8],
+        num_bits: u64,
+        num_hashes: u32,
+    ) -> Result<Self, ReplayProtectionError> {
+        let [num_generations] = take_bytes(bytes)?;
+        if num_generations == 0 || num_generations as usize > RETAINED_GENERATIONS {
+            return Err(ReplayProtectionError::MalformedPersistedFilter);
+        }
+
+        let generations = (0..num_generations)
+            .map(|_| BloomFilter::decode(bytes, num_bits, num_hashes))
+            .collect::<Result<_, _>>()?;
+        Ok(Shard { generations })
+    }
+}
+
+/// State of the filter that is persisted between restarts.
+struct FilterState {
+    /// Key used for hashing the replay tags so that the positions in the filter could not be
+    /// predicted (and deliberately polluted) by an adversary.
+    salt: [u8; SALT_SIZE],
+
+    shards: Vec<Mutex<Shard>>,
+}
+
+impl FilterState {
+    fn new(config: &Config) -> Self {
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+
+        let (num_bits, num_hashes) = config.shard_filter_parameters();
+        FilterState {
+            salt,
+            shards: (0..NUM_SHARDS)
+                .map(|_| Mutex::new(Shard::new(num_bits, num_hashes)))
+                .collect(),
+        }
+    }
+
+    fn shard(&self, tag_hash: &[u8; 32]) -> &Mutex<Shard> {
+        // note: the first 16 bytes are used for deriving the positions within the filter
+        // this unwrap can't fail as we're operating on fixed length array
+        let index = u64::from_le_bytes(tag_hash[16..24].try_into().unwrap()) % NUM_SHARDS as u64;
+        &self.shards[index as usize]
+    }
+
+    fn encode(&self, config: &Config) -> Vec<u8> {
+        let (num_bits, num_hashes) = config.shard_filter_parameters();
+
+        let mut buf = Vec::new();
+        buf.push(PERSISTED_FILTER_VERSION);
+        buf.extend_from_slice(&self.salt);
+        buf.extend_from_slice(&(NUM_SHARDS as u32).to_be_bytes());
+        buf.extend_from_slice(&num_bits.to_be_bytes());
+        buf.extend_from_slice(&num_hashes.to_be_bytes());
+        for shard in &self.shards {
+            // don't hold the locks of all the shards at once so that the packet processing
+            // wouldn't get stalled for the duration of the entire encoding
+            let shard = shard.lock().expect("mutex got poisoned").clone();
+            shard.encode_into(&mut buf)
+        }
+        buf
+    }
+
+    // returns `None` if the persisted filter was created with different parameters
+    fn decode(mut bytes: &[u8], config: &Config) -> Result<Option<Self>, ReplayProtectionError> {
+        let bytes = &mut bytes;
+
+        let [version] = take_bytes(bytes)?;
+        if version != PERSISTED_FILTER_VERSION {
+            return Err(ReplayProtectionError::UnsupportedPersistedFilterVersion(
+                version,
+            ));
+        }
+        let salt = take_bytes(bytes)?;
+        let num_shards = u32::from_be_bytes(take_bytes(bytes)?);
+        let num_bits = u64::from_be_bytes(take_bytes(bytes)?);
+        let num_hashes = u32::from_be_bytes(take_bytes(bytes)?);
+
+        if num_shards as usize != NUM_SHARDS
+            || (num_bits, num_hashes) != config.shard_filter_parameters()
+        {
+            return Ok(None);
+        }
+
+        let shards = (0..NUM_SHARDS)
+            .map(|_| Shard::decode(bytes, num_bits, num_hashes).map(Mutex::new))
+            .collect::<Result<_, _>>()?;
+        if !bytes.is_empty() {
+            return Err(ReplayProtectionError::MalformedPersistedFilter);
+        }
+
+        Ok(Some(FilterState { salt, shards }))
+    }
+
+    fn load(path: &Path, config: &Config) -> Result<Option<Self>, ReplayProtectionError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(path).map_err(|source| ReplayProtectionError::PersistenceFailure {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::decode(&bytes, config)
+    }
+}
+
+/// Bounded filter of the replay tags of all packets processed by the node,
+/// shared between all its connection handlers.
+#[derive(Clone)]
+pub struct ReplayProtectionFilter {
+    config: Arc<Config>,
+    state: Arc<FilterState>,
+    metrics: ReplayProtectionMetrics,
+}
+
+impl ReplayProtectionFilter {
+    /// Creates new instance of the filter, restoring its persisted state, if available.
+    pub fn new(config: Config) -> Self {
+        let restored = match &config.persistence_path {
+            Some(path) => match FilterState::load(path, &config) {
+                Ok(Some(state)) => {
+                    info!(
+                        "restored the replay protection filter from {}",
+                        path.display()
+                    );
+                    Some(state)
+                }
+                Ok(None) => None,
+                Err(err) => {
+                    warn!(
+                        "failed to restore the replay protection filter: {err}. \
+                        Starting with an empty one"
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let state = restored.unwrap_or_else(|| FilterState::new(&config));
+
+        ReplayProtectionFilter {
+            config: Arc::new(config),
+            state: Arc::new(state),
+            metrics: Default::default(),
+        }
+    }
+
+    pub fn metrics(&self) -> ReplayProtectionMetrics {
+        self.metrics.clone()
+    }
+
+    /// Records the replay tag of a successfully unwrapped packet and returns whether it has already
+    /// been seen before, i.e. whether the packet is a replay.
+    pub fn check_and_record(&self, replay_tag: &[u8]) -> bool {
+        self.metrics
+            .inner
+            .checked_packets
+            .fetch_add(1, Ordering::Relaxed);
+
+        let tag_hash = blake3::keyed_hash(&self.state.salt, replay_tag);
+        let tag_hash = tag_hash.as_bytes();
+
+        let mut shard = self
+            .state
+            .shard(tag_hash)
+            .lock()
+            .expect("mutex got poisoned");
+
+        if shard.current().items >= self.config.expected_packets_per_shard() as u64 {
+            warn!(
+                "a shard of the replay protection filter got full before the sphinx key rotation. \
+                Consider increasing its capacity"
+            );
+            shard.rotate();
+            self.metrics.inner.overflows.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let seen = shard.contains(tag_hash);
+        if seen {
+            self.metrics
+                .inner
+                .rejected_replays
+                .fetch_add(1, Ordering::Relaxed);
+        } else {
+            shard.current().insert(tag_hash);
+        }
+        seen
+    }
+
+    /// Starts a new generation of the filter. It must be called whenever the sphinx key of the node
+    /// gets rotated, so that once the old key is retired, the tags of packets created for it are forgotten.
+    pub fn rotate(&self) {
+        for shard in &self.state.shards {
+            shard.lock().expect("mutex got poisoned").rotate();
+        }
+        self.metrics.inner.rotations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Writes the current state of the filter to the disk, if persistence is enabled.
+    pub fn persist(&self) -> Result<(), ReplayProtectionError> {
+        let Some(path) = &self.config.persistence_path else {
+            return Ok(());
+        };
+
+        let encoded = self.state.encode(&self.config);
+
+        // write to a temporary file first so that a crash wouldn't leave us with a corrupted filter
+        let tmp_path = path.with_extension("tmp");
+        let io_err = |source| ReplayProtectionError::PersistenceFailure {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        let mut file = fs::File::create(&tmp_path).map_err(io_err)?;
+        file.write_all(&encoded).map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        fs::rename(&tmp_path, path).map_err(io_err)
+    }
+
+    fn persist_in_background(&self) -> tokio::task::JoinHandle<()> {
+        let filter = self.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(err) = filter.persist() {
+                error!("failed to persist the replay protection filter: {err}")
+            }
+        })
+    }
+
+    /// Periodically persists the filter until the shutdown signal is received,
+    /// at which point its final state is written to the disk.
+    pub async fn run_persistence(self, mut shutdown: TaskClient) {
+        if self.config.persistence_path.is_none() {
+            return;
+        }
+        debug!("Started ReplayProtectionFilter persistence with graceful shutdown support");
+
+        let mut interval = tokio::time::interval(self.config.persistence_interval);
+        // the first tick completes immediately
+        interval.tick().await;
+
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                _ = interval.tick() => {
+                    self.persist_in_background().await.ok();
+                }
+                _ = shutdown.recv() => {
+                    trace!("ReplayProtectionFilter: Received shutdown");
+                }
+            }
+        }
+
+        self.persist_in_background().await.ok();
+        debug!("ReplayProtectionFilter: Exiting");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Config {
+        Config::build()
+            .expected_packets_per_generation(10_000)
+            .false_positive_rate(1e-6)
+            .build()
+    }
+
+    #[test]
+    fn replayed_tags_are_detected() {
+        let filter = ReplayProtectionFilter::new(test_config());
+
+        for i in 0u32..500 {
+            assert!(!filter.check_and_record(&i.to_be_bytes()));
+        }
+        for i in 0u32..500 {
+            assert!(filter.check_and_record(&i.to_be_bytes()));
+        }
+
+        let metrics = filter.metrics();
+        assert_eq!(metrics.checked_packets(), 1000);
+        assert_eq!(metrics.rejected_replays(), 500);
+    }
+
+    #[test]
+    fn tags_are_remembered_until_their_generation_is_retired() {
+        let filter = ReplayProtectionFilter::new(test_config());
+
+        assert!(!filter.check_and_record(b"foomp"));
+        for _ in 1..RETAINED_GENERATIONS {
+            filter.rotate();
+            assert!(filter.check_and_record(b"foomp"));
+        }
+
+        filter.rotate();
+        assert!(!filter.check_and_record(b"foomp"));
+        assert_eq!(filter.metrics().rotations(), RETAINED_GENERATIONS as u64);
+    }
+
+    #[test]
+    fn full_shards_forget_their_oldest_generation() {
+        let config = Config::build()
+            .expected_packets_per_generation(NUM_SHARDS * 10)
+            .build();
+        let filter = ReplayProtectionFilter::new(config);
+
+        // insert enough tags for every shard to overflow multiple times
+        let total = (NUM_SHARDS * 10 * (RETAINED_GENERATIONS + 4)) as u32;
+        for i in 0..total {
+            filter.check_and_record(&i.to_be_bytes());
+        }
+        let metrics = filter.metrics();
+        assert!(metrics.overflows() > 0);
+        assert_eq!(metrics.rotations(), 0);
+
+        // the most recent tags are still remembered, while the oldest ones are gone
+        assert!(filter.check_and_record(&(total - 1).to_be_bytes()));
+        assert!(!filter.check_and_record(&0u32.to_be_bytes()));
+    }
+
+    #[test]
+    fn concurrently_replayed_tags_are_accepted_only_once() {
+        let filter = ReplayProtectionFilter::new(test_config());
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let filter = filter.clone();
+                std::thread::spawn(move || {
+                    (0u32..1000)
+                        .filter(|i| !filter.check_and_record(&i.to_be_bytes()))
+                        .count()
+                })
+            })
+            .collect();
+
+        let accepted: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert_eq!(accepted, 1000);
+        assert_eq!(filter.metrics().rejected_replays(), 7000);
+    }
+
+    #[test]
+    fn filter_survives_persistence() {
+        let path = std::env::temp_dir().join(format!(
+            "replay-protection-filter-test-{}",
+            OsRng.next_u64()
+        ));
+        let config = Config::build()
+            .expected_packets_per_generation(1000)
+            .persistence_path(Some(path.clone()))
+            .build();
+
+        let filter = ReplayProtectionFilter::new(config.clone());
+        assert!(!filter.check_and_record(b"foomp"));
+        filter.rotate();
+        assert!(!filter.check_and_record(b"bar"));
+        filter.persist().unwrap();
+
+        let restored = ReplayProtectionFilter::new(config);
+        assert!(restored.check_and_record(b"foomp"));
+        assert!(restored.check_and_record(b"bar"));
+        assert!(!restored.check_and_record(b"baz"));
+
+        // filters created with different parameters are not restored
+        let different_config = Config::build()
+            .expected_packets_per_generation(2000)
+            .persistence_path(Some(path.clone()))
+            .build();
+        let fresh = ReplayProtectionFilter::new(different_config);
+        assert!(!fresh.check_and_record(b"foomp"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn malformed_persisted_filter_is_rejected() {
+        let config = test_config();
+        let mut encoded = FilterState::new(&config).encode(&config);
+        assert!(FilterState::decode(&encoded, &config).unwrap().is_some());
+
+        encoded.pop();
+        assert!(FilterState::decode(&encoded, &config).is_err());
+    }
+
+    #[test]
+    fn settings_derive_the_persistence_path() {
+        let settings = ReplayProtectionSettings::default();
+        assert!(settings
+            .filter_config(Path::new("/foo"))
+            .persistence_path
+            .is_none());
+
+        let settings = ReplayProtectionSettings {
+            persist_filter: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.filter_config(Path::new("/foo")).persistence_path,
+            Some(PathBuf::from("/foo").join(PERSISTED_FILTER_FILENAME))
+        );
+    }
+}
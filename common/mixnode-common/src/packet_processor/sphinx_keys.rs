@@ -0,0 +1,98 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use log::*;
+use nym_sphinx_types::PrivateKey;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+struct PreviousKey {
+    key: Arc<PrivateKey>,
+    expiration: Instant,
+}
+
+struct SphinxKeysInner {
+    current: Arc<PrivateKey>,
+    upcoming: Option<Arc<PrivateKey>>,
+    previous: Option<PreviousKey>,
+}
+
+/// Keys that can currently be used for unwrapping received packets.
+pub(crate) struct ActiveKeys {
+    pub(crate) current: Arc<PrivateKey>,
+    upcoming: Option<Arc<PrivateKey>>,
+    previous: Option<Arc<PrivateKey>>,
+}
+
+impl ActiveKeys {
+    pub(crate) fn has_fallback(&self) -> bool {
+        self.upcoming.is_some() || self.previous.is_some()
+    }
+
+    /// Keys that should be attempted if the packet couldn't be unwrapped with the current key.
+    pub(crate) fn fallback(&self) -> impl Iterator<Item = &Arc<PrivateKey>> {
+        self.upcoming.iter().chain(self.previous.iter())
+    }
+}
+
+/// Private sphinx keys of this node used for unwrapping received packets.
+///
+/// After the key gets rotated, the previous one is still accepted for a limited time, so that
+/// packets constructed with a slightly outdated network topology, or ones that were still
+/// being delayed by other nodes at the time of the rotation, would not get dropped.
+/// Similarly, once the next key has been announced, it is accepted ahead of the rotation,
+/// since clients might start using it slightly before this node notices the new epoch.
+#[derive(Clone)]
+pub struct SphinxKeys {
+    inner: Arc<RwLock<SphinxKeysInner>>,
+}
+
+impl SphinxKeys {
+    pub fn new(current: PrivateKey) -> Self {
+        SphinxKeys {
+            inner: Arc::new(RwLock::new(SphinxKeysInner {
+                current: Arc::new(current),
+                upcoming: None,
+                previous: None,
+            })),
+        }
+    }
+
+    /// Starts accepting packets created with the announced key that is going to replace
+    /// the current one at the next rotation.
+    pub fn accept_upcoming(&self, upcoming_key: PrivateKey) {
+        // if the lock got poisoned, one of the processing threads has panicked and we can't do anything sensible
+        let mut guard = self.inner.write().expect("sphinx keys lock got poisoned");
+        guard.upcoming = Some(Arc::new(upcoming_key));
+    }
+
+    /// Replaces the current key with the provided one. The replaced key is going to be
+    /// accepted for the duration of the specified overlap window.
+    pub fn rotate(&self, new_key: PrivateKey, overlap: Duration) {
+        let mut guard = self.inner.write().expect("sphinx keys lock got poisoned");
+        let previous = std::mem::replace(&mut guard.current, Arc::new(new_key));
+        guard.upcoming = None;
+        guard.previous = Some(PreviousKey {
+            key: previous,
+            expiration: Instant::now() + overlap,
+        });
+        info!("Rotated the sphinx key. The previous one is going to be accepted for another {overlap:?}")
+    }
+
+    /// Returns the current key alongside the announced upcoming key and the previous one,
+    /// if it's still within its overlap window.
+    pub(crate) fn active_keys(&self) -> ActiveKeys {
+        let guard = self.inner.read().expect("sphinx keys lock got poisoned");
+        let previous = guard
+            .previous
+            .as_ref()
+            .filter(|previous| previous.expiration > Instant::now())
+            .map(|previous| Arc::clone(&previous.key));
+
+        ActiveKeys {
+            current: Arc::clone(&guard.current),
+            upcoming: guard.upcoming.clone(),
+            previous,
+        }
+    }
+}
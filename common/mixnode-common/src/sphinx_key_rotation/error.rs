@@ -0,0 +1,33 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+use validator_client::ValidatorClientError;
+
+#[derive(Error, Debug)]
+pub enum SphinxKeyRotationError {
+    #[error("failed to query the nym api - {0}")]
+    ValidatorClientError(#[from] ValidatorClientError),
+
+    #[error("the nym api does not know about the current interval")]
+    UnknownInterval,
+
+    #[error("the node with identity {identity} does not seem to be bonded")]
+    NodeNotBonded { identity: String },
+
+    #[error("failed to load sphinx keys from {path:?} - {source}")]
+    KeyLoadFailure {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("failed to store sphinx keys at {path:?} - {source}")]
+    KeyStoreFailure {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
@@ -0,0 +1,403 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::packet_processor::replay_protection::ReplayProtectionFilter;
+use crate::packet_processor::sphinx_keys::SphinxKeys;
+use crate::sphinx_key_rotation::error::SphinxKeyRotationError;
+use log::*;
+use nym_crypto::asymmetric::encryption;
+use nym_network_defaults::mainnet::NYM_API;
+use nym_pemstore::KeyPairPath;
+use nym_task::TaskClient;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use rand_07::rngs::OsRng;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::sleep;
+use url::Url;
+
+pub mod error;
+
+// by default all of those are overwritten by config data from mixnodes and gateways directly
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_OVERLAP: Duration = Duration::from_secs(60 * 60);
+
+/// Type of the node whose sphinx keys are being rotated, which determines how its bond is looked up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Mixnode,
+    Gateway,
+}
+
+impl NodeKind {
+    fn nym_cli_subcommand(&self) -> &'static str {
+        match self {
+            NodeKind::Mixnode => "mixnode",
+            NodeKind::Gateway => "gateway",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Type of the node whose sphinx keys are being rotated.
+    node_kind: NodeKind,
+
+    /// Base58-encoded identity key of this node used for finding its bond.
+    identity_key: String,
+
+    /// Specifies delay between subsequent checks of whether the announced key became active.
+    check_interval: Duration,
+
+    /// Specifies for how long the previous key is still accepted after the rotation.
+    overlap: Duration,
+
+    /// Paths to the files containing the currently used sphinx keys.
+    current_private_key_file: PathBuf,
+    current_public_key_file: PathBuf,
+
+    /// Paths to the files containing the sphinx keys that are going to be used after the next rotation.
+    upcoming_private_key_file: PathBuf,
+    upcoming_public_key_file: PathBuf,
+
+    /// URLs to the nym apis for obtaining the bond information and the current epoch.
+    nym_api_urls: Vec<Url>,
+}
+
+impl Config {
+    pub fn build() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    fn current_key_paths(&self) -> KeyPairPath {
+        KeyPairPath::new(
+            self.current_private_key_file.clone(),
+            self.current_public_key_file.clone(),
+        )
+    }
+
+    fn upcoming_key_paths(&self) -> KeyPairPath {
+        KeyPairPath::new(
+            self.upcoming_private_key_file.clone(),
+            self.upcoming_public_key_file.clone(),
+        )
+    }
+}
+
+#[must_use]
+pub struct ConfigBuilder(Config);
+
+impl ConfigBuilder {
+    pub fn new() -> ConfigBuilder {
+        Self::default()
+    }
+
+    pub fn node_kind(mut self, node_kind: NodeKind) -> Self {
+        self.0.node_kind = node_kind;
+        self
+    }
+
+    pub fn identity_key(mut self, identity_key: String) -> Self {
+        self.0.identity_key = identity_key;
+        self
+    }
+
+    pub fn check_interval(mut self, check_interval: Duration) -> Self {
+        self.0.check_interval = check_interval;
+        self
+    }
+
+    pub fn overlap(mut self, overlap: Duration) -> Self {
+        self.0.overlap = overlap;
+        self
+    }
+
+    pub fn current_key_files(mut self, private_key: PathBuf, public_key: PathBuf) -> Self {
+        self.0.current_private_key_file = private_key;
+        self.0.current_public_key_file = public_key;
+        self
+    }
+
+    pub fn upcoming_key_files(mut self, private_key: PathBuf, public_key: PathBuf) -> Self {
+        self.0.upcoming_private_key_file = private_key;
+        self.0.upcoming_public_key_file = public_key;
+        self
+    }
+
+    pub fn nym_api_urls(mut self, nym_api_urls: Vec<Url>) -> Self {
+        self.0.nym_api_urls = nym_api_urls;
+        self
+    }
+
+    pub fn build(self) -> Config {
+        // panics here are fine as those are only ever constructed at the initial setup
+        assert!(
+            !self.0.nym_api_urls.is_empty(),
+            "at least one validator endpoint must be provided",
+        );
+        assert!(
+            !self.0.identity_key.is_empty(),
+            "the identity key of the node must be provided"
+        );
+        assert!(
+            !self.0.current_private_key_file.as_os_str().is_empty()
+                && !self.0.upcoming_private_key_file.as_os_str().is_empty(),
+            "the sphinx key files must be provided"
+        );
+        self.0
+    }
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        ConfigBuilder(Config {
+            node_kind: NodeKind::Mixnode,
+            identity_key: Default::default(),
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            overlap: DEFAULT_OVERLAP,
+            current_private_key_file: Default::default(),
+            current_public_key_file: Default::default(),
+            upcoming_private_key_file: Default::default(),
+            upcoming_public_key_file: Default::default(),
+            nym_api_urls: vec![NYM_API.parse().expect("Invalid default API URL")],
+        })
+    }
+}
+
+/// State of the upcoming key of this node according to the bond information.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UpcomingKeyState {
+    /// The key hasn't been announced in the mixnet contract yet.
+    NotAnnounced,
+
+    /// The key has been announced and is going to be used from the next epoch onwards.
+    Announced,
+
+    /// The key should already be used in the current epoch.
+    Active,
+}
+
+/// Periodically checks the sphinx key announced in the mixnet contract. Once announced,
+/// the key is accepted by this node straight away and once it becomes active, i.e. its epoch begins,
+/// it replaces the current key and the next one is prepared.
+///
+/// The node never announces the keys by itself, as that would require it to hold the keys
+/// of the account that bonded it. The announcements are made by the operator, e.g. with `nym-cli`.
+pub struct SphinxKeyRotator {
+    config: Config,
+    sphinx_keys: SphinxKeys,
+    replay_protection: Option<ReplayProtectionFilter>,
+    shutdown_listener: TaskClient,
+
+    currently_used_api: usize,
+
+    // Note: similarly to verloc, this client only does bunch of REST queries,
+    // so it's fine to construct it here.
+    validator_client: validator_client::NymApiClient,
+}
+
+impl SphinxKeyRotator {
+    pub fn new(mut config: Config, sphinx_keys: SphinxKeys, shutdown_listener: TaskClient) -> Self {
+        config.nym_api_urls.shuffle(&mut thread_rng());
+
+        SphinxKeyRotator {
+            sphinx_keys,
+            replay_protection: None,
+            shutdown_listener,
+            currently_used_api: 0,
+            validator_client: validator_client::NymApiClient::new(config.nym_api_urls[0].clone()),
+            config,
+        }
+    }
+
+    /// Starts a new generation of the provided replay protection filter whenever the key gets rotated.
+    #[must_use]
+    pub fn with_replay_protection(mut self, replay_protection: ReplayProtectionFilter) -> Self {
+        self.replay_protection = Some(replay_protection);
+        self
+    }
+
+    fn use_next_nym_api(&mut self) {
+        if self.config.nym_api_urls.len() == 1 {
+            warn!("There's only a single validator API available - it won't be possible to use a different one");
+            return;
+        }
+
+        self.currently_used_api = (self.currently_used_api + 1) % self.config.nym_api_urls.len();
+        self.validator_client
+            .change_nym_api(self.config.nym_api_urls[self.currently_used_api].clone())
+    }
+
+    fn log_announcement_instructions(&self, upcoming_keys: &encryption::KeyPair) {
+        info!(
+            "The next sphinx key of this node is {}. To schedule its rotation, announce it in the mixnet contract with \
+            `nym-cli mixnet operators {} announce-sphinx-key --sphinx-key {}`",
+            upcoming_keys.public_key().to_base58_string(),
+            self.config.node_kind.nym_cli_subcommand(),
+            upcoming_keys.public_key().to_base58_string(),
+        )
+    }
+
+    fn generate_upcoming_keys(&self) -> Result<encryption::KeyPair, SphinxKeyRotationError> {
+        let upcoming_keys = encryption::KeyPair::new(&mut OsRng);
+        nym_pemstore::store_keypair(&upcoming_keys, &self.config.upcoming_key_paths()).map_err(
+            |source| SphinxKeyRotationError::KeyStoreFailure {
+                path: self.config.upcoming_private_key_file.clone(),
+                source,
+            },
+        )?;
+        Ok(upcoming_keys)
+    }
+
+    fn load_or_generate_upcoming_keys(
+        &self,
+    ) -> Result<encryption::KeyPair, SphinxKeyRotationError> {
+        if !self.config.upcoming_private_key_file.exists() {
+            info!("Generating new upcoming sphinx keys");
+            return self.generate_upcoming_keys();
+        }
+
+        nym_pemstore::load_keypair(&self.config.upcoming_key_paths()).map_err(|source| {
+            SphinxKeyRotationError::KeyLoadFailure {
+                path: self.config.upcoming_private_key_file.clone(),
+                source,
+            }
+        })
+    }
+
+    /// Checks whether the provided upcoming key has been announced and whether it should already
+    /// be used in the current epoch.
+    async fn upcoming_key_state(
+        &self,
+        upcoming_keys: &encryption::KeyPair,
+    ) -> Result<UpcomingKeyState, SphinxKeyRotationError> {
+        let current_epoch = self
+            .validator_client
+            .get_cached_current_interval()
+            .await?
+            .ok_or(SphinxKeyRotationError::UnknownInterval)?
+            .current_epoch_absolute_id();
+
+        let identity = &self.config.identity_key;
+        let (active_key, announced_key) = match self.config.node_kind {
+            NodeKind::Mixnode => self
+                .validator_client
+                .get_cached_mixnodes()
+                .await?
+                .into_iter()
+                .find(|node| &node.bond_information.mix_node.identity_key == identity)
+                .map(|node| {
+                    let bond = node.bond_information;
+                    (
+                        bond.sphinx_key_for_epoch(current_epoch).to_owned(),
+                        bond.upcoming_sphinx_key.map(|upcoming| upcoming.sphinx_key),
+                    )
+                }),
+            NodeKind::Gateway => self
+                .validator_client
+                .get_cached_gateways()
+                .await?
+                .into_iter()
+                .find(|bond| &bond.gateway.identity_key == identity)
+                .map(|bond| {
+                    (
+                        bond.sphinx_key_for_epoch(current_epoch).to_owned(),
+                        bond.upcoming_sphinx_key.map(|upcoming| upcoming.sphinx_key),
+                    )
+                }),
+        }
+        .ok_or_else(|| SphinxKeyRotationError::NodeNotBonded {
+            identity: identity.clone(),
+        })?;
+
+        let upcoming_key = upcoming_keys.public_key().to_base58_string();
+        if active_key == upcoming_key {
+            Ok(UpcomingKeyState::Active)
+        } else if announced_key.as_ref() == Some(&upcoming_key) {
+            Ok(UpcomingKeyState::Announced)
+        } else {
+            Ok(UpcomingKeyState::NotAnnounced)
+        }
+    }
+
+    /// Starts accepting packets created with the announced upcoming key ahead of the rotation.
+    fn accept_upcoming(&self, upcoming_keys: &encryption::KeyPair) {
+        debug!("The upcoming sphinx key has been announced - starting to accept packets created with it");
+        self.sphinx_keys
+            .accept_upcoming(upcoming_keys.private_key().into());
+    }
+
+    /// Starts using the upcoming keys for processing packets and generates new upcoming keys.
+    fn rotate(
+        &self,
+        upcoming_keys: encryption::KeyPair,
+    ) -> Result<encryption::KeyPair, SphinxKeyRotationError> {
+        // persist the keys first so that the node would keep using them after a restart
+        nym_pemstore::store_keypair(&upcoming_keys, &self.config.current_key_paths()).map_err(
+            |source| SphinxKeyRotationError::KeyStoreFailure {
+                path: self.config.current_private_key_file.clone(),
+                source,
+            },
+        )?;
+        self.sphinx_keys
+            .rotate(upcoming_keys.private_key().into(), self.config.overlap);
+        if let Some(replay_protection) = &self.replay_protection {
+            replay_protection.rotate()
+        }
+
+        let new_upcoming_keys = self.generate_upcoming_keys()?;
+        self.log_announcement_instructions(&new_upcoming_keys);
+        Ok(new_upcoming_keys)
+    }
+
+    pub async fn run(&mut self) {
+        let mut upcoming_keys = match self.load_or_generate_upcoming_keys() {
+            Ok(keys) => keys,
+            Err(err) => {
+                error!("Failed to prepare the upcoming sphinx keys - {err}. The sphinx key is not going to be rotated");
+                return;
+            }
+        };
+        self.log_announcement_instructions(&upcoming_keys);
+
+        let mut accepted = false;
+
+        while !self.shutdown_listener.is_shutdown() {
+            match self.upcoming_key_state(&upcoming_keys).await {
+                Ok(UpcomingKeyState::Active) => match self.rotate(upcoming_keys) {
+                    Ok(new_upcoming_keys) => {
+                        upcoming_keys = new_upcoming_keys;
+                        accepted = false;
+                    }
+                    Err(err) => {
+                        error!("Failed to rotate the sphinx keys - {err}. The sphinx key is not going to be rotated anymore");
+                        return;
+                    }
+                },
+                Ok(UpcomingKeyState::Announced) => {
+                    if !accepted {
+                        self.accept_upcoming(&upcoming_keys);
+                        accepted = true;
+                    }
+                }
+                Ok(UpcomingKeyState::NotAnnounced) => {
+                    trace!("the upcoming sphinx key has not been announced yet")
+                }
+                Err(err) => {
+                    warn!("Failed to check the state of the upcoming sphinx key - {err}. Going to attempt to use another validator API in the next run");
+                    self.use_next_nym_api();
+                }
+            }
+
+            tokio::select! {
+                _ = sleep(self.config.check_interval) => {},
+                _ = self.shutdown_listener.recv() => {
+                    log::trace!("SphinxKeyRotator: Received shutdown");
+                }
+            }
+        }
+
+        log::trace!("SphinxKeyRotator: Exiting");
+    }
+}
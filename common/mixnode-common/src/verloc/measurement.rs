@@ -23,6 +23,20 @@ pub struct VerlocResult {
     results: Vec<Verloc>,
 }
 
+impl VerlocResult {
+    pub fn total_tested(&self) -> usize {
+        self.total_tested
+    }
+
+    pub fn run_finished(&self) -> Option<std::time::SystemTime> {
+        self.run_finished
+    }
+
+    pub fn results(&self) -> &[Verloc] {
+        &self.results
+    }
+}
+
 impl AtomicVerlocResult {
     pub(crate) fn new() -> Self {
         AtomicVerlocResult {
@@ -36,7 +50,7 @@ impl AtomicVerlocResult {
     }
 
     // this could have also been achieved with a normal #[derive(Clone)] but I prefer to be explicit about it
-    pub(crate) fn clone_data_pointer(&self) -> Self {
+    pub fn clone_data_pointer(&self) -> Self {
         AtomicVerlocResult {
             inner: Arc::clone(&self.inner),
         }
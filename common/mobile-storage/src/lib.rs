@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use async_trait::async_trait;
+use std::io;
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,6 +14,12 @@ pub enum StorageError {
     #[allow(dead_code)]
     #[error("Code shouldn't reach this point")]
     InconsistentData,
+
+    #[error("failed to access the stored blob: {source}")]
+    BlobAccessFailure {
+        #[from]
+        source: io::Error,
+    },
 }
 
 #[derive(Clone)]
@@ -65,3 +73,51 @@ impl Storage for PersistentStorage {
         Err(StorageError::AndroidNotSupported)
     }
 }
+
+/// Storage of opaque blobs of client data (such as reply SURBs) that have to survive app restarts.
+#[async_trait]
+pub trait BlobStorage: Send + Sync {
+    async fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    async fn set_blob(&self, key: &str, value: &[u8]) -> Result<(), StorageError>;
+}
+
+/// Keeps every blob in a separate file within the provided directory,
+/// such as the data directory of the application.
+#[derive(Clone, Debug)]
+pub struct FsBlobStorage {
+    directory: PathBuf,
+}
+
+impl FsBlobStorage {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        FsBlobStorage {
+            directory: directory.into(),
+        }
+    }
+
+    fn blob_path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{key}.blob"))
+    }
+}
+
+#[async_trait]
+impl BlobStorage for FsBlobStorage {
+    async fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match std::fs::read(self.blob_path(key)) {
+            Ok(blob) => Ok(Some(blob)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn set_blob(&self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        std::fs::create_dir_all(&self.directory)?;
+
+        // write to a temporary file first so that we'd never leave a partially written blob behind
+        let temporary_path = self.directory.join(format!("{key}.blob.tmp"));
+        std::fs::write(&temporary_path, value)?;
+        std::fs::rename(temporary_path, self.blob_path(key))?;
+        Ok(())
+    }
+}
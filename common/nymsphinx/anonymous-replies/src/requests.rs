@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{ReplySurb, ReplySurbError};
+use nym_crypto::asymmetric::identity;
 use nym_sphinx_addressing::clients::{Recipient, RecipientFormattingError};
+use nym_sphinx_addressing::nodes::NodeIdentity;
 use rand::{CryptoRng, RngCore};
 use std::fmt::{Display, Formatter};
 use std::mem;
@@ -12,6 +14,11 @@ use thiserror::Error;
 use wasm_bindgen::prelude::*;
 
 pub const SENDER_TAG_SIZE: usize = 16;
+pub const GATEWAY_CHANGE_NONCE_SIZE: usize = 16;
+
+// domain separator so that a gateway change signature could never be confused with any other
+// signature created with the client's identity key
+const GATEWAY_CHANGE_DOMAIN: &[u8] = b"nym-gateway-change-notice";
 
 #[derive(Debug, Error)]
 pub enum InvalidAnonymousSenderTagRepresentation {
@@ -92,6 +99,122 @@ pub enum InvalidReplyRequestError {
 
     #[error("failed to deserialize replySURB - {0}")]
     MalformedReplySurb(#[from] ReplySurbError),
+
+    #[error("failed to deserialize the gateway change signature - {0}")]
+    MalformedSignature(#[from] identity::Ed25519RecoveryError),
+}
+
+/// Notice informing the remote party that the sender is now reachable at a new address, i.e. via
+/// a different gateway. The signature, created with the sender's identity key, covers the full
+/// new address (including the identity of the new gateway), the creation timestamp and a random
+/// nonce so that the notice could neither be forged nor replayed by the party relaying it.
+#[derive(Debug, Clone)]
+pub struct GatewayChangeNotice {
+    recipient: Recipient,
+    timestamp: u64,
+    nonce: [u8; GATEWAY_CHANGE_NONCE_SIZE],
+    signature: identity::Signature,
+}
+
+impl GatewayChangeNotice {
+    pub const LEN: usize = Recipient::LEN
+        + mem::size_of::<u64>()
+        + GATEWAY_CHANGE_NONCE_SIZE
+        + identity::SIGNATURE_LENGTH;
+
+    /// Creates a new notice announcing the provided address signed with the identity key
+    /// of the sender. The timestamp is expected to be expressed in seconds since the unix epoch.
+    pub fn new_signed<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        recipient: Recipient,
+        timestamp: u64,
+        identity_key: &identity::PrivateKey,
+    ) -> Self {
+        let mut nonce = [0u8; GATEWAY_CHANGE_NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+
+        let signature = identity_key.sign(&Self::signed_payload(&recipient, timestamp, &nonce));
+        GatewayChangeNotice {
+            recipient,
+            timestamp,
+            nonce,
+            signature,
+        }
+    }
+
+    fn signed_payload(
+        recipient: &Recipient,
+        timestamp: u64,
+        nonce: &[u8; GATEWAY_CHANGE_NONCE_SIZE],
+    ) -> Vec<u8> {
+        GATEWAY_CHANGE_DOMAIN
+            .iter()
+            .copied()
+            .chain(recipient.to_bytes())
+            .chain(timestamp.to_be_bytes())
+            .chain(nonce.iter().copied())
+            .collect()
+    }
+
+    pub fn recipient(&self) -> &Recipient {
+        &self.recipient
+    }
+
+    pub fn new_gateway(&self) -> &NodeIdentity {
+        self.recipient.gateway()
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn nonce(&self) -> &[u8; GATEWAY_CHANGE_NONCE_SIZE] {
+        &self.nonce
+    }
+
+    /// Checks whether the notice has been signed by the identity key of the announced recipient.
+    /// Note that it's up to the caller to decide whether the timestamp is still acceptable.
+    pub fn verify(&self) -> Result<(), identity::SignatureError> {
+        self.recipient.identity().verify(
+            &Self::signed_payload(&self.recipient, self.timestamp, &self.nonce),
+            &self.signature,
+        )
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.recipient
+            .to_bytes()
+            .into_iter()
+            .chain(self.timestamp.to_be_bytes())
+            .chain(self.nonce)
+            .chain(self.signature.to_bytes())
+            .collect()
+    }
+
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, InvalidReplyRequestError> {
+        if bytes.len() != Self::LEN {
+            return Err(InvalidReplyRequestError::RequestTooShortToDeserialize);
+        }
+
+        let mut recipient_bytes = [0u8; Recipient::LEN];
+        recipient_bytes.copy_from_slice(&bytes[..Recipient::LEN]);
+        let mut i = Recipient::LEN;
+
+        let mut timestamp_bytes = [0u8; mem::size_of::<u64>()];
+        timestamp_bytes.copy_from_slice(&bytes[i..i + mem::size_of::<u64>()]);
+        i += mem::size_of::<u64>();
+
+        let mut nonce = [0u8; GATEWAY_CHANGE_NONCE_SIZE];
+        nonce.copy_from_slice(&bytes[i..i + GATEWAY_CHANGE_NONCE_SIZE]);
+        i += GATEWAY_CHANGE_NONCE_SIZE;
+
+        Ok(GatewayChangeNotice {
+            recipient: Recipient::try_from_bytes(recipient_bytes)?,
+            timestamp: u64::from_be_bytes(timestamp_bytes),
+            nonce,
+            signature: identity::Signature::from_bytes(&bytes[i..])?,
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -354,6 +477,9 @@ impl Display for ReplyMessage {
                 f,
                 "request for {amount} additional reply SURBs from {recipient}",
             ),
+            ReplyMessageContent::GatewayChange { notice } => {
+                write!(f, "gateway change notice announcing {}", notice.recipient())
+            }
         }
     }
 }
@@ -374,6 +500,16 @@ impl ReplyMessage {
         }
     }
 
+    /// Creates a message carrying the notice informing the remote party that the sender
+    /// is now reachable at a new address.
+    pub fn new_gateway_change_message(notice: GatewayChangeNotice) -> Self {
+        ReplyMessage {
+            content: ReplyMessageContent::GatewayChange {
+                notice: Box::new(notice),
+            },
+        }
+    }
+
     pub fn into_bytes(self) -> Vec<u8> {
         let content_tag = self.content.tag();
 
@@ -397,6 +533,7 @@ impl ReplyMessage {
 enum ReplyMessageContentTag {
     Data = 0,
     SurbRequest = 1,
+    GatewayChange = 2,
 }
 
 impl TryFrom<u8> for ReplyMessageContentTag {
@@ -406,6 +543,7 @@ impl TryFrom<u8> for ReplyMessageContentTag {
         match value {
             _ if value == (ReplyMessageContentTag::Data as u8) => Ok(Self::Data),
             _ if value == (ReplyMessageContentTag::SurbRequest as u8) => Ok(Self::SurbRequest),
+            _ if value == (ReplyMessageContentTag::GatewayChange as u8) => Ok(Self::GatewayChange),
             val => Err(InvalidReplyRequestError::InvalidReplyContentTag { received: val }),
         }
     }
@@ -421,6 +559,9 @@ pub enum ReplyMessageContent {
         recipient: Box<Recipient>,
         amount: u32,
     },
+    GatewayChange {
+        notice: Box<GatewayChangeNotice>,
+    },
 }
 
 impl ReplyMessageContent {
@@ -432,6 +573,7 @@ impl ReplyMessageContent {
                 .into_iter()
                 .chain(amount.to_be_bytes().into_iter())
                 .collect(),
+            ReplyMessageContent::GatewayChange { notice } => notice.to_bytes(),
         }
     }
 
@@ -464,6 +606,9 @@ impl ReplyMessageContent {
                     ]),
                 })
             }
+            ReplyMessageContentTag::GatewayChange => Ok(ReplyMessageContent::GatewayChange {
+                notice: Box::new(GatewayChangeNotice::try_from_bytes(bytes)?),
+            }),
         }
     }
 
@@ -471,6 +616,118 @@ impl ReplyMessageContent {
         match self {
             ReplyMessageContent::Data { .. } => ReplyMessageContentTag::Data,
             ReplyMessageContent::SurbRequest { .. } => ReplyMessageContentTag::SurbRequest,
+            ReplyMessageContent::GatewayChange { .. } => ReplyMessageContentTag::GatewayChange,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::encryption;
+    use rand::rngs::OsRng;
+
+    fn recipient_with_keys(gateway: NodeIdentity) -> (Recipient, identity::KeyPair) {
+        let mut rng = OsRng;
+        let identity_keys = identity::KeyPair::new(&mut rng);
+        let encryption_keys = encryption::KeyPair::new(&mut rng);
+        let recipient = Recipient::new(
+            *identity_keys.public_key(),
+            *encryption_keys.public_key(),
+            gateway,
+        );
+        (recipient, identity_keys)
+    }
+
+    fn random_gateway() -> NodeIdentity {
+        *identity::KeyPair::new(&mut OsRng).public_key()
+    }
+
+    #[test]
+    fn gateway_change_notice_survives_round_trip() {
+        let new_gateway = random_gateway();
+        let (recipient, keys) = recipient_with_keys(new_gateway);
+        let notice =
+            GatewayChangeNotice::new_signed(&mut OsRng, recipient, 1234, keys.private_key());
+
+        let bytes = ReplyMessage::new_gateway_change_message(notice.clone()).into_bytes();
+        let recovered = ReplyMessage::try_from_bytes(&bytes).unwrap();
+
+        let recovered = match recovered.content {
+            ReplyMessageContent::GatewayChange { notice } => notice,
+            _ => panic!("unexpected reply content"),
+        };
+        assert_eq!(recovered.to_bytes(), notice.to_bytes());
+        assert_eq!(recovered.new_gateway(), &new_gateway);
+        assert_eq!(recovered.timestamp(), 1234);
+        assert!(recovered.verify().is_ok());
+    }
+
+    #[test]
+    fn gateway_change_notices_use_fresh_nonces() {
+        let (recipient, keys) = recipient_with_keys(random_gateway());
+        let first =
+            GatewayChangeNotice::new_signed(&mut OsRng, recipient, 1234, keys.private_key());
+        let second =
+            GatewayChangeNotice::new_signed(&mut OsRng, recipient, 1234, keys.private_key());
+
+        assert_ne!(first.nonce(), second.nonce());
+    }
+
+    #[test]
+    fn gateway_change_notice_signed_by_another_key_is_rejected() {
+        let (recipient, _) = recipient_with_keys(random_gateway());
+        let (_, other_keys) = recipient_with_keys(random_gateway());
+        let notice =
+            GatewayChangeNotice::new_signed(&mut OsRng, recipient, 1234, other_keys.private_key());
+
+        assert!(notice.verify().is_err());
+    }
+
+    #[test]
+    fn tampered_gateway_change_notice_is_rejected() {
+        let (recipient, keys) = recipient_with_keys(random_gateway());
+        let notice =
+            GatewayChangeNotice::new_signed(&mut OsRng, recipient, 1234, keys.private_key());
+        let bytes = notice.to_bytes();
+
+        // change the announced gateway
+        let (forged_recipient, _) = recipient_with_keys(random_gateway());
+        let mut redirected = bytes.clone();
+        redirected[..Recipient::LEN].copy_from_slice(
+            &Recipient::new(
+                *recipient.identity(),
+                *recipient.encryption_key(),
+                *forged_recipient.gateway(),
+            )
+            .to_bytes(),
+        );
+        let redirected = GatewayChangeNotice::try_from_bytes(&redirected).unwrap();
+        assert!(redirected.verify().is_err());
+
+        // change the timestamp
+        let mut refreshed = bytes.clone();
+        refreshed[Recipient::LEN + 7] ^= 1;
+        let refreshed = GatewayChangeNotice::try_from_bytes(&refreshed).unwrap();
+        assert!(refreshed.verify().is_err());
+
+        // change the nonce
+        let mut renonced = bytes;
+        renonced[Recipient::LEN + 8] ^= 1;
+        let renonced = GatewayChangeNotice::try_from_bytes(&renonced).unwrap();
+        assert!(renonced.verify().is_err());
+    }
+
+    #[test]
+    fn gateway_change_notice_of_invalid_length_is_rejected() {
+        let (recipient, keys) = recipient_with_keys(random_gateway());
+        let bytes =
+            GatewayChangeNotice::new_signed(&mut OsRng, recipient, 1234, keys.private_key())
+                .to_bytes();
+
+        assert!(GatewayChangeNotice::try_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut extended = bytes;
+        extended.push(0);
+        assert!(GatewayChangeNotice::try_from_bytes(&extended).is_err());
+    }
+}
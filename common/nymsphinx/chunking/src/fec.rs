@@ -0,0 +1,396 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::fragment::{Fragment, PARITY_FRAGMENT_HEADER_LEN};
+use crate::set::generate_set_id;
+use log::*;
+use rand::Rng;
+
+// Forward error correction works on the level of a single `FragmentSet`: the `k` data `Fragment`s
+// of the set are accompanied by `m` parity `Fragment`s so that the receiver could recover the whole
+// set after receiving *any* `k` out of those `k + m` `Fragment`s.
+//
+// The parity data is computed with a systematic Reed-Solomon code over GF(2^8) using a Cauchy
+// generator matrix, i.e. the `j`-th parity symbol is a linear combination of all the data symbols
+// where the coefficient for the `i`-th one is `1 / (x_j + y_i)` for `x_j = k + j` and `y_i = i`.
+// Every square submatrix of a Cauchy matrix is invertible, which is what guarantees that any
+// `k` `Fragment`s are sufficient for recovery. It also limits `k + m` to the size of the field.
+//
+// Each data symbol is the serialized data `Fragment` prefixed with its 2-byte length and padded
+// with zeroes to the common length, so that recovered `Fragment`s could be parsed back unambiguously.
+
+/// Number of bytes used to encode the length of each serialized data `Fragment` in the symbols.
+const SYMBOL_LENGTH_PREFIX: usize = 2;
+
+/// Number of bytes by which the serialized data `Fragment`s have to be shorter than the available
+/// plaintext, so that the parity `Fragment`s protecting them would also fit in the same packets.
+pub const PARITY_FRAGMENT_OVERHEAD: usize = PARITY_FRAGMENT_HEADER_LEN + SYMBOL_LENGTH_PREFIX;
+
+/// Maximum number of data and parity `Fragment`s combined, i.e. the size of the field.
+const MAX_TOTAL_FRAGMENTS: usize = 256;
+
+const GF_EXP: [u8; 512] = gf_exp_table();
+const GF_LOG: [u8; 256] = gf_log_table();
+
+// tables for GF(2^8) defined by the x^8 + x^4 + x^3 + x^2 + 1 polynomial
+const fn gf_exp_table() -> [u8; 512] {
+    let mut table = [0u8; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        table[i] = x as u8;
+        // duplicate the table so that multiplication would not need to reduce the sum of logs
+        table[i + 255] = x as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        i += 1;
+    }
+    table
+}
+
+const fn gf_log_table() -> [u8; 256] {
+    let exp = gf_exp_table();
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        GF_EXP[GF_LOG[a as usize] as usize + GF_LOG[b as usize] as usize]
+    }
+}
+
+fn gf_inv(a: u8) -> u8 {
+    debug_assert_ne!(a, 0);
+    GF_EXP[255 - GF_LOG[a as usize] as usize]
+}
+
+/// Computes `dst += coefficient * src` over GF(2^8).
+fn mul_add_symbol(dst: &mut [u8], src: &[u8], coefficient: u8) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= gf_mul(coefficient, *s)
+    }
+}
+
+/// Coefficient of the `data_index`-th data symbol in the `parity_index`-th parity symbol.
+fn cauchy_coefficient(data_fragments: usize, parity_index: usize, data_index: usize) -> u8 {
+    debug_assert!(data_fragments + parity_index < MAX_TOTAL_FRAGMENTS);
+    debug_assert!(data_index < data_fragments);
+
+    // x_j and y_i are always distinct and thus their sum (xor) is never 0
+    gf_inv(((data_fragments + parity_index) ^ data_index) as u8)
+}
+
+fn into_symbol(serialized_fragment: Vec<u8>, symbol_len: usize) -> Vec<u8> {
+    let mut symbol = Vec::with_capacity(symbol_len);
+    symbol.extend_from_slice(&(serialized_fragment.len() as u16).to_be_bytes());
+    symbol.extend(serialized_fragment);
+    symbol.resize(symbol_len, 0);
+    symbol
+}
+
+fn symbol_into_fragment(symbol: &[u8]) -> Option<Fragment> {
+    let len = u16::from_be_bytes([symbol[0], symbol[1]]) as usize;
+    let serialized = symbol.get(SYMBOL_LENGTH_PREFIX..SYMBOL_LENGTH_PREFIX + len)?;
+    Fragment::try_from_bytes(serialized).ok()
+}
+
+/// Determines number of parity `Fragment`s that should accompany set of `data_fragments` data
+/// `Fragment`s given the desired redundancy, i.e. ratio of parity to data `Fragment`s.
+/// Any positive redundancy results in at least a single parity `Fragment`.
+pub fn number_of_parity_fragments(data_fragments: usize, redundancy: f64) -> usize {
+    if data_fragments == 0 || data_fragments >= MAX_TOTAL_FRAGMENTS || redundancy <= 0.0 {
+        return 0;
+    }
+
+    let wanted = (data_fragments as f64 * redundancy).ceil() as usize;
+    wanted.clamp(1, MAX_TOTAL_FRAGMENTS - data_fragments)
+}
+
+/// Generates parity `Fragment`s for the provided `FragmentSet` allowing the receiver to recover
+/// the set after losing up to as many of its `Fragment`s as there were parity `Fragment`s created.
+pub fn generate_parity_fragments<R: Rng>(
+    rng: &mut R,
+    set: &[Fragment],
+    redundancy: f64,
+) -> Vec<Fragment> {
+    let parity_fragments = number_of_parity_fragments(set.len(), redundancy);
+    if parity_fragments == 0 {
+        return Vec::new();
+    }
+
+    let protected_set_id = set[0].id();
+    let serialized: Vec<_> = set.iter().map(|f| f.clone().into_bytes()).collect();
+    let symbol_len =
+        serialized.iter().map(Vec::len).max().unwrap_or_default() + SYMBOL_LENGTH_PREFIX;
+    let symbols: Vec<_> = serialized
+        .into_iter()
+        .map(|bytes| into_symbol(bytes, symbol_len))
+        .collect();
+
+    let mut parity_set_id = generate_set_id(rng);
+    while parity_set_id == protected_set_id {
+        parity_set_id = generate_set_id(rng);
+    }
+
+    (0..parity_fragments)
+        .map(|j| {
+            let mut parity = vec![0u8; symbol_len];
+            for (i, symbol) in symbols.iter().enumerate() {
+                mul_add_symbol(&mut parity, symbol, cauchy_coefficient(set.len(), j, i))
+            }
+
+            // the values are valid by construction: both ids are non-zero and distinct, and neither
+            // the number of data nor parity fragments can exceed u8::max_value()
+            Fragment::try_new_parity(
+                parity,
+                parity_set_id,
+                parity_fragments as u8,
+                (j + 1) as u8,
+                protected_set_id,
+                set.len() as u8,
+            )
+            .expect("failed to construct parity fragment")
+        })
+        .collect()
+}
+
+/// Attempts to recover the missing `Fragment`s of the `FragmentSet` of the provided id using
+/// the received parity `Fragment`s. Either all of them are recovered or none at all, which happens
+/// if there is not enough parity data available (yet).
+pub(crate) fn recover_missing_fragments(
+    set_id: i32,
+    fragments: &[Option<Fragment>],
+    parity: &[Fragment],
+) -> Vec<Fragment> {
+    let data_fragments = fragments.len();
+    let missing: Vec<_> = fragments
+        .iter()
+        .enumerate()
+        .filter(|(_, fragment)| fragment.is_none())
+        .map(|(i, _)| i)
+        .collect();
+
+    let symbol_len = match parity.first() {
+        Some(parity_fragment) => parity_fragment.payload().len(),
+        None => return Vec::new(),
+    };
+    if missing.is_empty() || symbol_len < SYMBOL_LENGTH_PREFIX {
+        return Vec::new();
+    }
+
+    let usable_parity: Vec<_> = parity
+        .iter()
+        .filter(|p| {
+            p.protected_set_len() == Some(data_fragments as u8)
+                && p.payload().len() == symbol_len
+                && data_fragments + (p.current_fragment() as usize) <= MAX_TOTAL_FRAGMENTS
+        })
+        .take(missing.len())
+        .collect();
+    if usable_parity.len() < missing.len() {
+        return Vec::new();
+    }
+
+    // remove contribution of all the received data fragments from the parity symbols,
+    // so that each of them becomes a combination of only the missing ones
+    let mut rows = Vec::with_capacity(missing.len());
+    for parity_fragment in usable_parity {
+        let j = parity_fragment.current_fragment() as usize - 1;
+        let mut rhs = parity_fragment.payload().to_vec();
+        for (i, fragment) in fragments.iter().enumerate() {
+            if let Some(fragment) = fragment {
+                let serialized = fragment.clone().into_bytes();
+                if serialized.len() + SYMBOL_LENGTH_PREFIX > symbol_len {
+                    warn!("received parity data inconsistent with the fragments of set {set_id}");
+                    return Vec::new();
+                }
+                let symbol = into_symbol(serialized, symbol_len);
+                mul_add_symbol(&mut rhs, &symbol, cauchy_coefficient(data_fragments, j, i));
+            }
+        }
+        let coefficients: Vec<_> = missing
+            .iter()
+            .map(|&i| cauchy_coefficient(data_fragments, j, i))
+            .collect();
+        rows.push((coefficients, rhs))
+    }
+
+    // and solve the resultant system of equations with gaussian elimination
+    for col in 0..missing.len() {
+        let pivot = match (col..rows.len()).find(|&r| rows[r].0[col] != 0) {
+            Some(pivot) => pivot,
+            // this can't happen for a valid cauchy matrix, but let's not panic on bad input
+            None => return Vec::new(),
+        };
+        rows.swap(col, pivot);
+
+        let inv = gf_inv(rows[col].0[col]);
+        for c in rows[col].0.iter_mut() {
+            *c = gf_mul(*c, inv)
+        }
+        for b in rows[col].1.iter_mut() {
+            *b = gf_mul(*b, inv)
+        }
+
+        let (pivot_coefficients, pivot_rhs) = rows[col].clone();
+        for (r, (coefficients, rhs)) in rows.iter_mut().enumerate() {
+            let factor = coefficients[col];
+            if r == col || factor == 0 {
+                continue;
+            }
+            mul_add_symbol(coefficients, &pivot_coefficients, factor);
+            mul_add_symbol(rhs, &pivot_rhs, factor);
+        }
+    }
+
+    let mut recovered = Vec::with_capacity(missing.len());
+    for (&i, (_, symbol)) in missing.iter().zip(rows) {
+        match symbol_into_fragment(&symbol) {
+            Some(fragment)
+                if !fragment.is_parity()
+                    && fragment.id() == set_id
+                    && fragment.total_fragments() as usize == data_fragments
+                    && fragment.current_fragment() as usize == i + 1 =>
+            {
+                recovered.push(fragment)
+            }
+            _ => {
+                warn!(
+                    "failed to recover fragment {} of set {set_id} from the parity data",
+                    i + 1
+                );
+                return Vec::new();
+            }
+        }
+    }
+
+    recovered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split_into_sets;
+    use rand::seq::SliceRandom;
+    use rand::{thread_rng, RngCore};
+
+    // just some arbitrary value to use in tests
+    const AVAILABLE_PLAINTEXT_SIZE: usize = 1024;
+
+    fn random_set(fragments: usize) -> Vec<Fragment> {
+        let mut rng = thread_rng();
+        let mut message = vec![0u8; fragments * (AVAILABLE_PLAINTEXT_SIZE - 7) - 42];
+        rng.fill_bytes(&mut message);
+
+        let mut sets = split_into_sets(&mut rng, &message, AVAILABLE_PLAINTEXT_SIZE);
+        assert_eq!(sets.len(), 1);
+        sets.pop().unwrap()
+    }
+
+    #[test]
+    fn field_tables_are_consistent() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+            assert_eq!(gf_mul(a, 1), a);
+            assert_eq!(gf_mul(a, 0), 0);
+        }
+    }
+
+    #[test]
+    fn number_of_parity_fragments_is_bounded() {
+        assert_eq!(number_of_parity_fragments(10, 0.0), 0);
+        assert_eq!(number_of_parity_fragments(10, 0.01), 1);
+        assert_eq!(number_of_parity_fragments(10, 0.25), 3);
+        assert_eq!(number_of_parity_fragments(200, 1.0), 56);
+        assert_eq!(number_of_parity_fragments(255, 0.5), 1);
+    }
+
+    #[test]
+    fn parity_fragments_fit_in_the_same_packets_as_data_fragments() {
+        let set = random_set(10);
+        let parity = generate_parity_fragments(&mut thread_rng(), &set, 0.3);
+        assert_eq!(parity.len(), 3);
+
+        let data_len = set[0].clone().into_bytes().len();
+        for parity_fragment in parity {
+            assert!(parity_fragment.is_parity());
+            assert_eq!(parity_fragment.data_set_id(), set[0].id());
+            assert_eq!(
+                parity_fragment.into_bytes().len(),
+                data_len + PARITY_FRAGMENT_OVERHEAD
+            );
+        }
+    }
+
+    #[test]
+    fn any_subset_of_missing_fragments_can_be_recovered() {
+        let mut rng = thread_rng();
+        let set = random_set(20);
+        let mut parity = generate_parity_fragments(&mut rng, &set, 0.25);
+        assert_eq!(parity.len(), 5);
+
+        for lost in 1..=5 {
+            let mut received: Vec<_> = set.iter().cloned().map(Some).collect();
+            let mut positions: Vec<_> = (0..set.len()).collect();
+            positions.shuffle(&mut rng);
+            for &i in positions.iter().take(lost) {
+                received[i] = None;
+            }
+
+            parity.shuffle(&mut rng);
+            let recovered = recover_missing_fragments(set[0].id(), &received, &parity[..lost]);
+            assert_eq!(recovered.len(), lost);
+            for fragment in recovered {
+                assert_eq!(
+                    Some(&fragment),
+                    set.get(fragment.current_fragment() as usize - 1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn recovery_fails_with_insufficient_parity() {
+        let set = random_set(20);
+        let parity = generate_parity_fragments(&mut thread_rng(), &set, 0.25);
+
+        let mut received: Vec<_> = set.iter().cloned().map(Some).collect();
+        received[3] = None;
+        received[7] = None;
+        received[11] = None;
+
+        assert!(recover_missing_fragments(set[0].id(), &received, &parity[..2]).is_empty());
+        assert_eq!(
+            recover_missing_fragments(set[0].id(), &received, &parity[..3]).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn recovery_works_for_sets_with_non_full_tail() {
+        let mut rng = thread_rng();
+        let mut message = vec![0u8; 5 * (AVAILABLE_PLAINTEXT_SIZE - 7) + 3];
+        rng.fill_bytes(&mut message);
+        let set = split_into_sets(&mut rng, &message, AVAILABLE_PLAINTEXT_SIZE)
+            .pop()
+            .unwrap();
+        assert_eq!(set.len(), 6);
+
+        let parity = generate_parity_fragments(&mut rng, &set, 0.1);
+        let mut received: Vec<_> = set.iter().cloned().map(Some).collect();
+        received[5] = None;
+
+        let recovered = recover_missing_fragments(set[0].id(), &received, &parity);
+        assert_eq!(recovered, vec![set[5].clone()]);
+    }
+}
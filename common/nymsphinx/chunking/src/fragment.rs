@@ -16,7 +16,7 @@ use std::fmt::{self, Debug, Formatter};
 // position.
 
 // TODO for later: with the removal of 'unfragmented' fragments, the first bit of each header
-// is completely useless, we should then think how to make the set_id become u32 instead of i32.
+// is only used to distinguish parity fragments, we should then think how to make the set_id become u32 instead of i32.
 // (the current limitation for making the seemingly trivial change is "linked id" which
 // has to have same amount of space available and right now it only has 31 bits available)
 
@@ -34,6 +34,13 @@ pub const UNLINKED_FRAGMENTED_HEADER_LEN: usize = 7;
 /// `Fragment` in a `FragmentSet`.
 pub const LINKED_FRAGMENTED_HEADER_LEN: usize = 10;
 
+/// Parity `Fragment`s do not carry any part of the message itself, but instead redundant data
+/// of another `FragmentSet` (refer to `fec.rs` for more details). On top of 4 byte id of their own
+/// set, 1 byte for total number of parity fragments and 1 byte for position of the current one,
+/// they require 4 bytes for id of the protected set and 1 byte to represent the number of
+/// data fragments in that set.
+pub const PARITY_FRAGMENT_HEADER_LEN: usize = 11;
+
 /// Maximum size of payload of each fragment is always the maximum amount of plaintext data
 /// we can put into a sphinx packet minus length of respective fragment header.
 pub const fn unlinked_fragment_payload_max_len(max_plaintext_size: usize) -> usize {
@@ -200,6 +207,28 @@ impl Fragment {
             .collect()
     }
 
+    /// Tries to encapsulate provided parity data of another `FragmentSet` into a `Fragment`.
+    /// Unlike data `Fragment`s, the length of the payload is not checked here as it depends on
+    /// the length of the serialized `Fragment`s in the protected set.
+    pub(crate) fn try_new_parity(
+        payload: Vec<u8>,
+        id: i32,
+        total_fragments: u8,
+        current_fragment: u8,
+        protected_set_id: i32,
+        protected_set_len: u8,
+    ) -> Result<Self, ChunkingError> {
+        let header = FragmentHeader::try_new_parity(
+            id,
+            total_fragments,
+            current_fragment,
+            protected_set_id,
+            protected_set_len,
+        )?;
+
+        Ok(Fragment { header, payload })
+    }
+
     /// Derive identifier unique for this particular fragment
     pub fn fragment_identifier(&self) -> FragmentIdentifier {
         FragmentIdentifier {
@@ -239,6 +268,33 @@ impl Fragment {
         self.header.next_fragments_set_id
     }
 
+    /// Checks whether this is a parity `Fragment` carrying redundant data of another `FragmentSet`
+    /// rather than a part of the message itself.
+    pub fn is_parity(&self) -> bool {
+        self.header.protected_set.is_some()
+    }
+
+    /// Extracts id of the `FragmentSet` whose data is carried by this `Fragment`, i.e. its own id
+    /// for data `Fragment`s and id of the protected set for the parity ones.
+    pub fn data_set_id(&self) -> i32 {
+        self.header
+            .protected_set
+            .map(|protected_set| protected_set.id)
+            .unwrap_or(self.header.id)
+    }
+
+    /// Extracts number of data `Fragment`s in the `FragmentSet` protected by this parity `Fragment`.
+    pub(crate) fn protected_set_len(&self) -> Option<u8> {
+        self.header
+            .protected_set
+            .map(|protected_set| protected_set.total_fragments)
+    }
+
+    /// Gets the payload associated with this `Fragment` without consuming it.
+    pub(crate) fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
     /// Consumes `self` to obtain payload (i.e. part of original message) associated with this
     /// `Fragment`.
     pub(crate) fn extract_payload(self) -> Vec<u8> {
@@ -289,6 +345,10 @@ impl Fragment {
 /// there is 7 bytes of overhead inside each sphinx packet sent
 /// and for the longest messages, without upper bound, there is usually also only 7 bytes
 /// of overhead apart from first and last fragments in each set that instead have 10 bytes of overhead.
+///
+/// Finally, if forward error correction is used, there is one more, 11 byte long, sequence
+/// representing a parity `Fragment` protecting another set:
+/// '0'bit || 31-bit ID || 1-byte TF || 1 byte CF || '0'bit || 31-bit protected ID || 1-byte protected TF
 #[derive(PartialEq, Clone, Debug)]
 pub(crate) struct FragmentHeader {
     /// ID associated with `FragmentSet` to which this particular `Fragment` belongs.
@@ -313,6 +373,20 @@ pub(crate) struct FragmentHeader {
     /// Optional ID of next `FragmentSet` into which the original message was split.
     /// Note, this option is only valid of `current_fragment == total_fragments == u8::max_value()`
     next_fragments_set_id: Option<i32>,
+
+    /// Set only for parity `Fragment`s to indicate which `FragmentSet` they are protecting.
+    /// Parity `Fragment`s are never linked to other sets.
+    protected_set: Option<ProtectedSet>,
+}
+
+/// Information regarding the `FragmentSet` whose data is protected by a parity `Fragment`.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub(crate) struct ProtectedSet {
+    /// ID of the protected `FragmentSet`.
+    id: i32,
+
+    /// Total number of data `Fragment`s in the protected `FragmentSet`.
+    total_fragments: u8,
 }
 
 impl FragmentHeader {
@@ -355,6 +429,39 @@ impl FragmentHeader {
             current_fragment,
             previous_fragments_set_id,
             next_fragments_set_id,
+            protected_set: None,
+        })
+    }
+
+    /// Tries to create a new parity `FragmentHeader` using provided metadata. Similarly to
+    /// `try_new`, logical checks are performed to see if the data is not self-contradictory.
+    fn try_new_parity(
+        id: i32,
+        total_fragments: u8,
+        current_fragment: u8,
+        protected_set_id: i32,
+        protected_set_len: u8,
+    ) -> Result<Self, ChunkingError> {
+        if id <= 0 || protected_set_id <= 0 || id == protected_set_id {
+            return Err(ChunkingError::MalformedHeaderError);
+        }
+        if total_fragments == 0 || current_fragment == 0 || total_fragments < current_fragment {
+            return Err(ChunkingError::MalformedHeaderError);
+        }
+        if protected_set_len == 0 {
+            return Err(ChunkingError::MalformedHeaderError);
+        }
+
+        Ok(FragmentHeader {
+            id,
+            total_fragments,
+            current_fragment,
+            previous_fragments_set_id: None,
+            next_fragments_set_id: None,
+            protected_set: Some(ProtectedSet {
+                id: protected_set_id,
+                total_fragments: protected_set_len,
+            }),
         })
     }
 
@@ -370,9 +477,9 @@ impl FragmentHeader {
             });
         }
         let frag_id = i32::from_be_bytes(b[0..4].try_into().unwrap());
-        // sanity check for the fragmentation flag
+        // cleared fragmentation flag indicates a parity fragment
         if ((frag_id >> 31) & 1) == 0 {
-            return Err(ChunkingError::MalformedHeaderError);
+            return Self::try_parity_from_bytes(b);
         }
 
         let id = frag_id & !(1 << 31); // make sure to clear the flag bit to parse id correctly
@@ -429,8 +536,43 @@ impl FragmentHeader {
         ))
     }
 
+    /// Tries to recover parity `FragmentHeader` from slice of bytes extracted from received sphinx packet.
+    /// If successful, returns `Self` and number of bytes used.
+    fn try_parity_from_bytes(b: &[u8]) -> Result<(Self, usize), ChunkingError> {
+        if b.len() < PARITY_FRAGMENT_HEADER_LEN {
+            return Err(ChunkingError::TooShortFragmentHeader {
+                received: b.len(),
+                expected: PARITY_FRAGMENT_HEADER_LEN,
+            });
+        }
+
+        // the flag bits of both ids are cleared, so they are going to get parsed as non-negative
+        let id = i32::from_be_bytes(b[0..4].try_into().unwrap());
+        let protected_set_id = i32::from_be_bytes(b[6..10].try_into().unwrap());
+        if protected_set_id < 0 {
+            return Err(ChunkingError::MalformedHeaderError);
+        }
+
+        Ok((
+            Self::try_new_parity(id, b[4], b[5], protected_set_id, b[10])?,
+            PARITY_FRAGMENT_HEADER_LEN,
+        ))
+    }
+
     /// Marshal this `FragmentHeader` into vector of bytes which can be put into a sphinx packet.
     fn to_bytes(&self) -> Vec<u8> {
+        if let Some(protected_set) = self.protected_set {
+            return self
+                .id
+                .to_be_bytes()
+                .into_iter()
+                .chain(std::iter::once(self.total_fragments))
+                .chain(std::iter::once(self.current_fragment))
+                .chain(protected_set.id.to_be_bytes())
+                .chain(std::iter::once(protected_set.total_fragments))
+                .collect();
+        }
+
         let frag_id = self.id | (1 << 31);
         let frag_id_bytes = frag_id.to_be_bytes();
         let bytes_prefix_iter = frag_id_bytes
@@ -1020,6 +1162,7 @@ mod fragment_header {
                 current_fragment: 11,
                 previous_fragments_set_id: None,
                 next_fragments_set_id: None,
+                protected_set: None,
             };
             let header_bytes = header.to_bytes();
             assert!(FragmentHeader::try_from_bytes(&header_bytes).is_err());
@@ -1034,6 +1177,7 @@ mod fragment_header {
                 current_fragment: 0,
                 previous_fragments_set_id: None,
                 next_fragments_set_id: None,
+                protected_set: None,
             };
             let header_bytes = header.to_bytes();
             assert!(FragmentHeader::try_from_bytes(&header_bytes).is_err());
@@ -1135,4 +1279,66 @@ mod fragment_header {
             assert_eq!(LINKED_FRAGMENTED_HEADER_LEN, bytes_used);
         }
     }
+
+    #[cfg(test)]
+    mod parity_payload {
+        use super::*;
+
+        #[test]
+        fn can_be_converted_to_and_from_bytes() {
+            let parity_header = FragmentHeader::try_new_parity(54321, 5, 2, 12345, 42).unwrap();
+
+            let mut header_bytes = parity_header.to_bytes();
+            assert_eq!(PARITY_FRAGMENT_HEADER_LEN, header_bytes.len());
+            header_bytes.append(vec![1, 2, 3, 4, 5].as_mut());
+
+            let (recovered_header, bytes_used) =
+                FragmentHeader::try_from_bytes(&header_bytes).unwrap();
+            assert_eq!(parity_header, recovered_header);
+            assert_eq!(PARITY_FRAGMENT_HEADER_LEN, bytes_used);
+        }
+
+        #[test]
+        fn retrieval_from_bytes_fail_for_insufficient_number_of_bytes_provided() {
+            let parity_header = FragmentHeader::try_new_parity(54321, 5, 2, 12345, 42).unwrap();
+
+            let header_bytes = parity_header.to_bytes();
+            let header_bytes = &header_bytes[..header_bytes.len() - 1];
+            assert!(FragmentHeader::try_from_bytes(header_bytes).is_err())
+        }
+
+        #[test]
+        fn retrieval_from_bytes_fail_for_invalid_protected_set_flag() {
+            let parity_header = FragmentHeader::try_new_parity(54321, 5, 2, 12345, 42).unwrap();
+
+            let mut header_bytes = parity_header.to_bytes();
+            header_bytes[6] |= 1 << 7;
+            assert!(FragmentHeader::try_from_bytes(&header_bytes).is_err())
+        }
+
+        #[test]
+        fn cannot_protect_itself() {
+            assert!(FragmentHeader::try_new_parity(12345, 5, 2, 12345, 42).is_err());
+        }
+
+        #[test]
+        fn creation_of_header_fails_for_invalid_positions() {
+            assert!(FragmentHeader::try_new_parity(54321, 5, 6, 12345, 42).is_err());
+            assert!(FragmentHeader::try_new_parity(54321, 5, 0, 12345, 42).is_err());
+            assert!(FragmentHeader::try_new_parity(54321, 0, 0, 12345, 42).is_err());
+            assert!(FragmentHeader::try_new_parity(54321, 5, 2, 12345, 0).is_err());
+        }
+
+        #[test]
+        fn parity_fragment_reports_protected_set() {
+            let fragment = Fragment::try_new_parity(vec![1, 2, 3], 54321, 5, 2, 12345, 42).unwrap();
+            assert!(fragment.is_parity());
+            assert_eq!(fragment.id(), 54321);
+            assert_eq!(fragment.data_set_id(), 12345);
+            assert_eq!(fragment.protected_set_len(), Some(42));
+
+            let recovered = Fragment::try_from_bytes(&fragment.clone().into_bytes()).unwrap();
+            assert_eq!(fragment, recovered);
+        }
+    }
 }
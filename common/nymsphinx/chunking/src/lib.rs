@@ -16,6 +16,7 @@ use thiserror::Error;
 // they should definitely be revisited.
 // For instance there are not tests for the cases when we are padding the message
 
+pub mod fec;
 pub mod fragment;
 pub mod reconstruction;
 pub mod set;
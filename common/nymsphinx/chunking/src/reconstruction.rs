@@ -1,5 +1,6 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
+use crate::fec;
 use crate::fragment::Fragment;
 use crate::ChunkingError;
 use log::*;
@@ -147,6 +148,9 @@ pub struct MessageReconstructor {
     // maximum sized sets but without one of required fragments. All of the received
     // data will be kept on the heap indefinitely in the current implementation.
     reconstructed_sets: HashMap<i32, ReconstructionBuffer>,
+
+    /// Received parity `Fragment`s, keyed by the ids of the `FragmentSet`s they are protecting.
+    parity_fragments: HashMap<i32, Vec<Fragment>>,
 }
 
 impl MessageReconstructor {
@@ -260,23 +264,82 @@ impl MessageReconstructor {
             .flat_map(|payload| payload.into_iter())
             .collect();
 
+        // any parity data for the used sets is no longer needed
+        for id in &set_id_sequence {
+            self.parity_fragments.remove(id);
+        }
+
         (message_content, set_id_sequence)
     }
 
+    /// If the set of given `id` is still missing some `Fragment`s, attempts to recover them
+    /// using the already received parity `Fragment`s.
+    fn try_recover_set(&mut self, set_id: i32) {
+        let parity = match self.parity_fragments.get(&set_id) {
+            Some(parity) => parity,
+            None => return,
+        };
+        let buf = match self.reconstructed_sets.get_mut(&set_id) {
+            Some(buf) if !buf.is_complete => buf,
+            _ => return,
+        };
+
+        let missing = buf.fragments.iter().filter(|frag| frag.is_none()).count();
+        if missing > parity.len() {
+            return;
+        }
+
+        let recovered = fec::recover_missing_fragments(set_id, &buf.fragments, parity);
+        if !recovered.is_empty() {
+            debug!(
+                "recovered {} lost fragment(s) of set {set_id} using parity data",
+                recovered.len()
+            );
+        }
+        for fragment in recovered {
+            buf.insert_fragment(fragment)
+        }
+    }
+
     /// Given recovered `Fragment`, tries to insert it into an appropriate `ReconstructionBuffer`.
     /// If a buffer does not exist, a new instance is created.
     /// If it was last remaining `Fragment` for the original message, the message is reconstructed
     /// and returned alongside all (if applicable) set ids used in the message.
+    ///
+    /// Parity `Fragment`s are buffered alongside the set they are protecting and are used
+    /// to recover any of its missing `Fragment`s once enough data has been received.
     pub fn insert_new_fragment(&mut self, fragment: Fragment) -> Option<ReconstructedMessage> {
-        let set_id = fragment.id();
-        let set_len = fragment.total_fragments();
+        let set_id = fragment.data_set_id();
+
+        if let Some(set_len) = fragment.protected_set_len() {
+            // make sure there's a buffer for the set even if all of its data fragments got lost
+            self.reconstructed_sets
+                .entry(set_id)
+                .or_insert_with(|| ReconstructionBuffer::new(set_len));
+            let parity = self.parity_fragments.entry(set_id).or_default();
+            if parity
+                .iter()
+                .any(|p| p.fragment_identifier() == fragment.fragment_identifier())
+            {
+                warn!(
+                    "duplicate parity fragment received! - frag - {} (set id: {})",
+                    fragment.current_fragment(),
+                    fragment.id()
+                );
+            } else {
+                parity.push(fragment);
+            }
+        } else {
+            let set_len = fragment.total_fragments();
+            let buf = self
+                .reconstructed_sets
+                .entry(set_id)
+                .or_insert_with(|| ReconstructionBuffer::new(set_len));
 
-        let buf = self
-            .reconstructed_sets
-            .entry(set_id)
-            .or_insert_with(|| ReconstructionBuffer::new(set_len));
+            buf.insert_fragment(fragment);
+        }
 
-        buf.insert_fragment(fragment);
+        self.try_recover_set(set_id);
         if self.is_message_fully_received(set_id) {
             Some(self.reconstruct_message(set_id))
         } else {
@@ -1847,4 +1910,130 @@ mod message_reconstruction {
             }
         }
     }
+
+    #[cfg(test)]
+    mod forward_error_correction {
+        use super::*;
+        use crate::fec::generate_parity_fragments;
+        use crate::set::max_one_way_linked_set_payload_length;
+
+        fn reconstruct(
+            message_reconstructor: &mut MessageReconstructor,
+            fragments: Vec<Vec<u8>>,
+        ) -> Option<ReconstructedMessage> {
+            // note that some of the fragments might still arrive after the message has been
+            // reconstructed, as the parity data made them redundant
+            let mut reconstructed = None;
+            for fragment in fragments {
+                let fragment = message_reconstructor.recover_fragment(fragment).unwrap();
+                if let Some(msg) = message_reconstructor.insert_new_fragment(fragment) {
+                    assert!(reconstructed.is_none());
+                    reconstructed = Some(msg)
+                }
+            }
+            reconstructed
+        }
+
+        #[test]
+        fn it_reconstructs_message_with_lost_fragments() {
+            let mut rng = thread_rng();
+
+            let mut message = vec![0u8; 30 * AVAILABLE_PLAINTEXT_SIZE];
+            rng.fill_bytes(&mut message);
+
+            let set = crate::split_into_sets(&mut rng, &message, AVAILABLE_PLAINTEXT_SIZE)
+                .pop()
+                .unwrap();
+            let mut parity = generate_parity_fragments(&mut rng, &set, 0.2);
+            assert_eq!(parity.len(), 7);
+
+            // lose some data fragments and one of the parity fragments
+            let mut fragments: Vec<_> = set.into_iter().skip(5).collect();
+            parity.pop();
+            fragments.append(&mut parity);
+            fragments.shuffle(&mut rng);
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            let (reconstructed, used_sets) = reconstruct(
+                &mut message_reconstructor,
+                fragments.into_iter().map(|x| x.into_bytes()).collect(),
+            )
+            .unwrap();
+            assert_eq!(reconstructed, message);
+            assert_eq!(used_sets.len(), 1);
+        }
+
+        #[test]
+        fn it_reconstructs_message_if_all_data_fragments_arrive_first() {
+            let mut rng = thread_rng();
+
+            let mut message = vec![0u8; 10 * AVAILABLE_PLAINTEXT_SIZE];
+            rng.fill_bytes(&mut message);
+
+            let set = crate::split_into_sets(&mut rng, &message, AVAILABLE_PLAINTEXT_SIZE)
+                .pop()
+                .unwrap();
+            let parity = generate_parity_fragments(&mut rng, &set, 0.2);
+
+            let fragments = set
+                .into_iter()
+                .chain(parity)
+                .map(|x| x.into_bytes())
+                .collect();
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            let (reconstructed, _) = reconstruct(&mut message_reconstructor, fragments).unwrap();
+            assert_eq!(reconstructed, message);
+        }
+
+        #[test]
+        fn it_does_not_reconstruct_message_with_too_many_lost_fragments() {
+            let mut rng = thread_rng();
+
+            let mut message = vec![0u8; 30 * AVAILABLE_PLAINTEXT_SIZE];
+            rng.fill_bytes(&mut message);
+
+            let set = crate::split_into_sets(&mut rng, &message, AVAILABLE_PLAINTEXT_SIZE)
+                .pop()
+                .unwrap();
+            let parity = generate_parity_fragments(&mut rng, &set, 0.1);
+            assert_eq!(parity.len(), 4);
+
+            let fragments = set
+                .into_iter()
+                .skip(5)
+                .chain(parity)
+                .map(|x| x.into_bytes())
+                .collect();
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            assert!(reconstruct(&mut message_reconstructor, fragments).is_none());
+        }
+
+        #[test]
+        fn it_reconstructs_message_split_into_two_sets_with_lost_fragments() {
+            let mut rng = thread_rng();
+
+            let mut message =
+                vec![0u8; max_one_way_linked_set_payload_length(AVAILABLE_PLAINTEXT_SIZE) + 12345];
+            rng.fill_bytes(&mut message);
+
+            let mut fragments = Vec::new();
+            for set in crate::split_into_sets(&mut rng, &message, AVAILABLE_PLAINTEXT_SIZE) {
+                let parity = generate_parity_fragments(&mut rng, &set, 0.1);
+                // lose the first (linked) fragment of each set
+                fragments.extend(set.into_iter().skip(1).chain(parity));
+            }
+            fragments.shuffle(&mut rng);
+
+            let mut message_reconstructor = MessageReconstructor::default();
+            let (reconstructed, used_sets) = reconstruct(
+                &mut message_reconstructor,
+                fragments.into_iter().map(|x| x.into_bytes()).collect(),
+            )
+            .unwrap();
+            assert_eq!(reconstructed, message);
+            assert_eq!(used_sets.len(), 2);
+        }
+    }
 }
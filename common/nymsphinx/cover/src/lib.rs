@@ -13,8 +13,7 @@ use nym_sphinx_params::packet_sizes::PacketSize;
 use nym_sphinx_params::{
     PacketEncryptionAlgorithm, PacketHkdfAlgorithm, PacketMode, DEFAULT_NUM_MIX_HOPS,
 };
-use nym_sphinx_types::builder::SphinxPacketBuilder;
-use nym_sphinx_types::{delays, Error as SphinxError};
+use nym_sphinx_types::{delays, NymPacket, NymPacketError};
 use nym_topology::{NymTopology, NymTopologyError};
 use rand::{CryptoRng, RngCore};
 use std::convert::TryFrom;
@@ -28,8 +27,8 @@ pub enum CoverMessageError {
     #[error("Could not construct cover message due to invalid topology - {0}")]
     InvalidTopologyError(#[from] NymTopologyError),
 
-    #[error("Could not construct a valid sphinx packet - {0}")]
-    SphinxError(#[from] SphinxError),
+    #[error("Could not construct a valid packet - {0}")]
+    PacketError(#[from] NymPacketError),
 }
 
 pub fn generate_loop_cover_surb_ack<R>(
@@ -110,16 +109,26 @@ where
     let delays = delays::generate_from_average_duration(route.len(), average_packet_delay);
     let destination = full_address.as_sphinx_destination();
 
+    // cover packets must use the same format as the real traffic in order to be indistinguishable from it
     // once merged, that's an easy rng injection point for sphinx packets : )
-    let packet = SphinxPacketBuilder::new()
-        .with_payload_size(packet_size.payload_size())
-        .build_packet(packet_payload, &route, &destination, &delays)
-        .unwrap();
+    let (packet, packet_mode) = if packet_size.is_outfox() {
+        let packet = NymPacket::outfox_build(packet_payload, &route, &destination, &delays)?;
+        (packet, PacketMode::Outfox)
+    } else {
+        let packet = NymPacket::sphinx_build(
+            packet_size.payload_size(),
+            packet_payload,
+            &route,
+            &destination,
+            &delays,
+        )?;
+        (packet, PacketMode::Mix)
+    };
 
     let first_hop_address =
         NymNodeRoutingAddress::try_from(route.first().unwrap().address).unwrap();
 
-    Ok(MixPacket::new(first_hop_address, packet, PacketMode::Mix))
+    Ok(MixPacket::new(first_hop_address, packet, packet_mode))
 }
 
 /// Helper function used to determine if given message represents a loop cover message.
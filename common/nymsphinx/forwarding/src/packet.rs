@@ -3,7 +3,7 @@
 
 use nym_sphinx_addressing::nodes::{NymNodeRoutingAddress, NymNodeRoutingAddressError};
 use nym_sphinx_params::{PacketMode, PacketSize};
-use nym_sphinx_types::SphinxPacket;
+use nym_sphinx_types::NymPacket;
 use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display, Formatter};
 
@@ -14,6 +14,7 @@ pub enum MixPacketFormattingError {
     InvalidPacketSize(usize),
     InvalidAddress,
     MalformedSphinxPacket,
+    MalformedOutfoxPacket,
 }
 
 impl Display for MixPacketFormattingError {
@@ -25,12 +26,13 @@ impl Display for MixPacketFormattingError {
             InvalidPacketSize(actual) =>
                 write!(
                     f,
-                    "received request had invalid size. (actual: {}, but expected one of: {} (ACK), {} (REGULAR), {}, {}, {} (EXTENDED))",
+                    "received request had invalid size. (actual: {}, but expected one of: {} (ACK), {} (REGULAR), {}, {}, {} (EXTENDED), {} (OUTFOX))",
                     actual, PacketSize::AckPacket.size(), PacketSize::RegularPacket.size(),
                     PacketSize::ExtendedPacket8.size(), PacketSize::ExtendedPacket16.size(),
-                    PacketSize::ExtendedPacket32.size()
+                    PacketSize::ExtendedPacket32.size(), PacketSize::OutfoxRegularPacket.size()
                 ),
             MalformedSphinxPacket => write!(f, "received sphinx packet was malformed"),
+            MalformedOutfoxPacket => write!(f, "received outfox packet was malformed"),
             InvalidPacketMode => write!(f, "provided packet mode is invalid")
         }
     }
@@ -46,32 +48,41 @@ impl From<NymNodeRoutingAddressError> for MixPacketFormattingError {
 
 pub struct MixPacket {
     next_hop: NymNodeRoutingAddress,
-    sphinx_packet: SphinxPacket,
+    packet: NymPacket,
     packet_mode: PacketMode,
 }
 
 impl Debug for MixPacket {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "MixPacket to {:?} with packet_mode {:?}. Sphinx header: {:?}, payload length: {}",
-            self.next_hop,
-            self.packet_mode,
-            self.sphinx_packet.header,
-            self.sphinx_packet.payload.len()
-        )
+        match &self.packet {
+            NymPacket::Sphinx(sphinx_packet) => write!(
+                f,
+                "MixPacket to {:?} with packet_mode {:?}. Sphinx header: {:?}, payload length: {}",
+                self.next_hop,
+                self.packet_mode,
+                sphinx_packet.header,
+                sphinx_packet.payload.len()
+            ),
+            NymPacket::Outfox(outfox_packet) => write!(
+                f,
+                "MixPacket to {:?} with packet_mode {:?}. Outfox packet length: {}",
+                self.next_hop,
+                self.packet_mode,
+                outfox_packet.len()
+            ),
+        }
     }
 }
 
 impl MixPacket {
     pub fn new(
         next_hop: NymNodeRoutingAddress,
-        sphinx_packet: SphinxPacket,
+        packet: NymPacket,
         packet_mode: PacketMode,
     ) -> Self {
         MixPacket {
             next_hop,
-            sphinx_packet,
+            packet,
             packet_mode,
         }
     }
@@ -80,12 +91,12 @@ impl MixPacket {
         self.next_hop
     }
 
-    pub fn sphinx_packet(&self) -> &SphinxPacket {
-        &self.sphinx_packet
+    pub fn packet(&self) -> &NymPacket {
+        &self.packet
     }
 
-    pub fn into_sphinx_packet(self) -> SphinxPacket {
-        self.sphinx_packet
+    pub fn into_packet(self) -> NymPacket {
+        self.packet
     }
 
     pub fn packet_mode(&self) -> PacketMode {
@@ -93,7 +104,8 @@ impl MixPacket {
     }
 
     // the message is formatted as follows:
-    // PACKET_MODE || FIRST_HOP || SPHINX_PACKET
+    // PACKET_MODE || FIRST_HOP || PACKET
+    // where the format of the PACKET (sphinx or outfox) is determined by the PACKET_MODE
     pub fn try_from_bytes(b: &[u8]) -> Result<Self, MixPacketFormattingError> {
         let packet_mode = match PacketMode::try_from(b[0]) {
             Ok(mode) => mode,
@@ -103,19 +115,22 @@ impl MixPacket {
         let next_hop = NymNodeRoutingAddress::try_from_bytes(&b[1..])?;
         let addr_offset = next_hop.bytes_min_len();
 
-        let sphinx_packet_data = &b[addr_offset + 1..];
-        let packet_size = sphinx_packet_data.len();
+        let packet_data = &b[addr_offset + 1..];
+        let packet_size = packet_data.len();
         if PacketSize::get_type(packet_size).is_err() {
             Err(MixPacketFormattingError::InvalidPacketSize(packet_size))
         } else {
-            let sphinx_packet = match SphinxPacket::from_bytes(sphinx_packet_data) {
-                Ok(packet) => packet,
-                Err(_) => return Err(MixPacketFormattingError::MalformedSphinxPacket),
+            let packet = if packet_mode.is_outfox() {
+                NymPacket::outfox_from_bytes(packet_data)
+                    .map_err(|_| MixPacketFormattingError::MalformedOutfoxPacket)?
+            } else {
+                NymPacket::sphinx_from_bytes(packet_data)
+                    .map_err(|_| MixPacketFormattingError::MalformedSphinxPacket)?
             };
 
             Ok(MixPacket {
                 next_hop,
-                sphinx_packet,
+                packet,
                 packet_mode,
             })
         }
@@ -124,7 +139,7 @@ impl MixPacket {
     pub fn into_bytes(self) -> Vec<u8> {
         std::iter::once(self.packet_mode as u8)
             .chain(self.next_hop.as_bytes().into_iter())
-            .chain(self.sphinx_packet.to_bytes().into_iter())
+            .chain(self.packet.to_bytes().into_iter())
             .collect()
     }
 }
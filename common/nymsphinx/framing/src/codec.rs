@@ -1,65 +1,64 @@
 // Copyright 2021-2022 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::packet::{FramedSphinxPacket, Header};
+use crate::packet::{FramedNymPacket, Header};
 use bytes::{Buf, BufMut, BytesMut};
 use nym_sphinx_params::packet_modes::InvalidPacketMode;
 use nym_sphinx_params::packet_sizes::{InvalidPacketSize, PacketSize};
-use nym_sphinx_types::Error as SphinxError;
-use nym_sphinx_types::SphinxPacket;
+use nym_sphinx_types::{NymPacket, NymPacketError};
 use std::io;
 use thiserror::Error;
 use tokio_util::codec::{Decoder, Encoder};
 
 #[derive(Error, Debug)]
-pub enum SphinxCodecError {
+pub enum NymCodecError {
     #[error("the packet size information was malformed - {0}")]
     InvalidPacketSize(#[from] InvalidPacketSize),
 
     #[error("the packet mode information was malformed - {0}")]
     InvalidPacketMode(#[from] InvalidPacketMode),
 
-    #[error("the actual sphinx packet was malformed - {0}")]
-    MalformedSphinxPacket(#[from] SphinxError),
+    #[error("the actual packet was malformed - {0}")]
+    MalformedPacket(#[from] NymPacketError),
 
     #[error("encountered an IO error - {0}")]
     IoError(#[from] io::Error),
 }
 
-impl From<SphinxCodecError> for io::Error {
-    fn from(err: SphinxCodecError) -> Self {
+impl From<NymCodecError> for io::Error {
+    fn from(err: NymCodecError) -> Self {
         match err {
-            SphinxCodecError::InvalidPacketSize(source) => {
+            NymCodecError::InvalidPacketSize(source) => {
                 io::Error::new(io::ErrorKind::InvalidInput, source)
             }
-            SphinxCodecError::InvalidPacketMode(source) => {
+            NymCodecError::InvalidPacketMode(source) => {
                 io::Error::new(io::ErrorKind::InvalidInput, source)
             }
-            SphinxCodecError::MalformedSphinxPacket(source) => {
+            NymCodecError::MalformedPacket(source) => {
                 io::Error::new(io::ErrorKind::InvalidData, source)
             }
-            SphinxCodecError::IoError(err) => err,
+            NymCodecError::IoError(err) => err,
         }
     }
 }
 
 // TODO: in the future it could be extended to have state containing symmetric encryption key
 // so that all data could be encrypted easily (alternatively we could just slap TLS)
-pub struct SphinxCodec;
+pub struct NymCodec;
 
-impl Encoder<FramedSphinxPacket> for SphinxCodec {
-    type Error = SphinxCodecError;
+impl Encoder<FramedNymPacket> for NymCodec {
+    type Error = NymCodecError;
 
-    fn encode(&mut self, item: FramedSphinxPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
+    fn encode(&mut self, item: FramedNymPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
         item.header.encode(dst);
         dst.put(item.packet.to_bytes().as_ref());
         Ok(())
     }
 }
 
-impl Decoder for SphinxCodec {
-    type Item = FramedSphinxPacket;
-    type Error = SphinxCodecError;
+impl Decoder for NymCodec {
+    type Item = FramedNymPacket;
+    type Error = NymCodecError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         if src.is_empty() {
@@ -76,23 +75,31 @@ impl Decoder for SphinxCodec {
             None => return Ok(None), // we have some data but not enough to get header back
         };
 
-        let sphinx_packet_size = header.packet_size.size();
-        let frame_len = header.size() + sphinx_packet_size;
+        let packet_size = header.packet_size.size();
+        let frame_len = header.size() + packet_size;
 
         if src.len() < frame_len {
             // we don't have enough bytes to read the rest of frame
-            src.reserve(sphinx_packet_size);
+            src.reserve(packet_size);
             return Ok(None);
         }
 
         // advance buffer past the header - at this point we have enough bytes
         src.advance(header.size());
-        let sphinx_packet_bytes = src.split_to(sphinx_packet_size);
+        let packet_bytes = src.split_to(packet_size).freeze();
 
         // here it could be debatable whether stream is corrupt or not,
         // but let's go with the safer approach and assume it is.
-        let packet = SphinxPacket::from_bytes(&sphinx_packet_bytes)?;
-        let nymsphinx_packet = FramedSphinxPacket { header, packet };
+        let packet = if header.packet_mode.is_outfox() {
+            NymPacket::outfox_from_bytes(&packet_bytes)?
+        } else {
+            NymPacket::sphinx_from_bytes(&packet_bytes)?
+        };
+        let nymsphinx_packet = FramedNymPacket {
+            header,
+            packet,
+            packet_bytes: Some(packet_bytes),
+        };
 
         // As per docs:
         // Before returning from the function, implementations should ensure that the buffer
@@ -128,13 +135,13 @@ impl Decoder for SphinxCodec {
 #[cfg(test)]
 mod packet_encoding {
     use super::*;
-    use nym_sphinx_types::builder::SphinxPacketBuilder;
+    use nym_sphinx_params::PacketMode;
     use nym_sphinx_types::{
         crypto, Delay as SphinxDelay, Destination, DestinationAddressBytes, Node, NodeAddressBytes,
         DESTINATION_ADDRESS_LENGTH, IDENTIFIER_LENGTH, NODE_ADDRESS_LENGTH,
     };
 
-    fn make_valid_sphinx_packet(size: PacketSize) -> SphinxPacket {
+    fn make_valid_route() -> ([Node; 3], Destination, Vec<SphinxDelay>) {
         let (_, node1_pk) = crypto::keygen();
         let node1 = Node::new(
             NodeAddressBytes::from_bytes([5u8; NODE_ADDRESS_LENGTH]),
@@ -161,31 +168,63 @@ mod packet_encoding {
             SphinxDelay::new_from_nanos(42),
             SphinxDelay::new_from_nanos(42),
         ];
-        SphinxPacketBuilder::new()
-            .with_payload_size(size.payload_size())
-            .build_packet(b"foomp", &route, &destination, &delays)
+        (route, destination, delays)
+    }
+
+    fn make_valid_sphinx_packet(size: PacketSize) -> NymPacket {
+        let (route, destination, delays) = make_valid_route();
+        NymPacket::sphinx_build(size.payload_size(), b"foomp", &route, &destination, &delays)
             .unwrap()
     }
 
+    fn make_valid_outfox_packet() -> NymPacket {
+        let (route, destination, delays) = make_valid_route();
+        let payload = vec![42u8; PacketSize::OutfoxRegularPacket.plaintext_size()];
+        NymPacket::outfox_build(payload, &route, &destination, &delays).unwrap()
+    }
+
     #[test]
     fn whole_packet_can_be_decoded_from_a_valid_encoded_instance() {
         let header = Default::default();
         let sphinx_packet = make_valid_sphinx_packet(Default::default());
         let sphinx_bytes = sphinx_packet.to_bytes();
 
-        let packet = FramedSphinxPacket {
+        let packet = FramedNymPacket {
             header,
             packet: sphinx_packet,
+            packet_bytes: None,
         };
 
         let mut bytes = BytesMut::new();
-        SphinxCodec.encode(packet, &mut bytes).unwrap();
-        let decoded = SphinxCodec.decode(&mut bytes).unwrap().unwrap();
+        NymCodec.encode(packet, &mut bytes).unwrap();
+        let decoded = NymCodec.decode(&mut bytes).unwrap().unwrap();
 
         assert_eq!(decoded.header, header);
+        assert_eq!(
+            decoded.packet_bytes.as_deref(),
+            Some(sphinx_bytes.as_slice())
+        );
         assert_eq!(decoded.packet.to_bytes(), sphinx_bytes)
     }
 
+    #[test]
+    fn whole_outfox_packet_can_be_decoded_from_a_valid_encoded_instance() {
+        let outfox_packet = make_valid_outfox_packet();
+        let outfox_bytes = outfox_packet.to_bytes();
+
+        let packet = FramedNymPacket::new(outfox_packet, PacketMode::Outfox, false);
+        let header = packet.header;
+        assert_eq!(header.packet_size, PacketSize::OutfoxRegularPacket);
+
+        let mut bytes = BytesMut::new();
+        NymCodec.encode(packet, &mut bytes).unwrap();
+        let decoded = NymCodec.decode(&mut bytes).unwrap().unwrap();
+
+        assert_eq!(decoded.header, header);
+        assert!(decoded.packet.is_outfox());
+        assert_eq!(decoded.packet.to_bytes(), outfox_bytes)
+    }
+
     #[cfg(test)]
     mod decode_will_allocate_enough_bytes_for_next_call {
         use super::*;
@@ -196,7 +235,7 @@ mod packet_encoding {
         fn for_empty_bytes() {
             // empty bytes should allocate for header + ack packet
             let mut empty_bytes = BytesMut::new();
-            assert!(SphinxCodec.decode(&mut empty_bytes).unwrap().is_none());
+            assert!(NymCodec.decode(&mut empty_bytes).unwrap().is_none());
             assert_eq!(
                 empty_bytes.capacity(),
                 Header::LEGACY_SIZE + PacketSize::AckPacket.size()
@@ -221,7 +260,7 @@ mod packet_encoding {
                 };
                 let mut bytes = BytesMut::new();
                 header.encode(&mut bytes);
-                assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+                assert!(NymCodec.decode(&mut bytes).unwrap().is_none());
 
                 assert_eq!(bytes.capacity(), Header::LEGACY_SIZE + packet_size.size())
             }
@@ -245,7 +284,7 @@ mod packet_encoding {
                 };
                 let mut bytes = BytesMut::new();
                 header.encode(&mut bytes);
-                assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+                assert!(NymCodec.decode(&mut bytes).unwrap().is_none());
 
                 assert_eq!(
                     bytes.capacity(),
@@ -257,18 +296,19 @@ mod packet_encoding {
         #[test]
         fn for_full_frame_with_legacy_header() {
             // if full frame is used exactly, there should be enough space for header + ack packet
-            let packet = FramedSphinxPacket {
+            let packet = FramedNymPacket {
                 header: Header {
                     packet_version: PacketVersion::Legacy,
                     packet_size: Default::default(),
                     packet_mode: Default::default(),
                 },
                 packet: make_valid_sphinx_packet(Default::default()),
+                packet_bytes: None,
             };
 
             let mut bytes = BytesMut::new();
-            SphinxCodec.encode(packet, &mut bytes).unwrap();
-            assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
+            NymCodec.encode(packet, &mut bytes).unwrap();
+            assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
             assert_eq!(
                 bytes.capacity(),
                 Header::LEGACY_SIZE + PacketSize::AckPacket.size()
@@ -278,14 +318,15 @@ mod packet_encoding {
         #[test]
         fn for_full_frame_with_versioned_header() {
             // if full frame is used exactly, there should be enough space for header + ack packet
-            let packet = FramedSphinxPacket {
+            let packet = FramedNymPacket {
                 header: Header::default(),
                 packet: make_valid_sphinx_packet(Default::default()),
+                packet_bytes: None,
             };
 
             let mut bytes = BytesMut::new();
-            SphinxCodec.encode(packet, &mut bytes).unwrap();
-            assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
+            NymCodec.encode(packet, &mut bytes).unwrap();
+            assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
             assert_eq!(
                 bytes.capacity(),
                 Header::VERSIONED_SIZE + PacketSize::AckPacket.size()
@@ -304,20 +345,21 @@ mod packet_encoding {
             ];
 
             for packet_size in packet_sizes {
-                let first_packet = FramedSphinxPacket {
+                let first_packet = FramedNymPacket {
                     header: Header {
                         packet_version: PacketVersion::Legacy,
                         packet_size: Default::default(),
                         packet_mode: Default::default(),
                     },
                     packet: make_valid_sphinx_packet(Default::default()),
+                    packet_bytes: None,
                 };
 
                 let mut bytes = BytesMut::new();
-                SphinxCodec.encode(first_packet, &mut bytes).unwrap();
+                NymCodec.encode(first_packet, &mut bytes).unwrap();
                 bytes.put_u8(packet_size as u8);
                 bytes.put_u8(PacketMode::default() as u8);
-                assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
+                assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
 
                 assert!(bytes.capacity() >= Header::LEGACY_SIZE + packet_size.size())
             }
@@ -335,17 +377,18 @@ mod packet_encoding {
             ];
 
             for packet_size in packet_sizes {
-                let first_packet = FramedSphinxPacket {
+                let first_packet = FramedNymPacket {
                     header: Header::default(),
                     packet: make_valid_sphinx_packet(Default::default()),
+                    packet_bytes: None,
                 };
 
                 let mut bytes = BytesMut::new();
-                SphinxCodec.encode(first_packet, &mut bytes).unwrap();
+                NymCodec.encode(first_packet, &mut bytes).unwrap();
                 bytes.put_u8(PacketVersion::new_versioned(123).as_u8().unwrap());
                 bytes.put_u8(packet_size as u8);
                 bytes.put_u8(PacketMode::default() as u8);
-                assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
+                assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
 
                 assert!(bytes.capacity() >= Header::VERSIONED_SIZE + packet_size.size())
             }
@@ -354,53 +397,57 @@ mod packet_encoding {
 
     #[test]
     fn can_decode_two_packets_immediately() {
-        let packet1 = FramedSphinxPacket {
+        let packet1 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            packet_bytes: None,
         };
 
-        let packet2 = FramedSphinxPacket {
+        let packet2 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            packet_bytes: None,
         };
 
         let mut bytes = BytesMut::new();
 
-        SphinxCodec.encode(packet1, &mut bytes).unwrap();
-        SphinxCodec.encode(packet2, &mut bytes).unwrap();
+        NymCodec.encode(packet1, &mut bytes).unwrap();
+        NymCodec.encode(packet2, &mut bytes).unwrap();
 
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+        assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
+        assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
+        assert!(NymCodec.decode(&mut bytes).unwrap().is_none());
     }
 
     #[test]
     fn can_decode_two_packets_in_separate_calls() {
-        let packet1 = FramedSphinxPacket {
+        let packet1 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            packet_bytes: None,
         };
 
-        let packet2 = FramedSphinxPacket {
+        let packet2 = FramedNymPacket {
             header: Header::default(),
             packet: make_valid_sphinx_packet(Default::default()),
+            packet_bytes: None,
         };
 
         let mut bytes = BytesMut::new();
         let mut bytes_tmp = BytesMut::new();
 
-        SphinxCodec.encode(packet1, &mut bytes).unwrap();
-        SphinxCodec.encode(packet2, &mut bytes_tmp).unwrap();
+        NymCodec.encode(packet1, &mut bytes).unwrap();
+        NymCodec.encode(packet2, &mut bytes_tmp).unwrap();
 
         let tmp = bytes_tmp.split_off(100);
         bytes.put(bytes_tmp);
 
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+        assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
+        assert!(NymCodec.decode(&mut bytes).unwrap().is_none());
 
         bytes.put(tmp);
 
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_some());
-        assert!(SphinxCodec.decode(&mut bytes).unwrap().is_none());
+        assert!(NymCodec.decode(&mut bytes).unwrap().is_some());
+        assert!(NymCodec.decode(&mut bytes).unwrap().is_none());
     }
 }
@@ -1,35 +1,39 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::codec::SphinxCodecError;
-use bytes::{BufMut, BytesMut};
+use crate::codec::NymCodecError;
+use bytes::{BufMut, Bytes, BytesMut};
 use nym_sphinx_params::packet_sizes::PacketSize;
 use nym_sphinx_params::packet_version::PacketVersion;
 use nym_sphinx_params::PacketMode;
-use nym_sphinx_types::SphinxPacket;
+use nym_sphinx_types::NymPacket;
 use std::convert::TryFrom;
 
-pub struct FramedSphinxPacket {
+pub struct FramedNymPacket {
     /// Contains any metadata helping receiver to handle the underlying packet.
     pub(crate) header: Header,
 
-    /// The actual SphinxPacket being sent.
-    pub(crate) packet: SphinxPacket,
+    /// The actual packet (sphinx or outfox) being sent.
+    pub(crate) packet: NymPacket,
+
+    /// The bytes the packet has been decoded from, if it has been received from the network.
+    pub(crate) packet_bytes: Option<Bytes>,
 }
 
-impl FramedSphinxPacket {
-    pub fn new(packet: SphinxPacket, packet_mode: PacketMode, use_legacy_version: bool) -> Self {
+impl FramedNymPacket {
+    pub fn new(packet: NymPacket, packet_mode: PacketMode, use_legacy_version: bool) -> Self {
         // If this fails somebody is using the library in a super incorrect way, because they
-        // already managed to somehow create a sphinx packet
+        // already managed to somehow create a sphinx (or outfox) packet
         let packet_size = PacketSize::get_type(packet.len()).unwrap();
 
-        FramedSphinxPacket {
+        FramedNymPacket {
             header: Header {
                 packet_version: PacketVersion::new(use_legacy_version),
                 packet_size,
                 packet_mode,
             },
             packet,
+            packet_bytes: None,
         }
     }
 
@@ -41,9 +45,15 @@ impl FramedSphinxPacket {
         self.header.packet_mode
     }
 
-    pub fn into_inner(self) -> SphinxPacket {
+    pub fn into_inner(self) -> NymPacket {
         self.packet
     }
+
+    /// Returns the packet alongside the bytes it has been decoded from, so that it could be
+    /// reconstructed without having to serialize it again.
+    pub fn into_parts(self) -> (NymPacket, Option<Bytes>) {
+        (self.packet, self.packet_bytes)
+    }
 }
 
 // Contains any metadata that might be useful for sending between mix nodes.
@@ -54,7 +64,7 @@ pub struct Header {
     /// Represents the wire format version used to construct this packet.
     pub(crate) packet_version: PacketVersion,
 
-    /// Represents type and consequently size of the included SphinxPacket.
+    /// Represents type and consequently size of the included packet.
     pub(crate) packet_size: PacketSize,
 
     /// Represents whether this packet is sent in a `vpn_mode` meaning it should not get delayed
@@ -95,7 +105,7 @@ impl Header {
         dst.reserve(self.packet_size.size());
     }
 
-    pub(crate) fn decode(src: &mut BytesMut) -> Result<Option<Self>, SphinxCodecError> {
+    pub(crate) fn decode(src: &mut BytesMut) -> Result<Option<Self>, NymCodecError> {
         if src.len() < Self::LEGACY_SIZE {
             // can't do anything if we don't have enough bytes - but reserve enough for the next call
             src.reserve(Self::LEGACY_SIZE);
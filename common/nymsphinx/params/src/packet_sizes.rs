@@ -3,7 +3,7 @@
 
 use crate::FRAG_ID_LEN;
 use nym_sphinx_types::header::HEADER_SIZE;
-use nym_sphinx_types::PAYLOAD_OVERHEAD_SIZE;
+use nym_sphinx_types::{OUTFOX_PACKET_OVERHEAD, PAYLOAD_OVERHEAD_SIZE};
 use std::convert::TryFrom;
 use std::str::FromStr;
 use thiserror::Error;
@@ -21,6 +21,9 @@ const EXTENDED_PACKET_SIZE_8: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + 8 *
 const EXTENDED_PACKET_SIZE_16: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + 16 * 1024;
 const EXTENDED_PACKET_SIZE_32: usize = HEADER_SIZE + PAYLOAD_OVERHEAD_SIZE + 32 * 1024;
 
+// outfox packets carry exactly the same amount of plaintext as the regular sphinx packets
+const OUTFOX_REGULAR_PACKET_SIZE: usize = OUTFOX_PACKET_OVERHEAD + 2 * 1024;
+
 #[derive(Debug, Error)]
 pub enum InvalidPacketSize {
     #[error("{received} is not a valid packet size tag")]
@@ -51,6 +54,9 @@ pub enum PacketSize {
 
     // for example for streaming fast and furious in compressed XviD quality
     ExtendedPacket16 = 5,
+
+    // regular-sized packet using the outfox packet format
+    OutfoxRegularPacket = 6,
 }
 
 impl FromStr for PacketSize {
@@ -63,6 +69,7 @@ impl FromStr for PacketSize {
             "extended8" => Ok(Self::ExtendedPacket8),
             "extended16" => Ok(Self::ExtendedPacket16),
             "extended32" => Ok(Self::ExtendedPacket32),
+            "outfox" => Ok(Self::OutfoxRegularPacket),
             s => Err(InvalidPacketSize::UnknownExtendedPacketVariant {
                 received: s.to_string(),
             }),
@@ -80,6 +87,7 @@ impl TryFrom<u8> for PacketSize {
             _ if value == (PacketSize::ExtendedPacket8 as u8) => Ok(Self::ExtendedPacket8),
             _ if value == (PacketSize::ExtendedPacket16 as u8) => Ok(Self::ExtendedPacket16),
             _ if value == (PacketSize::ExtendedPacket32 as u8) => Ok(Self::ExtendedPacket32),
+            _ if value == (PacketSize::OutfoxRegularPacket as u8) => Ok(Self::OutfoxRegularPacket),
             v => Err(InvalidPacketSize::UnknownPacketTag { received: v }),
         }
     }
@@ -93,15 +101,39 @@ impl PacketSize {
             PacketSize::ExtendedPacket8 => EXTENDED_PACKET_SIZE_8,
             PacketSize::ExtendedPacket16 => EXTENDED_PACKET_SIZE_16,
             PacketSize::ExtendedPacket32 => EXTENDED_PACKET_SIZE_32,
+            PacketSize::OutfoxRegularPacket => OUTFOX_REGULAR_PACKET_SIZE,
         }
     }
 
     pub fn plaintext_size(self) -> usize {
-        self.size() - HEADER_SIZE - PAYLOAD_OVERHEAD_SIZE
+        if self.is_outfox() {
+            self.size() - OUTFOX_PACKET_OVERHEAD
+        } else {
+            self.size() - HEADER_SIZE - PAYLOAD_OVERHEAD_SIZE
+        }
     }
 
     pub fn payload_size(self) -> usize {
-        self.size() - HEADER_SIZE
+        if self.is_outfox() {
+            // outfox does not put any additional overhead on the payload itself
+            self.plaintext_size()
+        } else {
+            self.size() - HEADER_SIZE
+        }
+    }
+
+    pub fn is_outfox(&self) -> bool {
+        matches!(self, PacketSize::OutfoxRegularPacket)
+    }
+
+    /// Returns the sphinx packet size able to carry the same amount of plaintext as this one.
+    /// Required whenever a sphinx packet has to be used regardless of the preferred format,
+    /// for example for reply SURBs.
+    pub fn as_sphinx_equivalent(self) -> Self {
+        match self {
+            PacketSize::OutfoxRegularPacket => PacketSize::RegularPacket,
+            other => other,
+        }
     }
 
     pub fn get_type(size: usize) -> Result<Self, InvalidPacketSize> {
@@ -115,6 +147,8 @@ impl PacketSize {
             Ok(PacketSize::ExtendedPacket16)
         } else if PacketSize::ExtendedPacket32.size() == size {
             Ok(PacketSize::ExtendedPacket32)
+        } else if PacketSize::OutfoxRegularPacket.size() == size {
+            Ok(PacketSize::OutfoxRegularPacket)
         } else {
             Err(InvalidPacketSize::UnknownPacketSize { received: size })
         }
@@ -122,7 +156,9 @@ impl PacketSize {
 
     pub fn is_extended_size(&self) -> bool {
         match self {
-            PacketSize::RegularPacket | PacketSize::AckPacket => false,
+            PacketSize::RegularPacket | PacketSize::AckPacket | PacketSize::OutfoxRegularPacket => {
+                false
+            }
             PacketSize::ExtendedPacket8
             | PacketSize::ExtendedPacket16
             | PacketSize::ExtendedPacket32 => true,
@@ -47,6 +47,7 @@ impl From<u8> for PacketVersion {
             n if n == PacketSize::ExtendedPacket8 as u8 => PacketVersion::Legacy,
             n if n == PacketSize::ExtendedPacket16 as u8 => PacketVersion::Legacy,
             n if n == PacketSize::ExtendedPacket32 as u8 => PacketVersion::Legacy,
+            n if n == PacketSize::OutfoxRegularPacket as u8 => PacketVersion::Legacy,
             n => PacketVersion::Versioned(n),
         }
     }
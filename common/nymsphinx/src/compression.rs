@@ -0,0 +1,312 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// Default upper bound on the size of a decompressed message, so that a tiny, maliciously
+/// crafted, payload could not exhaust the memory of the recipient.
+pub const DEFAULT_MAX_DECOMPRESSED_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    #[error("{received} is not a valid compression algorithm tag")]
+    UnknownAlgorithm { received: u8 },
+
+    #[error("the message was compressed with {algorithm} which is not supported by this client")]
+    UnsupportedAlgorithm { algorithm: MessageCompression },
+
+    #[error("the decompressed message exceeds the maximum allowed size of {max} bytes")]
+    DecompressedMessageTooLarge { max: usize },
+
+    #[error("failed to decompress the message - {0}")]
+    MalformedCompressedData(#[from] io::Error),
+}
+
+/// Compression algorithm applied to the content of a [`NymMessage`](crate::message::NymMessage)
+/// before it gets chunked.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageCompression {
+    #[default]
+    None = 0,
+    Deflate = 1,
+    Zstd = 2,
+}
+
+impl Display for MessageCompression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageCompression::None => write!(f, "no compression"),
+            MessageCompression::Deflate => write!(f, "deflate"),
+            MessageCompression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl TryFrom<u8> for MessageCompression {
+    type Error = CompressionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            _ if value == (MessageCompression::None as u8) => Ok(Self::None),
+            _ if value == (MessageCompression::Deflate as u8) => Ok(Self::Deflate),
+            _ if value == (MessageCompression::Zstd as u8) => Ok(Self::Zstd),
+            val => Err(CompressionError::UnknownAlgorithm { received: val }),
+        }
+    }
+}
+
+impl MessageCompression {
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, MessageCompression::None)
+    }
+
+    /// Attempts to compress the provided data. It returns the algorithm that was actually used
+    /// alongside the compressed data or `None` if compression is disabled or it would not have
+    /// made the data any shorter.
+    pub(crate) fn compress(self, data: &[u8]) -> Option<(MessageCompression, Vec<u8>)> {
+        let compressed = match self {
+            MessageCompression::None => return None,
+            MessageCompression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).ok()?;
+                encoder.finish().ok()?
+            }
+            #[cfg(all(feature = "zstd", not(target_arch = "wasm32")))]
+            MessageCompression::Zstd => zstd::bulk::compress(data, 0).ok()?,
+            // zstd is not available in wasm (or without the feature), so fallback to deflate instead
+            #[cfg(not(all(feature = "zstd", not(target_arch = "wasm32"))))]
+            MessageCompression::Zstd => return MessageCompression::Deflate.compress(data),
+        };
+
+        (compressed.len() < data.len()).then_some((self, compressed))
+    }
+
+    /// Decompresses the provided data making sure the result does not exceed the specified size.
+    pub(crate) fn decompress(
+        self,
+        data: &[u8],
+        max_decompressed_size: usize,
+    ) -> Result<Vec<u8>, CompressionError> {
+        match self {
+            MessageCompression::None => Ok(data.to_vec()),
+            MessageCompression::Deflate => {
+                read_bounded(DeflateDecoder::new(data), max_decompressed_size)
+            }
+            #[cfg(all(feature = "zstd", not(target_arch = "wasm32")))]
+            MessageCompression::Zstd => {
+                read_bounded(zstd::Decoder::new(data)?, max_decompressed_size)
+            }
+            #[cfg(not(all(feature = "zstd", not(target_arch = "wasm32"))))]
+            MessageCompression::Zstd => {
+                Err(CompressionError::UnsupportedAlgorithm { algorithm: self })
+            }
+        }
+    }
+}
+
+// the advertisement always has its highest bit set, so that it could never be confused
+// with the padding of the message it is attached to
+const SUPPORTED_COMPRESSION_MARKER: u8 = 0x80;
+
+/// Set of compression algorithms a client is able to decompress. Clients advertise it in
+/// the messages they send, so that their peers would only compress the messages addressed
+/// to clients that are able to read them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SupportedCompression(u8);
+
+impl SupportedCompression {
+    /// Returns the algorithms this client is able to decompress.
+    pub fn local() -> Self {
+        let supported = SupportedCompression::default().with(MessageCompression::Deflate);
+
+        #[cfg(all(feature = "zstd", not(target_arch = "wasm32")))]
+        let supported = supported.with(MessageCompression::Zstd);
+
+        supported
+    }
+
+    fn flag(algorithm: MessageCompression) -> u8 {
+        match algorithm {
+            MessageCompression::None => 0,
+            MessageCompression::Deflate => 1,
+            MessageCompression::Zstd => 1 << 1,
+        }
+    }
+
+    #[must_use]
+    pub fn with(self, algorithm: MessageCompression) -> Self {
+        SupportedCompression(self.0 | Self::flag(algorithm))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn supports(&self, algorithm: MessageCompression) -> bool {
+        self.0 & Self::flag(algorithm) == Self::flag(algorithm)
+    }
+
+    /// Chooses the algorithm for compressing messages addressed to the client with this support,
+    /// i.e. the preferred one if it's supported, deflate if it isn't, or no compression at all
+    /// if the client hasn't advertised support for either of them.
+    pub fn negotiate(&self, preferred: MessageCompression) -> MessageCompression {
+        if self.supports(preferred) {
+            preferred
+        } else if preferred.is_enabled() && self.supports(MessageCompression::Deflate) {
+            MessageCompression::Deflate
+        } else {
+            MessageCompression::None
+        }
+    }
+
+    pub(crate) fn to_advertisement(self) -> Option<u8> {
+        (!self.is_empty()).then_some(self.0 | SUPPORTED_COMPRESSION_MARKER)
+    }
+
+    pub(crate) fn from_advertisement(advertisement: u8) -> Self {
+        if advertisement & SUPPORTED_COMPRESSION_MARKER == 0 {
+            return SupportedCompression::default();
+        }
+        SupportedCompression(advertisement & !SUPPORTED_COMPRESSION_MARKER)
+    }
+}
+
+fn read_bounded<R: Read>(decoder: R, max_size: usize) -> Result<Vec<u8>, CompressionError> {
+    let mut decompressed = Vec::new();
+    // read a single byte more than allowed to detect messages exceeding the limit
+    decoder
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut decompressed)?;
+
+    if decompressed.len() > max_size {
+        Err(CompressionError::DecompressedMessageTooLarge { max: max_size })
+    } else {
+        Ok(decompressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compressible_data() -> Vec<u8> {
+        br#"{"jsonrpc":"2.0","method":"ping","params":[]}"#
+            .iter()
+            .copied()
+            .cycle()
+            .take(10_000)
+            .collect()
+    }
+
+    fn local_algorithms() -> Vec<MessageCompression> {
+        [MessageCompression::Deflate, MessageCompression::Zstd]
+            .into_iter()
+            .filter(|algorithm| SupportedCompression::local().supports(*algorithm))
+            .collect()
+    }
+
+    #[test]
+    fn compressed_data_can_be_decompressed() {
+        let data = compressible_data();
+
+        for algorithm in local_algorithms() {
+            let (used, compressed) = algorithm.compress(&data).unwrap();
+            assert_eq!(used, algorithm);
+            assert!(compressed.len() < data.len());
+            assert_eq!(used.decompress(&compressed, data.len()).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn incompressible_data_is_not_compressed() {
+        let data: Vec<u8> = (0..=255).collect();
+        assert!(MessageCompression::Deflate.compress(&data).is_none());
+        assert!(MessageCompression::Zstd.compress(&data).is_none());
+        assert!(MessageCompression::None
+            .compress(&compressible_data())
+            .is_none());
+    }
+
+    #[test]
+    fn decompression_is_bounded() {
+        let data = compressible_data();
+
+        for algorithm in local_algorithms() {
+            let (used, compressed) = algorithm.compress(&data).unwrap();
+            assert!(matches!(
+                used.decompress(&compressed, data.len() - 1),
+                Err(CompressionError::DecompressedMessageTooLarge { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn decompressing_malformed_data_fails() {
+        let garbage = vec![42u8; 100];
+        assert!(MessageCompression::Deflate
+            .decompress(&garbage, 1000)
+            .is_err());
+        assert!(MessageCompression::Zstd.decompress(&garbage, 1000).is_err());
+    }
+
+    #[test]
+    fn zstd_falls_back_to_deflate_when_unavailable() {
+        if SupportedCompression::local().supports(MessageCompression::Zstd) {
+            return;
+        }
+        let (used, _) = MessageCompression::Zstd
+            .compress(&compressible_data())
+            .unwrap();
+        assert_eq!(used, MessageCompression::Deflate);
+    }
+
+    #[test]
+    fn advertised_support_survives_round_trip() {
+        let supported = SupportedCompression::default()
+            .with(MessageCompression::Deflate)
+            .with(MessageCompression::Zstd);
+        let advertisement = supported.to_advertisement().unwrap();
+
+        // the advertisement must never be mistaken for the padding
+        assert!(advertisement > 1);
+        assert_eq!(
+            SupportedCompression::from_advertisement(advertisement),
+            supported
+        );
+        assert!(SupportedCompression::default().to_advertisement().is_none());
+        assert!(SupportedCompression::from_advertisement(0).is_empty());
+    }
+
+    #[test]
+    fn negotiation_respects_the_advertised_support() {
+        let deflate_only = SupportedCompression::default().with(MessageCompression::Deflate);
+        let both = deflate_only.with(MessageCompression::Zstd);
+        let unknown = SupportedCompression::default();
+
+        assert_eq!(
+            both.negotiate(MessageCompression::Zstd),
+            MessageCompression::Zstd
+        );
+        assert_eq!(
+            deflate_only.negotiate(MessageCompression::Zstd),
+            MessageCompression::Deflate
+        );
+        assert_eq!(
+            deflate_only.negotiate(MessageCompression::None),
+            MessageCompression::None
+        );
+        assert_eq!(
+            unknown.negotiate(MessageCompression::Deflate),
+            MessageCompression::None
+        );
+        assert_eq!(
+            unknown.negotiate(MessageCompression::Zstd),
+            MessageCompression::None
+        );
+    }
+}
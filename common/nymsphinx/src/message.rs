@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chunking;
+use crate::compression::{CompressionError, MessageCompression, SupportedCompression};
 use nym_crypto::asymmetric::encryption;
 use nym_crypto::Digest;
 use nym_sphinx_addressing::clients::Recipient;
@@ -29,8 +30,18 @@ pub enum NymMessageError {
 
     #[error("Received empty message for deserialization")]
     EmptyMessage,
+
+    #[error(transparent)]
+    InvalidCompression(#[from] CompressionError),
 }
 
+// the first byte of each message holds its type in the lower 4 bits and the compression algorithm
+// used for its content in the upper 4 bits. Since uncompressed messages have those bits unset,
+// they look exactly the same as before compression got introduced and thus older clients
+// are still able to parse them.
+const MESSAGE_TYPE_MASK: u8 = 0x0f;
+const COMPRESSION_SHIFT: u8 = 4;
+
 #[repr(u8)]
 enum NymMessageType {
     Plain = 0,
@@ -105,6 +116,17 @@ impl NymMessage {
         }
     }
 
+    /// Checks whether this is a reply that doesn't carry any data, such as a request for more reply
+    /// SURBs or a gateway change notice. Those are always expected to fit in a single reply SURB.
+    pub fn is_reply_control_message(&self) -> bool {
+        match self {
+            NymMessage::Reply(reply_msg) => {
+                !matches!(reply_msg.content, ReplyMessageContent::Data { .. })
+            }
+            _ => false,
+        }
+    }
+
     pub fn into_inner_data(self) -> Vec<u8> {
         match self {
             NymMessage::Plain(data) => data,
@@ -136,29 +158,48 @@ impl NymMessage {
     }
 
     // the message is in the format of:
-    // typ || msg
-    fn into_bytes(self) -> Vec<u8> {
-        let typ = self.typ();
-
-        std::iter::once(typ as u8)
-            .chain(self.inner_bytes())
-            .collect()
+    // compression || typ || msg
+    // where msg might be compressed with the specified algorithm if it made it any shorter
+    fn into_bytes(self, compression: MessageCompression) -> Vec<u8> {
+        let typ = self.typ() as u8;
+        let inner_bytes = self.inner_bytes();
+
+        match compression.compress(&inner_bytes) {
+            Some((used_compression, compressed)) => {
+                std::iter::once(typ | (used_compression as u8) << COMPRESSION_SHIFT)
+                    .chain(compressed)
+                    .collect()
+            }
+            None => std::iter::once(typ).chain(inner_bytes).collect(),
+        }
     }
 
-    fn try_from_bytes(bytes: &[u8], num_mix_hops: u8) -> Result<Self, NymMessageError> {
+    fn try_from_bytes(
+        bytes: &[u8],
+        num_mix_hops: u8,
+        max_decompressed_size: usize,
+    ) -> Result<Self, NymMessageError> {
         if bytes.is_empty() {
             return Err(NymMessageError::EmptyMessage);
         }
 
-        let typ_tag = NymMessageType::try_from(bytes[0])?;
+        let compression = MessageCompression::try_from(bytes[0] >> COMPRESSION_SHIFT)?;
+        let typ_tag = NymMessageType::try_from(bytes[0] & MESSAGE_TYPE_MASK)?;
+
+        let decompressed;
+        let content: &[u8] = if compression.is_enabled() {
+            decompressed = compression.decompress(&bytes[1..], max_decompressed_size)?;
+            &decompressed
+        } else {
+            &bytes[1..]
+        };
+
         match typ_tag {
-            NymMessageType::Plain => Ok(NymMessage::Plain(bytes[1..].to_vec())),
+            NymMessageType::Plain => Ok(NymMessage::Plain(content.to_vec())),
             NymMessageType::Repliable => Ok(NymMessage::Repliable(
-                RepliableMessage::try_from_bytes(&bytes[1..], num_mix_hops)?,
+                RepliableMessage::try_from_bytes(content, num_mix_hops)?,
             )),
-            NymMessageType::Reply => Ok(NymMessage::Reply(ReplyMessage::try_from_bytes(
-                &bytes[1..],
-            )?)),
+            NymMessageType::Reply => Ok(NymMessage::Reply(ReplyMessage::try_from_bytes(content)?)),
         }
     }
 
@@ -183,17 +224,40 @@ impl NymMessage {
     /// Pads the message so that after it gets chunked, it will occupy exactly N sphinx packets.
     /// Produces new_message = message || 1 || 0000....
     pub fn pad_to_full_packet_lengths(self, plaintext_per_packet: usize) -> PaddedMessage {
-        let bytes = self.into_bytes();
+        self.compress_and_pad_to_full_packet_lengths(
+            plaintext_per_packet,
+            MessageCompression::None,
+            SupportedCompression::default(),
+        )
+    }
+
+    /// Compresses the content of the message with the provided algorithm, if it makes it shorter,
+    /// and pads it so that after it gets chunked, it will occupy exactly N sphinx packets.
+    /// If any compression is supported by the sender, it is advertised in the last byte
+    /// of the padding, i.e. new_message = message || 1 || 0000... || supported_compression,
+    /// which is ignored by the clients that are not aware of it.
+    /// Note that the recipient has to understand the compression flag in order to parse the message.
+    pub fn compress_and_pad_to_full_packet_lengths(
+        self,
+        plaintext_per_packet: usize,
+        compression: MessageCompression,
+        supported_compression: SupportedCompression,
+    ) -> PaddedMessage {
+        let bytes = self.into_bytes(compression);
+        let advertisement = supported_compression.to_advertisement();
 
         // 1 is added as there will always have to be at least a single byte of padding (1) added
         // to be able to later distinguish the actual padding from the underlying message
-        let (_, space_left) =
-            chunking::number_of_required_fragments(bytes.len() + 1, plaintext_per_packet);
+        let (_, space_left) = chunking::number_of_required_fragments(
+            bytes.len() + 1 + usize::from(advertisement.is_some()),
+            plaintext_per_packet,
+        );
 
         bytes
             .into_iter()
             .chain(std::iter::once(1u8))
             .chain(std::iter::repeat(0u8).take(space_left))
+            .chain(advertisement)
             .collect::<Vec<_>>()
             .into()
     }
@@ -219,12 +283,50 @@ impl PaddedMessage {
             .collect()
     }
 
-    // reverse of NymMessage::pad_to_full_packet_lengths
-    pub fn remove_padding(self, num_mix_hops: u8) -> Result<NymMessage, NymMessageError> {
+    /// Splits the padded message into [`Fragment`]s, exactly like [`Self::split_into_fragments`],
+    /// but additionally accompanies each of the sets with parity [`Fragment`]s allowing the
+    /// recipient to recover it despite some of the packets getting lost.
+    /// Note that the serialized parity fragments are [`chunking::fec::PARITY_FRAGMENT_OVERHEAD`]
+    /// bytes longer than the data ones.
+    pub fn split_into_fragments_with_parity<R: Rng>(
+        self,
+        rng: &mut R,
+        plaintext_per_packet: usize,
+        redundancy: f64,
+    ) -> Vec<Fragment> {
+        chunking::split_into_sets(rng, &self.0, plaintext_per_packet)
+            .into_iter()
+            .flat_map(|fragment_set| {
+                let parity =
+                    chunking::fec::generate_parity_fragments(rng, &fragment_set, redundancy);
+                fragment_set.into_iter().chain(parity)
+            })
+            .collect()
+    }
+
+    // reverse of NymMessage::compress_and_pad_to_full_packet_lengths
+    // alongside the message, it returns the compression support advertised by its sender
+    // (which is empty if the sender has not advertised any)
+    pub fn remove_padding(
+        self,
+        num_mix_hops: u8,
+        max_decompressed_size: usize,
+    ) -> Result<(NymMessage, SupportedCompression), NymMessageError> {
         // we are looking for first occurrence of 1 in the tail and we get its index
         if let Some(padding_end) = self.0.iter().rposition(|b| *b == 1) {
+            // the advertisement (if any) is the very last byte after the zero padding
+            let supported_compression = self.0[padding_end + 1..]
+                .last()
+                .map(|advertisement| SupportedCompression::from_advertisement(*advertisement))
+                .unwrap_or_default();
+
             // and now we only take bytes until that point (but not including it)
-            NymMessage::try_from_bytes(&self.0[..padding_end], num_mix_hops)
+            let message = NymMessage::try_from_bytes(
+                &self.0[..padding_end],
+                num_mix_hops,
+                max_decompressed_size,
+            )?;
+            Ok((message, supported_compression))
         } else {
             Err(NymMessageError::InvalidMessagePadding)
         }
@@ -236,3 +338,61 @@ impl From<Vec<u8>> for PaddedMessage {
         PaddedMessage(bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT_PER_PACKET: usize = 100;
+
+    fn compressible_data() -> Vec<u8> {
+        b"hello world ".iter().copied().cycle().take(1000).collect()
+    }
+
+    #[test]
+    fn advertised_compression_support_survives_padding() {
+        let supported = SupportedCompression::default().with(MessageCompression::Deflate);
+        let padded = NymMessage::new_plain(compressible_data())
+            .compress_and_pad_to_full_packet_lengths(
+                PLAINTEXT_PER_PACKET,
+                MessageCompression::Deflate,
+                supported,
+            );
+        // the advertisement doesn't leave any space unused in the last packet
+        let (_, space_left) =
+            chunking::number_of_required_fragments(padded.0.len(), PLAINTEXT_PER_PACKET);
+        assert_eq!(space_left, 0);
+
+        let (message, advertised) = padded.remove_padding(3, 1024 * 1024).unwrap();
+        assert_eq!(message.into_inner_data(), compressible_data());
+        assert_eq!(advertised, supported);
+    }
+
+    #[test]
+    fn messages_without_advertisement_report_no_compression_support() {
+        let padded = NymMessage::new_plain(compressible_data())
+            .pad_to_full_packet_lengths(PLAINTEXT_PER_PACKET);
+
+        let (message, advertised) = padded.remove_padding(3, 1024 * 1024).unwrap();
+        assert_eq!(message.into_inner_data(), compressible_data());
+        assert!(advertised.is_empty());
+    }
+
+    #[test]
+    fn advertisement_does_not_affect_older_parsers() {
+        // the older clients simply look for the last 1 and ignore everything that follows it
+        for len in 0..2 * PLAINTEXT_PER_PACKET {
+            let data = vec![42u8; len];
+            let padded = NymMessage::new_plain(data.clone())
+                .compress_and_pad_to_full_packet_lengths(
+                    PLAINTEXT_PER_PACKET,
+                    MessageCompression::None,
+                    SupportedCompression::local(),
+                );
+
+            let padding_end = padded.0.iter().rposition(|b| *b == 1).unwrap();
+            assert_eq!(padded.0[0], NymMessageType::Plain as u8);
+            assert_eq!(&padded.0[1..padding_end], data.as_slice());
+        }
+    }
+}
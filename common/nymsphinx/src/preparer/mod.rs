@@ -1,6 +1,7 @@
 // Copyright 2021-2022 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::compression::{MessageCompression, SupportedCompression};
 use crate::message::NymMessage;
 use crate::NymsphinxPayloadBuilder;
 use nym_sphinx_acknowledgements::surb_ack::SurbAck;
@@ -8,12 +9,12 @@ use nym_sphinx_acknowledgements::AckKey;
 use nym_sphinx_addressing::clients::Recipient;
 use nym_sphinx_addressing::nodes::NymNodeRoutingAddress;
 use nym_sphinx_anonymous_replies::reply_surb::ReplySurb;
+use nym_sphinx_chunking::fec::PARITY_FRAGMENT_OVERHEAD;
 use nym_sphinx_chunking::fragment::{Fragment, FragmentIdentifier};
 use nym_sphinx_forwarding::packet::MixPacket;
 use nym_sphinx_params::packet_sizes::PacketSize;
-use nym_sphinx_params::DEFAULT_NUM_MIX_HOPS;
-use nym_sphinx_types::builder::SphinxPacketBuilder;
-use nym_sphinx_types::{delays, Delay};
+use nym_sphinx_params::{PacketMode, DEFAULT_NUM_MIX_HOPS};
+use nym_sphinx_types::{delays, Delay, NymPacket};
 use nym_topology::{NymTopology, NymTopologyError};
 use rand::{CryptoRng, Rng};
 use std::convert::TryFrom;
@@ -29,7 +30,7 @@ pub struct PreparedFragment {
 
     /// Indicates all data required to serialize and forward the data. It contains the actual
     /// address of the node to which the message should be sent, the actual 'chunk' of the message
-    /// going through the mix network and also the 'mode' of the packet, i.e. VPN or Mix.
+    /// going through the mix network and also the 'mode' of the packet, i.e. Mix or Outfox.
     pub mix_packet: MixPacket,
 
     /// Identifier to uniquely identify a fragment.
@@ -45,7 +46,8 @@ pub struct MessagePreparer<R> {
     /// Instance of a cryptographically secure random number generator.
     rng: R,
 
-    /// Size of the target [`SphinxPacket`] into which the underlying is going to get split.
+    /// Size (and consequently the format) of the target packet into which the underlying
+    /// message is going to get split.
     packet_size: PacketSize,
 
     /// Address of this client which also represent an address to which all acknowledgements
@@ -61,6 +63,13 @@ pub struct MessagePreparer<R> {
     /// Number of mix hops each packet ('real' message, ack, reply) is expected to take.
     /// Note that it does not include gateway hops.
     num_mix_hops: u8,
+
+    /// Ratio of parity to data fragments attached to the messages so that the recipient could
+    /// reconstruct them despite losing some of the packets. Forward error correction is disabled if 0.
+    fec_redundancy: f64,
+
+    /// Compression algorithms this client is able to decompress, advertised in every prepared message.
+    supported_compression: SupportedCompression,
 }
 
 impl<R> MessagePreparer<R>
@@ -80,21 +89,47 @@ where
             average_packet_delay,
             average_ack_delay,
             num_mix_hops: DEFAULT_NUM_MIX_HOPS,
+            fec_redundancy: 0.0,
+            supported_compression: SupportedCompression::default(),
         }
     }
 
     /// Allows setting non-default number of expected mix hops in the network.
+    /// Note that outfox packets are only supported with the default number of hops.
     pub fn with_mix_hops(mut self, hops: u8) -> Self {
         self.num_mix_hops = hops;
         self
     }
 
-    /// Allows setting non-default size of the sphinx packets sent out.
+    /// Allows setting non-default size of the packets sent out.
+    /// If an outfox packet size is chosen, all 'real' messages (apart from replies) will use
+    /// the outfox packet format.
     pub fn with_custom_real_message_packet_size(mut self, packet_size: PacketSize) -> Self {
         self.packet_size = packet_size;
         self
     }
 
+    /// Allows attaching parity fragments to the messages, in the provided ratio to the data fragments,
+    /// so that the recipient could reconstruct them despite losing some of the packets.
+    /// Note that each parity fragment of a reply uses up another reply SURB, and that parity is never
+    /// attached to control replies (as they have to fit in a single reply SURB) nor to messages
+    /// using the outfox packet format (as the size of those packets depends on the length of their payload).
+    pub fn with_forward_error_correction(mut self, redundancy: f64) -> Self {
+        self.fec_redundancy = redundancy;
+        self
+    }
+
+    /// Allows advertising the provided compression support in every prepared message, so that
+    /// the recipients would know they are allowed to compress the messages they send back.
+    /// The advertisement is ignored by the clients that are not aware of it.
+    pub fn with_supported_compression(
+        mut self,
+        supported_compression: SupportedCompression,
+    ) -> Self {
+        self.supported_compression = supported_compression;
+        self
+    }
+
     /// Overwrites existing sender address with the provided value.
     pub fn set_sender_address(&mut self, sender_address: Recipient) {
         self.sender_address = sender_address;
@@ -150,10 +185,14 @@ where
         let packet_payload = NymsphinxPayloadBuilder::new(fragment, surb_ack)
             .build_reply(reply_surb.encryption_key());
 
+        // reply SURBs are inherently sphinx-based, so if we're using outfox, we have to fallback
+        // to the sphinx packet carrying the same amount of data
+        let packet_size = self.packet_size.as_sphinx_equivalent();
+
         // the unwrap here is fine as the failures can only originate from attempting to use invalid payload lenghts
         // and we just very carefully constructed a (presumably) valid one
         let (sphinx_packet, first_hop_address) = reply_surb
-            .apply_surb(packet_payload, Some(self.packet_size))
+            .apply_surb(packet_payload, Some(packet_size))
             .unwrap();
 
         Ok(PreparedFragment {
@@ -161,18 +200,19 @@ where
             // well as the total delay of the ack packet.
             // we don't know the delays inside the reply surbs so we use best-effort estimation from our poisson distribution
             total_delay: expected_forward_delay + ack_delay,
-            mix_packet: MixPacket::new(first_hop_address, sphinx_packet, Default::default()),
+            mix_packet: MixPacket::new(first_hop_address, sphinx_packet.into(), PacketMode::Mix),
             fragment_identifier,
         })
     }
 
-    /// Tries to convert this [`Fragment`] into a [`SphinxPacket`] that can be sent through the Nym mix-network,
+    /// Tries to convert this [`Fragment`] into a [`NymPacket`] that can be sent through the Nym mix-network,
     /// such that it contains required SURB-ACK and public component of the ephemeral key used to
     /// derive the shared key.
     /// Also all the data, apart from the said public component, is encrypted with an ephemeral shared key.
     /// This method can fail if the provided network topology is invalid.
-    /// It returns total expected delay as well as the [`SphinxPacket`] (including first hop address)
+    /// It returns total expected delay as well as the [`NymPacket`] (including first hop address)
     /// to be sent through the network.
+    /// Depending on the configured [`PacketSize`], the packet is going to use either sphinx or outfox format.
     ///
     /// The procedure is as follows:
     /// For each fragment:
@@ -210,12 +250,23 @@ where
         // including set of delays
         let delays = delays::generate_from_average_duration(route.len(), self.average_packet_delay);
 
-        // create the actual sphinx packet here. With valid route and correct payload size,
+        // create the actual packet here. With valid route and correct payload size,
         // there's absolutely no reason for this call to fail.
-        let sphinx_packet = SphinxPacketBuilder::new()
-            .with_payload_size(self.packet_size.payload_size())
-            .build_packet(packet_payload, &route, &destination, &delays)
+        let (packet, packet_mode) = if self.packet_size.is_outfox() {
+            let packet =
+                NymPacket::outfox_build(packet_payload, &route, &destination, &delays).unwrap();
+            (packet, PacketMode::Outfox)
+        } else {
+            let packet = NymPacket::sphinx_build(
+                self.packet_size.payload_size(),
+                packet_payload,
+                &route,
+                &destination,
+                &delays,
+            )
             .unwrap();
+            (packet, PacketMode::Mix)
+        };
 
         // from the previously constructed route extract the first hop
         let first_hop_address =
@@ -226,7 +277,7 @@ where
             // well as the total delay of the ack packet.
             // note that the last hop of the packet is a gateway that does not do any delays
             total_delay: delays.iter().take(delays.len() - 1).sum::<Delay>() + ack_delay,
-            mix_packet: MixPacket::new(first_hop_address, sphinx_packet, Default::default()),
+            mix_packet: MixPacket::new(first_hop_address, packet, packet_mode),
             fragment_identifier,
         })
     }
@@ -249,10 +300,45 @@ where
     }
 
     pub fn pad_and_split_message(&mut self, message: NymMessage) -> Vec<Fragment> {
+        self.compress_pad_and_split_message(message, MessageCompression::None)
+    }
+
+    /// Compresses the content of the message with the provided algorithm, if it makes it shorter,
+    /// before padding and splitting it exactly like [`Self::pad_and_split_message`].
+    /// Note that the recipient has to understand the compression flag in the message header
+    /// in order to parse it, so it should only be used for the recipients that have advertised it.
+    pub fn compress_pad_and_split_message(
+        &mut self,
+        message: NymMessage,
+        compression: MessageCompression,
+    ) -> Vec<Fragment> {
         let plaintext_per_packet = message.available_plaintext_per_packet(self.packet_size);
 
+        let use_fec = self.fec_redundancy > 0.0
+            && !self.packet_size.is_outfox()
+            && !message.is_reply_control_message();
+        if use_fec {
+            // make the data fragments slightly shorter so that the parity would also fit in the packets
+            let data_plaintext_per_packet = plaintext_per_packet - PARITY_FRAGMENT_OVERHEAD;
+            return message
+                .compress_and_pad_to_full_packet_lengths(
+                    data_plaintext_per_packet,
+                    compression,
+                    self.supported_compression,
+                )
+                .split_into_fragments_with_parity(
+                    &mut self.rng,
+                    data_plaintext_per_packet,
+                    self.fec_redundancy,
+                );
+        }
+
         message
-            .pad_to_full_packet_lengths(plaintext_per_packet)
+            .compress_and_pad_to_full_packet_lengths(
+                plaintext_per_packet,
+                compression,
+                self.supported_compression,
+            )
             .split_into_fragments(&mut self.rng, plaintext_per_packet)
     }
 }
@@ -1,6 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::compression::{SupportedCompression, DEFAULT_MAX_DECOMPRESSED_MESSAGE_SIZE};
 use crate::message::{NymMessage, NymMessageError, PaddedMessage, PlainMessage};
 use nym_crypto::aes::cipher::{KeyIvInit, StreamCipher};
 use nym_crypto::asymmetric::encryption;
@@ -27,6 +28,10 @@ pub struct ReconstructedMessage {
     /// Optional ephemeral sender tag indicating pseudo-identity of the party who sent us the message
     /// (alongside any reply SURBs)
     pub sender_tag: Option<AnonymousSenderTag>,
+
+    /// Optional identifier assigned to the message by the persistent storage of the client.
+    /// If present, the message is going to be redelivered until it has been explicitly acknowledged.
+    pub delivery_id: Option<u64>,
 }
 
 impl From<ReconstructedMessage> for (Vec<u8>, Option<AnonymousSenderTag>) {
@@ -40,6 +45,7 @@ impl ReconstructedMessage {
         Self {
             message,
             sender_tag: Some(sender_tag),
+            delivery_id: None,
         }
     }
 
@@ -53,6 +59,7 @@ impl From<PlainMessage> for ReconstructedMessage {
         ReconstructedMessage {
             message,
             sender_tag: None,
+            delivery_id: None,
         }
     }
 }
@@ -81,6 +88,9 @@ pub struct MessageReceiver {
     /// Number of mix hops each packet ('real' message, ack, reply) is expected to take.
     /// Note that it does not include gateway hops.
     num_mix_hops: u8,
+
+    /// Maximum size of the reconstructed message after reversing its compression (if any).
+    max_decompressed_message_size: usize,
 }
 
 impl MessageReceiver {
@@ -95,6 +105,13 @@ impl MessageReceiver {
         self
     }
 
+    /// Allows setting non-default upper bound on the size of decompressed messages.
+    #[must_use]
+    pub fn with_max_decompressed_message_size(mut self, max_size: usize) -> Self {
+        self.max_decompressed_message_size = max_size;
+        self
+    }
+
     fn decrypt_raw_message<C>(&self, message: &mut [u8], key: &CipherKey<C>)
     where
         C: StreamCipher + KeyIvInit,
@@ -149,18 +166,24 @@ impl MessageReceiver {
 
     /// Inserts given [`Fragment`] into the reconstructor.
     /// If it was last remaining [`Fragment`] for the original message, the message is reconstructed
-    /// and returned alongside all (if applicable) set ids used in the message.
+    /// (and decompressed, if the sender has compressed it) and returned alongside all
+    /// (if applicable) set ids used in the message.
     ///
     /// # Returns:
     /// - The reconstructed message alongside optional reply SURB,
+    /// - Compression algorithms the sender of the message is able to decompress,
     /// - List of ids of all the [`Set`]s used during reconstruction to detect stale retransmissions.
     pub fn insert_new_fragment(
         &mut self,
         fragment: Fragment,
-    ) -> Result<Option<(NymMessage, Vec<i32>)>, MessageRecoveryError> {
+    ) -> Result<Option<(NymMessage, SupportedCompression, Vec<i32>)>, MessageRecoveryError> {
         if let Some((message, used_sets)) = self.reconstructor.insert_new_fragment(fragment) {
-            match PaddedMessage::new_reconstructed(message).remove_padding(self.num_mix_hops) {
-                Ok(message) => Ok(Some((message, used_sets))),
+            match PaddedMessage::new_reconstructed(message)
+                .remove_padding(self.num_mix_hops, self.max_decompressed_message_size)
+            {
+                Ok((message, supported_compression)) => {
+                    Ok(Some((message, supported_compression, used_sets)))
+                }
                 Err(err) => Err(MessageRecoveryError::MalformedReconstructedMessage {
                     source: err,
                     used_sets,
@@ -177,6 +200,7 @@ impl Default for MessageReceiver {
         MessageReceiver {
             reconstructor: Default::default(),
             num_mix_hops: DEFAULT_NUM_MIX_HOPS,
+            max_decompressed_message_size: DEFAULT_MAX_DECOMPRESSED_MESSAGE_SIZE,
         }
     }
 }
@@ -211,6 +235,7 @@ mod message_receiver {
                     "B3GzG62aXAZNg14RoMCp3BhELNBrySLr2JqrwyfYFzRc",
                 )
                 .unwrap(),
+                upcoming_sphinx_key: None,
                 layer: Layer::One,
                 version: "0.8.0-dev".to_string(),
             }],
@@ -231,6 +256,7 @@ mod message_receiver {
                     "5Z1VqYwM2xeKxd8H7fJpGWasNiDFijYBAee7MErkZ5QT",
                 )
                 .unwrap(),
+                upcoming_sphinx_key: None,
                 layer: Layer::Two,
                 version: "0.8.0-dev".to_string(),
             }],
@@ -251,6 +277,7 @@ mod message_receiver {
                     "9EyjhCggr2QEA2nakR88YHmXgpy92DWxoe2draDRkYof",
                 )
                 .unwrap(),
+                upcoming_sphinx_key: None,
                 layer: Layer::Three,
                 version: "0.8.0-dev".to_string(),
             }],
@@ -274,6 +301,7 @@ mod message_receiver {
                     "EB42xvMFMD5rUCstE2CDazgQQJ22zLv8SPm1Luxni44c",
                 )
                 .unwrap(),
+                upcoming_sphinx_key: None,
                 version: "0.8.0-dev".to_string(),
             }],
         )
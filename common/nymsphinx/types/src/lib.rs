@@ -1,6 +1,10 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use sphinx::packet::builder::SphinxPacketBuilder;
+use std::convert::TryFrom;
+use thiserror::Error;
+
 // re-exporting types and constants available in sphinx
 pub use sphinx::{
     constants::{
@@ -13,5 +17,113 @@ pub use sphinx::{
     payload::{Payload, PAYLOAD_OVERHEAD_SIZE},
     route::{Destination, DestinationAddressBytes, Node, NodeAddressBytes, SURBIdentifier},
     surb::{SURBMaterial, SURB},
-    Error, ProcessedPacket, Result, SphinxPacket,
+    Error, ErrorKind, ProcessedPacket, Result, SphinxPacket,
+};
+
+// re-exporting types and constants available in outfox
+pub use nym_outfox::{
+    constants::{OUTFOX_PACKET_OVERHEAD, ROUTING_INFORMATION_LENGTH as OUTFOX_ROUTING_LENGTH},
+    error::OutFoxError,
+    packet::{OutfoxHop, OutfoxPacket, OutfoxRoutingInformation},
 };
+
+#[derive(Error, Debug)]
+pub enum NymPacketError {
+    #[error("Sphinx error: {0}")]
+    Sphinx(#[from] Error),
+
+    #[error("Outfox error: {0}")]
+    Outfox(#[from] OutFoxError),
+}
+
+/// A packet that can be sent through the mix network, using one of the supported packet formats.
+pub enum NymPacket {
+    Sphinx(SphinxPacket),
+    Outfox(OutfoxPacket),
+}
+
+impl From<SphinxPacket> for NymPacket {
+    fn from(packet: SphinxPacket) -> Self {
+        NymPacket::Sphinx(packet)
+    }
+}
+
+impl From<OutfoxPacket> for NymPacket {
+    fn from(packet: OutfoxPacket) -> Self {
+        NymPacket::Outfox(packet)
+    }
+}
+
+impl NymPacket {
+    pub fn sphinx_build<M: AsRef<[u8]>>(
+        payload_size: usize,
+        message: M,
+        route: &[Node],
+        destination: &Destination,
+        delays: &[Delay],
+    ) -> std::result::Result<NymPacket, NymPacketError> {
+        Ok(NymPacket::Sphinx(
+            SphinxPacketBuilder::new()
+                .with_payload_size(payload_size)
+                .build_packet(message, route, destination, delays)?,
+        ))
+    }
+
+    /// Builds an outfox packet with the same routing semantics as a sphinx packet, i.e.
+    /// each node on the route learns the address of the next hop and the delay it should apply,
+    /// whilst the final node learns the address of the destination.
+    pub fn outfox_build<M: AsRef<[u8]>>(
+        message: M,
+        route: &[Node],
+        destination: &Destination,
+        delays: &[Delay],
+    ) -> std::result::Result<NymPacket, NymPacketError> {
+        let hops = route
+            .iter()
+            .zip(delays.iter())
+            .enumerate()
+            .map(|(i, (node, delay))| {
+                let next_address = match route.get(i + 1) {
+                    Some(next_hop) => *next_hop.address.as_bytes_ref(),
+                    None => *destination.address.as_bytes_ref(),
+                };
+                OutfoxHop::new(*node.pub_key.as_bytes(), next_address, delay.to_nanos())
+            })
+            .collect::<Vec<_>>();
+
+        Ok(NymPacket::Outfox(OutfoxPacket::build(
+            message.as_ref(),
+            &hops,
+        )?))
+    }
+
+    pub fn sphinx_from_bytes(bytes: &[u8]) -> std::result::Result<NymPacket, NymPacketError> {
+        Ok(NymPacket::Sphinx(SphinxPacket::from_bytes(bytes)?))
+    }
+
+    pub fn outfox_from_bytes(bytes: &[u8]) -> std::result::Result<NymPacket, NymPacketError> {
+        Ok(NymPacket::Outfox(OutfoxPacket::try_from(bytes)?))
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            NymPacket::Sphinx(packet) => packet.len(),
+            NymPacket::Outfox(packet) => packet.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_outfox(&self) -> bool {
+        matches!(self, NymPacket::Outfox(_))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            NymPacket::Sphinx(packet) => packet.to_bytes(),
+            NymPacket::Outfox(packet) => packet.to_bytes(),
+        }
+    }
+}
@@ -1,11 +1,12 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::traits::{PemStorableKey, PemStorableKeyPair};
+use crate::traits::{KeyCipher, PemStorableKey, PemStorableKeyPair};
 use pem::{self, Pem};
 use std::fs::File;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
 
 pub mod traits;
 
@@ -40,12 +41,45 @@ where
     store_key(keypair.private_key(), &paths.private_key_path)
 }
 
+/// Loads the keypair whose private key has been stored with [`store_keypair_encrypted`].
+pub fn load_keypair_encrypted<T, C>(paths: &KeyPairPath, cipher: &C) -> io::Result<T>
+where
+    T: PemStorableKeyPair,
+    C: KeyCipher,
+{
+    let private = load_encrypted_key::<T::PrivatePemKey, _>(&paths.private_key_path, cipher)?;
+    let public = load_key::<T::PublicPemKey>(&paths.public_key_path)?;
+    Ok(T::from_keys(private, public))
+}
+
+/// Stores the keypair with its private key encrypted. The public key is kept in plaintext.
+pub fn store_keypair_encrypted<T, C>(keypair: &T, paths: &KeyPairPath, cipher: &C) -> io::Result<()>
+where
+    T: PemStorableKeyPair,
+    C: KeyCipher,
+{
+    store_key(keypair.public_key(), &paths.public_key_path)?;
+    store_encrypted_key(keypair.private_key(), &paths.private_key_path, cipher)
+}
+
+// encrypted keys are stored under a distinct tag so that they would never get mistaken for plaintext ones
+fn encrypted_pem_type(pem_type: &str) -> String {
+    format!("ENCRYPTED {pem_type}")
+}
+
 pub fn load_key<T>(path: &Path) -> io::Result<T>
 where
     T: PemStorableKey,
 {
     let key_pem = read_pem_file(path)?;
 
+    if encrypted_pem_type(T::pem_type()) == key_pem.tag {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "the key is encrypted - a passphrase is required to load it",
+        ));
+    }
+
     if T::pem_type() != key_pem.tag {
         return Err(io::Error::new(
             io::ErrorKind::Other,
@@ -68,6 +102,35 @@ where
     write_pem_file(path, key.to_bytes(), T::pem_type())
 }
 
+pub fn load_encrypted_key<T, C>(path: &Path, cipher: &C) -> io::Result<T>
+where
+    T: PemStorableKey,
+    C: KeyCipher,
+{
+    let key_pem = read_pem_file(path)?;
+
+    if encrypted_pem_type(T::pem_type()) != key_pem.tag {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "unexpected encrypted key pem tag",
+        ));
+    }
+
+    let plaintext = cipher.decrypt(&key_pem.contents)?;
+    T::from_bytes(&plaintext)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+}
+
+pub fn store_encrypted_key<T, C>(key: &T, path: &Path, cipher: &C) -> io::Result<()>
+where
+    T: PemStorableKey,
+    C: KeyCipher,
+{
+    let plaintext = Zeroizing::new(key.to_bytes());
+    let ciphertext = cipher.encrypt(&plaintext)?;
+    write_pem_file(path, ciphertext, &encrypted_pem_type(T::pem_type()))
+}
+
 fn read_pem_file(filepath: &Path) -> io::Result<Pem> {
     let mut pem_bytes = File::open(filepath)?;
     let mut buf = Vec::new();
@@ -106,3 +169,136 @@ fn write_pem_file(filepath: &Path, data: Vec<u8>, tag: &str) -> io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::{self, Display, Formatter};
+
+    #[derive(Debug, PartialEq)]
+    struct DummyKey(Vec<u8>);
+
+    #[derive(Debug)]
+    struct DummyKeyError;
+
+    impl Display for DummyKeyError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "empty key")
+        }
+    }
+
+    impl std::error::Error for DummyKeyError {}
+
+    impl PemStorableKey for DummyKey {
+        type Error = DummyKeyError;
+
+        fn pem_type() -> &'static str {
+            "DUMMY KEY"
+        }
+
+        fn to_bytes(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self, Self::Error> {
+            if bytes.is_empty() {
+                return Err(DummyKeyError);
+            }
+            Ok(DummyKey(bytes.to_vec()))
+        }
+    }
+
+    // xors the data with the key and appends a checksum, so that any modifications would get detected
+    struct XorCipher(u8);
+
+    impl KeyCipher for XorCipher {
+        fn encrypt(&self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+            let checksum = plaintext.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+            Ok(plaintext
+                .iter()
+                .chain(std::iter::once(&checksum))
+                .map(|b| b ^ self.0)
+                .collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> io::Result<Zeroizing<Vec<u8>>> {
+            let mut plaintext =
+                Zeroizing::new(ciphertext.iter().map(|b| b ^ self.0).collect::<Vec<_>>());
+            let checksum = plaintext
+                .pop()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty ciphertext"))?;
+            if plaintext.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)) != checksum {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid checksum",
+                ));
+            }
+            Ok(plaintext)
+        }
+    }
+
+    const KEY: &[u8] = b"very secret key material";
+
+    #[test]
+    fn encrypted_key_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.pem");
+
+        store_encrypted_key(&DummyKey(KEY.to_vec()), &path, &XorCipher(42)).unwrap();
+
+        let pem = read_pem_file(&path).unwrap();
+        assert_eq!(pem.tag, "ENCRYPTED DUMMY KEY");
+        assert_ne!(pem.contents[..KEY.len()], *KEY);
+
+        let loaded: DummyKey = load_encrypted_key(&path, &XorCipher(42)).unwrap();
+        assert_eq!(loaded, DummyKey(KEY.to_vec()));
+    }
+
+    #[test]
+    fn encrypted_key_cannot_be_loaded_as_plaintext_and_vice_versa() {
+        let dir = tempfile::tempdir().unwrap();
+        let encrypted = dir.path().join("encrypted.pem");
+        let plaintext = dir.path().join("plaintext.pem");
+
+        store_encrypted_key(&DummyKey(KEY.to_vec()), &encrypted, &XorCipher(42)).unwrap();
+        store_key(&DummyKey(KEY.to_vec()), &plaintext).unwrap();
+
+        assert!(load_key::<DummyKey>(&encrypted).is_err());
+        assert!(load_encrypted_key::<DummyKey, _>(&plaintext, &XorCipher(42)).is_err());
+    }
+
+    #[test]
+    fn encrypted_key_with_wrong_cipher_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.pem");
+
+        store_encrypted_key(&DummyKey(KEY.to_vec()), &path, &XorCipher(42)).unwrap();
+        let err = load_encrypted_key::<DummyKey, _>(&path, &XorCipher(43)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn tampered_and_truncated_encrypted_keys_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.pem");
+
+        store_encrypted_key(&DummyKey(KEY.to_vec()), &path, &XorCipher(42)).unwrap();
+        let pem = read_pem_file(&path).unwrap();
+
+        let mut tampered = pem.contents.clone();
+        tampered[0] ^= 1;
+        write_pem_file(&path, tampered, &pem.tag).unwrap();
+        assert!(load_encrypted_key::<DummyKey, _>(&path, &XorCipher(42)).is_err());
+
+        write_pem_file(
+            &path,
+            pem.contents[..pem.contents.len() - 1].to_vec(),
+            &pem.tag,
+        )
+        .unwrap();
+        assert!(load_encrypted_key::<DummyKey, _>(&path, &XorCipher(42)).is_err());
+
+        write_pem_file(&path, Vec::new(), &pem.tag).unwrap();
+        assert!(load_encrypted_key::<DummyKey, _>(&path, &XorCipher(42)).is_err());
+    }
+}
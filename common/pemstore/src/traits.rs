@@ -1,6 +1,9 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use std::io;
+use zeroize::Zeroizing;
+
 pub trait PemStorableKey: Sized {
     type Error: std::error::Error;
     fn pem_type() -> &'static str;
@@ -8,6 +11,13 @@ pub trait PemStorableKey: Sized {
     fn from_bytes(bytes: &[u8]) -> Result<Self, Self::Error>;
 }
 
+/// Encryption applied to the private keys before they get written to the disk.
+/// The decrypted key material is zeroized as soon as it's no longer needed.
+pub trait KeyCipher {
+    fn encrypt(&self, plaintext: &[u8]) -> io::Result<Vec<u8>>;
+    fn decrypt(&self, ciphertext: &[u8]) -> io::Result<Zeroizing<Vec<u8>>>;
+}
+
 pub trait PemStorableKeyPair {
     type PrivatePemKey: PemStorableKey;
     type PublicPemKey: PemStorableKey;
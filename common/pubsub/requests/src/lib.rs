@@ -0,0 +1,279 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use service_providers_common::interface;
+use service_providers_common::interface::ServiceProviderMessagingError;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+pub use request::*;
+pub use response::*;
+pub use version::*;
+
+pub mod request;
+pub mod response;
+pub mod version;
+
+pub type PubSubProviderRequest = interface::Request<PubSubRequest>;
+pub type PubSubProviderResponse = interface::Response<PubSubRequest>;
+
+/// Maximum length, in bytes, of the name of a topic.
+pub const MAX_TOPIC_LENGTH: usize = u8::MAX as usize;
+
+#[derive(Debug, Error)]
+pub enum PubSubRequestError {
+    #[error("no data provided")]
+    NoData,
+
+    #[error("{value} is not a valid request flag")]
+    UnknownRequestFlag { value: u8 },
+
+    #[error("{value} is not a valid response flag")]
+    UnknownResponseFlag { value: u8 },
+
+    #[error("the legacy (unversioned) protocol is not supported by the pub/sub provider")]
+    LegacyProtocol,
+
+    #[error("topic name must not be empty")]
+    EmptyTopic,
+
+    #[error("topic name is {length} bytes long, while at most {MAX_TOPIC_LENGTH} are allowed")]
+    TopicTooLong { length: usize },
+
+    #[error("not enough bytes to recover the topic")]
+    TopicTooShort,
+
+    #[error("topic name is not utf8 encoded: {source}")]
+    MalformedTopic { source: std::string::FromUtf8Error },
+
+    #[error("not enough bytes to recover the number of subscribers")]
+    SubscribersTooShort,
+
+    #[error("the publish request is missing its authorisation flag")]
+    PublishAuthorisationFlagMissing,
+
+    #[error("{value} is not a valid publish authorisation flag")]
+    UnknownPublishAuthorisationFlag { value: u8 },
+
+    #[error("not enough bytes to recover the publisher signature")]
+    PublisherSignatureTooShort,
+
+    #[error("the publisher identity key is malformed: {source}")]
+    MalformedPublisherKey {
+        source: nym_crypto::asymmetric::identity::Ed25519RecoveryError,
+    },
+
+    #[error("the publisher signature is malformed: {source}")]
+    MalformedPublisherSignature {
+        source: nym_crypto::asymmetric::identity::Ed25519RecoveryError,
+    },
+
+    #[error("error message is not utf8 encoded: {source}")]
+    MalformedErrorMessage { source: std::string::FromUtf8Error },
+
+    #[error(transparent)]
+    ProviderInterfaceError(#[from] ServiceProviderMessagingError),
+}
+
+/// Name of a topic messages get published to. It is a non-empty utf8 string of at most
+/// [`MAX_TOPIC_LENGTH`] bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Topic(String);
+
+impl Topic {
+    pub fn new<S: Into<String>>(name: S) -> Result<Self, PubSubRequestError> {
+        let name = name.into();
+        if name.is_empty() {
+            return Err(PubSubRequestError::EmptyTopic);
+        }
+        if name.len() > MAX_TOPIC_LENGTH {
+            return Err(PubSubRequestError::TopicTooLong { length: name.len() });
+        }
+        Ok(Topic(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // the topic is encoded as | 1 byte length | name |
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        std::iter::once(self.0.len() as u8)
+            .chain(self.0.into_bytes())
+            .collect()
+    }
+
+    // returns the recovered topic alongside the remaining bytes
+    pub(crate) fn try_from_bytes(b: &[u8]) -> Result<(Self, &[u8]), PubSubRequestError> {
+        if b.is_empty() {
+            return Err(PubSubRequestError::TopicTooShort);
+        }
+        let length = b[0] as usize;
+        if b.len() < 1 + length {
+            return Err(PubSubRequestError::TopicTooShort);
+        }
+
+        let name = String::from_utf8(b[1..1 + length].to_vec())
+            .map_err(|source| PubSubRequestError::MalformedTopic { source })?;
+        Ok((Topic::new(name)?, &b[1 + length..]))
+    }
+}
+
+impl FromStr for Topic {
+    type Err = PubSubRequestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Topic::new(s)
+    }
+}
+
+impl AsRef<str> for Topic {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Topic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_crypto::asymmetric::identity;
+    use service_providers_common::interface::{
+        ProviderInterfaceVersion, RequestContent, ResponseContent, Serializable,
+    };
+
+    fn topic(name: &str) -> Topic {
+        name.parse().unwrap()
+    }
+
+    fn publisher() -> identity::KeyPair {
+        identity::KeyPair::new(&mut rand::rngs::OsRng)
+    }
+
+    #[test]
+    fn topic_names_are_validated() {
+        assert!(matches!(
+            Topic::new(""),
+            Err(PubSubRequestError::EmptyTopic)
+        ));
+        assert!(Topic::new("a".repeat(MAX_TOPIC_LENGTH)).is_ok());
+        assert!(matches!(
+            Topic::new("a".repeat(MAX_TOPIC_LENGTH + 1)),
+            Err(PubSubRequestError::TopicTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn request_serialization_roundtrip() {
+        let requests = vec![
+            PubSubRequest::new_subscribe(topic("news")),
+            PubSubRequest::new_unsubscribe(topic("news")),
+            PubSubRequest::new_publish(topic("news"), b"hello world".to_vec()),
+            PubSubRequest::new_publish(topic("empty"), Vec::new()),
+            PubSubRequest::new_signed_publish(topic("news"), b"hello world".to_vec(), &publisher()),
+            PubSubRequest::new_signed_publish(topic("empty"), Vec::new(), &publisher()),
+        ];
+
+        for request in requests {
+            let full_request = PubSubProviderRequest::new_provider_data(
+                ProviderInterfaceVersion::new_current(),
+                request.clone(),
+            );
+            let bytes = full_request.into_bytes();
+            let recovered = PubSubProviderRequest::try_from_bytes(&bytes).unwrap();
+            match recovered.content {
+                RequestContent::ProviderData(recovered) => {
+                    assert_eq!(recovered.protocol_version, request.protocol_version);
+                    assert_eq!(recovered.content, request.content);
+                }
+                RequestContent::Control(_) => panic!("unexpected control request"),
+            }
+        }
+    }
+
+    #[test]
+    fn response_serialization_roundtrip() {
+        let responses = vec![
+            PubSubResponse::new_subscribed(topic("news")),
+            PubSubResponse::new_unsubscribed(topic("news")),
+            PubSubResponse::new_published(topic("news"), 42),
+            PubSubResponse::new_message(topic("news"), b"hello world".to_vec()),
+            PubSubResponse::new_error(topic("news"), "too many subscribers".to_string()),
+        ];
+
+        for response in responses {
+            let expected = response.content.clone();
+            let full_response = PubSubProviderResponse::new_provider_data(
+                ProviderInterfaceVersion::new_current(),
+                response,
+            );
+            let bytes = full_response.into_bytes();
+            let recovered = PubSubProviderResponse::try_from_bytes(&bytes).unwrap();
+            match recovered.content {
+                ResponseContent::ProviderData(recovered) => {
+                    assert_eq!(recovered.content, expected)
+                }
+                ResponseContent::Control(_) => panic!("unexpected control response"),
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_requests_are_rejected() {
+        let bytes = PubSubRequest::new_subscribe(topic("news")).into_bytes();
+        for i in 0..bytes.len() {
+            assert!(PubSubRequest::try_from_bytes(&bytes[..i]).is_err());
+        }
+    }
+
+    #[test]
+    fn truncated_signed_publish_requests_are_rejected() {
+        let request = PubSubRequest::new_signed_publish(topic("news"), Vec::new(), &publisher());
+        let bytes = request.into_bytes();
+        for i in 0..bytes.len() {
+            assert!(PubSubRequest::try_from_bytes(&bytes[..i]).is_err());
+        }
+    }
+
+    #[test]
+    fn publisher_signatures_cover_topic_and_payload() {
+        let keys = publisher();
+        let signature = PublisherSignature::sign(&keys, &topic("news"), b"hello");
+        assert!(signature.verify(&topic("news"), b"hello"));
+        assert!(!signature.verify(&topic("news"), b"hellO"));
+        assert!(!signature.verify(&topic("newz"), b"hello"));
+
+        let impersonated = PublisherSignature {
+            publisher: *publisher().public_key(),
+            signature: signature.signature,
+        };
+        assert!(!impersonated.verify(&topic("news"), b"hello"));
+    }
+
+    #[test]
+    fn unknown_publish_authorisation_flags_are_rejected() {
+        let mut bytes = PubSubRequest::new_publish(topic("news"), Vec::new()).into_bytes();
+        // version || flag || topic len || "news" || authorisation flag
+        bytes[7] = 42;
+        assert!(matches!(
+            PubSubRequest::try_from_bytes(&bytes),
+            Err(PubSubRequestError::UnknownPublishAuthorisationFlag { value: 42 })
+        ));
+    }
+
+    #[test]
+    fn legacy_requests_are_rejected() {
+        let mut bytes = PubSubRequest::new_subscribe(topic("news")).into_bytes();
+        bytes[0] = 0;
+        assert!(matches!(
+            PubSubRequest::try_from_bytes(&bytes),
+            Err(PubSubRequestError::LegacyProtocol)
+        ));
+    }
+}
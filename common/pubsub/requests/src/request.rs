@@ -0,0 +1,289 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{PubSubProtocolVersion, PubSubRequestError, PubSubResponse, Topic};
+use nym_crypto::asymmetric::identity;
+use service_providers_common::interface::{Serializable, ServiceProviderRequest};
+
+// prefix of every signed publish so that the signature could not be replayed in any other context
+const PUBLISH_SIGNATURE_DOMAIN: &[u8] = b"nym-pubsub-publish";
+
+const UNSIGNED_PUBLISH: u8 = 0;
+const SIGNED_PUBLISH: u8 = 1;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug)]
+pub enum RequestFlag {
+    Subscribe = 0,
+    Unsubscribe = 1,
+    Publish = 2,
+}
+
+impl TryFrom<u8> for RequestFlag {
+    type Error = PubSubRequestError;
+
+    fn try_from(value: u8) -> Result<RequestFlag, PubSubRequestError> {
+        match value {
+            _ if value == (RequestFlag::Subscribe as u8) => Ok(Self::Subscribe),
+            _ if value == (RequestFlag::Unsubscribe as u8) => Ok(Self::Unsubscribe),
+            _ if value == (RequestFlag::Publish as u8) => Ok(Self::Publish),
+            value => Err(PubSubRequestError::UnknownRequestFlag { value }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PubSubRequest {
+    pub protocol_version: PubSubProtocolVersion,
+    pub content: PubSubRequestContent,
+}
+
+impl Serializable for PubSubRequest {
+    type Error = PubSubRequestError;
+
+    // requests have the format of
+    // <version> || <flag> || <topic> || <data>
+    fn into_bytes(self) -> Vec<u8> {
+        // we never construct legacy requests ourselves
+        let version = self.protocol_version.as_u8().unwrap_or_default();
+        std::iter::once(version)
+            .chain(self.content.into_bytes())
+            .collect()
+    }
+
+    fn try_from_bytes(b: &[u8]) -> Result<Self, Self::Error> {
+        if b.is_empty() {
+            return Err(PubSubRequestError::NoData);
+        }
+
+        let protocol_version = PubSubProtocolVersion::from(b[0]);
+        if protocol_version.is_legacy() {
+            return Err(PubSubRequestError::LegacyProtocol);
+        }
+
+        Ok(PubSubRequest {
+            protocol_version,
+            content: PubSubRequestContent::try_from_bytes(&b[1..])?,
+        })
+    }
+}
+
+impl ServiceProviderRequest for PubSubRequest {
+    type ProtocolVersion = PubSubProtocolVersion;
+    type Response = PubSubResponse;
+    type Error = PubSubRequestError;
+
+    fn provider_specific_version(&self) -> Self::ProtocolVersion {
+        self.protocol_version
+    }
+
+    fn max_supported_version() -> Self::ProtocolVersion {
+        PubSubProtocolVersion::new_current()
+    }
+}
+
+impl PubSubRequest {
+    pub fn new(content: PubSubRequestContent) -> PubSubRequest {
+        PubSubRequest {
+            protocol_version: PubSubProtocolVersion::new_current(),
+            content,
+        }
+    }
+
+    pub fn new_subscribe(topic: Topic) -> PubSubRequest {
+        Self::new(PubSubRequestContent::Subscribe(topic))
+    }
+
+    pub fn new_unsubscribe(topic: Topic) -> PubSubRequest {
+        Self::new(PubSubRequestContent::Unsubscribe(topic))
+    }
+
+    pub fn new_publish(topic: Topic, payload: Vec<u8>) -> PubSubRequest {
+        Self::new(PubSubRequestContent::Publish {
+            topic,
+            payload,
+            authorisation: None,
+        })
+    }
+
+    pub fn new_signed_publish(
+        topic: Topic,
+        payload: Vec<u8>,
+        publisher: &identity::KeyPair,
+    ) -> PubSubRequest {
+        let authorisation = PublisherSignature::sign(publisher, &topic, &payload);
+        Self::new(PubSubRequestContent::Publish {
+            topic,
+            payload,
+            authorisation: Some(authorisation),
+        })
+    }
+}
+
+/// Proof that a publish request has been made by the holder of the attached identity key.
+/// It is required by the providers for topics restricted to a set of publishers.
+#[derive(Debug, Clone)]
+pub struct PublisherSignature {
+    pub publisher: identity::PublicKey,
+    pub signature: identity::Signature,
+}
+
+impl PublisherSignature {
+    const LEN: usize = identity::PUBLIC_KEY_LENGTH + identity::SIGNATURE_LENGTH;
+
+    // <domain> || <topic len> || <topic> || <payload>
+    fn signed_message(topic: &Topic, payload: &[u8]) -> Vec<u8> {
+        PUBLISH_SIGNATURE_DOMAIN
+            .iter()
+            .copied()
+            .chain(topic.clone().into_bytes())
+            .chain(payload.iter().copied())
+            .collect()
+    }
+
+    pub fn sign(publisher: &identity::KeyPair, topic: &Topic, payload: &[u8]) -> Self {
+        PublisherSignature {
+            publisher: *publisher.public_key(),
+            signature: publisher
+                .private_key()
+                .sign(&Self::signed_message(topic, payload)),
+        }
+    }
+
+    /// Checks whether the signature over the topic and payload is valid for the attached key.
+    pub fn verify(&self, topic: &Topic, payload: &[u8]) -> bool {
+        self.publisher
+            .verify(&Self::signed_message(topic, payload), &self.signature)
+            .is_ok()
+    }
+
+    fn into_bytes(self) -> impl Iterator<Item = u8> {
+        self.publisher
+            .to_bytes()
+            .into_iter()
+            .chain(self.signature.to_bytes())
+    }
+
+    // returns the recovered signature alongside the remaining bytes
+    fn try_from_bytes(b: &[u8]) -> Result<(Self, &[u8]), PubSubRequestError> {
+        if b.len() < Self::LEN {
+            return Err(PubSubRequestError::PublisherSignatureTooShort);
+        }
+        let publisher = identity::PublicKey::from_bytes(&b[..identity::PUBLIC_KEY_LENGTH])
+            .map_err(|source| PubSubRequestError::MalformedPublisherKey { source })?;
+        let signature = identity::Signature::from_bytes(&b[identity::PUBLIC_KEY_LENGTH..Self::LEN])
+            .map_err(|source| PubSubRequestError::MalformedPublisherSignature { source })?;
+
+        Ok((
+            PublisherSignature {
+                publisher,
+                signature,
+            },
+            &b[Self::LEN..],
+        ))
+    }
+}
+
+// signatures do not implement `PartialEq`, so compare their byte representation instead
+impl PartialEq for PublisherSignature {
+    fn eq(&self, other: &Self) -> bool {
+        self.publisher == other.publisher && self.signature.to_bytes() == other.signature.to_bytes()
+    }
+}
+
+impl Eq for PublisherSignature {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PubSubRequestContent {
+    /// Start (or renew) receiving messages published to the topic via the attached reply SURBs.
+    Subscribe(Topic),
+
+    /// Stop receiving messages published to the topic.
+    Unsubscribe(Topic),
+
+    /// Send the payload to every current subscriber of the topic.
+    /// Topics restricted by the provider additionally require a signature of an allowed publisher.
+    Publish {
+        topic: Topic,
+        payload: Vec<u8>,
+        authorisation: Option<PublisherSignature>,
+    },
+}
+
+impl PubSubRequestContent {
+    fn flag(&self) -> RequestFlag {
+        match self {
+            PubSubRequestContent::Subscribe(_) => RequestFlag::Subscribe,
+            PubSubRequestContent::Unsubscribe(_) => RequestFlag::Unsubscribe,
+            PubSubRequestContent::Publish { .. } => RequestFlag::Publish,
+        }
+    }
+
+    pub fn topic(&self) -> &Topic {
+        match self {
+            PubSubRequestContent::Subscribe(topic)
+            | PubSubRequestContent::Unsubscribe(topic)
+            | PubSubRequestContent::Publish { topic, .. } => topic,
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let flag = self.flag() as u8;
+        match self {
+            PubSubRequestContent::Subscribe(topic) | PubSubRequestContent::Unsubscribe(topic) => {
+                std::iter::once(flag).chain(topic.into_bytes()).collect()
+            }
+            // <flag> || <topic> || <is signed> || [<publisher key> || <signature>] || <payload>
+            PubSubRequestContent::Publish {
+                topic,
+                payload,
+                authorisation,
+            } => {
+                let mut bytes: Vec<_> = std::iter::once(flag).chain(topic.into_bytes()).collect();
+                match authorisation {
+                    Some(authorisation) => {
+                        bytes.push(SIGNED_PUBLISH);
+                        bytes.extend(authorisation.into_bytes());
+                    }
+                    None => bytes.push(UNSIGNED_PUBLISH),
+                }
+                bytes.extend(payload);
+                bytes
+            }
+        }
+    }
+
+    fn try_from_bytes(b: &[u8]) -> Result<Self, PubSubRequestError> {
+        if b.is_empty() {
+            return Err(PubSubRequestError::NoData);
+        }
+
+        let flag = RequestFlag::try_from(b[0])?;
+        let (topic, remaining) = Topic::try_from_bytes(&b[1..])?;
+        match flag {
+            RequestFlag::Subscribe => Ok(PubSubRequestContent::Subscribe(topic)),
+            RequestFlag::Unsubscribe => Ok(PubSubRequestContent::Unsubscribe(topic)),
+            RequestFlag::Publish => {
+                let Some((&signed, remaining)) = remaining.split_first() else {
+                    return Err(PubSubRequestError::PublishAuthorisationFlagMissing);
+                };
+                let (authorisation, payload) = match signed {
+                    UNSIGNED_PUBLISH => (None, remaining),
+                    SIGNED_PUBLISH => {
+                        let (authorisation, payload) =
+                            PublisherSignature::try_from_bytes(remaining)?;
+                        (Some(authorisation), payload)
+                    }
+                    value => {
+                        return Err(PubSubRequestError::UnknownPublishAuthorisationFlag { value })
+                    }
+                };
+                Ok(PubSubRequestContent::Publish {
+                    topic,
+                    payload: payload.to_vec(),
+                    authorisation,
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{PubSubProtocolVersion, PubSubRequestError, Topic};
+use service_providers_common::interface::{Serializable, ServiceProviderResponse};
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug)]
+pub enum ResponseFlag {
+    Subscribed = 0,
+    Unsubscribed = 1,
+    Published = 2,
+    Message = 3,
+    Error = 4,
+}
+
+impl TryFrom<u8> for ResponseFlag {
+    type Error = PubSubRequestError;
+
+    fn try_from(value: u8) -> Result<ResponseFlag, PubSubRequestError> {
+        match value {
+            _ if value == (ResponseFlag::Subscribed as u8) => Ok(Self::Subscribed),
+            _ if value == (ResponseFlag::Unsubscribed as u8) => Ok(Self::Unsubscribed),
+            _ if value == (ResponseFlag::Published as u8) => Ok(Self::Published),
+            _ if value == (ResponseFlag::Message as u8) => Ok(Self::Message),
+            _ if value == (ResponseFlag::Error as u8) => Ok(Self::Error),
+            value => Err(PubSubRequestError::UnknownResponseFlag { value }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PubSubResponse {
+    pub protocol_version: PubSubProtocolVersion,
+    pub content: PubSubResponseContent,
+}
+
+impl Serializable for PubSubResponse {
+    type Error = PubSubRequestError;
+
+    // responses have the format of
+    // <version> || <flag> || <topic> || <data>
+    fn into_bytes(self) -> Vec<u8> {
+        // we never construct legacy responses ourselves
+        let version = self.protocol_version.as_u8().unwrap_or_default();
+        std::iter::once(version)
+            .chain(self.content.into_bytes())
+            .collect()
+    }
+
+    fn try_from_bytes(b: &[u8]) -> Result<Self, Self::Error> {
+        if b.is_empty() {
+            return Err(PubSubRequestError::NoData);
+        }
+
+        let protocol_version = PubSubProtocolVersion::from(b[0]);
+        if protocol_version.is_legacy() {
+            return Err(PubSubRequestError::LegacyProtocol);
+        }
+
+        Ok(PubSubResponse {
+            protocol_version,
+            content: PubSubResponseContent::try_from_bytes(&b[1..])?,
+        })
+    }
+}
+
+impl ServiceProviderResponse for PubSubResponse {}
+
+impl PubSubResponse {
+    pub fn new(content: PubSubResponseContent) -> PubSubResponse {
+        PubSubResponse {
+            protocol_version: PubSubProtocolVersion::new_current(),
+            content,
+        }
+    }
+
+    pub fn new_subscribed(topic: Topic) -> PubSubResponse {
+        Self::new(PubSubResponseContent::Subscribed(topic))
+    }
+
+    pub fn new_unsubscribed(topic: Topic) -> PubSubResponse {
+        Self::new(PubSubResponseContent::Unsubscribed(topic))
+    }
+
+    pub fn new_published(topic: Topic, subscribers: u32) -> PubSubResponse {
+        Self::new(PubSubResponseContent::Published { topic, subscribers })
+    }
+
+    pub fn new_message(topic: Topic, payload: Vec<u8>) -> PubSubResponse {
+        Self::new(PubSubResponseContent::Message { topic, payload })
+    }
+
+    pub fn new_error(topic: Topic, message: String) -> PubSubResponse {
+        Self::new(PubSubResponseContent::Error { topic, message })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PubSubResponseContent {
+    /// The subscription to the topic has been registered (or renewed).
+    Subscribed(Topic),
+
+    /// The subscription to the topic has been removed.
+    Unsubscribed(Topic),
+
+    /// The message has been forwarded to the specified number of subscribers.
+    Published { topic: Topic, subscribers: u32 },
+
+    /// A message published to a topic we are subscribed to.
+    Message { topic: Topic, payload: Vec<u8> },
+
+    /// The request concerning the topic could not be fulfilled.
+    Error { topic: Topic, message: String },
+}
+
+impl PubSubResponseContent {
+    fn flag(&self) -> ResponseFlag {
+        match self {
+            PubSubResponseContent::Subscribed(_) => ResponseFlag::Subscribed,
+            PubSubResponseContent::Unsubscribed(_) => ResponseFlag::Unsubscribed,
+            PubSubResponseContent::Published { .. } => ResponseFlag::Published,
+            PubSubResponseContent::Message { .. } => ResponseFlag::Message,
+            PubSubResponseContent::Error { .. } => ResponseFlag::Error,
+        }
+    }
+
+    pub fn topic(&self) -> &Topic {
+        match self {
+            PubSubResponseContent::Subscribed(topic)
+            | PubSubResponseContent::Unsubscribed(topic)
+            | PubSubResponseContent::Published { topic, .. }
+            | PubSubResponseContent::Message { topic, .. }
+            | PubSubResponseContent::Error { topic, .. } => topic,
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let flag = self.flag() as u8;
+        let (topic, data) = match self {
+            PubSubResponseContent::Subscribed(topic)
+            | PubSubResponseContent::Unsubscribed(topic) => (topic, Vec::new()),
+            PubSubResponseContent::Published { topic, subscribers } => {
+                (topic, subscribers.to_be_bytes().to_vec())
+            }
+            PubSubResponseContent::Message { topic, payload } => (topic, payload),
+            PubSubResponseContent::Error { topic, message } => (topic, message.into_bytes()),
+        };
+
+        std::iter::once(flag)
+            .chain(topic.into_bytes())
+            .chain(data)
+            .collect()
+    }
+
+    fn try_from_bytes(b: &[u8]) -> Result<Self, PubSubRequestError> {
+        if b.is_empty() {
+            return Err(PubSubRequestError::NoData);
+        }
+
+        let flag = ResponseFlag::try_from(b[0])?;
+        let (topic, remaining) = Topic::try_from_bytes(&b[1..])?;
+        match flag {
+            ResponseFlag::Subscribed => Ok(PubSubResponseContent::Subscribed(topic)),
+            ResponseFlag::Unsubscribed => Ok(PubSubResponseContent::Unsubscribed(topic)),
+            ResponseFlag::Published => {
+                let subscribers = remaining
+                    .try_into()
+                    .map(u32::from_be_bytes)
+                    .map_err(|_| PubSubRequestError::SubscribersTooShort)?;
+                Ok(PubSubResponseContent::Published { topic, subscribers })
+            }
+            ResponseFlag::Message => Ok(PubSubResponseContent::Message {
+                topic,
+                payload: remaining.to_vec(),
+            }),
+            ResponseFlag::Error => {
+                let message = String::from_utf8(remaining.to_vec())
+                    .map_err(|source| PubSubRequestError::MalformedErrorMessage { source })?;
+                Ok(PubSubResponseContent::Error { topic, message })
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use service_providers_common::{define_simple_version, interface::Version};
+
+/// Defines initial version of the communication interface between pub/sub clients and
+/// the pub/sub service provider.
+// note: there has never been a legacy (unversioned) pub/sub protocol, so anything below it
+// is going to get rejected
+pub const INITIAL_INTERFACE_VERSION: u8 = 1;
+
+/// Defines the current version of the communication interface between pub/sub clients and
+/// the pub/sub service provider.
+/// It has to be incremented for any breaking change.
+pub const INTERFACE_VERSION: u8 = 1;
+
+define_simple_version!(
+    PubSubProtocolVersion,
+    INITIAL_INTERFACE_VERSION,
+    INTERFACE_VERSION
+);
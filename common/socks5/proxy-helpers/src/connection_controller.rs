@@ -7,16 +7,36 @@ use log::*;
 use nym_task::connections::{ConnectionCommand, ConnectionCommandSender};
 use nym_task::TaskClient;
 use ordered_buffer::{OrderedMessage, OrderedMessageBuffer, ReadContiguousData};
-use socks5_requests::{ConnectionId, NetworkData, SendRequest};
+use socks5_requests::{
+    ConnectionErrorKind, ConnectionFailure, ConnectionId, CreditRequest, NetworkCredit,
+    NetworkData, NetworkDatagram, RemoteAddress, SendRequest,
+};
 use std::collections::{HashMap, HashSet};
 
 /// A generic message produced after reading from a socket/connection. It includes data that was
 /// actually read alongside boolean indicating whether the connection got closed so that
-/// remote could act accordingly.
+/// remote could act accordingly. It can also carry information about the state of
+/// the remote connection that is not a part of the data stream.
 #[derive(Debug)]
-pub struct ConnectionMessage {
-    pub payload: Vec<u8>,
-    pub socket_closed: bool,
+pub enum ConnectionMessage {
+    /// The remote connection has been successfully established.
+    Established,
+
+    /// Data read from the remote connection. If `socket_closed` is set, the remote is not going
+    /// to send anything more, but it might still be reading.
+    Data {
+        payload: Vec<u8>,
+        socket_closed: bool,
+    },
+
+    /// The remote connection could not be established or it has failed.
+    Failed(ConnectionErrorKind),
+
+    /// The remote has aborted the connection and is not going to read nor send anything more.
+    Reset,
+
+    /// The remote allows us to send this many more bytes on the connection.
+    Credit(u32),
 }
 
 /// Channel responsible for sending data that was received from mix network into particular connection.
@@ -28,6 +48,21 @@ pub type ConnectionSender = mpsc::UnboundedSender<ConnectionMessage>;
 /// Receiver part of the [`ConnectionSender`]
 pub type ConnectionReceiver = mpsc::UnboundedReceiver<ConnectionMessage>;
 
+/// A single UDP datagram exchanged with the remote as part of an UDP association.
+/// Unlike the connection data, datagrams are not ordered.
+#[derive(Debug)]
+pub struct Datagram {
+    pub remote_addr: RemoteAddress,
+    pub data: Vec<u8>,
+}
+
+/// Channel responsible for sending datagrams that were received from mix network into particular
+/// UDP association.
+pub type DatagramSender = mpsc::UnboundedSender<Datagram>;
+
+/// Receiver part of the [`DatagramSender`]
+pub type DatagramReceiver = mpsc::UnboundedReceiver<Datagram>;
+
 pub type ControllerSender = mpsc::UnboundedSender<ControllerCommand>;
 pub type ControllerReceiver = mpsc::UnboundedReceiver<ControllerCommand>;
 
@@ -36,6 +71,10 @@ pub enum ControllerCommand {
         connection_id: ConnectionId,
         connection_sender: ConnectionSender,
     },
+    InsertDatagram {
+        connection_id: ConnectionId,
+        datagram_sender: DatagramSender,
+    },
     Remove {
         connection_id: ConnectionId,
     },
@@ -44,6 +83,24 @@ pub enum ControllerCommand {
         data: Vec<u8>,
         is_closed: bool,
     },
+    SendDatagram {
+        connection_id: ConnectionId,
+        datagram: Datagram,
+    },
+    Established {
+        connection_id: ConnectionId,
+    },
+    Fail {
+        connection_id: ConnectionId,
+        kind: ConnectionErrorKind,
+    },
+    Reset {
+        connection_id: ConnectionId,
+    },
+    Credit {
+        connection_id: ConnectionId,
+        credit: u32,
+    },
 }
 
 impl From<NetworkData> for ControllerCommand {
@@ -56,6 +113,45 @@ impl From<NetworkData> for ControllerCommand {
     }
 }
 
+impl From<NetworkDatagram> for ControllerCommand {
+    fn from(value: NetworkDatagram) -> Self {
+        ControllerCommand::SendDatagram {
+            connection_id: value.connection_id,
+            datagram: Datagram {
+                remote_addr: value.remote_addr,
+                data: value.data,
+            },
+        }
+    }
+}
+
+impl From<ConnectionFailure> for ControllerCommand {
+    fn from(value: ConnectionFailure) -> Self {
+        ControllerCommand::Fail {
+            connection_id: value.connection_id,
+            kind: value.kind,
+        }
+    }
+}
+
+impl From<NetworkCredit> for ControllerCommand {
+    fn from(value: NetworkCredit) -> Self {
+        ControllerCommand::Credit {
+            connection_id: value.connection_id,
+            credit: value.credit,
+        }
+    }
+}
+
+impl From<CreditRequest> for ControllerCommand {
+    fn from(value: CreditRequest) -> Self {
+        ControllerCommand::Credit {
+            connection_id: value.conn_id,
+            credit: value.credit,
+        }
+    }
+}
+
 impl From<SendRequest> for ControllerCommand {
     fn from(value: SendRequest) -> Self {
         ControllerCommand::Send {
@@ -103,6 +199,7 @@ pub enum BroadcastActiveConnections {
 /// proxy.
 pub struct Controller {
     active_connections: HashMap<ConnectionId, ActiveConnection>,
+    active_associations: HashMap<ConnectionId, DatagramSender>,
     receiver: ControllerReceiver,
 
     // TODO: this will need to be either completely removed (from code) or periodically cleaned
@@ -119,6 +216,9 @@ pub struct Controller {
     // un-order messages. Note we don't ever expect to have more than 1-2 messages per connection here
     pending_messages: HashMap<ConnectionId, Vec<(Vec<u8>, bool)>>,
 
+    // connections that got reset before they were established
+    pending_resets: HashSet<ConnectionId>,
+
     shutdown: TaskClient,
 }
 
@@ -131,10 +231,12 @@ impl Controller {
         (
             Controller {
                 active_connections: HashMap::new(),
+                active_associations: HashMap::new(),
                 receiver,
                 recently_closed: HashSet::new(),
                 client_connection_tx,
                 pending_messages: HashMap::new(),
+                pending_resets: HashSet::new(),
                 shutdown,
             },
             sender,
@@ -150,6 +252,10 @@ impl Controller {
         };
         if let Some(_active_conn) = self.active_connections.insert(conn_id, active_connection) {
             error!("Received a duplicate 'Connect'!")
+        } else if self.pending_resets.remove(&conn_id) {
+            debug!("Connection {conn_id} got reset before it was established");
+            self.pending_messages.remove(&conn_id);
+            self.send_status(conn_id, ConnectionMessage::Reset);
         } else {
             // check if there were any pending messages
             if let Some(pending) = self.pending_messages.remove(&conn_id) {
@@ -161,9 +267,21 @@ impl Controller {
         }
     }
 
+    fn insert_association(&mut self, conn_id: ConnectionId, datagram_sender: DatagramSender) {
+        if self
+            .active_associations
+            .insert(conn_id, datagram_sender)
+            .is_some()
+        {
+            error!("Received a duplicate UDP association!")
+        }
+    }
+
     fn remove_connection(&mut self, conn_id: ConnectionId) {
         debug!("Removing {} from controller", conn_id);
-        if self.active_connections.remove(&conn_id).is_none() {
+        if self.active_connections.remove(&conn_id).is_none()
+            && self.active_associations.remove(&conn_id).is_none()
+        {
             error!(
                 "tried to remove non-existing connection with id: {:?}",
                 conn_id
@@ -202,7 +320,7 @@ impl Controller {
                     .connection_sender
                     .as_mut()
                     .unwrap()
-                    .unbounded_send(ConnectionMessage {
+                    .unbounded_send(ConnectionMessage::Data {
                         payload: payload.data,
                         socket_closed: active_connection.is_closed,
                     })
@@ -239,6 +357,54 @@ impl Controller {
         }
     }
 
+    /// Forwards the information about the state of the connection, bypassing the ordered buffer.
+    fn send_status(&mut self, conn_id: ConnectionId, status: ConnectionMessage) {
+        let aborted = matches!(
+            status,
+            ConnectionMessage::Failed(_) | ConnectionMessage::Reset
+        );
+
+        if let Some(active_connection) = self.active_connections.get_mut(&conn_id) {
+            if aborted {
+                // nothing that is still in the buffer is ever going to be read
+                active_connection.is_closed = true;
+            }
+            if let Err(err) = active_connection
+                .connection_sender
+                .as_mut()
+                .unwrap()
+                .unbounded_send(status)
+            {
+                debug!("Connection {conn_id} is no longer receiving data: {err}");
+            }
+        } else if let Some(association) = self.active_associations.get(&conn_id) {
+            if aborted {
+                // UDP associations don't care about the details, they just stop relaying datagrams
+                association.close_channel();
+            }
+        } else if aborted && !self.recently_closed.contains(&conn_id) {
+            // the connection might still be getting established
+            debug!("Received a 'Reset' before 'Connect' for {conn_id}");
+            self.pending_resets.insert(conn_id);
+        } else {
+            debug!("Received status of an unknown connection {conn_id}: {status:?}");
+        }
+    }
+
+    fn send_to_association(&mut self, conn_id: ConnectionId, datagram: Datagram) {
+        if let Some(association) = self.active_associations.get(&conn_id) {
+            if let Err(err) = association.unbounded_send(datagram) {
+                debug!("UDP association {conn_id} is no longer receiving datagrams: {err}");
+            }
+        } else {
+            // datagrams are unreliable anyway, so don't bother buffering them
+            debug!(
+                "Received a datagram for unknown UDP association {conn_id} ({} bytes were dropped)",
+                datagram.data.len()
+            );
+        }
+    }
+
     pub async fn run(&mut self) {
         loop {
             tokio::select! {
@@ -249,6 +415,24 @@ impl Controller {
                     Some(ControllerCommand::Insert{connection_id, connection_sender}) => {
                         self.insert_connection(connection_id, connection_sender)
                     }
+                    Some(ControllerCommand::SendDatagram{connection_id, datagram}) => {
+                        self.send_to_association(connection_id, datagram)
+                    }
+                    Some(ControllerCommand::InsertDatagram{connection_id, datagram_sender}) => {
+                        self.insert_association(connection_id, datagram_sender)
+                    }
+                    Some(ControllerCommand::Established{ connection_id }) => {
+                        self.send_status(connection_id, ConnectionMessage::Established)
+                    }
+                    Some(ControllerCommand::Fail{ connection_id, kind }) => {
+                        self.send_status(connection_id, ConnectionMessage::Failed(kind))
+                    }
+                    Some(ControllerCommand::Reset{ connection_id }) => {
+                        self.send_status(connection_id, ConnectionMessage::Reset)
+                    }
+                    Some(ControllerCommand::Credit{ connection_id, credit }) => {
+                        self.send_status(connection_id, ConnectionMessage::Credit(credit))
+                    }
                     Some(ControllerCommand::Remove{ connection_id }) => self.remove_connection(connection_id),
                     None => {
                         log::trace!("SOCKS5 Controller: Stopping since channel closed");
@@ -0,0 +1,183 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::sync::Notify;
+
+/// Number of bytes either side of the connection is allowed to send before it has to wait
+/// for the other side to grant it more credit.
+pub const DEFAULT_WINDOW_SIZE: u32 = 1024 * 1024;
+
+// the credit is only granted once a decent chunk of the window has been used up,
+// so that we wouldn't be sending a credit message for every single read
+const CREDIT_GRANT_THRESHOLD: u32 = DEFAULT_WINDOW_SIZE / 4;
+
+/// The amount of data we are still allowed to send to the remote. It's shared between
+/// the inbound half of the proxy, which uses it up, and the outbound half, which receives
+/// the credit from the remote.
+#[derive(Debug)]
+pub(super) struct SendWindow {
+    // it can go negative as we don't know how much we're going to read from the socket
+    // until we actually read it
+    available: AtomicI64,
+    credited: Notify,
+}
+
+impl SendWindow {
+    pub(super) fn new() -> Self {
+        SendWindow {
+            available: AtomicI64::new(DEFAULT_WINDOW_SIZE as i64),
+            credited: Notify::new(),
+        }
+    }
+
+    pub(super) fn has_credit(&self) -> bool {
+        self.available.load(Ordering::SeqCst) > 0
+    }
+
+    pub(super) fn consume(&self, amount: usize) {
+        self.available.fetch_sub(amount as i64, Ordering::SeqCst);
+    }
+
+    pub(super) fn grant(&self, credit: u32) {
+        self.available.fetch_add(credit as i64, Ordering::SeqCst);
+        self.credited.notify_one();
+    }
+
+    /// Waits until the remote has granted us some credit.
+    pub(super) async fn credited(&self) {
+        while !self.has_credit() {
+            self.credited.notified().await
+        }
+    }
+}
+
+/// Keeps track of the data received from the remote that has been written to the socket,
+/// but for which the remote has not been given credit back yet.
+#[derive(Debug, Default)]
+pub(super) struct ReceiveWindow {
+    unacknowledged: u32,
+}
+
+impl ReceiveWindow {
+    /// Marks `amount` of bytes as consumed and returns the credit that should be granted
+    /// back to the remote, if it's time to do so.
+    pub(super) fn consume(&mut self, amount: usize) -> Option<u32> {
+        self.unacknowledged = self.unacknowledged.saturating_add(amount as u32);
+        if self.unacknowledged >= CREDIT_GRANT_THRESHOLD {
+            Some(std::mem::take(&mut self.unacknowledged))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn send_window_starts_with_full_credit() {
+        let window = SendWindow::new();
+        assert!(window.has_credit());
+
+        window.consume(DEFAULT_WINDOW_SIZE as usize - 1);
+        assert!(window.has_credit());
+
+        window.consume(1);
+        assert!(!window.has_credit());
+    }
+
+    #[test]
+    fn send_window_can_go_negative() {
+        let window = SendWindow::new();
+        window.consume(DEFAULT_WINDOW_SIZE as usize + 100);
+        assert!(!window.has_credit());
+
+        // the overdraft has to be paid off before we're allowed to send again
+        window.grant(100);
+        assert!(!window.has_credit());
+        window.grant(1);
+        assert!(window.has_credit());
+    }
+
+    #[tokio::test]
+    async fn send_window_credited_returns_immediately_with_credit() {
+        let window = SendWindow::new();
+        tokio::time::timeout(Duration::from_millis(100), window.credited())
+            .await
+            .expect("the window has credit available");
+    }
+
+    #[tokio::test]
+    async fn send_window_credited_waits_for_grant() {
+        let window = Arc::new(SendWindow::new());
+        window.consume(DEFAULT_WINDOW_SIZE as usize);
+
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), window.credited())
+                .await
+                .is_err()
+        );
+
+        let waiting_window = Arc::clone(&window);
+        let waiter = tokio::spawn(async move { waiting_window.credited().await });
+        window.grant(CREDIT_GRANT_THRESHOLD);
+
+        tokio::time::timeout(Duration::from_millis(100), waiter)
+            .await
+            .expect("the grant should have woken up the waiter")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_window_credited_keeps_waiting_for_insufficient_grant() {
+        let window = Arc::new(SendWindow::new());
+        window.consume(DEFAULT_WINDOW_SIZE as usize + 10);
+
+        let waiting_window = Arc::clone(&window);
+        let waiter = tokio::spawn(async move { waiting_window.credited().await });
+
+        // this isn't enough to cover the overdraft
+        window.grant(5);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        window.grant(10);
+        tokio::time::timeout(Duration::from_millis(100), waiter)
+            .await
+            .expect("the grant should have woken up the waiter")
+            .unwrap();
+    }
+
+    #[test]
+    fn receive_window_grants_credit_past_threshold() {
+        let mut window = ReceiveWindow::default();
+
+        let below = CREDIT_GRANT_THRESHOLD as usize - 1;
+        assert_eq!(window.consume(below), None);
+        assert_eq!(window.consume(1), Some(CREDIT_GRANT_THRESHOLD));
+
+        // and the counter got reset
+        assert_eq!(window.consume(below), None);
+    }
+
+    #[test]
+    fn receive_window_grants_everything_consumed() {
+        let mut window = ReceiveWindow::default();
+
+        assert_eq!(window.consume(10), None);
+        assert_eq!(
+            window.consume(CREDIT_GRANT_THRESHOLD as usize),
+            Some(CREDIT_GRANT_THRESHOLD + 10)
+        );
+    }
+
+    #[test]
+    fn receive_window_saturates() {
+        let mut window = ReceiveWindow::default();
+        assert_eq!(window.consume(usize::MAX), Some(u32::MAX));
+    }
+}
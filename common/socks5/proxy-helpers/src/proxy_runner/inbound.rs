@@ -1,6 +1,7 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+use super::flow_control::SendWindow;
 use super::MixProxySender;
 use super::SHUTDOWN_TIMEOUT;
 use crate::available_reader::AvailableReader;
@@ -160,6 +161,14 @@ async fn wait_for_lane(
     }
 }
 
+async fn wait_for_credit(send_window: Option<&SendWindow>) {
+    match send_window {
+        Some(send_window) => send_window.credited().await,
+        // without flow control we're never going to run out of credit
+        None => futures::future::pending().await,
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(super) async fn run_inbound<F, S>(
     mut reader: OwnedReadHalf,
@@ -169,6 +178,8 @@ pub(super) async fn run_inbound<F, S>(
     mix_sender: MixProxySender<S>,
     adapter_fn: F,
     shutdown_notify: Arc<Notify>,
+    abort_notify: Arc<Notify>,
+    send_window: Option<Arc<SendWindow>>,
     lane_queue_lengths: Option<LaneQueueLengths>,
     mut shutdown_listener: TaskClient,
 ) -> OwnedReadHalf
@@ -183,8 +194,17 @@ where
     tokio::pin!(shutdown_future);
 
     loop {
+        // stop reading from the socket if the remote is not ready to receive any more data
+        let has_credit = send_window
+            .as_ref()
+            .map(|send_window| send_window.has_credit())
+            .unwrap_or(true);
+
         select! {
-            read_data = &mut available_reader.next() => {
+            read_data = &mut available_reader.next(), if has_credit => {
+                if let (Some(send_window), Some(Ok(data))) = (&send_window, &read_data) {
+                    send_window.consume(data.len());
+                }
                 if deal_with_data(
                     read_data,
                     &local_destination_address,
@@ -208,6 +228,13 @@ where
                 send_empty_close(connection_id, &mut message_sender, &mix_sender, &adapter_fn).await;
                 break;
             }
+            _ = wait_for_credit(send_window.as_deref()), if !has_credit => {
+                trace!("{} - received more credit from the remote", connection_id);
+            }
+            _ = abort_notify.notified() => {
+                debug!("closing inbound proxy after the remote has aborted the connection");
+                break;
+            }
             _ = shutdown_listener.recv() => {
                 log::trace!("ProxyRunner inbound: Received shutdown");
                 break;
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::connection_controller::ConnectionReceiver;
+use flow_control::SendWindow;
 use nym_task::connections::LaneQueueLengths;
 use nym_task::TaskClient;
 use socks5_requests::ConnectionId;
@@ -9,9 +10,12 @@ use std::fmt::Debug;
 use std::{sync::Arc, time::Duration};
 use tokio::{net::TcpStream, sync::Notify};
 
+mod flow_control;
 mod inbound;
 mod outbound;
 
+pub use flow_control::DEFAULT_WINDOW_SIZE;
+
 // TODO: make this configurable
 const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -82,12 +86,19 @@ where
 
     // The `adapter_fn` is used to transform whatever was read into appropriate
     // request/response as required by entity running particular side of the proxy.
-    pub async fn run<F>(mut self, adapter_fn: F) -> Self
+    // Similarly, the `credit_adapter_fn` creates the message granting the remote more credit.
+    // If it's not provided, the flow control is disabled on this connection.
+    pub async fn run<F, C>(mut self, adapter_fn: F, credit_adapter_fn: Option<C>) -> Self
     where
         F: Fn(ConnectionId, Vec<u8>, bool) -> S + Send + Sync + 'static,
+        C: Fn(ConnectionId, u32) -> S + Send + Sync + 'static,
     {
         let (read_half, write_half) = self.socket.take().unwrap().into_split();
         let shutdown_notify = Arc::new(Notify::new());
+        let abort_notify = Arc::new(Notify::new());
+        let send_window = credit_adapter_fn
+            .is_some()
+            .then(|| Arc::new(SendWindow::new()));
 
         // should run until either inbound closes or is notified from outbound
         let inbound_future = inbound::run_inbound(
@@ -98,6 +109,8 @@ where
             self.mix_sender.clone(),
             adapter_fn,
             Arc::clone(&shutdown_notify),
+            Arc::clone(&abort_notify),
+            send_window.clone(),
             self.lane_queue_lengths.clone(),
             self.shutdown_listener.clone(),
         );
@@ -107,8 +120,12 @@ where
             self.local_destination_address.clone(),
             self.remote_source_address.clone(),
             self.mix_receiver.take().unwrap(),
+            self.mix_sender.clone(),
+            credit_adapter_fn,
             self.connection_id,
             shutdown_notify,
+            abort_notify,
+            send_window,
             self.shutdown_listener.clone(),
         );
 
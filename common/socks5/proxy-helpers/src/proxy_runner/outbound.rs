@@ -1,13 +1,15 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use super::SHUTDOWN_TIMEOUT;
+use super::flow_control::{ReceiveWindow, SendWindow};
+use super::{MixProxySender, SHUTDOWN_TIMEOUT};
 use crate::connection_controller::{ConnectionMessage, ConnectionReceiver};
 use futures::FutureExt;
 use futures::StreamExt;
 use log::*;
 use nym_task::TaskClient;
 use socks5_requests::ConnectionId;
+use std::fmt::Debug;
 use std::{sync::Arc, time::Duration};
 use tokio::io::AsyncWriteExt;
 use tokio::select;
@@ -15,55 +17,126 @@ use tokio::{net::tcp::OwnedWriteHalf, sync::Notify, time::sleep, time::Instant};
 
 const MIX_TTL: Duration = Duration::from_secs(5 * 60);
 
+/// What should happen with the proxy after handling a message received from the mixnet.
+enum MessageOutcome {
+    Continue { written: usize },
+    Closed,
+    Aborted,
+}
+
 async fn deal_with_message(
     connection_message: ConnectionMessage,
     writer: &mut OwnedWriteHalf,
     local_destination_address: &str,
     remote_source_address: &str,
     connection_id: ConnectionId,
-) -> bool {
+    send_window: Option<&SendWindow>,
+) -> MessageOutcome {
+    let (payload, socket_closed) = match connection_message {
+        ConnectionMessage::Data {
+            payload,
+            socket_closed,
+        } => (payload, socket_closed),
+        ConnectionMessage::Established => {
+            // the data is already flowing, so there's nothing to do here
+            trace!(target: &*format!("({connection_id}) socks5 outbound"), "Remote connection got established");
+            return MessageOutcome::Continue { written: 0 };
+        }
+        ConnectionMessage::Credit(credit) => {
+            match send_window {
+                Some(send_window) => send_window.grant(credit),
+                None => {
+                    debug!(target: &*format!("({connection_id}) socks5 outbound"), "Received credit on a connection without flow control")
+                }
+            }
+            return MessageOutcome::Continue { written: 0 };
+        }
+        ConnectionMessage::Failed(kind) => {
+            debug!(target: &*format!("({connection_id}) socks5 outbound"),
+                   "Remote connection has failed ({kind:?}) - aborting the local connection");
+            return MessageOutcome::Aborted;
+        }
+        ConnectionMessage::Reset => {
+            debug!(target: &*format!("({connection_id}) socks5 outbound"),
+                   "Remote connection got reset - aborting the local connection");
+            return MessageOutcome::Aborted;
+        }
+    };
+
     debug!(
         target: &*format!("({connection_id}) socks5 outbound"),
         "[{} bytes]\t{} → remote → mixnet → local → {} Remote closed: {}",
-        connection_message.payload.len(),
+        payload.len(),
         remote_source_address,
         local_destination_address,
-        connection_message.socket_closed
+        socket_closed
     );
 
-    if let Err(err) = writer.write_all(&connection_message.payload).await {
+    if let Err(err) = writer.write_all(&payload).await {
         // the other half is probably going to blow up too (if not, this task also needs to notify the other one!!)
         error!(target: &*format!("({connection_id}) socks5 outbound"), "failed to write response back to the socket - {err}");
-        return true;
+        return MessageOutcome::Closed;
     }
-    if connection_message.socket_closed {
+    if socket_closed {
+        // the remote is done sending, but it might still be reading, so only close our write side
         debug!(target: &*format!("({connection_id}) socks5 outbound"),
-               "Remote socket got closed - closing the local socket too");
-        return true;
+               "Remote socket got closed - closing the write side of the local socket too");
+        if let Err(err) = writer.shutdown().await {
+            debug!(target: &*format!("({connection_id}) socks5 outbound"), "failed to shutdown the write side of the socket - {err}");
+        }
+        return MessageOutcome::Closed;
+    }
+    MessageOutcome::Continue {
+        written: payload.len(),
     }
-    false
 }
 
-pub(super) async fn run_outbound(
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn run_outbound<C, S>(
     mut writer: OwnedWriteHalf,
     local_destination_address: String, // addresses are provided for better logging
     remote_source_address: String,
     mut mix_receiver: ConnectionReceiver,
+    mix_sender: MixProxySender<S>,
+    credit_adapter_fn: Option<C>,
     connection_id: ConnectionId,
     shutdown_notify: Arc<Notify>,
+    abort_notify: Arc<Notify>,
+    send_window: Option<Arc<SendWindow>>,
     mut shutdown_listener: TaskClient,
-) -> (OwnedWriteHalf, ConnectionReceiver) {
+) -> (OwnedWriteHalf, ConnectionReceiver)
+where
+    C: Fn(ConnectionId, u32) -> S + Send + 'static,
+    S: Debug,
+{
     let shutdown_future = shutdown_notify.notified().then(|_| sleep(SHUTDOWN_TIMEOUT));
     tokio::pin!(shutdown_future);
 
     let mut mix_timeout = Box::pin(sleep(MIX_TTL));
+    let mut receive_window = ReceiveWindow::default();
 
     loop {
         select! {
             connection_message = &mut mix_receiver.next() => {
                 if let Some(connection_message) = connection_message {
-                    if deal_with_message(connection_message, &mut writer, &local_destination_address, &remote_source_address, connection_id).await {
-                        break;
+                    match deal_with_message(connection_message, &mut writer, &local_destination_address, &remote_source_address, connection_id, send_window.as_deref()).await {
+                        MessageOutcome::Continue { written } => {
+                            // let the remote know it can send more data once we've actually
+                            // managed to get rid of what it has sent so far
+                            if let Some(credit_adapter_fn) = &credit_adapter_fn {
+                                if let Some(credit) = receive_window.consume(written) {
+                                    if mix_sender.send(credit_adapter_fn(connection_id, credit)).await.is_err() {
+                                        warn!("failed to grant credit on {connection_id} - the mix sender has stopped receiving");
+                                    }
+                                }
+                            }
+                        }
+                        MessageOutcome::Closed => break,
+                        MessageOutcome::Aborted => {
+                            // don't wait for the local socket to close, the remote is gone anyway
+                            abort_notify.notify_one();
+                            break;
+                        }
                     }
                     mix_timeout.as_mut().reset(Instant::now() + MIX_TTL);
                 } else {
@@ -15,6 +15,9 @@ pub type RemoteAddress = String;
 pub enum RequestFlag {
     Connect = 0,
     Send = 1,
+    Datagram = 2,
+    Reset = 3,
+    Credit = 4,
 }
 
 impl TryFrom<u8> for RequestFlag {
@@ -24,6 +27,9 @@ impl TryFrom<u8> for RequestFlag {
         match value {
             _ if value == (RequestFlag::Connect as u8) => Ok(Self::Connect),
             _ if value == (RequestFlag::Send as u8) => Ok(Self::Send),
+            _ if value == (RequestFlag::Datagram as u8) => Ok(Self::Datagram),
+            _ if value == (RequestFlag::Reset as u8) => Ok(Self::Reset),
+            _ if value == (RequestFlag::Credit as u8) => Ok(Self::Credit),
             value => Err(RequestDeserializationError::UnknownRequestFlag { value }),
         }
     }
@@ -49,8 +55,17 @@ pub enum RequestDeserializationError {
     #[error("too short return address")]
     ReturnAddressTooShort,
 
+    #[error("not enough bytes to recover the return address flag")]
+    MissingReturnAddressFlag,
+
+    #[error("datagrams are not supported by the version {version} of the interface")]
+    UnsupportedDatagrams { version: Socks5ProtocolVersion },
+
     #[error("malformed return address - {0}")]
     MalformedReturnAddress(RecipientFormattingError),
+
+    #[error("not enough bytes to recover the credit")]
+    CreditTooShort,
 }
 
 impl RequestDeserializationError {
@@ -74,6 +89,21 @@ pub struct SendRequest {
     pub local_closed: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct DatagramRequest {
+    pub conn_id: ConnectionId,
+    pub remote_addr: RemoteAddress,
+    pub data: Vec<u8>,
+    pub return_address: Option<Recipient>,
+}
+
+/// Allows the service provider to send `credit` more bytes on the connection.
+#[derive(Debug, Clone)]
+pub struct CreditRequest {
+    pub conn_id: ConnectionId,
+    pub credit: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct Socks5Request {
     pub protocol_version: Socks5ProtocolVersion,
@@ -108,9 +138,19 @@ impl Serializable for Socks5Request {
         }
 
         let protocol_version = Socks5ProtocolVersion::from(b[0]);
+        let content = Socks5RequestContent::try_from_bytes(&b[1..])?;
+        if matches!(content, Socks5RequestContent::Datagram(_))
+            && !protocol_version.supports_datagrams()
+        {
+            return Err(RequestDeserializationError::UnsupportedDatagrams {
+                version: protocol_version,
+            }
+            .into());
+        }
+
         Ok(Socks5Request {
             protocol_version,
-            content: Socks5RequestContent::try_from_bytes(&b[1..])?,
+            content,
         })
     }
 }
@@ -166,6 +206,40 @@ impl Socks5Request {
             content: Socks5RequestContent::new_send(conn_id, data, local_closed),
         }
     }
+
+    pub fn new_datagram(
+        protocol_version: Socks5ProtocolVersion,
+        conn_id: ConnectionId,
+        remote_addr: RemoteAddress,
+        data: Vec<u8>,
+        return_address: Option<Recipient>,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::new_datagram(conn_id, remote_addr, data, return_address),
+        }
+    }
+
+    pub fn new_reset(
+        protocol_version: Socks5ProtocolVersion,
+        conn_id: ConnectionId,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::new_reset(conn_id),
+        }
+    }
+
+    pub fn new_credit(
+        protocol_version: Socks5ProtocolVersion,
+        conn_id: ConnectionId,
+        credit: u32,
+    ) -> Socks5Request {
+        Socks5Request {
+            protocol_version,
+            content: Socks5RequestContent::new_credit(conn_id, credit),
+        }
+    }
 }
 
 /// A request from a SOCKS5 client that a Nym Socks5 service provider should
@@ -179,6 +253,18 @@ pub enum Socks5RequestContent {
 
     /// Re-use an existing TCP connection, sending more request data up it.
     Send(SendRequest),
+
+    /// Relay a single UDP datagram to the specified `RemoteAddress` as part of an UDP association.
+    /// Any datagrams received back from that address should be returned on the same `ConnectionId`
+    /// to the specified `Recipient`.
+    Datagram(Box<DatagramRequest>),
+
+    /// Abort the TCP connection immediately, without waiting for any of the outstanding data
+    /// to get delivered.
+    Reset(ConnectionId),
+
+    /// Grant the service provider more credit for sending data on an established TCP connection.
+    Credit(CreditRequest),
 }
 
 impl Socks5RequestContent {
@@ -208,6 +294,31 @@ impl Socks5RequestContent {
         })
     }
 
+    /// Construct a new Request::Datagram instance
+    pub fn new_datagram(
+        conn_id: ConnectionId,
+        remote_addr: RemoteAddress,
+        data: Vec<u8>,
+        return_address: Option<Recipient>,
+    ) -> Socks5RequestContent {
+        Socks5RequestContent::Datagram(Box::new(DatagramRequest {
+            conn_id,
+            remote_addr,
+            data,
+            return_address,
+        }))
+    }
+
+    /// Construct a new Request::Reset instance
+    pub fn new_reset(conn_id: ConnectionId) -> Socks5RequestContent {
+        Socks5RequestContent::Reset(conn_id)
+    }
+
+    /// Construct a new Request::Credit instance
+    pub fn new_credit(conn_id: ConnectionId, credit: u32) -> Socks5RequestContent {
+        Socks5RequestContent::Credit(CreditRequest { conn_id, credit })
+    }
+
     /// Deserialize the request type, connection id, destination address and port,
     /// and the request body from bytes.
     ///
@@ -220,8 +331,9 @@ impl Socks5RequestContent {
     // /// --------------------------------------------------------------------------------------
     ///
     /// The request_flag tells us whether this is a new connection request (`new_connect`),
-    /// an already-established connection we should send up (`new_send`), or
-    /// a request to close an established connection (`new_close`).
+    /// an already-established connection we should send up (`new_send`),
+    /// a datagram to relay (`new_datagram`), a request to abort an established
+    /// connection (`new_reset`) or more credit for sending data (`new_credit`).
     pub fn try_from_bytes(b: &[u8]) -> Result<Socks5RequestContent, RequestDeserializationError> {
         // each request needs to at least contain flag and ConnectionId
         if b.is_empty() {
@@ -234,28 +346,7 @@ impl Socks5RequestContent {
         let conn_id = u64::from_be_bytes([b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8]]);
         match RequestFlag::try_from(b[0])? {
             RequestFlag::Connect => {
-                let connect_request_bytes = &b[9..];
-
-                // we need to be able to read at least 2 bytes that specify address length
-                if connect_request_bytes.len() < 2 {
-                    return Err(RequestDeserializationError::AddressLengthTooShort);
-                }
-
-                let address_length =
-                    u16::from_be_bytes([connect_request_bytes[0], connect_request_bytes[1]])
-                        as usize;
-
-                if connect_request_bytes.len() < 2 + address_length {
-                    return Err(RequestDeserializationError::AddressTooShort);
-                }
-
-                let address_start = 2;
-                let address_end = address_start + address_length;
-                let address_bytes = &connect_request_bytes[address_start..address_end];
-                let remote_address = String::from_utf8_lossy(address_bytes).to_string();
-
-                // just a temporary reference to mid-slice for ease of use
-                let recipient_data_bytes = &connect_request_bytes[address_end..];
+                let (remote_address, recipient_data_bytes) = parse_remote_address(&b[9..])?;
 
                 let return_address = if recipient_data_bytes.is_empty() {
                     None
@@ -263,13 +354,7 @@ impl Socks5RequestContent {
                     if recipient_data_bytes.len() != Recipient::LEN {
                         return Err(RequestDeserializationError::ReturnAddressTooShort);
                     }
-
-                    let mut return_bytes = [0u8; Recipient::LEN];
-                    return_bytes.copy_from_slice(&recipient_data_bytes[..Recipient::LEN]);
-                    Some(
-                        Recipient::try_from_bytes(return_bytes)
-                            .map_err(RequestDeserializationError::MalformedReturnAddress)?,
-                    )
+                    Some(parse_return_address(recipient_data_bytes)?)
                 };
 
                 Ok(Socks5RequestContent::new_connect(
@@ -288,6 +373,39 @@ impl Socks5RequestContent {
                     local_closed,
                 }))
             }
+            RequestFlag::Datagram => {
+                let (remote_address, remaining) = parse_remote_address(&b[9..])?;
+
+                if remaining.is_empty() {
+                    return Err(RequestDeserializationError::MissingReturnAddressFlag);
+                }
+                let (return_address, data) = if remaining[0] != 0 {
+                    if remaining.len() < 1 + Recipient::LEN {
+                        return Err(RequestDeserializationError::ReturnAddressTooShort);
+                    }
+                    (
+                        Some(parse_return_address(&remaining[1..])?),
+                        &remaining[1 + Recipient::LEN..],
+                    )
+                } else {
+                    (None, &remaining[1..])
+                };
+
+                Ok(Socks5RequestContent::new_datagram(
+                    conn_id,
+                    remote_address,
+                    data.to_vec(),
+                    return_address,
+                ))
+            }
+            RequestFlag::Reset => Ok(Socks5RequestContent::new_reset(conn_id)),
+            RequestFlag::Credit => {
+                if b.len() < 13 {
+                    return Err(RequestDeserializationError::CreditTooShort);
+                }
+                let credit = u32::from_be_bytes([b[9], b[10], b[11], b[12]]);
+                Ok(Socks5RequestContent::new_credit(conn_id, credit))
+            }
         }
     }
 
@@ -317,10 +435,65 @@ impl Socks5RequestContent {
                 .chain(std::iter::once(req.local_closed as u8))
                 .chain(req.data.into_iter())
                 .collect(),
+            // datagram is: DATAGRAM_FLAG || CONN_ID || REMOTE_LEN || REMOTE || HAS_RETURN || [RETURN] || DATA
+            Socks5RequestContent::Datagram(req) => {
+                let remote_address_bytes = req.remote_addr.into_bytes();
+                let remote_address_bytes_len = remote_address_bytes.len() as u16;
+                let return_address_bytes = req
+                    .return_address
+                    .map(|address| address.to_bytes().to_vec())
+                    .unwrap_or_default();
+
+                std::iter::once(RequestFlag::Datagram as u8)
+                    .chain(req.conn_id.to_be_bytes().into_iter())
+                    .chain(remote_address_bytes_len.to_be_bytes().into_iter())
+                    .chain(remote_address_bytes.into_iter())
+                    .chain(std::iter::once(!return_address_bytes.is_empty() as u8))
+                    .chain(return_address_bytes.into_iter())
+                    .chain(req.data.into_iter())
+                    .collect()
+            }
+            // reset is: RESET_FLAG || CONN_ID
+            Socks5RequestContent::Reset(conn_id) => std::iter::once(RequestFlag::Reset as u8)
+                .chain(conn_id.to_be_bytes().into_iter())
+                .collect(),
+            // credit is: CREDIT_FLAG || CONN_ID || CREDIT
+            Socks5RequestContent::Credit(req) => std::iter::once(RequestFlag::Credit as u8)
+                .chain(req.conn_id.to_be_bytes().into_iter())
+                .chain(req.credit.to_be_bytes().into_iter())
+                .collect(),
         }
     }
 }
 
+/// Attempts to recover the length-prefixed remote address, returning it alongside any remaining bytes.
+fn parse_remote_address(b: &[u8]) -> Result<(RemoteAddress, &[u8]), RequestDeserializationError> {
+    // we need to be able to read at least 2 bytes that specify address length
+    if b.len() < 2 {
+        return Err(RequestDeserializationError::AddressLengthTooShort);
+    }
+
+    let address_length = u16::from_be_bytes([b[0], b[1]]) as usize;
+
+    if b.len() < 2 + address_length {
+        return Err(RequestDeserializationError::AddressTooShort);
+    }
+
+    let address_start = 2;
+    let address_end = address_start + address_length;
+    let address_bytes = &b[address_start..address_end];
+    let remote_address = String::from_utf8_lossy(address_bytes).to_string();
+
+    Ok((remote_address, &b[address_end..]))
+}
+
+fn parse_return_address(b: &[u8]) -> Result<Recipient, RequestDeserializationError> {
+    let mut return_bytes = [0u8; Recipient::LEN];
+    return_bytes.copy_from_slice(&b[..Recipient::LEN]);
+    Recipient::try_from_bytes(return_bytes)
+        .map_err(RequestDeserializationError::MalformedReturnAddress)
+}
+
 #[cfg(test)]
 mod request_deserialization_tests {
     use super::*;
@@ -611,4 +784,145 @@ mod request_deserialization_tests {
             }
         }
     }
+
+    #[cfg(test)]
+    mod relaying_datagrams {
+        use super::*;
+        use crate::version::{DATAGRAM_INTERFACE_VERSION, FLOW_CONTROL_INTERFACE_VERSION};
+
+        fn datagram_request(protocol_version: Socks5ProtocolVersion) -> Vec<u8> {
+            Socks5Request::new_datagram(
+                protocol_version,
+                42,
+                "foo.com:53".to_string(),
+                vec![1, 2, 3],
+                None,
+            )
+            .into_bytes()
+        }
+
+        #[test]
+        fn are_rejected_for_versions_without_datagram_support() {
+            for version in [
+                Socks5ProtocolVersion::new_legacy(),
+                Socks5ProtocolVersion::new_versioned(FLOW_CONTROL_INTERFACE_VERSION),
+            ] {
+                match Socks5Request::try_from_bytes(&datagram_request(version)).unwrap_err() {
+                    Socks5RequestError::RequestDeserialization {
+                        source: RequestDeserializationError::UnsupportedDatagrams { .. },
+                    } => {}
+                    _ => unreachable!(),
+                }
+            }
+        }
+
+        #[test]
+        fn are_accepted_for_versions_with_datagram_support() {
+            let version = Socks5ProtocolVersion::new_versioned(DATAGRAM_INTERFACE_VERSION);
+            let request = Socks5Request::try_from_bytes(&datagram_request(version)).unwrap();
+            assert_eq!(request.protocol_version, version);
+            assert!(matches!(request.content, Socks5RequestContent::Datagram(_)));
+        }
+
+        #[test]
+        fn returns_error_when_return_address_flag_is_missing() {
+            // 8 bytes connection id, "foo.com:53" remote address and nothing else
+            let request_bytes: Vec<_> =
+                [RequestFlag::Datagram as u8, 1, 2, 3, 4, 5, 6, 7, 8, 0, 10]
+                    .into_iter()
+                    .chain(b"foo.com:53".iter().copied())
+                    .collect();
+
+            match Socks5RequestContent::try_from_bytes(&request_bytes).unwrap_err() {
+                RequestDeserializationError::MissingReturnAddressFlag => {}
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn works_without_return_address() {
+            let request = Socks5RequestContent::new_datagram(
+                42,
+                "foo.com:53".to_string(),
+                vec![1, 2, 3],
+                None,
+            );
+
+            match Socks5RequestContent::try_from_bytes(&request.into_bytes()).unwrap() {
+                Socks5RequestContent::Datagram(req) => {
+                    assert_eq!(42, req.conn_id);
+                    assert_eq!("foo.com:53", req.remote_addr);
+                    assert_eq!(vec![1, 2, 3], req.data);
+                    assert!(req.return_address.is_none());
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn works_with_return_address() {
+            let recipient = Recipient::try_from_base58_string("CytBseW6yFXUMzz4SGAKdNLGR7q3sJLLYxyBGvutNEQV.4QXYyEVc5fUDjmmi8PrHN9tdUFV4PCvSJE1278cHyvoe@4sBbL1ngf1vtNqykydQKTFh26sQCw888GpUqvPvyNB4f").unwrap();
+            let request = Socks5RequestContent::new_datagram(
+                42,
+                "foo.com:53".to_string(),
+                Vec::new(),
+                Some(recipient),
+            );
+
+            match Socks5RequestContent::try_from_bytes(&request.into_bytes()).unwrap() {
+                Socks5RequestContent::Datagram(req) => {
+                    assert_eq!(42, req.conn_id);
+                    assert_eq!("foo.com:53", req.remote_addr);
+                    assert!(req.data.is_empty());
+                    assert_eq!(
+                        req.return_address.unwrap().to_bytes().to_vec(),
+                        recipient.to_bytes().to_vec()
+                    );
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod resetting_a_connection {
+        use super::*;
+
+        #[test]
+        fn works_when_request_is_sized_properly() {
+            let request_bytes = Socks5RequestContent::new_reset(42).into_bytes();
+            assert_eq!(9, request_bytes.len());
+
+            match Socks5RequestContent::try_from_bytes(&request_bytes).unwrap() {
+                Socks5RequestContent::Reset(conn_id) => assert_eq!(42, conn_id),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod granting_credit {
+        use super::*;
+
+        #[test]
+        fn returns_error_when_credit_is_too_short() {
+            let request_bytes = [RequestFlag::Credit as u8, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0];
+            match Socks5RequestContent::try_from_bytes(&request_bytes).unwrap_err() {
+                RequestDeserializationError::CreditTooShort => {}
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn works_when_request_is_sized_properly() {
+            let request_bytes = Socks5RequestContent::new_credit(42, 65536).into_bytes();
+            match Socks5RequestContent::try_from_bytes(&request_bytes).unwrap() {
+                Socks5RequestContent::Credit(req) => {
+                    assert_eq!(42, req.conn_id);
+                    assert_eq!(65536, req.credit);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
 }
@@ -1,8 +1,9 @@
 // Copyright 2020-2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{ConnectionId, Socks5ProtocolVersion, Socks5RequestError};
+use crate::{ConnectionId, RemoteAddress, Socks5ProtocolVersion, Socks5RequestError};
 use service_providers_common::interface::{Serializable, ServiceProviderResponse};
+use std::io;
 use thiserror::Error;
 
 // don't start tags from 0 for easier backwards compatibility since `NetworkData`
@@ -13,6 +14,10 @@ use thiserror::Error;
 pub enum ResponseFlag {
     NetworkData = 1,
     ConnectionError = 2,
+    Datagram = 3,
+    Connected = 4,
+    ConnectionFailure = 5,
+    Credit = 6,
 }
 
 impl TryFrom<u8> for ResponseFlag {
@@ -22,6 +27,10 @@ impl TryFrom<u8> for ResponseFlag {
         match value {
             _ if value == (ResponseFlag::NetworkData as u8) => Ok(Self::NetworkData),
             _ if value == (ResponseFlag::ConnectionError as u8) => Ok(Self::ConnectionError),
+            _ if value == (ResponseFlag::Datagram as u8) => Ok(Self::Datagram),
+            _ if value == (ResponseFlag::Connected as u8) => Ok(Self::Connected),
+            _ if value == (ResponseFlag::ConnectionFailure as u8) => Ok(Self::ConnectionFailure),
+            _ if value == (ResponseFlag::Credit as u8) => Ok(Self::Credit),
             value => Err(ResponseDeserializationError::UnknownResponseFlag { value }),
         }
     }
@@ -38,6 +47,21 @@ pub enum ResponseDeserializationError {
     #[error("no data provided")]
     NoData,
 
+    #[error("not enough bytes to recover the length of the address")]
+    AddressLengthTooShort,
+
+    #[error("not enough bytes to recover the address")]
+    AddressTooShort,
+
+    #[error("not enough bytes to recover the connection error kind")]
+    ConnectionErrorKindTooShort,
+
+    #[error("not enough bytes to recover the credit")]
+    CreditTooShort,
+
+    #[error("datagrams are not supported by the used version of the interface")]
+    UnsupportedDatagrams,
+
     #[error("message is not utf8 encoded: {source}")]
     MalformedErrorMessage {
         #[from]
@@ -83,6 +107,11 @@ impl Serializable for Socks5Response {
         } else {
             Socks5ResponseContent::try_from_bytes(&b[1..])
         }?;
+        if matches!(content, Socks5ResponseContent::Datagram(_))
+            && !protocol_version.supports_datagrams()
+        {
+            return Err(ResponseDeserializationError::UnsupportedDatagrams.into());
+        }
         Ok(Socks5Response {
             protocol_version,
             content,
@@ -135,12 +164,69 @@ impl Socks5Response {
             content: Socks5ResponseContent::new_connection_error(connection_id, error_message),
         }
     }
+
+    pub fn new_datagram(
+        protocol_version: Socks5ProtocolVersion,
+        connection_id: ConnectionId,
+        remote_addr: RemoteAddress,
+        data: Vec<u8>,
+    ) -> Socks5Response {
+        Socks5Response {
+            protocol_version,
+            content: Socks5ResponseContent::new_datagram(connection_id, remote_addr, data),
+        }
+    }
+
+    pub fn new_connected(
+        protocol_version: Socks5ProtocolVersion,
+        connection_id: ConnectionId,
+    ) -> Socks5Response {
+        Socks5Response {
+            protocol_version,
+            content: Socks5ResponseContent::new_connected(connection_id),
+        }
+    }
+
+    pub fn new_connection_failure(
+        protocol_version: Socks5ProtocolVersion,
+        connection_id: ConnectionId,
+        kind: ConnectionErrorKind,
+        message: String,
+    ) -> Socks5Response {
+        Socks5Response {
+            protocol_version,
+            content: Socks5ResponseContent::new_connection_failure(connection_id, kind, message),
+        }
+    }
+
+    pub fn new_credit(
+        protocol_version: Socks5ProtocolVersion,
+        connection_id: ConnectionId,
+        credit: u32,
+    ) -> Socks5Response {
+        Socks5Response {
+            protocol_version,
+            content: Socks5ResponseContent::new_credit(connection_id, credit),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum Socks5ResponseContent {
     NetworkData(NetworkData),
     ConnectionError(ConnectionError),
+    Datagram(NetworkDatagram),
+
+    /// The connection to the remote has been successfully established.
+    Connected(ConnectionId),
+
+    /// The connection to the remote could not be established or it has failed afterwards.
+    /// Unlike `ConnectionError`, it tells the client why, so that it could be reported
+    /// back to the application.
+    ConnectionFailure(ConnectionFailure),
+
+    /// The client is allowed to send more data on the connection.
+    Credit(NetworkCredit),
 }
 
 impl Socks5ResponseContent {
@@ -163,6 +249,34 @@ impl Socks5ResponseContent {
         Socks5ResponseContent::ConnectionError(ConnectionError::new(connection_id, error_message))
     }
 
+    pub fn new_datagram(
+        connection_id: ConnectionId,
+        remote_addr: RemoteAddress,
+        data: Vec<u8>,
+    ) -> Socks5ResponseContent {
+        Socks5ResponseContent::Datagram(NetworkDatagram::new(connection_id, remote_addr, data))
+    }
+
+    pub fn new_connected(connection_id: ConnectionId) -> Socks5ResponseContent {
+        Socks5ResponseContent::Connected(connection_id)
+    }
+
+    pub fn new_connection_failure(
+        connection_id: ConnectionId,
+        kind: ConnectionErrorKind,
+        message: String,
+    ) -> Socks5ResponseContent {
+        Socks5ResponseContent::ConnectionFailure(ConnectionFailure::new(
+            connection_id,
+            kind,
+            message,
+        ))
+    }
+
+    pub fn new_credit(connection_id: ConnectionId, credit: u32) -> Socks5ResponseContent {
+        Socks5ResponseContent::Credit(NetworkCredit::new(connection_id, credit))
+    }
+
     pub fn into_bytes(self) -> Vec<u8> {
         match self {
             Socks5ResponseContent::NetworkData(res) => {
@@ -175,6 +289,22 @@ impl Socks5ResponseContent {
                     .chain(res.into_bytes().into_iter())
                     .collect()
             }
+            Socks5ResponseContent::Datagram(res) => std::iter::once(ResponseFlag::Datagram as u8)
+                .chain(res.into_bytes().into_iter())
+                .collect(),
+            Socks5ResponseContent::Connected(connection_id) => {
+                std::iter::once(ResponseFlag::Connected as u8)
+                    .chain(connection_id.to_be_bytes().into_iter())
+                    .collect()
+            }
+            Socks5ResponseContent::ConnectionFailure(res) => {
+                std::iter::once(ResponseFlag::ConnectionFailure as u8)
+                    .chain(res.into_bytes().into_iter())
+                    .collect()
+            }
+            Socks5ResponseContent::Credit(res) => std::iter::once(ResponseFlag::Credit as u8)
+                .chain(res.into_bytes().into_iter())
+                .collect(),
         }
     }
 
@@ -193,6 +323,23 @@ impl Socks5ResponseContent {
             ResponseFlag::ConnectionError => Ok(Socks5ResponseContent::ConnectionError(
                 ConnectionError::try_from_bytes(&b[1..])?,
             )),
+            ResponseFlag::Datagram => Ok(Socks5ResponseContent::Datagram(
+                NetworkDatagram::try_from_bytes(&b[1..])?,
+            )),
+            ResponseFlag::Connected => {
+                if b.len() < 9 {
+                    return Err(ResponseDeserializationError::ConnectionIdTooShort);
+                }
+                Ok(Socks5ResponseContent::Connected(u64::from_be_bytes([
+                    b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8],
+                ])))
+            }
+            ResponseFlag::ConnectionFailure => Ok(Socks5ResponseContent::ConnectionFailure(
+                ConnectionFailure::try_from_bytes(&b[1..])?,
+            )),
+            ResponseFlag::Credit => Ok(Socks5ResponseContent::Credit(
+                NetworkCredit::try_from_bytes(&b[1..])?,
+            )),
         }
     }
 }
@@ -318,6 +465,206 @@ impl ConnectionError {
     }
 }
 
+/// The reason the connection to the remote could not be established or has failed.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionErrorKind {
+    Other = 0,
+    NotAllowed = 1,
+    NetworkUnreachable = 2,
+    HostUnreachable = 3,
+    ConnectionRefused = 4,
+    TimedOut = 5,
+}
+
+impl From<u8> for ConnectionErrorKind {
+    // unknown kinds, possibly introduced by newer service providers, are treated as generic failures
+    fn from(value: u8) -> Self {
+        match value {
+            _ if value == (ConnectionErrorKind::NotAllowed as u8) => Self::NotAllowed,
+            _ if value == (ConnectionErrorKind::NetworkUnreachable as u8) => {
+                Self::NetworkUnreachable
+            }
+            _ if value == (ConnectionErrorKind::HostUnreachable as u8) => Self::HostUnreachable,
+            _ if value == (ConnectionErrorKind::ConnectionRefused as u8) => Self::ConnectionRefused,
+            _ if value == (ConnectionErrorKind::TimedOut as u8) => Self::TimedOut,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl From<&io::Error> for ConnectionErrorKind {
+    fn from(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::ConnectionRefused => ConnectionErrorKind::ConnectionRefused,
+            io::ErrorKind::TimedOut => ConnectionErrorKind::TimedOut,
+            // returned when the address could not be resolved
+            io::ErrorKind::NotFound | io::ErrorKind::AddrNotAvailable => {
+                ConnectionErrorKind::HostUnreachable
+            }
+            io::ErrorKind::PermissionDenied => ConnectionErrorKind::NotAllowed,
+            _ => ConnectionErrorKind::Other,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConnectionFailure {
+    pub connection_id: ConnectionId,
+    pub kind: ConnectionErrorKind,
+    pub message: String,
+}
+
+impl ConnectionFailure {
+    pub fn new(connection_id: ConnectionId, kind: ConnectionErrorKind, message: String) -> Self {
+        ConnectionFailure {
+            connection_id,
+            kind,
+            message,
+        }
+    }
+
+    pub fn try_from_bytes(b: &[u8]) -> Result<ConnectionFailure, ResponseDeserializationError> {
+        if b.is_empty() {
+            return Err(ResponseDeserializationError::NoData);
+        }
+
+        if b.len() < 8 {
+            return Err(ResponseDeserializationError::ConnectionIdTooShort);
+        }
+        let connection_id = u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+
+        if b.len() < 9 {
+            return Err(ResponseDeserializationError::ConnectionErrorKindTooShort);
+        }
+        let kind = ConnectionErrorKind::from(b[8]);
+        let message = String::from_utf8(b[9..].to_vec())?;
+
+        Ok(ConnectionFailure {
+            connection_id,
+            kind,
+            message,
+        })
+    }
+
+    /// Serializes the failure as CONN_ID || KIND || MESSAGE
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.connection_id
+            .to_be_bytes()
+            .into_iter()
+            .chain(std::iter::once(self.kind as u8))
+            .chain(self.message.into_bytes().into_iter())
+            .collect()
+    }
+}
+
+/// Credit granted by the Socks5 service provider, allowing the client to send
+/// more data on the connection.
+#[derive(Debug)]
+pub struct NetworkCredit {
+    pub connection_id: ConnectionId,
+    pub credit: u32,
+}
+
+impl NetworkCredit {
+    pub fn new(connection_id: ConnectionId, credit: u32) -> Self {
+        NetworkCredit {
+            connection_id,
+            credit,
+        }
+    }
+
+    pub fn try_from_bytes(b: &[u8]) -> Result<NetworkCredit, ResponseDeserializationError> {
+        if b.is_empty() {
+            return Err(ResponseDeserializationError::NoData);
+        }
+
+        if b.len() < 8 {
+            return Err(ResponseDeserializationError::ConnectionIdTooShort);
+        }
+        let connection_id = u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+
+        if b.len() < 12 {
+            return Err(ResponseDeserializationError::CreditTooShort);
+        }
+        let credit = u32::from_be_bytes([b[8], b[9], b[10], b[11]]);
+
+        Ok(NetworkCredit {
+            connection_id,
+            credit,
+        })
+    }
+
+    /// Serializes the credit as CONN_ID || CREDIT
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.connection_id
+            .to_be_bytes()
+            .into_iter()
+            .chain(self.credit.to_be_bytes().into_iter())
+            .collect()
+    }
+}
+
+/// An UDP datagram received by the Socks5 service provider from the remote address
+/// as part of an UDP association.
+#[derive(Debug)]
+pub struct NetworkDatagram {
+    pub connection_id: ConnectionId,
+    pub remote_addr: RemoteAddress,
+    pub data: Vec<u8>,
+}
+
+impl NetworkDatagram {
+    pub fn new(connection_id: ConnectionId, remote_addr: RemoteAddress, data: Vec<u8>) -> Self {
+        NetworkDatagram {
+            connection_id,
+            remote_addr,
+            data,
+        }
+    }
+
+    pub fn try_from_bytes(b: &[u8]) -> Result<NetworkDatagram, ResponseDeserializationError> {
+        if b.is_empty() {
+            return Err(ResponseDeserializationError::NoData);
+        }
+
+        if b.len() < 8 {
+            return Err(ResponseDeserializationError::ConnectionIdTooShort);
+        }
+        let connection_id = u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+
+        if b.len() < 10 {
+            return Err(ResponseDeserializationError::AddressLengthTooShort);
+        }
+        let address_length = u16::from_be_bytes([b[8], b[9]]) as usize;
+        let address_end = 10 + address_length;
+        if b.len() < address_end {
+            return Err(ResponseDeserializationError::AddressTooShort);
+        }
+        let remote_addr = String::from_utf8(b[10..address_end].to_vec())?;
+
+        Ok(NetworkDatagram {
+            connection_id,
+            remote_addr,
+            data: b[address_end..].to_vec(),
+        })
+    }
+
+    /// Serializes the datagram as CONN_ID || REMOTE_LEN || REMOTE || DATA
+    pub fn into_bytes(self) -> Vec<u8> {
+        let remote_address_bytes = self.remote_addr.into_bytes();
+        let remote_address_bytes_len = remote_address_bytes.len() as u16;
+
+        self.connection_id
+            .to_be_bytes()
+            .into_iter()
+            .chain(remote_address_bytes_len.to_be_bytes().into_iter())
+            .chain(remote_address_bytes.into_iter())
+            .chain(self.data.into_iter())
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -414,4 +761,130 @@ mod tests {
             ));
         }
     }
+
+    #[cfg(test)]
+    mod network_datagram_serde_tests {
+        use super::*;
+        use crate::version::{DATAGRAM_INTERFACE_VERSION, FLOW_CONTROL_INTERFACE_VERSION};
+
+        #[test]
+        fn is_gated_on_the_protocol_version() {
+            let response = |version| {
+                Socks5Response::new_datagram(version, 42, "1.1.1.1:53".to_string(), vec![1])
+                    .into_bytes()
+            };
+
+            let old = Socks5ProtocolVersion::new_versioned(FLOW_CONTROL_INTERFACE_VERSION);
+            assert!(matches!(
+                Socks5Response::try_from_bytes(&response(old)),
+                Err(Socks5RequestError::ResponseDeserialization {
+                    source: ResponseDeserializationError::UnsupportedDatagrams
+                })
+            ));
+
+            let current = Socks5ProtocolVersion::new_versioned(DATAGRAM_INTERFACE_VERSION);
+            let deserialized = Socks5Response::try_from_bytes(&response(current)).unwrap();
+            assert!(matches!(
+                deserialized.content,
+                Socks5ResponseContent::Datagram(_)
+            ));
+        }
+
+        #[test]
+        fn simple_serde() {
+            let datagram = NetworkDatagram::new(42, "1.1.1.1:53".to_string(), vec![1, 2, 3]);
+            let deserialized = NetworkDatagram::try_from_bytes(&datagram.into_bytes()).unwrap();
+
+            assert_eq!(42, deserialized.connection_id);
+            assert_eq!("1.1.1.1:53", deserialized.remote_addr);
+            assert_eq!(vec![1, 2, 3], deserialized.data);
+        }
+
+        #[test]
+        fn deserialization_errors() {
+            let err = NetworkDatagram::try_from_bytes(&[]).unwrap_err();
+            assert_eq!(err, ResponseDeserializationError::NoData);
+
+            let err = NetworkDatagram::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 0]).unwrap_err();
+            assert_eq!(err, ResponseDeserializationError::AddressLengthTooShort);
+
+            let err =
+                NetworkDatagram::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 0, 5, 1]).unwrap_err();
+            assert_eq!(err, ResponseDeserializationError::AddressTooShort);
+        }
+    }
+
+    #[cfg(test)]
+    mod connection_status_serde_tests {
+        use super::*;
+
+        #[test]
+        fn connected_serde() {
+            let bytes = Socks5ResponseContent::new_connected(42).into_bytes();
+            match Socks5ResponseContent::try_from_bytes(&bytes).unwrap() {
+                Socks5ResponseContent::Connected(connection_id) => assert_eq!(42, connection_id),
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn connection_failure_serde() {
+            let bytes = Socks5ResponseContent::new_connection_failure(
+                42,
+                ConnectionErrorKind::ConnectionRefused,
+                "foomp".to_string(),
+            )
+            .into_bytes();
+
+            match Socks5ResponseContent::try_from_bytes(&bytes).unwrap() {
+                Socks5ResponseContent::ConnectionFailure(failure) => {
+                    assert_eq!(42, failure.connection_id);
+                    assert_eq!(ConnectionErrorKind::ConnectionRefused, failure.kind);
+                    assert_eq!("foomp", failure.message);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn unknown_error_kinds_are_treated_as_generic_failures() {
+            let failure =
+                ConnectionFailure::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 255]).unwrap();
+            assert_eq!(ConnectionErrorKind::Other, failure.kind);
+            assert!(failure.message.is_empty());
+        }
+
+        #[test]
+        fn deserialization_errors() {
+            let err = ConnectionFailure::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap_err();
+            assert_eq!(
+                err,
+                ResponseDeserializationError::ConnectionErrorKindTooShort
+            );
+
+            let err = Socks5ResponseContent::try_from_bytes(&[ResponseFlag::Connected as u8, 1, 2])
+                .unwrap_err();
+            assert_eq!(err, ResponseDeserializationError::ConnectionIdTooShort);
+        }
+    }
+
+    #[cfg(test)]
+    mod network_credit_serde_tests {
+        use super::*;
+
+        #[test]
+        fn simple_serde() {
+            let credit = NetworkCredit::new(42, 65536);
+            let deserialized = NetworkCredit::try_from_bytes(&credit.into_bytes()).unwrap();
+
+            assert_eq!(42, deserialized.connection_id);
+            assert_eq!(65536, deserialized.credit);
+        }
+
+        #[test]
+        fn deserialization_errors() {
+            let err = NetworkCredit::try_from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 0]).unwrap_err();
+            assert_eq!(err, ResponseDeserializationError::CreditTooShort);
+        }
+    }
 }
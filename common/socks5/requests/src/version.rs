@@ -12,10 +12,41 @@ pub const INITIAL_INTERFACE_VERSION: u8 = 3;
 /// Defines the current version of the communication interface between socks5 clients and
 /// network requesters (socks5).
 /// It has to be incremented for any breaking change.
-pub const INTERFACE_VERSION: u8 = 3;
+pub const INTERFACE_VERSION: u8 = 6;
+
+/// Defines the first version of the interface in which the service provider reports
+/// the outcome of every connection attempt with either `Connected` or `ConnectionFailure` response
+/// and understands `Reset` requests.
+pub const CONNECTION_STATUS_INTERFACE_VERSION: u8 = 4;
+
+/// Defines the first version of the interface in which both sides of a connection use credit-based
+/// flow control, i.e. they only send as much data as the other side has explicitly allowed.
+pub const FLOW_CONTROL_INTERFACE_VERSION: u8 = 5;
+
+/// Defines the first version of the interface in which UDP datagrams can be relayed
+/// as part of UDP associations.
+pub const DATAGRAM_INTERFACE_VERSION: u8 = 6;
 
 define_simple_version!(
     Socks5ProtocolVersion,
     INITIAL_INTERFACE_VERSION,
     INTERFACE_VERSION
 );
+
+impl Socks5ProtocolVersion {
+    /// Checks whether this version of the interface supports explicit connection status responses
+    /// and connection resets.
+    pub fn supports_connection_status(&self) -> bool {
+        matches!(self, Socks5ProtocolVersion::Versioned(version) if *version >= CONNECTION_STATUS_INTERFACE_VERSION)
+    }
+
+    /// Checks whether this version of the interface uses credit-based flow control.
+    pub fn supports_flow_control(&self) -> bool {
+        matches!(self, Socks5ProtocolVersion::Versioned(version) if *version >= FLOW_CONTROL_INTERFACE_VERSION)
+    }
+
+    /// Checks whether this version of the interface supports relaying UDP datagrams.
+    pub fn supports_datagrams(&self) -> bool {
+        matches!(self, Socks5ProtocolVersion::Versioned(version) if *version >= DATAGRAM_INTERFACE_VERSION)
+    }
+}
@@ -1,9 +1,9 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{filter, NetworkAddress};
+use crate::{filter, NetworkAddress, UpcomingSphinxKey};
 use nym_crypto::asymmetric::{encryption, identity};
-use nym_mixnet_contract_common::GatewayBond;
+use nym_mixnet_contract_common::{EpochId, GatewayBond};
 use nym_sphinx_addressing::nodes::{NodeIdentity, NymNodeRoutingAddress};
 use nym_sphinx_types::Node as SphinxNode;
 use std::convert::{TryFrom, TryInto};
@@ -42,6 +42,7 @@ pub struct Node {
     pub clients_port: u16,
     pub identity_key: identity::PublicKey,
     pub sphinx_key: encryption::PublicKey, // TODO: or nymsphinx::PublicKey? both are x25519
+    pub upcoming_sphinx_key: Option<UpcomingSphinxKey>,
     pub version: String,
 }
 
@@ -53,6 +54,17 @@ impl Node {
     pub fn clients_address(&self) -> String {
         format!("ws://{}:{}", self.host, self.clients_port)
     }
+
+    /// Starts using the announced sphinx key if it's already valid in the specified (absolute) epoch.
+    pub fn use_sphinx_key_for_epoch(&mut self, absolute_epoch_id: EpochId) {
+        if let Some(upcoming) = self.upcoming_sphinx_key.take() {
+            if upcoming.is_active(absolute_epoch_id) {
+                self.sphinx_key = upcoming.sphinx_key
+            } else {
+                self.upcoming_sphinx_key = Some(upcoming)
+            }
+        }
+    }
 }
 
 impl fmt::Display for Node {
@@ -112,6 +124,11 @@ impl<'a> TryFrom<&'a GatewayBond> for Node {
             clients_port: bond.gateway.clients_port,
             identity_key: identity::PublicKey::from_base58_string(&bond.gateway.identity_key)?,
             sphinx_key: encryption::PublicKey::from_base58_string(&bond.gateway.sphinx_key)?,
+            upcoming_sphinx_key: bond
+                .upcoming_sphinx_key
+                .as_ref()
+                .map(UpcomingSphinxKey::try_from)
+                .transpose()?,
             version: bond.gateway.version.clone(),
         })
     }
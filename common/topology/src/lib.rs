@@ -3,13 +3,14 @@
 
 use crate::filter::VersionFilterable;
 use log::warn;
+use nym_crypto::asymmetric::encryption;
 use nym_mixnet_contract_common::mixnode::MixNodeDetails;
-use nym_mixnet_contract_common::GatewayBond;
+use nym_mixnet_contract_common::{EpochId, GatewayBond};
 use nym_sphinx_addressing::nodes::NodeIdentity;
 use nym_sphinx_types::Node as SphinxNode;
 use rand::Rng;
 use std::collections::HashMap;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::fmt::{self, Display, Formatter};
 use std::io;
 use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
@@ -81,6 +82,33 @@ impl Display for NetworkAddress {
 
 pub type MixLayer = u8;
 
+/// Sphinx key announced by a node that is going to replace its current key
+/// once the specified epoch begins.
+#[derive(Debug, Clone)]
+pub struct UpcomingSphinxKey {
+    pub sphinx_key: encryption::PublicKey,
+    pub valid_from_epoch: EpochId,
+}
+
+impl UpcomingSphinxKey {
+    pub fn is_active(&self, absolute_epoch_id: EpochId) -> bool {
+        absolute_epoch_id >= self.valid_from_epoch
+    }
+}
+
+impl TryFrom<&nym_mixnet_contract_common::UpcomingSphinxKey> for UpcomingSphinxKey {
+    type Error = encryption::KeyRecoveryError;
+
+    fn try_from(
+        upcoming: &nym_mixnet_contract_common::UpcomingSphinxKey,
+    ) -> Result<Self, Self::Error> {
+        Ok(UpcomingSphinxKey {
+            sphinx_key: encryption::PublicKey::from_base58_string(&upcoming.sphinx_key)?,
+            valid_from_epoch: upcoming.valid_from_epoch,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NymTopology {
     mixes: HashMap<MixLayer, Vec<mix::Node>>,
@@ -238,6 +266,17 @@ impl NymTopology {
         Ok(())
     }
 
+    /// Makes all nodes that have announced new sphinx keys valid for the specified (absolute) epoch
+    /// use them from now on, so that the constructed packets could be processed by those nodes.
+    pub fn use_sphinx_keys_for_epoch(&mut self, absolute_epoch_id: EpochId) {
+        for mix in self.mixes.values_mut().flat_map(|layer| layer.iter_mut()) {
+            mix.use_sphinx_key_for_epoch(absolute_epoch_id)
+        }
+        for gateway in self.gateways.iter_mut() {
+            gateway.use_sphinx_key_for_epoch(absolute_epoch_id)
+        }
+    }
+
     #[must_use]
     pub fn filter_system_version(&self, expected_version: &str) -> Self {
         self.filter_node_versions(expected_version)
@@ -323,6 +362,7 @@ mod converting_mixes_to_vec {
                     "C7cown6dYCLZpLiMFC1PaBmhvLvmJmLDJGeRTbPD45bX",
                 )
                 .unwrap(),
+                upcoming_sphinx_key: None,
                 layer: Layer::One,
                 version: "0.x.0".to_string(),
             };
@@ -359,3 +399,51 @@ mod converting_mixes_to_vec {
         }
     }
 }
+
+#[cfg(test)]
+mod using_sphinx_keys_for_epoch {
+    use super::*;
+    use nym_crypto::asymmetric::identity;
+    use nym_mixnet_contract_common::Layer;
+
+    const CURRENT_KEY: &str = "C7cown6dYCLZpLiMFC1PaBmhvLvmJmLDJGeRTbPD45bX";
+    const UPCOMING_KEY: &str = "3ebjp1Fb9hdcS1AR6AZihgeJiMHkB5jjJUsvqNnfQwU7";
+
+    fn mix_with_upcoming_key(valid_from_epoch: EpochId) -> mix::Node {
+        mix::Node {
+            mix_id: 42,
+            owner: "N/A".to_string(),
+            host: "3.3.3.3".parse().unwrap(),
+            mix_host: "3.3.3.3:1789".parse().unwrap(),
+            identity_key: identity::PublicKey::from_base58_string(
+                "3ebjp1Fb9hdcS1AR6AZihgeJiMHkB5jjJUsvqNnfQwU7",
+            )
+            .unwrap(),
+            sphinx_key: encryption::PublicKey::from_base58_string(CURRENT_KEY).unwrap(),
+            upcoming_sphinx_key: Some(UpcomingSphinxKey {
+                sphinx_key: encryption::PublicKey::from_base58_string(UPCOMING_KEY).unwrap(),
+                valid_from_epoch,
+            }),
+            layer: Layer::One,
+            version: "0.x.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_switches_to_keys_valid_in_the_epoch() {
+        let mut mixes: HashMap<MixLayer, Vec<mix::Node>> = HashMap::new();
+        mixes.insert(1, vec![mix_with_upcoming_key(5)]);
+        mixes.insert(2, vec![mix_with_upcoming_key(6)]);
+        let mut topology = NymTopology::new(mixes, vec![]);
+
+        topology.use_sphinx_keys_for_epoch(5);
+
+        let switched = &topology.mixes()[&1][0];
+        assert_eq!(switched.sphinx_key.to_base58_string(), UPCOMING_KEY);
+        assert!(switched.upcoming_sphinx_key.is_none());
+
+        let not_switched = &topology.mixes()[&2][0];
+        assert_eq!(not_switched.sphinx_key.to_base58_string(), CURRENT_KEY);
+        assert!(not_switched.upcoming_sphinx_key.is_some());
+    }
+}
@@ -1,9 +1,9 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{filter, NetworkAddress};
+use crate::{filter, NetworkAddress, UpcomingSphinxKey};
 use nym_crypto::asymmetric::{encryption, identity};
-use nym_mixnet_contract_common::{Layer, MixId, MixNodeBond};
+use nym_mixnet_contract_common::{EpochId, Layer, MixId, MixNodeBond};
 use nym_sphinx_addressing::nodes::NymNodeRoutingAddress;
 use nym_sphinx_types::Node as SphinxNode;
 use std::convert::{TryFrom, TryInto};
@@ -37,10 +37,24 @@ pub struct Node {
     pub mix_host: SocketAddr,
     pub identity_key: identity::PublicKey,
     pub sphinx_key: encryption::PublicKey, // TODO: or nymsphinx::PublicKey? both are x25519
+    pub upcoming_sphinx_key: Option<UpcomingSphinxKey>,
     pub layer: Layer,
     pub version: String,
 }
 
+impl Node {
+    /// Starts using the announced sphinx key if it's already valid in the specified (absolute) epoch.
+    pub fn use_sphinx_key_for_epoch(&mut self, absolute_epoch_id: EpochId) {
+        if let Some(upcoming) = self.upcoming_sphinx_key.take() {
+            if upcoming.is_active(absolute_epoch_id) {
+                self.sphinx_key = upcoming.sphinx_key
+            } else {
+                self.upcoming_sphinx_key = Some(upcoming)
+            }
+        }
+    }
+}
+
 impl filter::Versioned for Node {
     fn version(&self) -> String {
         self.version.clone()
@@ -86,6 +100,11 @@ impl<'a> TryFrom<&'a MixNodeBond> for Node {
             mix_host,
             identity_key: identity::PublicKey::from_base58_string(&bond.mix_node.identity_key)?,
             sphinx_key: encryption::PublicKey::from_base58_string(&bond.mix_node.sphinx_key)?,
+            upcoming_sphinx_key: bond
+                .upcoming_sphinx_key
+                .as_ref()
+                .map(UpcomingSphinxKey::try_from)
+                .transpose()?,
             layer: bond.layer,
             version: bond.mix_node.version.clone(),
         })
@@ -5,7 +5,7 @@ use crate::currency::{DecCoin, RegisteredCoins};
 use crate::error::TypesError;
 use crate::mixnode::MixNodeCostParams;
 use nym_mixnet_contract_common::{
-    BlockHeight, EpochEventId, IntervalEventId, IntervalRewardingParamsUpdate, MixId,
+    BlockHeight, EpochEventId, IdentityKey, IntervalEventId, IntervalRewardingParamsUpdate, MixId,
     PendingEpochEvent as MixnetContractPendingEpochEvent,
     PendingEpochEventKind as MixnetContractPendingEpochEventKind,
     PendingIntervalEvent as MixnetContractPendingIntervalEvent,
@@ -67,6 +67,12 @@ pub enum PendingEpochEventData {
     UpdateActiveSetSize {
         new_size: u32,
     },
+    PromoteMixnodeSphinxKey {
+        mix_id: MixId,
+    },
+    PromoteGatewaySphinxKey {
+        identity: IdentityKey,
+    },
 }
 
 impl PendingEpochEventData {
@@ -107,6 +113,12 @@ impl PendingEpochEventData {
             MixnetContractPendingEpochEventKind::UpdateActiveSetSize { new_size } => {
                 Ok(PendingEpochEventData::UpdateActiveSetSize { new_size })
             }
+            MixnetContractPendingEpochEventKind::PromoteMixnodeSphinxKey { mix_id } => {
+                Ok(PendingEpochEventData::PromoteMixnodeSphinxKey { mix_id })
+            }
+            MixnetContractPendingEpochEventKind::PromoteGatewaySphinxKey { identity } => {
+                Ok(PendingEpochEventData::PromoteGatewaySphinxKey { identity })
+            }
         }
     }
 }
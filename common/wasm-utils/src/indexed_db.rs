@@ -0,0 +1,161 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Minimal async wrapper around the browser's IndexedDB, where every object store holds raw bytes
+//! under explicitly provided keys.
+
+use futures::Future;
+use js_sys::{Array, Promise, Uint8Array};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode, IdbVersionChangeEvent};
+
+// Attaches the callbacks to the request immediately (so that we could not miss its completion)
+// and returns a future resolving to the result of the request.
+fn request_result(request: &IdbRequest) -> impl Future<Output = Result<JsValue, JsValue>> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    let request = request.clone();
+
+    async move {
+        JsFuture::from(promise).await?;
+        request.result()
+    }
+}
+
+// Safety: when compiled to wasm32 everything is going to be running on a single thread and so there
+// is no shared memory right now. Refer to the comment on `JSWebsocket` for more details.
+unsafe impl Send for Database {}
+unsafe impl Sync for Database {}
+
+#[derive(Debug)]
+pub struct Database {
+    inner: IdbDatabase,
+}
+
+impl Database {
+    /// Opens (or creates) the database with the specified name, making sure all of the
+    /// provided object stores exist.
+    pub async fn open(name: &str, version: u32, object_stores: &[&str]) -> Result<Self, JsValue> {
+        let factory = web_sys::window()
+            .ok_or_else(|| JsValue::from_str("no window available"))?
+            .indexed_db()?
+            .ok_or_else(|| JsValue::from_str("IndexedDB is not supported by this browser"))?;
+
+        let open_request = factory.open_with_u32(name, version)?;
+
+        let upgrade_request = open_request.clone();
+        let object_stores: Vec<String> = object_stores.iter().map(|s| s.to_string()).collect();
+        let on_upgrade_needed = Closure::wrap(Box::new(move |_: IdbVersionChangeEvent| {
+            let db: IdbDatabase = match upgrade_request.result() {
+                Ok(db) => db.unchecked_into(),
+                Err(err) => {
+                    crate::console_error!("failed to upgrade the database: {:?}", err);
+                    return;
+                }
+            };
+            let existing = db.object_store_names();
+            for store in &object_stores {
+                if !existing.contains(store) {
+                    if let Err(err) = db.create_object_store(store) {
+                        crate::console_error!("failed to create object store {store}: {:?}", err);
+                    }
+                }
+            }
+        }) as Box<dyn FnMut(IdbVersionChangeEvent)>);
+        open_request.set_onupgradeneeded(Some(on_upgrade_needed.as_ref().unchecked_ref()));
+
+        let db = request_result(&open_request).await;
+        open_request.set_onupgradeneeded(None);
+
+        Ok(Database {
+            inner: db?.unchecked_into(),
+        })
+    }
+
+    fn object_store(
+        &self,
+        store: &str,
+        mode: IdbTransactionMode,
+    ) -> Result<IdbObjectStore, JsValue> {
+        self.inner
+            .transaction_with_str_and_mode(store, mode)?
+            .object_store(store)
+    }
+
+    pub async fn get(&self, store: &str, key: &JsValue) -> Result<Option<Vec<u8>>, JsValue> {
+        let request = self
+            .object_store(store, IdbTransactionMode::Readonly)?
+            .get(key)?;
+
+        let value = request_result(&request).await?;
+        if value.is_undefined() {
+            Ok(None)
+        } else {
+            Ok(Some(Uint8Array::new(&value).to_vec()))
+        }
+    }
+
+    /// Retrieves all entries of the object store, ordered by their keys.
+    pub async fn get_all(&self, store: &str) -> Result<Vec<(JsValue, Vec<u8>)>, JsValue> {
+        let object_store = self.object_store(store, IdbTransactionMode::Readonly)?;
+        let keys = request_result(&object_store.get_all_keys()?);
+        let values = request_result(&object_store.get_all()?);
+
+        let keys: Array = keys.await?.unchecked_into();
+        let values: Array = values.await?.unchecked_into();
+
+        Ok(keys
+            .iter()
+            .zip(values.iter())
+            .map(|(key, value)| (key, Uint8Array::new(&value).to_vec()))
+            .collect())
+    }
+
+    pub async fn put(&self, store: &str, key: &JsValue, value: &[u8]) -> Result<(), JsValue> {
+        self.put_all(store, &[(key.clone(), value.to_vec())]).await
+    }
+
+    /// Inserts (or overwrites) all of the provided entries within a single transaction.
+    pub async fn put_all(
+        &self,
+        store: &str,
+        entries: &[(JsValue, Vec<u8>)],
+    ) -> Result<(), JsValue> {
+        let object_store = self.object_store(store, IdbTransactionMode::Readwrite)?;
+
+        let mut pending = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            let value = Uint8Array::from(value.as_slice());
+            pending.push(request_result(&object_store.put_with_key(&value, key)?));
+        }
+        for request in pending {
+            request.await?;
+        }
+        Ok(())
+    }
+
+    /// Removes all entries with the provided keys within a single transaction.
+    pub async fn delete_all(&self, store: &str, keys: &[JsValue]) -> Result<(), JsValue> {
+        let object_store = self.object_store(store, IdbTransactionMode::Readwrite)?;
+
+        let mut pending = Vec::with_capacity(keys.len());
+        for key in keys {
+            pending.push(request_result(&object_store.delete(key)?));
+        }
+        for request in pending {
+            request.await?;
+        }
+        Ok(())
+    }
+
+    pub async fn clear(&self, store: &str) -> Result<(), JsValue> {
+        let request = self
+            .object_store(store, IdbTransactionMode::Readwrite)?
+            .clear()?;
+        request_result(&request).await.map(|_| ())
+    }
+}
@@ -6,6 +6,9 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
 
+#[cfg(feature = "indexed-db")]
+pub mod indexed_db;
+
 #[cfg(feature = "websocket")]
 pub mod websocket;
 
@@ -261,6 +261,11 @@ pub fn execute(
                 deps, info, new_config, owner,
             )
         }
+        ExecuteMsg::AnnounceMixnodeSphinxKey { sphinx_key } => {
+            crate::mixnodes::transactions::try_announce_mixnode_sphinx_key(
+                deps, env, info, sphinx_key,
+            )
+        }
 
         // gateway-related:
         ExecuteMsg::BondGateway {
@@ -291,6 +296,11 @@ pub fn execute(
         ExecuteMsg::UnbondGatewayOnBehalf { owner } => {
             crate::gateways::transactions::try_remove_gateway_on_behalf(deps, info, owner)
         }
+        ExecuteMsg::AnnounceGatewaySphinxKey { sphinx_key } => {
+            crate::gateways::transactions::try_announce_gateway_sphinx_key(
+                deps, env, info, sphinx_key,
+            )
+        }
 
         // delegation-related:
         ExecuteMsg::DelegateToMixnode { mix_id } => {
@@ -2,14 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::storage;
+use crate::interval::storage as interval_storage;
 use crate::mixnet_contract_settings::storage as mixnet_params_storage;
 use crate::support::helpers::{
-    ensure_no_existing_bond, validate_node_identity_signature, validate_pledge,
+    ensure_no_existing_bond, validate_node_identity_signature, validate_pledge, validate_sphinx_key,
 };
 use cosmwasm_std::{wasm_execute, Addr, BankMsg, Coin, DepsMut, Env, MessageInfo, Response};
 use mixnet_contract_common::error::MixnetContractError;
-use mixnet_contract_common::events::{new_gateway_bonding_event, new_gateway_unbonding_event};
-use mixnet_contract_common::{Gateway, GatewayBond};
+use mixnet_contract_common::events::{
+    new_gateway_bonding_event, new_gateway_sphinx_key_announcement_event,
+    new_gateway_unbonding_event,
+};
+use mixnet_contract_common::pending_events::PendingEpochEventKind;
+use mixnet_contract_common::{Gateway, GatewayBond, SphinxKey};
 use vesting_contract_common::messages::ExecuteMsg as VestingContractExecuteMsg;
 
 pub fn try_add_gateway(
@@ -175,10 +180,58 @@ pub(crate) fn _try_remove_gateway(
     )))
 }
 
+// note: announcing new sphinx keys does not involve any funds, so it is always performed
+// directly by the owner, even if the gateway has been bonded via a proxy
+pub fn try_announce_gateway_sphinx_key(
+    deps: DepsMut<'_>,
+    env: Env,
+    info: MessageInfo,
+    sphinx_key: SphinxKey,
+) -> Result<Response, MixnetContractError> {
+    let existing_bond = match storage::gateways()
+        .idx
+        .owner
+        .item(deps.storage, info.sender.clone())?
+    {
+        Some(record) => record.1,
+        None => return Err(MixnetContractError::NoAssociatedGatewayBond { owner: info.sender }),
+    };
+
+    validate_sphinx_key(&sphinx_key)?;
+
+    let current_epoch =
+        interval_storage::current_interval(deps.storage)?.current_epoch_absolute_id();
+
+    let mut updated_bond = existing_bond.clone();
+    let upcoming = updated_bond.announce_sphinx_key(sphinx_key, current_epoch);
+    let announcement_event = new_gateway_sphinx_key_announcement_event(
+        &info.sender,
+        existing_bond.identity(),
+        &upcoming.sphinx_key,
+        upcoming.valid_from_epoch,
+    );
+
+    storage::gateways().replace(
+        deps.storage,
+        existing_bond.identity(),
+        Some(&updated_bond),
+        Some(&existing_bond),
+    )?;
+
+    // make the announced key the current key of the gateway once the epoch is over
+    let epoch_event = PendingEpochEventKind::PromoteGatewaySphinxKey {
+        identity: existing_bond.gateway.identity_key,
+    };
+    interval_storage::push_new_epoch_event(deps.storage, &env, epoch_event)?;
+
+    Ok(Response::new().add_event(announcement_event))
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::contract::execute;
-    use crate::gateways::transactions::try_add_gateway;
+    use crate::gateways::storage;
+    use crate::gateways::transactions::{try_add_gateway, try_announce_gateway_sphinx_key};
     use crate::interval::pending_events;
     use crate::mixnet_contract_settings::storage::minimum_gateway_pledge;
     use crate::support::tests;
@@ -188,7 +241,7 @@ pub mod tests {
     use cosmwasm_std::{coin, Addr, BankMsg, Response, Uint128};
     use mixnet_contract_common::error::MixnetContractError;
     use mixnet_contract_common::events::new_gateway_unbonding_event;
-    use mixnet_contract_common::ExecuteMsg;
+    use mixnet_contract_common::{ExecuteMsg, UpcomingSphinxKey};
 
     #[test]
     fn gateway_add() {
@@ -367,4 +420,94 @@ pub mod tests {
         assert_eq!(1, gateway_bonds.len());
         assert_eq!(&Addr::unchecked("bob"), gateway_bonds[0].owner());
     }
+
+    #[test]
+    fn announcing_gateway_sphinx_key() {
+        let mut test = test_helpers::TestSetup::new();
+        let env = test.env();
+
+        let sender = "alice";
+        let info = mock_info(sender, &[]);
+        let new_key = nym_crypto::asymmetric::encryption::KeyPair::new(&mut test.rng)
+            .public_key()
+            .to_base58_string();
+
+        // try announcing a key for a non existing gateway bond
+        let res = try_announce_gateway_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info.clone(),
+            new_key.clone(),
+        );
+        assert_eq!(
+            res,
+            Err(MixnetContractError::NoAssociatedGatewayBond {
+                owner: Addr::unchecked(sender)
+            })
+        );
+
+        let identity = test_helpers::add_gateway(
+            &mut test.rng,
+            test.deps.as_mut(),
+            env.clone(),
+            sender,
+            tests::fixtures::good_gateway_pledge(),
+        );
+        let original_key = storage::gateways()
+            .load(test.deps().storage, &identity)
+            .unwrap()
+            .gateway
+            .sphinx_key;
+
+        // malformed keys are rejected
+        let res = try_announce_gateway_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info.clone(),
+            "foomp".into(),
+        );
+        assert!(matches!(
+            res,
+            Err(MixnetContractError::MalformedX25519SphinxKey(_))
+        ));
+
+        let res = try_announce_gateway_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info.clone(),
+            new_key.clone(),
+        );
+        assert!(res.is_ok());
+
+        // the announced key is only going to be used from the next epoch onwards
+        let epoch_id = test.current_interval().current_epoch_absolute_id();
+        let gateway = storage::gateways()
+            .load(test.deps().storage, &identity)
+            .unwrap();
+        assert_eq!(gateway.gateway.sphinx_key, original_key);
+        assert_eq!(gateway.sphinx_key_for_epoch(epoch_id), original_key);
+        assert_eq!(gateway.sphinx_key_for_epoch(epoch_id + 1), new_key);
+
+        // once it's in use, announcing another key makes it the current key of the gateway
+        test.skip_to_next_epoch();
+        let another_key = nym_crypto::asymmetric::encryption::KeyPair::new(&mut test.rng)
+            .public_key()
+            .to_base58_string();
+        let res = try_announce_gateway_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info,
+            another_key.clone(),
+        );
+        assert!(res.is_ok());
+
+        let gateway = storage::gateways()
+            .load(test.deps().storage, &identity)
+            .unwrap();
+        assert_eq!(gateway.gateway.sphinx_key, new_key);
+        assert_eq!(
+            gateway.upcoming_sphinx_key,
+            Some(UpcomingSphinxKey::new(another_key, epoch_id + 2))
+        );
+    }
 }
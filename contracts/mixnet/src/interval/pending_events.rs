@@ -3,6 +3,7 @@
 
 use crate::delegations;
 use crate::delegations::storage as delegations_storage;
+use crate::gateways::storage as gateways_storage;
 use crate::interval::helpers::change_interval_config;
 use crate::interval::storage;
 use crate::mixnet_contract_settings::storage as mixnet_params_storage;
@@ -14,7 +15,8 @@ use cosmwasm_std::{wasm_execute, Addr, Coin, DepsMut, Env, Response};
 use mixnet_contract_common::error::MixnetContractError;
 use mixnet_contract_common::events::{
     new_active_set_update_event, new_delegation_event, new_delegation_on_unbonded_node_event,
-    new_mixnode_cost_params_update_event, new_mixnode_unbonding_event, new_pledge_increase_event,
+    new_gateway_sphinx_key_promotion_event, new_mixnode_cost_params_update_event,
+    new_mixnode_sphinx_key_promotion_event, new_mixnode_unbonding_event, new_pledge_increase_event,
     new_rewarding_params_update_event, new_undelegation_event,
 };
 use mixnet_contract_common::mixnode::MixNodeCostParams;
@@ -23,7 +25,7 @@ use mixnet_contract_common::pending_events::{
     PendingIntervalEventKind,
 };
 use mixnet_contract_common::reward_params::IntervalRewardingParamsUpdate;
-use mixnet_contract_common::{BlockHeight, Delegation, MixId};
+use mixnet_contract_common::{BlockHeight, Delegation, IdentityKey, MixId};
 use vesting_contract_common::messages::ExecuteMsg as VestingContractExecuteMsg;
 
 pub(crate) trait ContractExecutableEvent {
@@ -295,6 +297,96 @@ pub(crate) fn increase_pledge(
     Ok(Response::new().add_event(new_pledge_increase_event(created_at, mix_id, &increase)))
 }
 
+pub(crate) fn promote_mixnode_sphinx_key(
+    deps: DepsMut<'_>,
+    created_at: BlockHeight,
+    mix_id: MixId,
+) -> Result<Response, MixnetContractError> {
+    // the node might have unbonded in the meantime
+    let existing_bond = match mixnodes_storage::mixnode_bonds().may_load(deps.storage, mix_id)? {
+        Some(bond) => bond,
+        None => return Ok(Response::new()),
+    };
+
+    // the queue is only emptied upon the epoch finishing, so the keys announced for the next epoch
+    // are already supposed to be used
+    let next_epoch = storage::current_interval(deps.storage)?
+        .current_epoch_absolute_id()
+        .saturating_add(1);
+
+    let mut updated_bond = existing_bond.clone();
+    if !updated_bond.promote_sphinx_key(next_epoch) {
+        // either the key has already been promoted or another one has been announced in its place
+        return Ok(Response::new());
+    }
+
+    // we can't fail the whole queue if, somehow, the key is already used by another node.
+    // the announced key is still going to be returned by `sphinx_key_for_epoch`
+    if let Some((_, other_bond)) = mixnodes_storage::mixnode_bonds()
+        .idx
+        .sphinx_key
+        .item(deps.storage, updated_bond.mix_node.sphinx_key.clone())?
+    {
+        if other_bond.mix_id != mix_id {
+            return Ok(Response::new());
+        }
+    }
+
+    mixnodes_storage::mixnode_bonds().replace(
+        deps.storage,
+        mix_id,
+        Some(&updated_bond),
+        Some(&existing_bond),
+    )?;
+
+    Ok(
+        Response::new().add_event(new_mixnode_sphinx_key_promotion_event(
+            created_at,
+            mix_id,
+            &updated_bond.mix_node.sphinx_key,
+        )),
+    )
+}
+
+pub(crate) fn promote_gateway_sphinx_key(
+    deps: DepsMut<'_>,
+    created_at: BlockHeight,
+    identity: IdentityKey,
+) -> Result<Response, MixnetContractError> {
+    // the gateway might have unbonded in the meantime
+    let existing_bond = match gateways_storage::gateways().may_load(deps.storage, &identity)? {
+        Some(bond) => bond,
+        None => return Ok(Response::new()),
+    };
+
+    // the queue is only emptied upon the epoch finishing, so the keys announced for the next epoch
+    // are already supposed to be used
+    let next_epoch = storage::current_interval(deps.storage)?
+        .current_epoch_absolute_id()
+        .saturating_add(1);
+
+    let mut updated_bond = existing_bond.clone();
+    if !updated_bond.promote_sphinx_key(next_epoch) {
+        // either the key has already been promoted or another one has been announced in its place
+        return Ok(Response::new());
+    }
+
+    gateways_storage::gateways().replace(
+        deps.storage,
+        &identity,
+        Some(&updated_bond),
+        Some(&existing_bond),
+    )?;
+
+    Ok(
+        Response::new().add_event(new_gateway_sphinx_key_promotion_event(
+            created_at,
+            &identity,
+            &updated_bond.gateway.sphinx_key,
+        )),
+    )
+}
+
 impl ContractExecutableEvent for PendingEpochEventData {
     fn execute(self, deps: DepsMut<'_>, env: &Env) -> Result<Response, MixnetContractError> {
         // note that the basic validation on all those events was already performed before
@@ -320,6 +412,12 @@ impl ContractExecutableEvent for PendingEpochEventData {
             PendingEpochEventKind::UpdateActiveSetSize { new_size } => {
                 update_active_set_size(deps, self.created_at, new_size)
             }
+            PendingEpochEventKind::PromoteMixnodeSphinxKey { mix_id } => {
+                promote_mixnode_sphinx_key(deps, self.created_at, mix_id)
+            }
+            PendingEpochEventKind::PromoteGatewaySphinxKey { identity } => {
+                promote_gateway_sphinx_key(deps, self.created_at, identity)
+            }
         }
     }
 }
@@ -1412,6 +1510,130 @@ mod tests {
         assert_eq!(updated.active_set_size, 50)
     }
 
+    #[cfg(test)]
+    mod promoting_sphinx_keys {
+        use super::*;
+        use crate::gateways::transactions::try_announce_gateway_sphinx_key;
+        use crate::mixnodes::transactions::try_announce_mixnode_sphinx_key;
+        use crate::support::tests;
+        use cosmwasm_std::testing::mock_info;
+
+        fn new_sphinx_key(test: &mut TestSetup) -> String {
+            nym_crypto::asymmetric::encryption::KeyPair::new(&mut test.rng)
+                .public_key()
+                .to_base58_string()
+        }
+
+        #[test]
+        fn doesnt_do_anything_if_node_has_unbonded() {
+            let mut test = TestSetup::new();
+
+            let res = promote_mixnode_sphinx_key(test.deps_mut(), 123, 42);
+            assert_eq!(res, Ok(Response::default()));
+
+            let res = promote_gateway_sphinx_key(test.deps_mut(), 123, "foomp".into());
+            assert_eq!(res, Ok(Response::default()));
+        }
+
+        #[test]
+        fn makes_announced_key_the_current_mixnode_key_once_the_epoch_is_over() {
+            let mut test = TestSetup::new();
+            let mix_id = test.add_dummy_mixnode("mix-owner", None);
+            let new_key = new_sphinx_key(&mut test);
+
+            let env = test.env();
+            try_announce_mixnode_sphinx_key(
+                test.deps_mut(),
+                env,
+                mock_info("mix-owner", &[]),
+                new_key.clone(),
+            )
+            .unwrap();
+
+            let epoch_events = test.pending_epoch_events();
+            assert_eq!(
+                epoch_events[0].kind,
+                PendingEpochEventKind::PromoteMixnodeSphinxKey { mix_id }
+            );
+
+            test.skip_to_current_epoch_end();
+            test.execute_all_pending_events();
+
+            let bond = mixnodes_storage::mixnode_bonds()
+                .load(test.deps().storage, mix_id)
+                .unwrap();
+            assert_eq!(bond.mix_node.sphinx_key, new_key);
+            assert!(bond.upcoming_sphinx_key.is_none());
+        }
+
+        #[test]
+        fn makes_announced_key_the_current_gateway_key_once_the_epoch_is_over() {
+            let mut test = TestSetup::new();
+            let env = test.env();
+            let identity = test_helpers::add_gateway(
+                &mut test.rng,
+                test.deps.as_mut(),
+                env.clone(),
+                "gateway-owner",
+                tests::fixtures::good_gateway_pledge(),
+            );
+            let new_key = new_sphinx_key(&mut test);
+
+            try_announce_gateway_sphinx_key(
+                test.deps_mut(),
+                env,
+                mock_info("gateway-owner", &[]),
+                new_key.clone(),
+            )
+            .unwrap();
+
+            let epoch_events = test.pending_epoch_events();
+            assert_eq!(
+                epoch_events[0].kind,
+                PendingEpochEventKind::PromoteGatewaySphinxKey {
+                    identity: identity.clone()
+                }
+            );
+
+            test.skip_to_current_epoch_end();
+            test.execute_all_pending_events();
+
+            let bond = gateways_storage::gateways()
+                .load(test.deps().storage, &identity)
+                .unwrap();
+            assert_eq!(bond.gateway.sphinx_key, new_key);
+            assert!(bond.upcoming_sphinx_key.is_none());
+        }
+
+        #[test]
+        fn only_the_latest_announced_key_is_promoted() {
+            let mut test = TestSetup::new();
+            let mix_id = test.add_dummy_mixnode("mix-owner", None);
+            let first_key = new_sphinx_key(&mut test);
+            let second_key = new_sphinx_key(&mut test);
+
+            for key in [first_key, second_key.clone()] {
+                let env = test.env();
+                try_announce_mixnode_sphinx_key(
+                    test.deps_mut(),
+                    env,
+                    mock_info("mix-owner", &[]),
+                    key,
+                )
+                .unwrap();
+            }
+
+            test.skip_to_current_epoch_end();
+            test.execute_all_pending_events();
+
+            let bond = mixnodes_storage::mixnode_bonds()
+                .load(test.deps().storage, mix_id)
+                .unwrap();
+            assert_eq!(bond.mix_node.sphinx_key, second_key);
+            assert!(bond.upcoming_sphinx_key.is_none());
+        }
+    }
+
     #[cfg(test)]
     mod changing_mix_cost_params {
         use super::*;
@@ -11,18 +11,18 @@ use crate::mixnodes::helpers::{
 };
 use crate::support::helpers::{
     ensure_bonded, ensure_is_authorized, ensure_no_existing_bond, ensure_proxy_match,
-    validate_node_identity_signature, validate_pledge,
+    validate_node_identity_signature, validate_pledge, validate_sphinx_key,
 };
 use cosmwasm_std::{coin, Addr, Coin, DepsMut, Env, MessageInfo, Response, Storage};
 use mixnet_contract_common::error::MixnetContractError;
 use mixnet_contract_common::events::{
     new_mixnode_bonding_event, new_mixnode_config_update_event,
-    new_mixnode_pending_cost_params_update_event, new_pending_mixnode_unbonding_event,
-    new_pending_pledge_increase_event,
+    new_mixnode_pending_cost_params_update_event, new_mixnode_sphinx_key_announcement_event,
+    new_pending_mixnode_unbonding_event, new_pending_pledge_increase_event,
 };
 use mixnet_contract_common::mixnode::{MixNodeConfigUpdate, MixNodeCostParams};
 use mixnet_contract_common::pending_events::{PendingEpochEventKind, PendingIntervalEventKind};
-use mixnet_contract_common::{Layer, MixId, MixNode};
+use mixnet_contract_common::{Layer, MixId, MixNode, SphinxKey};
 
 pub(crate) fn update_mixnode_layer(
     mix_id: MixId,
@@ -312,6 +312,47 @@ pub(crate) fn _try_update_mixnode_config(
     Ok(Response::new().add_event(cfg_update_event))
 }
 
+// note: unlike other operator actions, announcing new sphinx keys does not involve any funds,
+// so it is always performed directly by the owner, even if the node has been bonded via a proxy
+pub(crate) fn try_announce_mixnode_sphinx_key(
+    deps: DepsMut<'_>,
+    env: Env,
+    info: MessageInfo,
+    sphinx_key: SphinxKey,
+) -> Result<Response, MixnetContractError> {
+    let existing_bond = must_get_mixnode_bond_by_owner(deps.storage, &info.sender)?;
+
+    ensure_bonded(&existing_bond)?;
+    validate_sphinx_key(&sphinx_key)?;
+
+    let current_epoch =
+        interval_storage::current_interval(deps.storage)?.current_epoch_absolute_id();
+
+    let mut updated_bond = existing_bond.clone();
+    let upcoming = updated_bond.announce_sphinx_key(sphinx_key, current_epoch);
+    let announcement_event = new_mixnode_sphinx_key_announcement_event(
+        existing_bond.mix_id,
+        &info.sender,
+        &upcoming.sphinx_key,
+        upcoming.valid_from_epoch,
+    );
+
+    storage::mixnode_bonds().replace(
+        deps.storage,
+        existing_bond.mix_id,
+        Some(&updated_bond),
+        Some(&existing_bond),
+    )?;
+
+    // make the announced key the current key of the node once the epoch is over
+    let epoch_event = PendingEpochEventKind::PromoteMixnodeSphinxKey {
+        mix_id: existing_bond.mix_id,
+    };
+    interval_storage::push_new_epoch_event(deps.storage, &env, epoch_event)?;
+
+    Ok(Response::new().add_event(announcement_event))
+}
+
 pub(crate) fn try_update_mixnode_cost_params(
     deps: DepsMut<'_>,
     env: Env,
@@ -374,7 +415,9 @@ pub mod tests {
     use crate::support::tests::{fixtures, test_helpers};
     use cosmwasm_std::testing::{mock_env, mock_info};
     use cosmwasm_std::{Order, StdResult, Uint128};
-    use mixnet_contract_common::{ExecuteMsg, Layer, LayerDistribution, Percent};
+    use mixnet_contract_common::{
+        ExecuteMsg, Layer, LayerDistribution, Percent, UpcomingSphinxKey,
+    };
 
     #[test]
     fn mixnode_add() {
@@ -623,6 +666,93 @@ pub mod tests {
         assert_eq!(res, Err(MixnetContractError::MixnodeIsUnbonding { mix_id }))
     }
 
+    #[test]
+    fn announcing_mixnode_sphinx_key() {
+        let mut test = test_helpers::TestSetup::new();
+        let env = test.env();
+
+        let sender = "alice";
+        let info = mock_info(sender, &[]);
+        let new_key = nym_crypto::asymmetric::encryption::KeyPair::new(&mut test.rng)
+            .public_key()
+            .to_base58_string();
+
+        // try announcing a key for a non existing mixnode bond
+        let res = try_announce_mixnode_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info.clone(),
+            new_key.clone(),
+        );
+        assert_eq!(
+            res,
+            Err(MixnetContractError::NoAssociatedMixNodeBond {
+                owner: Addr::unchecked(sender)
+            })
+        );
+
+        let mix_id = test.add_dummy_mixnode(sender, None);
+        let original_key =
+            must_get_mixnode_bond_by_owner(test.deps().storage, &Addr::unchecked(sender))
+                .unwrap()
+                .mix_node
+                .sphinx_key;
+
+        // malformed keys are rejected
+        let res = try_announce_mixnode_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info.clone(),
+            "foomp".into(),
+        );
+        assert!(matches!(
+            res,
+            Err(MixnetContractError::MalformedX25519SphinxKey(_))
+        ));
+
+        let res = try_announce_mixnode_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info.clone(),
+            new_key.clone(),
+        );
+        assert!(res.is_ok());
+
+        // the announced key is only going to be used from the next epoch onwards
+        let epoch_id = test.current_interval().current_epoch_absolute_id();
+        let mix =
+            must_get_mixnode_bond_by_owner(test.deps().storage, &Addr::unchecked(sender)).unwrap();
+        assert_eq!(mix.mix_node.sphinx_key, original_key);
+        assert_eq!(mix.sphinx_key_for_epoch(epoch_id), original_key);
+        assert_eq!(mix.sphinx_key_for_epoch(epoch_id + 1), new_key);
+
+        // once it's in use, announcing another key makes it the current key of the node
+        test.skip_to_next_epoch();
+        let another_key = nym_crypto::asymmetric::encryption::KeyPair::new(&mut test.rng)
+            .public_key()
+            .to_base58_string();
+        let res = try_announce_mixnode_sphinx_key(
+            test.deps_mut(),
+            env.clone(),
+            info.clone(),
+            another_key.clone(),
+        );
+        assert!(res.is_ok());
+
+        let mix =
+            must_get_mixnode_bond_by_owner(test.deps().storage, &Addr::unchecked(sender)).unwrap();
+        assert_eq!(mix.mix_node.sphinx_key, new_key);
+        assert_eq!(
+            mix.upcoming_sphinx_key,
+            Some(UpcomingSphinxKey::new(another_key, epoch_id + 2))
+        );
+
+        // but we cannot announce any keys whilst the mixnode is already unbonding
+        test.start_unbonding_mixnode(mix_id);
+        let res = try_announce_mixnode_sphinx_key(test.deps_mut(), env.clone(), info, new_key);
+        assert_eq!(res, Err(MixnetContractError::MixnodeIsUnbonding { mix_id }))
+    }
+
     #[test]
     fn updating_mixnode_cost_params() {
         let mut deps = test_helpers::init_contract();
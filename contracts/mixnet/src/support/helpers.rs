@@ -5,7 +5,7 @@ use crate::gateways::storage as gateways_storage;
 use crate::mixnodes::storage as mixnodes_storage;
 use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Deps, Response, Storage};
 use mixnet_contract_common::error::MixnetContractError;
-use mixnet_contract_common::{IdentityKeyRef, MixNodeBond};
+use mixnet_contract_common::{IdentityKeyRef, MixNodeBond, SphinxKeyRef};
 
 // helper trait to attach `Msg` to a response if it's provided
 pub(crate) trait AttachOptionalMessage<T> {
@@ -212,6 +212,21 @@ pub fn validate_family_signature(
     validate_signature(deps, family_member.as_bytes(), signature, family_head)
 }
 
+pub(crate) fn validate_sphinx_key(sphinx_key: SphinxKeyRef<'_>) -> Result<(), MixnetContractError> {
+    let mut key_bytes = [0u8; 32];
+    let used_bytes = bs58::decode(sphinx_key)
+        .into(&mut key_bytes)
+        .map_err(|err| MixnetContractError::MalformedX25519SphinxKey(err.to_string()))?;
+
+    if used_bytes != 32 {
+        return Err(MixnetContractError::MalformedX25519SphinxKey(
+            "Too few bytes provided for the public key".into(),
+        ));
+    }
+
+    Ok(())
+}
+
 pub(crate) fn validate_signature(
     deps: Deps<'_>,
     signed_bytes: &[u8],
@@ -87,10 +87,10 @@ impl fmt::Display for GatewayRequestsError {
             RequestOfInvalidSize(actual) =>
                 write!(
                 f,
-                "received request had invalid size. (actual: {}, but expected one of: {} (ACK), {} (REGULAR), {}, {}, {} (EXTENDED))",
+                "received request had invalid size. (actual: {}, but expected one of: {} (ACK), {} (REGULAR), {}, {}, {} (EXTENDED), {} (OUTFOX))",
                 actual, PacketSize::AckPacket.size(), PacketSize::RegularPacket.size(),
                 PacketSize::ExtendedPacket8.size(), PacketSize::ExtendedPacket16.size(),
-                PacketSize::ExtendedPacket32.size()
+                PacketSize::ExtendedPacket32.size(), PacketSize::OutfoxRegularPacket.size()
             ),
             MalformedSphinxPacket => write!(f, "received sphinx packet was malformed"),
             MalformedEncryption => write!(f, "the received encrypted data was malformed"),
@@ -211,11 +211,19 @@ pub enum ServerResponse {
         protocol_version: Option<u8>,
         status: bool,
         bandwidth_remaining: i64,
+        /// Number of messages for the client the gateway had to drop (because its inbox was full
+        /// or the messages have expired) since it was last connected.
+        #[serde(default)]
+        dropped_messages: i64,
     },
     Register {
         #[serde(default)]
         protocol_version: Option<u8>,
         status: bool,
+        /// Number of messages for the client the gateway had to drop (because its inbox was full
+        /// or the messages have expired) since it was last connected.
+        #[serde(default)]
+        dropped_messages: i64,
     },
     Bandwidth {
         available_total: i64,
@@ -0,0 +1,32 @@
+/*
+ * Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+-- number and total size of the messages currently stored for each client. It's kept up to date
+-- by the triggers below, so that enforcing the inbox quotas wouldn't require scanning the whole store
+CREATE TABLE inbox_usage
+(
+    client_address_bs58 TEXT    NOT NULL PRIMARY KEY UNIQUE,
+    messages            INTEGER NOT NULL,
+    bytes               INTEGER NOT NULL
+);
+
+INSERT INTO inbox_usage(client_address_bs58, messages, bytes)
+SELECT client_address_bs58, COUNT(*), SUM(LENGTH(content))
+FROM message_store
+GROUP BY client_address_bs58;
+
+CREATE TRIGGER message_store_inserted AFTER INSERT ON message_store
+BEGIN
+    INSERT INTO inbox_usage(client_address_bs58, messages, bytes)
+    VALUES (NEW.client_address_bs58, 1, LENGTH(NEW.content))
+    ON CONFLICT(client_address_bs58) DO UPDATE SET messages = messages + 1, bytes = bytes + excluded.bytes;
+END;
+
+CREATE TRIGGER message_store_deleted AFTER DELETE ON message_store
+BEGIN
+    UPDATE inbox_usage
+    SET messages = messages - 1, bytes = bytes - LENGTH(OLD.content)
+    WHERE client_address_bs58 = OLD.client_address_bs58;
+END;
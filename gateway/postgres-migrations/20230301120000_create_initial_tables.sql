@@ -0,0 +1,25 @@
+/*
+ * Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+CREATE TABLE shared_keys
+(
+    client_address_bs58                      TEXT NOT NULL PRIMARY KEY,
+    derived_aes128_ctr_blake3_hmac_keys_bs58 TEXT NOT NULL
+);
+
+CREATE TABLE message_store
+(
+    id                  BIGSERIAL NOT NULL PRIMARY KEY,
+    client_address_bs58 TEXT      NOT NULL,
+    content             BYTEA     NOT NULL
+);
+
+CREATE TABLE available_bandwidth
+(
+    client_address_bs58 TEXT   NOT NULL PRIMARY KEY,
+    available           BIGINT NOT NULL
+);
+
+CREATE INDEX message_store_index ON message_store (client_address_bs58, id);
@@ -0,0 +1,18 @@
+/*
+ * Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+-- unix timestamp (in seconds) of when the message got stored.
+-- the messages stored before this migration are treated as if they have just been received
+ALTER TABLE message_store ADD COLUMN stored_at BIGINT NOT NULL DEFAULT CAST(EXTRACT(EPOCH FROM NOW()) AS BIGINT);
+ALTER TABLE message_store ALTER COLUMN stored_at DROP DEFAULT;
+
+CREATE INDEX message_store_stored_at_index ON message_store (stored_at);
+
+-- number of messages that got dropped (due to inbox quotas or expiry) since the client has last connected
+CREATE TABLE dropped_messages
+(
+    client_address_bs58 TEXT   NOT NULL PRIMARY KEY,
+    dropped             BIGINT NOT NULL
+);
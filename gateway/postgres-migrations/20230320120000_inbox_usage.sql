@@ -0,0 +1,41 @@
+/*
+ * Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+-- number and total size of the messages currently stored for each client. It's kept up to date
+-- by the trigger below, so that enforcing the inbox quotas wouldn't require scanning the whole store
+CREATE TABLE inbox_usage
+(
+    client_address_bs58 TEXT   NOT NULL PRIMARY KEY,
+    messages            BIGINT NOT NULL,
+    bytes               BIGINT NOT NULL
+);
+
+INSERT INTO inbox_usage(client_address_bs58, messages, bytes)
+SELECT client_address_bs58, COUNT(*), SUM(LENGTH(content))
+FROM message_store
+GROUP BY client_address_bs58;
+
+CREATE FUNCTION update_inbox_usage() RETURNS TRIGGER AS
+$$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO inbox_usage(client_address_bs58, messages, bytes)
+        VALUES (NEW.client_address_bs58, 1, LENGTH(NEW.content))
+        ON CONFLICT (client_address_bs58)
+        DO UPDATE SET messages = inbox_usage.messages + 1, bytes = inbox_usage.bytes + EXCLUDED.bytes;
+    ELSE
+        UPDATE inbox_usage
+        SET messages = messages - 1, bytes = bytes - LENGTH(OLD.content)
+        WHERE client_address_bs58 = OLD.client_address_bs58;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER message_store_inbox_usage
+    AFTER INSERT OR DELETE
+    ON message_store
+    FOR EACH ROW
+EXECUTE FUNCTION update_inbox_usage();
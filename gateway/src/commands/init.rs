@@ -36,6 +36,14 @@ pub struct Init {
     #[clap(long)]
     clients_port: Option<u16>,
 
+    /// Enable/disable the http api exposing the gateway health, metrics and description
+    #[clap(long)]
+    enabled_http_api: Option<bool>,
+
+    /// The port on which the gateway will be listening for http requests
+    #[clap(long)]
+    http_api_port: Option<u16>,
+
     /// The host that will be reported to the directory server
     #[clap(long)]
     // TODO: could this be changed to `Option<url::Url>`?
@@ -86,6 +94,8 @@ impl From<Init> for OverrideConfig {
             wallet_address: Some(init_config.wallet_address),
             mix_port: init_config.mix_port,
             clients_port: init_config.clients_port,
+            enabled_http_api: init_config.enabled_http_api,
+            http_api_port: init_config.http_api_port,
             datastore: init_config.datastore,
             announce_host: init_config.announce_host,
             nym_apis: init_config.nym_apis,
@@ -175,6 +185,8 @@ mod tests {
             wallet_address: "n1z9egw0knv47nmur0p8vk4rcx59h9gg4zjx9ede".parse().unwrap(),
             mix_port: Some(42),
             clients_port: Some(43),
+            enabled_http_api: None,
+            http_api_port: None,
             announce_host: Some("foo-announce-host".to_string()),
             datastore: Some("/foo-datastore".parse().unwrap()),
             nym_apis: None,
@@ -198,8 +210,8 @@ mod tests {
         };
 
         // The test is really if this instantiates with InMemStorage without panics
+        let storage = InMemStorage::new(config.get_message_retrieval_limit());
         let _gateway =
-            Gateway::new_from_keys_and_storage(config, identity_keys, sphinx_keys, InMemStorage)
-                .await;
+            Gateway::new_from_keys_and_storage(config, identity_keys, sphinx_keys, storage).await;
     }
 }
@@ -15,6 +15,7 @@ use std::net::IpAddr;
 use std::path::PathBuf;
 use validator_client::nyxd::{self, AccountId};
 
+pub(crate) mod describe;
 pub(crate) mod init;
 pub(crate) mod node_details;
 pub(crate) mod run;
@@ -23,6 +24,9 @@ pub(crate) mod upgrade;
 
 #[derive(Subcommand)]
 pub(crate) enum Commands {
+    /// Describe your gateway and tell people why they should use it
+    Describe(describe::Describe),
+
     /// Initialise the gateway
     Init(init::Init),
 
@@ -52,6 +56,8 @@ pub(crate) struct OverrideConfig {
     wallet_address: Option<nyxd::AccountId>,
     mix_port: Option<u16>,
     clients_port: Option<u16>,
+    enabled_http_api: Option<bool>,
+    http_api_port: Option<u16>,
     datastore: Option<PathBuf>,
     announce_host: Option<String>,
     enabled_statistics: Option<bool>,
@@ -62,12 +68,13 @@ pub(crate) struct OverrideConfig {
     only_coconut_credentials: Option<bool>,
 }
 
-pub(crate) async fn execute(args: Cli) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn execute(args: Cli) -> Result<(), Box<dyn Error + Send + Sync>> {
     let bin_name = "nym-gateway";
 
     let output = args.output();
 
     match args.command {
+        Commands::Describe(m) => describe::execute(m)?,
         Commands::Init(m) => init::execute(m, output.clone()).await?,
         Commands::NodeDetails(m) => node_details::execute(m, output.clone()).await?,
         Commands::Run(m) => run::execute(m, output.clone()).await?,
@@ -100,6 +107,8 @@ pub(crate) fn override_config(
     config = config
         .with_optional(Config::with_mix_port, args.mix_port)
         .with_optional(Config::with_clients_port, args.clients_port)
+        .with_optional(Config::with_enabled_http_api, args.enabled_http_api)
+        .with_optional(Config::with_http_api_port, args.http_api_port)
         .with_optional_custom_env(
             Config::with_custom_nym_apis,
             args.nym_apis,
@@ -34,6 +34,14 @@ pub struct Run {
     #[clap(long)]
     clients_port: Option<u16>,
 
+    /// Enable/disable the http api exposing the gateway health, metrics and description
+    #[clap(long)]
+    enabled_http_api: Option<bool>,
+
+    /// The port on which the gateway will be listening for http requests
+    #[clap(long)]
+    http_api_port: Option<u16>,
+
     /// The host that will be reported to the directory server
     #[clap(long)]
     // TODO: could this be changed to `Option<url::Url>`?
@@ -84,6 +92,8 @@ impl From<Run> for OverrideConfig {
             wallet_address: run_config.wallet_address,
             mix_port: run_config.mix_port,
             clients_port: run_config.clients_port,
+            enabled_http_api: run_config.enabled_http_api,
+            http_api_port: run_config.http_api_port,
             datastore: run_config.datastore,
             announce_host: run_config.announce_host,
             nym_apis: run_config.nym_apis,
@@ -0,0 +1,75 @@
+// Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+#[macro_use]
+extern crate rocket;
+
+use clap::{Parser, ValueEnum};
+use lazy_static::lazy_static;
+use nym_bin_common::build_information::BinaryBuildInformation;
+
+mod commands;
+mod config;
+pub(crate) mod error;
+mod node;
+pub(crate) mod support;
+
+pub use commands::execute;
+pub use node::embedded::EmbeddedGateway;
+
+lazy_static! {
+    pub static ref PRETTY_BUILD_INFORMATION: String =
+        BinaryBuildInformation::new(env!("CARGO_PKG_VERSION")).pretty_print();
+}
+
+// Helper for passing LONG_VERSION to clap
+fn pretty_build_info_static() -> &'static str {
+    &PRETTY_BUILD_INFORMATION
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Text,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Text
+    }
+}
+
+#[derive(Parser)]
+#[clap(author = "Nymtech", version, about, long_version = pretty_build_info_static())]
+pub struct Cli {
+    /// Path pointing to an env file that configures the gateway.
+    #[clap(short, long)]
+    pub config_env_file: Option<std::path::PathBuf>,
+
+    #[clap(short, long)]
+    pub(crate) output: Option<OutputFormat>,
+
+    #[clap(subcommand)]
+    pub(crate) command: commands::Commands,
+}
+
+impl Cli {
+    pub fn output(&self) -> OutputFormat {
+        if let Some(ref output) = self.output {
+            output.clone()
+        } else {
+            OutputFormat::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        Cli::command().debug_assert();
+    }
+}
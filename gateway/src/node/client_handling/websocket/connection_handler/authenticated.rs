@@ -49,6 +49,9 @@ pub(crate) enum RequestHandlingError {
     #[error("This gateway is only accepting coconut credentials for bandwidth")]
     OnlyCoconutCredentials,
 
+    #[error("This gateway is not accepting coconut credentials for bandwidth")]
+    CoconutCredentialsUnsupported,
+
     #[error("Nyxd Error - {0}")]
     NyxdError(#[from] validator_client::nyxd::error::NyxdError),
 
@@ -172,6 +175,7 @@ where
             .storage
             .consume_bandwidth(self.client.address, amount)
             .await?;
+        self.inner.metrics.record_consumed_bandwidth(amount);
         Ok(())
     }
 
@@ -185,6 +189,7 @@ where
             error!("We failed to forward requested mix packet - {err}. Presumably our mix forwarder has crashed. We cannot continue.");
             process::exit(1);
         }
+        self.inner.metrics.record_forwarded_client_packet();
     }
 
     /// Tries to handle the received bandwidth request by checking correctness of the received data
@@ -206,17 +211,17 @@ where
             iv,
         )?;
 
-        // Get the latest coconut signers and their VK
-        let credential_api_clients = self
+        let coconut_verifier = self
             .inner
             .coconut_verifier
+            .clone()
+            .ok_or(RequestHandlingError::CoconutCredentialsUnsupported)?;
+
+        // Get the latest coconut signers and their VK
+        let credential_api_clients = coconut_verifier
             .all_coconut_api_clients(*credential.epoch_id())
             .await?;
-        let current_api_clients = self
-            .inner
-            .coconut_verifier
-            .all_current_coconut_api_clients()
-            .await?;
+        let current_api_clients = coconut_verifier.all_current_coconut_api_clients().await?;
         if credential_api_clients.is_empty() || current_api_clients.is_empty() {
             return Err(RequestHandlingError::NotEnoughNymAPIs {
                 received: 0,
@@ -233,8 +238,7 @@ where
             ));
         }
 
-        self.inner
-            .coconut_verifier
+        coconut_verifier
             .release_funds(current_api_clients, &credential)
             .await?;
 
@@ -252,6 +256,9 @@ where
         }
 
         self.increase_bandwidth(bandwidth_value as i64).await?;
+        self.inner
+            .metrics
+            .record_credential_redemption(bandwidth_value as i64);
         let available_total = self.get_available_bandwidth().await?;
 
         Ok(ServerResponse::Bandwidth { available_total })
@@ -266,6 +273,7 @@ where
 
         self.increase_bandwidth(FREE_TESTNET_BANDWIDTH_VALUE)
             .await?;
+        self.inner.metrics.record_testnet_bandwidth_claim();
         let available_total = self.get_available_bandwidth().await?;
 
         Ok(ServerResponse::Bandwidth { available_total })
@@ -283,7 +291,7 @@ where
         &self,
         mix_packet: MixPacket,
     ) -> Result<ServerResponse, RequestHandlingError> {
-        let consumed_bandwidth = mix_packet.sphinx_packet().len() as i64;
+        let consumed_bandwidth = mix_packet.packet().len() as i64;
 
         let available_bandwidth = self.get_available_bandwidth().await?;
 
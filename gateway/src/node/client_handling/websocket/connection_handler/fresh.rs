@@ -6,6 +6,7 @@ use crate::node::client_handling::websocket::connection_handler::coconut::Coconu
 use crate::node::client_handling::websocket::connection_handler::{
     AuthenticatedHandler, ClientDetails, InitialAuthResult, SocketStream,
 };
+use crate::node::statistics::metrics::GatewayMetrics;
 use crate::node::storage::error::StorageError;
 use crate::node::storage::Storage;
 use futures::{channel::mpsc, SinkExt, StreamExt};
@@ -74,7 +75,9 @@ pub(crate) struct FreshHandler<R, S, St> {
     pub(crate) outbound_mix_sender: MixForwardingSender,
     pub(crate) socket_connection: SocketStream<S>,
     pub(crate) storage: St,
-    pub(crate) coconut_verifier: Arc<CoconutVerifier>,
+    /// Verifier of the coconut bandwidth credentials. If not set, the credentials are rejected.
+    pub(crate) coconut_verifier: Option<Arc<CoconutVerifier>>,
+    pub(crate) metrics: GatewayMetrics,
 }
 
 impl<R, S, St> FreshHandler<R, S, St>
@@ -95,7 +98,8 @@ where
         local_identity: Arc<identity::KeyPair>,
         storage: St,
         active_clients_store: ActiveClientsStore,
-        coconut_verifier: Arc<CoconutVerifier>,
+        coconut_verifier: Option<Arc<CoconutVerifier>>,
+        metrics: GatewayMetrics,
     ) -> Self {
         FreshHandler {
             rng,
@@ -106,6 +110,7 @@ where
             local_identity,
             storage,
             coconut_verifier,
+            metrics,
         }
     }
 
@@ -431,6 +436,12 @@ where
             .get_available_bandwidth(address)
             .await?
             .unwrap_or(0);
+        // only reveal (and reset) the number of dropped messages to the actual client
+        let dropped_messages = if status {
+            self.storage.take_dropped_messages(address).await?
+        } else {
+            0
+        };
         let client_details =
             shared_keys.map(|shared_keys| ClientDetails::new(address, shared_keys));
 
@@ -440,6 +451,7 @@ where
                 protocol_version: Some(PROTOCOL_VERSION),
                 status,
                 bandwidth_remaining,
+                dropped_messages,
             },
         ))
     }
@@ -511,12 +523,14 @@ where
         let client_details = ClientDetails::new(remote_address, shared_keys);
 
         let status = self.register_client(client_details).await?;
+        let dropped_messages = self.storage.take_dropped_messages(remote_address).await?;
 
         Ok(InitialAuthResult::new(
             Some(client_details),
             ServerResponse::Register {
                 protocol_version: Some(PROTOCOL_VERSION),
                 status,
+                dropped_messages,
             },
         ))
     }
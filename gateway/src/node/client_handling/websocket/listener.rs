@@ -4,6 +4,7 @@
 use crate::node::client_handling::active_clients::ActiveClientsStore;
 use crate::node::client_handling::websocket::connection_handler::coconut::CoconutVerifier;
 use crate::node::client_handling::websocket::connection_handler::FreshHandler;
+use crate::node::statistics::metrics::GatewayMetrics;
 use crate::node::storage::Storage;
 use log::*;
 use mixnet_client::forwarder::MixForwardingSender;
@@ -19,6 +20,7 @@ pub(crate) struct Listener {
     local_identity: Arc<identity::KeyPair>,
     only_coconut_credentials: bool,
     pub(crate) coconut_verifier: Arc<CoconutVerifier>,
+    metrics: GatewayMetrics,
 }
 
 impl Listener {
@@ -27,12 +29,14 @@ impl Listener {
         local_identity: Arc<identity::KeyPair>,
         only_coconut_credentials: bool,
         coconut_verifier: Arc<CoconutVerifier>,
+        metrics: GatewayMetrics,
     ) -> Self {
         Listener {
             address,
             local_identity,
             only_coconut_credentials,
             coconut_verifier,
+            metrics,
         }
     }
 
@@ -76,7 +80,8 @@ impl Listener {
                                 Arc::clone(&self.local_identity),
                                 storage.clone(),
                                 active_clients_store.clone(),
-                                Arc::clone(&self.coconut_verifier),
+                                Some(Arc::clone(&self.coconut_verifier)),
+                                self.metrics.clone(),
                             );
                             let shutdown = shutdown.clone();
                             tokio::spawn(async move { handle.start_handling(shutdown).await });
@@ -0,0 +1,107 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::Config;
+use crate::node::client_handling::active_clients::ActiveClientsStore;
+use crate::node::client_handling::websocket::connection_handler::FreshHandler;
+use crate::node::mixnet_handling::receiver::connection_handler::ConnectionHandler;
+use crate::node::mixnet_handling::PacketProcessor;
+use crate::node::statistics::metrics::GatewayMetrics;
+use crate::node::storage::retention::InboxQuota;
+use crate::node::storage::InMemStorage;
+use mixnet_client::forwarder::MixForwardingSender;
+use mixnode_common::packet_processor::sphinx_keys::SphinxKeys;
+use nym_crypto::asymmetric::identity;
+use nym_task::TaskClient;
+use rand::rngs::OsRng;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+/// Gateway running the same mixnet and client connection handlers as the actual node, but
+/// using in-memory storage and without any access to the nyxd or nym-api. As a result
+/// replay protection is disabled and coconut credentials are rejected (clients can still claim
+/// the free testnet bandwidth).
+///
+/// It does not bind to any sockets itself: the caller is responsible for accepting the
+/// connections and passing them to the appropriate handler. Intended for running gateways
+/// inside in-process test networks.
+#[derive(Clone)]
+pub struct EmbeddedGateway {
+    identity_keypair: Arc<identity::KeyPair>,
+    storage: InMemStorage,
+    active_clients_store: ActiveClientsStore,
+    outbound_mix_sender: MixForwardingSender,
+    mix_connection_handler: ConnectionHandler<InMemStorage>,
+    metrics: GatewayMetrics,
+}
+
+impl EmbeddedGateway {
+    /// Creates a new gateway that is going to send all its outbound packets,
+    /// i.e. the ones sent by its clients and the acks, via the provided `outbound_mix_sender`.
+    pub fn new(
+        identity_keypair: Arc<identity::KeyPair>,
+        sphinx_keys: SphinxKeys,
+        outbound_mix_sender: MixForwardingSender,
+    ) -> Self {
+        let config = Config::default();
+        let storage = InMemStorage::new(config.get_message_retrieval_limit());
+        let inbox_quota = InboxQuota::new(
+            config.get_maximum_client_inbox_messages(),
+            config.get_maximum_client_inbox_bytes(),
+        );
+        let active_clients_store = ActiveClientsStore::new();
+        let metrics = GatewayMetrics::default();
+
+        let mix_connection_handler = ConnectionHandler::new(
+            PacketProcessor::new(sphinx_keys, None),
+            storage.clone(),
+            inbox_quota,
+            outbound_mix_sender.clone(),
+            active_clients_store.clone(),
+            metrics.clone(),
+        );
+
+        EmbeddedGateway {
+            identity_keypair,
+            storage,
+            active_clients_store,
+            outbound_mix_sender,
+            mix_connection_handler,
+            metrics,
+        }
+    }
+
+    /// Spawns a task handling the mix packets received on the provided connection.
+    pub fn handle_mix_connection(
+        &self,
+        conn: TcpStream,
+        remote: SocketAddr,
+        shutdown: TaskClient,
+    ) -> JoinHandle<()> {
+        let handler = self.mix_connection_handler.clone();
+        tokio::spawn(handler.handle_connection(conn, remote, shutdown))
+    }
+
+    /// Spawns a task handling the (websocket) client connection, starting with the
+    /// registration or the authentication of the client.
+    pub fn handle_client_connection(
+        &self,
+        conn: TcpStream,
+        shutdown: TaskClient,
+    ) -> JoinHandle<()> {
+        let handler = FreshHandler::new(
+            OsRng,
+            conn,
+            false,
+            self.outbound_mix_sender.clone(),
+            Arc::clone(&self.identity_keypair),
+            self.storage.clone(),
+            self.active_clients_store.clone(),
+            None,
+            self.metrics.clone(),
+        );
+        tokio::spawn(async move { handler.start_handling(shutdown).await })
+    }
+}
@@ -0,0 +1,12 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use mixnode_common::node_description::NodeDescription;
+use rocket::serde::json::Json;
+use rocket::State;
+
+/// Returns a description of the node and why someone might want to use it.
+#[get("/description")]
+pub(crate) fn description(description: &State<NodeDescription>) -> Json<NodeDescription> {
+    Json(description.inner().clone())
+}
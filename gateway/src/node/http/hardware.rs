@@ -0,0 +1,11 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use mixnode_common::hardware::{hardware_info, Hardware};
+use rocket::serde::json::Json;
+
+/// Provides hardware information which Nym can use to optimize mixnet speed over time (memory, crypto hardware, CPU, cores, etc).
+#[get("/hardware")]
+pub(crate) fn hardware() -> Json<Option<Hardware>> {
+    Json(hardware_info())
+}
@@ -0,0 +1,60 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::State;
+use std::time::Instant;
+
+pub(crate) struct HealthState {
+    started_at: Instant,
+}
+
+impl HealthState {
+    pub(crate) fn new() -> Self {
+        HealthState {
+            started_at: Instant::now(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub(crate) struct Health {
+    status: &'static str,
+    uptime_seconds: u64,
+}
+
+/// Indicates whether the gateway is up alongside the time it has been running for.
+#[get("/health")]
+pub(crate) fn health(state: &State<HealthState>) -> Json<Health> {
+    Json(Health {
+        status: "up",
+        uptime_seconds: state.started_at.elapsed().as_secs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+
+    #[tokio::test]
+    async fn health_reports_the_gateway_as_up() {
+        let rocket = rocket::build()
+            .mount("/", routes![health])
+            .manage(HealthState::new());
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+
+        let response = client.get("/health").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let health: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(health["status"], "up");
+        assert!(health["uptime_seconds"].is_u64());
+    }
+}
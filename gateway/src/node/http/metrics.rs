@@ -0,0 +1,187 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::node::client_handling::active_clients::ActiveClientsStore;
+use crate::node::statistics::metrics::GatewayMetrics;
+use crate::node::storage::Storage;
+use log::warn;
+use mixnet_client::ConnectionStats;
+use mixnode_common::metrics::{MetricType, PrometheusEncoder};
+use mixnode_common::packet_processor::replay_protection::ReplayProtectionMetrics;
+use rocket::http::ContentType;
+use rocket::State;
+
+pub(crate) struct MetricsState {
+    gateway_metrics: GatewayMetrics,
+    active_clients_store: ActiveClientsStore,
+    storage: Box<dyn Storage>,
+    connection_stats: ConnectionStats,
+    replay_protection: Option<ReplayProtectionMetrics>,
+}
+
+impl MetricsState {
+    pub(crate) fn new<St: Storage + 'static>(
+        gateway_metrics: GatewayMetrics,
+        active_clients_store: ActiveClientsStore,
+        storage: St,
+        connection_stats: ConnectionStats,
+        replay_protection: Option<ReplayProtectionMetrics>,
+    ) -> Self {
+        MetricsState {
+            gateway_metrics,
+            active_clients_store,
+            storage: Box::new(storage),
+            connection_stats,
+            replay_protection,
+        }
+    }
+
+    async fn encode_clients(&self, encoder: &mut PrometheusEncoder) {
+        encoder.gauge(
+            "nym_gateway_active_clients",
+            "Number of clients currently connected to the gateway.",
+            self.active_clients_store.size(),
+        );
+
+        // if we fail to query the storage, just omit the inbox metrics rather than failing the entire request
+        match self.storage.get_total_inbox_usage().await {
+            Ok(usage) => {
+                encoder.gauge(
+                    "nym_gateway_stored_messages",
+                    "Number of messages currently stored in the inboxes of offline clients.",
+                    usage.messages,
+                );
+                encoder.gauge(
+                    "nym_gateway_stored_messages_bytes",
+                    "Total size of the messages currently stored in the inboxes of offline clients.",
+                    usage.bytes,
+                );
+            }
+            Err(err) => warn!("failed to obtain the inbox usage - {err}"),
+        }
+
+        encoder.describe(
+            "nym_gateway_delivered_messages_total",
+            "Total number of messages received from the mixnet destined for the clients of this gateway.",
+            MetricType::Counter,
+        );
+        encoder.sample(
+            "nym_gateway_delivered_messages_total",
+            &[("delivery", "pushed")],
+            self.gateway_metrics.messages_pushed_to_clients(),
+        );
+        encoder.sample(
+            "nym_gateway_delivered_messages_total",
+            &[("delivery", "stored")],
+            self.gateway_metrics.messages_stored(),
+        );
+    }
+
+    fn encode_bandwidth(&self, encoder: &mut PrometheusEncoder) {
+        encoder.counter(
+            "nym_gateway_bandwidth_consumed_bytes_total",
+            "Total bandwidth consumed by the clients sending packets into the mixnet.",
+            self.gateway_metrics.bandwidth_consumed(),
+        );
+        encoder.counter(
+            "nym_gateway_credential_redemptions_total",
+            "Total number of successfully redeemed bandwidth credentials.",
+            self.gateway_metrics.credential_redemptions(),
+        );
+        encoder.counter(
+            "nym_gateway_bandwidth_redeemed_bytes_total",
+            "Total bandwidth obtained by the clients through redeeming bandwidth credentials.",
+            self.gateway_metrics.bandwidth_redeemed(),
+        );
+        encoder.counter(
+            "nym_gateway_testnet_bandwidth_claims_total",
+            "Total number of claims of the free testnet bandwidth.",
+            self.gateway_metrics.testnet_bandwidth_claims(),
+        );
+    }
+
+    fn encode_mixnet_traffic(&self, encoder: &mut PrometheusEncoder) {
+        encoder.counter(
+            "nym_gateway_mixnet_packets_received_total",
+            "Total number of packets received from the mixnet.",
+            self.gateway_metrics.mixnet_packets_received(),
+        );
+        encoder.counter(
+            "nym_gateway_mixnet_packets_rejected_total",
+            "Total number of packets received from the mixnet that could not be processed.",
+            self.gateway_metrics.mixnet_packets_rejected(),
+        );
+
+        encoder.describe(
+            "nym_gateway_mixnet_packets_sent_total",
+            "Total number of packets forwarded into the mixnet. It does not imply they were delivered.",
+            MetricType::Counter,
+        );
+        encoder.sample(
+            "nym_gateway_mixnet_packets_sent_total",
+            &[("packet_type", "client")],
+            self.gateway_metrics.client_packets_forwarded(),
+        );
+        encoder.sample(
+            "nym_gateway_mixnet_packets_sent_total",
+            &[("packet_type", "ack")],
+            self.gateway_metrics.acks_forwarded(),
+        );
+
+        encoder.gauge(
+            "nym_gateway_mixnet_connections_established",
+            "Number of currently open connections to mixnodes.",
+            self.connection_stats.established(),
+        );
+        encoder.gauge(
+            "nym_gateway_mixnet_connections_pending",
+            "Number of connections to mixnodes that are currently being established.",
+            self.connection_stats.pending(),
+        );
+        encoder.counter(
+            "nym_gateway_mixnet_connection_failures_total",
+            "Total number of failed attempts at connecting to mixnodes.",
+            self.connection_stats.failed_attempts(),
+        );
+    }
+
+    fn encode_replay_protection(&self, encoder: &mut PrometheusEncoder) {
+        let Some(replay_protection) = &self.replay_protection else {
+            return;
+        };
+
+        encoder.counter(
+            "nym_gateway_replay_protection_checked_packets_total",
+            "Total number of packets checked against the replay protection filter.",
+            replay_protection.checked_packets(),
+        );
+        encoder.counter(
+            "nym_gateway_replay_protection_rejected_packets_total",
+            "Total number of packets rejected as replays.",
+            replay_protection.rejected_replays(),
+        );
+        encoder.counter(
+            "nym_gateway_replay_protection_rotations_total",
+            "Total number of rotations of the replay protection filter.",
+            replay_protection.rotations(),
+        );
+        encoder.counter(
+            "nym_gateway_replay_protection_overflows_total",
+            "Total number of times a shard of the replay protection filter got full before the sphinx key rotation.",
+            replay_protection.overflows(),
+        );
+    }
+}
+
+/// Returns the metrics of this gateway in the Prometheus text format.
+#[get("/metrics")]
+pub(crate) async fn metrics(state: &State<MetricsState>) -> (ContentType, String) {
+    let mut encoder = PrometheusEncoder::new();
+
+    state.encode_clients(&mut encoder).await;
+    state.encode_bandwidth(&mut encoder);
+    state.encode_mixnet_traffic(&mut encoder);
+    state.encode_replay_protection(&mut encoder);
+
+    (ContentType::Plain, encoder.finish())
+}
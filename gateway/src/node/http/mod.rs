@@ -0,0 +1,14 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+pub(crate) mod description;
+pub(crate) mod hardware;
+pub(crate) mod health;
+pub(crate) mod metrics;
+
+use rocket::Request;
+
+#[catch(404)]
+pub(crate) fn not_found(req: &Request<'_>) -> String {
+    format!("I couldn't find '{}'. Try something else?", req.uri())
+}
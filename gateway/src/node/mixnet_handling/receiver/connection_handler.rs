@@ -4,15 +4,17 @@
 use crate::node::client_handling::active_clients::ActiveClientsStore;
 use crate::node::client_handling::websocket::message_receiver::MixMessageSender;
 use crate::node::mixnet_handling::receiver::packet_processing::PacketProcessor;
+use crate::node::statistics::metrics::GatewayMetrics;
 use crate::node::storage::error::StorageError;
+use crate::node::storage::retention::InboxQuota;
 use crate::node::storage::Storage;
 use futures::StreamExt;
 use log::*;
 use mixnet_client::forwarder::MixForwardingSender;
 use mixnode_common::packet_processor::processor::ProcessedFinalHop;
 use nym_sphinx::forwarding::packet::MixPacket;
-use nym_sphinx::framing::codec::SphinxCodec;
-use nym_sphinx::framing::packet::FramedSphinxPacket;
+use nym_sphinx::framing::codec::NymCodec;
+use nym_sphinx::framing::packet::FramedNymPacket;
 use nym_sphinx::DestinationAddressBytes;
 use nym_task::TaskClient;
 use std::collections::HashMap;
@@ -30,7 +32,9 @@ pub(crate) struct ConnectionHandler<St: Storage> {
     clients_store_cache: HashMap<DestinationAddressBytes, MixMessageSender>,
     active_clients_store: ActiveClientsStore,
     storage: St,
+    inbox_quota: InboxQuota,
     ack_sender: MixForwardingSender,
+    metrics: GatewayMetrics,
 }
 
 impl<St: Storage + Clone> Clone for ConnectionHandler<St> {
@@ -48,7 +52,9 @@ impl<St: Storage + Clone> Clone for ConnectionHandler<St> {
             clients_store_cache,
             active_clients_store: self.active_clients_store.clone(),
             storage: self.storage.clone(),
+            inbox_quota: self.inbox_quota,
             ack_sender: self.ack_sender.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -57,15 +63,19 @@ impl<St: Storage> ConnectionHandler<St> {
     pub(crate) fn new(
         packet_processor: PacketProcessor,
         storage: St,
+        inbox_quota: InboxQuota,
         ack_sender: MixForwardingSender,
         active_clients_store: ActiveClientsStore,
+        metrics: GatewayMetrics,
     ) -> Self {
         ConnectionHandler {
             packet_processor,
             clients_store_cache: HashMap::new(),
             storage,
+            inbox_quota,
             active_clients_store,
             ack_sender,
+            metrics,
         }
     }
 
@@ -116,7 +126,19 @@ impl<St: Storage> ConnectionHandler<St> {
             client_address
         );
 
-        self.storage.store_message(client_address, message).await
+        if self
+            .storage
+            .store_message(client_address, message, self.inbox_quota)
+            .await?
+        {
+            self.metrics.record_stored_message();
+        } else {
+            // note: the message is still going to get acked as otherwise the sender would keep on
+            // retransmitting it into the full inbox. The client is informed about the dropped
+            // messages once it authenticates instead.
+            debug!("The inbox of {client_address} is full. The received message got dropped");
+        }
+        Ok(())
     }
 
     fn forward_ack(&self, forward_ack: Option<MixPacket>, client_address: DestinationAddressBytes) {
@@ -128,6 +150,7 @@ impl<St: Storage> ConnectionHandler<St> {
             );
 
             self.ack_sender.unbounded_send(forward_ack).unwrap();
+            self.metrics.record_forwarded_ack();
         }
     }
 
@@ -146,7 +169,10 @@ impl<St: Storage> ConnectionHandler<St> {
                 Err(err) => error!("Failed to store client data - {err}"),
                 Ok(_) => trace!("Stored packet for {}", client_address),
             },
-            Ok(_) => trace!("Pushed received packet to {}", client_address),
+            Ok(_) => {
+                trace!("Pushed received packet to {}", client_address);
+                self.metrics.record_message_pushed_to_client();
+            }
         }
 
         // if we managed to either push message directly to the [online] client or store it at
@@ -155,17 +181,15 @@ impl<St: Storage> ConnectionHandler<St> {
         self.forward_ack(forward_ack, client_address);
     }
 
-    async fn handle_received_packet(&mut self, framed_sphinx_packet: FramedSphinxPacket) {
-        //
-        // TODO: here be replay attack detection - it will require similar key cache to the one in
-        // packet processor for vpn packets,
-        // question: can it also be per connection vs global?
-        //
+    async fn handle_received_packet(&mut self, framed_nym_packet: FramedNymPacket) {
+        // note: replayed packets are rejected by the packet processor itself, using the replay
+        // protection filter shared by all connections
 
-        let processed_final_hop = match self.packet_processor.process_received(framed_sphinx_packet)
-        {
+        self.metrics.record_received_mixnet_packet();
+        let processed_final_hop = match self.packet_processor.process_received(framed_nym_packet) {
             Err(err) => {
                 debug!("We failed to process received sphinx packet - {err}");
+                self.metrics.record_rejected_mixnet_packet();
                 return;
             }
             Ok(processed_final_hop) => processed_final_hop,
@@ -182,23 +206,23 @@ impl<St: Storage> ConnectionHandler<St> {
     ) {
         debug!("Starting connection handler for {:?}", remote);
         shutdown.mark_as_success();
-        let mut framed_conn = Framed::new(conn, SphinxCodec);
+        let mut framed_conn = Framed::new(conn, NymCodec);
         while !shutdown.is_shutdown() {
             tokio::select! {
                 biased;
                 _ = shutdown.recv() => {
                     log::trace!("ConnectionHandler: received shutdown");
                 }
-                Some(framed_sphinx_packet) = framed_conn.next() => {
-                    match framed_sphinx_packet {
-                        Ok(framed_sphinx_packet) => {
+                Some(framed_nym_packet) = framed_conn.next() => {
+                    match framed_nym_packet {
+                        Ok(framed_nym_packet) => {
                             // TODO: benchmark spawning tokio task with full processing vs just processing it
                             // synchronously under higher load in single and multi-threaded situation.
 
                             // in theory we could process multiple sphinx packet from the same connection in parallel,
                             // but we already handle multiple concurrent connections so if anything, making
                             // that change would only slow things down
-                            self.handle_received_packet(framed_sphinx_packet).await;
+                            self.handle_received_packet(framed_nym_packet).await;
                         }
                         Err(err) => {
                             error!(
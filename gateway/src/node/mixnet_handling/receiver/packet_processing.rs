@@ -4,8 +4,9 @@
 use mixnode_common::packet_processor::error::MixProcessingError;
 pub use mixnode_common::packet_processor::processor::MixProcessingResult;
 use mixnode_common::packet_processor::processor::{ProcessedFinalHop, SphinxPacketProcessor};
-use nym_crypto::asymmetric::encryption;
-use nym_sphinx::framing::packet::FramedSphinxPacket;
+use mixnode_common::packet_processor::replay_protection::ReplayProtectionFilter;
+use mixnode_common::packet_processor::sphinx_keys::SphinxKeys;
+use nym_sphinx::framing::packet::FramedNymPacket;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -24,15 +25,21 @@ pub struct PacketProcessor {
 }
 
 impl PacketProcessor {
-    pub(crate) fn new(encryption_key: &encryption::PrivateKey) -> Self {
-        PacketProcessor {
-            inner_processor: SphinxPacketProcessor::new(encryption_key.into()),
+    pub(crate) fn new(
+        sphinx_keys: SphinxKeys,
+        replay_protection: Option<ReplayProtectionFilter>,
+    ) -> Self {
+        let mut inner_processor = SphinxPacketProcessor::new(sphinx_keys);
+        if let Some(replay_protection) = replay_protection {
+            inner_processor = inner_processor.with_replay_protection(replay_protection);
         }
+
+        PacketProcessor { inner_processor }
     }
 
     pub(crate) fn process_received(
         &self,
-        received: FramedSphinxPacket,
+        received: FramedNymPacket,
     ) -> Result<ProcessedFinalHop, GatewayProcessingError> {
         match self.inner_processor.process_received(received)? {
             MixProcessingResult::ForwardHop(..) => {
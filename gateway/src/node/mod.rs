@@ -1,21 +1,34 @@
 // Copyright 2020-2023 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use self::storage::PersistentStorage;
+use self::storage::retention::{InboxPruner, InboxQuota};
+use self::storage::{GatewayStorage, InMemStorage, PersistentStorage, PostgresStorage};
 use crate::commands::ensure_correct_bech32_prefix;
 use crate::config::persistence::pathfinder::GatewayPathfinder;
-use crate::config::Config;
+use crate::config::{Config, StorageBackend};
 use crate::error::GatewayError;
 use crate::node::client_handling::active_clients::ActiveClientsStore;
 use crate::node::client_handling::websocket;
 use crate::node::client_handling::websocket::connection_handler::coconut::CoconutVerifier;
+use crate::node::http::description::description;
+use crate::node::http::hardware::hardware;
+use crate::node::http::health::{health, HealthState};
+use crate::node::http::metrics::{metrics, MetricsState};
+use crate::node::http::not_found;
 use crate::node::mixnet_handling::receiver::connection_handler::ConnectionHandler;
 use crate::node::statistics::collector::GatewayStatisticsCollector;
+use crate::node::statistics::metrics::GatewayMetrics;
 use crate::node::storage::Storage;
 use crate::{commands::sign::load_identity_keys, OutputFormat};
 use colored::Colorize;
+use config::NymConfig;
 use log::*;
 use mixnet_client::forwarder::{MixForwardingSender, PacketForwarder};
+use mixnet_client::ConnectionStats;
+use mixnode_common::node_description::NodeDescription;
+use mixnode_common::packet_processor::replay_protection::ReplayProtectionFilter;
+use mixnode_common::packet_processor::sphinx_keys::SphinxKeys;
+use mixnode_common::sphinx_key_rotation::{self, NodeKind, SphinxKeyRotator};
 use nym_crypto::asymmetric::{encryption, identity};
 use nym_network_defaults::NymNetworkDetails;
 use nym_task::{TaskClient, TaskManager};
@@ -29,20 +42,39 @@ use std::sync::Arc;
 use validator_client::Client;
 
 pub(crate) mod client_handling;
+pub(crate) mod embedded;
+pub(crate) mod http;
 pub(crate) mod mixnet_handling;
 pub(crate) mod statistics;
 pub(crate) mod storage;
 
 /// Wire up and create Gateway instance
-pub(crate) async fn create_gateway(config: Config) -> Gateway<PersistentStorage> {
+pub(crate) async fn create_gateway(config: Config) -> Gateway<GatewayStorage> {
     let storage = initialise_storage(&config).await;
     Gateway::new(config, storage).await
 }
 
-async fn initialise_storage(config: &Config) -> PersistentStorage {
-    let path = config.get_persistent_store_path();
+async fn initialise_storage(config: &Config) -> GatewayStorage {
     let retrieval_limit = config.get_message_retrieval_limit();
-    match PersistentStorage::init(path, retrieval_limit).await {
+    let storage = match config.get_storage_backend() {
+        StorageBackend::Sqlite => {
+            let path = config.get_persistent_store_path();
+            PersistentStorage::init(path, retrieval_limit)
+                .await
+                .map(Into::into)
+        }
+        StorageBackend::Postgres => {
+            PostgresStorage::init(config.get_postgres_url(), retrieval_limit)
+                .await
+                .map(Into::into)
+        }
+        StorageBackend::InMemory => {
+            warn!("Using in-memory storage - all stored messages, shared keys and bandwidth will be lost once the gateway shuts down");
+            Ok(InMemStorage::new(retrieval_limit).into())
+        }
+    };
+
+    match storage {
         Err(err) => panic!("failed to initialise gateway storage - {err}"),
         Ok(storage) => storage,
     }
@@ -55,6 +87,7 @@ pub(crate) struct Gateway<St: Storage> {
     /// x25519 keypair used for Diffie-Hellman. Currently only used for sphinx key derivation.
     sphinx_keypair: Arc<encryption::KeyPair>,
     storage: St,
+    descriptor: NodeDescription,
 }
 
 impl<St> Gateway<St>
@@ -67,9 +100,10 @@ where
         // let storage = Self::initialise_storage(&config).await;
 
         Gateway {
-            config,
             identity_keypair: Arc::new(Self::load_identity_keys(&pathfinder)),
             sphinx_keypair: Arc::new(Self::load_sphinx_keys(&pathfinder)),
+            descriptor: Self::load_node_description(&config),
+            config,
             storage,
         }
     }
@@ -86,9 +120,14 @@ where
             identity_keypair: Arc::new(identity_keypair),
             sphinx_keypair: Arc::new(sphinx_keypair),
             storage,
+            descriptor: NodeDescription::default(),
         }
     }
 
+    fn load_node_description(config: &Config) -> NodeDescription {
+        NodeDescription::load_from_file(config.config_directory()).unwrap_or_default()
+    }
+
     fn load_identity_keys(pathfinder: &GatewayPathfinder) -> identity::KeyPair {
         let identity_keypair: identity::KeyPair =
             nym_pemstore::load_keypair(&nym_pemstore::KeyPairPath::new(
@@ -155,22 +194,81 @@ where
         Ok(())
     }
 
+    fn start_replay_protection(&self, shutdown: TaskClient) -> Option<ReplayProtectionFilter> {
+        if !self.config.get_replay_protection_enabled() {
+            warn!(
+                "Replay protection is disabled - previously processed packets will not be rejected"
+            );
+            return None;
+        }
+        info!("Starting replay protection...");
+
+        let filter = ReplayProtectionFilter::new(self.config.get_replay_protection_filter_config());
+        tokio::spawn(filter.clone().run_persistence(shutdown));
+        Some(filter)
+    }
+
+    fn start_sphinx_key_rotation(
+        &self,
+        sphinx_keys: SphinxKeys,
+        replay_protection: Option<ReplayProtectionFilter>,
+        shutdown: TaskClient,
+    ) {
+        if !self.config.get_sphinx_key_rotation_enabled() {
+            warn!("Sphinx key rotation is disabled - the current sphinx key is going to be used indefinitely");
+            return;
+        }
+        info!("Starting sphinx key rotator...");
+
+        let config = sphinx_key_rotation::Config::build()
+            .node_kind(NodeKind::Gateway)
+            .identity_key(self.identity_keypair.public_key().to_base58_string())
+            .check_interval(self.config.get_sphinx_key_rotation_check_interval())
+            .overlap(self.config.get_sphinx_key_overlap())
+            .current_key_files(
+                self.config.get_private_sphinx_key_file(),
+                self.config.get_public_sphinx_key_file(),
+            )
+            .upcoming_key_files(
+                self.config.get_upcoming_private_sphinx_key_file(),
+                self.config.get_upcoming_public_sphinx_key_file(),
+            )
+            .nym_api_urls(self.config.get_nym_api_endpoints())
+            .build();
+
+        let mut sphinx_key_rotator = SphinxKeyRotator::new(config, sphinx_keys, shutdown);
+        if let Some(replay_protection) = replay_protection {
+            sphinx_key_rotator = sphinx_key_rotator.with_replay_protection(replay_protection)
+        }
+        tokio::spawn(async move { sphinx_key_rotator.run().await });
+    }
+
     fn start_mix_socket_listener(
         &self,
         ack_sender: MixForwardingSender,
         active_clients_store: ActiveClientsStore,
+        sphinx_keys: SphinxKeys,
+        replay_protection: Option<ReplayProtectionFilter>,
+        gateway_metrics: GatewayMetrics,
         shutdown: TaskClient,
     ) {
         info!("Starting mix socket listener...");
 
         let packet_processor =
-            mixnet_handling::PacketProcessor::new(self.sphinx_keypair.private_key());
+            mixnet_handling::PacketProcessor::new(sphinx_keys, replay_protection);
+
+        let inbox_quota = InboxQuota::new(
+            self.config.get_maximum_client_inbox_messages(),
+            self.config.get_maximum_client_inbox_bytes(),
+        );
 
         let connection_handler = ConnectionHandler::new(
             packet_processor,
             self.storage.clone(),
+            inbox_quota,
             ack_sender,
             active_clients_store,
+            gateway_metrics,
         );
 
         let listening_address = SocketAddr::new(
@@ -187,6 +285,7 @@ where
         active_clients_store: ActiveClientsStore,
         shutdown: TaskClient,
         coconut_verifier: Arc<CoconutVerifier>,
+        gateway_metrics: GatewayMetrics,
     ) {
         info!("Starting client [web]socket listener...");
 
@@ -200,6 +299,7 @@ where
             Arc::clone(&self.identity_keypair),
             self.config.get_only_coconut_credentials(),
             coconut_verifier,
+            gateway_metrics,
         )
         .start(
             forwarding_channel,
@@ -209,7 +309,50 @@ where
         );
     }
 
-    fn start_packet_forwarder(&self, shutdown: TaskClient) -> MixForwardingSender {
+    fn start_inbox_pruner(&self, shutdown: TaskClient) {
+        info!("Starting inbox pruner...");
+
+        let inbox_pruner = InboxPruner::new(
+            self.storage.clone(),
+            self.config.get_stored_messages_ttl(),
+            self.config.get_stored_messages_pruning_interval(),
+        );
+
+        tokio::spawn(async move { inbox_pruner.run(shutdown).await });
+    }
+
+    fn start_http_api(&self, metrics_state: MetricsState) {
+        info!(
+            "Starting HTTP API on http://{}:{}",
+            self.config.get_listening_address(),
+            self.config.get_http_api_port()
+        );
+
+        let mut config = rocket::config::Config::release_default();
+
+        // bind to the same address as we are using for the mixnet and client traffic
+        config.address = self.config.get_listening_address();
+        config.port = self.config.get_http_api_port();
+
+        let descriptor = self.descriptor.clone();
+
+        tokio::spawn(async move {
+            rocket::build()
+                .configure(config)
+                .mount("/", routes![health, metrics, description, hardware])
+                .register("/", catchers![not_found])
+                .manage(HealthState::new())
+                .manage(metrics_state)
+                .manage(descriptor)
+                .launch()
+                .await
+        });
+    }
+
+    fn start_packet_forwarder(
+        &self,
+        shutdown: TaskClient,
+    ) -> (MixForwardingSender, ConnectionStats) {
         info!("Starting mix packet forwarder...");
 
         let (mut packet_forwarder, packet_sender) = PacketForwarder::new(
@@ -221,8 +364,9 @@ where
             shutdown,
         );
 
+        let connection_stats = packet_forwarder.connection_stats();
         tokio::spawn(async move { packet_forwarder.run().await });
-        packet_sender
+        (packet_sender, connection_stats)
     }
 
     async fn wait_for_interrupt(
@@ -315,12 +459,31 @@ where
             CoconutVerifier::new(nyxd_client)
         };
 
-        let mix_forwarding_channel = self.start_packet_forwarder(shutdown.subscribe());
+        let (mix_forwarding_channel, connection_stats) =
+            self.start_packet_forwarder(shutdown.subscribe());
+
+        self.start_inbox_pruner(shutdown.subscribe());
+
+        let replay_protection = self.start_replay_protection(shutdown.subscribe());
+        let sphinx_keys = SphinxKeys::new(self.sphinx_keypair.private_key().into());
+        self.start_sphinx_key_rotation(
+            sphinx_keys.clone(),
+            replay_protection.clone(),
+            shutdown.subscribe(),
+        );
+
+        let replay_protection_metrics = replay_protection
+            .as_ref()
+            .map(ReplayProtectionFilter::metrics);
+        let gateway_metrics = GatewayMetrics::default();
 
         let active_clients_store = ActiveClientsStore::new();
         self.start_mix_socket_listener(
             mix_forwarding_channel.clone(),
             active_clients_store.clone(),
+            sphinx_keys,
+            replay_protection,
+            gateway_metrics.clone(),
             shutdown.subscribe(),
         );
 
@@ -339,11 +502,26 @@ where
 
         self.start_client_websocket_listener(
             mix_forwarding_channel,
-            active_clients_store,
+            active_clients_store.clone(),
             shutdown.subscribe(),
             Arc::new(coconut_verifier),
+            gateway_metrics.clone(),
         );
 
+        if self.config.get_enabled_http_api() {
+            let metrics_state = MetricsState::new(
+                gateway_metrics,
+                active_clients_store,
+                self.storage.clone(),
+                connection_stats,
+                replay_protection_metrics,
+            );
+
+            // Similarly to the mixnode, rocket handles shutdown on its own and its runtime
+            // is forcefully terminated once the gateway exits.
+            self.start_http_api(metrics_state);
+        }
+
         info!("Finished nym gateway startup procedure - it should now be able to receive mix and client traffic!");
 
         self.wait_for_interrupt(shutdown).await
@@ -0,0 +1,132 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Counters of the traffic handled by the gateway since it has started up, exposed via the http api.
+#[derive(Clone, Default)]
+pub(crate) struct GatewayMetrics {
+    inner: Arc<GatewayMetricsInner>,
+}
+
+#[derive(Default)]
+struct GatewayMetricsInner {
+    mixnet_packets_received: AtomicU64,
+    mixnet_packets_rejected: AtomicU64,
+    messages_pushed_to_clients: AtomicU64,
+    messages_stored: AtomicU64,
+    client_packets_forwarded: AtomicU64,
+    acks_forwarded: AtomicU64,
+    bandwidth_consumed: AtomicU64,
+    credential_redemptions: AtomicU64,
+    bandwidth_redeemed: AtomicU64,
+    testnet_bandwidth_claims: AtomicU64,
+}
+
+impl GatewayMetrics {
+    pub(crate) fn record_received_mixnet_packet(&self) {
+        self.inner
+            .mixnet_packets_received
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_rejected_mixnet_packet(&self) {
+        self.inner
+            .mixnet_packets_rejected
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_message_pushed_to_client(&self) {
+        self.inner
+            .messages_pushed_to_clients
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_stored_message(&self) {
+        self.inner.messages_stored.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_forwarded_client_packet(&self) {
+        self.inner
+            .client_packets_forwarded
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_forwarded_ack(&self) {
+        self.inner.acks_forwarded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_consumed_bandwidth(&self, amount: i64) {
+        self.inner
+            .bandwidth_consumed
+            .fetch_add(amount.max(0) as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_credential_redemption(&self, bandwidth: i64) {
+        self.inner
+            .credential_redemptions
+            .fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .bandwidth_redeemed
+            .fetch_add(bandwidth.max(0) as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_testnet_bandwidth_claim(&self) {
+        self.inner
+            .testnet_bandwidth_claims
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of packets received from the mixnet.
+    pub(crate) fn mixnet_packets_received(&self) -> u64 {
+        self.inner.mixnet_packets_received.load(Ordering::Relaxed)
+    }
+
+    /// Number of packets received from the mixnet that could not be processed, for example because they were replayed.
+    pub(crate) fn mixnet_packets_rejected(&self) -> u64 {
+        self.inner.mixnet_packets_rejected.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages pushed directly to the connected clients.
+    pub(crate) fn messages_pushed_to_clients(&self) -> u64 {
+        self.inner
+            .messages_pushed_to_clients
+            .load(Ordering::Relaxed)
+    }
+
+    /// Number of messages stored in the inboxes of offline clients.
+    pub(crate) fn messages_stored(&self) -> u64 {
+        self.inner.messages_stored.load(Ordering::Relaxed)
+    }
+
+    /// Number of packets sent by the clients that got forwarded into the mixnet.
+    pub(crate) fn client_packets_forwarded(&self) -> u64 {
+        self.inner.client_packets_forwarded.load(Ordering::Relaxed)
+    }
+
+    /// Number of acks of the received packets that got forwarded into the mixnet.
+    pub(crate) fn acks_forwarded(&self) -> u64 {
+        self.inner.acks_forwarded.load(Ordering::Relaxed)
+    }
+
+    /// Total bandwidth, in bytes, consumed by the clients.
+    pub(crate) fn bandwidth_consumed(&self) -> u64 {
+        self.inner.bandwidth_consumed.load(Ordering::Relaxed)
+    }
+
+    /// Number of successfully redeemed bandwidth credentials.
+    pub(crate) fn credential_redemptions(&self) -> u64 {
+        self.inner.credential_redemptions.load(Ordering::Relaxed)
+    }
+
+    /// Total bandwidth, in bytes, obtained by the clients through redeeming credentials.
+    pub(crate) fn bandwidth_redeemed(&self) -> u64 {
+        self.inner.bandwidth_redeemed.load(Ordering::Relaxed)
+    }
+
+    /// Number of claims of the free testnet bandwidth.
+    pub(crate) fn testnet_bandwidth_claims(&self) -> u64 {
+        self.inner.testnet_bandwidth_claims.load(Ordering::Relaxed)
+    }
+}
@@ -10,4 +10,7 @@ pub(crate) enum StorageError {
 
     #[error("Failed to perform database migration - {0}")]
     MigrationError(#[from] sqlx::migrate::MigrateError),
+
+    #[error("No database url has been provided for the postgres storage backend")]
+    MissingDatabaseUrl,
 }
@@ -0,0 +1,511 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::node::storage::error::StorageError;
+use crate::node::storage::models::{InboxUsage, PersistedSharedKeys, StoredMessage};
+use crate::node::storage::retention::InboxQuota;
+use crate::node::storage::{current_unix_timestamp, Storage};
+use async_trait::async_trait;
+use gateway_requests::registration::handshake::SharedKeys;
+use nym_sphinx::DestinationAddressBytes;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+struct InMemStoredMessage {
+    content: Vec<u8>,
+    stored_at: i64,
+}
+
+#[derive(Default)]
+struct InMemStorageInner {
+    shared_keys: HashMap<String, PersistedSharedKeys>,
+
+    // messages of each client ordered by their ids
+    inboxes: HashMap<String, BTreeMap<i64, InMemStoredMessage>>,
+    // allows removing the messages by their ids alone
+    message_owners: HashMap<i64, String>,
+    // kept up to date alongside the inboxes, so that they wouldn't have to be scanned
+    inbox_usage: HashMap<String, InboxUsage>,
+    next_message_id: i64,
+    dropped_messages: HashMap<String, i64>,
+
+    available_bandwidth: HashMap<String, i64>,
+}
+
+/// In-memory implementation of `Storage`. It's useful for testing environments and short-lived
+/// gateways, as all the stored data is lost once the gateway shuts down.
+// note that clone here is fine as upon cloning the same underlying data will be used
+#[derive(Clone)]
+pub(crate) struct InMemStorage {
+    inner: Arc<Mutex<InMemStorageInner>>,
+
+    /// Maximum number of messages that can be obtained from the storage per operation.
+    retrieval_limit: i64,
+}
+
+impl InMemStorage {
+    /// Creates a new, empty, `InMemStorage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_retrieval_limit`: maximum number of stored client messages that can be retrieved at once.
+    pub(crate) fn new(message_retrieval_limit: i64) -> Self {
+        InMemStorage {
+            inner: Arc::new(Mutex::new(InMemStorageInner::default())),
+            retrieval_limit: message_retrieval_limit,
+        }
+    }
+
+    fn inner(&self) -> MutexGuard<'_, InMemStorageInner> {
+        // the lock is never held across any operation that could panic
+        self.inner
+            .lock()
+            .expect("in-memory storage mutex got poisoned")
+    }
+}
+
+impl InMemStorageInner {
+    fn update_usage(&mut self, client_address_bs58: &str, messages: i64, bytes: i64) {
+        let usage = self
+            .inbox_usage
+            .entry(client_address_bs58.to_string())
+            .or_default();
+        usage.messages += messages;
+        usage.bytes += bytes;
+        if usage.messages <= 0 {
+            self.inbox_usage.remove(client_address_bs58);
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for InMemStorage {
+    async fn insert_shared_keys(
+        &self,
+        client_address: DestinationAddressBytes,
+        shared_keys: SharedKeys,
+    ) -> Result<(), StorageError> {
+        let client_address_bs58 = client_address.as_base58_string();
+        let persisted_shared_keys = PersistedSharedKeys {
+            client_address_bs58: client_address_bs58.clone(),
+            derived_aes128_ctr_blake3_hmac_keys_bs58: shared_keys.to_base58_string(),
+        };
+        self.inner()
+            .shared_keys
+            .insert(client_address_bs58, persisted_shared_keys);
+        Ok(())
+    }
+
+    async fn get_shared_keys(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<Option<PersistedSharedKeys>, StorageError> {
+        Ok(self
+            .inner()
+            .shared_keys
+            .get(&client_address.as_base58_string())
+            .cloned())
+    }
+
+    async fn remove_shared_keys(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<(), StorageError> {
+        self.inner()
+            .shared_keys
+            .remove(&client_address.as_base58_string());
+        Ok(())
+    }
+
+    async fn store_message(
+        &self,
+        client_address: DestinationAddressBytes,
+        message: Vec<u8>,
+        quota: InboxQuota,
+    ) -> Result<bool, StorageError> {
+        let client_address_bs58 = client_address.as_base58_string();
+        // the lock is held for the whole operation, so the check and the insertion are atomic
+        let mut inner = self.inner();
+
+        let usage = inner
+            .inbox_usage
+            .get(&client_address_bs58)
+            .copied()
+            .unwrap_or_default();
+        if !quota.allows(usage, message.len()) {
+            *inner
+                .dropped_messages
+                .entry(client_address_bs58)
+                .or_default() += 1;
+            return Ok(false);
+        }
+
+        inner.update_usage(&client_address_bs58, 1, message.len() as i64);
+        inner.next_message_id += 1;
+        let id = inner.next_message_id;
+        inner.message_owners.insert(id, client_address_bs58.clone());
+        inner
+            .inboxes
+            .entry(client_address_bs58)
+            .or_default()
+            .insert(
+                id,
+                InMemStoredMessage {
+                    content: message,
+                    stored_at: current_unix_timestamp(),
+                },
+            );
+        Ok(true)
+    }
+
+    async fn retrieve_messages(
+        &self,
+        client_address: DestinationAddressBytes,
+        start_after: Option<i64>,
+    ) -> Result<(Vec<StoredMessage>, Option<i64>), StorageError> {
+        let client_address_bs58 = client_address.as_base58_string();
+        let inner = self.inner();
+        let Some(inbox) = inner.inboxes.get(&client_address_bs58) else {
+            return Ok((Vec::new(), None));
+        };
+
+        // get 1 additional message to check whether there will be more to grab next time
+        let start = start_after.map(Bound::Excluded).unwrap_or(Bound::Unbounded);
+        let mut messages: Vec<_> = inbox
+            .range((start, Bound::Unbounded))
+            .take(self.retrieval_limit as usize + 1)
+            .map(|(id, message)| StoredMessage {
+                id: *id,
+                client_address_bs58: client_address_bs58.clone(),
+                content: message.content.clone(),
+            })
+            .collect();
+
+        if messages.len() > self.retrieval_limit as usize {
+            messages.truncate(self.retrieval_limit as usize);
+            // assuming retrieval_limit > 0, unwrap will not fail
+            let start_after = messages.last().unwrap().id;
+            Ok((messages, Some(start_after)))
+        } else {
+            Ok((messages, None))
+        }
+    }
+
+    async fn remove_messages(&self, ids: Vec<i64>) -> Result<(), StorageError> {
+        let mut inner = self.inner();
+        for id in ids {
+            let Some(owner) = inner.message_owners.remove(&id) else {
+                continue;
+            };
+            let Some(inbox) = inner.inboxes.get_mut(&owner) else {
+                continue;
+            };
+            let removed = inbox.remove(&id);
+            if inbox.is_empty() {
+                inner.inboxes.remove(&owner);
+            }
+            if let Some(removed) = removed {
+                inner.update_usage(&owner, -1, -(removed.content.len() as i64));
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_inbox_usage(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<InboxUsage, StorageError> {
+        Ok(self
+            .inner()
+            .inbox_usage
+            .get(&client_address.as_base58_string())
+            .copied()
+            .unwrap_or_default())
+    }
+
+    async fn get_total_inbox_usage(&self) -> Result<InboxUsage, StorageError> {
+        let inner = self.inner();
+        let mut usage = InboxUsage::default();
+        for client_usage in inner.inbox_usage.values() {
+            usage.messages += client_usage.messages;
+            usage.bytes += client_usage.bytes;
+        }
+        Ok(usage)
+    }
+
+    async fn remove_expired_messages(&self, stored_before: i64) -> Result<u64, StorageError> {
+        let mut guard = self.inner();
+        let inner = &mut *guard;
+
+        let mut removed = 0;
+        for (client, inbox) in inner.inboxes.iter_mut() {
+            let mut expired = InboxUsage::default();
+            inbox.retain(|id, message| {
+                let is_expired = message.stored_at < stored_before;
+                if is_expired {
+                    inner.message_owners.remove(id);
+                    expired.messages += 1;
+                    expired.bytes += message.content.len() as i64;
+                }
+                !is_expired
+            });
+            if expired.messages > 0 {
+                *inner.dropped_messages.entry(client.clone()).or_default() += expired.messages;
+                let usage = inner.inbox_usage.entry(client.clone()).or_default();
+                usage.messages -= expired.messages;
+                usage.bytes -= expired.bytes;
+                removed += expired.messages as u64;
+            }
+        }
+        inner.inbox_usage.retain(|_, usage| usage.messages > 0);
+        inner.inboxes.retain(|_, inbox| !inbox.is_empty());
+
+        Ok(removed)
+    }
+
+    async fn take_dropped_messages(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<i64, StorageError> {
+        Ok(self
+            .inner()
+            .dropped_messages
+            .remove(&client_address.as_base58_string())
+            .unwrap_or_default())
+    }
+
+    async fn create_bandwidth_entry(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<(), StorageError> {
+        self.inner()
+            .available_bandwidth
+            .entry(client_address.as_base58_string())
+            .or_insert(0);
+        Ok(())
+    }
+
+    async fn get_available_bandwidth(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<Option<i64>, StorageError> {
+        Ok(self
+            .inner()
+            .available_bandwidth
+            .get(&client_address.as_base58_string())
+            .copied())
+    }
+
+    async fn increase_bandwidth(
+        &self,
+        client_address: DestinationAddressBytes,
+        amount: i64,
+    ) -> Result<(), StorageError> {
+        if let Some(available) = self
+            .inner()
+            .available_bandwidth
+            .get_mut(&client_address.as_base58_string())
+        {
+            *available += amount
+        }
+        Ok(())
+    }
+
+    async fn consume_bandwidth(
+        &self,
+        client_address: DestinationAddressBytes,
+        amount: i64,
+    ) -> Result<(), StorageError> {
+        if let Some(available) = self
+            .inner()
+            .available_bandwidth
+            .get_mut(&client_address.as_base58_string())
+        {
+            *available -= amount
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> DestinationAddressBytes {
+        DestinationAddressBytes::from_bytes([byte; 32])
+    }
+
+    async fn store(storage: &InMemStorage, client: u8, message: Vec<u8>) {
+        let stored = storage
+            .store_message(address(client), message, InboxQuota::default())
+            .await
+            .unwrap();
+        assert!(stored)
+    }
+
+    #[tokio::test]
+    async fn messages_are_retrieved_in_pages() {
+        let storage = InMemStorage::new(2);
+        for i in 0..5u8 {
+            store(&storage, 1, vec![i]).await;
+        }
+        store(&storage, 2, vec![42]).await;
+
+        let (first, start_after) = storage.retrieve_messages(address(1), None).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].content, vec![0]);
+
+        let (second, start_after) = storage
+            .retrieve_messages(address(1), start_after)
+            .await
+            .unwrap();
+        assert_eq!(second.len(), 2);
+        assert_eq!(second[0].content, vec![2]);
+
+        let (last, start_after) = storage
+            .retrieve_messages(address(1), start_after)
+            .await
+            .unwrap();
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].content, vec![4]);
+        assert!(start_after.is_none());
+    }
+
+    #[tokio::test]
+    async fn removed_messages_are_no_longer_retrieved() {
+        let storage = InMemStorage::new(100);
+        store(&storage, 1, vec![1]).await;
+        store(&storage, 1, vec![2]).await;
+
+        let (messages, _) = storage.retrieve_messages(address(1), None).await.unwrap();
+        storage.remove_messages(vec![messages[0].id]).await.unwrap();
+
+        let (messages, _) = storage.retrieve_messages(address(1), None).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn total_inbox_usage_covers_all_clients() {
+        let storage = InMemStorage::new(100);
+        assert_eq!(
+            storage.get_total_inbox_usage().await.unwrap(),
+            InboxUsage::default()
+        );
+
+        store(&storage, 1, vec![1, 2]).await;
+        store(&storage, 1, vec![3]).await;
+        store(&storage, 2, vec![4, 5, 6]).await;
+        assert_eq!(
+            storage.get_total_inbox_usage().await.unwrap(),
+            InboxUsage {
+                messages: 3,
+                bytes: 6
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn messages_beyond_the_quota_are_dropped() {
+        let storage = InMemStorage::new(100);
+        let quota = InboxQuota::new(2, 100);
+
+        assert!(storage
+            .store_message(address(1), vec![1; 50], quota)
+            .await
+            .unwrap());
+        assert!(!storage
+            .store_message(address(1), vec![2; 51], quota)
+            .await
+            .unwrap());
+        assert!(storage
+            .store_message(address(1), vec![3; 50], quota)
+            .await
+            .unwrap());
+        assert!(!storage
+            .store_message(address(1), vec![4], quota)
+            .await
+            .unwrap());
+        // other inboxes are unaffected
+        assert!(storage
+            .store_message(address(2), vec![5], quota)
+            .await
+            .unwrap());
+
+        assert_eq!(
+            storage.get_inbox_usage(address(1)).await.unwrap(),
+            InboxUsage {
+                messages: 2,
+                bytes: 100
+            }
+        );
+        assert_eq!(storage.take_dropped_messages(address(1)).await.unwrap(), 2);
+
+        // removing the messages frees up the space again
+        let (messages, _) = storage.retrieve_messages(address(1), None).await.unwrap();
+        storage.remove_messages(vec![messages[0].id]).await.unwrap();
+        assert_eq!(
+            storage.get_inbox_usage(address(1)).await.unwrap(),
+            InboxUsage {
+                messages: 1,
+                bytes: 50
+            }
+        );
+        assert!(storage
+            .store_message(address(1), vec![6; 50], quota)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn expired_messages_are_recorded_as_dropped() {
+        let storage = InMemStorage::new(100);
+        store(&storage, 1, vec![1, 2, 3]).await;
+        store(&storage, 1, vec![4]).await;
+        // the message that didn't fit into the inbox got recorded as dropped as well
+        assert!(!storage
+            .store_message(address(1), vec![5], InboxQuota::new(2, 0))
+            .await
+            .unwrap());
+        assert_eq!(
+            storage.get_inbox_usage(address(1)).await.unwrap(),
+            InboxUsage {
+                messages: 2,
+                bytes: 4
+            }
+        );
+
+        let removed = storage
+            .remove_expired_messages(current_unix_timestamp() + 1)
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(
+            storage.get_inbox_usage(address(1)).await.unwrap(),
+            InboxUsage::default()
+        );
+
+        assert_eq!(storage.take_dropped_messages(address(1)).await.unwrap(), 3);
+        assert_eq!(storage.take_dropped_messages(address(1)).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn bandwidth_is_only_tracked_for_known_clients() {
+        let storage = InMemStorage::new(100);
+        storage.increase_bandwidth(address(1), 100).await.unwrap();
+        assert!(storage
+            .get_available_bandwidth(address(1))
+            .await
+            .unwrap()
+            .is_none());
+
+        storage.create_bandwidth_entry(address(1)).await.unwrap();
+        storage.increase_bandwidth(address(1), 100).await.unwrap();
+        storage.consume_bandwidth(address(1), 30).await.unwrap();
+        assert_eq!(
+            storage.get_available_bandwidth(address(1)).await.unwrap(),
+            Some(70)
+        );
+    }
+}
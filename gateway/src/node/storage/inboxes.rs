@@ -1,7 +1,8 @@
 // Copyright 2020 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::node::storage::models::StoredMessage;
+use crate::node::storage::models::{InboxUsage, StoredMessage};
+use crate::node::storage::retention::InboxQuota;
 
 #[derive(Clone)]
 pub(crate) struct InboxManager {
@@ -25,25 +26,75 @@ impl InboxManager {
         }
     }
 
-    /// Inserts new message to the storage for an offline client for future retrieval.
+    /// Inserts new message to the storage for an offline client for future retrieval, provided
+    /// its inbox is still within the quota. Otherwise the message is recorded as dropped instead.
+    /// The quota check and the insertion happen within a single transaction.
     ///
     /// # Arguments
     ///
     /// * `client_address_bs58`: base58-encoded address of the client
     /// * `content`: raw content of the message to store.
+    /// * `stored_at`: unix timestamp of when the message got stored.
+    /// * `quota`: limits on the messages stored for the client.
+    ///
+    /// returns whether the message got stored.
     pub(crate) async fn insert_message(
         &self,
         client_address_bs58: &str,
         content: Vec<u8>,
-    ) -> Result<(), sqlx::Error> {
+        stored_at: i64,
+        quota: InboxQuota,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.connection_pool.begin().await?;
+
+        // start with a write, so that the transaction would take the database lock straight away
+        // and no other message could get inserted in between the quota check and the insertion
         sqlx::query!(
-            "INSERT INTO message_store(client_address_bs58, content) VALUES (?, ?)",
-            client_address_bs58,
-            content,
+            r#"
+                INSERT INTO inbox_usage(client_address_bs58, messages, bytes) VALUES (?, 0, 0)
+                ON CONFLICT(client_address_bs58) DO NOTHING
+            "#,
+            client_address_bs58
         )
-        .execute(&self.connection_pool)
+        .execute(&mut tx)
         .await?;
-        Ok(())
+
+        let usage = sqlx::query_as!(
+            InboxUsage,
+            r#"
+                SELECT messages as "messages!: i64", bytes as "bytes!: i64"
+                FROM inbox_usage
+                WHERE client_address_bs58 = ?
+            "#,
+            client_address_bs58
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        let within_quota = quota.allows(usage, content.len());
+        if within_quota {
+            sqlx::query!(
+                "INSERT INTO message_store(client_address_bs58, content, stored_at) VALUES (?, ?, ?)",
+                client_address_bs58,
+                content,
+                stored_at,
+            )
+            .execute(&mut tx)
+            .await?;
+        } else {
+            sqlx::query!(
+                r#"
+                    INSERT INTO dropped_messages(client_address_bs58, dropped) VALUES (?, 1)
+                    ON CONFLICT(client_address_bs58) DO UPDATE SET dropped = dropped + 1
+                "#,
+                client_address_bs58
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(within_quota)
     }
 
     /// Retrieves messages stored for the particular client specified by the provided address.
@@ -70,7 +121,7 @@ impl InboxManager {
             sqlx::query_as!(
                 StoredMessage,
                 r#"
-                    SELECT * FROM message_store 
+                    SELECT id, client_address_bs58, content FROM message_store
                     WHERE client_address_bs58 = ? AND id > ?
                     ORDER BY id ASC
                     LIMIT ?;
@@ -85,7 +136,7 @@ impl InboxManager {
             sqlx::query_as!(
                 StoredMessage,
                 r#"
-                    SELECT * FROM message_store 
+                    SELECT id, client_address_bs58, content FROM message_store
                     WHERE client_address_bs58 = ?
                     ORDER BY id ASC
                     LIMIT ?;
@@ -119,4 +170,120 @@ impl InboxManager {
             .await?;
         Ok(())
     }
+
+    /// Retrieves the number and the total size of messages currently stored for the particular client.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_address_bs58`: base58-encoded address of the client
+    pub(crate) async fn get_usage(
+        &self,
+        client_address_bs58: &str,
+    ) -> Result<InboxUsage, sqlx::Error> {
+        let usage = sqlx::query_as!(
+            InboxUsage,
+            r#"
+                SELECT messages as "messages!: i64", bytes as "bytes!: i64"
+                FROM inbox_usage
+                WHERE client_address_bs58 = ?
+            "#,
+            client_address_bs58
+        )
+        .fetch_optional(&self.connection_pool)
+        .await?;
+        Ok(usage.unwrap_or_default())
+    }
+
+    /// Retrieves the number and the total size of messages currently stored for all clients.
+    pub(crate) async fn get_total_usage(&self) -> Result<InboxUsage, sqlx::Error> {
+        sqlx::query_as!(
+            InboxUsage,
+            r#"
+                SELECT COALESCE(SUM(messages), 0) as "messages!: i64", COALESCE(SUM(bytes), 0) as "bytes!: i64"
+                FROM inbox_usage
+            "#
+        )
+        .fetch_one(&self.connection_pool)
+        .await
+    }
+
+    /// Removes all messages stored before the specified time and records them as dropped
+    /// for their respective clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `stored_before`: unix timestamp before which the messages are considered expired.
+    ///
+    /// returns the number of removed messages.
+    pub(crate) async fn remove_expired_messages(
+        &self,
+        stored_before: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.connection_pool.begin().await?;
+
+        let expired = sqlx::query!(
+            r#"
+                SELECT client_address_bs58, COUNT(*) as "count!: i64"
+                FROM message_store
+                WHERE stored_at < ?
+                GROUP BY client_address_bs58
+            "#,
+            stored_before
+        )
+        .fetch_all(&mut tx)
+        .await?;
+
+        for client in expired {
+            sqlx::query!(
+                r#"
+                    INSERT INTO dropped_messages(client_address_bs58, dropped) VALUES (?, ?)
+                    ON CONFLICT(client_address_bs58) DO UPDATE SET dropped = dropped + excluded.dropped
+                "#,
+                client.client_address_bs58,
+                client.count
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+
+        let removed = sqlx::query!(
+            "DELETE FROM message_store WHERE stored_at < ?",
+            stored_before
+        )
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    /// Retrieves the number of messages dropped for the particular client and resets it.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_address_bs58`: base58-encoded address of the client
+    pub(crate) async fn take_dropped_messages(
+        &self,
+        client_address_bs58: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.connection_pool.begin().await?;
+
+        let dropped = sqlx::query_scalar!(
+            "SELECT dropped FROM dropped_messages WHERE client_address_bs58 = ?",
+            client_address_bs58
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            "DELETE FROM dropped_messages WHERE client_address_bs58 = ?",
+            client_address_bs58
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(dropped.unwrap_or_default())
+    }
 }
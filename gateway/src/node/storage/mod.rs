@@ -4,7 +4,8 @@
 use crate::node::storage::bandwidth::BandwidthManager;
 use crate::node::storage::error::StorageError;
 use crate::node::storage::inboxes::InboxManager;
-use crate::node::storage::models::{PersistedSharedKeys, StoredMessage};
+use crate::node::storage::models::{InboxUsage, PersistedSharedKeys, StoredMessage};
+use crate::node::storage::retention::InboxQuota;
 use crate::node::storage::shared_keys::SharedKeysManager;
 use async_trait::async_trait;
 use gateway_requests::registration::handshake::SharedKeys;
@@ -12,13 +13,20 @@ use log::{debug, error};
 use nym_sphinx::DestinationAddressBytes;
 use sqlx::ConnectOptions;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 mod bandwidth;
 pub(crate) mod error;
+mod in_memory;
 mod inboxes;
-mod models;
+pub(crate) mod models;
+mod postgres;
+pub(crate) mod retention;
 mod shared_keys;
 
+pub(crate) use in_memory::InMemStorage;
+pub(crate) use postgres::PostgresStorage;
+
 #[async_trait]
 pub(crate) trait Storage: Send + Sync {
     /// Inserts provided derived shared keys into the database.
@@ -57,17 +65,23 @@ pub(crate) trait Storage: Send + Sync {
         client_address: DestinationAddressBytes,
     ) -> Result<(), StorageError>;
 
-    /// Inserts new message to the storage for an offline client for future retrieval.
+    /// Inserts new message to the storage for an offline client for future retrieval, provided
+    /// its inbox is still within the quota. Otherwise the message is recorded as dropped instead.
+    /// The quota check and the insertion are performed atomically.
     ///
     /// # Arguments
     ///
     /// * `client_address`: address of the client
     /// * `message`: raw message to store.
+    /// * `quota`: limits on the messages stored for the client.
+    ///
+    /// returns whether the message got stored.
     async fn store_message(
         &self,
         client_address: DestinationAddressBytes,
         message: Vec<u8>,
-    ) -> Result<(), StorageError>;
+        quota: InboxQuota,
+    ) -> Result<bool, StorageError>;
 
     /// Retrieves messages stored for the particular client specified by the provided address.
     ///
@@ -91,6 +105,43 @@ pub(crate) trait Storage: Send + Sync {
     /// * `ids`: ids of the messages to remove
     async fn remove_messages(&self, ids: Vec<i64>) -> Result<(), StorageError>;
 
+    /// Retrieves the number and the total size of messages currently stored for the particular client.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_address`: address of the client
+    // the quotas are enforced by `store_message` itself, so currently nothing else needs it.
+    // However, retain the function for consistency and completion sake
+    #[allow(dead_code)]
+    async fn get_inbox_usage(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<InboxUsage, StorageError>;
+
+    /// Retrieves the number and the total size of messages currently stored for all clients.
+    async fn get_total_inbox_usage(&self) -> Result<InboxUsage, StorageError>;
+
+    /// Removes all messages stored before the specified time and records them as dropped
+    /// for their respective clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `stored_before`: unix timestamp before which the messages are considered expired.
+    ///
+    /// returns the number of removed messages.
+    async fn remove_expired_messages(&self, stored_before: i64) -> Result<u64, StorageError>;
+
+    /// Retrieves the number of messages dropped for the particular client since the last time
+    /// this method got called and resets it back to 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_address`: address of the client
+    async fn take_dropped_messages(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<i64, StorageError>;
+
     /// Creates a new bandwidth entry for the particular client.
     ///
     /// # Arguments
@@ -136,6 +187,14 @@ pub(crate) trait Storage: Send + Sync {
     ) -> Result<(), StorageError>;
 }
 
+/// Returns the current unix timestamp (in seconds) used for marking when messages got stored.
+pub(crate) fn current_unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
 // note that clone here is fine as upon cloning the same underlying pool will be used
 #[derive(Clone)]
 pub(crate) struct PersistentStorage {
@@ -235,11 +294,18 @@ impl Storage for PersistentStorage {
         &self,
         client_address: DestinationAddressBytes,
         message: Vec<u8>,
-    ) -> Result<(), StorageError> {
-        self.inbox_manager
-            .insert_message(&client_address.as_base58_string(), message)
+        quota: InboxQuota,
+    ) -> Result<bool, StorageError> {
+        let stored = self
+            .inbox_manager
+            .insert_message(
+                &client_address.as_base58_string(),
+                message,
+                current_unix_timestamp(),
+                quota,
+            )
             .await?;
-        Ok(())
+        Ok(stored)
     }
 
     async fn retrieve_messages(
@@ -261,6 +327,41 @@ impl Storage for PersistentStorage {
         Ok(())
     }
 
+    async fn get_inbox_usage(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<InboxUsage, StorageError> {
+        let usage = self
+            .inbox_manager
+            .get_usage(&client_address.as_base58_string())
+            .await?;
+        Ok(usage)
+    }
+
+    async fn get_total_inbox_usage(&self) -> Result<InboxUsage, StorageError> {
+        let usage = self.inbox_manager.get_total_usage().await?;
+        Ok(usage)
+    }
+
+    async fn remove_expired_messages(&self, stored_before: i64) -> Result<u64, StorageError> {
+        let removed = self
+            .inbox_manager
+            .remove_expired_messages(stored_before)
+            .await?;
+        Ok(removed)
+    }
+
+    async fn take_dropped_messages(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<i64, StorageError> {
+        let dropped = self
+            .inbox_manager
+            .take_dropped_messages(&client_address.as_base58_string())
+            .await?;
+        Ok(dropped)
+    }
+
     async fn create_bandwidth_entry(
         &self,
         client_address: DestinationAddressBytes,
@@ -306,91 +407,247 @@ impl Storage for PersistentStorage {
     }
 }
 
-/// In-memory implementation of `Storage`. The intention is primarily in testing environments.
-#[cfg(test)]
+/// Storage backend selected in the gateway config.
+// note that clone here is fine as all of the variants share their underlying data upon cloning
 #[derive(Clone)]
-pub(crate) struct InMemStorage;
+pub(crate) enum GatewayStorage {
+    Sqlite(PersistentStorage),
+    Postgres(PostgresStorage),
+    InMemory(InMemStorage),
+}
 
-#[cfg(test)]
-impl InMemStorage {
-    #[allow(unused)]
-    async fn init<P: AsRef<Path> + Send>() -> Result<Self, StorageError> {
-        todo!()
+impl From<PersistentStorage> for GatewayStorage {
+    fn from(storage: PersistentStorage) -> Self {
+        GatewayStorage::Sqlite(storage)
     }
 }
 
-#[cfg(test)]
+impl From<PostgresStorage> for GatewayStorage {
+    fn from(storage: PostgresStorage) -> Self {
+        GatewayStorage::Postgres(storage)
+    }
+}
+
+impl From<InMemStorage> for GatewayStorage {
+    fn from(storage: InMemStorage) -> Self {
+        GatewayStorage::InMemory(storage)
+    }
+}
+
+macro_rules! delegate {
+    ($self:ident, $method:ident($($arg:ident),*)) => {
+        match $self {
+            GatewayStorage::Sqlite(storage) => storage.$method($($arg),*).await,
+            GatewayStorage::Postgres(storage) => storage.$method($($arg),*).await,
+            GatewayStorage::InMemory(storage) => storage.$method($($arg),*).await,
+        }
+    };
+}
+
 #[async_trait]
-impl Storage for InMemStorage {
+impl Storage for GatewayStorage {
     async fn insert_shared_keys(
         &self,
-        _client_address: DestinationAddressBytes,
-        _shared_keys: SharedKeys,
+        client_address: DestinationAddressBytes,
+        shared_keys: SharedKeys,
     ) -> Result<(), StorageError> {
-        todo!()
+        delegate!(self, insert_shared_keys(client_address, shared_keys))
     }
 
     async fn get_shared_keys(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<Option<PersistedSharedKeys>, StorageError> {
-        todo!()
+        delegate!(self, get_shared_keys(client_address))
     }
 
     async fn remove_shared_keys(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<(), StorageError> {
-        todo!()
+        delegate!(self, remove_shared_keys(client_address))
     }
 
     async fn store_message(
         &self,
-        _client_address: DestinationAddressBytes,
-        _message: Vec<u8>,
-    ) -> Result<(), StorageError> {
-        todo!()
+        client_address: DestinationAddressBytes,
+        message: Vec<u8>,
+        quota: InboxQuota,
+    ) -> Result<bool, StorageError> {
+        delegate!(self, store_message(client_address, message, quota))
     }
 
     async fn retrieve_messages(
         &self,
-        _client_address: DestinationAddressBytes,
-        _start_after: Option<i64>,
+        client_address: DestinationAddressBytes,
+        start_after: Option<i64>,
     ) -> Result<(Vec<StoredMessage>, Option<i64>), StorageError> {
-        todo!()
+        delegate!(self, retrieve_messages(client_address, start_after))
+    }
+
+    async fn remove_messages(&self, ids: Vec<i64>) -> Result<(), StorageError> {
+        delegate!(self, remove_messages(ids))
+    }
+
+    async fn get_inbox_usage(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<InboxUsage, StorageError> {
+        delegate!(self, get_inbox_usage(client_address))
+    }
+
+    async fn get_total_inbox_usage(&self) -> Result<InboxUsage, StorageError> {
+        delegate!(self, get_total_inbox_usage())
+    }
+
+    async fn remove_expired_messages(&self, stored_before: i64) -> Result<u64, StorageError> {
+        delegate!(self, remove_expired_messages(stored_before))
     }
 
-    async fn remove_messages(&self, _ids: Vec<i64>) -> Result<(), StorageError> {
-        todo!()
+    async fn record_dropped_messages(
+        &self,
+        client_address: DestinationAddressBytes,
+        count: i64,
+    ) -> Result<(), StorageError> {
+        delegate!(self, record_dropped_messages(client_address, count))
+    }
+
+    async fn take_dropped_messages(
+        &self,
+        client_address: DestinationAddressBytes,
+    ) -> Result<i64, StorageError> {
+        delegate!(self, take_dropped_messages(client_address))
     }
 
     async fn create_bandwidth_entry(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<(), StorageError> {
-        todo!()
+        delegate!(self, create_bandwidth_entry(client_address))
     }
 
     async fn get_available_bandwidth(
         &self,
-        _client_address: DestinationAddressBytes,
+        client_address: DestinationAddressBytes,
     ) -> Result<Option<i64>, StorageError> {
-        todo!()
+        delegate!(self, get_available_bandwidth(client_address))
     }
 
     async fn increase_bandwidth(
         &self,
-        _client_address: DestinationAddressBytes,
-        _amount: i64,
+        client_address: DestinationAddressBytes,
+        amount: i64,
     ) -> Result<(), StorageError> {
-        todo!()
+        delegate!(self, increase_bandwidth(client_address, amount))
     }
 
     async fn consume_bandwidth(
         &self,
-        _client_address: DestinationAddressBytes,
-        _amount: i64,
+        client_address: DestinationAddressBytes,
+        amount: i64,
     ) -> Result<(), StorageError> {
-        todo!()
+        delegate!(self, consume_bandwidth(client_address, amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // sqlite database in the temporary directory, removed once the test is over
+    struct TemporaryDatabase(PathBuf);
+
+    impl TemporaryDatabase {
+        fn new() -> Self {
+            TemporaryDatabase(std::env::temp_dir().join(format!(
+                "gateway-storage-test-{}.sqlite",
+                rand::random::<u64>()
+            )))
+        }
+
+        async fn storage(&self) -> PersistentStorage {
+            PersistentStorage::init(&self.0, 100).await.unwrap()
+        }
+    }
+
+    impl Drop for TemporaryDatabase {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn address(byte: u8) -> DestinationAddressBytes {
+        DestinationAddressBytes::from_bytes([byte; 32])
+    }
+
+    // (messages, bytes) stored for the particular client
+    async fn usage(storage: &PersistentStorage, client: u8) -> (i64, i64) {
+        let usage = storage.get_inbox_usage(address(client)).await.unwrap();
+        (usage.messages, usage.bytes)
+    }
+
+    async fn total_usage(storage: &PersistentStorage) -> (i64, i64) {
+        let usage = storage.get_total_inbox_usage().await.unwrap();
+        (usage.messages, usage.bytes)
+    }
+
+    async fn store(storage: &PersistentStorage, client: u8, message: Vec<u8>) -> bool {
+        storage
+            .store_message(address(client), message, InboxQuota::new(2, 0))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn inbox_usage_follows_stored_and_removed_messages() {
+        let database = TemporaryDatabase::new();
+        let storage = database.storage().await;
+        assert_eq!(total_usage(&storage).await, (0, 0));
+
+        assert!(store(&storage, 1, vec![1, 2]).await);
+        assert!(store(&storage, 1, vec![3]).await);
+        assert!(store(&storage, 2, vec![4, 5, 6]).await);
+
+        assert_eq!(usage(&storage, 1).await, (2, 3));
+        assert_eq!(usage(&storage, 2).await, (1, 3));
+        assert_eq!(usage(&storage, 3).await, (0, 0));
+        assert_eq!(total_usage(&storage).await, (3, 6));
+
+        let (messages, _) = storage.retrieve_messages(address(1), None).await.unwrap();
+        storage.remove_messages(vec![messages[0].id]).await.unwrap();
+
+        assert_eq!(usage(&storage, 1).await, (1, 1));
+        assert_eq!(total_usage(&storage).await, (2, 4));
+    }
+
+    #[tokio::test]
+    async fn messages_over_the_quota_are_dropped_without_being_counted() {
+        let database = TemporaryDatabase::new();
+        let storage = database.storage().await;
+
+        assert!(store(&storage, 1, vec![1]).await);
+        assert!(store(&storage, 1, vec![2]).await);
+        assert!(!store(&storage, 1, vec![3, 4, 5]).await);
+
+        assert_eq!(usage(&storage, 1).await, (2, 2));
+        assert_eq!(total_usage(&storage).await, (2, 2));
+        assert_eq!(storage.take_dropped_messages(address(1)).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_messages_are_no_longer_counted() {
+        let database = TemporaryDatabase::new();
+        let storage = database.storage().await;
+
+        assert!(store(&storage, 1, vec![1, 2]).await);
+        assert!(store(&storage, 2, vec![3]).await);
+
+        let removed = storage.remove_expired_messages(i64::MAX).await.unwrap();
+        assert_eq!(removed, 2);
+
+        assert_eq!(usage(&storage, 1).await, (0, 0));
+        assert_eq!(total_usage(&storage).await, (0, 0));
+        assert_eq!(storage.take_dropped_messages(address(2)).await.unwrap(), 1);
     }
 }
@@ -1,11 +1,13 @@
 // Copyright 2021 - Nym Technologies SA <contact@nymtech.net>
 // SPDX-License-Identifier: Apache-2.0
 
+#[derive(Clone, sqlx::FromRow)]
 pub(crate) struct PersistedSharedKeys {
     pub(crate) client_address_bs58: String,
     pub(crate) derived_aes128_ctr_blake3_hmac_keys_bs58: String,
 }
 
+#[derive(sqlx::FromRow)]
 pub(crate) struct StoredMessage {
     pub(crate) id: i64,
     #[allow(dead_code)]
@@ -18,3 +20,12 @@ pub(crate) struct PersistedBandwidth {
     pub(crate) client_address_bs58: String,
     pub(crate) available: i64,
 }
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, sqlx::FromRow)]
+pub(crate) struct InboxUsage {
+    /// Number of messages currently stored for the client.
+    pub(crate) messages: i64,
+
+    /// Total size, in bytes, of the messages currently stored for the client.
+    pub(crate) bytes: i64,
+}
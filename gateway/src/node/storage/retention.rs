@@ -0,0 +1,104 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::node::storage::models::InboxUsage;
+use crate::node::storage::{current_unix_timestamp, Storage};
+use log::*;
+use nym_task::TaskClient;
+use std::time::Duration;
+
+/// Limits on the messages that can be stored for a single offline client.
+/// The default quota does not impose any limits.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct InboxQuota {
+    /// Maximum number of stored messages. 0 disables the limit.
+    max_messages: i64,
+
+    /// Maximum total size (in bytes) of stored messages. 0 disables the limit.
+    max_bytes: i64,
+}
+
+impl InboxQuota {
+    pub(crate) fn new(max_messages: i64, max_bytes: i64) -> Self {
+        InboxQuota {
+            max_messages,
+            max_bytes,
+        }
+    }
+
+    /// Checks whether a message of the specified length can be stored in an inbox with the given usage.
+    pub(crate) fn allows(&self, usage: InboxUsage, message_len: usize) -> bool {
+        let within_messages = self.max_messages <= 0 || usage.messages < self.max_messages;
+        let within_bytes =
+            self.max_bytes <= 0 || usage.bytes + message_len as i64 <= self.max_bytes;
+        within_messages && within_bytes
+    }
+}
+
+/// Periodically removes messages that have been stored for offline clients for longer than
+/// the configured time to live.
+pub(crate) struct InboxPruner<St> {
+    storage: St,
+    ttl: Duration,
+    pruning_interval: Duration,
+}
+
+impl<St> InboxPruner<St>
+where
+    St: Storage,
+{
+    pub(crate) fn new(storage: St, ttl: Duration, pruning_interval: Duration) -> Self {
+        InboxPruner {
+            storage,
+            ttl,
+            pruning_interval,
+        }
+    }
+
+    async fn remove_expired_messages(&self) {
+        let stored_before = current_unix_timestamp() - self.ttl.as_secs() as i64;
+        match self.storage.remove_expired_messages(stored_before).await {
+            Err(err) => error!("Failed to remove expired client messages - {err}"),
+            Ok(0) => trace!("There were no expired client messages to remove"),
+            Ok(removed) => info!("Removed {removed} expired client messages"),
+        }
+    }
+
+    pub(crate) async fn run(self, mut shutdown: TaskClient) {
+        let mut pruning_interval = tokio::time::interval(self.pruning_interval);
+
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => {
+                    log::trace!("InboxPruner: received shutdown");
+                }
+                _ = pruning_interval.tick() => self.remove_expired_messages().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(messages: i64, bytes: i64) -> InboxUsage {
+        InboxUsage { messages, bytes }
+    }
+
+    #[test]
+    fn quota_limits_both_messages_and_bytes() {
+        let quota = InboxQuota::new(2, 100);
+        assert!(quota.allows(usage(0, 0), 100));
+        assert!(quota.allows(usage(1, 50), 50));
+        assert!(!quota.allows(usage(1, 50), 51));
+        assert!(!quota.allows(usage(2, 10), 1));
+    }
+
+    #[test]
+    fn zero_disables_the_limits() {
+        let quota = InboxQuota::new(0, 0);
+        assert!(quota.allows(usage(i64::MAX - 1, 1_000_000_000), 1_000_000));
+    }
+}
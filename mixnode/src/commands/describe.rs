@@ -2,10 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::config::Config;
-use crate::node::node_description::NodeDescription;
 use clap::Args;
 use colored::Colorize;
 use config::NymConfig;
+use mixnode_common::node_description::NodeDescription;
 use std::io;
 use std::io::Write;
 
@@ -7,6 +7,7 @@ use config::defaults::{
     DEFAULT_HTTP_API_LISTENING_PORT, DEFAULT_MIX_LISTENING_PORT, DEFAULT_VERLOC_LISTENING_PORT,
 };
 use config::NymConfig;
+use mixnode_common::packet_processor::replay_protection::{self, ReplayProtectionSettings};
 use serde::{Deserialize, Deserializer, Serialize};
 use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
@@ -32,10 +33,13 @@ const DEFAULT_RETRY_TIMEOUT: Duration = Duration::from_secs(60 * 30);
 // 'DEBUG'
 const DEFAULT_NODE_STATS_LOGGING_DELAY: Duration = Duration::from_millis(60_000);
 const DEFAULT_NODE_STATS_UPDATING_DELAY: Duration = Duration::from_millis(30_000);
+const DEFAULT_RECENT_DROPPED_PACKETS_CAPACITY: usize = 256;
 const DEFAULT_PACKET_FORWARDING_INITIAL_BACKOFF: Duration = Duration::from_millis(10_000);
 const DEFAULT_PACKET_FORWARDING_MAXIMUM_BACKOFF: Duration = Duration::from_millis(300_000);
 const DEFAULT_INITIAL_CONNECTION_TIMEOUT: Duration = Duration::from_millis(1_500);
 const DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE: usize = 128;
+const DEFAULT_SPHINX_KEY_ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_SPHINX_KEY_OVERLAP: Duration = Duration::from_secs(60 * 60);
 
 pub fn missing_string_value<T: From<String>>() -> T {
     MISSING_VALUE.to_string().into()
@@ -238,6 +242,10 @@ impl Config {
         self.debug.node_stats_updating_delay
     }
 
+    pub fn get_recent_dropped_packets_capacity(&self) -> usize {
+        self.debug.recent_dropped_packets_capacity
+    }
+
     pub fn get_listening_address(&self) -> IpAddr {
         self.mixnode.listening_address
     }
@@ -278,6 +286,36 @@ impl Config {
         self.debug.use_legacy_framed_packet_version
     }
 
+    pub fn get_replay_protection_enabled(&self) -> bool {
+        self.debug.replay_protection.enabled
+    }
+
+    pub fn get_replay_protection_filter_config(&self) -> replay_protection::Config {
+        self.debug
+            .replay_protection
+            .filter_config(&self.data_directory())
+    }
+
+    pub fn get_sphinx_key_rotation_enabled(&self) -> bool {
+        self.debug.sphinx_key_rotation_enabled
+    }
+
+    pub fn get_sphinx_key_rotation_check_interval(&self) -> Duration {
+        self.debug.sphinx_key_rotation_check_interval
+    }
+
+    pub fn get_sphinx_key_overlap(&self) -> Duration {
+        self.debug.sphinx_key_overlap
+    }
+
+    pub fn get_upcoming_private_sphinx_key_file(&self) -> PathBuf {
+        self.data_directory().join("upcoming_private_sphinx.pem")
+    }
+
+    pub fn get_upcoming_public_sphinx_key_file(&self) -> PathBuf {
+        self.data_directory().join("upcoming_public_sphinx.pem")
+    }
+
     pub fn get_version(&self) -> &str {
         &self.mixnode.version
     }
@@ -468,6 +506,9 @@ struct Debug {
     #[serde(with = "humantime_serde")]
     node_stats_updating_delay: Duration,
 
+    /// Maximum number of the most recent packet drop events kept for the purposes of the http api.
+    recent_dropped_packets_capacity: usize,
+
     /// Initial value of an exponential backoff to reconnect to dropped TCP connection when
     /// forwarding sphinx packets.
     #[serde(with = "humantime_serde")]
@@ -490,6 +531,22 @@ struct Debug {
     // existing nodes whilst everyone else is upgrading and getting the code for handling the new field.
     // It shall be disabled in the subsequent releases.
     use_legacy_framed_packet_version: bool,
+
+    /// Settings of the filter used for rejecting replayed sphinx packets.
+    #[serde(flatten)]
+    replay_protection: ReplayProtectionSettings,
+
+    /// Specifies whether the mixnode should rotate its sphinx key once the key it has announced
+    /// in the mixnet contract becomes active.
+    sphinx_key_rotation_enabled: bool,
+
+    /// Delay between subsequent checks of whether the announced sphinx key has become active.
+    #[serde(with = "humantime_serde")]
+    sphinx_key_rotation_check_interval: Duration,
+
+    /// Duration for which packets created with the previous sphinx key are still accepted after the rotation.
+    #[serde(with = "humantime_serde")]
+    sphinx_key_overlap: Duration,
 }
 
 impl Default for Debug {
@@ -497,12 +554,17 @@ impl Default for Debug {
         Debug {
             node_stats_logging_delay: DEFAULT_NODE_STATS_LOGGING_DELAY,
             node_stats_updating_delay: DEFAULT_NODE_STATS_UPDATING_DELAY,
+            recent_dropped_packets_capacity: DEFAULT_RECENT_DROPPED_PACKETS_CAPACITY,
             packet_forwarding_initial_backoff: DEFAULT_PACKET_FORWARDING_INITIAL_BACKOFF,
             packet_forwarding_maximum_backoff: DEFAULT_PACKET_FORWARDING_MAXIMUM_BACKOFF,
             initial_connection_timeout: DEFAULT_INITIAL_CONNECTION_TIMEOUT,
             maximum_connection_buffer_size: DEFAULT_MAXIMUM_CONNECTION_BUFFER_SIZE,
             // TODO: remember to change it in one of future releases!!
             use_legacy_framed_packet_version: true,
+            replay_protection: Default::default(),
+            sphinx_key_rotation_enabled: true,
+            sphinx_key_rotation_check_interval: DEFAULT_SPHINX_KEY_ROTATION_CHECK_INTERVAL,
+            sphinx_key_overlap: DEFAULT_SPHINX_KEY_OVERLAP,
         }
     }
 }
@@ -0,0 +1,12 @@
+use crate::node::node_statistics::{DroppedPacketsLog, DroppedPacketsSnapshot};
+use rocket::serde::json::Json;
+use rocket::State;
+
+/// Returns the number of packets dropped by this mixnode for each reason since startup
+/// alongside a rate limited sample of the most recent drop events.
+#[get("/dropped-packets")]
+pub(crate) async fn dropped_packets(
+    log: &State<DroppedPacketsLog>,
+) -> Json<DroppedPacketsSnapshot> {
+    Json(log.snapshot())
+}
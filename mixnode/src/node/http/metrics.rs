@@ -0,0 +1,234 @@
+use crate::node::node_statistics::{DroppedPacketsLog, ProcessingLatency, SharedNodeStats};
+use mixnet_client::ConnectionStats;
+use mixnode_common::dropped_packets::DropReason;
+use mixnode_common::metrics::{MetricType, PrometheusEncoder};
+use mixnode_common::packet_delayforwarder::{DelayAccounting, DelayQueueLength};
+use mixnode_common::packet_processor::replay_protection::ReplayProtectionMetrics;
+use mixnode_common::verloc::AtomicVerlocResult;
+use rocket::http::ContentType;
+use rocket::State;
+use std::time::UNIX_EPOCH;
+
+/// Sources of all the metrics exposed by this mixnode.
+pub(crate) struct MetricsState {
+    pub(crate) node_stats: SharedNodeStats,
+    pub(crate) verloc: AtomicVerlocResult,
+    pub(crate) delay_queue_length: DelayQueueLength,
+    pub(crate) delay_accounting: DelayAccounting,
+    pub(crate) connection_stats: ConnectionStats,
+    pub(crate) dropped_packets_log: DroppedPacketsLog,
+    pub(crate) processing_latency: ProcessingLatency,
+    pub(crate) replay_protection: Option<ReplayProtectionMetrics>,
+}
+
+impl MetricsState {
+    async fn encode_packet_stats(&self, encoder: &mut PrometheusEncoder) {
+        let stats = self.node_stats.clone_data().await;
+
+        encoder.counter(
+            "nym_mixnode_packets_received_total",
+            "Total number of packets received by the mixnode.",
+            stats.packets_received_since_startup(),
+        );
+
+        encoder.describe(
+            "nym_mixnode_packets_sent_total",
+            "Total number of packets sent to each destination. It does not imply they were delivered.",
+            MetricType::Counter,
+        );
+        for (destination, count) in stats.packets_sent_since_startup() {
+            encoder.sample(
+                "nym_mixnode_packets_sent_total",
+                &[("destination", destination)],
+                count,
+            );
+        }
+
+        encoder.describe(
+            "nym_mixnode_packets_dropped_total",
+            "Total number of packets explicitly dropped for each destination.",
+            MetricType::Counter,
+        );
+        for (destination, count) in stats.packets_explicitly_dropped_since_startup() {
+            encoder.sample(
+                "nym_mixnode_packets_dropped_total",
+                &[("destination", destination)],
+                count,
+            );
+        }
+
+        encoder.describe(
+            "nym_mixnode_packets_dropped_by_reason_total",
+            "Total number of packets dropped or rejected for each reason.",
+            MetricType::Counter,
+        );
+        for reason in DropReason::ALL {
+            encoder.sample(
+                "nym_mixnode_packets_dropped_by_reason_total",
+                &[("reason", reason.as_str())],
+                self.dropped_packets_log.total(reason),
+            );
+        }
+    }
+
+    fn encode_forwarding_stats(&self, encoder: &mut PrometheusEncoder) {
+        encoder.gauge(
+            "nym_mixnode_delay_queue_packets",
+            "Number of packets currently being delayed before getting forwarded.",
+            self.delay_queue_length.get(),
+        );
+        encoder.gauge(
+            "nym_mixnode_mixnet_connections_established",
+            "Number of currently open connections to other nodes.",
+            self.connection_stats.established(),
+        );
+        encoder.gauge(
+            "nym_mixnode_mixnet_connections_pending",
+            "Number of connections to other nodes that are currently being established.",
+            self.connection_stats.pending(),
+        );
+        encoder.counter(
+            "nym_mixnode_mixnet_connection_failures_total",
+            "Total number of failed attempts at connecting to other nodes.",
+            self.connection_stats.failed_attempts(),
+        );
+    }
+
+    fn encode_delay_accounting(&self, encoder: &mut PrometheusEncoder) {
+        encoder.counter(
+            "nym_mixnode_delayed_packets_total",
+            "Total number of forwarded packets that had a delay attached to them.",
+            self.delay_accounting.delayed_packets(),
+        );
+        encoder.describe(
+            "nym_mixnode_requested_delay_seconds_total",
+            "Sum of the delays requested by all the delayed packets.",
+            MetricType::Counter,
+        );
+        encoder.sample(
+            "nym_mixnode_requested_delay_seconds_total",
+            &[],
+            self.delay_accounting.total_requested_delay().as_secs_f64(),
+        );
+        encoder.describe(
+            "nym_mixnode_actual_delay_seconds_total",
+            "Sum of the delays actually experienced by all the delayed packets.",
+            MetricType::Counter,
+        );
+        encoder.sample(
+            "nym_mixnode_actual_delay_seconds_total",
+            &[],
+            self.delay_accounting.total_actual_delay().as_secs_f64(),
+        );
+        encoder.histogram(
+            "nym_mixnode_delay_lateness_seconds",
+            "How much later than requested the delayed packets got forwarded.",
+            &self.delay_accounting.lateness(),
+        );
+    }
+
+    fn encode_processing_latency(&self, encoder: &mut PrometheusEncoder) {
+        encoder.describe(
+            "nym_mixnode_packet_processing_seconds",
+            "Time it took to unwrap the received packets.",
+            MetricType::Histogram,
+        );
+        encoder.histogram_samples(
+            "nym_mixnode_packet_processing_seconds",
+            &[("packet_type", "sphinx")],
+            &self.processing_latency.sphinx_snapshot(),
+        );
+        encoder.histogram_samples(
+            "nym_mixnode_packet_processing_seconds",
+            &[("packet_type", "outfox")],
+            &self.processing_latency.outfox_snapshot(),
+        );
+    }
+
+    fn encode_replay_protection(&self, encoder: &mut PrometheusEncoder) {
+        let Some(replay_protection) = &self.replay_protection else {
+            return;
+        };
+
+        encoder.counter(
+            "nym_mixnode_replay_protection_checked_packets_total",
+            "Total number of packets checked against the replay protection filter.",
+            replay_protection.checked_packets(),
+        );
+        encoder.counter(
+            "nym_mixnode_replay_protection_rejected_packets_total",
+            "Total number of packets rejected as replays.",
+            replay_protection.rejected_replays(),
+        );
+        encoder.counter(
+            "nym_mixnode_replay_protection_rotations_total",
+            "Total number of rotations of the replay protection filter.",
+            replay_protection.rotations(),
+        );
+        encoder.counter(
+            "nym_mixnode_replay_protection_overflows_total",
+            "Total number of times a shard of the replay protection filter got full before the sphinx key rotation.",
+            replay_protection.overflows(),
+        );
+    }
+
+    async fn encode_verloc(&self, encoder: &mut PrometheusEncoder) {
+        let verloc = self.verloc.clone_data().await;
+
+        encoder.gauge(
+            "nym_mixnode_verloc_tested_nodes",
+            "Number of nodes tested during the latest verloc measurement run.",
+            verloc.total_tested(),
+        );
+        if let Some(run_finished) = verloc.run_finished() {
+            let timestamp = run_finished
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            encoder.gauge(
+                "nym_mixnode_verloc_last_run_timestamp_seconds",
+                "Unix timestamp of the completion of the latest verloc measurement run.",
+                timestamp,
+            );
+        }
+
+        encoder.describe(
+            "nym_mixnode_verloc_rtt_seconds",
+            "Round-trip times to other mixnodes measured during the latest verloc run.",
+            MetricType::Gauge,
+        );
+        for result in verloc.results() {
+            let Some(measurement) = result.latest_measurement else {
+                continue;
+            };
+            let identity = result.identity.to_base58_string();
+            for (statistic, value) in [
+                ("minimum", measurement.minimum),
+                ("mean", measurement.mean),
+                ("maximum", measurement.maximum),
+                ("standard_deviation", measurement.standard_deviation),
+            ] {
+                encoder.sample(
+                    "nym_mixnode_verloc_rtt_seconds",
+                    &[("identity", &identity), ("statistic", statistic)],
+                    value.as_secs_f64(),
+                );
+            }
+        }
+    }
+}
+
+/// Returns the metrics of this mixnode in the Prometheus text format.
+#[get("/metrics")]
+pub(crate) async fn metrics(state: &State<MetricsState>) -> (ContentType, String) {
+    let mut encoder = PrometheusEncoder::new();
+
+    state.encode_packet_stats(&mut encoder).await;
+    state.encode_forwarding_stats(&mut encoder);
+    state.encode_delay_accounting(&mut encoder);
+    state.encode_processing_latency(&mut encoder);
+    state.encode_replay_protection(&mut encoder);
+    state.encode_verloc(&mut encoder).await;
+
+    (ContentType::Plain, encoder.finish())
+}
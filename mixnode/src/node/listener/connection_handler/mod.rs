@@ -4,13 +4,13 @@
 use crate::node::listener::connection_handler::packet_processing::{
     MixProcessingResult, PacketProcessor,
 };
-use crate::node::packet_delayforwarder::PacketDelayForwardSender;
 use crate::node::TaskClient;
 use futures::StreamExt;
 use log::{error, info};
+use mixnode_common::packet_delayforwarder::PacketDelayForwardSender;
 use nym_sphinx::forwarding::packet::MixPacket;
-use nym_sphinx::framing::codec::SphinxCodec;
-use nym_sphinx::framing::packet::FramedSphinxPacket;
+use nym_sphinx::framing::codec::NymCodec;
+use nym_sphinx::framing::packet::FramedNymPacket;
 use nym_sphinx::Delay as SphinxDelay;
 use std::net::SocketAddr;
 use tokio::net::TcpStream;
@@ -48,17 +48,17 @@ impl ConnectionHandler {
             .expect("the delay-forwarder has died!");
     }
 
-    fn handle_received_packet(&self, framed_sphinx_packet: FramedSphinxPacket) {
-        //
-        // TODO: here be replay attack detection - it will require similar key cache to the one in
-        // packet processor for vpn packets,
-        // question: can it also be per connection vs global?
-        //
+    fn handle_received_packet(&self, framed_nym_packet: FramedNymPacket) {
+        // note: replayed packets are rejected by the packet processor itself, using the replay
+        // protection filter shared by all connections
 
         // all processing such, key caching, etc. was done.
         // however, if it was a forward hop, we still need to delay it
-        match self.packet_processor.process_received(framed_sphinx_packet) {
-            Err(err) => debug!("We failed to process received sphinx packet - {err}"),
+        match self.packet_processor.process_received(framed_nym_packet) {
+            Err(err) => {
+                debug!("We failed to process received sphinx packet - {err}");
+                self.packet_processor.report_rejected(&err)
+            }
             Ok(res) => match res {
                 MixProcessingResult::ForwardHop(forward_packet, delay) => {
                     self.delay_and_forward_packet(forward_packet, delay)
@@ -78,16 +78,16 @@ impl ConnectionHandler {
     ) {
         debug!("Starting connection handler for {:?}", remote);
         shutdown.mark_as_success();
-        let mut framed_conn = Framed::new(conn, SphinxCodec);
+        let mut framed_conn = Framed::new(conn, NymCodec);
         while !shutdown.is_shutdown() {
             tokio::select! {
                 biased;
                 _ = shutdown.recv() => {
                     log::trace!("ConnectionHandler: received shutdown");
                 }
-                Some(framed_sphinx_packet) = framed_conn.next() => {
-                    match framed_sphinx_packet {
-                        Ok(framed_sphinx_packet) => {
+                Some(framed_nym_packet) = framed_conn.next() => {
+                    match framed_nym_packet {
+                        Ok(framed_nym_packet) => {
                             // TODO: benchmark spawning tokio task with full processing vs just processing it
                             // synchronously (without delaying inside of course,
                             // delay is moved to a global DelayQueue)
@@ -96,7 +96,7 @@ impl ConnectionHandler {
                             // in theory we could process multiple sphinx packet from the same connection in parallel,
                             // but we already handle multiple concurrent connections so if anything, making
                             // that change would only slow things down
-                            self.handle_received_packet(framed_sphinx_packet);
+                            self.handle_received_packet(framed_nym_packet);
                         }
                         Err(err) => {
                             error!(
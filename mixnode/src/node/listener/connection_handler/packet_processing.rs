@@ -2,11 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::node::node_statistics;
+use mixnode_common::dropped_packets::DropReason;
 use mixnode_common::packet_processor::error::MixProcessingError;
 pub use mixnode_common::packet_processor::processor::MixProcessingResult;
 use mixnode_common::packet_processor::processor::SphinxPacketProcessor;
-use nym_crypto::asymmetric::encryption;
-use nym_sphinx::framing::packet::FramedSphinxPacket;
+use mixnode_common::packet_processor::replay_protection::ReplayProtectionFilter;
+use mixnode_common::packet_processor::sphinx_keys::SphinxKeys;
+use nym_sphinx::framing::packet::FramedNymPacket;
+use std::time::Instant;
 
 // PacketProcessor contains all data required to correctly unwrap and forward sphinx packets
 #[derive(Clone)]
@@ -16,24 +19,48 @@ pub struct PacketProcessor {
 
     /// Responsible for updating metrics data
     node_stats_update_sender: node_statistics::UpdateSender,
+
+    /// Records how long it took to unwrap received packets
+    processing_latency: node_statistics::ProcessingLatency,
 }
 
 impl PacketProcessor {
     pub(crate) fn new(
-        encryption_key: &encryption::PrivateKey,
+        sphinx_keys: SphinxKeys,
         node_stats_update_sender: node_statistics::UpdateSender,
+        processing_latency: node_statistics::ProcessingLatency,
+        replay_protection: Option<ReplayProtectionFilter>,
     ) -> Self {
+        let mut inner_processor = SphinxPacketProcessor::new(sphinx_keys);
+        if let Some(replay_protection) = replay_protection {
+            inner_processor = inner_processor.with_replay_protection(replay_protection);
+        }
+
         PacketProcessor {
-            inner_processor: SphinxPacketProcessor::new(encryption_key.into()),
+            inner_processor,
             node_stats_update_sender,
+            processing_latency,
         }
     }
 
     pub(crate) fn process_received(
         &self,
-        received: FramedSphinxPacket,
+        received: FramedNymPacket,
     ) -> Result<MixProcessingResult, MixProcessingError> {
         self.node_stats_update_sender.report_received();
-        self.inner_processor.process_received(received)
+
+        let packet_mode = received.packet_mode();
+        let processing_start = Instant::now();
+        let processed = self.inner_processor.process_received(received);
+        self.processing_latency
+            .observe(packet_mode, processing_start.elapsed());
+
+        processed
+    }
+
+    /// Records the received packet that failed to get processed as dropped.
+    pub(crate) fn report_rejected(&self, err: &MixProcessingError) {
+        self.node_stats_update_sender
+            .report_rejected(DropReason::from(err))
     }
 }
@@ -6,7 +6,9 @@ use crate::config::persistence::pathfinder::MixNodePathfinder;
 use crate::config::Config;
 use crate::node::http::{
     description::description,
+    dropped_packets::dropped_packets,
     hardware::hardware,
+    metrics::{metrics, MetricsState},
     not_found,
     stats::stats,
     verloc::{verloc as verlocRoute, VerlocState},
@@ -14,13 +16,20 @@ use crate::node::http::{
 use crate::node::listener::connection_handler::packet_processing::PacketProcessor;
 use crate::node::listener::connection_handler::ConnectionHandler;
 use crate::node::listener::Listener;
-use crate::node::node_description::NodeDescription;
-use crate::node::node_statistics::SharedNodeStats;
-use crate::node::packet_delayforwarder::{DelayForwarder, PacketDelayForwardSender};
+use crate::node::node_statistics::{DroppedPacketsLog, ProcessingLatency, SharedNodeStats};
 use crate::OutputFormat;
 use colored::Colorize;
 use config::NymConfig;
+use futures::channel::mpsc;
 use log::{error, info, warn};
+use mixnet_client::ConnectionStats;
+use mixnode_common::node_description::NodeDescription;
+use mixnode_common::packet_delayforwarder::{
+    DelayAccounting, DelayForwarder, DelayQueueLength, PacketDelayForwardSender,
+};
+use mixnode_common::packet_processor::replay_protection::ReplayProtectionFilter;
+use mixnode_common::packet_processor::sphinx_keys::SphinxKeys;
+use mixnode_common::sphinx_key_rotation::{self, NodeKind, SphinxKeyRotator};
 use mixnode_common::verloc::{self, AtomicVerlocResult, VerlocMeasurer};
 use nym_bin_common::version_checker::parse_version;
 use nym_crypto::asymmetric::{encryption, identity};
@@ -33,9 +42,7 @@ use std::sync::Arc;
 
 mod http;
 mod listener;
-pub(crate) mod node_description;
 mod node_statistics;
-mod packet_delayforwarder;
 
 // the MixNode will live for whole duration of this program
 pub struct MixNode {
@@ -130,6 +137,8 @@ impl MixNode {
         &self,
         atomic_verloc_result: AtomicVerlocResult,
         node_stats_pointer: SharedNodeStats,
+        dropped_packets_log: DroppedPacketsLog,
+        metrics_state: MetricsState,
     ) {
         info!("Starting HTTP API on http://localhost:8000");
 
@@ -145,42 +154,118 @@ impl MixNode {
         tokio::spawn(async move {
             rocket::build()
                 .configure(config)
-                .mount("/", routes![verlocRoute, description, stats, hardware])
+                .mount(
+                    "/",
+                    routes![
+                        verlocRoute,
+                        description,
+                        stats,
+                        dropped_packets,
+                        hardware,
+                        metrics
+                    ],
+                )
                 .register("/", catchers![not_found])
                 .manage(verloc_state)
                 .manage(descriptor)
                 .manage(node_stats_pointer)
+                .manage(dropped_packets_log)
+                .manage(metrics_state)
                 .launch()
                 .await
         });
     }
 
+    fn start_replay_protection(&self, shutdown: TaskClient) -> Option<ReplayProtectionFilter> {
+        if !self.config.get_replay_protection_enabled() {
+            warn!(
+                "Replay protection is disabled - previously processed packets will not be rejected"
+            );
+            return None;
+        }
+        info!("Starting replay protection...");
+
+        let filter = ReplayProtectionFilter::new(self.config.get_replay_protection_filter_config());
+        tokio::spawn(filter.clone().run_persistence(shutdown));
+        Some(filter)
+    }
+
+    fn start_sphinx_key_rotation(
+        &self,
+        sphinx_keys: SphinxKeys,
+        replay_protection: Option<ReplayProtectionFilter>,
+        shutdown: TaskClient,
+    ) {
+        if !self.config.get_sphinx_key_rotation_enabled() {
+            warn!("Sphinx key rotation is disabled - the current sphinx key is going to be used indefinitely");
+            return;
+        }
+        info!("Starting sphinx key rotator...");
+
+        let config = sphinx_key_rotation::Config::build()
+            .node_kind(NodeKind::Mixnode)
+            .identity_key(self.identity_keypair.public_key().to_base58_string())
+            .check_interval(self.config.get_sphinx_key_rotation_check_interval())
+            .overlap(self.config.get_sphinx_key_overlap())
+            .current_key_files(
+                self.config.get_private_sphinx_key_file(),
+                self.config.get_public_sphinx_key_file(),
+            )
+            .upcoming_key_files(
+                self.config.get_upcoming_private_sphinx_key_file(),
+                self.config.get_upcoming_public_sphinx_key_file(),
+            )
+            .nym_api_urls(self.config.get_nym_api_endpoints())
+            .build();
+
+        let mut sphinx_key_rotator = SphinxKeyRotator::new(config, sphinx_keys, shutdown);
+        if let Some(replay_protection) = replay_protection {
+            sphinx_key_rotator = sphinx_key_rotator.with_replay_protection(replay_protection)
+        }
+        tokio::spawn(async move { sphinx_key_rotator.run().await });
+    }
+
     fn start_node_stats_controller(
         &self,
+        replay_protection: Option<&ReplayProtectionFilter>,
         shutdown: TaskClient,
-    ) -> (SharedNodeStats, node_statistics::UpdateSender) {
+    ) -> (
+        SharedNodeStats,
+        DroppedPacketsLog,
+        node_statistics::UpdateSender,
+    ) {
         info!("Starting node stats controller...");
         let controller = node_statistics::Controller::new(
             self.config.get_node_stats_logging_delay(),
             self.config.get_node_stats_updating_delay(),
+            replay_protection.map(ReplayProtectionFilter::metrics),
+            self.config.get_recent_dropped_packets_capacity(),
             shutdown,
         );
         let node_stats_pointer = controller.get_node_stats_data_pointer();
+        let dropped_packets_log = controller.get_dropped_packets_log();
         let update_sender = controller.start();
 
-        (node_stats_pointer, update_sender)
+        (node_stats_pointer, dropped_packets_log, update_sender)
     }
 
     fn start_socket_listener(
         &self,
         node_stats_update_sender: node_statistics::UpdateSender,
+        processing_latency: ProcessingLatency,
         delay_forwarding_channel: PacketDelayForwardSender,
+        sphinx_keys: SphinxKeys,
+        replay_protection: Option<ReplayProtectionFilter>,
         shutdown: TaskClient,
     ) {
         info!("Starting socket listener...");
 
-        let packet_processor =
-            PacketProcessor::new(self.sphinx_keypair.private_key(), node_stats_update_sender);
+        let packet_processor = PacketProcessor::new(
+            sphinx_keys,
+            node_stats_update_sender,
+            processing_latency,
+            replay_protection,
+        );
 
         let connection_handler = ConnectionHandler::new(packet_processor, delay_forwarding_channel);
 
@@ -196,7 +281,12 @@ impl MixNode {
         &mut self,
         node_stats_update_sender: node_statistics::UpdateSender,
         shutdown: TaskClient,
-    ) -> PacketDelayForwardSender {
+    ) -> (
+        PacketDelayForwardSender,
+        DelayQueueLength,
+        DelayAccounting,
+        ConnectionStats,
+    ) {
         info!("Starting packet delay-forwarder...");
 
         let client_config = mixnet_client::Config::new(
@@ -207,16 +297,26 @@ impl MixNode {
             self.config.get_use_legacy_sphinx_framing(),
         );
 
-        let mut packet_forwarder = DelayForwarder::new(
-            mixnet_client::Client::new(client_config),
-            node_stats_update_sender,
-            shutdown,
-        );
+        let (dropped_packets_sender, dropped_packets_receiver) = mpsc::unbounded();
+        let mixnet_client = mixnet_client::Client::new(client_config)
+            .with_dropped_packets_sender(dropped_packets_sender);
+        let connection_stats = mixnet_client.connection_stats();
+
+        let mut packet_forwarder =
+            DelayForwarder::new(mixnet_client, node_stats_update_sender, shutdown)
+                .with_dropped_packets_receiver(dropped_packets_receiver);
 
         let packet_sender = packet_forwarder.sender();
+        let delay_queue_length = packet_forwarder.delay_queue_length();
+        let delay_accounting = packet_forwarder.delay_accounting();
 
         tokio::spawn(async move { packet_forwarder.run().await });
-        packet_sender
+        (
+            packet_sender,
+            delay_queue_length,
+            delay_accounting,
+            connection_stats,
+        )
     }
 
     fn start_verloc_measurements(&self, shutdown: TaskClient) -> AtomicVerlocResult {
@@ -314,21 +414,54 @@ impl MixNode {
 
         let shutdown = TaskManager::default();
 
-        let (node_stats_pointer, node_stats_update_sender) =
-            self.start_node_stats_controller(shutdown.subscribe());
-        let delay_forwarding_channel = self
-            .start_packet_delay_forwarder(node_stats_update_sender.clone(), shutdown.subscribe());
+        let replay_protection = self.start_replay_protection(shutdown.subscribe());
+        let sphinx_keys = SphinxKeys::new(self.sphinx_keypair.private_key().into());
+        self.start_sphinx_key_rotation(
+            sphinx_keys.clone(),
+            replay_protection.clone(),
+            shutdown.subscribe(),
+        );
+        let (node_stats_pointer, dropped_packets_log, node_stats_update_sender) =
+            self.start_node_stats_controller(replay_protection.as_ref(), shutdown.subscribe());
+        let (delay_forwarding_channel, delay_queue_length, delay_accounting, connection_stats) =
+            self.start_packet_delay_forwarder(
+                node_stats_update_sender.clone(),
+                shutdown.subscribe(),
+            );
+        let processing_latency = ProcessingLatency::default();
+        let replay_protection_metrics = replay_protection
+            .as_ref()
+            .map(ReplayProtectionFilter::metrics);
         self.start_socket_listener(
             node_stats_update_sender,
+            processing_latency.clone(),
             delay_forwarding_channel,
+            sphinx_keys,
+            replay_protection,
             shutdown.subscribe(),
         );
         let atomic_verloc_results = self.start_verloc_measurements(shutdown.subscribe());
 
+        let metrics_state = MetricsState {
+            node_stats: node_stats_pointer.clone(),
+            verloc: atomic_verloc_results.clone_data_pointer(),
+            delay_queue_length,
+            delay_accounting,
+            connection_stats,
+            dropped_packets_log: dropped_packets_log.clone(),
+            processing_latency,
+            replay_protection: replay_protection_metrics,
+        };
+
         // Rocket handles shutdown on it's own, but its shutdown handling should be incorporated
         // with that of the rest of the tasks.
         // Currently it's runtime is forcefully terminated once the mixnode exits.
-        self.start_http_api(atomic_verloc_results, node_stats_pointer);
+        self.start_http_api(
+            atomic_verloc_results,
+            node_stats_pointer,
+            dropped_packets_log,
+            metrics_state,
+        );
 
         info!("Finished nym mixnode startup procedure - it should now be able to receive mix traffic!");
         self.wait_for_interrupt(shutdown).await
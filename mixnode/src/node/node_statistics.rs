@@ -1,13 +1,19 @@
 use futures::channel::mpsc;
 use futures::lock::Mutex;
 use futures::StreamExt;
+use mixnode_common::dropped_packets::DropReason;
+use mixnode_common::metrics::{HistogramSnapshot, LatencyHistogram};
+use mixnode_common::packet_delayforwarder::ForwardingReporter;
+use mixnode_common::packet_processor::replay_protection::ReplayProtectionMetrics;
+use nym_sphinx::params::PacketMode;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::ops::DerefMut;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::time::Instant;
 
 use super::TaskClient;
 
@@ -34,6 +40,8 @@ impl SharedNodeStats {
                 packets_received_since_last_update: 0,
                 packets_sent_since_last_update: HashMap::new(),
                 packets_explicitly_dropped_since_last_update: HashMap::new(),
+                packets_rejected_as_replays_since_startup: 0,
+                packets_rejected_as_replays_since_last_update: 0,
             })),
         }
     }
@@ -43,6 +51,7 @@ impl SharedNodeStats {
         new_received: u64,
         new_sent: PacketsMap,
         new_dropped: PacketsMap,
+        total_rejected_replays: u64,
     ) {
         let mut guard = self.inner.write().await;
         let snapshot_time = SystemTime::now();
@@ -60,7 +69,7 @@ impl SharedNodeStats {
 
         for (mix, count) in &new_dropped {
             *guard
-                .packets_explicitly_dropped_since_last_update
+                .packets_explicitly_dropped_since_startup
                 .entry(mix.clone())
                 .or_insert(0) += *count;
         }
@@ -68,6 +77,9 @@ impl SharedNodeStats {
         guard.packets_received_since_last_update = new_received;
         guard.packets_sent_since_last_update = new_sent;
         guard.packets_explicitly_dropped_since_last_update = new_dropped;
+        guard.packets_rejected_as_replays_since_last_update =
+            total_rejected_replays.saturating_sub(guard.packets_rejected_as_replays_since_startup);
+        guard.packets_rejected_as_replays_since_startup = total_rejected_replays;
     }
 
     pub(crate) async fn clone_data(&self) -> NodeStats {
@@ -102,9 +114,26 @@ pub(crate) struct NodeStats {
 
     // we know for sure we dropped packets to those destinations
     packets_explicitly_dropped_since_last_update: PacketsMap,
+
+    // packets we have already processed before
+    packets_rejected_as_replays_since_startup: u64,
+
+    packets_rejected_as_replays_since_last_update: u64,
 }
 
 impl NodeStats {
+    pub(crate) fn packets_received_since_startup(&self) -> u64 {
+        self.packets_received_since_startup
+    }
+
+    pub(crate) fn packets_sent_since_startup(&self) -> &PacketsMap {
+        &self.packets_sent_since_startup
+    }
+
+    pub(crate) fn packets_explicitly_dropped_since_startup(&self) -> &PacketsMap {
+        &self.packets_explicitly_dropped_since_startup
+    }
+
     pub(crate) fn simplify(&self) -> NodeStatsSimple {
         NodeStatsSimple {
             update_time: self.update_time,
@@ -121,6 +150,10 @@ impl NodeStats {
                 .packets_explicitly_dropped_since_last_update
                 .values()
                 .sum(),
+            packets_rejected_as_replays_since_startup: self
+                .packets_rejected_as_replays_since_startup,
+            packets_rejected_as_replays_since_last_update: self
+                .packets_rejected_as_replays_since_last_update,
         }
     }
 }
@@ -148,12 +181,162 @@ pub(crate) struct NodeStatsSimple {
 
     // we know for sure we dropped those packets
     packets_explicitly_dropped_since_last_update: u64,
+
+    // packets we have already processed before
+    packets_rejected_as_replays_since_startup: u64,
+
+    packets_rejected_as_replays_since_last_update: u64,
+}
+
+/// Histograms of the time it took to unwrap the received packets of each type.
+#[derive(Clone, Default)]
+pub(crate) struct ProcessingLatency {
+    sphinx: LatencyHistogram,
+    outfox: LatencyHistogram,
+}
+
+impl ProcessingLatency {
+    pub(crate) fn observe(&self, packet_mode: PacketMode, latency: Duration) {
+        if packet_mode.is_outfox() {
+            self.outfox.observe(latency)
+        } else {
+            self.sphinx.observe(latency)
+        }
+    }
+
+    pub(crate) fn sphinx_snapshot(&self) -> HistogramSnapshot {
+        self.sphinx.snapshot()
+    }
+
+    pub(crate) fn outfox_snapshot(&self) -> HistogramSnapshot {
+        self.outfox.snapshot()
+    }
+}
+
+// the most recent events are only logged once per this interval for each of the drop reasons,
+// the totals are always kept up to date
+const DROP_EVENT_LOGGING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Single occurrence of the node dropping packets.
+#[derive(Clone, Debug)]
+struct DropEvent {
+    recorded: Instant,
+    reason: DropReason,
+    packets: u64,
+}
+
+/// Bounded, rate limited log of the most recent packet drops alongside the total number of packets
+/// dropped for each reason since startup.
+#[derive(Clone)]
+pub(crate) struct DroppedPacketsLog {
+    inner: Arc<DroppedPacketsLogInner>,
+}
+
+struct DroppedPacketsLogInner {
+    started: Instant,
+    capacity: usize,
+
+    // indexed by the discriminant of the `DropReason`
+    totals: [AtomicU64; DropReason::ALL.len()],
+
+    // milliseconds since `started` at which an event for given reason was last logged
+    last_logged: [AtomicU64; DropReason::ALL.len()],
+    recent: std::sync::Mutex<VecDeque<DropEvent>>,
+}
+
+impl DroppedPacketsLog {
+    pub(crate) fn new(capacity: usize) -> Self {
+        DroppedPacketsLog {
+            inner: Arc::new(DroppedPacketsLogInner {
+                started: Instant::now(),
+                capacity,
+                totals: Default::default(),
+                last_logged: Default::default(),
+                recent: std::sync::Mutex::new(VecDeque::with_capacity(capacity)),
+            }),
+        }
+    }
+
+    fn record(&self, reason: DropReason, packets: u64) {
+        let index = reason as usize;
+        self.inner.totals[index].fetch_add(packets, Ordering::Relaxed);
+
+        if self.inner.capacity == 0 || !self.should_log(index) {
+            return;
+        }
+
+        let mut recent = self.inner.recent.lock().unwrap();
+        if recent.len() == self.inner.capacity {
+            recent.pop_front();
+        }
+        recent.push_back(DropEvent {
+            recorded: Instant::now(),
+            reason,
+            packets,
+        });
+    }
+
+    // make sure only a single event per reason gets logged within the logging interval,
+    // so that a flood of bad packets wouldn't contend on the lock
+    fn should_log(&self, index: usize) -> bool {
+        // offset by the interval so that the very first event would always get logged
+        let now = (self.inner.started.elapsed() + DROP_EVENT_LOGGING_INTERVAL).as_millis() as u64;
+        let last_logged = &self.inner.last_logged[index];
+        let previous = last_logged.load(Ordering::Relaxed);
+
+        now.saturating_sub(previous) >= DROP_EVENT_LOGGING_INTERVAL.as_millis() as u64
+            && last_logged
+                .compare_exchange(previous, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+    }
+
+    /// Total number of packets dropped for the provided reason since startup.
+    pub(crate) fn total(&self, reason: DropReason) -> u64 {
+        self.inner.totals[reason as usize].load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn snapshot(&self) -> DroppedPacketsSnapshot {
+        let totals = DropReason::ALL
+            .into_iter()
+            .map(|reason| (reason, self.total(reason)))
+            .collect();
+
+        let recent = self.inner.recent.lock().unwrap();
+        DroppedPacketsSnapshot {
+            totals,
+            // return the most recent events first
+            recent: recent
+                .iter()
+                .rev()
+                .map(|event| RecentDrop {
+                    reason: event.reason,
+                    packets: event.packets,
+                    minutes_ago: event.recorded.elapsed().as_secs() / 60,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Publicly exposed recent packet drop. It deliberately includes neither the address of the remote
+/// nor the exact time of the drop as not to aid any traffic analysis.
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct RecentDrop {
+    reason: DropReason,
+    packets: u64,
+    minutes_ago: u64,
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct DroppedPacketsSnapshot {
+    totals: HashMap<DropReason, u64>,
+    recent: Vec<RecentDrop>,
 }
 
 pub(crate) enum PacketEvent {
     Sent(String),
     Received,
-    Dropped(String),
+    Dropped(String, u64),
 }
 
 #[derive(Debug, Clone)]
@@ -189,10 +372,10 @@ impl CurrentPacketData {
         *receiver_count += 1;
     }
 
-    async fn increment_dropped(&self, destination: String) {
+    async fn increment_dropped(&self, destination: String, count: u64) {
         let mut unlocked = self.inner.dropped.lock().await;
         let dropped_count = unlocked.entry(destination).or_insert(0);
-        *dropped_count += 1;
+        *dropped_count += count;
     }
 
     async fn acquire_and_reset(&self) -> (u64, PacketsMap, PacketsMap) {
@@ -237,8 +420,8 @@ impl UpdateHandler {
                         PacketEvent::Sent(destination) => {
                             self.current_data.increment_sent(destination).await
                         }
-                        PacketEvent::Dropped(destination) => {
-                            self.current_data.increment_dropped(destination).await
+                        PacketEvent::Dropped(destination, count) => {
+                            self.current_data.increment_dropped(destination, count).await
                         }
                     }
                 }
@@ -255,34 +438,50 @@ impl UpdateHandler {
 
 // Channel to report statistics
 #[derive(Clone)]
-pub struct UpdateSender(PacketDataSender);
+pub struct UpdateSender {
+    sender: PacketDataSender,
+    dropped_packets_log: DroppedPacketsLog,
+}
 
 impl UpdateSender {
-    pub(crate) fn new(update_sender: PacketDataSender) -> Self {
-        UpdateSender(update_sender)
+    pub(crate) fn new(sender: PacketDataSender, dropped_packets_log: DroppedPacketsLog) -> Self {
+        UpdateSender {
+            sender,
+            dropped_packets_log,
+        }
     }
 
-    pub(crate) fn report_sent(&self, destination: String) {
+    // TODO: in the future this could be slightly optimised to get rid of the channel
+    // in favour of incrementing value directly
+    pub(crate) fn report_received(&self) {
         // in unbounded_send() failed it means that the receiver channel was disconnected
         // and hence something weird must have happened without a way of recovering
-        self.0
-            .unbounded_send(PacketEvent::Sent(destination))
-            .unwrap()
+        self.sender.unbounded_send(PacketEvent::Received).unwrap()
     }
 
-    // TODO: in the future this could be slightly optimised to get rid of the channel
-    // in favour of incrementing value directly
-    pub(crate) fn report_received(&self) {
+    /// Reports a received packet that got rejected, for example because it was malformed or replayed.
+    pub(crate) fn report_rejected(&self, reason: DropReason) {
+        // this doesn't go through the channel as otherwise anyone could fill it up by sending us garbage
+        self.dropped_packets_log.record(reason, 1)
+    }
+}
+
+impl ForwardingReporter for UpdateSender {
+    fn report_sent(&self, destination: String) {
         // in unbounded_send() failed it means that the receiver channel was disconnected
         // and hence something weird must have happened without a way of recovering
-        self.0.unbounded_send(PacketEvent::Received).unwrap()
+        self.sender
+            .unbounded_send(PacketEvent::Sent(destination))
+            .unwrap()
     }
 
-    pub(crate) fn report_dropped(&self, destination: String) {
+    fn report_dropped(&self, destination: String, reason: DropReason, count: usize) {
+        self.dropped_packets_log.record(reason, count as u64);
+
         // in unbounded_send() failed it means that the receiver channel was disconnected
         // and hence something weird must have happened without a way of recovering
-        self.0
-            .unbounded_send(PacketEvent::Dropped(destination))
+        self.sender
+            .unbounded_send(PacketEvent::Dropped(destination, count as u64))
             .unwrap()
     }
 }
@@ -293,6 +492,7 @@ struct StatsUpdater {
     updating_delay: Duration,
     current_packet_data: CurrentPacketData,
     current_stats: SharedNodeStats,
+    replay_protection_metrics: Option<ReplayProtectionMetrics>,
     shutdown: TaskClient,
 }
 
@@ -301,12 +501,14 @@ impl StatsUpdater {
         updating_delay: Duration,
         current_packet_data: CurrentPacketData,
         current_stats: SharedNodeStats,
+        replay_protection_metrics: Option<ReplayProtectionMetrics>,
         shutdown: TaskClient,
     ) -> Self {
         StatsUpdater {
             updating_delay,
             current_packet_data,
             current_stats,
+            replay_protection_metrics,
             shutdown,
         }
     }
@@ -314,7 +516,14 @@ impl StatsUpdater {
     async fn update_stats(&self) {
         // grab new data since last update
         let (received, sent, dropped) = self.current_packet_data.acquire_and_reset().await;
-        self.current_stats.update(received, sent, dropped).await;
+        let rejected_replays = self
+            .replay_protection_metrics
+            .as_ref()
+            .map(ReplayProtectionMetrics::rejected_replays)
+            .unwrap_or_default();
+        self.current_stats
+            .update(received, sent, dropped, rejected_replays)
+            .await;
     }
 
     async fn run(&mut self) {
@@ -376,6 +585,14 @@ impl PacketStatsConsoleLogger {
                     difference_secs,
                 );
             }
+            if stats.packets_rejected_as_replays_since_startup > 0 {
+                info!(
+                    "Since startup rejected {} replayed packets! ({} in last {} seconds)",
+                    stats.packets_rejected_as_replays_since_startup,
+                    stats.packets_rejected_as_replays_since_last_update,
+                    difference_secs,
+                );
+            }
 
             debug!(
                 "Since startup received {} packets ({} in last {} seconds)",
@@ -445,17 +662,23 @@ pub struct Controller {
 
     /// Pointer to the current node stats
     node_stats: SharedNodeStats,
+
+    /// Pointer to the log of recently dropped packets
+    dropped_packets_log: DroppedPacketsLog,
 }
 
 impl Controller {
     pub(crate) fn new(
         logging_delay: Duration,
         stats_updating_delay: Duration,
+        replay_protection_metrics: Option<ReplayProtectionMetrics>,
+        recent_dropped_packets_capacity: usize,
         shutdown: TaskClient,
     ) -> Self {
         let (sender, receiver) = mpsc::unbounded();
         let shared_packet_data = CurrentPacketData::new();
         let shared_node_stats = SharedNodeStats::new();
+        let dropped_packets_log = DroppedPacketsLog::new(recent_dropped_packets_capacity);
 
         Controller {
             update_handler: UpdateHandler::new(
@@ -463,7 +686,7 @@ impl Controller {
                 receiver,
                 shutdown.clone(),
             ),
-            update_sender: UpdateSender::new(sender),
+            update_sender: UpdateSender::new(sender, dropped_packets_log.clone()),
             console_logger: PacketStatsConsoleLogger::new(
                 logging_delay,
                 shared_node_stats.clone(),
@@ -473,12 +696,18 @@ impl Controller {
                 stats_updating_delay,
                 shared_packet_data,
                 shared_node_stats.clone(),
+                replay_protection_metrics,
                 shutdown,
             ),
             node_stats: shared_node_stats,
+            dropped_packets_log,
         }
     }
 
+    pub(crate) fn get_dropped_packets_log(&self) -> DroppedPacketsLog {
+        self.dropped_packets_log.clone()
+    }
+
     pub(crate) fn get_node_stats_data_pointer(&self) -> SharedNodeStats {
         SharedNodeStats {
             inner: Arc::clone(&self.node_stats.inner),
@@ -510,8 +739,13 @@ mod tests {
         let logging_delay = Duration::from_millis(20);
         let stats_updating_delay = Duration::from_millis(10);
         let shutdown = TaskManager::default();
-        let node_stats_controller =
-            Controller::new(logging_delay, stats_updating_delay, shutdown.subscribe());
+        let node_stats_controller = Controller::new(
+            logging_delay,
+            stats_updating_delay,
+            None,
+            10,
+            shutdown.subscribe(),
+        );
 
         let node_stats_pointer = node_stats_controller.get_node_stats_data_pointer();
         let update_sender = node_stats_controller.start();
@@ -537,4 +771,71 @@ mod tests {
         assert_eq!(&stats.packets_received_since_startup, &0u64);
         assert!(&stats.packets_explicitly_dropped_since_startup.is_empty());
     }
+
+    #[tokio::test]
+    async fn dropped_packets_log_is_bounded() {
+        let shutdown = TaskManager::default();
+        let node_stats_controller = Controller::new(
+            Duration::from_millis(20),
+            Duration::from_millis(10),
+            None,
+            2,
+            shutdown.subscribe(),
+        );
+
+        let node_stats_pointer = node_stats_controller.get_node_stats_data_pointer();
+        let dropped_packets_log = node_stats_controller.get_dropped_packets_log();
+        let update_sender = node_stats_controller.start();
+        tokio::time::pause();
+
+        update_sender.report_dropped("foo".to_string(), DropReason::QueueFull, 1);
+        update_sender.report_dropped("bar".to_string(), DropReason::ConnectionRefused, 5);
+        update_sender.report_rejected(DropReason::Replay);
+        tokio::task::yield_now().await;
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        tokio::task::yield_now().await;
+
+        let snapshot = dropped_packets_log.snapshot();
+        assert_eq!(snapshot.recent.len(), 2);
+        assert_eq!(snapshot.recent[0].reason, DropReason::Replay);
+        assert_eq!(snapshot.recent[1].reason, DropReason::ConnectionRefused);
+        assert_eq!(dropped_packets_log.total(DropReason::QueueFull), 1);
+        assert_eq!(dropped_packets_log.total(DropReason::ConnectionRefused), 5);
+        assert_eq!(dropped_packets_log.total(DropReason::Replay), 1);
+
+        // rejected packets are not attributed to any destination
+        let stats = node_stats_pointer.read().await;
+        assert_eq!(
+            &stats.packets_explicitly_dropped_since_startup.get("bar"),
+            &Some(&5u64)
+        );
+        assert_eq!(&stats.packets_explicitly_dropped_since_startup.len(), &2);
+    }
+
+    #[tokio::test]
+    async fn drop_events_are_rate_limited() {
+        tokio::time::pause();
+        let dropped_packets_log = DroppedPacketsLog::new(10);
+
+        for _ in 0..100 {
+            dropped_packets_log.record(DropReason::Malformed, 1);
+        }
+        dropped_packets_log.record(DropReason::Replay, 1);
+
+        let snapshot = dropped_packets_log.snapshot();
+        assert_eq!(snapshot.recent.len(), 2);
+        assert_eq!(snapshot.totals[&DropReason::Malformed], 100);
+        assert_eq!(snapshot.totals[&DropReason::Replay], 1);
+        assert_eq!(snapshot.totals[&DropReason::QueueFull], 0);
+
+        tokio::time::advance(DROP_EVENT_LOGGING_INTERVAL).await;
+        dropped_packets_log.record(DropReason::Malformed, 1);
+
+        let snapshot = dropped_packets_log.snapshot();
+        assert_eq!(snapshot.recent.len(), 3);
+        assert_eq!(snapshot.recent[0].reason, DropReason::Malformed);
+        assert_eq!(snapshot.recent[0].minutes_ago, 0);
+        assert_eq!(dropped_packets_log.total(DropReason::Malformed), 101);
+    }
 }
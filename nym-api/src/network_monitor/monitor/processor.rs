@@ -76,7 +76,7 @@ impl ReceivedProcessorInner {
                 &mut message,
             )?;
         let fragment = self.message_receiver.recover_fragment(plaintext)?;
-        let (recovered, _) = self
+        let (recovered, _, _) = self
             .message_receiver
             .insert_new_fragment(fragment)?
             .ok_or(ProcessingError::NonTestPacketReceived)?; // if it's a test packet it MUST BE reconstructed with single fragment
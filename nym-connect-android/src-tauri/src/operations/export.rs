@@ -18,7 +18,7 @@ pub async fn get_identity_key(
     };
 
     let pathfinder = ClientKeyPathfinder::new_from_config(config.get_base());
-    let key_manager = KeyManager::load_keys(&pathfinder)?;
+    let key_manager = KeyManager::load_keys(&pathfinder, None)?;
     let identity_keypair = key_manager.identity_keypair();
 
     Ok(identity_keypair)
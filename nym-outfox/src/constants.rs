@@ -0,0 +1,36 @@
+//! Constants shared by the `outfox` packet format and its users.
+
+/// Length of the serialized curve25519 group element attached to every layer.
+pub const GROUPELEMENTBYTES: usize = 32;
+
+/// Length of the AEAD (chacha20poly1305) tag attached to every layer.
+pub const TAGBYTES: usize = 16;
+
+/// Length of the address of the next hop (or the final destination) included in the routing
+/// information of every layer.
+pub const NODE_ADDRESS_LENGTH: usize = 32;
+
+/// Length of the (big endian, in nanoseconds) delay included in the routing information of every layer.
+pub const DELAY_LENGTH: usize = 8;
+
+/// Length of the routing information included in every layer of an [`OutfoxPacket`](crate::packet::OutfoxPacket).
+pub const ROUTING_INFORMATION_LENGTH: usize = NODE_ADDRESS_LENGTH + DELAY_LENGTH;
+
+/// Length of the replay tag of an unwrapped layer, see [`OutfoxPacket::decode_next_layer_with_replay_tag`](crate::packet::OutfoxPacket::decode_next_layer_with_replay_tag).
+pub const REPLAY_TAG_LENGTH: usize = 32;
+
+/// Number of mixing stages used by default, i.e. 3 mix layers and the final gateway.
+pub const DEFAULT_ROUTING_STAGES: usize = 4;
+
+/// Length of the serialized parameters prepended to every [`OutfoxPacket`](crate::packet::OutfoxPacket):
+/// the payload length (4 bytes, big endian). Note that the number of stages is not included
+/// so that it would not reveal the position of the packet on its route.
+pub const MIX_PARAMS_LEN: usize = 4;
+
+/// Number of bytes added to the payload of a packet with the specified number of routing stages.
+pub const fn outfox_packet_overhead(stages: usize) -> usize {
+    MIX_PARAMS_LEN + stages * (GROUPELEMENTBYTES + TAGBYTES + ROUTING_INFORMATION_LENGTH)
+}
+
+/// Number of bytes added to the payload of a packet using the default number of routing stages.
+pub const OUTFOX_PACKET_OVERHEAD: usize = outfox_packet_overhead(DEFAULT_ROUTING_STAGES);
@@ -17,4 +17,10 @@ pub enum OutFoxError {
     InvalidKeyLength,
     #[error("Message length must be greater then {MIN_MESSAGE_LEN} bytes")]
     InvalidMessageLength,
+    #[error("Packet must consist of between 1 and {max} routing stages, got: {got}")]
+    InvalidNumberOfStages { max: usize, got: usize },
+    #[error("There are no more layers left to unwrap")]
+    NoRemainingLayers,
+    #[error("The packet has not been fully unwrapped yet")]
+    NotFinalHop,
 }
@@ -65,16 +65,15 @@ use curve25519_dalek::scalar::Scalar;
 
 use std::convert::TryInto;
 
-const GROUPELEMENTBYTES: usize = 32;
-const TAGBYTES: usize = 16;
-
 use std::ops::Range;
 
+use crate::constants::{GROUPELEMENTBYTES, TAGBYTES};
 use crate::error::OutFoxError;
 use crate::lion::*;
 
 /// A structure that holds mix packet construction parameters. These incluse the length
 /// of the routing information at each hop, the number of hops, and the payload length.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MixCreationParameters {
     /// The routing length is inner first, so \[0\] is the innermost routing length, etc (in bytes)
     pub routing_information_length_by_stage: Vec<usize>,
@@ -106,6 +105,11 @@ impl MixCreationParameters {
         len
     }
 
+    /// The number of mixing stages (layers) this packet is made of.
+    pub fn num_stages(&self) -> usize {
+        self.routing_information_length_by_stage.len()
+    }
+
     /// Get the mix packet parameters for a single stage of mixing.
     pub fn get_stage_params(&self, layer_number: usize) -> (Range<usize>, MixStageParameters) {
         assert!(layer_number < self.routing_information_length_by_stage.len());
@@ -133,6 +137,7 @@ impl MixCreationParameters {
 }
 
 /// A structure representing the parameters of a single stage of mixing.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MixStageParameters {
     /// The routing information length for this stage of mixing
     pub routing_information_length_bytes: usize,
@@ -231,6 +236,18 @@ impl MixStageParameters {
         buffer: &mut [u8],
         mix_secret_key: &Scalar,
     ) -> Result<MontgomeryPoint, OutFoxError> {
+        let shared_key = derive_shared_key(buffer, mix_secret_key)?;
+        self.decode_mix_layer_with_shared_key(buffer, &shared_key)?;
+        Ok(shared_key)
+    }
+
+    /// Decodes the mix layer using an already derived shared key. If the header fails to get
+    /// authenticated, the buffer is left untouched.
+    pub fn decode_mix_layer_with_shared_key(
+        &self,
+        buffer: &mut [u8],
+        shared_key: &MontgomeryPoint,
+    ) -> Result<(), OutFoxError> {
         // Check the length of the incoming buffer is correct.
         if buffer.len() != self.incoming_packet_length() {
             return Err(OutFoxError::LenMismatch {
@@ -239,10 +256,6 @@ impl MixStageParameters {
             });
         }
 
-        // Derive the shared key for this packet
-        let user_public_key = MontgomeryPoint(buffer[self.pub_element_range()].try_into().unwrap());
-        let shared_key = mix_secret_key * user_public_key;
-
         // Compute the AEAD and check the Tag, if wrong return Err
         let header_aead_key = ChaCha20Poly1305::new_from_slice(&shared_key.0[..]).unwrap();
         let nonce = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
@@ -255,13 +268,31 @@ impl MixStageParameters {
                 &nonce.into(),
                 &[],
                 &mut buffer[self.header_range()],
-                tag.as_slice().try_into().unwrap(),
+                tag,
             )
             .map_err(|_| OutFoxError::ChaCha20Poly1305Error)?;
 
         // Do a round of LION on the payload
         lion_transform_decrypt(&mut buffer[self.payload_range()], &shared_key.0)?;
 
-        Ok(shared_key)
+        Ok(())
     }
 }
+
+/// Derives the master key shared between the mix and the creator of the layer starting at
+/// the beginning of the provided buffer.
+pub fn derive_shared_key(
+    buffer: &[u8],
+    mix_secret_key: &Scalar,
+) -> Result<MontgomeryPoint, OutFoxError> {
+    if buffer.len() < GROUPELEMENTBYTES {
+        return Err(OutFoxError::LenMismatch {
+            expected: GROUPELEMENTBYTES,
+            got: buffer.len(),
+        });
+    }
+
+    // the unwrap is fine as we have checked the length of the buffer
+    let user_public_key = MontgomeryPoint(buffer[..GROUPELEMENTBYTES].try_into().unwrap());
+    Ok(mix_secret_key * user_public_key)
+}
@@ -1,3 +1,5 @@
+pub mod constants;
 pub mod error;
 pub mod format;
 pub mod lion;
+pub mod packet;
@@ -0,0 +1,294 @@
+//! # The `outfox` packet
+//!
+//! [OutfoxPacket] ties together the per-stage [MixStageParameters](crate::format::MixStageParameters)
+//! into a complete packet that can be constructed by a client, put on the wire and unwrapped,
+//! one layer at a time, by every hop on its route.
+//!
+//! All layers use routing information of the same length ([ROUTING_INFORMATION_LENGTH]), consisting
+//! of the address of the next hop (or the final destination in the case of the last layer) followed
+//! by the delay the node should apply to the packet before forwarding it.
+//!
+//! On the wire the packet is represented as `[Params, Buffer, Padding]`, where `Params` (of length
+//! [MIX_PARAMS_LEN]) encodes the payload length. Every unwrapped layer is replaced by the equivalent
+//! amount of random `Padding` at the end of the packet so that its length remains constant throughout
+//! its entire route.
+//!
+//! The number of remaining stages is deliberately not included in `Params`, as it would reveal
+//! the position of the packet on its route to anyone observing it. Instead, a node recovers it by
+//! finding the (only) number of stages for which the header of its layer gets authenticated.
+
+use crate::constants::{
+    GROUPELEMENTBYTES, MIX_PARAMS_LEN, NODE_ADDRESS_LENGTH, REPLAY_TAG_LENGTH,
+    ROUTING_INFORMATION_LENGTH, TAGBYTES,
+};
+use crate::error::OutFoxError;
+use crate::format::{derive_shared_key, MixCreationParameters, MixStageParameters};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use std::convert::{TryFrom, TryInto};
+use std::ops::Range;
+use zeroize::Zeroize;
+
+/// Length of a single layer of the packet, excluding the payload.
+const LAYER_LENGTH: usize = GROUPELEMENTBYTES + TAGBYTES + ROUTING_INFORMATION_LENGTH;
+
+const REPLAY_TAG_CONTEXT: &str = "NYM_OUTFOX_REPLAY_TAG";
+
+/// Public information about a single hop on the route of an [OutfoxPacket].
+pub struct OutfoxHop {
+    /// The x25519 (montgomery) public key of the node.
+    pub public_key: [u8; 32],
+
+    /// The routing information the node is going to recover after unwrapping its layer,
+    /// i.e. the address of the next hop (or the destination) and the delay to apply.
+    pub routing_information: [u8; ROUTING_INFORMATION_LENGTH],
+}
+
+impl OutfoxHop {
+    pub fn new(public_key: [u8; 32], next_address: [u8; NODE_ADDRESS_LENGTH], delay: u64) -> Self {
+        let mut routing_information = [0u8; ROUTING_INFORMATION_LENGTH];
+        routing_information[..NODE_ADDRESS_LENGTH].copy_from_slice(&next_address);
+        routing_information[NODE_ADDRESS_LENGTH..].copy_from_slice(&delay.to_be_bytes());
+
+        OutfoxHop {
+            public_key,
+            routing_information,
+        }
+    }
+}
+
+/// Routing information recovered by a node after unwrapping its layer of an [OutfoxPacket].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutfoxRoutingInformation {
+    /// Address of the next hop, or, if this was the final layer, of the destination.
+    pub next_address: [u8; NODE_ADDRESS_LENGTH],
+
+    /// Delay (in nanoseconds) that should be applied to the packet before forwarding it.
+    pub delay: u64,
+}
+
+impl From<&[u8; ROUTING_INFORMATION_LENGTH]> for OutfoxRoutingInformation {
+    fn from(bytes: &[u8; ROUTING_INFORMATION_LENGTH]) -> Self {
+        // those unwraps can't fail as we're operating on fixed length array
+        OutfoxRoutingInformation {
+            next_address: bytes[..NODE_ADDRESS_LENGTH].try_into().unwrap(),
+            delay: u64::from_be_bytes(bytes[NODE_ADDRESS_LENGTH..].try_into().unwrap()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutfoxPacket {
+    payload_length: usize,
+
+    // the number of layers that still have to be unwrapped, if known. It is only known to the
+    // creator of the packet and to the node that has just unwrapped its layer.
+    remaining_stages: Option<usize>,
+    buffer: Vec<u8>,
+}
+
+fn random_scalar() -> Scalar {
+    let mut bytes = [0u8; 64];
+    // if we can't get any randomness out of the OS, we can't do anything sensible anyway
+    getrandom::getrandom(&mut bytes).expect("failed to obtain randomness from the OS");
+    let scalar = Scalar::from_bytes_mod_order_wide(&bytes);
+    bytes.zeroize();
+    scalar
+}
+
+impl OutfoxPacket {
+    /// Builds a new packet carrying the provided payload through the specified route.
+    /// The first element of the route represents the first hop.
+    pub fn build(payload: &[u8], route: &[OutfoxHop]) -> Result<Self, OutFoxError> {
+        if route.is_empty() || route.len() > u8::MAX as usize {
+            return Err(OutFoxError::InvalidNumberOfStages {
+                max: u8::MAX as usize,
+                got: route.len(),
+            });
+        }
+
+        let mut mix_params = MixCreationParameters::new(payload.len());
+        for _ in route {
+            mix_params.add_outer_layer(ROUTING_INFORMATION_LENGTH);
+        }
+
+        let total_len = mix_params.total_packet_length();
+        let mut buffer = vec![0u8; total_len];
+        buffer[total_len - payload.len()..].copy_from_slice(payload);
+
+        // layers are constructed inner first, i.e. starting with the last hop on the route
+        for (layer_number, hop) in route.iter().rev().enumerate() {
+            let (range, stage_params) = mix_params.get_stage_params(layer_number);
+            let user_secret_key = random_scalar();
+            stage_params.encode_mix_layer(
+                &mut buffer[range],
+                &user_secret_key,
+                &MontgomeryPoint(hop.public_key),
+                &hop.routing_information,
+            )?;
+        }
+
+        Ok(OutfoxPacket {
+            payload_length: payload.len(),
+            remaining_stages: Some(route.len()),
+            buffer,
+        })
+    }
+
+    /// The number of layers that still have to be unwrapped, if known.
+    /// It is not known for packets that were received and not yet unwrapped.
+    pub fn remaining_stages(&self) -> Option<usize> {
+        self.remaining_stages
+    }
+
+    /// Indicates whether all layers of the packet have been unwrapped and the payload can be recovered.
+    pub fn is_fully_unwrapped(&self) -> bool {
+        self.remaining_stages == Some(0)
+    }
+
+    /// Length of the serialized packet. Note that it remains constant as layers get unwrapped.
+    pub fn len(&self) -> usize {
+        MIX_PARAMS_LEN + self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The maximum number of layers the packet could still consist of given its length.
+    fn max_stages(&self) -> usize {
+        let max = self.buffer.len().saturating_sub(self.payload_length) / LAYER_LENGTH;
+        max.min(u8::MAX as usize)
+    }
+
+    /// Parameters of the outermost layer assuming the packet consists of the specified number of stages.
+    fn outermost_stage_params(&self, stages: usize) -> (Range<usize>, MixStageParameters) {
+        let mut mix_params = MixCreationParameters::new(self.payload_length);
+        for _ in 0..stages {
+            mix_params.add_outer_layer(ROUTING_INFORMATION_LENGTH);
+        }
+        mix_params.get_stage_params(stages - 1)
+    }
+
+    /// Unwraps the outermost layer of the packet using the provided x25519 secret key of the node
+    /// and returns the routing information included in it.
+    pub fn decode_next_layer(
+        &mut self,
+        mix_secret_key: &[u8; 32],
+    ) -> Result<OutfoxRoutingInformation, OutFoxError> {
+        self.decode_next_layer_with_replay_tag(mix_secret_key)
+            .map(|(routing_information, _)| routing_information)
+    }
+
+    /// Unwraps the outermost layer of the packet just like [decode_next_layer](Self::decode_next_layer),
+    /// but additionally returns the replay tag of the layer, which is unique for every packet and hop.
+    ///
+    /// The tag is derived from the key shared between the node and the creator of the layer
+    /// rather than from the public element carried in the packet, since the latter could be
+    /// re-encoded (e.g. by setting the ignored most significant bit) without changing the shared
+    /// key, and thus without invalidating the layer.
+    pub fn decode_next_layer_with_replay_tag(
+        &mut self,
+        mix_secret_key: &[u8; 32],
+    ) -> Result<(OutfoxRoutingInformation, [u8; REPLAY_TAG_LENGTH]), OutFoxError> {
+        let candidates = match self.remaining_stages {
+            Some(0) => return Err(OutFoxError::NoRemainingLayers),
+            Some(remaining) => remaining..=remaining,
+            None => 1..=self.max_stages(),
+        };
+
+        let mix_secret_key = Scalar::from_bytes_mod_order(*mix_secret_key);
+        let shared_key = derive_shared_key(&self.buffer, &mix_secret_key)?;
+
+        // the header only gets authenticated when we're using the correct number of stages,
+        // in which case the layer gets decoded in place. Otherwise the buffer is left untouched.
+        let mut unwrapped = None;
+        for stages in candidates {
+            let (range, stage_params) = self.outermost_stage_params(stages);
+            debug_assert_eq!(range.start, 0);
+
+            match stage_params
+                .decode_mix_layer_with_shared_key(&mut self.buffer[range], &shared_key)
+            {
+                Ok(()) => {
+                    unwrapped = Some((stages, stage_params));
+                    break;
+                }
+                Err(OutFoxError::ChaCha20Poly1305Error) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        let (stages, stage_params) = unwrapped.ok_or(OutFoxError::ChaCha20Poly1305Error)?;
+
+        let routing_range = stage_params.routing_data_range();
+        // this can't fail as we have explicitly specified the routing information length
+        let routing_bytes: [u8; ROUTING_INFORMATION_LENGTH] =
+            self.buffer[routing_range.clone()].try_into().unwrap();
+
+        // strip the processed layer and replace it with random padding,
+        // so that it would be indistinguishable from the rest of the packet
+        self.buffer.drain(..routing_range.end);
+        let padding_start = self.buffer.len();
+        self.buffer.resize(padding_start + routing_range.end, 0);
+        getrandom::getrandom(&mut self.buffer[padding_start..])
+            .expect("failed to obtain randomness from the OS");
+        self.remaining_stages = Some(stages - 1);
+
+        let replay_tag = blake3::derive_key(REPLAY_TAG_CONTEXT, &shared_key.0);
+        Ok(((&routing_bytes).into(), replay_tag))
+    }
+
+    /// Recovers the payload of a fully unwrapped packet.
+    pub fn recover_plaintext(mut self) -> Result<Vec<u8>, OutFoxError> {
+        if !self.is_fully_unwrapped() {
+            return Err(OutFoxError::NotFinalHop);
+        }
+        // get rid of the padding
+        self.buffer.truncate(self.payload_length);
+        Ok(self.buffer)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // this cast is safe as we're never going to have payloads exceeding 4GB
+        let payload_len = self.payload_length as u32;
+
+        let mut bytes = Vec::with_capacity(self.len());
+        bytes.extend_from_slice(&payload_len.to_be_bytes());
+        bytes.extend_from_slice(&self.buffer);
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for OutfoxPacket {
+    type Error = OutFoxError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() < MIX_PARAMS_LEN {
+            return Err(OutFoxError::LenMismatch {
+                expected: MIX_PARAMS_LEN,
+                got: bytes.len(),
+            });
+        }
+
+        // the unwrap is fine as we have checked the length of the input
+        let payload_length =
+            u32::from_be_bytes(bytes[..MIX_PARAMS_LEN].try_into().unwrap()) as usize;
+
+        // the packet has to contain at least a single layer to unwrap
+        // note that the buffer might contain padding after some of the layers got unwrapped
+        let buffer = &bytes[MIX_PARAMS_LEN..];
+        let minimum_length = payload_length.saturating_add(LAYER_LENGTH);
+        if buffer.len() < minimum_length {
+            return Err(OutFoxError::LenMismatch {
+                expected: minimum_length,
+                got: buffer.len(),
+            });
+        }
+
+        Ok(OutfoxPacket {
+            payload_length,
+            remaining_stages: None,
+            buffer: buffer.to_vec(),
+        })
+    }
+}
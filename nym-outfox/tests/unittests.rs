@@ -5,10 +5,12 @@ mod tests {
 
     use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
     use curve25519_dalek::scalar::Scalar;
-    use std::convert::TryInto;
+    use std::convert::{TryFrom, TryInto};
 
+    use nym_outfox::constants::*;
     use nym_outfox::format::*;
     use nym_outfox::lion::*;
+    use nym_outfox::packet::*;
 
     use std::iter::repeat_with;
 
@@ -139,4 +141,129 @@ mod tests {
             .decode_mix_layer(&mut buf[range0], &mix_secret)
             .unwrap();
     }
+
+    #[test]
+    fn test_packet_build_and_unwrap() {
+        let mix_secrets: Vec<_> = (0..DEFAULT_ROUTING_STAGES)
+            .map(|_| Scalar::from_bytes_mod_order(randombytes(32).try_into().unwrap()))
+            .collect();
+
+        let route: Vec<_> = mix_secrets
+            .iter()
+            .enumerate()
+            .map(|(i, secret)| {
+                let public_key = (&ED25519_BASEPOINT_TABLE * secret).to_montgomery();
+                OutfoxHop::new(
+                    public_key.0,
+                    [i as u8; NODE_ADDRESS_LENGTH],
+                    i as u64 * 1000,
+                )
+            })
+            .collect();
+
+        let payload = randombytes(2048);
+        let packet = OutfoxPacket::build(&payload, &route).unwrap();
+        assert_eq!(
+            packet.len(),
+            payload.len() + outfox_packet_overhead(DEFAULT_ROUTING_STAGES)
+        );
+
+        let mut received = OutfoxPacket::try_from(packet.to_bytes().as_slice()).unwrap();
+        assert_eq!(received.to_bytes(), packet.to_bytes());
+        // the number of stages can't be learned from the received packet
+        assert!(received.remaining_stages().is_none());
+
+        let mut seen_tags = Vec::new();
+        for (i, secret) in mix_secrets.iter().enumerate() {
+            assert!(!received.is_fully_unwrapped());
+            let (routing, replay_tag) = received
+                .decode_next_layer_with_replay_tag(secret.as_bytes())
+                .unwrap();
+            assert!(!seen_tags.contains(&replay_tag));
+            seen_tags.push(replay_tag);
+
+            assert_eq!(routing.next_address, [i as u8; NODE_ADDRESS_LENGTH]);
+            assert_eq!(routing.delay, i as u64 * 1000);
+            assert_eq!(
+                received.remaining_stages(),
+                Some(DEFAULT_ROUTING_STAGES - i - 1)
+            );
+
+            // make sure the intermediate packets can be correctly serialized
+            if !received.is_fully_unwrapped() {
+                received = OutfoxPacket::try_from(received.to_bytes().as_slice()).unwrap();
+                assert_eq!(received.len(), packet.len());
+            }
+        }
+
+        assert!(received
+            .decode_next_layer(mix_secrets[0].as_bytes())
+            .is_err());
+        assert_eq!(received.recover_plaintext().unwrap(), payload);
+    }
+
+    #[test]
+    fn test_serialized_packet_does_not_reveal_route_position() {
+        let mix_secrets: Vec<_> = (0..DEFAULT_ROUTING_STAGES)
+            .map(|_| Scalar::from_bytes_mod_order(randombytes(32).try_into().unwrap()))
+            .collect();
+        let route: Vec<_> = mix_secrets
+            .iter()
+            .map(|secret| {
+                let public_key = (&ED25519_BASEPOINT_TABLE * secret).to_montgomery();
+                OutfoxHop::new(public_key.0, [0; NODE_ADDRESS_LENGTH], 0)
+            })
+            .collect();
+
+        let payload = randombytes(1024);
+        let mut packet = OutfoxPacket::build(&payload, &route).unwrap();
+        let initial_params = packet.to_bytes()[..MIX_PARAMS_LEN].to_vec();
+
+        for secret in &mix_secrets[..DEFAULT_ROUTING_STAGES - 1] {
+            packet.decode_next_layer(secret.as_bytes()).unwrap();
+            let bytes = packet.to_bytes();
+
+            // the cleartext parameters are identical at every hop
+            assert_eq!(bytes[..MIX_PARAMS_LEN], initial_params[..]);
+
+            // and the stripped layers are not replaced by a recognisable (zero) padding
+            let layer_len = GROUPELEMENTBYTES + TAGBYTES + ROUTING_INFORMATION_LENGTH;
+            assert!(bytes[bytes.len() - layer_len..].iter().any(|b| *b != 0));
+        }
+    }
+
+    #[test]
+    fn test_reencoded_public_element_has_the_same_replay_tag() {
+        let mix_secret = Scalar::from_bytes_mod_order(randombytes(32).try_into().unwrap());
+        let public_key = (&ED25519_BASEPOINT_TABLE * &mix_secret).to_montgomery();
+        let route = vec![OutfoxHop::new(public_key.0, [0; NODE_ADDRESS_LENGTH], 0)];
+
+        let bytes = OutfoxPacket::build(&randombytes(1024), &route)
+            .unwrap()
+            .to_bytes();
+        // the most significant bit of the public element is ignored by the scalar multiplication
+        let mut reencoded = bytes.clone();
+        reencoded[MIX_PARAMS_LEN + GROUPELEMENTBYTES - 1] ^= 0x80;
+
+        let (_, tag) = OutfoxPacket::try_from(bytes.as_slice())
+            .unwrap()
+            .decode_next_layer_with_replay_tag(mix_secret.as_bytes())
+            .unwrap();
+        let (_, reencoded_tag) = OutfoxPacket::try_from(reencoded.as_slice())
+            .unwrap()
+            .decode_next_layer_with_replay_tag(mix_secret.as_bytes())
+            .unwrap();
+        assert_eq!(tag, reencoded_tag);
+    }
+
+    #[test]
+    fn test_packet_unwrap_with_wrong_key_fails() {
+        let mix_secret = Scalar::from_bytes_mod_order(randombytes(32).try_into().unwrap());
+        let public_key = (&ED25519_BASEPOINT_TABLE * &mix_secret).to_montgomery();
+        let route = vec![OutfoxHop::new(public_key.0, [0; NODE_ADDRESS_LENGTH], 0)];
+
+        let mut packet = OutfoxPacket::build(&randombytes(1024), &route).unwrap();
+        let wrong_secret = Scalar::from_bytes_mod_order(randombytes(32).try_into().unwrap());
+        assert!(packet.decode_next_layer(wrong_secret.as_bytes()).is_err());
+    }
 }
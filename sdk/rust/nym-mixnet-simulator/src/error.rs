@@ -0,0 +1,16 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SimulatorError {
+    #[error("failed to bind to a loopback socket - {0}")]
+    SocketBindFailure(#[from] std::io::Error),
+
+    #[error("the simulated network requires at least a single mixnode on each layer")]
+    NoMixnodes,
+
+    #[error("the simulated network requires at least a single gateway")]
+    NoGateways,
+}
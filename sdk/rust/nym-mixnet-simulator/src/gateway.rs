@@ -0,0 +1,111 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    INITIAL_CONNECTION_TIMEOUT, INITIAL_RECONNECTION_BACKOFF, LOOPBACK_HOST,
+    MAXIMUM_CONNECTION_BUFFER_SIZE, MAXIMUM_RECONNECTION_BACKOFF,
+};
+use log::warn;
+use mixnet_client::forwarder::PacketForwarder;
+use mixnode_common::packet_processor::sphinx_keys::SphinxKeys;
+use nym_crypto::asymmetric::{encryption, identity};
+use nym_gateway::EmbeddedGateway;
+use nym_task::TaskClient;
+use nym_topology::{gateway, NetworkAddress};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// Gateway listening on loopback that relies on the handlers of the actual gateway
+/// (see [`EmbeddedGateway`]) for dealing with both the mix packets and the clients.
+pub(crate) struct SimulatedGateway {
+    mix_listener: TcpListener,
+    clients_listener: TcpListener,
+    identity_keys: Arc<identity::KeyPair>,
+    sphinx_keys: encryption::KeyPair,
+}
+
+impl SimulatedGateway {
+    pub(crate) async fn new() -> std::io::Result<Self> {
+        let mut rng = rand::rngs::OsRng;
+
+        Ok(SimulatedGateway {
+            mix_listener: TcpListener::bind((LOOPBACK_HOST, 0)).await?,
+            clients_listener: TcpListener::bind((LOOPBACK_HOST, 0)).await?,
+            identity_keys: Arc::new(identity::KeyPair::new(&mut rng)),
+            sphinx_keys: encryption::KeyPair::new(&mut rng),
+        })
+    }
+
+    pub(crate) fn topology_node(&self) -> std::io::Result<gateway::Node> {
+        Ok(gateway::Node {
+            owner: "simulated-gateway".to_string(),
+            stake: 0,
+            location: "loopback".to_string(),
+            host: NetworkAddress::IpAddr(LOOPBACK_HOST.into()),
+            mix_host: self.mix_listener.local_addr()?,
+            clients_port: self.clients_listener.local_addr()?.port(),
+            identity_key: *self.identity_keys.public_key(),
+            sphinx_key: *self.sphinx_keys.public_key(),
+            upcoming_sphinx_key: None,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+    }
+
+    pub(crate) fn start(
+        self,
+        forwarder_shutdown: TaskClient,
+        mut mix_shutdown: TaskClient,
+        mut clients_shutdown: TaskClient,
+    ) {
+        let (mut packet_forwarder, outbound_mix_sender) = PacketForwarder::new(
+            INITIAL_RECONNECTION_BACKOFF,
+            MAXIMUM_RECONNECTION_BACKOFF,
+            INITIAL_CONNECTION_TIMEOUT,
+            MAXIMUM_CONNECTION_BUFFER_SIZE,
+            false,
+            forwarder_shutdown,
+        );
+        tokio::spawn(async move { packet_forwarder.run().await });
+
+        let gateway = EmbeddedGateway::new(
+            self.identity_keys,
+            SphinxKeys::new(self.sphinx_keys.private_key().into()),
+            outbound_mix_sender,
+        );
+
+        let mix_gateway = gateway.clone();
+        let mix_listener = self.mix_listener;
+        tokio::spawn(async move {
+            mix_shutdown.mark_as_success();
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = mix_shutdown.recv() => break,
+                    connection = mix_listener.accept() => match connection {
+                        Ok((socket, remote)) => {
+                            mix_gateway.handle_mix_connection(socket, remote, mix_shutdown.clone());
+                        }
+                        Err(err) => warn!("failed to accept incoming mix connection - {err}"),
+                    }
+                }
+            }
+        });
+
+        let clients_listener = self.clients_listener;
+        tokio::spawn(async move {
+            clients_shutdown.mark_as_success();
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = clients_shutdown.recv() => break,
+                    connection = clients_listener.accept() => match connection {
+                        Ok((socket, _)) => {
+                            gateway.handle_client_connection(socket, clients_shutdown.clone());
+                        }
+                        Err(err) => warn!("failed to accept incoming client connection - {err}"),
+                    }
+                }
+            }
+        });
+    }
+}
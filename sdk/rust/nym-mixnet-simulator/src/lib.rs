@@ -0,0 +1,218 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-process mixnet simulator for testing applications built on top of the nym-sdk without
+//! requiring access to a live network.
+//!
+//! The simulator spins up a set of mixnodes (each one with its own `SphinxPacketProcessor` and
+//! `DelayForwarder`) and gateways (running the handlers of the actual gateway on top of an
+//! in-memory storage) listening on loopback, alongside
+//! a synthetic `NymTopology` describing them. Clients can be pointed at it by using the
+//! provided topology provider.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use nym_mixnet_simulator::MixnetSimulator;
+//! use nym_sdk::mixnet;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let simulator = MixnetSimulator::builder().start().await.unwrap();
+//!
+//!     let mut config = mixnet::Config::default();
+//!     config.debug_config = nym_mixnet_simulator::simulation_debug_config();
+//!
+//!     let mut client = mixnet::MixnetClientBuilder::new()
+//!         .config(config)
+//!         .custom_topology_provider(Box::new(simulator.topology_provider()))
+//!         .build::<mixnet::EmptyReplyStorage>()
+//!         .await
+//!         .unwrap()
+//!         .connect_to_mixnet()
+//!         .await
+//!         .unwrap();
+//!
+//!     let our_address = client.nym_address();
+//!     client.send_str(our_address, "hello there").await;
+//!     let received = client.wait_for_messages().await.unwrap();
+//!     assert_eq!(received[0].message, b"hello there");
+//!
+//!     client.disconnect().await;
+//!     simulator.shutdown().await;
+//! }
+//! ```
+
+use crate::gateway::SimulatedGateway;
+use crate::mixnode::SimulatedMixnode;
+use client_core::client::topology_control::HardcodedTopologyProvider;
+use client_core::config::DebugConfig;
+use nym_mixnet_contract_common::Layer;
+use nym_task::TaskManager;
+use nym_topology::{MixLayer, NymTopology};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+pub use error::SimulatorError;
+pub use network_conditions::NetworkConditions;
+
+mod error;
+mod gateway;
+mod mixnode;
+mod network_conditions;
+
+pub(crate) const LOOPBACK_HOST: Ipv4Addr = Ipv4Addr::LOCALHOST;
+
+// settings of the clients used for forwarding packets between the simulated nodes
+pub(crate) const INITIAL_RECONNECTION_BACKOFF: Duration = Duration::from_millis(100);
+pub(crate) const MAXIMUM_RECONNECTION_BACKOFF: Duration = Duration::from_secs(1);
+pub(crate) const INITIAL_CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+pub(crate) const MAXIMUM_CONNECTION_BUFFER_SIZE: usize = 2000;
+
+const DEFAULT_MIXNODES_PER_LAYER: usize = 1;
+const DEFAULT_GATEWAYS: usize = 1;
+
+pub(crate) fn forwarding_client_config() -> mixnet_client::Config {
+    mixnet_client::Config::new(
+        INITIAL_RECONNECTION_BACKOFF,
+        MAXIMUM_RECONNECTION_BACKOFF,
+        INITIAL_CONNECTION_TIMEOUT,
+        MAXIMUM_CONNECTION_BUFFER_SIZE,
+        false,
+    )
+}
+
+/// Client [`DebugConfig`] suitable for running against the simulator: the packet delays are
+/// kept to the minimum, the cover traffic is disabled and the real messages are sent as soon
+/// as they're available so that the tests are not slowed down by the traffic shaping.
+pub fn simulation_debug_config() -> DebugConfig {
+    DebugConfig {
+        average_packet_delay: Duration::from_millis(1),
+        average_ack_delay: Duration::from_millis(1),
+        ack_wait_multiplier: 1.5,
+        ack_wait_addition: Duration::from_millis(500),
+        message_sending_average_delay: Duration::from_millis(1),
+        disable_loop_cover_traffic_stream: true,
+        disable_main_poisson_packet_distribution: true,
+        ..Default::default()
+    }
+}
+
+pub struct MixnetSimulatorBuilder {
+    mixnodes_per_layer: usize,
+    gateways: usize,
+    network_conditions: NetworkConditions,
+}
+
+impl Default for MixnetSimulatorBuilder {
+    fn default() -> Self {
+        MixnetSimulatorBuilder {
+            mixnodes_per_layer: DEFAULT_MIXNODES_PER_LAYER,
+            gateways: DEFAULT_GATEWAYS,
+            network_conditions: NetworkConditions::new(),
+        }
+    }
+}
+
+impl MixnetSimulatorBuilder {
+    #[must_use]
+    pub fn mixnodes_per_layer(mut self, mixnodes_per_layer: usize) -> Self {
+        self.mixnodes_per_layer = mixnodes_per_layer;
+        self
+    }
+
+    #[must_use]
+    pub fn gateways(mut self, gateways: usize) -> Self {
+        self.gateways = gateways;
+        self
+    }
+
+    #[must_use]
+    pub fn network_conditions(mut self, network_conditions: NetworkConditions) -> Self {
+        self.network_conditions = network_conditions;
+        self
+    }
+
+    /// Binds all the simulated nodes to loopback sockets and starts them up.
+    pub async fn start(self) -> Result<MixnetSimulator, SimulatorError> {
+        if self.mixnodes_per_layer == 0 {
+            return Err(SimulatorError::NoMixnodes);
+        }
+        if self.gateways == 0 {
+            return Err(SimulatorError::NoGateways);
+        }
+
+        let task_manager = TaskManager::default();
+
+        let mut mixes: HashMap<MixLayer, Vec<_>> = HashMap::new();
+        let mut mix_id = 0;
+        for layer in [Layer::One, Layer::Two, Layer::Three] {
+            for _ in 0..self.mixnodes_per_layer {
+                mix_id += 1;
+                let mixnode = SimulatedMixnode::new(mix_id, layer).await?;
+                mixes
+                    .entry(layer as MixLayer)
+                    .or_default()
+                    .push(mixnode.topology_node()?);
+
+                mixnode.start(
+                    self.network_conditions.clone(),
+                    task_manager.subscribe(),
+                    task_manager.subscribe(),
+                );
+            }
+        }
+
+        let mut gateways = Vec::with_capacity(self.gateways);
+        for _ in 0..self.gateways {
+            let gateway = SimulatedGateway::new().await?;
+            gateways.push(gateway.topology_node()?);
+
+            gateway.start(
+                task_manager.subscribe(),
+                task_manager.subscribe(),
+                task_manager.subscribe(),
+            );
+        }
+
+        Ok(MixnetSimulator {
+            topology: NymTopology::new(mixes, gateways),
+            network_conditions: self.network_conditions,
+            task_manager,
+        })
+    }
+}
+
+/// Handle to the running simulated network. The nodes are stopped once [`Self::shutdown`] is called.
+pub struct MixnetSimulator {
+    topology: NymTopology,
+    network_conditions: NetworkConditions,
+    task_manager: TaskManager,
+}
+
+impl MixnetSimulator {
+    pub fn builder() -> MixnetSimulatorBuilder {
+        MixnetSimulatorBuilder::default()
+    }
+
+    /// Topology describing all the simulated nodes.
+    pub fn topology(&self) -> &NymTopology {
+        &self.topology
+    }
+
+    /// Topology provider that can be used with the clients in order to make them use
+    /// the simulated network.
+    pub fn topology_provider(&self) -> HardcodedTopologyProvider {
+        HardcodedTopologyProvider::new(self.topology.clone())
+    }
+
+    pub fn network_conditions(&self) -> &NetworkConditions {
+        &self.network_conditions
+    }
+
+    pub async fn shutdown(mut self) {
+        self.task_manager.signal_shutdown().ok();
+        self.task_manager.wait_for_shutdown().await;
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::network_conditions::NetworkConditions;
+use crate::{forwarding_client_config, LOOPBACK_HOST};
+use futures::StreamExt;
+use log::{debug, error, trace, warn};
+use mixnode_common::packet_delayforwarder::{DelayForwarder, PacketDelayForwardSender};
+use mixnode_common::packet_processor::processor::{MixProcessingResult, SphinxPacketProcessor};
+use mixnode_common::packet_processor::sphinx_keys::SphinxKeys;
+use nym_crypto::asymmetric::{encryption, identity};
+use nym_mixnet_contract_common::{Layer, MixId};
+use nym_sphinx::framing::codec::NymCodec;
+use nym_sphinx::framing::packet::FramedNymPacket;
+use nym_task::TaskClient;
+use nym_topology::{mix, NetworkAddress};
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Instant;
+use tokio_util::codec::Framed;
+
+pub(crate) struct SimulatedMixnode {
+    mix_id: MixId,
+    layer: Layer,
+    listener: TcpListener,
+    identity_keys: identity::KeyPair,
+    sphinx_keys: encryption::KeyPair,
+}
+
+impl SimulatedMixnode {
+    pub(crate) async fn new(mix_id: MixId, layer: Layer) -> std::io::Result<Self> {
+        let mut rng = rand::rngs::OsRng;
+
+        Ok(SimulatedMixnode {
+            mix_id,
+            layer,
+            listener: TcpListener::bind((LOOPBACK_HOST, 0)).await?,
+            identity_keys: identity::KeyPair::new(&mut rng),
+            sphinx_keys: encryption::KeyPair::new(&mut rng),
+        })
+    }
+
+    pub(crate) fn topology_node(&self) -> std::io::Result<mix::Node> {
+        Ok(mix::Node {
+            mix_id: self.mix_id,
+            owner: format!("simulated-mixnode-{}", self.mix_id),
+            host: NetworkAddress::IpAddr(LOOPBACK_HOST.into()),
+            mix_host: self.listener.local_addr()?,
+            identity_key: *self.identity_keys.public_key(),
+            sphinx_key: *self.sphinx_keys.public_key(),
+            upcoming_sphinx_key: None,
+            layer: self.layer,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+    }
+
+    pub(crate) fn start(
+        self,
+        network_conditions: NetworkConditions,
+        forwarder_shutdown: TaskClient,
+        mut listener_shutdown: TaskClient,
+    ) {
+        let mut delay_forwarder = DelayForwarder::new(
+            mixnet_client::Client::new(forwarding_client_config()),
+            network_conditions.clone(),
+            forwarder_shutdown,
+        );
+        let delay_forwarding_channel = delay_forwarder.sender();
+        tokio::spawn(async move { delay_forwarder.run().await });
+
+        let handler = ConnectionHandler {
+            packet_processor: SphinxPacketProcessor::new(SphinxKeys::new(
+                self.sphinx_keys.private_key().into(),
+            )),
+            delay_forwarding_channel,
+            network_conditions,
+        };
+
+        let listener = self.listener;
+        tokio::spawn(async move {
+            listener_shutdown.mark_as_success();
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = listener_shutdown.recv() => {
+                        trace!("SimulatedMixnode listener: received shutdown");
+                        break;
+                    }
+                    connection = listener.accept() => match connection {
+                        Ok((socket, remote)) => {
+                            let handler = handler.clone();
+                            let shutdown = listener_shutdown.clone();
+                            tokio::spawn(handler.handle_connection(socket, remote, shutdown));
+                        }
+                        Err(err) => warn!("failed to accept incoming connection - {err}"),
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[derive(Clone)]
+struct ConnectionHandler {
+    packet_processor: SphinxPacketProcessor,
+    delay_forwarding_channel: PacketDelayForwardSender,
+    network_conditions: NetworkConditions,
+}
+
+impl ConnectionHandler {
+    fn handle_received_packet(&self, framed_nym_packet: FramedNymPacket) {
+        if self.network_conditions.should_drop_received() {
+            trace!("dropping received packet as requested by the network conditions");
+            return;
+        }
+
+        match self.packet_processor.process_received(framed_nym_packet) {
+            Err(err) => debug!("We failed to process received sphinx packet - {err}"),
+            Ok(MixProcessingResult::ForwardHop(forward_packet, delay)) => {
+                let forward_instant = delay.map(|delay| Instant::now() + delay.to_duration());
+                self.delay_forwarding_channel
+                    .unbounded_send((forward_packet, forward_instant))
+                    .expect("the delay-forwarder has died!");
+            }
+            Ok(MixProcessingResult::FinalHop(..)) => {
+                warn!("a simulated mixnode has received a final hop packet")
+            }
+        }
+    }
+
+    async fn handle_connection(
+        self,
+        conn: TcpStream,
+        remote: SocketAddr,
+        mut shutdown: TaskClient,
+    ) {
+        debug!("Starting simulated mixnode connection handler for {remote}");
+        shutdown.mark_as_success();
+
+        let mut framed_conn = Framed::new(conn, NymCodec);
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => {
+                    trace!("ConnectionHandler: received shutdown");
+                }
+                framed_nym_packet = framed_conn.next() => match framed_nym_packet {
+                    Some(Ok(framed_nym_packet)) => self.handle_received_packet(framed_nym_packet),
+                    Some(Err(err)) => {
+                        error!("The socket connection got corrupted with error: {err}. Closing the socket");
+                        return;
+                    }
+                    None => return,
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use mixnode_common::dropped_packets::DropReason;
+use mixnode_common::packet_delayforwarder::ForwardingReporter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Default)]
+struct NetworkConditionsInner {
+    packets_to_drop: AtomicUsize,
+
+    processed: AtomicUsize,
+    dropped: AtomicUsize,
+    failed_forwards: AtomicUsize,
+    forwarded: AtomicUsize,
+}
+
+/// Handle shared between all simulated mixnodes allowing to deterministically inject faults into
+/// the network, e.g. in order to trigger retransmissions, and to inspect the amount of traffic
+/// that went through it.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkConditions {
+    inner: Arc<NetworkConditionsInner>,
+}
+
+impl NetworkConditions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the mixnodes silently drop the next `n` packets they receive,
+    /// regardless of which layer they're on.
+    pub fn drop_next_packets(&self, n: usize) {
+        self.inner.packets_to_drop.fetch_add(n, Ordering::SeqCst);
+    }
+
+    /// Number of packets received by the mixnodes, including the dropped ones.
+    pub fn processed_packets(&self) -> usize {
+        self.inner.processed.load(Ordering::SeqCst)
+    }
+
+    /// Number of packets the mixnodes have deliberately dropped due to [`Self::drop_next_packets`].
+    pub fn dropped_packets(&self) -> usize {
+        self.inner.dropped.load(Ordering::SeqCst)
+    }
+
+    /// Number of packets the mixnodes have failed to forward to the next hop,
+    /// e.g. because it could not be reached.
+    pub fn failed_forwards(&self) -> usize {
+        self.inner.failed_forwards.load(Ordering::SeqCst)
+    }
+
+    /// Number of packets the mixnodes have forwarded to the next hop.
+    pub fn forwarded_packets(&self) -> usize {
+        self.inner.forwarded.load(Ordering::SeqCst)
+    }
+
+    /// Registers a newly received packet and determines whether it should be dropped.
+    pub(crate) fn should_drop_received(&self) -> bool {
+        self.inner.processed.fetch_add(1, Ordering::SeqCst);
+
+        let should_drop = self
+            .inner
+            .packets_to_drop
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |remaining| {
+                remaining.checked_sub(1)
+            })
+            .is_ok();
+
+        if should_drop {
+            self.inner.dropped.fetch_add(1, Ordering::SeqCst);
+        }
+        should_drop
+    }
+}
+
+impl ForwardingReporter for NetworkConditions {
+    fn report_sent(&self, _destination: String) {
+        self.inner.forwarded.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn report_dropped(&self, destination: String, reason: DropReason, count: usize) {
+        log::warn!("failed to forward {count} packet(s) to {destination} ({reason})");
+        self.inner
+            .failed_forwards
+            .fetch_add(count, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nym_mixnet_simulator::{simulation_debug_config, MixnetSimulator};
+use nym_sdk::mixnet::{
+    self, IncludedSurbs, InputMessage, MixnetClient, MixnetListener, ReconstructedMessage,
+};
+use nym_task::connections::TransmissionLane;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn connect_client(simulator: &MixnetSimulator) -> MixnetClient {
+    let mut config = mixnet::Config::default();
+    config.debug_config = simulation_debug_config();
+
+    mixnet::MixnetClientBuilder::new()
+        .config(config)
+        .custom_topology_provider(Box::new(simulator.topology_provider()))
+        .build::<mixnet::EmptyReplyStorage>()
+        .await
+        .unwrap()
+        .connect_to_mixnet()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_message(client: &mut MixnetClient) -> ReconstructedMessage {
+    tokio::time::timeout(TEST_TIMEOUT, client.wait_for_messages())
+        .await
+        .expect("timed out while waiting for the message")
+        .expect("the client has shut down")
+        .pop()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn messages_get_delivered_through_the_simulated_network() {
+    let simulator = MixnetSimulator::builder().start().await.unwrap();
+    let mut client = connect_client(&simulator).await;
+
+    let our_address = client.nym_address();
+    client.send_str(our_address, "hello there").await;
+
+    let received = wait_for_message(&mut client).await;
+    assert_eq!(received.message, b"hello there");
+    assert!(simulator.network_conditions().forwarded_packets() > 0);
+
+    client.disconnect().await;
+    simulator.shutdown().await;
+}
+
+#[tokio::test]
+async fn dropped_packets_get_retransmitted() {
+    let simulator = MixnetSimulator::builder().start().await.unwrap();
+    let mut client = connect_client(&simulator).await;
+
+    simulator.network_conditions().drop_next_packets(1);
+
+    let our_address = client.nym_address();
+    client
+        .send_bytes(
+            our_address,
+            b"foomp".to_vec(),
+            IncludedSurbs::ExposeSelfAddress,
+        )
+        .await;
+
+    let received = wait_for_message(&mut client).await;
+    assert_eq!(received.message, b"foomp");
+    assert_eq!(simulator.network_conditions().dropped_packets(), 1);
+    assert_eq!(simulator.network_conditions().failed_forwards(), 0);
+
+    client.disconnect().await;
+    simulator.shutdown().await;
+}
+
+#[tokio::test]
+async fn replies_can_be_sent_with_surbs() {
+    let simulator = MixnetSimulator::builder()
+        .mixnodes_per_layer(2)
+        .start()
+        .await
+        .unwrap();
+    let mut alice = connect_client(&simulator).await;
+    let mut bob = connect_client(&simulator).await;
+
+    let bob_address = bob.nym_address();
+    alice
+        .send_bytes(bob_address, b"hi bob".to_vec(), IncludedSurbs::new(5))
+        .await;
+
+    let received = wait_for_message(&mut bob).await;
+    assert_eq!(received.message, b"hi bob");
+    let sender_tag = received
+        .sender_tag
+        .expect("the message should have been sent anonymously");
+
+    bob.sender()
+        .send_input_message(InputMessage::new_reply(
+            sender_tag,
+            b"hi anonymous".to_vec(),
+            TransmissionLane::General,
+        ))
+        .await;
+
+    let reply = wait_for_message(&mut alice).await;
+    assert_eq!(reply.message, b"hi anonymous");
+
+    alice.disconnect().await;
+    bob.disconnect().await;
+    simulator.shutdown().await;
+}
+
+#[tokio::test]
+async fn streams_deliver_ordered_data_in_both_directions() {
+    let simulator = MixnetSimulator::builder()
+        .mixnodes_per_layer(2)
+        .start()
+        .await
+        .unwrap();
+    let alice = MixnetListener::new(connect_client(&simulator).await);
+    let mut bob = MixnetListener::new(connect_client(&simulator).await);
+
+    let mut alice_stream = alice.connect(bob.nym_address()).unwrap();
+    for i in 0..10u8 {
+        alice_stream.write_all(&[i; 100]).await.unwrap();
+    }
+
+    let mut bob_stream = tokio::time::timeout(TEST_TIMEOUT, bob.accept())
+        .await
+        .expect("timed out while waiting for the stream")
+        .unwrap();
+    assert_eq!(bob_stream.connection_id(), alice_stream.connection_id());
+    assert!(bob_stream.remote_address().is_none());
+
+    let mut received = vec![0u8; 1000];
+    tokio::time::timeout(TEST_TIMEOUT, bob_stream.read_exact(&mut received))
+        .await
+        .expect("timed out while waiting for the data")
+        .unwrap();
+    let expected: Vec<_> = (0..10u8).flat_map(|i| [i; 100]).collect();
+    assert_eq!(received, expected);
+
+    bob_stream.write_all(b"general kenobi").await.unwrap();
+    bob_stream.shutdown().await.unwrap();
+
+    let mut reply = Vec::new();
+    tokio::time::timeout(TEST_TIMEOUT, alice_stream.read_to_end(&mut reply))
+        .await
+        .expect("timed out while waiting for the reply")
+        .unwrap();
+    assert_eq!(reply, b"general kenobi");
+
+    drop(alice_stream);
+    drop(bob_stream);
+    alice.disconnect().await;
+    bob.disconnect().await;
+    simulator.shutdown().await;
+}
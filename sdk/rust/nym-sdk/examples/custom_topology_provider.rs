@@ -0,0 +1,40 @@
+use nym_sdk::mixnet;
+
+#[tokio::main]
+async fn main() {
+    nym_bin_common::logging::setup_logging();
+
+    // The topology file is expected to contain the json-serialised `mixnet::TopologyFile`,
+    // i.e. the list of mixnodes and gateways in the same format as returned by the nym-api
+    let topology_file = std::env::args()
+        .nth(1)
+        .expect("path to the topology file was not provided");
+
+    let topology_provider =
+        mixnet::HardcodedTopologyProvider::new_from_file(topology_file).unwrap();
+
+    // Rather than periodically querying the nym-api, the client is going to keep on using the
+    // topology we loaded from the file
+    let client = mixnet::MixnetClientBuilder::new()
+        .custom_topology_provider(Box::new(topology_provider))
+        .build::<mixnet::EmptyReplyStorage>()
+        .await
+        .unwrap();
+
+    let mut client = client.connect_to_mixnet().await.unwrap();
+
+    let our_address = client.nym_address();
+    println!("Our client nym address is: {our_address}");
+
+    // Send a message throught the mixnet to ourselves
+    client.send_str(our_address, "hello there").await;
+
+    println!("Waiting for message");
+    if let Some(received) = client.wait_for_messages().await {
+        for r in received {
+            println!("Received: {}", String::from_utf8_lossy(&r.message));
+        }
+    }
+
+    client.disconnect().await;
+}
@@ -49,7 +49,7 @@ async fn main() {
     println!("Our client nym address is: {our_address}");
 
     // Send important info up the pipe to a buddy
-    client.send_str(*our_address, "hello there").await;
+    client.send_str(our_address, "hello there").await;
 
     println!("Waiting for message");
     if let Some(received) = client.wait_for_messages().await {
@@ -0,0 +1,39 @@
+use nym_sdk::mixnet;
+
+#[tokio::main]
+async fn main() {
+    nym_bin_common::logging::setup_logging();
+
+    // Address of a running `nym-pubsub-provider`
+    let provider_address = std::env::args()
+        .nth(1)
+        .expect("the address of the pub/sub provider must be provided");
+    let provider = mixnet::Recipient::try_from_base58_string(provider_address).unwrap();
+
+    let client = mixnet::MixnetClient::connect_new().await.unwrap();
+
+    // From now on all the messages received by the client are treated as pub/sub responses
+    let mut pubsub = mixnet::PubSubClient::new(client, provider);
+    let topic: mixnet::Topic = "news".parse().unwrap();
+
+    // Subscribe to the topic and publish to it ourselves. The message comes back using the
+    // SURBs we attached to the subscription
+    pubsub.subscribe(topic.clone()).await;
+    pubsub.publish(topic, b"hello there".to_vec()).await;
+
+    println!("Waiting for the message");
+    while let Some(event) = pubsub.next_event().await {
+        match event {
+            mixnet::PubSubResponseContent::Message { topic, payload } => {
+                println!(
+                    "Received on '{topic}': {}",
+                    String::from_utf8_lossy(&payload)
+                );
+                break;
+            }
+            other => println!("Received: {other:?}"),
+        }
+    }
+
+    pubsub.disconnect().await;
+}
@@ -0,0 +1,36 @@
+use nym_sdk::mixnet;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    nym_bin_common::logging::setup_logging();
+
+    let client = mixnet::MixnetClient::connect_new().await.unwrap();
+
+    // From now on all the messages received by the client are treated as rpc packets
+    let rpc = mixnet::MixnetRpc::new(client);
+    let our_address = rpc.nym_address();
+    println!("Our client nym address is: {our_address}");
+
+    // Serve the requests for the "reverse" method
+    rpc.register_handler("reverse", |mut payload| async move {
+        payload.reverse();
+        Ok(payload)
+    })
+    .unwrap();
+
+    // Call ourselves. The response comes back using the SURBs attached to the request
+    println!("Waiting for the response");
+    let response = rpc
+        .request(
+            our_address,
+            "reverse",
+            b"hello there".to_vec(),
+            Duration::from_secs(30),
+        )
+        .await
+        .unwrap();
+    println!("Received: {}", String::from_utf8_lossy(&response));
+
+    rpc.disconnect().await;
+}
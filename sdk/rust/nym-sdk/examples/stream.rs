@@ -0,0 +1,31 @@
+use nym_sdk::mixnet;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::main]
+async fn main() {
+    nym_bin_common::logging::setup_logging();
+
+    let client = mixnet::MixnetClient::connect_new().await.unwrap();
+
+    // From now on all the messages received by the client are treated as stream packets
+    let mut listener = mixnet::MixnetListener::new(client);
+    let our_address = listener.nym_address();
+    println!("Our client nym address is: {our_address}");
+
+    // Open a stream to ourselves and write some data to it
+    let mut outbound = listener.connect(our_address).unwrap();
+    outbound.write_all(b"hello ").await.unwrap();
+    outbound.write_all(b"there").await.unwrap();
+    outbound.shutdown().await.unwrap();
+
+    // The other end of the stream can only reply using the attached SURBs
+    println!("Waiting for the stream to be accepted");
+    let mut inbound = listener.accept().await.unwrap();
+    let mut received = String::new();
+    inbound.read_to_string(&mut received).await.unwrap();
+    println!("Received: {received}");
+
+    drop(outbound);
+    drop(inbound);
+    listener.disconnect().await;
+}
@@ -33,6 +33,23 @@ pub enum Error {
     ReregisteringGatewayNotSupported,
     #[error("no gateway key set")]
     NoGatewayKeySet,
+    #[error("the custom topology provider failed to provide a network topology")]
+    CustomTopologyUnavailable,
+    #[error("the mixnet listener has been shut down")]
+    MixnetListenerShutdown,
+    #[error("the mixnet rpc has been shut down")]
+    MixnetRpcShutdown,
+    #[error("no response to the rpc request has been received in time")]
+    RpcTimeout,
+    #[error("the rpc request has failed on the remote: {0}")]
+    RpcRemoteError(String),
+    #[error("the rpc method name is {length} bytes long while at most {max} bytes are allowed")]
+    RpcMethodTooLong { length: usize, max: usize },
+    #[error("persisting received messages requires the storage to be enabled")]
+    ReceivedMessagesPersistenceWithoutStorage,
+
+    #[error("the chosen reply storage backend does not support encryption")]
+    StorageEncryptionUnsupported,
 
     #[error("failed to create reply storage backend: {source}")]
     StorageError {
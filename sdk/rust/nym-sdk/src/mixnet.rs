@@ -17,7 +17,7 @@
 //!     println!("Our client nym address is: {our_address}");
 //!
 //!     // Send a message throught the mixnet to ourselves
-//!     client.send_str(*our_address, "hello there").await;
+//!     client.send_str(our_address, "hello there").await;
 //!
 //!     println!("Waiting for message");
 //!     if let Some(received) = client.wait_for_messages().await {
@@ -34,22 +34,41 @@ mod client;
 mod config;
 mod connection_state;
 mod keys;
+mod listener;
 mod paths;
+mod pubsub;
+mod rpc;
+mod stream;
 
 pub use client::{
     DisconnectedMixnetClient, IncludedSurbs, MixnetClient, MixnetClientBuilder, MixnetClientSender,
 };
 pub use client_core::{
     client::{
+        gateway_failover::SelfAddress,
         inbound_messages::InputMessage,
+        real_messages_control::PacketPreparationMetrics,
         replies::reply_storage::{fs_backend::Backend as ReplyStorage, Empty as EmptyReplyStorage},
+        statistics::{
+            ClientStatistics, ClientStatisticsSnapshot, DestinationStatistics,
+            StatisticsDestination,
+        },
+        topology_control::{
+            HardcodedTopologyProvider, NymApiTopologyProvider, TopologyFile, TopologyProvider,
+        },
     },
-    config::GatewayEndpointConfig,
+    config::{persistence::encryption::StoragePassphrase, GatewayEndpointConfig},
 };
 pub use config::Config;
 pub use keys::{Keys, KeysArc};
+pub use listener::MixnetListener;
 pub use nym_sphinx::{
     addressing::clients::{ClientIdentity, Recipient},
     receiver::ReconstructedMessage,
 };
+pub use nym_topology::NymTopology;
 pub use paths::{GatewayKeyMode, KeyMode, StoragePaths};
+pub use pubsub::PubSubClient;
+pub use pubsub_requests::{PubSubResponseContent, Topic};
+pub use rpc::{MixnetRpc, RpcConfig, RpcRequestId, RpcResult};
+pub use stream::MixnetStream;
@@ -1,16 +1,24 @@
-use std::{path::Path, sync::Arc};
+use std::{io, path::Path, sync::Arc};
 
 use client_core::{
     client::{
         base_client::{
             BaseClientBuilder, ClientInput, ClientOutput, ClientState, CredentialsToggle,
         },
+        gateway_failover::{FailoverStore, SelfAddress},
         inbound_messages::InputMessage,
         key_manager::KeyManager,
-        received_buffer::ReconstructedMessagesReceiver,
+        real_messages_control::PacketPreparationMetrics,
+        received_buffer::{storage::fs_backend, ReconstructedMessagesReceiver},
         replies::reply_storage::ReplyStorageBackend,
+        statistics::ClientStatistics,
+        topology_control::TopologyProvider,
     },
-    config::{persistence::key_pathfinder::ClientKeyPathfinder, GatewayEndpointConfig},
+    config::{
+        persistence::{encryption::StoragePassphrase, key_pathfinder::ClientKeyPathfinder},
+        GatewayEndpointConfig,
+    },
+    error::ClientCoreError,
 };
 use nym_crypto::asymmetric::identity;
 use nym_sphinx::{
@@ -23,6 +31,7 @@ use nym_task::{
 };
 
 use futures::StreamExt;
+use gateway_requests::registration::handshake::SharedKeys;
 use validator_client::nyxd::SigningNyxdClient;
 
 use crate::{Error, Result};
@@ -38,6 +47,10 @@ pub struct MixnetClientBuilder {
     storage_paths: Option<StoragePaths>,
     keys: Option<Keys>,
     gateway_config: Option<GatewayEndpointConfig>,
+    standby_gateways: Vec<GatewayEndpointConfig>,
+    custom_topology_provider: Option<Box<dyn TopologyProvider>>,
+    persist_received_messages: bool,
+    storage_passphrase: Option<StoragePassphrase>,
 }
 
 impl MixnetClientBuilder {
@@ -70,6 +83,50 @@ impl MixnetClientBuilder {
         self
     }
 
+    /// Gateways the client is going to fail over to, in order, if the connection to its current
+    /// gateway is lost and could not be re-established. Note that the address of the client
+    /// changes whenever that happens, see [`MixnetClient::nym_address`].
+    ///
+    /// If the client has been given [`StoragePaths`], the gateway it fails over to (and the key
+    /// shared with it) is written to them, so it's going to be used on the next start. The standby
+    /// gateways themselves are not stored and have to be provided every time the client is built.
+    #[must_use]
+    pub fn standby_gateways(mut self, standby_gateways: Vec<GatewayEndpointConfig>) -> Self {
+        self.standby_gateways = standby_gateways;
+        self
+    }
+
+    /// Use a custom [`TopologyProvider`] instead of retrieving the network topology from the
+    /// nym-api, for example [`crate::mixnet::HardcodedTopologyProvider`] when running against
+    /// a local testnet. The gateway to register with is then also chosen out of the provided
+    /// topology, so the nym-api is never contacted.
+    #[must_use]
+    pub fn custom_topology_provider(
+        mut self,
+        topology_provider: Box<dyn TopologyProvider>,
+    ) -> Self {
+        self.custom_topology_provider = Some(topology_provider);
+        self
+    }
+
+    /// Persist received messages until they are acknowledged with
+    /// [`MixnetClient::acknowledge_messages`], so that they would be delivered again
+    /// if the client restarts before processing them. Requires the storage to be enabled.
+    #[must_use]
+    pub fn persist_received_messages(mut self) -> Self {
+        self.persist_received_messages = true;
+        self
+    }
+
+    /// Encrypt the private keys and the reply storage with the provided passphrase.
+    /// The same passphrase has to be provided whenever the client is created with the same
+    /// storage paths. Requires a reply storage backend that supports encryption.
+    #[must_use]
+    pub fn storage_passphrase(mut self, passphrase: StoragePassphrase) -> Self {
+        self.storage_passphrase = Some(passphrase);
+        self
+    }
+
     /// Construct a [`DisconnectedMixnetClient`] from the setup specified.
     pub async fn build<B>(self) -> Result<DisconnectedMixnetClient<B>>
     where
@@ -79,12 +136,26 @@ impl MixnetClientBuilder {
         let config = self.config.unwrap_or_default();
         let storage_paths = self.storage_paths;
 
-        let mut client = DisconnectedMixnetClient::new(Some(config), storage_paths).await?;
+        let mut client =
+            DisconnectedMixnetClient::new(Some(config), storage_paths, self.storage_passphrase)
+                .await?;
 
         if let Some(keys) = self.keys {
             client.set_keys(keys);
         }
 
+        if let Some(topology_provider) = self.custom_topology_provider {
+            client.set_topology_provider(topology_provider);
+        }
+
+        if self.persist_received_messages {
+            client.set_persist_received_messages();
+        }
+
+        if !self.standby_gateways.is_empty() {
+            client.set_standby_gateways(self.standby_gateways);
+        }
+
         // If we have a gateway config, we can move the client into a registered state. This will
         // fail if no gateway key is set.
         if let Some(gateway_config) = self.gateway_config {
@@ -118,6 +189,18 @@ where
 
     /// The storage backend for reply-SURBs
     reply_storage_backend: B,
+
+    /// Alternative source of the network topology. If not set, the nym-api is used.
+    custom_topology_provider: Option<Box<dyn TopologyProvider>>,
+
+    /// Whether received messages are persisted until they get acknowledged.
+    persist_received_messages: bool,
+
+    /// Gateways to fail over to if the connection to the current one is lost.
+    standby_gateways: Vec<GatewayEndpointConfig>,
+
+    /// Passphrase used for encrypting the stored private keys and reply storage.
+    storage_passphrase: Option<StoragePassphrase>,
 }
 
 impl<B> DisconnectedMixnetClient<B>
@@ -133,6 +216,7 @@ where
     async fn new(
         config: Option<Config>,
         paths: Option<StoragePaths>,
+        storage_passphrase: Option<StoragePassphrase>,
     ) -> Result<DisconnectedMixnetClient<B>>
     where
         <B as ReplyStorageBackend>::StorageError: Send + Sync,
@@ -141,11 +225,21 @@ where
         let reply_surb_database_path = paths.as_ref().map(|p| p.reply_surb_database_path.clone());
 
         // The reply storage backend is generic, and can be set by the caller/instantiator
-        let reply_storage_backend = B::new(&config.debug_config, reply_surb_database_path)
-            .await
-            .map_err(|err| Error::StorageError {
-                source: Box::new(err),
-            })?;
+        let reply_storage_backend = match &storage_passphrase {
+            Some(passphrase) => {
+                B::new_encrypted(&config.debug_config, reply_surb_database_path, passphrase)
+                    .await
+                    .map_err(|err| Error::StorageError {
+                        source: Box::new(err),
+                    })?
+                    .ok_or(Error::StorageEncryptionUnsupported)?
+            }
+            None => B::new(&config.debug_config, reply_surb_database_path)
+                .await
+                .map_err(|err| Error::StorageError {
+                    source: Box::new(err),
+                })?,
+        };
 
         // If we are provided paths to keys, use them if they are available. And if they are
         // not, write the generated keys back to storage.
@@ -153,7 +247,10 @@ where
             let path_finder = ClientKeyPathfinder::from(paths.clone());
 
             // Try load keys
-            match KeyManager::load_keys_but_gateway_is_optional(&path_finder) {
+            match KeyManager::load_keys_but_gateway_is_optional(
+                &path_finder,
+                storage_passphrase.as_ref(),
+            ) {
                 Ok(key_manager) => {
                     log::debug!("Keys loaded");
                     key_manager
@@ -173,7 +270,7 @@ where
                     // Create new keys and write to storage
                     let key_manager = client_core::init::new_client_keys();
                     // WARN: this will overwrite!
-                    key_manager.store_keys(&path_finder)?;
+                    key_manager.store_keys(&path_finder, storage_passphrase.as_ref())?;
                     key_manager
                 }
             }
@@ -189,6 +286,10 @@ where
             storage_paths: paths,
             state: BuilderState::New,
             reply_storage_backend,
+            custom_topology_provider: None,
+            persist_received_messages: false,
+            standby_gateways: Vec::new(),
+            storage_passphrase,
         })
     }
 
@@ -210,6 +311,22 @@ where
             .insert_gateway_shared_key(Arc::new(keys.gateway_shared_key));
     }
 
+    /// Sets the topology provider of this [`DisconnectedMixnetClient<B>`].
+    fn set_topology_provider(&mut self, topology_provider: Box<dyn TopologyProvider>) {
+        self.custom_topology_provider = Some(topology_provider);
+    }
+
+    /// Makes this [`DisconnectedMixnetClient<B>`] persist received messages until they get
+    /// acknowledged.
+    fn set_persist_received_messages(&mut self) {
+        self.persist_received_messages = true;
+    }
+
+    /// Sets the gateways this [`DisconnectedMixnetClient<B>`] is going to fail over to.
+    fn set_standby_gateways(&mut self, standby_gateways: Vec<GatewayEndpointConfig>) {
+        self.standby_gateways = standby_gateways;
+    }
+
     /// Returns the keys of this [`DisconnectedMixnetClient<B>`]. Client keys are always available
     /// since if none are specified at creation time, new random ones are generated.
     pub fn get_keys(&self) -> KeysArc {
@@ -238,6 +355,9 @@ where
     /// Register with a gateway. If a gateway is provided in the config then that will try to be
     /// used. If none is specified, a gateway at random will be picked.
     ///
+    /// If a custom [`TopologyProvider`] has been set, the gateway is chosen out of the topology
+    /// it provides rather than the one obtained from the nym-api.
+    ///
     /// # Errors
     ///
     /// This function will return an error if you try to re-register when in an already registered
@@ -255,12 +375,33 @@ where
             .map(identity::PublicKey::from_base58_string)
             .transpose()?;
 
-        let gateway_config = client_core::init::register_with_gateway(
-            &mut self.key_manager,
-            self.config.nym_api_endpoints.clone(),
-            user_chosen_gateway,
-        )
-        .await?;
+        // if we were given a custom topology, choose the gateway out of it rather than from
+        // the list obtained from the nym-api
+        let custom_topology = match self.custom_topology_provider.as_mut() {
+            Some(provider) => Some(
+                provider
+                    .get_new_topology()
+                    .await
+                    .ok_or(Error::CustomTopologyUnavailable)?,
+            ),
+            None => None,
+        };
+
+        let gateway_config = if let Some(topology) = custom_topology {
+            client_core::init::register_with_gateway_from_topology(
+                &mut self.key_manager,
+                &topology,
+                user_chosen_gateway,
+            )
+            .await?
+        } else {
+            client_core::init::register_with_gateway(
+                &mut self.key_manager,
+                self.config.nym_api_endpoints.clone(),
+                user_chosen_gateway,
+            )
+            .await?
+        };
 
         self.state = BuilderState::Registered {
             gateway_endpoint_config: gateway_config,
@@ -280,21 +421,16 @@ where
                 path_finder.gateway_shared_key().to_path_buf(),
             ));
         };
-        self.key_manager.store_gateway_key(&path_finder)?;
+        self.key_manager
+            .store_gateway_key(&path_finder, self.storage_passphrase.as_ref())?;
         Ok(())
     }
 
     fn write_gateway_endpoint_config(&self, gateway_endpoint_config_path: &Path) -> Result<()> {
-        let gateway_endpoint_config = toml::to_string(
-            self.get_gateway_endpoint()
-                .ok_or(Error::GatewayNotAvailableForWriting)?,
-        )?;
-
-        // Ensure the whole directory structure exists
-        if let Some(parent_dir) = gateway_endpoint_config_path.parent() {
-            std::fs::create_dir_all(parent_dir)?;
-        }
-        std::fs::write(gateway_endpoint_config_path, gateway_endpoint_config)?;
+        let gateway_endpoint_config = self
+            .get_gateway_endpoint()
+            .ok_or(Error::GatewayNotAvailableForWriting)?;
+        write_gateway_endpoint_config(gateway_endpoint_config, gateway_endpoint_config_path)?;
         Ok(())
     }
 
@@ -365,17 +501,17 @@ where
 
         // At this point we should be in a registered state, either at function entry or by the
         // above convenience logic.
-        let BuilderState::Registered { gateway_endpoint_config } = self.state else {
+        let BuilderState::Registered {
+            gateway_endpoint_config,
+        } = self.state
+        else {
             return Err(Error::FailedToTransitionToRegisteredState);
         };
 
-        let nym_address =
-            client_core::init::get_client_address(&self.key_manager, &gateway_endpoint_config);
-
         // TODO: we currently don't support having a bandwidth controller
         let bandwidth_controller = None;
 
-        let base_builder: BaseClientBuilder<'_, _, SigningNyxdClient> = BaseClientBuilder::new(
+        let mut base_builder: BaseClientBuilder<'_, _, SigningNyxdClient> = BaseClientBuilder::new(
             &gateway_endpoint_config,
             &self.config.debug_config,
             self.key_manager.clone(),
@@ -385,6 +521,35 @@ where
             self.config.nym_api_endpoints.clone(),
         );
 
+        if let Some(topology_provider) = self.custom_topology_provider {
+            base_builder = base_builder.with_topology_provider(topology_provider);
+        }
+
+        if !self.standby_gateways.is_empty() {
+            base_builder = base_builder.with_standby_gateways(self.standby_gateways);
+            if let Some(paths) = &self.storage_paths {
+                base_builder =
+                    base_builder.with_failover_store(Box::new(StoragePathsFailoverStore {
+                        key_manager: self.key_manager.clone(),
+                        paths: paths.clone(),
+                        storage_passphrase: self.storage_passphrase.clone(),
+                    }));
+            }
+        }
+
+        if self.persist_received_messages {
+            let paths = self
+                .storage_paths
+                .as_ref()
+                .ok_or(Error::ReceivedMessagesPersistenceWithoutStorage)?;
+            let received_messages_store =
+                fs_backend::Backend::init(&paths.received_messages_database_path)
+                    .await
+                    .map_err(ClientCoreError::from)?;
+            base_builder =
+                base_builder.with_received_messages_store(Box::new(received_messages_store));
+        }
+
         let mut started_client = base_builder.start_base().await?;
         let client_input = started_client.client_input.register_producer();
         let mut client_output = started_client.client_output.register_consumer();
@@ -394,7 +559,7 @@ where
         let reconstructed_receiver = client_output.register_receiver()?;
 
         Ok(MixnetClient {
-            nym_address,
+            nym_address: client_state.self_address.clone(),
             key_manager: self.key_manager,
             client_input,
             client_output,
@@ -431,8 +596,9 @@ impl IncludedSurbs {
 
 /// Client connected to the Nym mixnet.
 pub struct MixnetClient {
-    /// The nym address of this connected client.
-    nym_address: Recipient,
+    /// The nym address of this connected client. It changes whenever the client fails over to
+    /// one of its standby gateways.
+    nym_address: SelfAddress,
 
     /// Keys handled by the client
     key_manager: KeyManager,
@@ -443,7 +609,6 @@ pub struct MixnetClient {
 
     /// Output from the client from the users perspective. This is typically messages arriving from
     /// the mixnet.
-    #[allow(dead_code)]
     client_output: ClientOutput,
 
     /// The current state of the client that is exposed to the user. This includes things like
@@ -487,8 +652,17 @@ impl MixnetClient {
 
     /// Get the nym address for this client, if it is available. The nym address is composed of the
     /// client identity, the client encryption key, and the gateway identity.
-    pub fn nym_address(&self) -> &Recipient {
-        &self.nym_address
+    ///
+    /// Note that the address changes whenever the client fails over to one of its standby
+    /// gateways, so it should be retrieved again rather than cached.
+    pub fn nym_address(&self) -> Recipient {
+        self.nym_address.current()
+    }
+
+    /// Get a shallow clone of the always up to date [`SelfAddress`] of this client. This is
+    /// useful for components that have to keep embedding the current address of the client.
+    pub fn self_address(&self) -> SelfAddress {
+        self.nym_address.clone()
     }
 
     /// Get a shallow clone of [`MixnetClientSender`]. Useful if you want split the send and
@@ -512,6 +686,19 @@ impl MixnetClient {
         self.client_state.shared_lane_queue_lengths.clone()
     }
 
+    /// Get a shallow clone of [`PacketPreparationMetrics`]. This is useful for checking whether
+    /// the client manages to construct its packets as fast as it is supposed to be sending them.
+    pub fn packet_preparation_metrics(&self) -> PacketPreparationMetrics {
+        self.client_state.packet_preparation_metrics.clone()
+    }
+
+    /// Get a shallow clone of [`ClientStatistics`]. This is useful for diagnosing delivery problems
+    /// as it keeps track of the packets sent to and received from each destination, their
+    /// retransmissions, acknowledgement round trip times and reply SURB usage.
+    pub fn statistics(&self) -> ClientStatistics {
+        self.client_state.statistics.clone()
+    }
+
     /// Sends stringy data to the supplied Nym address
     ///
     /// # Example
@@ -597,6 +784,15 @@ impl MixnetClient {
         }
     }
 
+    /// Confirm that the messages with the provided delivery ids have been processed, so that they
+    /// are not going to be delivered again. Only relevant if the client has been built with
+    /// [`MixnetClientBuilder::persist_received_messages`], in which case all received messages
+    /// have their `delivery_id` set.
+    pub fn acknowledge_messages(&self, delivery_ids: Vec<u64>) -> Result<()> {
+        self.client_output.acknowledge_messages(delivery_ids)?;
+        Ok(())
+    }
+
     /// Disconnect from the mixnet. Currently it is not supported to reconnect a disconnected
     /// client.
     pub async fn disconnect(&mut self) {
@@ -605,6 +801,44 @@ impl MixnetClient {
     }
 }
 
+fn write_gateway_endpoint_config(
+    gateway_endpoint_config: &GatewayEndpointConfig,
+    gateway_endpoint_config_path: &Path,
+) -> io::Result<()> {
+    let gateway_endpoint_config = toml::to_string(gateway_endpoint_config)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    // Ensure the whole directory structure exists
+    if let Some(parent_dir) = gateway_endpoint_config_path.parent() {
+        std::fs::create_dir_all(parent_dir)?;
+    }
+    std::fs::write(gateway_endpoint_config_path, gateway_endpoint_config)
+}
+
+/// Writes the gateway the client has failed over to, and the key shared with it, to the storage
+/// paths so that the client would reconnect to it after a restart.
+struct StoragePathsFailoverStore {
+    key_manager: KeyManager,
+    paths: StoragePaths,
+    storage_passphrase: Option<StoragePassphrase>,
+}
+
+impl FailoverStore for StoragePathsFailoverStore {
+    fn store_failover(
+        &mut self,
+        current_gateway: &GatewayEndpointConfig,
+        _standby_gateways: &[GatewayEndpointConfig],
+        shared_key: Arc<SharedKeys>,
+    ) -> io::Result<()> {
+        self.key_manager.insert_gateway_shared_key(shared_key);
+        self.key_manager.store_gateway_key(
+            &ClientKeyPathfinder::from(self.paths.clone()),
+            self.storage_passphrase.as_ref(),
+        )?;
+        write_gateway_endpoint_config(current_gateway, &self.paths.gateway_endpoint_config)
+    }
+}
+
 pub struct MixnetClientSender {
     client_input: ClientInput,
 }
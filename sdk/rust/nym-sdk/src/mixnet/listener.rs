@@ -0,0 +1,309 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::channel::{mpsc, oneshot};
+use futures::StreamExt;
+use log::{debug, trace, warn};
+use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::receiver::ReconstructedMessage;
+use nym_task::connections::{ConnectionId, TransmissionLane};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use super::stream::{MixnetStream, StreamFrame, StreamPacket, StreamRemote};
+use super::{InputMessage, MixnetClient, MixnetClientSender, SelfAddress};
+use crate::{Error, Result};
+
+/// Number of reply SURBs attached to every packet sent on a stream we have opened, so that the
+/// remote could keep on responding to us.
+const DEFAULT_STREAM_REPLY_SURBS: u32 = 10;
+
+/// Maximum number of frames buffered for a stream that is not being read. Once it's exceeded the
+/// stream gets reset, as dropping any of its frames would have left a permanent gap in the data.
+pub(crate) const STREAM_BUFFER_SIZE: usize = 512;
+
+/// For how long the packets received for a closed stream are ignored rather than being treated
+/// as opening a new one. It only has to outlive any packets that were still in the mixnet.
+const CLOSED_STREAM_RETENTION: Duration = Duration::from_secs(10 * 60);
+
+/// How often we forget about the streams that were closed for longer than [`CLOSED_STREAM_RETENTION`].
+const CLOSED_STREAMS_PRUNING_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(crate) enum RouterCommand {
+    /// Start routing packets with the specified connection id to the provided channel.
+    Register {
+        connection_id: ConnectionId,
+        incoming: mpsc::Sender<StreamFrame>,
+    },
+
+    /// Send the serialized stream packet to the remote.
+    Send {
+        remote: StreamRemote,
+        connection_id: ConnectionId,
+        packet: Vec<u8>,
+    },
+
+    /// The stream has been dropped and any further packets on it should be ignored.
+    Deregister(ConnectionId),
+}
+
+/// Accepts [`MixnetStream`]s opened by other clients and opens new ones.
+///
+/// It takes ownership of the [`MixnetClient`] and from then on, all the messages it receives are
+/// interpreted as stream packets and routed to the relevant streams.
+///
+/// # Example
+///
+/// ```no_run
+/// use nym_sdk::mixnet;
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = mixnet::MixnetClient::connect_new().await.unwrap();
+///     let mut listener = mixnet::MixnetListener::new(client);
+///     let our_address = listener.nym_address();
+///
+///     let mut outbound = listener.connect(our_address).unwrap();
+///     outbound.write_all(b"hello there").await.unwrap();
+///
+///     let mut inbound = listener.accept().await.unwrap();
+///     let mut buf = [0u8; 11];
+///     inbound.read_exact(&mut buf).await.unwrap();
+///
+///     listener.disconnect().await;
+/// }
+/// ```
+pub struct MixnetListener {
+    nym_address: SelfAddress,
+    router: mpsc::UnboundedSender<RouterCommand>,
+    accepted: mpsc::UnboundedReceiver<MixnetStream>,
+    router_shutdown: Option<oneshot::Sender<()>>,
+    router_handle: JoinHandle<MixnetClient>,
+}
+
+impl MixnetListener {
+    /// Start routing the messages received by the client to the streams.
+    pub fn new(client: MixnetClient) -> Self {
+        let nym_address = client.self_address();
+        let (router_sender, router_receiver) = mpsc::unbounded();
+        let (accepted_sender, accepted_receiver) = mpsc::unbounded();
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+
+        let router = StreamRouter {
+            client_sender: client.sender(),
+            client,
+            commands: router_receiver,
+            commands_sender: router_sender.clone(),
+            accepted: accepted_sender,
+            streams: HashMap::new(),
+            closed_streams: HashMap::new(),
+        };
+
+        MixnetListener {
+            nym_address,
+            router: router_sender,
+            accepted: accepted_receiver,
+            router_shutdown: Some(shutdown_sender),
+            router_handle: tokio::spawn(router.run(shutdown_receiver)),
+        }
+    }
+
+    /// Get the nym address of the underlying client.
+    pub fn nym_address(&self) -> Recipient {
+        self.nym_address.current()
+    }
+
+    /// Wait for the next stream opened by a remote client.
+    /// Returns `None` if the listener has been shut down.
+    pub async fn accept(&mut self) -> Option<MixnetStream> {
+        self.accepted.next().await
+    }
+
+    /// Open a new stream to the provided recipient. The recipient will only learn about the stream
+    /// once the corresponding packet gets through the mixnet, so this method does not wait for it.
+    pub fn connect(&self, recipient: Recipient) -> Result<MixnetStream> {
+        let connection_id = rand::random();
+        let (incoming_sender, incoming_receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+
+        self.router
+            .unbounded_send(RouterCommand::Register {
+                connection_id,
+                incoming: incoming_sender,
+            })
+            .map_err(|_| Error::MixnetListenerShutdown)?;
+
+        let remote = StreamRemote::Recipient(recipient);
+        let open = StreamPacket {
+            connection_id,
+            frame: StreamFrame::Open,
+        };
+        self.router
+            .unbounded_send(RouterCommand::Send {
+                remote,
+                connection_id,
+                packet: open.into_bytes(),
+            })
+            .map_err(|_| Error::MixnetListenerShutdown)?;
+
+        Ok(MixnetStream::new(
+            connection_id,
+            remote,
+            self.router.clone(),
+            incoming_receiver,
+        ))
+    }
+
+    /// Stop routing the stream packets and disconnect the underlying client from the mixnet.
+    pub async fn disconnect(mut self) {
+        if let Some(shutdown) = self.router_shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        match self.router_handle.await {
+            Ok(mut client) => client.disconnect().await,
+            Err(err) => warn!("the stream router has failed - {err}"),
+        }
+    }
+}
+
+struct StreamRouter {
+    client: MixnetClient,
+    client_sender: MixnetClientSender,
+
+    commands: mpsc::UnboundedReceiver<RouterCommand>,
+    // handed over to the streams opened by remote clients
+    commands_sender: mpsc::UnboundedSender<RouterCommand>,
+
+    accepted: mpsc::UnboundedSender<MixnetStream>,
+    streams: HashMap<ConnectionId, mpsc::Sender<StreamFrame>>,
+    closed_streams: HashMap<ConnectionId, Instant>,
+}
+
+impl StreamRouter {
+    fn close_stream(&mut self, connection_id: ConnectionId) {
+        self.streams.remove(&connection_id);
+        self.closed_streams.insert(connection_id, Instant::now());
+    }
+
+    fn prune_closed_streams(&mut self) {
+        let now = Instant::now();
+        self.closed_streams
+            .retain(|_, closed_at| now.duration_since(*closed_at) < CLOSED_STREAM_RETENTION);
+    }
+
+    fn on_reconstructed_message(&mut self, message: ReconstructedMessage) {
+        let packet = match StreamPacket::try_from_bytes(&message.message) {
+            Ok(packet) => packet,
+            Err(err) => {
+                debug!("received a message that is not a valid stream packet - {err}");
+                return;
+            }
+        };
+        let connection_id = packet.connection_id;
+
+        if self.closed_streams.contains_key(&connection_id) {
+            trace!("received a packet for an already closed stream {connection_id}");
+            return;
+        }
+
+        if !self.streams.contains_key(&connection_id) {
+            // packets can get out of the mixnet in any order, so whatever we receive first
+            // for an unknown connection opens the stream
+            let sender_tag = match message.sender_tag {
+                Some(sender_tag) => sender_tag,
+                None => {
+                    warn!("received a packet for an unknown stream {connection_id} without any reply SURBs");
+                    return;
+                }
+            };
+
+            let (incoming_sender, incoming_receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+            self.streams.insert(connection_id, incoming_sender);
+
+            let stream = MixnetStream::new(
+                connection_id,
+                StreamRemote::SenderTag(sender_tag),
+                self.commands_sender.clone(),
+                incoming_receiver,
+            );
+            if self.accepted.unbounded_send(stream).is_err() {
+                debug!("the listener is no longer accepting new streams");
+            }
+        }
+
+        if let Some(stream) = self.streams.get_mut(&connection_id) {
+            if let Err(err) = stream.try_send(packet.frame) {
+                if err.is_full() {
+                    warn!("stream {connection_id} is not being read - resetting it");
+                } else {
+                    trace!("stream {connection_id} is no longer receiving data");
+                }
+                self.close_stream(connection_id);
+            }
+        }
+    }
+
+    async fn on_command(&mut self, command: RouterCommand) {
+        match command {
+            RouterCommand::Register {
+                connection_id,
+                incoming,
+            } => {
+                self.streams.insert(connection_id, incoming);
+            }
+            RouterCommand::Send {
+                remote,
+                connection_id,
+                packet,
+            } => {
+                let lane = TransmissionLane::ConnectionId(connection_id);
+                let input_message = match remote {
+                    StreamRemote::Recipient(recipient) => InputMessage::new_anonymous(
+                        recipient,
+                        packet,
+                        DEFAULT_STREAM_REPLY_SURBS,
+                        lane,
+                    ),
+                    StreamRemote::SenderTag(sender_tag) => {
+                        InputMessage::new_reply(sender_tag, packet, lane)
+                    }
+                };
+                self.client_sender.send_input_message(input_message).await;
+            }
+            RouterCommand::Deregister(connection_id) => self.close_stream(connection_id),
+        }
+    }
+
+    async fn run(mut self, mut shutdown: oneshot::Receiver<()>) -> MixnetClient {
+        let mut pruning_interval = tokio::time::interval(CLOSED_STREAMS_PRUNING_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    trace!("StreamRouter: received shutdown");
+                    break;
+                }
+                _ = pruning_interval.tick() => self.prune_closed_streams(),
+                messages = self.client.wait_for_messages() => match messages {
+                    Some(messages) => {
+                        for message in messages {
+                            self.on_reconstructed_message(message)
+                        }
+                    }
+                    None => {
+                        debug!("the mixnet client has stopped receiving messages");
+                        break;
+                    }
+                },
+                command = self.commands.next() => match command {
+                    Some(command) => self.on_command(command).await,
+                    // can't happen as we're holding a sender ourselves
+                    None => break,
+                }
+            }
+        }
+
+        // dropping the stream channels will make any further reads on them fail with `ConnectionReset`
+        self.client
+    }
+}
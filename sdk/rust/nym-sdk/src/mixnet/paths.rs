@@ -61,6 +61,9 @@ pub struct StoragePaths {
 
     /// The database storing reply surbs in-between sessions
     pub reply_surb_database_path: PathBuf,
+
+    /// The database storing received messages until they get acknowledged
+    pub received_messages_database_path: PathBuf,
 }
 
 impl StoragePaths {
@@ -88,6 +91,7 @@ impl StoragePaths {
             gateway_endpoint_config: dir.join("gateway_endpoint_config.toml"),
             credential_database_path: dir.join("db.sqlite"),
             reply_surb_database_path: dir.join("persistent_reply_store.sqlite"),
+            received_messages_database_path: dir.join("received_messages_store.sqlite"),
         })
     }
 }
@@ -118,6 +122,7 @@ impl<T> From<&client_core::config::Config<T>> for StoragePaths {
             gateway_endpoint_config: Default::default(),
             credential_database_path: value.get_database_path(),
             reply_surb_database_path: value.get_reply_surb_database_path(),
+            received_messages_database_path: value.get_received_messages_database_path(),
         }
     }
 }
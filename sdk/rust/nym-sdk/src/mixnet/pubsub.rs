@@ -0,0 +1,166 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use log::{debug, warn};
+use nym_crypto::asymmetric::identity;
+use nym_sphinx::addressing::clients::Recipient;
+use pubsub_requests::{
+    PubSubProviderRequest, PubSubProviderResponse, PubSubRequest, PubSubResponseContent, Topic,
+};
+use service_providers_common::interface::{ProviderInterfaceVersion, ResponseContent};
+use std::collections::VecDeque;
+
+use super::{IncludedSurbs, MixnetClient};
+
+/// Number of reply SURBs attached to every subscription request. Once the provider runs low on
+/// them, its `ReplyController` is going to request more of them from us.
+const SUBSCRIBE_REPLY_SURBS: u32 = 10;
+
+/// Number of reply SURBs attached to all other requests, which are answered with a single reply.
+const REQUEST_REPLY_SURBS: u32 = 1;
+
+/// Client of the pub/sub service provider. Subscriptions are registered anonymously: the provider
+/// only ever learns our sender tag and delivers the published messages using our reply SURBs.
+///
+/// Note that the provider expires subscriptions that have not been renewed for a while, so
+/// long-lived subscribers should periodically call [`PubSubClient::subscribe`] again.
+///
+/// # Example
+///
+/// ```no_run
+/// use nym_sdk::mixnet;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let provider = mixnet::Recipient::try_from_base58_string("foobar").unwrap();
+///     let client = mixnet::MixnetClient::connect_new().await.unwrap();
+///     let mut pubsub = mixnet::PubSubClient::new(client, provider);
+///
+///     let topic: mixnet::Topic = "news".parse().unwrap();
+///     pubsub.subscribe(topic.clone()).await;
+///     pubsub.publish(topic, b"hello there".to_vec()).await;
+///
+///     while let Some(event) = pubsub.next_event().await {
+///         println!("{event:?}");
+///     }
+/// }
+/// ```
+pub struct PubSubClient {
+    client: MixnetClient,
+    provider: Recipient,
+
+    // events that have been received alongside the one returned by `next_event`
+    pending_events: VecDeque<PubSubResponseContent>,
+}
+
+impl PubSubClient {
+    /// Creates a pub/sub client talking to the provider at the given address. From now on all the
+    /// messages received by the mixnet client are treated as pub/sub responses.
+    pub fn new(client: MixnetClient, provider: Recipient) -> Self {
+        PubSubClient {
+            client,
+            provider,
+            pending_events: VecDeque::new(),
+        }
+    }
+
+    /// Get the nym address of the underlying mixnet client.
+    pub fn nym_address(&self) -> Recipient {
+        self.client.nym_address()
+    }
+
+    /// Get the address of the pub/sub provider.
+    pub fn provider(&self) -> &Recipient {
+        &self.provider
+    }
+
+    async fn send_request(&self, request: PubSubRequest, reply_surbs: u32) {
+        let request = PubSubProviderRequest::new_provider_data(
+            ProviderInterfaceVersion::new_current(),
+            request,
+        );
+        self.client
+            .send_bytes(
+                self.provider,
+                request.into_bytes(),
+                IncludedSurbs::new(reply_surbs),
+            )
+            .await
+    }
+
+    /// Subscribes to (or renews the subscription of) the topic. The provider confirms it with
+    /// [`PubSubResponseContent::Subscribed`].
+    pub async fn subscribe(&self, topic: Topic) {
+        self.send_request(PubSubRequest::new_subscribe(topic), SUBSCRIBE_REPLY_SURBS)
+            .await
+    }
+
+    /// Cancels the subscription of the topic. The provider confirms it with
+    /// [`PubSubResponseContent::Unsubscribed`].
+    pub async fn unsubscribe(&self, topic: Topic) {
+        self.send_request(PubSubRequest::new_unsubscribe(topic), REQUEST_REPLY_SURBS)
+            .await
+    }
+
+    /// Publishes the payload to all subscribers of the topic. The provider responds with
+    /// [`PubSubResponseContent::Published`] containing the number of subscribers that the
+    /// message has been forwarded to.
+    pub async fn publish(&self, topic: Topic, payload: Vec<u8>) {
+        self.send_request(
+            PubSubRequest::new_publish(topic, payload),
+            REQUEST_REPLY_SURBS,
+        )
+        .await
+    }
+
+    /// Publishes the payload signed with the provided identity keys. This is required for topics
+    /// the provider has restricted to a set of publishers, in which case the public key has to be
+    /// on the provider's list of publishers of that topic.
+    pub async fn publish_signed(
+        &self,
+        topic: Topic,
+        payload: Vec<u8>,
+        publisher: &identity::KeyPair,
+    ) {
+        self.send_request(
+            PubSubRequest::new_signed_publish(topic, payload, publisher),
+            REQUEST_REPLY_SURBS,
+        )
+        .await
+    }
+
+    /// Waits for the next message or response from the provider. Returns `None` once the
+    /// underlying mixnet client has shut down.
+    pub async fn next_event(&mut self) -> Option<PubSubResponseContent> {
+        loop {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Some(event);
+            }
+
+            for reconstructed in self.client.wait_for_messages().await? {
+                let response = match PubSubProviderResponse::try_from_bytes(&reconstructed.message)
+                {
+                    Ok(response) => response,
+                    Err(err) => {
+                        warn!("failed to deserialize received pub/sub response: {err}");
+                        continue;
+                    }
+                };
+
+                match response.content {
+                    ResponseContent::ProviderData(response) => {
+                        self.pending_events.push_back(response.content)
+                    }
+                    ResponseContent::Control(control) => {
+                        debug!("ignoring control response from the pub/sub provider: {control:?}")
+                    }
+                }
+            }
+        }
+    }
+
+    /// Disconnect the underlying mixnet client.
+    pub async fn disconnect(mut self) {
+        self.client.disconnect().await
+    }
+}
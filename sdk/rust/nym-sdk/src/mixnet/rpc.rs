@@ -0,0 +1,935 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::channel::{mpsc, oneshot};
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use log::{debug, trace, warn};
+use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+use nym_sphinx::receiver::ReconstructedMessage;
+use nym_task::connections::TransmissionLane;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use super::{InputMessage, MixnetClient, MixnetClientSender, SelfAddress};
+use crate::{Error, Result};
+
+const REQUEST_FRAME: u8 = 0;
+const RESPONSE_FRAME: u8 = 1;
+const ERROR_FRAME: u8 = 2;
+
+const PACKET_HEADER_SIZE: usize = 1 + std::mem::size_of::<RpcRequestId>();
+
+/// The length of the method name is encoded with 2 bytes.
+const MAX_METHOD_LEN: usize = u16::MAX as usize;
+
+/// Number of reply SURBs attached to every request by default. If the response does not fit in
+/// them, the `ReplyController` of the service is going to request more of them from us.
+const DEFAULT_RPC_REPLY_SURBS: u32 = 10;
+
+/// Number of responses the service remembers, so that retransmitted requests are answered
+/// again rather than handled for the second time.
+const RESPONSE_CACHE_SIZE: usize = 1024;
+
+/// Number of requests the service handles at the same time by default. Any requests above
+/// that are rejected until some of the handlers finish.
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 128;
+
+/// Time the handler is given to produce the response by default.
+const DEFAULT_HANDLER_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub type RpcRequestId = u64;
+
+/// Result of handling an RPC request. The error message is passed back to the caller.
+pub type RpcResult = std::result::Result<Vec<u8>, String>;
+
+type RpcHandler = Arc<dyn Fn(Vec<u8>) -> BoxFuture<'static, RpcResult> + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RpcPacketError {
+    #[error(
+        "received packet was too short. Got {received} bytes, but expected at least {expected}"
+    )]
+    TooShort { received: usize, expected: usize },
+
+    #[error("received packet had unknown frame type {0}")]
+    UnknownFrameType(u8),
+
+    #[error("received request had malformed method name")]
+    MalformedMethod,
+
+    #[error(
+        "the method name is {length} bytes long while at most {MAX_METHOD_LEN} bytes are allowed"
+    )]
+    MethodTooLong { length: usize },
+}
+
+/// Unit of data exchanged between the RPC caller and the service.
+/// The serialized format is:
+/// | 1 byte frame type | 8 bytes request id | frame content |
+/// where the request content is | 2 bytes method length | method | payload |,
+/// the response content is the payload and the error content is the utf8 encoded message.
+#[derive(Debug)]
+pub(crate) enum RpcPacket {
+    Request {
+        id: RpcRequestId,
+        method: String,
+        payload: Vec<u8>,
+    },
+    Response {
+        id: RpcRequestId,
+        payload: Vec<u8>,
+    },
+    Error {
+        id: RpcRequestId,
+        message: String,
+    },
+}
+
+impl RpcPacket {
+    pub(crate) fn into_bytes(self) -> Result<Vec<u8>, RpcPacketError> {
+        let (frame_type, id, content) = match self {
+            RpcPacket::Request {
+                id,
+                method,
+                payload,
+            } => {
+                let method_len =
+                    u16::try_from(method.len()).map_err(|_| RpcPacketError::MethodTooLong {
+                        length: method.len(),
+                    })?;
+                let content = method_len
+                    .to_be_bytes()
+                    .into_iter()
+                    .chain(method.into_bytes())
+                    .chain(payload)
+                    .collect();
+                (REQUEST_FRAME, id, content)
+            }
+            RpcPacket::Response { id, payload } => (RESPONSE_FRAME, id, payload),
+            RpcPacket::Error { id, message } => (ERROR_FRAME, id, message.into_bytes()),
+        };
+
+        Ok(std::iter::once(frame_type)
+            .chain(id.to_be_bytes())
+            .chain(content)
+            .collect())
+    }
+
+    pub(crate) fn try_from_bytes(bytes: &[u8]) -> Result<Self, RpcPacketError> {
+        if bytes.len() < PACKET_HEADER_SIZE {
+            return Err(RpcPacketError::TooShort {
+                received: bytes.len(),
+                expected: PACKET_HEADER_SIZE,
+            });
+        }
+
+        let mut id_bytes = [0u8; 8];
+        id_bytes.copy_from_slice(&bytes[1..PACKET_HEADER_SIZE]);
+        let id = RpcRequestId::from_be_bytes(id_bytes);
+        let content = &bytes[PACKET_HEADER_SIZE..];
+
+        match bytes[0] {
+            REQUEST_FRAME => {
+                if content.len() < 2 {
+                    return Err(RpcPacketError::TooShort {
+                        received: bytes.len(),
+                        expected: PACKET_HEADER_SIZE + 2,
+                    });
+                }
+                let method_len = u16::from_be_bytes([content[0], content[1]]) as usize;
+                if content.len() < 2 + method_len {
+                    return Err(RpcPacketError::TooShort {
+                        received: bytes.len(),
+                        expected: PACKET_HEADER_SIZE + 2 + method_len,
+                    });
+                }
+                let method = String::from_utf8(content[2..2 + method_len].to_vec())
+                    .map_err(|_| RpcPacketError::MalformedMethod)?;
+
+                Ok(RpcPacket::Request {
+                    id,
+                    method,
+                    payload: content[2 + method_len..].to_vec(),
+                })
+            }
+            RESPONSE_FRAME => Ok(RpcPacket::Response {
+                id,
+                payload: content.to_vec(),
+            }),
+            ERROR_FRAME => Ok(RpcPacket::Error {
+                id,
+                message: String::from_utf8_lossy(content).into_owned(),
+            }),
+            other => Err(RpcPacketError::UnknownFrameType(other)),
+        }
+    }
+}
+
+/// Configuration of the requests sent and served by [`MixnetRpc`].
+#[derive(Debug, Clone, Copy)]
+pub struct RpcConfig {
+    /// Number of reply SURBs attached to every request. If the response requires more of them,
+    /// the service is going to automatically request them from us.
+    pub reply_surbs: u32,
+
+    /// Number of times the request is sent before giving up. Every attempt waits for the
+    /// full timeout before the request is sent again.
+    pub max_attempts: u32,
+
+    /// Maximum number of requests being handled at the same time. Any further requests are
+    /// answered with an error until some of the handlers finish.
+    pub max_concurrent_requests: usize,
+
+    /// Time the handler is given to produce the response before the caller gets an error instead.
+    pub handler_timeout: Duration,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        RpcConfig {
+            reply_surbs: DEFAULT_RPC_REPLY_SURBS,
+            max_attempts: 1,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            handler_timeout: DEFAULT_HANDLER_TIMEOUT,
+        }
+    }
+}
+
+enum RpcCommand {
+    /// Start serving requests for the provided method.
+    RegisterHandler { method: String, handler: RpcHandler },
+
+    /// Route the response to the request with the specified id to the provided channel.
+    RegisterRequest {
+        id: RpcRequestId,
+        response: oneshot::Sender<Result<Vec<u8>>>,
+    },
+
+    /// The caller is no longer waiting for the response.
+    CancelRequest(RpcRequestId),
+
+    /// Send the serialized request to the service.
+    SendRequest {
+        recipient: Recipient,
+        packet: Vec<u8>,
+    },
+
+    /// The handler has finished processing the request and its response should be sent back.
+    HandlerFinished {
+        sender_tag: AnonymousSenderTag,
+        id: RpcRequestId,
+        response: RpcResult,
+    },
+}
+
+// makes sure the router forgets about the request if the caller stops waiting for it
+struct PendingRequest {
+    id: RpcRequestId,
+    router: mpsc::UnboundedSender<RpcCommand>,
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        self.router
+            .unbounded_send(RpcCommand::CancelRequest(self.id))
+            .ok();
+    }
+}
+
+/// Request/response layer built on top of reply SURBs, so that the service never learns
+/// the address of the caller.
+///
+/// It takes ownership of the [`MixnetClient`] and from then on, all the messages it receives are
+/// interpreted as RPC packets. The same instance can both serve requests, via the registered
+/// handlers, and make requests to other services.
+///
+/// # Example
+///
+/// ```no_run
+/// use nym_sdk::mixnet;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = mixnet::MixnetClient::connect_new().await.unwrap();
+///     let rpc = mixnet::MixnetRpc::new(client);
+///     rpc.register_handler("echo", |payload| async move { Ok(payload) })
+///         .unwrap();
+///
+///     let response = rpc
+///         .request(rpc.nym_address(), "echo", b"hello".to_vec(), Duration::from_secs(30))
+///         .await
+///         .unwrap();
+///     assert_eq!(response, b"hello");
+///
+///     rpc.disconnect().await;
+/// }
+/// ```
+pub struct MixnetRpc {
+    nym_address: SelfAddress,
+    config: RpcConfig,
+    router: mpsc::UnboundedSender<RpcCommand>,
+    router_shutdown: Option<oneshot::Sender<()>>,
+    router_handle: JoinHandle<MixnetClient>,
+}
+
+impl MixnetRpc {
+    /// Start routing the messages received by the client to the RPC handlers and callers.
+    pub fn new(client: MixnetClient) -> Self {
+        Self::new_with_config(client, RpcConfig::default())
+    }
+
+    pub fn new_with_config(client: MixnetClient, config: RpcConfig) -> Self {
+        let nym_address = client.self_address();
+        let (router_sender, router_receiver) = mpsc::unbounded();
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+
+        let router = RpcRouter {
+            client_sender: client.sender(),
+            client,
+            reply_surbs: config.reply_surbs,
+            handler_timeout: config.handler_timeout,
+            commands: router_receiver,
+            commands_sender: router_sender.clone(),
+            handlers: HashMap::new(),
+            pending_requests: HashMap::new(),
+            served_requests: ServedRequests::new(
+                config.max_concurrent_requests,
+                config.handler_timeout,
+            ),
+        };
+
+        MixnetRpc {
+            nym_address,
+            config,
+            router: router_sender,
+            router_shutdown: Some(shutdown_sender),
+            router_handle: tokio::spawn(router.run(shutdown_receiver)),
+        }
+    }
+
+    /// Get the nym address of the underlying client.
+    pub fn nym_address(&self) -> Recipient {
+        self.nym_address.current()
+    }
+
+    /// Serve the requests for the provided method with the handler. Registering another handler
+    /// for the same method replaces the previous one.
+    pub fn register_handler<F, Fut>(&self, method: &str, handler: F) -> Result<()>
+    where
+        F: Fn(Vec<u8>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = RpcResult> + Send + 'static,
+    {
+        // such method could have never been called
+        if method.len() > MAX_METHOD_LEN {
+            return Err(Error::RpcMethodTooLong {
+                length: method.len(),
+                max: MAX_METHOD_LEN,
+            });
+        }
+
+        let handler: RpcHandler = Arc::new(move |payload| handler(payload).boxed());
+        self.router
+            .unbounded_send(RpcCommand::RegisterHandler {
+                method: method.to_string(),
+                handler,
+            })
+            .map_err(|_| Error::MixnetRpcShutdown)
+    }
+
+    /// Call the method of the service at the provided address and wait for its response.
+    /// The request is retransmitted, up to the configured number of attempts, if the response
+    /// does not arrive within the timeout.
+    pub async fn request(
+        &self,
+        recipient: Recipient,
+        method: &str,
+        payload: Vec<u8>,
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        let id = rand::random();
+        let packet = RpcPacket::Request {
+            id,
+            method: method.to_string(),
+            payload,
+        }
+        .into_bytes()
+        .map_err(|_| Error::RpcMethodTooLong {
+            length: method.len(),
+            max: MAX_METHOD_LEN,
+        })?;
+
+        let (response_sender, mut response_receiver) = oneshot::channel();
+
+        self.router
+            .unbounded_send(RpcCommand::RegisterRequest {
+                id,
+                response: response_sender,
+            })
+            .map_err(|_| Error::MixnetRpcShutdown)?;
+        let _pending = PendingRequest {
+            id,
+            router: self.router.clone(),
+        };
+
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            if attempt > 1 {
+                debug!("retransmitting request {id} (attempt {attempt})");
+            }
+            self.router
+                .unbounded_send(RpcCommand::SendRequest {
+                    recipient,
+                    packet: packet.clone(),
+                })
+                .map_err(|_| Error::MixnetRpcShutdown)?;
+
+            match tokio::time::timeout(timeout, &mut response_receiver).await {
+                Ok(Ok(response)) => return response,
+                Ok(Err(_)) => return Err(Error::MixnetRpcShutdown),
+                Err(_) => trace!("request {id} has timed out"),
+            }
+        }
+
+        Err(Error::RpcTimeout)
+    }
+
+    /// Stop handling the RPC packets and disconnect the underlying client from the mixnet.
+    pub async fn disconnect(mut self) {
+        if let Some(shutdown) = self.router_shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        match self.router_handle.await {
+            Ok(mut client) => client.disconnect().await,
+            Err(err) => warn!("the rpc router has failed - {err}"),
+        }
+    }
+}
+
+type ServedRequestKey = (AnonymousSenderTag, RpcRequestId);
+
+#[derive(Debug, PartialEq)]
+enum ServedRequestState {
+    /// The request has not been seen before and it should be handled.
+    New,
+
+    /// The request is currently being handled.
+    InProgress,
+
+    /// The request has already been handled and the contained response should be resent.
+    Handled(Vec<u8>),
+
+    /// There are too many requests being handled already.
+    Overloaded,
+}
+
+/// Keeps track of the requests being served, so that the retransmitted ones are answered
+/// again rather than handled for the second time.
+struct ServedRequests {
+    max_in_progress: usize,
+    handler_timeout: Duration,
+
+    in_progress: HashMap<ServedRequestKey, Instant>,
+    response_cache: HashMap<ServedRequestKey, Vec<u8>>,
+    response_cache_order: VecDeque<ServedRequestKey>,
+}
+
+impl ServedRequests {
+    fn new(max_in_progress: usize, handler_timeout: Duration) -> Self {
+        ServedRequests {
+            max_in_progress,
+            handler_timeout,
+            in_progress: HashMap::new(),
+            response_cache: HashMap::new(),
+            response_cache_order: VecDeque::new(),
+        }
+    }
+
+    // the handlers are timed out on their own, so anything that's still here well after
+    // that time must have belonged to a handler that has panicked
+    fn remove_stale(&mut self) {
+        let max_age = self.handler_timeout * 2;
+        self.in_progress
+            .retain(|_, started| started.elapsed() <= max_age);
+    }
+
+    fn check(&mut self, key: &ServedRequestKey) -> ServedRequestState {
+        if let Some(cached) = self.response_cache.get(key) {
+            return ServedRequestState::Handled(cached.clone());
+        }
+
+        self.remove_stale();
+        if self.in_progress.contains_key(key) {
+            ServedRequestState::InProgress
+        } else if self.in_progress.len() >= self.max_in_progress {
+            ServedRequestState::Overloaded
+        } else {
+            ServedRequestState::New
+        }
+    }
+
+    fn start(&mut self, key: ServedRequestKey) {
+        self.in_progress.insert(key, Instant::now());
+    }
+
+    fn finish(&mut self, key: ServedRequestKey, packet: Vec<u8>) {
+        self.in_progress.remove(&key);
+
+        if self.response_cache_order.len() >= RESPONSE_CACHE_SIZE {
+            if let Some(oldest) = self.response_cache_order.pop_front() {
+                self.response_cache.remove(&oldest);
+            }
+        }
+        self.response_cache_order.push_back(key);
+        self.response_cache.insert(key, packet);
+    }
+}
+
+struct RpcRouter {
+    client: MixnetClient,
+    client_sender: MixnetClientSender,
+    reply_surbs: u32,
+    handler_timeout: Duration,
+
+    commands: mpsc::UnboundedReceiver<RpcCommand>,
+    // handed over to the spawned handler tasks
+    commands_sender: mpsc::UnboundedSender<RpcCommand>,
+
+    handlers: HashMap<String, RpcHandler>,
+    pending_requests: HashMap<RpcRequestId, oneshot::Sender<Result<Vec<u8>>>>,
+
+    served_requests: ServedRequests,
+}
+
+impl RpcRouter {
+    async fn send_response(&mut self, sender_tag: AnonymousSenderTag, packet: Vec<u8>) {
+        // if the response doesn't fit in the reply SURBs we have got, the reply controller
+        // is going to request more of them from the caller
+        let input_message = InputMessage::new_reply(sender_tag, packet, TransmissionLane::General);
+        self.client_sender.send_input_message(input_message).await;
+    }
+
+    async fn send_error(
+        &mut self,
+        sender_tag: AnonymousSenderTag,
+        id: RpcRequestId,
+        message: String,
+    ) {
+        match (RpcPacket::Error { id, message }).into_bytes() {
+            Ok(packet) => self.send_response(sender_tag, packet).await,
+            Err(err) => warn!("failed to encode the error response to {id} - {err}"),
+        }
+    }
+
+    async fn on_request(
+        &mut self,
+        sender_tag: Option<AnonymousSenderTag>,
+        id: RpcRequestId,
+        method: String,
+        payload: Vec<u8>,
+    ) {
+        let Some(sender_tag) = sender_tag else {
+            warn!(
+                "received request {id} without any reply SURBs - there's no way to respond to it"
+            );
+            return;
+        };
+        let key = (sender_tag, id);
+
+        match self.served_requests.check(&key) {
+            ServedRequestState::New => (),
+            // the request has been retransmitted
+            ServedRequestState::Handled(packet) => {
+                trace!("request {id} has already been handled - resending the response");
+                self.send_response(sender_tag, packet).await;
+                return;
+            }
+            ServedRequestState::InProgress => {
+                trace!("request {id} is already being handled");
+                return;
+            }
+            ServedRequestState::Overloaded => {
+                debug!("too many requests are being handled - rejecting request {id}");
+                let message = "the service is overloaded - try again later".to_string();
+                self.send_error(sender_tag, id, message).await;
+                return;
+            }
+        }
+
+        let Some(handler) = self.handlers.get(&method) else {
+            debug!("received request {id} for unknown method '{method}'");
+            self.send_error(sender_tag, id, format!("unknown method '{method}'"))
+                .await;
+            return;
+        };
+
+        self.served_requests.start(key);
+        let handler = Arc::clone(handler);
+        let handler_timeout = self.handler_timeout;
+        let commands_sender = self.commands_sender.clone();
+        tokio::spawn(async move {
+            let response = tokio::time::timeout(handler_timeout, handler(payload))
+                .await
+                .unwrap_or_else(|_| Err("the request handling has timed out".to_string()));
+
+            commands_sender
+                .unbounded_send(RpcCommand::HandlerFinished {
+                    sender_tag,
+                    id,
+                    response,
+                })
+                .ok();
+        });
+    }
+
+    fn on_response(&mut self, id: RpcRequestId, response: Result<Vec<u8>>) {
+        match self.pending_requests.remove(&id) {
+            Some(sender) => {
+                if sender.send(response).is_err() {
+                    trace!("the caller is no longer waiting for the response to {id}");
+                }
+            }
+            None => trace!("received response to unknown (or already completed) request {id}"),
+        }
+    }
+
+    async fn on_reconstructed_message(&mut self, message: ReconstructedMessage) {
+        let packet = match RpcPacket::try_from_bytes(&message.message) {
+            Ok(packet) => packet,
+            Err(err) => {
+                debug!("received a message that is not a valid rpc packet - {err}");
+                return;
+            }
+        };
+
+        match packet {
+            RpcPacket::Request {
+                id,
+                method,
+                payload,
+            } => {
+                self.on_request(message.sender_tag, id, method, payload)
+                    .await
+            }
+            RpcPacket::Response { id, payload } => self.on_response(id, Ok(payload)),
+            RpcPacket::Error { id, message } => {
+                self.on_response(id, Err(Error::RpcRemoteError(message)))
+            }
+        }
+    }
+
+    async fn on_command(&mut self, command: RpcCommand) {
+        match command {
+            RpcCommand::RegisterHandler { method, handler } => {
+                self.handlers.insert(method, handler);
+            }
+            RpcCommand::RegisterRequest { id, response } => {
+                self.pending_requests.insert(id, response);
+            }
+            RpcCommand::CancelRequest(id) => {
+                self.pending_requests.remove(&id);
+            }
+            RpcCommand::SendRequest { recipient, packet } => {
+                let input_message = InputMessage::new_anonymous(
+                    recipient,
+                    packet,
+                    self.reply_surbs,
+                    TransmissionLane::General,
+                );
+                self.client_sender.send_input_message(input_message).await;
+            }
+            RpcCommand::HandlerFinished {
+                sender_tag,
+                id,
+                response,
+            } => {
+                let packet = match response {
+                    Ok(payload) => RpcPacket::Response { id, payload },
+                    Err(message) => RpcPacket::Error { id, message },
+                };
+                // only the requests can fail to get encoded
+                let packet = match packet.into_bytes() {
+                    Ok(packet) => packet,
+                    Err(err) => {
+                        warn!("failed to encode the response to {id} - {err}");
+                        return;
+                    }
+                };
+                self.served_requests
+                    .finish((sender_tag, id), packet.clone());
+                self.send_response(sender_tag, packet).await;
+            }
+        }
+    }
+
+    async fn run(mut self, mut shutdown: oneshot::Receiver<()>) -> MixnetClient {
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    trace!("RpcRouter: received shutdown");
+                    break;
+                }
+                messages = self.client.wait_for_messages() => match messages {
+                    Some(messages) => {
+                        for message in messages {
+                            self.on_reconstructed_message(message).await
+                        }
+                    }
+                    None => {
+                        debug!("the mixnet client has stopped receiving messages");
+                        break;
+                    }
+                },
+                command = self.commands.next() => match command {
+                    Some(command) => self.on_command(command).await,
+                    // can't happen as we're holding a sender ourselves
+                    None => break,
+                }
+            }
+        }
+
+        // dropping the pending requests will make all of the callers fail
+        self.client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nym_sphinx::anonymous_replies::requests::SENDER_TAG_SIZE;
+
+    fn sender_tag(value: u8) -> AnonymousSenderTag {
+        AnonymousSenderTag::from_bytes([value; SENDER_TAG_SIZE])
+    }
+
+    #[test]
+    fn request_round_trip() {
+        let packet = RpcPacket::Request {
+            id: 1234567890,
+            method: "echo".to_string(),
+            payload: b"hello".to_vec(),
+        };
+        match RpcPacket::try_from_bytes(&packet.into_bytes().unwrap()).unwrap() {
+            RpcPacket::Request {
+                id,
+                method,
+                payload,
+            } => {
+                assert_eq!(id, 1234567890);
+                assert_eq!(method, "echo");
+                assert_eq!(payload, b"hello");
+            }
+            other => panic!("unexpected packet {other:?}"),
+        }
+    }
+
+    #[test]
+    fn request_with_empty_method_and_payload_round_trip() {
+        let packet = RpcPacket::Request {
+            id: 42,
+            method: String::new(),
+            payload: Vec::new(),
+        };
+        match RpcPacket::try_from_bytes(&packet.into_bytes().unwrap()).unwrap() {
+            RpcPacket::Request {
+                id,
+                method,
+                payload,
+            } => {
+                assert_eq!(id, 42);
+                assert!(method.is_empty());
+                assert!(payload.is_empty());
+            }
+            other => panic!("unexpected packet {other:?}"),
+        }
+    }
+
+    #[test]
+    fn response_round_trip() {
+        let packet = RpcPacket::Response {
+            id: u64::MAX,
+            payload: vec![1, 2, 3],
+        };
+        match RpcPacket::try_from_bytes(&packet.into_bytes().unwrap()).unwrap() {
+            RpcPacket::Response { id, payload } => {
+                assert_eq!(id, u64::MAX);
+                assert_eq!(payload, vec![1, 2, 3]);
+            }
+            other => panic!("unexpected packet {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_round_trip() {
+        let packet = RpcPacket::Error {
+            id: 7,
+            message: "unknown method 'foo'".to_string(),
+        };
+        match RpcPacket::try_from_bytes(&packet.into_bytes().unwrap()).unwrap() {
+            RpcPacket::Error { id, message } => {
+                assert_eq!(id, 7);
+                assert_eq!(message, "unknown method 'foo'");
+            }
+            other => panic!("unexpected packet {other:?}"),
+        }
+    }
+
+    #[test]
+    fn too_long_method_is_rejected() {
+        let packet = RpcPacket::Request {
+            id: 1,
+            method: "a".repeat(MAX_METHOD_LEN + 1),
+            payload: Vec::new(),
+        };
+        assert!(matches!(
+            packet.into_bytes(),
+            Err(RpcPacketError::MethodTooLong { length }) if length == MAX_METHOD_LEN + 1
+        ));
+
+        let packet = RpcPacket::Request {
+            id: 1,
+            method: "a".repeat(MAX_METHOD_LEN),
+            payload: Vec::new(),
+        };
+        assert!(packet.into_bytes().is_ok());
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let bytes = RpcPacket::Response {
+            id: 1,
+            payload: Vec::new(),
+        }
+        .into_bytes()
+        .unwrap();
+
+        for len in 0..PACKET_HEADER_SIZE {
+            assert!(matches!(
+                RpcPacket::try_from_bytes(&bytes[..len]),
+                Err(RpcPacketError::TooShort { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn truncated_request_is_rejected() {
+        let bytes = RpcPacket::Request {
+            id: 1,
+            method: "echo".to_string(),
+            payload: Vec::new(),
+        }
+        .into_bytes()
+        .unwrap();
+
+        // anything shorter than the header, the method length and the method itself is invalid
+        for len in 0..bytes.len() {
+            assert!(matches!(
+                RpcPacket::try_from_bytes(&bytes[..len]),
+                Err(RpcPacketError::TooShort { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn unknown_frame_is_rejected() {
+        let mut bytes = RpcPacket::Response {
+            id: 1,
+            payload: Vec::new(),
+        }
+        .into_bytes()
+        .unwrap();
+        bytes[0] = 42;
+
+        assert!(matches!(
+            RpcPacket::try_from_bytes(&bytes),
+            Err(RpcPacketError::UnknownFrameType(42))
+        ));
+    }
+
+    #[test]
+    fn malformed_method_is_rejected() {
+        let mut bytes = vec![REQUEST_FRAME];
+        bytes.extend_from_slice(&1u64.to_be_bytes());
+        bytes.extend_from_slice(&2u16.to_be_bytes());
+        bytes.extend_from_slice(&[0xff, 0xfe]);
+
+        assert!(matches!(
+            RpcPacket::try_from_bytes(&bytes),
+            Err(RpcPacketError::MalformedMethod)
+        ));
+    }
+
+    #[test]
+    fn retransmitted_requests_are_not_handled_twice() {
+        let mut served = ServedRequests::new(10, Duration::from_secs(60));
+        let key = (sender_tag(1), 1);
+
+        assert_eq!(served.check(&key), ServedRequestState::New);
+        served.start(key);
+        assert_eq!(served.check(&key), ServedRequestState::InProgress);
+
+        served.finish(key, b"response".to_vec());
+        assert_eq!(
+            served.check(&key),
+            ServedRequestState::Handled(b"response".to_vec())
+        );
+
+        // the same id from a different sender is a different request
+        assert_eq!(served.check(&(sender_tag(2), 1)), ServedRequestState::New);
+    }
+
+    #[test]
+    fn response_cache_is_bounded() {
+        let mut served = ServedRequests::new(10, Duration::from_secs(60));
+        for id in 0..RESPONSE_CACHE_SIZE as u64 + 1 {
+            let key = (sender_tag(1), id);
+            served.start(key);
+            served.finish(key, id.to_be_bytes().to_vec());
+        }
+
+        assert_eq!(served.response_cache.len(), RESPONSE_CACHE_SIZE);
+        assert_eq!(served.response_cache_order.len(), RESPONSE_CACHE_SIZE);
+        assert!(served.in_progress.is_empty());
+
+        // the oldest response got evicted
+        assert_eq!(served.check(&(sender_tag(1), 0)), ServedRequestState::New);
+        assert_eq!(
+            served.check(&(sender_tag(1), 1)),
+            ServedRequestState::Handled(1u64.to_be_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn requests_in_progress_are_bounded() {
+        let mut served = ServedRequests::new(2, Duration::from_secs(60));
+        served.start((sender_tag(1), 1));
+        served.start((sender_tag(1), 2));
+
+        assert_eq!(
+            served.check(&(sender_tag(1), 3)),
+            ServedRequestState::Overloaded
+        );
+        // the requests that are already being handled are still recognised
+        assert_eq!(
+            served.check(&(sender_tag(1), 1)),
+            ServedRequestState::InProgress
+        );
+
+        served.finish((sender_tag(1), 1), Vec::new());
+        assert_eq!(served.check(&(sender_tag(1), 3)), ServedRequestState::New);
+    }
+
+    #[test]
+    fn stale_requests_in_progress_are_evicted() {
+        let mut served = ServedRequests::new(1, Duration::from_millis(1));
+        served.start((sender_tag(1), 1));
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(served.check(&(sender_tag(1), 2)), ServedRequestState::New);
+        assert!(served.in_progress.is_empty());
+    }
+}
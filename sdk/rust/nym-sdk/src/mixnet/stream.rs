@@ -0,0 +1,408 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::channel::mpsc;
+use futures::StreamExt;
+use nym_sphinx::addressing::clients::Recipient;
+use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+use nym_task::connections::ConnectionId;
+use ordered_buffer::{MessageError, OrderedMessage, OrderedMessageBuffer, OrderedMessageSender};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::listener::RouterCommand;
+
+const OPEN_FRAME: u8 = 0;
+const DATA_FRAME: u8 = 1;
+const CLOSE_FRAME: u8 = 2;
+
+const PACKET_HEADER_SIZE: usize = 1 + std::mem::size_of::<ConnectionId>();
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum StreamPacketError {
+    #[error(
+        "received packet was too short. Got {received} bytes, but expected at least {expected}"
+    )]
+    TooShort { received: usize, expected: usize },
+
+    #[error("received packet had unknown frame type {0}")]
+    UnknownFrameType(u8),
+
+    #[error("received packet contained malformed ordered message: {0}")]
+    MalformedOrderedMessage(#[from] MessageError),
+}
+
+#[derive(Debug)]
+pub(crate) enum StreamFrame {
+    /// Announces a new stream to the remote so that it could be accepted before any data is sent.
+    Open,
+
+    /// Chunk of the stream data.
+    Data(OrderedMessage),
+
+    /// Marks the end of the stream. It carries an empty ordered message so that it is only
+    /// processed after all the preceding data has been read.
+    Close(OrderedMessage),
+}
+
+/// Unit of data exchanged between two ends of a [`MixnetStream`].
+/// The serialized format is:
+/// | 1 byte frame type | 8 bytes connection id | ordered message (data and close frames only) |
+#[derive(Debug)]
+pub(crate) struct StreamPacket {
+    pub(crate) connection_id: ConnectionId,
+    pub(crate) frame: StreamFrame,
+}
+
+impl StreamPacket {
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        let (frame_type, message) = match self.frame {
+            StreamFrame::Open => (OPEN_FRAME, None),
+            StreamFrame::Data(message) => (DATA_FRAME, Some(message)),
+            StreamFrame::Close(message) => (CLOSE_FRAME, Some(message)),
+        };
+
+        std::iter::once(frame_type)
+            .chain(self.connection_id.to_be_bytes())
+            .chain(message.map(OrderedMessage::into_bytes).unwrap_or_default())
+            .collect()
+    }
+
+    pub(crate) fn try_from_bytes(bytes: &[u8]) -> Result<Self, StreamPacketError> {
+        if bytes.len() < PACKET_HEADER_SIZE {
+            return Err(StreamPacketError::TooShort {
+                received: bytes.len(),
+                expected: PACKET_HEADER_SIZE,
+            });
+        }
+
+        let mut connection_id_bytes = [0u8; 8];
+        connection_id_bytes.copy_from_slice(&bytes[1..PACKET_HEADER_SIZE]);
+        let connection_id = ConnectionId::from_be_bytes(connection_id_bytes);
+
+        let frame = match bytes[0] {
+            OPEN_FRAME => StreamFrame::Open,
+            DATA_FRAME => StreamFrame::Data(OrderedMessage::try_from_bytes(
+                bytes[PACKET_HEADER_SIZE..].to_vec(),
+            )?),
+            CLOSE_FRAME => StreamFrame::Close(OrderedMessage::try_from_bytes(
+                bytes[PACKET_HEADER_SIZE..].to_vec(),
+            )?),
+            other => return Err(StreamPacketError::UnknownFrameType(other)),
+        };
+
+        Ok(StreamPacket {
+            connection_id,
+            frame,
+        })
+    }
+}
+
+/// The other end of the stream.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum StreamRemote {
+    /// We have opened the stream and know the full address of the remote.
+    Recipient(Recipient),
+
+    /// The remote has opened the stream and we can only reply to it using the provided SURBs.
+    SenderTag(AnonymousSenderTag),
+}
+
+/// Bidirectional, ordered byte stream to another Nym client, created either by
+/// [`MixnetListener::connect`](super::MixnetListener::connect) or
+/// [`MixnetListener::accept`](super::MixnetListener::accept).
+///
+/// Data written to the stream is sequenced with an [`OrderedMessageSender`] and reassembled on the
+/// other side with an [`OrderedMessageBuffer`], so it's read back in the order it was written
+/// regardless of the order the packets got out of the mixnet. The side that accepted the stream
+/// never learns the address of the other end, as all of its data is sent back using the reply SURBs.
+pub struct MixnetStream {
+    connection_id: ConnectionId,
+    remote: StreamRemote,
+
+    router: mpsc::UnboundedSender<RouterCommand>,
+    incoming: mpsc::Receiver<StreamFrame>,
+
+    message_sender: OrderedMessageSender,
+    message_buffer: OrderedMessageBuffer,
+
+    /// Index of the next message we expect to read out of the buffer.
+    next_index: u64,
+
+    /// Index of the close frame sent by the remote, if we have received it.
+    remote_close_index: Option<u64>,
+
+    /// Data that has already been ordered, but not yet read by the user.
+    read_buffer: Vec<u8>,
+
+    closed: bool,
+}
+
+impl MixnetStream {
+    pub(crate) fn new(
+        connection_id: ConnectionId,
+        remote: StreamRemote,
+        router: mpsc::UnboundedSender<RouterCommand>,
+        incoming: mpsc::Receiver<StreamFrame>,
+    ) -> Self {
+        MixnetStream {
+            connection_id,
+            remote,
+            router,
+            incoming,
+            message_sender: OrderedMessageSender::new(),
+            message_buffer: OrderedMessageBuffer::new(),
+            next_index: 0,
+            remote_close_index: None,
+            read_buffer: Vec::new(),
+            closed: false,
+        }
+    }
+
+    /// Identifier of this stream, shared by both of its ends.
+    pub fn connection_id(&self) -> ConnectionId {
+        self.connection_id
+    }
+
+    /// Address of the other end of the stream, if it's known, i.e. if we have opened the stream.
+    pub fn remote_address(&self) -> Option<Recipient> {
+        match self.remote {
+            StreamRemote::Recipient(recipient) => Some(recipient),
+            StreamRemote::SenderTag(_) => None,
+        }
+    }
+
+    fn send_frame(&self, frame: StreamFrame) -> io::Result<()> {
+        let packet = StreamPacket {
+            connection_id: self.connection_id,
+            frame,
+        };
+
+        self.router
+            .unbounded_send(RouterCommand::Send {
+                remote: self.remote,
+                connection_id: self.connection_id,
+                packet: packet.into_bytes(),
+            })
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+
+        let close_message = self.message_sender.wrap_message(Vec::new());
+        self.send_frame(StreamFrame::Close(close_message))
+    }
+
+    fn on_frame(&mut self, frame: StreamFrame) {
+        let message = match frame {
+            StreamFrame::Open => return,
+            StreamFrame::Data(message) => message,
+            StreamFrame::Close(message) => {
+                self.remote_close_index = Some(message.index);
+                message
+            }
+        };
+
+        self.message_buffer.write(message);
+        if let Some(contiguous) = self.message_buffer.read() {
+            self.next_index = contiguous.last_index;
+            self.read_buffer.extend(contiguous.data);
+        }
+    }
+
+    fn remote_has_closed(&self) -> bool {
+        // the close frame itself has been read out of the buffer
+        matches!(self.remote_close_index, Some(close_index) if self.next_index > close_index)
+    }
+}
+
+impl AsyncRead for MixnetStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            if !self.read_buffer.is_empty() {
+                let n = std::cmp::min(buf.remaining(), self.read_buffer.len());
+                buf.put_slice(&self.read_buffer[..n]);
+                self.read_buffer.drain(..n);
+                return Poll::Ready(Ok(()));
+            }
+
+            if self.remote_has_closed() {
+                return Poll::Ready(Ok(()));
+            }
+
+            match self.incoming.poll_next_unpin(cx) {
+                Poll::Ready(Some(frame)) => self.on_frame(frame),
+                // the stream got reset, either because it was not read fast enough
+                // or because the underlying client has shut down
+                Poll::Ready(None) => {
+                    return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()))
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for MixnetStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        let message = self.message_sender.wrap_message(buf.to_vec());
+        Poll::Ready(
+            self.send_frame(StreamFrame::Data(message))
+                .map(|_| buf.len()),
+        )
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // all the data is immediately handed over to the client
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.close())
+    }
+}
+
+impl Drop for MixnetStream {
+    fn drop(&mut self) {
+        // if the router is gone there's nothing else to do anyway
+        let _ = self.close();
+        let _ = self
+            .router
+            .unbounded_send(RouterCommand::Deregister(self.connection_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ordered_message(index: u64, data: &[u8]) -> OrderedMessage {
+        OrderedMessage {
+            data: data.to_vec(),
+            index,
+        }
+    }
+
+    fn round_trip(frame: StreamFrame) -> StreamPacket {
+        let packet = StreamPacket {
+            connection_id: 1234567890,
+            frame,
+        };
+        let decoded = StreamPacket::try_from_bytes(&packet.into_bytes()).unwrap();
+        assert_eq!(decoded.connection_id, 1234567890);
+        decoded
+    }
+
+    #[test]
+    fn open_frame_round_trip() {
+        assert!(matches!(
+            round_trip(StreamFrame::Open).frame,
+            StreamFrame::Open
+        ));
+    }
+
+    #[test]
+    fn data_frame_round_trip() {
+        let message = ordered_message(42, b"hello there");
+        match round_trip(StreamFrame::Data(message.clone())).frame {
+            StreamFrame::Data(decoded) => assert_eq!(decoded, message),
+            other => panic!("unexpected frame {other:?}"),
+        }
+    }
+
+    #[test]
+    fn close_frame_round_trip() {
+        let message = ordered_message(7, &[]);
+        match round_trip(StreamFrame::Close(message.clone())).frame {
+            StreamFrame::Close(decoded) => assert_eq!(decoded, message),
+            other => panic!("unexpected frame {other:?}"),
+        }
+    }
+
+    #[test]
+    fn packet_layout_is_stable() {
+        let packet = StreamPacket {
+            connection_id: 1,
+            frame: StreamFrame::Data(ordered_message(2, &[3])),
+        };
+        assert_eq!(
+            packet.into_bytes(),
+            vec![DATA_FRAME, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 3]
+        );
+    }
+
+    #[test]
+    fn truncated_packets_are_rejected() {
+        let bytes = StreamPacket {
+            connection_id: 1,
+            frame: StreamFrame::Open,
+        }
+        .into_bytes();
+
+        for len in 0..PACKET_HEADER_SIZE {
+            assert!(matches!(
+                StreamPacket::try_from_bytes(&bytes[..len]),
+                Err(StreamPacketError::TooShort { .. })
+            ));
+        }
+
+        // data frame without the full ordered message index
+        let mut bytes = bytes;
+        bytes[0] = DATA_FRAME;
+        bytes.extend_from_slice(&[0, 0, 0]);
+        assert!(matches!(
+            StreamPacket::try_from_bytes(&bytes),
+            Err(StreamPacketError::MalformedOrderedMessage(_))
+        ));
+    }
+
+    #[test]
+    fn data_frames_without_ordered_message_are_rejected() {
+        let mut bytes = StreamPacket {
+            connection_id: 1,
+            frame: StreamFrame::Open,
+        }
+        .into_bytes();
+        bytes[0] = CLOSE_FRAME;
+
+        assert!(matches!(
+            StreamPacket::try_from_bytes(&bytes),
+            Err(StreamPacketError::MalformedOrderedMessage(
+                MessageError::NoData
+            ))
+        ));
+    }
+
+    #[test]
+    fn unknown_frame_types_are_rejected() {
+        let mut bytes = StreamPacket {
+            connection_id: 1,
+            frame: StreamFrame::Open,
+        }
+        .into_bytes();
+        bytes[0] = 42;
+
+        assert!(matches!(
+            StreamPacket::try_from_bytes(&bytes),
+            Err(StreamPacketError::UnknownFrameType(42))
+        ));
+    }
+}
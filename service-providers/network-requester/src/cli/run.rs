@@ -10,6 +10,7 @@ use clap::Args;
 use config::NymConfig;
 use nym_bin_common::version_checker;
 use nym_sphinx::addressing::clients::Recipient;
+use std::path::PathBuf;
 
 const ENABLE_STATISTICS: &str = "enable-statistics";
 
@@ -24,6 +25,11 @@ pub(crate) struct Run {
     #[clap(long)]
     open_proxy: bool,
 
+    /// Path to the file with the outbound request policy, i.e. the allow and deny rules and
+    /// the per-sender rate limit. Changes made to the file are picked up while running.
+    #[clap(long)]
+    policy: Option<PathBuf>,
+
     /// Enable service anonymized statistics that get sent to a statistics aggregator server
     #[clap(long)]
     enable_statistics: bool,
@@ -150,9 +156,10 @@ pub(crate) async fn execute(args: &Run) -> Result<(), NetworkRequesterError> {
     let server = crate::core::NRServiceProviderBuilder::new(
         config,
         args.open_proxy,
+        args.policy.clone(),
         args.enable_statistics,
         stats_provider_addr,
     )
-    .await;
+    .await?;
     server.run_service_provider().await
 }
@@ -59,6 +59,15 @@ impl ClientCoreConfigTrait for Config {
     fn get_gateway_endpoint(&self) -> &client_core::config::GatewayEndpointConfig {
         self.base.get_gateway_endpoint()
     }
+
+    fn set_gateway_endpoints(
+        &mut self,
+        gateway_endpoint: client_core::config::GatewayEndpointConfig,
+        standby_gateways: Vec<client_core::config::GatewayEndpointConfig>,
+    ) {
+        self.base
+            .set_gateway_endpoints(gateway_endpoint, standby_gateways)
+    }
 }
 
 impl Config {
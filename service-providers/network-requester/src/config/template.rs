@@ -76,6 +76,15 @@ gateway_owner = '{{ client.gateway_endpoint.gateway_owner }}'
 # Address of the gateway listener to which all client requests should be sent.
 gateway_listener = '{{ client.gateway_endpoint.gateway_listener }}'
 
+# Gateways the client is going to fail over to, in order, if its gateway becomes unreachable.
+# Each entry is a `[[client.standby_gateways]]` table with the same fields as `[client.gateway_endpoint]`.
+{{#each client.standby_gateways }}
+[[client.standby_gateways]]
+gateway_id = '{{ this.gateway_id }}'
+gateway_owner = '{{ this.gateway_owner }}'
+gateway_listener = '{{ this.gateway_listener }}'
+{{/each}}
+
 
 ##### logging configuration options #####
 
@@ -5,7 +5,9 @@ use crate::allowed_hosts;
 use crate::allowed_hosts::OutboundRequestFilter;
 use crate::config::Config;
 use crate::error::NetworkRequesterError;
+use crate::policy::{AddressCheck, PolicyDecision, PolicyFilter, PolicyWatcher};
 use crate::reply::MixnetMessage;
+use crate::socks5::udp::{OutboundDatagram, OutboundDatagramSender};
 use crate::statistics::ServiceStatisticsCollector;
 use crate::{reply, socks5};
 use async_trait::async_trait;
@@ -16,23 +18,30 @@ use nym_sphinx::addressing::clients::Recipient;
 use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
 use nym_task::connections::LaneQueueLengths;
 use nym_task::{TaskClient, TaskManager};
-use proxy_helpers::connection_controller::{Controller, ControllerCommand, ControllerSender};
+use proxy_helpers::connection_controller::{
+    Controller, ControllerCommand, ControllerSender, Datagram,
+};
 use proxy_helpers::proxy_runner::{MixProxyReader, MixProxySender};
 use service_providers_common::interface::{
     BinaryInformation, ProviderInterfaceVersion, Request, RequestVersion,
 };
 use service_providers_common::ServiceProvider;
 use socks5_requests::{
-    ConnectRequest, ConnectionId, NetworkData, SendRequest, Socks5ProtocolVersion,
-    Socks5ProviderRequest, Socks5Request, Socks5RequestContent, Socks5Response,
+    ConnectRequest, ConnectionErrorKind, ConnectionId, CreditRequest, DatagramRequest, NetworkData,
+    SendRequest, Socks5ProtocolVersion, Socks5ProviderRequest, Socks5Request, Socks5RequestContent,
+    Socks5Response,
 };
 use statistics_common::collector::StatisticsSender;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 // Since it's an atomic, it's safe to be kept static and shared across threads
 static ACTIVE_PROXIES: AtomicUsize = AtomicUsize::new(0);
 
+/// Maximum number of UDP associations that can be running at the same time.
+const MAX_UDP_ASSOCIATIONS: usize = 256;
+
 pub(crate) fn new_legacy_request_version() -> RequestVersion<Socks5Request> {
     RequestVersion {
         provider_interface: ProviderInterfaceVersion::Legacy,
@@ -45,6 +54,8 @@ pub(crate) fn new_legacy_request_version() -> RequestVersion<Socks5Request> {
 pub struct NRServiceProviderBuilder {
     config: Config,
     outbound_request_filter: OutboundRequestFilter,
+    policy_filter: Option<PolicyFilter>,
+    policy_watcher: Option<PolicyWatcher>,
     open_proxy: bool,
     enable_statistics: bool,
     stats_provider_addr: Option<Recipient>,
@@ -52,11 +63,13 @@ pub struct NRServiceProviderBuilder {
 
 struct NRServiceProvider {
     outbound_request_filter: OutboundRequestFilter,
+    policy_filter: Option<PolicyFilter>,
     open_proxy: bool,
     mixnet_client: nym_sdk::mixnet::MixnetClient,
 
     controller_sender: ControllerSender,
     mix_input_sender: MixProxySender<MixnetMessage>,
+    udp_associations: HashMap<ConnectionId, OutboundDatagramSender>,
     //shared_lane_queue_lengths: LaneQueueLengths,
     stats_collector: Option<ServiceStatisticsCollector>,
     shutdown: TaskManager,
@@ -139,6 +152,12 @@ impl ServiceProvider<Socks5Request> for NRServiceProvider {
                 }
                 self.handle_proxy_send(req)
             }
+            Socks5RequestContent::Datagram(req) => {
+                self.handle_proxy_datagram(request_version, sender, req)
+                    .await
+            }
+            Socks5RequestContent::Reset(conn_id) => self.handle_proxy_reset(conn_id),
+            Socks5RequestContent::Credit(req) => self.handle_proxy_credit(req),
         }
 
         Ok(None)
@@ -149,9 +168,10 @@ impl NRServiceProviderBuilder {
     pub async fn new(
         config: Config,
         open_proxy: bool,
+        policy_file: Option<PathBuf>,
         enable_statistics: bool,
         stats_provider_addr: Option<Recipient>,
-    ) -> NRServiceProviderBuilder {
+    ) -> Result<NRServiceProviderBuilder, NetworkRequesterError> {
         let standard_hosts = allowed_hosts::fetch_standard_allowed_list().await;
 
         log::info!("Standard allowed hosts: {:?}", standard_hosts);
@@ -169,13 +189,25 @@ impl NRServiceProviderBuilder {
         );
 
         let outbound_request_filter = OutboundRequestFilter::new(allowed_hosts, unknown_hosts);
-        NRServiceProviderBuilder {
+
+        let (policy_filter, policy_watcher) = match policy_file {
+            Some(policy_file) => {
+                log::info!("Loading the request policy from {:?}", policy_file);
+                let (filter, watcher) = PolicyFilter::new(policy_file)?;
+                (Some(filter), Some(watcher))
+            }
+            None => (None, None),
+        };
+
+        Ok(NRServiceProviderBuilder {
             config,
             outbound_request_filter,
+            policy_filter,
+            policy_watcher,
             open_proxy,
             enable_statistics,
             stats_provider_addr,
-        }
+        })
     }
 
     /// Start all subsystems
@@ -200,6 +232,10 @@ impl NRServiceProviderBuilder {
             active_connections_controller.run().await;
         });
 
+        if let Some(policy_watcher) = self.policy_watcher {
+            tokio::spawn(policy_watcher.run(shutdown.subscribe()));
+        }
+
         let stats_collector = if self.enable_statistics {
             let stats_collector =
                 ServiceStatisticsCollector::new(self.stats_provider_addr, mix_input_sender.clone())
@@ -217,7 +253,7 @@ impl NRServiceProviderBuilder {
 
         let stats_collector_clone = stats_collector.clone();
         let mixnet_client_sender = mixnet_client.sender();
-        let self_address = *mixnet_client.nym_address();
+        let self_address = mixnet_client.nym_address();
 
         // start the listener for mix messages
         tokio::spawn(async move {
@@ -231,10 +267,12 @@ impl NRServiceProviderBuilder {
 
         let service_provider = NRServiceProvider {
             outbound_request_filter: self.outbound_request_filter,
+            policy_filter: self.policy_filter,
             open_proxy: self.open_proxy,
             mixnet_client,
             controller_sender,
             mix_input_sender,
+            udp_associations: HashMap::new(),
             //shared_lane_queue_lengths: mixnet_client.shared_lane_queue_lengths(),
             stats_collector,
             shutdown,
@@ -316,19 +354,46 @@ impl NRServiceProvider {
         remote_version: RequestVersion<Socks5Request>,
         connection_id: ConnectionId,
         remote_addr: String,
+        address_check: Option<AddressCheck>,
         return_address: reply::MixnetAddress,
         controller_sender: ControllerSender,
         mix_input_sender: MixProxySender<MixnetMessage>,
         lane_queue_lengths: LaneQueueLengths,
         shutdown: TaskClient,
     ) {
-        let mut conn = match socks5::tcp::Connection::new(
-            connection_id,
-            remote_addr.clone(),
-            return_address.clone(),
-        )
-        .await
-        {
+        // resolve the remote only once, so that we'd connect to exactly the addresses
+        // that were checked against the policy
+        let connection = match socks5::tcp::resolve(&remote_addr).await {
+            Ok(resolved) => {
+                if let Some(address_check) = address_check {
+                    if !address_check.allows(&remote_addr, &resolved).await {
+                        let log_msg = format!(
+                            "Domain {remote_addr:?} resolves to addresses denied by the policy"
+                        );
+                        Self::reject_request(
+                            &mix_input_sender,
+                            return_address,
+                            remote_version,
+                            connection_id,
+                            log_msg,
+                        )
+                        .await;
+                        return;
+                    }
+                }
+
+                socks5::tcp::Connection::new(
+                    connection_id,
+                    remote_addr.clone(),
+                    &resolved,
+                    return_address.clone(),
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+
+        let mut conn = match connection {
             Ok(conn) => conn,
             Err(err) => {
                 log::error!(
@@ -338,12 +403,25 @@ impl NRServiceProvider {
                 );
 
                 // inform the remote that the connection is closed before it even was established
-                let mixnet_message = MixnetMessage::new_network_data_response(
-                    return_address,
-                    remote_version,
-                    connection_id,
-                    NetworkData::new_closed_empty(connection_id),
-                );
+                let mixnet_message = if remote_version
+                    .provider_protocol
+                    .supports_connection_status()
+                {
+                    MixnetMessage::new_connection_failure(
+                        return_address,
+                        remote_version,
+                        connection_id,
+                        ConnectionErrorKind::from(&err),
+                        format!("failed to connect to {remote_addr}: {err}"),
+                    )
+                } else {
+                    MixnetMessage::new_network_data_response(
+                        return_address,
+                        remote_version,
+                        connection_id,
+                        NetworkData::new_closed_empty(connection_id),
+                    )
+                };
 
                 mix_input_sender
                     .send(mixnet_message)
@@ -363,6 +441,18 @@ impl NRServiceProvider {
             })
             .unwrap();
 
+        if remote_version
+            .provider_protocol
+            .supports_connection_status()
+        {
+            let mixnet_message =
+                MixnetMessage::new_connected(return_address, remote_version.clone(), connection_id);
+            mix_input_sender
+                .send(mixnet_message)
+                .await
+                .expect("InputMessageReceiver has stopped receiving!");
+        }
+
         let old_count = ACTIVE_PROXIES.fetch_add(1, Ordering::SeqCst);
         log::info!(
             "Starting proxy for {} (currently there are {} proxies being handled)",
@@ -409,21 +499,27 @@ impl NRServiceProvider {
         let remote_addr = connect_req.remote_addr;
         let conn_id = connect_req.conn_id;
 
-        if !self.open_proxy && !self.outbound_request_filter.check(&remote_addr) {
-            let log_msg = format!("Domain {remote_addr:?} failed filter check");
-            log::info!("{}", log_msg);
-            let msg = MixnetMessage::new_connection_error(
-                return_address,
-                remote_version,
-                conn_id,
-                log_msg,
-            );
-            self.mix_input_sender
-                .send(msg)
+        let allowed = match self.check_outbound_request(&remote_addr).await {
+            Ok(address_check) => self
+                .admit_sender(&return_address, &remote_addr)
                 .await
-                .expect("InputMessageReceiver has stopped receiving!");
-            return;
-        }
+                .map(|_| address_check),
+            Err(err) => Err(err),
+        };
+        let address_check = match allowed {
+            Ok(address_check) => address_check,
+            Err(log_msg) => {
+                Self::reject_request(
+                    &self.mix_input_sender,
+                    return_address,
+                    remote_version,
+                    conn_id,
+                    log_msg,
+                )
+                .await;
+                return;
+            }
+        };
 
         let controller_sender_clone = self.controller_sender.clone();
         let mix_input_sender_clone = self.mix_input_sender.clone();
@@ -436,6 +532,7 @@ impl NRServiceProvider {
                 remote_version,
                 conn_id,
                 remote_addr,
+                address_check,
                 return_address,
                 controller_sender_clone,
                 mix_input_sender_clone,
@@ -446,9 +543,208 @@ impl NRServiceProvider {
         });
     }
 
+    /// Checks the remote address against the operator's policy and the allowed hosts.
+    /// It does not resolve the remote, so if the policy has any rules matching the networks,
+    /// the returned [`AddressCheck`] has to be completed by the task handling the request.
+    async fn check_outbound_request(
+        &mut self,
+        remote_addr: &str,
+    ) -> Result<Option<AddressCheck>, String> {
+        let (decision, address_check) = match &self.policy_filter {
+            Some(policy_filter) => policy_filter.check(remote_addr).await,
+            None => (PolicyDecision::NoMatch, None),
+        };
+
+        let allowed = match decision {
+            PolicyDecision::Allow => true,
+            // the explicit deny rules apply even to the open proxies
+            PolicyDecision::Deny => {
+                return Err(format!("Domain {remote_addr:?} is denied by the policy"))
+            }
+            PolicyDecision::NoMatch => {
+                self.open_proxy || self.outbound_request_filter.check(remote_addr)
+            }
+        };
+
+        match address_check {
+            // the addresses the domain resolves to might still get it allowed or denied
+            Some(address_check) => Ok(Some(address_check.with_fallback(allowed))),
+            None if allowed => Ok(None),
+            None => Err(format!("Domain {remote_addr:?} failed filter check")),
+        }
+    }
+
+    async fn reject_request(
+        mix_input_sender: &MixProxySender<MixnetMessage>,
+        return_address: reply::MixnetAddress,
+        remote_version: RequestVersion<Socks5Request>,
+        conn_id: ConnectionId,
+        log_msg: String,
+    ) {
+        log::info!("{}", log_msg);
+        let msg = MixnetMessage::new_connection_failure(
+            return_address,
+            remote_version,
+            conn_id,
+            ConnectionErrorKind::NotAllowed,
+            log_msg,
+        );
+        mix_input_sender
+            .send(msg)
+            .await
+            .expect("InputMessageReceiver has stopped receiving!");
+    }
+
+    /// Counts the new connection (or UDP association) towards the sender's rate limit.
+    async fn admit_sender(
+        &mut self,
+        return_address: &reply::MixnetAddress,
+        remote_addr: &str,
+    ) -> Result<(), String> {
+        let Some(policy_filter) = &mut self.policy_filter else {
+            return Ok(());
+        };
+        if policy_filter.admit(&return_address.sender_id()).await {
+            Ok(())
+        } else {
+            Err(format!(
+                "Sender exceeded its rate limit, rejecting request to {remote_addr:?}"
+            ))
+        }
+    }
+
     fn handle_proxy_send(&mut self, req: SendRequest) {
         self.controller_sender.unbounded_send(req.into()).unwrap()
     }
+
+    fn handle_proxy_credit(&mut self, req: CreditRequest) {
+        self.controller_sender.unbounded_send(req.into()).unwrap()
+    }
+
+    fn handle_proxy_reset(&mut self, conn_id: ConnectionId) {
+        // dropping the only sender is going to stop the association
+        if self.udp_associations.remove(&conn_id).is_some() {
+            log::debug!("UDP association {conn_id} got reset by the client");
+            return;
+        }
+
+        self.controller_sender
+            .unbounded_send(ControllerCommand::Reset {
+                connection_id: conn_id,
+            })
+            .unwrap()
+    }
+
+    async fn handle_proxy_datagram(
+        &mut self,
+        remote_version: RequestVersion<Socks5Request>,
+        sender_tag: Option<AnonymousSenderTag>,
+        datagram_req: Box<DatagramRequest>,
+    ) {
+        let Some(return_address) = reply::MixnetAddress::new(datagram_req.return_address, sender_tag) else {
+            log::warn!(
+                "attempted to relay a datagram with no way of returning data back to the sender"
+            );
+            return;
+        };
+
+        let conn_id = datagram_req.conn_id;
+        let remote_addr = datagram_req.remote_addr;
+
+        let address_check = match self.check_outbound_request(&remote_addr).await {
+            Ok(address_check) => address_check,
+            Err(log_msg) => {
+                Self::reject_request(
+                    &self.mix_input_sender,
+                    return_address,
+                    remote_version,
+                    conn_id,
+                    log_msg,
+                )
+                .await;
+                return;
+            }
+        };
+
+        let mut datagram = OutboundDatagram {
+            datagram: Datagram {
+                remote_addr: remote_addr.clone(),
+                data: datagram_req.data,
+            },
+            address_check,
+        };
+
+        if let Some(association) = self.udp_associations.get(&conn_id) {
+            match association.unbounded_send(datagram) {
+                Ok(_) => return,
+                // the association has timed out - we're going to create a new one
+                Err(err) => datagram = err.into_inner(),
+            }
+        }
+        self.udp_associations
+            .retain(|_, association| !association.is_closed());
+        if self.udp_associations.len() >= MAX_UDP_ASSOCIATIONS {
+            let log_msg = format!(
+                "Reached the maximum number of UDP associations ({MAX_UDP_ASSOCIATIONS}), rejecting request to {remote_addr:?}"
+            );
+            Self::reject_request(
+                &self.mix_input_sender,
+                return_address,
+                remote_version,
+                conn_id,
+                log_msg,
+            )
+            .await;
+            return;
+        }
+
+        if let Err(log_msg) = self.admit_sender(&return_address, &remote_addr).await {
+            Self::reject_request(
+                &self.mix_input_sender,
+                return_address,
+                remote_version,
+                conn_id,
+                log_msg,
+            )
+            .await;
+            return;
+        }
+
+        let association = match socks5::udp::UdpAssociation::new(
+            conn_id,
+            return_address.clone(),
+            remote_version.clone(),
+        )
+        .await
+        {
+            Ok(association) => association,
+            Err(err) => {
+                log::error!("failed to create UDP association: {err}");
+                let msg = MixnetMessage::new_connection_failure(
+                    return_address,
+                    remote_version,
+                    conn_id,
+                    ConnectionErrorKind::from(&err),
+                    format!("failed to create UDP association: {err}"),
+                );
+                self.mix_input_sender
+                    .send(msg)
+                    .await
+                    .expect("InputMessageReceiver has stopped receiving!");
+                return;
+            }
+        };
+
+        let (datagram_sender, datagram_receiver) = mpsc::unbounded();
+        // the receiver is alive, so this can't fail
+        datagram_sender.unbounded_send(datagram).ok();
+        self.udp_associations.insert(conn_id, datagram_sender);
+
+        log::info!("Starting UDP association {conn_id}");
+        let mix_input_sender = self.mix_input_sender.clone();
+        let shutdown = self.shutdown.subscribe();
+        tokio::spawn(association.run(datagram_receiver, mix_input_sender, shutdown));
+    }
 }
 
 // Helper function to create the mixnet client.
@@ -1,3 +1,4 @@
+use crate::policy::PolicyError;
 use client_core::error::ClientCoreError;
 use socks5_requests::Socks5RequestError;
 
@@ -21,6 +22,12 @@ pub enum NetworkRequesterError {
     #[error("failed to load configuration file: {0}")]
     FailedToLoadConfig(String),
 
+    #[error("failed to load the request policy: {source}")]
+    FailedToLoadPolicy {
+        #[from]
+        source: PolicyError,
+    },
+
     #[error("failed local version check, client and config mismatch")]
     FailedLocalVersionCheck,
 
@@ -12,6 +12,7 @@ mod cli;
 mod config;
 mod core;
 mod error;
+mod policy;
 mod reply;
 mod socks5;
 mod statistics;
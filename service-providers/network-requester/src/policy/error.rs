@@ -0,0 +1,42 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PolicyError {
+    #[error("failed to read the policy file {path:?}: {source}")]
+    ReadFailure {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("line {line}: unknown directive '{directive}'")]
+    UnknownDirective { line: usize, directive: String },
+
+    #[error("line {line}: missing {argument}")]
+    MissingArgument { line: usize, argument: &'static str },
+
+    #[error("line {line}: unexpected argument '{argument}'")]
+    UnexpectedArgument { line: usize, argument: String },
+
+    #[error("line {line}: '{pattern}' is not a valid host pattern")]
+    InvalidHostPattern { line: usize, pattern: String },
+
+    #[error("line {line}: invalid regular expression: {source}")]
+    InvalidRegex { line: usize, source: regex::Error },
+
+    #[error("line {line}: '{ports}' is not a valid list of ports")]
+    InvalidPorts { line: usize, ports: String },
+
+    #[error("line {line}: '{value}' is not a valid {argument}")]
+    InvalidRateLimit {
+        line: usize,
+        argument: &'static str,
+        value: String,
+    },
+
+    #[error("line {line}: the rate limit has already been set on line {previous}")]
+    DuplicateRateLimit { line: usize, previous: usize },
+}
@@ -0,0 +1,401 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+//! Operator-defined policy for the outbound requests, loaded from a file such as:
+//!
+//! ```text
+//! # deny rules always take precedence over the allow rules
+//! deny 10.0.0.0/8
+//! deny * 25
+//! allow nymtech.net
+//! allow *.example.com 80,443
+//! allow ~^api[0-9]+\.example\.org$ 8000-9000
+//!
+//! # every sender can open at most 100 connections per minute
+//! rate-limit 100 60
+//! ```
+//!
+//! Requests that are not covered by any rule fall back to the `allowed.list` filter.
+//!
+//! Note that the rate limit is applied per sender address or, for the anonymous senders,
+//! per sender tag. Both of them can be freely regenerated by the clients, so the limit
+//! only protects against the well-behaved clients getting carried away, not against
+//! a determined abuser.
+
+use rate_limit::{RateLimit, RateLimiter};
+use rule::{normalise_address, Action, Rule};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+mod error;
+mod rate_limit;
+mod rule;
+mod watcher;
+
+pub use error::PolicyError;
+pub(crate) use watcher::PolicyWatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PolicyDecision {
+    Allow,
+    Deny,
+
+    /// None of the rules apply to the request.
+    NoMatch,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RequestPolicy {
+    rules: Vec<Rule>,
+    rate_limit: Option<RateLimit>,
+}
+
+impl RequestPolicy {
+    pub(crate) fn load<P: AsRef<Path>>(path: P) -> Result<Self, PolicyError> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|source| PolicyError::ReadFailure {
+                path: path.to_path_buf(),
+                source,
+            })?
+            .parse()
+    }
+
+    /// Whether any of the rules has to know the addresses the remote resolves to.
+    fn requires_resolution(&self, remote_addr: &str) -> bool {
+        let (host, _) = split_host_port(remote_addr);
+        host.parse::<IpAddr>().is_err() && self.rules.iter().any(Rule::matches_addresses)
+    }
+
+    /// Checks the remote address, i.e. `host:port`, against the rules. If the host is a domain,
+    /// `resolved` are the addresses it resolves to.
+    pub(crate) fn evaluate(&self, remote_addr: &str, resolved: &[IpAddr]) -> PolicyDecision {
+        let (host, port) = split_host_port(remote_addr);
+        let addresses: Vec<_> = match host.parse() {
+            Ok(address) => vec![normalise_address(address)],
+            Err(_) => resolved.iter().copied().map(normalise_address).collect(),
+        };
+
+        let mut decision = PolicyDecision::NoMatch;
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(host, &addresses, port))
+        {
+            match rule.action {
+                Action::Deny => return PolicyDecision::Deny,
+                Action::Allow => decision = PolicyDecision::Allow,
+            }
+        }
+        decision
+    }
+}
+
+impl FromStr for RequestPolicy {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policy = RequestPolicy::default();
+        let mut rate_limit_line = None;
+
+        for (index, raw_line) in s.lines().enumerate() {
+            let line = index + 1;
+            let raw_line = raw_line.trim();
+            if raw_line.is_empty() || raw_line.starts_with('#') {
+                continue;
+            }
+
+            let mut args = raw_line.split_whitespace();
+            match args.next() {
+                Some("allow") => policy.rules.push(Rule::parse(line, Action::Allow, args)?),
+                Some("deny") => policy.rules.push(Rule::parse(line, Action::Deny, args)?),
+                Some("rate-limit") => {
+                    if let Some(previous) = rate_limit_line {
+                        return Err(PolicyError::DuplicateRateLimit { line, previous });
+                    }
+                    policy.rate_limit = Some(RateLimit::parse(line, args)?);
+                    rate_limit_line = Some(line);
+                }
+                directive => {
+                    return Err(PolicyError::UnknownDirective {
+                        line,
+                        directive: directive.unwrap_or_default().to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(policy)
+    }
+}
+
+fn split_host_port(remote_addr: &str) -> (&str, Option<u16>) {
+    // it might be an ipv6 address in brackets, so don't just blindly split on the last colon
+    if remote_addr.parse::<SocketAddr>().is_ok() {
+        if let Some((host, port)) = remote_addr.rsplit_once(':') {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            return (host, port.parse().ok());
+        }
+    }
+    match remote_addr.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host, port.parse().ok()),
+        _ => (remote_addr, None),
+    }
+}
+
+/// Applies the [`RequestPolicy`], which is kept up to date by the corresponding
+/// [`PolicyWatcher`], to the requests made by the clients.
+pub(crate) struct PolicyFilter {
+    policy: Arc<RwLock<RequestPolicy>>,
+    rate_limiter: RateLimiter,
+}
+
+impl PolicyFilter {
+    /// Loads the policy from the file. The returned watcher has to be run
+    /// for the changes made to the file to be picked up.
+    pub(crate) fn new(path: PathBuf) -> Result<(Self, PolicyWatcher), PolicyError> {
+        let policy = Arc::new(RwLock::new(RequestPolicy::load(&path)?));
+        let watcher = PolicyWatcher::new(path, Arc::clone(&policy));
+
+        Ok((
+            PolicyFilter {
+                policy,
+                rate_limiter: RateLimiter::default(),
+            },
+            watcher,
+        ))
+    }
+
+    /// Checks the remote address against the rules without resolving it, so that a slow resolver
+    /// wouldn't hold up any other requests. If the remote is a domain and any of the rules
+    /// match networks, the returned [`AddressCheck`] has to be completed against the addresses
+    /// it resolves to before connecting to any of them.
+    pub(crate) async fn check(&self, remote_addr: &str) -> (PolicyDecision, Option<AddressCheck>) {
+        let policy = self.policy.read().await;
+        let decision = policy.evaluate(remote_addr, &[]);
+
+        // the addresses can't overturn the deny rules that have already matched the domain
+        let address_check = (decision != PolicyDecision::Deny
+            && policy.requires_resolution(remote_addr))
+        .then(|| AddressCheck {
+            policy: Arc::clone(&self.policy),
+            fallback: false,
+        });
+        (decision, address_check)
+    }
+
+    /// Returns whether the sender is still within its rate limit and if so,
+    /// counts the new request towards it.
+    pub(crate) async fn admit(&mut self, sender: &str) -> bool {
+        match &self.policy.read().await.rate_limit {
+            Some(rate_limit) => self.rate_limiter.admit(sender, rate_limit),
+            None => true,
+        }
+    }
+}
+
+/// Part of the policy check that can only be completed once the remote domain has been resolved.
+/// It's meant to be done by the task handling the request, which then has to use exactly
+/// the checked addresses, as otherwise the domain could resolve to something else in the meantime.
+#[derive(Clone)]
+pub(crate) struct AddressCheck {
+    policy: Arc<RwLock<RequestPolicy>>,
+
+    // whether the request is allowed if none of the rules apply to it
+    fallback: bool,
+}
+
+impl AddressCheck {
+    /// Sets whether the request should be allowed if none of the rules apply to it,
+    /// for example because it's present on the allowed list.
+    #[must_use]
+    pub(crate) fn with_fallback(mut self, allowed: bool) -> Self {
+        self.fallback = allowed;
+        self
+    }
+
+    pub(crate) async fn allows(&self, remote_addr: &str, resolved: &[SocketAddr]) -> bool {
+        let resolved: Vec<_> = resolved.iter().map(SocketAddr::ip).collect();
+        match self.policy.read().await.evaluate(remote_addr, &resolved) {
+            PolicyDecision::Allow => true,
+            PolicyDecision::Deny => false,
+            PolicyDecision::NoMatch => self.fallback,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(raw: &str) -> RequestPolicy {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn parsing_skips_comments_and_empty_lines() {
+        let policy = policy(
+            r#"
+            # comment
+            allow nymtech.net
+
+            deny * 25
+            rate-limit 10 60
+            "#,
+        );
+        assert_eq!(policy.rules.len(), 2);
+        assert!(policy.rate_limit.is_some());
+    }
+
+    #[test]
+    fn parsing_reports_the_invalid_line() {
+        let err = "allow nymtech.net\nblock 10.0.0.0/8"
+            .parse::<RequestPolicy>()
+            .unwrap_err();
+        assert!(matches!(err, PolicyError::UnknownDirective { line: 2, .. }));
+
+        let err = "rate-limit 10 60\nrate-limit 20 60"
+            .parse::<RequestPolicy>()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            PolicyError::DuplicateRateLimit {
+                line: 2,
+                previous: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn deny_rules_take_precedence() {
+        let policy = policy("allow nymtech.net\ndeny *.nymtech.net 25\nallow * 25");
+        assert_eq!(
+            policy.evaluate("nymtech.net:25", &[]),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            policy.evaluate("mail.nymtech.net:25", &[]),
+            PolicyDecision::Deny
+        );
+        assert_eq!(
+            policy.evaluate("mail.nymtech.net:443", &[]),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            policy.evaluate("example.com:25", &[]),
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            policy.evaluate("example.com:443", &[]),
+            PolicyDecision::NoMatch
+        );
+    }
+
+    #[test]
+    fn ip_addresses_are_evaluated_with_their_ports() {
+        let policy = policy("deny 10.0.0.0/8\nallow ::1 8080\nallow * 443");
+        assert_eq!(policy.evaluate("10.1.2.3:443", &[]), PolicyDecision::Deny);
+        assert_eq!(policy.evaluate("1.2.3.4:443", &[]), PolicyDecision::Allow);
+        assert_eq!(policy.evaluate("[::1]:8080", &[]), PolicyDecision::Allow);
+        assert_eq!(policy.evaluate("[::1]:8081", &[]), PolicyDecision::NoMatch);
+        assert_eq!(policy.evaluate("::1", &[]), PolicyDecision::NoMatch);
+    }
+
+    #[test]
+    fn internal_networks_cannot_be_bypassed() {
+        let policy = policy("deny 10.0.0.0/8\nallow * 443");
+        let internal: IpAddr = "10.1.2.3".parse().unwrap();
+        let mapped: IpAddr = "::ffff:10.1.2.3".parse().unwrap();
+
+        // through a domain resolving to an internal address
+        assert_eq!(
+            policy.evaluate("internal.example.com:443", &[internal]),
+            PolicyDecision::Deny
+        );
+        assert_eq!(
+            policy.evaluate("internal.example.com:443", &[mapped]),
+            PolicyDecision::Deny
+        );
+        assert_eq!(
+            policy.evaluate("example.com:443", &["1.2.3.4".parse().unwrap()]),
+            PolicyDecision::Allow
+        );
+
+        // or through an IPv4-mapped IPv6 address
+        assert_eq!(
+            policy.evaluate("[::ffff:10.1.2.3]:443", &[]),
+            PolicyDecision::Deny
+        );
+    }
+
+    #[test]
+    fn resolution_is_only_required_by_networks() {
+        assert!(!policy("allow nymtech.net\ndeny * 25").requires_resolution("nymtech.net:443"));
+        assert!(policy("allow nymtech.net\ndeny 10.0.0.0/8").requires_resolution("nymtech.net:443"));
+    }
+
+    #[test]
+    fn ip_addresses_are_not_resolved() {
+        let policy = policy("deny 10.0.0.0/8");
+        assert!(!policy.requires_resolution("10.1.2.3:443"));
+        assert!(!policy.requires_resolution("[::1]:443"));
+    }
+
+    #[tokio::test]
+    async fn domains_are_checked_against_their_addresses() {
+        let filter = PolicyFilter {
+            policy: Arc::new(RwLock::new(policy(
+                "deny 10.0.0.0/8\ndeny *.internal.com\nallow 1.2.3.0/24",
+            ))),
+            rate_limiter: RateLimiter::default(),
+        };
+
+        let (decision, address_check) = filter.check("internal.com:443").await;
+        assert_eq!(decision, PolicyDecision::NoMatch);
+        let address_check = address_check.unwrap();
+        assert!(
+            !address_check
+                .allows("internal.com:443", &["10.1.2.3:443".parse().unwrap()])
+                .await
+        );
+        assert!(
+            address_check
+                .allows("internal.com:443", &["1.2.3.4:443".parse().unwrap()])
+                .await
+        );
+        assert!(
+            !address_check
+                .allows("internal.com:443", &["5.6.7.8:443".parse().unwrap()])
+                .await
+        );
+        assert!(
+            address_check
+                .with_fallback(true)
+                .allows("internal.com:443", &["5.6.7.8:443".parse().unwrap()])
+                .await
+        );
+
+        // nothing to be checked once the domain has been denied or for the ip addresses
+        let (decision, address_check) = filter.check("db.internal.com:443").await;
+        assert_eq!(decision, PolicyDecision::Deny);
+        assert!(address_check.is_none());
+        let (decision, address_check) = filter.check("10.1.2.3:443").await;
+        assert_eq!(decision, PolicyDecision::Deny);
+        assert!(address_check.is_none());
+    }
+
+    #[test]
+    fn splitting_host_and_port() {
+        assert_eq!(
+            split_host_port("nymtech.net:443"),
+            ("nymtech.net", Some(443))
+        );
+        assert_eq!(split_host_port("nymtech.net"), ("nymtech.net", None));
+        assert_eq!(split_host_port("1.2.3.4:80"), ("1.2.3.4", Some(80)));
+        assert_eq!(split_host_port("[::1]:80"), ("::1", Some(80)));
+        assert_eq!(split_host_port("2001:db8::1"), ("2001:db8::1", None));
+    }
+}
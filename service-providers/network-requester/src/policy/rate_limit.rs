@@ -0,0 +1,173 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::error::PolicyError;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// `rate-limit <max requests> <period in seconds>` line of the policy file.
+/// Every sender is allowed to make at most `max_requests` within every `period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct RateLimit {
+    max_requests: u32,
+    period: Duration,
+}
+
+impl RateLimit {
+    pub(super) fn parse<'a, I>(line: usize, mut args: I) -> Result<Self, PolicyError>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let mut next_number = |argument| {
+            let value = args
+                .next()
+                .ok_or(PolicyError::MissingArgument { line, argument })?;
+            match value.parse() {
+                Ok(number) if number > 0 => Ok(number),
+                _ => Err(PolicyError::InvalidRateLimit {
+                    line,
+                    argument,
+                    value: value.to_string(),
+                }),
+            }
+        };
+
+        let max_requests = next_number("maximum number of requests")?;
+        let period = next_number("period (in seconds)")?;
+
+        if let Some(argument) = args.next() {
+            return Err(PolicyError::UnexpectedArgument {
+                line,
+                argument: argument.to_string(),
+            });
+        }
+
+        Ok(RateLimit {
+            max_requests,
+            period: Duration::from_secs(period as u64),
+        })
+    }
+}
+
+struct Window {
+    started: Instant,
+    requests: u32,
+}
+
+/// Keeps track of the number of requests made by each sender within the current rate limit period.
+#[derive(Default)]
+pub(super) struct RateLimiter {
+    windows: HashMap<String, Window>,
+    last_cleanup: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Records the request made by the sender and returns whether it fits within the limit.
+    pub(super) fn admit(&mut self, sender: &str, limit: &RateLimit) -> bool {
+        self.admit_at(sender, limit, Instant::now())
+    }
+
+    fn admit_at(&mut self, sender: &str, limit: &RateLimit, now: Instant) -> bool {
+        self.remove_expired(limit, now);
+
+        let window = self.windows.entry(sender.to_string()).or_insert(Window {
+            started: now,
+            requests: 0,
+        });
+        if now.duration_since(window.started) >= limit.period {
+            window.started = now;
+            window.requests = 0;
+        }
+
+        if window.requests >= limit.max_requests {
+            return false;
+        }
+        window.requests += 1;
+        true
+    }
+
+    // forget about the senders that haven't made any requests in a while,
+    // so that we wouldn't keep track of every single one we have ever seen
+    fn remove_expired(&mut self, limit: &RateLimit, now: Instant) {
+        match self.last_cleanup {
+            Some(last_cleanup) if now.duration_since(last_cleanup) < limit.period => {}
+            _ => {
+                self.windows
+                    .retain(|_, window| now.duration_since(window.started) < limit.period);
+                self.last_cleanup = Some(now);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(raw: &str) -> RateLimit {
+        RateLimit::parse(1, raw.split_whitespace()).unwrap()
+    }
+
+    #[test]
+    fn parsing_rejects_invalid_limits() {
+        let parse = |raw: &str| RateLimit::parse(1, raw.split_whitespace());
+        assert_eq!(
+            parse("10 60").unwrap(),
+            RateLimit {
+                max_requests: 10,
+                period: Duration::from_secs(60)
+            }
+        );
+        assert!(matches!(
+            parse("10"),
+            Err(PolicyError::MissingArgument { .. })
+        ));
+        assert!(matches!(
+            parse("0 60"),
+            Err(PolicyError::InvalidRateLimit { .. })
+        ));
+        assert!(matches!(
+            parse("ten 60"),
+            Err(PolicyError::InvalidRateLimit { .. })
+        ));
+        assert!(matches!(
+            parse("10 60 5"),
+            Err(PolicyError::UnexpectedArgument { .. })
+        ));
+    }
+
+    #[test]
+    fn senders_are_limited_independently() {
+        let limit = limit("2 60");
+        let mut limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        assert!(limiter.admit_at("alice", &limit, now));
+        assert!(limiter.admit_at("alice", &limit, now));
+        assert!(!limiter.admit_at("alice", &limit, now));
+        assert!(limiter.admit_at("bob", &limit, now));
+    }
+
+    #[test]
+    fn limit_is_reset_after_the_period() {
+        let limit = limit("1 60");
+        let mut limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        assert!(limiter.admit_at("alice", &limit, now));
+        assert!(!limiter.admit_at("alice", &limit, now + Duration::from_secs(59)));
+        assert!(limiter.admit_at("alice", &limit, now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn idle_senders_are_forgotten() {
+        let limit = limit("1 60");
+        let mut limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        limiter.admit_at("alice", &limit, now);
+        limiter.admit_at("bob", &limit, now + Duration::from_secs(61));
+        assert_eq!(limiter.windows.len(), 1);
+        assert!(limiter.windows.contains_key("bob"));
+    }
+}
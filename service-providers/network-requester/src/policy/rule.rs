@@ -0,0 +1,325 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::error::PolicyError;
+use ipnetwork::IpNetwork;
+use regex::{Regex, RegexBuilder};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Action {
+    Allow,
+    Deny,
+}
+
+/// Describes which hosts are covered by a rule.
+#[derive(Debug)]
+pub(super) enum HostPattern {
+    /// `*` - matches every host.
+    Any,
+
+    /// `nymtech.net` - matches the domain itself and all of its subdomains.
+    Domain(String),
+
+    /// `*.nymtech.net` - matches only the subdomains of the domain.
+    Subdomains(String),
+
+    /// `~^api[0-9]+\.nymtech\.net$` - matches hosts the regular expression matches,
+    /// ignoring the case.
+    Regex(Regex),
+
+    /// `10.0.0.0/8` or `1.2.3.4` - matches ip addresses within the network.
+    /// The domains are checked using the addresses they resolve to: a deny rule matches
+    /// if any of them is within the network, while an allow rule requires all of them to be.
+    Network(IpNetwork),
+}
+
+impl HostPattern {
+    fn parse(line: usize, raw: &str) -> Result<Self, PolicyError> {
+        if raw == "*" {
+            return Ok(HostPattern::Any);
+        }
+        if let Some(regex) = raw.strip_prefix('~') {
+            return RegexBuilder::new(regex)
+                .case_insensitive(true)
+                .build()
+                .map(HostPattern::Regex)
+                .map_err(|source| PolicyError::InvalidRegex { line, source });
+        }
+        if let Ok(network) = raw.parse() {
+            return Ok(HostPattern::Network(network));
+        }
+
+        let (pattern, domain) = match raw.strip_prefix("*.") {
+            Some(domain) => (HostPattern::Subdomains(domain.to_lowercase()), domain),
+            None => (HostPattern::Domain(raw.to_lowercase()), raw),
+        };
+        // the remaining wildcards would be silently treated as literal characters otherwise
+        if domain.is_empty() || domain.contains(['*', '/', ':']) {
+            return Err(PolicyError::InvalidHostPattern {
+                line,
+                pattern: raw.to_string(),
+            });
+        }
+        Ok(pattern)
+    }
+
+    /// `addresses` are the (normalised) ip addresses of the host, i.e. either the host itself
+    /// or the addresses it resolved to.
+    fn matches(&self, host: &str, addresses: &[IpAddr], action: Action) -> bool {
+        match self {
+            HostPattern::Any => true,
+            HostPattern::Domain(domain) => {
+                let host = host.to_lowercase();
+                host == *domain || is_subdomain_of(&host, domain)
+            }
+            HostPattern::Subdomains(domain) => is_subdomain_of(&host.to_lowercase(), domain),
+            HostPattern::Regex(regex) => regex.is_match(host),
+            HostPattern::Network(network) => {
+                let mut contained = addresses.iter().map(|address| network.contains(*address));
+                match action {
+                    Action::Deny => contained.any(|contained| contained),
+                    Action::Allow => !addresses.is_empty() && contained.all(|contained| contained),
+                }
+            }
+        }
+    }
+
+    fn is_network(&self) -> bool {
+        matches!(self, HostPattern::Network(_))
+    }
+}
+
+/// Converts the IPv4-mapped IPv6 addresses, such as `::ffff:10.0.0.1`, into plain IPv4 ones,
+/// so that they'd be matched by the IPv4 networks.
+pub(super) fn normalise_address(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+fn is_subdomain_of(host: &str, domain: &str) -> bool {
+    host.strip_suffix(domain)
+        .map(|prefix| prefix.ends_with('.'))
+        .unwrap_or_default()
+}
+
+/// Inclusive range of ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PortRange {
+    start: u16,
+    end: u16,
+}
+
+impl PortRange {
+    /// Parses a comma separated list of ports and port ranges, such as `80,443,8000-9000`.
+    fn parse_list(line: usize, raw: &str) -> Result<Vec<Self>, PolicyError> {
+        let invalid = || PolicyError::InvalidPorts {
+            line,
+            ports: raw.to_string(),
+        };
+
+        raw.split(',')
+            .map(|range| {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                let start = start.parse().map_err(|_| invalid())?;
+                let end = end.parse().map_err(|_| invalid())?;
+                if start > end {
+                    return Err(invalid());
+                }
+                Ok(PortRange { start, end })
+            })
+            .collect()
+    }
+
+    fn contains(&self, port: u16) -> bool {
+        self.start <= port && port <= self.end
+    }
+}
+
+/// Single `allow` or `deny` line of the policy file, i.e. `<action> <host pattern> [ports]`.
+#[derive(Debug)]
+pub(super) struct Rule {
+    pub(super) action: Action,
+    host: HostPattern,
+
+    // if empty, the rule applies to all ports
+    ports: Vec<PortRange>,
+}
+
+impl Rule {
+    pub(super) fn parse<'a, I>(
+        line: usize,
+        action: Action,
+        mut args: I,
+    ) -> Result<Self, PolicyError>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let host = args.next().ok_or(PolicyError::MissingArgument {
+            line,
+            argument: "host pattern",
+        })?;
+        let host = HostPattern::parse(line, host)?;
+        let ports = args
+            .next()
+            .map(|ports| PortRange::parse_list(line, ports))
+            .transpose()?
+            .unwrap_or_default();
+
+        if let Some(argument) = args.next() {
+            return Err(PolicyError::UnexpectedArgument {
+                line,
+                argument: argument.to_string(),
+            });
+        }
+
+        Ok(Rule {
+            action,
+            host,
+            ports,
+        })
+    }
+
+    /// Whether the rule needs to know the addresses the domains resolve to.
+    pub(super) fn matches_addresses(&self) -> bool {
+        self.host.is_network()
+    }
+
+    pub(super) fn matches(&self, host: &str, addresses: &[IpAddr], port: Option<u16>) -> bool {
+        let port_matches = match port {
+            _ if self.ports.is_empty() => true,
+            Some(port) => self.ports.iter().any(|range| range.contains(port)),
+            None => false,
+        };
+        port_matches && self.host.matches(host, addresses, self.action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(raw: &str) -> Rule {
+        Rule::parse(1, Action::Allow, raw.split_whitespace()).unwrap()
+    }
+
+    fn deny_rule(raw: &str) -> Rule {
+        Rule::parse(1, Action::Deny, raw.split_whitespace()).unwrap()
+    }
+
+    fn ip(raw: &str) -> IpAddr {
+        normalise_address(raw.parse().unwrap())
+    }
+
+    #[test]
+    fn domains_match_themselves_and_their_subdomains() {
+        let rule = rule("nymtech.net");
+        assert!(rule.matches("nymtech.net", &[], Some(443)));
+        assert!(rule.matches("foomp.NYMTECH.net", &[], Some(443)));
+        assert!(!rule.matches("notnymtech.net", &[], Some(443)));
+        assert!(!rule.matches("nymtech.net.evil.com", &[], Some(443)));
+    }
+
+    #[test]
+    fn wildcards_only_match_subdomains() {
+        let rule = rule("*.nymtech.net");
+        assert!(rule.matches("foomp.nymtech.net", &[], None));
+        assert!(rule.matches("a.b.nymtech.net", &[], None));
+        assert!(!rule.matches("nymtech.net", &[], None));
+    }
+
+    #[test]
+    fn regexes_match_the_whole_host() {
+        let rule = rule(r"~^api[0-9]+\.nymtech\.net$");
+        assert!(rule.matches("api42.nymtech.net", &[], None));
+        assert!(!rule.matches("api.nymtech.net", &[], None));
+    }
+
+    #[test]
+    fn regexes_ignore_the_case() {
+        let rule = rule(r"~^api[0-9]+\.nymtech\.net$");
+        assert!(rule.matches("API42.NymTech.net", &[], None));
+    }
+
+    #[test]
+    fn networks_match_ip_addresses() {
+        let v4 = rule("10.0.0.0/8");
+        assert!(v4.matches("10.1.2.3", &[ip("10.1.2.3")], None));
+        assert!(!v4.matches("11.1.2.3", &[ip("11.1.2.3")], None));
+        assert!(!v4.matches("nymtech.net", &[], None));
+
+        let v6 = rule("2620:0:2d0:200::7/32");
+        assert!(v6.matches("2620:0:42::42", &[ip("2620:0:42::42")], None));
+        assert!(!v6.matches("2621::", &[ip("2621::")], None));
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_are_normalised() {
+        assert_eq!(ip("::ffff:10.1.2.3"), ip("10.1.2.3"));
+        assert_eq!(ip("::1"), "::1".parse::<IpAddr>().unwrap());
+
+        let rule = deny_rule("10.0.0.0/8");
+        assert!(rule.matches("::ffff:10.1.2.3", &[ip("::ffff:10.1.2.3")], None));
+    }
+
+    #[test]
+    fn networks_match_resolved_domains() {
+        let internal = [ip("10.1.2.3")];
+        let mixed = [ip("1.2.3.4"), ip("10.1.2.3")];
+
+        // any internal address is enough to deny the domain...
+        let deny = deny_rule("10.0.0.0/8");
+        assert!(deny.matches("internal.example.com", &internal, None));
+        assert!(deny.matches("internal.example.com", &mixed, None));
+        assert!(!deny.matches("example.com", &[ip("1.2.3.4")], None));
+
+        // ...but all of them have to be within the network to allow it
+        let allow = rule("10.0.0.0/8");
+        assert!(allow.matches("internal.example.com", &internal, None));
+        assert!(!allow.matches("internal.example.com", &mixed, None));
+        assert!(!allow.matches("internal.example.com", &[], None));
+    }
+
+    #[test]
+    fn ports_restrict_the_rule() {
+        let rule = rule("* 80,443,8000-9000");
+        assert!(rule.matches("nymtech.net", &[], Some(80)));
+        assert!(rule.matches("nymtech.net", &[], Some(8500)));
+        assert!(!rule.matches("nymtech.net", &[], Some(22)));
+        assert!(!rule.matches("nymtech.net", &[], None));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let parse = |raw: &str| Rule::parse(1, Action::Deny, raw.split_whitespace());
+        assert!(matches!(
+            parse(""),
+            Err(PolicyError::MissingArgument { .. })
+        ));
+        assert!(matches!(
+            parse("foo.*.com"),
+            Err(PolicyError::InvalidHostPattern { .. })
+        ));
+        assert!(matches!(
+            parse("~(["),
+            Err(PolicyError::InvalidRegex { .. })
+        ));
+        assert!(matches!(
+            parse("* 443-80"),
+            Err(PolicyError::InvalidPorts { .. })
+        ));
+        assert!(matches!(
+            parse("* 80,http"),
+            Err(PolicyError::InvalidPorts { .. })
+        ));
+        assert!(matches!(
+            parse("* 80 443"),
+            Err(PolicyError::UnexpectedArgument { .. })
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use super::RequestPolicy;
+use nym_task::TaskClient;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+
+const POLICY_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reloads the policy whenever its file gets modified, so that the operator wouldn't have to
+/// restart the network requester to change it.
+pub(crate) struct PolicyWatcher {
+    path: PathBuf,
+    policy: Arc<RwLock<RequestPolicy>>,
+    last_modified: Option<SystemTime>,
+}
+
+impl PolicyWatcher {
+    pub(super) fn new(path: PathBuf, policy: Arc<RwLock<RequestPolicy>>) -> Self {
+        let last_modified = Self::modification_time(&path);
+        PolicyWatcher {
+            path,
+            policy,
+            last_modified,
+        }
+    }
+
+    fn modification_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    async fn reload_if_modified(&mut self) {
+        let last_modified = Self::modification_time(&self.path);
+        if last_modified == self.last_modified {
+            return;
+        }
+        self.last_modified = last_modified;
+
+        // if the new policy is broken, keep on using the old one rather than letting everything through
+        match RequestPolicy::load(&self.path) {
+            Ok(policy) => {
+                log::info!("Reloaded the request policy from {:?}", self.path);
+                *self.policy.write().await = policy;
+            }
+            Err(err) => {
+                log::error!("Failed to reload the request policy, keeping the old one: {err}")
+            }
+        }
+    }
+
+    pub(crate) async fn run(mut self, mut shutdown: TaskClient) {
+        let mut reload_interval = tokio::time::interval(POLICY_RELOAD_INTERVAL);
+
+        while !shutdown.is_shutdown() {
+            tokio::select! {
+                _ = shutdown.recv() => {
+                    log::trace!("PolicyWatcher: Received shutdown");
+                }
+                _ = reload_interval.tick() => self.reload_if_modified().await,
+            }
+        }
+    }
+}
@@ -9,8 +9,9 @@ use service_providers_common::interface::{
     ControlRequest, ControlResponse, ProviderInterfaceVersion, RequestVersion,
 };
 use socks5_requests::{
-    ConnectionId, NetworkData, Socks5ProviderRequest, Socks5ProviderResponse, Socks5Request,
-    Socks5RequestContent, Socks5Response, Socks5ResponseContent,
+    ConnectionErrorKind, ConnectionId, NetworkData, RemoteAddress, Socks5ProviderRequest,
+    Socks5ProviderResponse, Socks5Request, Socks5RequestContent, Socks5Response,
+    Socks5ResponseContent,
 };
 use std::fmt::{Debug, Formatter};
 
@@ -131,6 +132,51 @@ impl MixnetMessage {
         Self::new_provider_response(address, connection_id, msg)
     }
 
+    pub(crate) fn new_connected(
+        address: MixnetAddress,
+        request_version: RequestVersion<Socks5Request>,
+        connection_id: ConnectionId,
+    ) -> Self {
+        let res = Socks5Response::new_connected(request_version.provider_protocol, connection_id);
+        let msg =
+            Socks5ProviderResponse::new_provider_data(request_version.provider_interface, res);
+
+        Self::new_provider_response(address, connection_id, msg)
+    }
+
+    /// Creates a response telling the client why its connection has failed. Clients that don't
+    /// understand those get the plain `ConnectionError` instead.
+    pub(crate) fn new_connection_failure(
+        address: MixnetAddress,
+        request_version: RequestVersion<Socks5Request>,
+        connection_id: ConnectionId,
+        kind: ConnectionErrorKind,
+        error_message: String,
+    ) -> Self {
+        if !request_version
+            .provider_protocol
+            .supports_connection_status()
+        {
+            return Self::new_connection_error(
+                address,
+                request_version,
+                connection_id,
+                error_message,
+            );
+        }
+
+        let res = Socks5Response::new_connection_failure(
+            request_version.provider_protocol,
+            connection_id,
+            kind,
+            error_message,
+        );
+        let msg =
+            Socks5ProviderResponse::new_provider_data(request_version.provider_interface, res);
+
+        Self::new_provider_response(address, connection_id, msg)
+    }
+
     // TODO: the naming is awful, but naming things is difficult...
     pub(crate) fn new_network_data_response_content(
         address: MixnetAddress,
@@ -143,6 +189,39 @@ impl MixnetMessage {
         Self::new_network_data_response(address, request_version, connection_id, response_content)
     }
 
+    pub(crate) fn new_credit_response(
+        address: MixnetAddress,
+        request_version: RequestVersion<Socks5Request>,
+        connection_id: ConnectionId,
+        credit: u32,
+    ) -> Self {
+        let res =
+            Socks5Response::new_credit(request_version.provider_protocol, connection_id, credit);
+        let msg =
+            Socks5ProviderResponse::new_provider_data(request_version.provider_interface, res);
+
+        Self::new_provider_response(address, connection_id, msg)
+    }
+
+    pub(crate) fn new_datagram_response(
+        address: MixnetAddress,
+        request_version: RequestVersion<Socks5Request>,
+        connection_id: ConnectionId,
+        remote_addr: RemoteAddress,
+        data: Vec<u8>,
+    ) -> Self {
+        let res = Socks5Response::new_datagram(
+            request_version.provider_protocol,
+            connection_id,
+            remote_addr,
+            data,
+        );
+        let msg =
+            Socks5ProviderResponse::new_provider_data(request_version.provider_interface, res);
+
+        Self::new_provider_response(address, connection_id, msg)
+    }
+
     pub(crate) fn data_size(&self) -> usize {
         self.data.len()
     }
@@ -174,6 +253,14 @@ impl MixnetAddress {
         None
     }
 
+    /// Identifies the sender for the purposes of rate limiting.
+    pub(crate) fn sender_id(&self) -> String {
+        match self {
+            MixnetAddress::Known(recipient) => recipient.to_string(),
+            MixnetAddress::Anonymous(sender_tag) => sender_tag.to_base58_string(),
+        }
+    }
+
     pub(super) fn send_back_to(self, message: Vec<u8>, connection_id: u64) -> InputMessage {
         match self {
             MixnetAddress::Known(recipient) => InputMessage::Regular {
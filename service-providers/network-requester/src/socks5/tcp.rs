@@ -10,6 +10,7 @@ use proxy_helpers::proxy_runner::{MixProxySender, ProxyRunner};
 use service_providers_common::interface::RequestVersion;
 use socks5_requests::{ConnectionId, RemoteAddress, Socks5Request};
 use std::io;
+use std::net::SocketAddr;
 use tokio::net::TcpStream;
 
 /// An outbound TCP connection between the Socks5 service provider, which makes
@@ -23,13 +24,25 @@ pub(crate) struct Connection {
     return_address: reply::MixnetAddress,
 }
 
+/// Resolves the remote address. It's done separately from connecting, so that the failure
+/// could be told apart from the remote refusing the connection and so that the resolved
+/// addresses could be checked before connecting to any of them.
+pub(crate) async fn resolve(address: &str) -> io::Result<Vec<SocketAddr>> {
+    Ok(tokio::net::lookup_host(address)
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))?
+        .collect())
+}
+
 impl Connection {
+    /// Connects to the first of the already resolved addresses of the remote that accepts the connection.
     pub(crate) async fn new(
         id: ConnectionId,
         address: RemoteAddress,
+        resolved: &[SocketAddr],
         return_address: reply::MixnetAddress,
     ) -> io::Result<Self> {
-        let conn = TcpStream::connect(&address).await?;
+        let conn = TcpStream::connect(resolved).await?;
 
         Ok(Connection {
             id,
@@ -51,6 +64,24 @@ impl Connection {
         let remote_source_address = "???".to_string(); // we don't know ip address of requester
         let connection_id = self.id;
         let return_address = self.return_address.clone();
+
+        // only grant the client credit if it knows what to do with it
+        let credit_adapter_fn = remote_version
+            .provider_protocol
+            .supports_flow_control()
+            .then(|| {
+                let return_address = return_address.clone();
+                let remote_version = remote_version.clone();
+                move |conn_id, credit| {
+                    MixnetMessage::new_credit_response(
+                        return_address.clone(),
+                        remote_version.clone(),
+                        conn_id,
+                        credit,
+                    )
+                }
+            });
+
         let (stream, _) = ProxyRunner::new(
             stream,
             self.address.clone(),
@@ -61,15 +92,18 @@ impl Connection {
             Some(lane_queue_lengths),
             shutdown,
         )
-        .run(move |conn_id, read_data, socket_closed| {
-            MixnetMessage::new_network_data_response_content(
-                return_address.clone(),
-                remote_version.clone(),
-                conn_id,
-                read_data,
-                socket_closed,
-            )
-        })
+        .run(
+            move |conn_id, read_data, socket_closed| {
+                MixnetMessage::new_network_data_response_content(
+                    return_address.clone(),
+                    remote_version.clone(),
+                    conn_id,
+                    read_data,
+                    socket_closed,
+                )
+            },
+            credit_adapter_fn,
+        )
         .await
         .into_inner();
         self.conn = Some(stream);
@@ -0,0 +1,212 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::policy::AddressCheck;
+use crate::reply;
+use crate::reply::MixnetMessage;
+use futures::channel::mpsc;
+use futures::StreamExt;
+use log::{debug, info, trace, warn};
+use nym_task::TaskClient;
+use proxy_helpers::connection_controller::Datagram;
+use proxy_helpers::proxy_runner::MixProxySender;
+use service_providers_common::interface::RequestVersion;
+use socks5_requests::{ConnectionErrorKind, ConnectionId, Socks5Request};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::{sleep_until, Instant};
+
+// maximum size of the UDP payload
+const MAX_UDP_DATAGRAM_SIZE: usize = 65535;
+
+/// Period of inactivity after which the association is going to be torn down.
+/// The client doesn't tell us when it's done so this is the only way of cleaning up.
+const UDP_ASSOCIATION_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// For how long the resolved remote addresses are reused before resolving them again.
+const RESOLVED_REMOTE_TTL: Duration = Duration::from_secs(30);
+
+/// Maximum number of the resolved remote addresses cached by a single association.
+const MAX_RESOLVED_REMOTES: usize = 64;
+
+pub(crate) type OutboundDatagramSender = mpsc::UnboundedSender<OutboundDatagram>;
+pub(crate) type OutboundDatagramReceiver = mpsc::UnboundedReceiver<OutboundDatagram>;
+
+/// Datagram to be relayed to the remote alongside the part of the policy check
+/// that has to be done against the address the remote resolves to.
+pub(crate) struct OutboundDatagram {
+    pub(crate) datagram: Datagram,
+    pub(crate) address_check: Option<AddressCheck>,
+}
+
+struct ResolvedRemote {
+    // we're bound to an IPv4 socket, so we can't use any IPv6 addresses
+    address: Option<SocketAddr>,
+    resolved_at: Instant,
+}
+
+/// An UDP association between the Socks5 service provider and remote hosts, which relays
+/// the datagrams received through the mixnet and sends back whatever those hosts respond with.
+pub(crate) struct UdpAssociation {
+    id: ConnectionId,
+    socket: UdpSocket,
+    return_address: reply::MixnetAddress,
+    remote_version: RequestVersion<Socks5Request>,
+
+    // only the hosts we have sent something to are allowed to send datagrams back
+    known_remotes: HashSet<SocketAddr>,
+
+    // so that we wouldn't have to resolve the remote for every single datagram
+    resolved_remotes: HashMap<String, ResolvedRemote>,
+}
+
+impl UdpAssociation {
+    pub(crate) async fn new(
+        id: ConnectionId,
+        return_address: reply::MixnetAddress,
+        remote_version: RequestVersion<Socks5Request>,
+    ) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+
+        Ok(UdpAssociation {
+            id,
+            socket,
+            return_address,
+            remote_version,
+            known_remotes: HashSet::new(),
+            resolved_remotes: HashMap::new(),
+        })
+    }
+
+    async fn resolve(&mut self, remote_addr: &str) -> Option<SocketAddr> {
+        if let Some(resolved) = self.resolved_remotes.get(remote_addr) {
+            if resolved.resolved_at.elapsed() < RESOLVED_REMOTE_TTL {
+                return resolved.address;
+            }
+        }
+
+        let address = match tokio::net::lookup_host(remote_addr).await {
+            Ok(mut addresses) => addresses.find(|address| address.is_ipv4()),
+            Err(err) => {
+                debug!("failed to resolve {remote_addr}: {err}");
+                None
+            }
+        };
+
+        if self.resolved_remotes.len() >= MAX_RESOLVED_REMOTES {
+            self.resolved_remotes
+                .retain(|_, resolved| resolved.resolved_at.elapsed() < RESOLVED_REMOTE_TTL);
+        }
+        if self.resolved_remotes.len() < MAX_RESOLVED_REMOTES {
+            self.resolved_remotes.insert(
+                remote_addr.to_string(),
+                ResolvedRemote {
+                    address,
+                    resolved_at: Instant::now(),
+                },
+            );
+        }
+        address
+    }
+
+    /// Sends the datagram to the remote, unless the address it resolves to is denied by the policy,
+    /// in which case the returned error describes the reason.
+    async fn send_to_remote(&mut self, outbound: OutboundDatagram) -> Result<(), String> {
+        let datagram = outbound.datagram;
+        let Some(remote) = self.resolve(&datagram.remote_addr).await else {
+            debug!("{} has no IPv4 address", datagram.remote_addr);
+            return Ok(());
+        };
+
+        if let Some(address_check) = outbound.address_check {
+            if !address_check.allows(&datagram.remote_addr, &[remote]).await {
+                return Err(format!(
+                    "Domain {:?} resolves to an address denied by the policy",
+                    datagram.remote_addr
+                ));
+            }
+        }
+
+        match self.socket.send_to(&datagram.data, remote).await {
+            Ok(_) => {
+                self.known_remotes.insert(remote);
+            }
+            Err(err) => warn!("failed to send datagram to {remote}: {err}"),
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn run(
+        mut self,
+        mut mix_receiver: OutboundDatagramReceiver,
+        mix_sender: MixProxySender<MixnetMessage>,
+        mut shutdown: TaskClient,
+    ) {
+        shutdown.mark_as_success();
+        let mut buf = vec![0u8; MAX_UDP_DATAGRAM_SIZE];
+        let mut last_activity = Instant::now();
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => {
+                    trace!("UdpAssociation: Received shutdown");
+                    break;
+                }
+                datagram = mix_receiver.next() => match datagram {
+                    Some(datagram) => {
+                        last_activity = Instant::now();
+                        if let Err(log_msg) = self.send_to_remote(datagram).await {
+                            info!("{log_msg}");
+                            let failure = MixnetMessage::new_connection_failure(
+                                self.return_address.clone(),
+                                self.remote_version.clone(),
+                                self.id,
+                                ConnectionErrorKind::NotAllowed,
+                                log_msg,
+                            );
+                            if mix_sender.send(failure).await.is_err() {
+                                warn!("InputMessageReceiver has stopped receiving!");
+                                break;
+                            }
+                        }
+                    }
+                    None => break,
+                },
+                received = self.socket.recv_from(&mut buf) => {
+                    let (n, source) = match received {
+                        Ok(received) => received,
+                        Err(err) => {
+                            warn!("failed to receive datagram: {err}");
+                            continue;
+                        }
+                    };
+                    if !self.known_remotes.contains(&source) {
+                        debug!("ignoring datagram from unknown address {source}");
+                        continue;
+                    }
+                    last_activity = Instant::now();
+
+                    let response = MixnetMessage::new_datagram_response(
+                        self.return_address.clone(),
+                        self.remote_version.clone(),
+                        self.id,
+                        source.to_string(),
+                        buf[..n].to_vec(),
+                    );
+                    if mix_sender.send(response).await.is_err() {
+                        warn!("InputMessageReceiver has stopped receiving!");
+                        break;
+                    }
+                }
+                _ = sleep_until(last_activity + UDP_ASSOCIATION_IDLE_TIMEOUT) => {
+                    debug!("UDP association {} has been idle for too long", self.id);
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    cli::{override_config, OverrideConfig},
+    config::Config,
+    error::PubSubProviderError,
+};
+use clap::Args;
+use config::NymConfig;
+use nym_bin_common::version_checker;
+use std::time::Duration;
+
+const DEFAULT_SUBSCRIPTION_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Args, Clone)]
+pub(crate) struct Run {
+    /// Id of the nym-mixnet-client we want to run.
+    #[clap(long)]
+    id: String,
+
+    /// Number of seconds after which a subscription expires unless the subscriber renews it.
+    #[clap(long, default_value_t = DEFAULT_SUBSCRIPTION_TTL_SECS)]
+    subscription_ttl: u64,
+
+    /// Set this client to work in a enabled credentials mode that would attempt to use gateway
+    /// with bandwidth credential requirement.
+    #[clap(long)]
+    enabled_credentials_mode: Option<bool>,
+
+    /// Mostly debug-related option to increase default traffic rate so that you would not need to
+    /// modify config post init
+    #[clap(long, hide = true)]
+    fastmode: bool,
+
+    /// Disable loop cover traffic and the Poisson rate limiter (for debugging only)
+    #[clap(long, hide = true)]
+    no_cover: bool,
+}
+
+impl From<Run> for OverrideConfig {
+    fn from(run_config: Run) -> Self {
+        OverrideConfig {
+            nym_apis: None,
+            fastmode: run_config.fastmode,
+            no_cover: run_config.no_cover,
+            nyxd_urls: None,
+            enabled_credentials_mode: run_config.enabled_credentials_mode,
+        }
+    }
+}
+
+// this only checks compatibility between config the binary. It does not take into consideration
+// network version. It might do so in the future.
+fn version_check(cfg: &Config) -> bool {
+    let binary_version = env!("CARGO_PKG_VERSION");
+    let config_version = cfg.get_base().get_version();
+    if binary_version == config_version {
+        true
+    } else {
+        log::warn!(
+            "The pub/sub provider binary has different version than what is specified \
+            in config file! {} and {}",
+            binary_version,
+            config_version
+        );
+        if version_checker::is_minor_version_compatible(binary_version, config_version) {
+            log::info!(
+                "but they are still semver compatible. \
+                However, consider running the `upgrade` command"
+            );
+            true
+        } else {
+            log::error!(
+                "and they are semver incompatible! - \
+                please run the `upgrade` command before attempting `run` again"
+            );
+            false
+        }
+    }
+}
+
+pub(crate) async fn execute(args: &Run) -> Result<(), PubSubProviderError> {
+    let id = &args.id;
+
+    let mut config = match Config::load_from_file(id) {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            log::error!(
+                "Failed to load config for {}. \
+                Are you sure you have run `init` before? (Error was: {err})",
+                id
+            );
+            return Err(PubSubProviderError::FailedToLoadConfig(id.to_string()));
+        }
+    };
+
+    let override_config_fields = OverrideConfig::from(args.clone());
+    config = override_config(config, override_config_fields);
+
+    if config.get_base_mut().set_empty_fields_to_defaults() {
+        log::warn!(
+            "Some of the core config options were left unset. \
+            The default values are going to get used instead."
+        );
+    }
+
+    if !version_check(&config) {
+        log::error!("Failed the local version check");
+        return Err(PubSubProviderError::FailedLocalVersionCheck);
+    }
+
+    log::info!("Starting pub/sub service provider");
+    let subscription_ttl = Duration::from_secs(args.subscription_ttl);
+    let provider = crate::core::PubSubServiceProvider::new(config, subscription_ttl).await?;
+    provider.run().await
+}
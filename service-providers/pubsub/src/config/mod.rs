@@ -0,0 +1,199 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::template::config_template;
+use client_core::config::ClientCoreConfigTrait;
+use config::{NymConfig, OptionalSet};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub use client_core::config::Config as BaseConfig;
+pub use client_core::config::MISSING_VALUE;
+pub use client_core::config::{DebugConfig, GatewayEndpointConfig};
+
+mod template;
+
+const DEFAULT_MAX_PAYLOAD_SIZE: usize = 32 * 1024;
+const DEFAULT_MAX_TOPICS: usize = 10_000;
+const DEFAULT_MAX_SUBSCRIBERS_PER_TOPIC: usize = 1_000;
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(flatten)]
+    base: BaseConfig<Config>,
+
+    #[serde(default)]
+    pubsub: PubSub,
+}
+
+impl NymConfig for Config {
+    fn template() -> &'static str {
+        config_template()
+    }
+
+    // TODO: merge base dir with `HostStore`.
+    fn default_root_directory() -> PathBuf {
+        dirs::home_dir()
+            .expect("Failed to evaluate $HOME value")
+            .join(".nym")
+            .join("service-providers")
+            .join("pubsub")
+    }
+
+    fn try_default_root_directory() -> Option<PathBuf> {
+        dirs::home_dir().map(|path| path.join(".nym").join("service-providers").join("pubsub"))
+    }
+
+    fn root_directory(&self) -> PathBuf {
+        self.base.get_nym_root_directory()
+    }
+
+    fn config_directory(&self) -> PathBuf {
+        self.root_directory()
+            .join(self.base.get_id())
+            .join("config")
+    }
+
+    fn data_directory(&self) -> PathBuf {
+        self.root_directory().join(self.base.get_id()).join("data")
+    }
+}
+
+impl ClientCoreConfigTrait for Config {
+    fn get_gateway_endpoint(&self) -> &client_core::config::GatewayEndpointConfig {
+        self.base.get_gateway_endpoint()
+    }
+
+    fn set_gateway_endpoints(
+        &mut self,
+        gateway_endpoint: client_core::config::GatewayEndpointConfig,
+        standby_gateways: Vec<client_core::config::GatewayEndpointConfig>,
+    ) {
+        self.base
+            .set_gateway_endpoints(gateway_endpoint, standby_gateways)
+    }
+}
+
+impl Config {
+    pub fn new<S: Into<String>>(id: S) -> Self {
+        Config {
+            base: BaseConfig::new(id),
+            pubsub: PubSub::default(),
+        }
+    }
+
+    // getters
+    pub fn get_config_file_save_location(&self) -> PathBuf {
+        self.config_directory().join(Self::config_file_name())
+    }
+
+    pub fn get_base(&self) -> &BaseConfig<Self> {
+        &self.base
+    }
+
+    pub fn get_base_mut(&mut self) -> &mut BaseConfig<Self> {
+        &mut self.base
+    }
+
+    pub fn get_max_payload_size(&self) -> usize {
+        self.pubsub.max_payload_size
+    }
+
+    pub fn get_max_topics(&self) -> usize {
+        self.pubsub.max_topics
+    }
+
+    pub fn get_max_subscribers_per_topic(&self) -> usize {
+        self.pubsub.max_subscribers_per_topic
+    }
+
+    pub fn get_restricted_topics(&self) -> &[RestrictedTopic] {
+        &self.pubsub.restricted_topics
+    }
+
+    // poor man's 'builder' method
+    pub fn with_base<F, T>(mut self, f: F, val: T) -> Self
+    where
+        F: Fn(BaseConfig<Self>, T) -> BaseConfig<Self>,
+    {
+        self.base = f(self.base, val);
+        self
+    }
+
+    // helper methods to use `OptionalSet` trait. Those are defined due to very... ehm. 'specific' structure of this config
+    // (plz, lets refactor it)
+    pub fn with_optional_ext<F, T>(mut self, f: F, val: Option<T>) -> Self
+    where
+        F: Fn(BaseConfig<Self>, T) -> BaseConfig<Self>,
+    {
+        self.base = self.base.with_optional(f, val);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_optional_env_ext<F, T>(mut self, f: F, val: Option<T>, env_var: &str) -> Self
+    where
+        F: Fn(BaseConfig<Self>, T) -> BaseConfig<Self>,
+        T: FromStr,
+        <T as FromStr>::Err: Debug,
+    {
+        self.base = self.base.with_optional_env(f, val, env_var);
+        self
+    }
+
+    pub fn with_optional_custom_env_ext<F, T, G>(
+        mut self,
+        f: F,
+        val: Option<T>,
+        env_var: &str,
+        parser: G,
+    ) -> Self
+    where
+        F: Fn(BaseConfig<Self>, T) -> BaseConfig<Self>,
+        G: Fn(&str) -> T,
+    {
+        self.base = self.base.with_optional_custom_env(f, val, env_var, parser);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PubSub {
+    /// Maximum size, in bytes, of a single published payload.
+    max_payload_size: usize,
+
+    /// Maximum number of topics that can have subscribers at the same time.
+    max_topics: usize,
+
+    /// Maximum number of subscribers of a single topic.
+    max_subscribers_per_topic: usize,
+
+    /// Topics that only the specified publishers are allowed to publish to.
+    #[serde(default)]
+    restricted_topics: Vec<RestrictedTopic>,
+}
+
+impl Default for PubSub {
+    fn default() -> Self {
+        PubSub {
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            max_topics: DEFAULT_MAX_TOPICS,
+            max_subscribers_per_topic: DEFAULT_MAX_SUBSCRIBERS_PER_TOPIC,
+            restricted_topics: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestrictedTopic {
+    /// Name of the restricted topic.
+    pub topic: String,
+
+    /// Base58 encoded identity keys of the publishers allowed to publish to the topic.
+    pub publishers: Vec<String>,
+}
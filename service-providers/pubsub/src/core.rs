@@ -0,0 +1,290 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::Config;
+use crate::error::PubSubProviderError;
+use crate::publishers::PublisherPolicy;
+use crate::subscriptions::Subscriptions;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use nym_bin_common::build_information::BinaryBuildInformation;
+use nym_sdk::mixnet::{InputMessage, MixnetClient, MixnetClientSender};
+use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+use nym_task::connections::TransmissionLane;
+use pubsub_requests::{
+    PubSubProviderRequest, PubSubProviderResponse, PubSubRequest, PubSubRequestContent,
+    PubSubResponse, PublisherSignature, Topic,
+};
+use service_providers_common::interface::{BinaryInformation, ProviderInterfaceVersion, Request};
+use service_providers_common::ServiceProvider;
+use std::time::{Duration, Instant};
+
+/// How often the subscriptions that haven't been renewed in time get removed.
+const EXPIRED_SUBSCRIPTIONS_CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(crate) struct PubSubServiceProvider {
+    mixnet_client: MixnetClient,
+    mixnet_client_sender: MixnetClientSender,
+    subscriptions: Subscriptions,
+    publishers: PublisherPolicy,
+    max_payload_size: usize,
+}
+
+#[async_trait]
+impl ServiceProvider<PubSubRequest> for PubSubServiceProvider {
+    type ServiceProviderError = PubSubProviderError;
+
+    async fn on_request(
+        &mut self,
+        sender: Option<AnonymousSenderTag>,
+        request: Request<PubSubRequest>,
+    ) -> Result<(), Self::ServiceProviderError> {
+        if let Some(response) = self.handle_request(sender, request).await? {
+            if let Some(sender) = sender {
+                self.send_reply(sender, response.into_bytes()).await;
+            } else {
+                debug!(
+                    "the request didn't contain any reply SURBs - there's no way to respond to it"
+                )
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_binary_info_control_request(
+        &self,
+    ) -> Result<BinaryInformation, Self::ServiceProviderError> {
+        Ok(BinaryInformation {
+            binary_name: env!("CARGO_PKG_NAME").to_string(),
+            build_information: BinaryBuildInformation::new(env!("CARGO_PKG_VERSION")).to_owned(),
+        })
+    }
+
+    async fn handle_provider_data_request(
+        &mut self,
+        sender: Option<AnonymousSenderTag>,
+        request: PubSubRequest,
+        interface_version: ProviderInterfaceVersion,
+    ) -> Result<Option<PubSubResponse>, Self::ServiceProviderError> {
+        log::debug!(
+            "received request of version {:?} (interface) / {:?} (pubsub)",
+            interface_version,
+            request.protocol_version
+        );
+
+        let response = match request.content {
+            PubSubRequestContent::Subscribe(topic) => self.handle_subscribe(sender, topic),
+            PubSubRequestContent::Unsubscribe(topic) => self.handle_unsubscribe(sender, topic),
+            PubSubRequestContent::Publish {
+                topic,
+                payload,
+                authorisation,
+            } => {
+                self.handle_publish(topic, payload, authorisation.as_ref())
+                    .await
+            }
+        };
+        Ok(Some(response))
+    }
+}
+
+impl PubSubServiceProvider {
+    pub(crate) async fn new(
+        config: Config,
+        subscription_ttl: Duration,
+    ) -> Result<Self, PubSubProviderError> {
+        let publishers = PublisherPolicy::new(config.get_restricted_topics())?;
+        info!(
+            "publishing to {} topics is restricted",
+            publishers.restricted_topics()
+        );
+
+        let mixnet_client = create_mixnet_client(config.get_base()).await?;
+        let mixnet_client_sender = mixnet_client.sender();
+
+        Ok(PubSubServiceProvider {
+            mixnet_client,
+            mixnet_client_sender,
+            subscriptions: Subscriptions::new(
+                subscription_ttl,
+                config.get_max_topics(),
+                config.get_max_subscribers_per_topic(),
+            ),
+            publishers,
+            max_payload_size: config.get_max_payload_size(),
+        })
+    }
+
+    pub(crate) async fn run(mut self) -> Result<(), PubSubProviderError> {
+        info!(
+            "The address of this client is: {}",
+            self.mixnet_client.nym_address()
+        );
+        info!("All systems go. Press CTRL-C to stop the server.");
+
+        let mut cleanup_interval = tokio::time::interval(EXPIRED_SUBSCRIPTIONS_CLEANUP_INTERVAL);
+
+        // TODO: incorporate graceful shutdowns
+        loop {
+            tokio::select! {
+                reconstructed_messages = self.mixnet_client.wait_for_messages() => {
+                    let Some(reconstructed_messages) = reconstructed_messages else {
+                        break;
+                    };
+                    for reconstructed in reconstructed_messages {
+                        let sender = reconstructed.sender_tag;
+                        let request = PubSubProviderRequest::try_from_bytes(&reconstructed.message);
+                        let request = match request {
+                            Ok(req) => req,
+                            Err(err) => {
+                                warn!("Failed to deserialize received message: {err}");
+                                continue;
+                            }
+                        };
+
+                        if let Err(err) = self.on_request(sender, request).await {
+                            warn!("failed to resolve the received request: {err}");
+                        }
+                    }
+                }
+                _ = cleanup_interval.tick() => {
+                    let removed = self.subscriptions.remove_expired(Instant::now());
+                    if removed > 0 {
+                        info!("removed {removed} expired subscriptions");
+                    }
+                    debug!(
+                        "currently there are {} subscriptions across {} topics",
+                        self.subscriptions.total_subscriptions(),
+                        self.subscriptions.topics()
+                    );
+                }
+            }
+        }
+
+        log::error!("Pub/sub provider exited unexpectedly");
+        Ok(())
+    }
+
+    async fn send_reply(&mut self, recipient: AnonymousSenderTag, data: Vec<u8>) {
+        // if the data doesn't fit in the reply SURBs we have got, the reply controller
+        // is going to request more of them from the recipient
+        let input_message = InputMessage::new_reply(recipient, data, TransmissionLane::General);
+        self.mixnet_client_sender
+            .send_input_message(input_message)
+            .await;
+    }
+
+    fn handle_subscribe(
+        &mut self,
+        sender: Option<AnonymousSenderTag>,
+        topic: Topic,
+    ) -> PubSubResponse {
+        let Some(sender) = sender else {
+            // there's no way of delivering anything to this subscriber
+            return PubSubResponse::new_error(
+                topic,
+                "subscriptions require attaching reply SURBs".to_string(),
+            );
+        };
+
+        match self
+            .subscriptions
+            .subscribe(topic.clone(), sender, Instant::now())
+        {
+            Ok(()) => {
+                debug!("{sender} subscribed to '{topic}'");
+                PubSubResponse::new_subscribed(topic)
+            }
+            Err(err) => {
+                debug!("rejected the subscription of {sender} to '{topic}': {err}");
+                PubSubResponse::new_error(topic, err.to_string())
+            }
+        }
+    }
+
+    fn handle_unsubscribe(
+        &mut self,
+        sender: Option<AnonymousSenderTag>,
+        topic: Topic,
+    ) -> PubSubResponse {
+        match sender {
+            Some(sender) if self.subscriptions.unsubscribe(&topic, &sender) => {
+                debug!("{sender} unsubscribed from '{topic}'");
+                PubSubResponse::new_unsubscribed(topic)
+            }
+            _ => PubSubResponse::new_error(topic, "no such subscription".to_string()),
+        }
+    }
+
+    async fn handle_publish(
+        &mut self,
+        topic: Topic,
+        payload: Vec<u8>,
+        authorisation: Option<&PublisherSignature>,
+    ) -> PubSubResponse {
+        // the payload is going to get copied to every subscriber, so don't let it grow unbounded
+        if payload.len() > self.max_payload_size {
+            return PubSubResponse::new_error(
+                topic,
+                format!(
+                    "the payload is {} bytes long, while at most {} are allowed",
+                    payload.len(),
+                    self.max_payload_size
+                ),
+            );
+        }
+
+        if !self.publishers.may_publish(&topic, &payload, authorisation) {
+            debug!("rejected an unauthorised publish to '{topic}'");
+            return PubSubResponse::new_error(
+                topic,
+                "the topic requires a signature of one of its publishers".to_string(),
+            );
+        }
+
+        let subscribers = self.subscriptions.subscribers(&topic, Instant::now());
+        debug!(
+            "publishing {} bytes to {} subscribers of '{topic}'",
+            payload.len(),
+            subscribers.len()
+        );
+
+        for subscriber in &subscribers {
+            let message = PubSubProviderResponse::new_provider_data(
+                ProviderInterfaceVersion::new_current(),
+                PubSubResponse::new_message(topic.clone(), payload.clone()),
+            );
+            self.send_reply(*subscriber, message.into_bytes()).await;
+        }
+
+        PubSubResponse::new_published(topic, subscribers.len() as u32)
+    }
+}
+
+async fn create_mixnet_client<T>(
+    config: &client_core::config::Config<T>,
+) -> Result<MixnetClient, PubSubProviderError> {
+    let nym_api_endpoints = config.get_nym_api_endpoints();
+    let debug_config = config.get_debug_config().clone();
+
+    let mixnet_config = nym_sdk::mixnet::Config {
+        user_chosen_gateway: None,
+        nym_api_endpoints,
+        debug_config,
+    };
+
+    let storage_paths = nym_sdk::mixnet::StoragePaths::from(config);
+
+    let mixnet_client = nym_sdk::mixnet::MixnetClientBuilder::new()
+        .config(mixnet_config)
+        .enable_storage(storage_paths)
+        .gateway_config(config.get_gateway_endpoint_config().clone())
+        .build::<nym_sdk::mixnet::ReplyStorage>()
+        .await
+        .map_err(|err| PubSubProviderError::FailedToSetupMixnetClient { source: err })?;
+
+    mixnet_client
+        .connect_to_mixnet()
+        .await
+        .map_err(|err| PubSubProviderError::FailedToConnectToMixnet { source: err })
+}
@@ -0,0 +1,45 @@
+use client_core::error::ClientCoreError;
+use pubsub_requests::PubSubRequestError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PubSubProviderError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("client-core error: {0}")]
+    ClientCoreError(#[from] ClientCoreError),
+
+    #[error("encountered an error while trying to handle a provider request: {source}")]
+    ProviderRequestError {
+        #[from]
+        source: PubSubRequestError,
+    },
+
+    #[error("failed to setup gateway: {source}")]
+    FailedToSetupGateway { source: ClientCoreError },
+
+    #[error("failed to load configuration file: {0}")]
+    FailedToLoadConfig(String),
+
+    #[error("failed local version check, client and config mismatch")]
+    FailedLocalVersionCheck,
+
+    #[error("the restricted topic '{topic}' is invalid: {source}")]
+    InvalidRestrictedTopic {
+        topic: String,
+        source: PubSubRequestError,
+    },
+
+    #[error("the publisher key '{key}' of the restricted topic '{topic}' is invalid: {source}")]
+    InvalidPublisherKey {
+        topic: String,
+        key: String,
+        source: nym_crypto::asymmetric::identity::Ed25519RecoveryError,
+    },
+
+    #[error("failed to setup mixnet client: {source}")]
+    FailedToSetupMixnetClient { source: nym_sdk::Error },
+
+    #[error("failed to connect to mixnet: {source}")]
+    FailedToConnectToMixnet { source: nym_sdk::Error },
+}
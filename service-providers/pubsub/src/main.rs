@@ -0,0 +1,26 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::{crate_name, crate_version, Parser};
+use nym_bin_common::logging::{banner, setup_logging};
+use nym_network_defaults::setup_env;
+
+use error::PubSubProviderError;
+
+mod cli;
+mod config;
+mod core;
+mod error;
+mod publishers;
+mod subscriptions;
+
+#[tokio::main]
+async fn main() -> Result<(), PubSubProviderError> {
+    setup_logging();
+    println!("{}", banner(crate_name!(), crate_version!()));
+
+    let args = cli::Cli::parse();
+    setup_env(args.config_env_file.as_ref());
+
+    cli::execute(args).await
+}
@@ -0,0 +1,132 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::RestrictedTopic;
+use crate::error::PubSubProviderError;
+use nym_crypto::asymmetric::identity;
+use pubsub_requests::{PublisherSignature, Topic};
+use std::collections::HashMap;
+
+/// Decides who is allowed to publish to which topic. Topics that haven't been restricted
+/// are open to everyone, while publishing to the restricted ones requires a valid signature
+/// made with the identity key of one of their publishers.
+#[derive(Debug, Default)]
+pub(crate) struct PublisherPolicy {
+    restricted_topics: HashMap<Topic, Vec<identity::PublicKey>>,
+}
+
+impl PublisherPolicy {
+    pub(crate) fn new(restricted_topics: &[RestrictedTopic]) -> Result<Self, PubSubProviderError> {
+        let mut policy = PublisherPolicy::default();
+        for restricted in restricted_topics {
+            let topic = Topic::new(restricted.topic.clone()).map_err(|source| {
+                PubSubProviderError::InvalidRestrictedTopic {
+                    topic: restricted.topic.clone(),
+                    source,
+                }
+            })?;
+
+            let publishers = policy.restricted_topics.entry(topic).or_default();
+            for key in &restricted.publishers {
+                let publisher = identity::PublicKey::from_base58_string(key).map_err(|source| {
+                    PubSubProviderError::InvalidPublisherKey {
+                        topic: restricted.topic.clone(),
+                        key: key.clone(),
+                        source,
+                    }
+                })?;
+                publishers.push(publisher);
+            }
+        }
+        Ok(policy)
+    }
+
+    pub(crate) fn restricted_topics(&self) -> usize {
+        self.restricted_topics.len()
+    }
+
+    /// Checks whether the payload is allowed to get published to the topic.
+    pub(crate) fn may_publish(
+        &self,
+        topic: &Topic,
+        payload: &[u8],
+        authorisation: Option<&PublisherSignature>,
+    ) -> bool {
+        let Some(publishers) = self.restricted_topics.get(topic) else {
+            return true;
+        };
+        let Some(authorisation) = authorisation else {
+            return false;
+        };
+
+        publishers.contains(&authorisation.publisher) && authorisation.verify(topic, payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topic(name: &str) -> Topic {
+        name.parse().unwrap()
+    }
+
+    fn publisher() -> identity::KeyPair {
+        identity::KeyPair::new(&mut rand::rngs::OsRng)
+    }
+
+    fn restricted(topic: &str, publishers: &[&identity::KeyPair]) -> RestrictedTopic {
+        RestrictedTopic {
+            topic: topic.to_string(),
+            publishers: publishers
+                .iter()
+                .map(|keys| keys.public_key().to_base58_string())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn unrestricted_topics_are_open_to_everyone() {
+        let alice = publisher();
+        let policy = PublisherPolicy::new(&[restricted("news", &[&alice])]).unwrap();
+
+        assert!(policy.may_publish(&topic("weather"), b"sunny", None));
+        let signature = PublisherSignature::sign(&publisher(), &topic("weather"), b"sunny");
+        assert!(policy.may_publish(&topic("weather"), b"sunny", Some(&signature)));
+    }
+
+    #[test]
+    fn restricted_topics_require_signature_of_allowed_publisher() {
+        let (alice, bob) = (publisher(), publisher());
+        let policy = PublisherPolicy::new(&[restricted("news", &[&alice, &bob])]).unwrap();
+
+        assert!(!policy.may_publish(&topic("news"), b"hello", None));
+
+        for keys in [&alice, &bob] {
+            let signature = PublisherSignature::sign(keys, &topic("news"), b"hello");
+            assert!(policy.may_publish(&topic("news"), b"hello", Some(&signature)));
+
+            // the signature doesn't cover any other payload
+            assert!(!policy.may_publish(&topic("news"), b"bye", Some(&signature)));
+        }
+
+        let eve = publisher();
+        let signature = PublisherSignature::sign(&eve, &topic("news"), b"hello");
+        assert!(!policy.may_publish(&topic("news"), b"hello", Some(&signature)));
+    }
+
+    #[test]
+    fn invalid_restrictions_are_rejected() {
+        assert!(matches!(
+            PublisherPolicy::new(&[restricted("", &[&publisher()])]),
+            Err(PubSubProviderError::InvalidRestrictedTopic { .. })
+        ));
+
+        let mut invalid_key = restricted("news", &[&publisher()]);
+        invalid_key.publishers.push("not a key".to_string());
+        assert!(matches!(
+            PublisherPolicy::new(&[invalid_key]),
+            Err(PubSubProviderError::InvalidPublisherKey { .. })
+        ));
+    }
+}
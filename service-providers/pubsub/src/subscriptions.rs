@@ -0,0 +1,259 @@
+// Copyright 2023 - Nym Technologies SA <contact@nymtech.net>
+// SPDX-License-Identifier: Apache-2.0
+
+use nym_sphinx::anonymous_replies::requests::AnonymousSenderTag;
+use pubsub_requests::Topic;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SubscriptionError {
+    TooManyTopics { max: usize },
+    TooManySubscribers { max: usize },
+}
+
+impl Display for SubscriptionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SubscriptionError::TooManyTopics { max } => {
+                write!(f, "the provider already serves the maximum of {max} topics")
+            }
+            SubscriptionError::TooManySubscribers { max } => {
+                write!(f, "the topic already has the maximum of {max} subscribers")
+            }
+        }
+    }
+}
+
+/// Keeps track of the anonymous subscribers of every topic. Subscribers are only known by their
+/// sender tags, which are used for sending them the published messages via their reply SURBs.
+pub(crate) struct Subscriptions {
+    ttl: Duration,
+    max_topics: usize,
+    max_subscribers_per_topic: usize,
+
+    // for every topic, the subscribers alongside the time their subscription expires at
+    topics: HashMap<Topic, HashMap<AnonymousSenderTag, Instant>>,
+}
+
+impl Subscriptions {
+    pub(crate) fn new(ttl: Duration, max_topics: usize, max_subscribers_per_topic: usize) -> Self {
+        Subscriptions {
+            ttl,
+            max_topics,
+            max_subscribers_per_topic,
+            topics: HashMap::new(),
+        }
+    }
+
+    /// Registers the subscription or, if it already exists, extends its expiry.
+    /// New subscriptions are rejected once the topic, or the provider, is at its limit.
+    pub(crate) fn subscribe(
+        &mut self,
+        topic: Topic,
+        subscriber: AnonymousSenderTag,
+        now: Instant,
+    ) -> Result<(), SubscriptionError> {
+        if !self.topics.contains_key(&topic) && self.topics.len() >= self.max_topics {
+            return Err(SubscriptionError::TooManyTopics {
+                max: self.max_topics,
+            });
+        }
+
+        let subscribers = self.topics.entry(topic).or_default();
+        if !subscribers.contains_key(&subscriber)
+            && subscribers.len() >= self.max_subscribers_per_topic
+        {
+            // make room by dropping the subscriptions that already expired, if there are any
+            subscribers.retain(|_, expiry| *expiry > now);
+            if subscribers.len() >= self.max_subscribers_per_topic {
+                return Err(SubscriptionError::TooManySubscribers {
+                    max: self.max_subscribers_per_topic,
+                });
+            }
+        }
+
+        subscribers.insert(subscriber, now + self.ttl);
+        Ok(())
+    }
+
+    /// Removes the subscription. Returns whether it existed in the first place.
+    pub(crate) fn unsubscribe(&mut self, topic: &Topic, subscriber: &AnonymousSenderTag) -> bool {
+        let Some(subscribers) = self.topics.get_mut(topic) else {
+            return false;
+        };
+
+        let removed = subscribers.remove(subscriber).is_some();
+        if subscribers.is_empty() {
+            self.topics.remove(topic);
+        }
+        removed
+    }
+
+    /// Returns the current subscribers of the topic.
+    pub(crate) fn subscribers(&self, topic: &Topic, now: Instant) -> Vec<AnonymousSenderTag> {
+        self.topics
+            .get(topic)
+            .map(|subscribers| {
+                subscribers
+                    .iter()
+                    .filter(|(_, expiry)| **expiry > now)
+                    .map(|(subscriber, _)| *subscriber)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Removes all subscriptions that haven't been renewed in time. Returns the number of
+    /// removed subscriptions.
+    pub(crate) fn remove_expired(&mut self, now: Instant) -> usize {
+        let mut removed = 0;
+        self.topics.retain(|_, subscribers| {
+            let before = subscribers.len();
+            subscribers.retain(|_, expiry| *expiry > now);
+            removed += before - subscribers.len();
+            !subscribers.is_empty()
+        });
+        removed
+    }
+
+    pub(crate) fn topics(&self) -> usize {
+        self.topics.len()
+    }
+
+    pub(crate) fn total_subscriptions(&self) -> usize {
+        self.topics.values().map(HashMap::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn unlimited() -> Subscriptions {
+        Subscriptions::new(TTL, usize::MAX, usize::MAX)
+    }
+
+    fn topic(name: &str) -> Topic {
+        name.parse().unwrap()
+    }
+
+    fn subscriber() -> AnonymousSenderTag {
+        AnonymousSenderTag::new_random(&mut rand::rngs::OsRng)
+    }
+
+    #[test]
+    fn subscribers_are_tracked_per_topic() {
+        let now = Instant::now();
+        let mut subscriptions = unlimited();
+        let (alice, bob) = (subscriber(), subscriber());
+
+        subscriptions.subscribe(topic("news"), alice, now).unwrap();
+        subscriptions.subscribe(topic("news"), bob, now).unwrap();
+        subscriptions.subscribe(topic("weather"), bob, now).unwrap();
+
+        let mut news = subscriptions.subscribers(&topic("news"), now);
+        news.sort_by_key(|tag| tag.to_bytes());
+        let mut expected = vec![alice, bob];
+        expected.sort_by_key(|tag| tag.to_bytes());
+        assert_eq!(news, expected);
+
+        assert_eq!(subscriptions.subscribers(&topic("weather"), now), vec![bob]);
+        assert!(subscriptions.subscribers(&topic("sports"), now).is_empty());
+        assert_eq!(subscriptions.topics(), 2);
+        assert_eq!(subscriptions.total_subscriptions(), 3);
+    }
+
+    #[test]
+    fn unsubscribing_removes_empty_topics() {
+        let now = Instant::now();
+        let mut subscriptions = unlimited();
+        let alice = subscriber();
+
+        subscriptions.subscribe(topic("news"), alice, now).unwrap();
+        assert!(!subscriptions.unsubscribe(&topic("news"), &subscriber()));
+        assert!(!subscriptions.unsubscribe(&topic("weather"), &alice));
+        assert!(subscriptions.unsubscribe(&topic("news"), &alice));
+
+        assert!(subscriptions.subscribers(&topic("news"), now).is_empty());
+        assert_eq!(subscriptions.topics(), 0);
+    }
+
+    #[test]
+    fn subscriptions_expire_unless_renewed() {
+        let now = Instant::now();
+        let mut subscriptions = unlimited();
+        let (alice, bob) = (subscriber(), subscriber());
+
+        subscriptions.subscribe(topic("news"), alice, now).unwrap();
+        subscriptions.subscribe(topic("news"), bob, now).unwrap();
+
+        // bob renews the subscription halfway through
+        subscriptions
+            .subscribe(topic("news"), bob, now + TTL / 2)
+            .unwrap();
+
+        let later = now + TTL;
+        assert_eq!(subscriptions.subscribers(&topic("news"), later), vec![bob]);
+        assert_eq!(subscriptions.remove_expired(later), 1);
+        assert_eq!(subscriptions.total_subscriptions(), 1);
+
+        assert_eq!(subscriptions.remove_expired(later + TTL), 1);
+        assert_eq!(subscriptions.topics(), 0);
+    }
+
+    #[test]
+    fn topics_are_limited() {
+        let now = Instant::now();
+        let mut subscriptions = Subscriptions::new(TTL, 2, usize::MAX);
+        let alice = subscriber();
+
+        subscriptions.subscribe(topic("news"), alice, now).unwrap();
+        subscriptions
+            .subscribe(topic("weather"), alice, now)
+            .unwrap();
+        assert_eq!(
+            subscriptions.subscribe(topic("sports"), alice, now),
+            Err(SubscriptionError::TooManyTopics { max: 2 })
+        );
+
+        // existing topics still accept subscribers
+        subscriptions
+            .subscribe(topic("news"), subscriber(), now)
+            .unwrap();
+
+        // and new ones are accepted again once some topic is gone
+        assert!(subscriptions.unsubscribe(&topic("weather"), &alice));
+        subscriptions
+            .subscribe(topic("sports"), alice, now)
+            .unwrap();
+    }
+
+    #[test]
+    fn subscribers_per_topic_are_limited() {
+        let now = Instant::now();
+        let mut subscriptions = Subscriptions::new(TTL, usize::MAX, 2);
+        let (alice, bob) = (subscriber(), subscriber());
+
+        subscriptions.subscribe(topic("news"), alice, now).unwrap();
+        subscriptions.subscribe(topic("news"), bob, now).unwrap();
+        assert_eq!(
+            subscriptions.subscribe(topic("news"), subscriber(), now),
+            Err(SubscriptionError::TooManySubscribers { max: 2 })
+        );
+
+        // renewals are not affected by the limit
+        subscriptions
+            .subscribe(topic("news"), bob, now + TTL / 2)
+            .unwrap();
+
+        // and the expired subscriptions make room for the new ones
+        subscriptions
+            .subscribe(topic("news"), subscriber(), now + TTL)
+            .unwrap();
+        assert_eq!(subscriptions.total_subscriptions(), 2);
+    }
+}
@@ -16,6 +16,9 @@ pub(crate) async fn execute(
         nym_cli_commands::validator::mixnet::operators::gateway::MixnetOperatorsGatewayCommands::Unbound(_args) => {
             nym_cli_commands::validator::mixnet::operators::gateway::unbond_gateway::unbond_gateway(create_signing_client(global_args, network_details)?).await
         },
+        nym_cli_commands::validator::mixnet::operators::gateway::MixnetOperatorsGatewayCommands::AnnounceSphinxKey(args) => {
+            nym_cli_commands::validator::mixnet::operators::gateway::announce_sphinx_key::announce_sphinx_key(args, create_signing_client(global_args, network_details)?).await
+        },
         _ => unreachable!(),
     }
     Ok(())
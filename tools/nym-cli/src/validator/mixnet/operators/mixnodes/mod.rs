@@ -29,6 +29,9 @@ pub(crate) async fn execute(
         nym_cli_commands::validator::mixnet::operators::mixnode::MixnetOperatorsMixnodeCommands::Unbound(args) => {
             nym_cli_commands::validator::mixnet::operators::mixnode::unbond_mixnode::unbond_mixnode(args, create_signing_client(global_args, network_details)?).await
         }
+        nym_cli_commands::validator::mixnet::operators::mixnode::MixnetOperatorsMixnodeCommands::AnnounceSphinxKey(args) => {
+            nym_cli_commands::validator::mixnet::operators::mixnode::announce_sphinx_key::announce_sphinx_key(args, create_signing_client(global_args, network_details)?).await
+        }
         _ => unreachable!(),
     }
     Ok(())